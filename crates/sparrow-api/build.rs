@@ -46,10 +46,34 @@ fn main() {
             "#[serde(flatten)]",
         )
         .field_attribute("kaskada.v1alpha.FeatureSet.formulas", "#[serde(default)]")
+        .field_attribute(
+            "kaskada.v1alpha.FeatureSet.outputs",
+            "#[serde(default, skip_serializing_if = \"Vec::is_empty\")]",
+        )
         .field_attribute(
             "kaskada.v1alpha.Formula.source_location",
             "#[serde(default)]",
         )
+        .field_attribute(
+            "kaskada.v1alpha.TableConfig.additional_group_column_names",
+            "#[serde(default)]",
+        )
+        .field_attribute(
+            "kaskada.v1alpha.TableConfig.change_data_capture",
+            "#[serde(default, skip_serializing_if = \"Option::is_none\")]",
+        )
+        .field_attribute(
+            "kaskada.v1alpha.OperationPlan.ScanOperation.filter",
+            "#[serde(default, skip_serializing_if = \"Option::is_none\")]",
+        )
+        .field_attribute(
+            "kaskada.v1alpha.ComputePlan.max_lookback_ns",
+            "#[serde(default, skip_serializing_if = \"Option::is_none\")]",
+        )
+        .field_attribute(
+            "kaskada.v1alpha.ComputePlan.outputs",
+            "#[serde(default, skip_serializing_if = \"Vec::is_empty\")]",
+        )
         // Add some annotations to allow the following to work with clap.
         .type_attribute(
             "kaskada.v1alpha.ExecuteRequest.Limits",
@@ -59,6 +83,14 @@ fn main() {
             "kaskada.v1alpha.ExecuteRequest.Limits.preview_rows",
             "#[arg(long, default_value_t = 0)]",
         )
+        .field_attribute(
+            "kaskada.v1alpha.ExecuteRequest.Limits.partitions",
+            "#[arg(long, default_value_t = 0)]",
+        )
+        .field_attribute(
+            "kaskada.v1alpha.ExecuteRequest.Limits.max_memory_bytes",
+            "#[arg(long, default_value_t = 0)]",
+        )
         .type_attribute(
             "kaskada.v1alpha.LateBoundValue",
             "#[derive(clap::Subcommand, enum_map::Enum)]",
@@ -15,6 +15,9 @@ mod slice_traits;
 /// Implementations and traits for parts of the plan
 mod plan_impl;
 
+/// Human-readable explanation of a [ComputePlan]
+mod explain_impl;
+
 /// Traits for [PreparedFile]
 mod prepared_file_impl;
 
@@ -36,16 +39,19 @@ impl ExecuteResponse {
     //
     /// Returns `None` if not an `ObjectStoreOutput`.
     pub fn output_paths(&self) -> Option<Vec<String>> {
-        match &self.destination {
-            Some(output) => match &output.destination {
-                Some(destination::Destination::ObjectStore(store)) => store
-                    .output_paths
-                    .as_ref()
-                    .map(|output_paths| output_paths.paths.clone()),
-                Some(_) | None => None,
-            },
-            None => None,
-        }
+        self.destination
+            .as_ref()
+            .and_then(|destination| destination.output_paths())
+    }
+
+    /// Returns an owned vec of the output paths for the named output.
+    ///
+    /// Returns `None` if the output has not been reported or is not an
+    /// `ObjectStoreOutput`.
+    pub fn named_output_paths(&self, name: &str) -> Option<Vec<String>> {
+        self.output_destinations
+            .get(name)
+            .and_then(|destination| destination.output_paths())
     }
 
     /// Returns a mutable reference to the output paths.
@@ -66,6 +72,21 @@ impl ExecuteResponse {
     }
 }
 
+impl Destination {
+    /// Returns an owned vec of the output paths for this destination.
+    ///
+    /// Returns `None` if not an `ObjectStoreOutput`.
+    pub fn output_paths(&self) -> Option<Vec<String>> {
+        match &self.destination {
+            Some(destination::Destination::ObjectStore(store)) => store
+                .output_paths
+                .as_ref()
+                .map(|output_paths| output_paths.paths.clone()),
+            Some(_) | None => None,
+        }
+    }
+}
+
 impl FeatureSet {
     pub fn new(query: &str, formulas: Vec<(&str, &str)>) -> Self {
         Self {
@@ -79,6 +100,7 @@ impl FeatureSet {
                 })
                 .collect(),
             query: query.to_owned(),
+            outputs: vec![],
         }
     }
 }
@@ -100,10 +122,12 @@ impl TableConfig {
             time_column_name: time_column_name.to_owned(),
             subsort_column_name: subsort_column_name.map(|s| s.to_owned()),
             group_column_name: group_column_name.to_owned(),
+            additional_group_column_names: vec![],
             grouping: grouping.to_owned(),
             source: Some(Source {
                 source: Some(source::Source::Kaskada(KaskadaSource {})),
             }),
+            change_data_capture: None,
         }
     }
 
@@ -133,6 +157,54 @@ impl TableConfig {
             &self.group_column_name,
             &self.grouping,
         )
+        .with_additional_group_columns(&self.additional_group_column_names)
+        .with_change_data_capture(self.operation_column_name())
+    }
+
+    /// Adds additional grouping columns, creating a composite entity key.
+    pub fn with_additional_group_columns(mut self, column_names: &[impl AsRef<str>]) -> Self {
+        self.additional_group_column_names = column_names
+            .iter()
+            .map(|name| name.as_ref().to_owned())
+            .collect();
+        self
+    }
+
+    /// Returns the names of the columns making up the entity key.
+    ///
+    /// This contains the `group_column_name` followed by any additional
+    /// grouping columns.
+    pub fn group_column_names(&self) -> impl Iterator<Item = &str> + '_ {
+        std::iter::once(self.group_column_name.as_str()).chain(
+            self.additional_group_column_names
+                .iter()
+                .map(String::as_str),
+        )
+    }
+
+    /// Returns true if the entity key consists of multiple columns.
+    pub fn has_composite_key(&self) -> bool {
+        !self.additional_group_column_names.is_empty()
+    }
+
+    /// Configures the table as a change-data-capture table, with the operation
+    /// of each row in the given column.
+    ///
+    /// If `operation_column_name` is `None`, the table is append-only.
+    pub fn with_change_data_capture(mut self, operation_column_name: Option<&str>) -> Self {
+        self.change_data_capture =
+            operation_column_name.map(|operation_column_name| ChangeDataCapture {
+                operation_column_name: operation_column_name.to_owned(),
+            });
+        self
+    }
+
+    /// Returns the name of the operation column, if this is a change-data-capture
+    /// table.
+    pub fn operation_column_name(&self) -> Option<&str> {
+        self.change_data_capture
+            .as_ref()
+            .map(|cdc| cdc.operation_column_name.as_str())
     }
 }
 
@@ -199,6 +271,7 @@ impl std::fmt::Display for TickBehavior {
             TickBehavior::Monthly => write!(f, "monthly"),
             TickBehavior::Yearly => write!(f, "yearly"),
             TickBehavior::Finished => write!(f, "final"),
+            TickBehavior::Examples => write!(f, "examples"),
             TickBehavior::Unspecified => panic!("Unspecified tick behavior"),
         }
     }
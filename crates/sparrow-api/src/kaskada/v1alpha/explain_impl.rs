@@ -0,0 +1,410 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use itertools::Itertools;
+
+use super::operation_plan::tick_operation::TickBehavior;
+use super::operation_plan::Operator;
+use super::plan_impl::Pretty;
+use super::{
+    slice_plan, ComputePlan, ComputeTable, ExpressionPlan, OperationPlan, OperationProgress,
+    SlicePlan,
+};
+
+impl ComputePlan {
+    /// Return a human-readable explanation of the plan.
+    ///
+    /// The plan is rendered as a tree rooted at the operation(s) producing the
+    /// output. Each operation lists its domain and the expressions it
+    /// computes. Scans also list the projected columns, the slice and the
+    /// number of rows estimated from the prepared files in `tables`.
+    ///
+    /// If `progress` is non-empty (as reported by executing with
+    /// `operation_progress`) each operation is annotated with the actual rows
+    /// and time.
+    pub fn explain(&self, tables: &[ComputeTable], progress: &[OperationProgress]) -> String {
+        let mut explainer = Explainer {
+            plan: self,
+            tables,
+            progress,
+            explained: HashSet::new(),
+            output: String::new(),
+        };
+
+        // Roots are the operations which are not the input to any other operation.
+        let inputs: HashSet<u32> = self
+            .operations
+            .iter()
+            .filter_map(|operation| operation.operator.as_ref())
+            .flat_map(|operator| operator.input_ops_iter())
+            .collect();
+        for index in 0..self.operations.len() {
+            if !inputs.contains(&(index as u32)) {
+                explainer.explain_operation(index, 0);
+            }
+        }
+
+        explainer.output
+    }
+}
+
+struct Explainer<'a> {
+    plan: &'a ComputePlan,
+    tables: &'a [ComputeTable],
+    progress: &'a [OperationProgress],
+    /// Operations which have already been explained.
+    ///
+    /// Operations with multiple consumers are only explained once, and
+    /// referenced in later occurrences.
+    explained: HashSet<usize>,
+    output: String,
+}
+
+impl<'a> Explainer<'a> {
+    fn line(&mut self, depth: usize, line: impl std::fmt::Display) {
+        writeln!(self.output, "{:indent$}{line}", "", indent = depth * 2).expect("write to string");
+    }
+
+    fn explain_operation(&mut self, index: usize, depth: usize) {
+        // Copy the references so they aren't borrowed from `self`.
+        let (plan, progress) = (self.plan, self.progress);
+        let Some(operation) = plan.operations.get(index) else {
+            self.line(depth, format_args!("Operation {index}: missing"));
+            return;
+        };
+        if !self.explained.insert(index) {
+            self.line(depth, format_args!("Operation {index} (see above)"));
+            return;
+        }
+
+        self.line(
+            depth,
+            format_args!(
+                "Operation {index}: {}",
+                operation.operator.as_ref().pretty_fmt()
+            ),
+        );
+        let Some(operator) = &operation.operator else {
+            return;
+        };
+
+        self.line(depth + 2, format_args!("domain: {}", Domain(operator)));
+        if let Operator::Scan(scan) = operator {
+            if let Some(schema) = &scan.schema {
+                self.line(
+                    depth + 2,
+                    format_args!(
+                        "projection: {}",
+                        schema.fields.iter().map(|field| &field.name).format(", ")
+                    ),
+                );
+            }
+            if let Some(slice_plan) = &scan.slice_plan {
+                self.line(depth + 2, format_args!("slice: {}", SliceName(slice_plan)));
+                self.explain_estimate(slice_plan, depth + 2);
+            }
+            if let Some(filter) = &scan.filter {
+                self.line(depth + 2, format_args!("filter: {}", filter.pretty_fmt()));
+            }
+        }
+
+        self.explain_expressions(operation, depth + 2);
+
+        if let Some(progress) = progress
+            .iter()
+            .find(|progress| progress.operation_index as usize == index)
+        {
+            self.line(
+                depth + 2,
+                format_args!(
+                    "actual: {} rows in ({} batches), {} rows out ({} batches), busy {:.3}ms, \
+                     buffered {} rows ({} bytes)",
+                    progress.input_rows,
+                    progress.input_batches,
+                    progress.output_rows,
+                    progress.output_batches,
+                    progress.busy_nanos as f64 / 1_000_000.0,
+                    progress.buffered_rows,
+                    progress.buffered_bytes
+                ),
+            );
+        }
+
+        for input in operator.input_ops_iter() {
+            self.explain_operation(input as usize, depth + 1);
+        }
+    }
+
+    fn explain_estimate(&mut self, slice_plan: &SlicePlan, depth: usize) {
+        let tables = self.tables;
+        let Some(table) = tables.iter().find(|table| {
+            table.config.as_ref().map(|config| &config.name) == Some(&slice_plan.table_name)
+        }) else {
+            self.line(depth, "estimated input: unknown (table not provided)");
+            return;
+        };
+
+        // Prefer the file set prepared for the slice, but fall back to any
+        // file set if the table wasn't prepared with that slice.
+        let file_set = table
+            .file_sets
+            .iter()
+            .find(|file_set| file_set.slice_plan.as_ref() == Some(slice_plan))
+            .or_else(|| table.file_sets.first());
+        let (num_files, num_rows) = file_set.map_or((0, 0), |file_set| {
+            (
+                file_set.prepared_files.len(),
+                file_set
+                    .prepared_files
+                    .iter()
+                    .map(|file| file.num_rows)
+                    .sum::<i64>(),
+            )
+        });
+        self.line(
+            depth,
+            format_args!("estimated input: {num_rows} rows in {num_files} prepared files"),
+        );
+    }
+
+    fn explain_expressions(&mut self, operation: &OperationPlan, depth: usize) {
+        if operation.expressions.is_empty() {
+            return;
+        }
+        self.line(depth, "expressions:");
+        for (index, expression) in operation.expressions.iter().enumerate() {
+            self.line(
+                depth + 1,
+                format_args!("{index}: {}", ExpressionName(expression)),
+            );
+        }
+    }
+}
+
+/// Describes the rows an operation produces.
+struct Domain<'a>(&'a Operator);
+
+impl<'a> std::fmt::Display for Domain<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Operator::Scan(scan) => write!(
+                f,
+                "rows of table '{}'",
+                scan.slice_plan
+                    .as_ref()
+                    .map(|slice| &slice.table_name)
+                    .pretty_fmt()
+            ),
+            Operator::Merge(merge) => write!(
+                f,
+                "union of the rows of operations {} and {}",
+                merge.left, merge.right
+            ),
+            Operator::Select(select) => write!(
+                f,
+                "rows of operation {} where {} is true",
+                select.input,
+                select.condition.as_ref().pretty_fmt()
+            ),
+            Operator::WithKey(with_key) => write!(
+                f,
+                "rows of operation {} re-keyed to grouping '{}'",
+                with_key.input, with_key.grouping
+            ),
+            Operator::Tick(tick) => match tick.behavior() {
+                TickBehavior::Finished => {
+                    write!(f, "a final row for each entity of operation {}", tick.input)
+                }
+                TickBehavior::Examples => write!(
+                    f,
+                    "a row for each example, merged with operation {}",
+                    tick.input
+                ),
+                behavior => write!(
+                    f,
+                    "{behavior} ticks for each entity of operation {}",
+                    tick.input
+                ),
+            },
+            Operator::LookupRequest(request) => write!(
+                f,
+                "rows of operation {} keyed by the foreign entity",
+                request.primary_operation
+            ),
+            Operator::LookupResponse(response) => write!(
+                f,
+                "rows of operation {} keyed by the requesting entity",
+                response.foreign_operation
+            ),
+            Operator::ShiftTo(shift) => write!(
+                f,
+                "rows of operation {} shifted to a later time",
+                shift.input
+            ),
+            Operator::ShiftUntil(shift) => write!(
+                f,
+                "rows of operation {} held until the condition is true",
+                shift.input
+            ),
+        }
+    }
+}
+
+struct SliceName<'a>(&'a SlicePlan);
+
+impl<'a> std::fmt::Display for SliceName<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0.slice {
+            None => write!(f, "all entities"),
+            Some(slice_plan::Slice::Percent(percent)) => {
+                write!(f, "{}% of entities", percent.percent)
+            }
+            Some(slice_plan::Slice::EntityKeys(keys)) => {
+                write!(f, "{} entity keys", keys.entity_keys.len())
+            }
+            Some(slice_plan::Slice::Sample(sample)) => {
+                write!(
+                    f,
+                    "{}% of entities sampled with seed {}",
+                    sample.percent, sample.seed
+                )
+            }
+            Some(slice_plan::Slice::Stratified(stratified)) => {
+                write!(
+                    f,
+                    "entities stratified by '{}.{}' ({} strata, {}% otherwise) with seed {}",
+                    stratified.table_name,
+                    stratified.column,
+                    stratified.strata.len(),
+                    stratified.default_percent,
+                    stratified.seed
+                )
+            }
+            Some(slice_plan::Slice::Dimension(dimension)) => {
+                write!(
+                    f,
+                    "entities where '{}.{}' is one of {} values",
+                    dimension.table_name,
+                    dimension.column,
+                    dimension.values.len()
+                )
+            }
+        }
+    }
+}
+
+struct ExpressionName<'a>(&'a ExpressionPlan);
+
+impl<'a> std::fmt::Display for ExpressionName<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.operator.as_ref().pretty_fmt())?;
+        if !self.0.arguments.is_empty() {
+            write!(f, "({})", self.0.arguments.iter().format(", "))?;
+        }
+        if self.0.output {
+            write!(f, " [output]")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::operation_plan::{MergeOperation, ScanOperation, TickOperation};
+    use super::super::{compute_table, PreparedFile, TableConfig};
+    use super::*;
+
+    fn scan(table_name: &str) -> OperationPlan {
+        OperationPlan {
+            operator: Some(Operator::Scan(ScanOperation {
+                slice_plan: Some(SlicePlan {
+                    table_name: table_name.to_owned(),
+                    slice: None,
+                }),
+                ..ScanOperation::default()
+            })),
+            ..OperationPlan::default()
+        }
+    }
+
+    #[test]
+    fn test_explain() {
+        let plan = ComputePlan {
+            operations: vec![
+                scan("Purchases"),
+                scan("Reviews"),
+                OperationPlan {
+                    operator: Some(Operator::Merge(MergeOperation { left: 0, right: 1 })),
+                    ..OperationPlan::default()
+                },
+                OperationPlan {
+                    operator: Some(Operator::Tick(TickOperation {
+                        behavior: TickBehavior::Finished as i32,
+                        input: 2,
+                    })),
+                    expressions: vec![ExpressionPlan {
+                        arguments: vec![],
+                        output: true,
+                        operator: Some(super::super::expression_plan::Operator::Instruction(
+                            "time_of".to_owned(),
+                        )),
+                        ..ExpressionPlan::default()
+                    }],
+                },
+            ],
+            ..ComputePlan::default()
+        };
+        let tables = vec![ComputeTable {
+            config: Some(TableConfig {
+                name: "Purchases".to_owned(),
+                ..TableConfig::default()
+            }),
+            metadata: None,
+            file_sets: vec![compute_table::FileSet {
+                slice_plan: None,
+                prepared_files: vec![
+                    PreparedFile {
+                        num_rows: 10,
+                        ..PreparedFile::default()
+                    },
+                    PreparedFile {
+                        num_rows: 15,
+                        ..PreparedFile::default()
+                    },
+                ],
+            }],
+        }];
+        let progress = vec![OperationProgress {
+            operation_index: 2,
+            operation_label: "merge".to_owned(),
+            input_rows: 30,
+            input_batches: 2,
+            output_rows: 25,
+            output_batches: 2,
+            busy_nanos: 1_500_000,
+            buffered_bytes: 0,
+            buffered_rows: 0,
+            watermark: 0,
+        }];
+
+        assert_eq!(
+            plan.explain(&tables, &progress),
+            "Operation 3: Tick (final)
+    domain: a final row for each entity of operation 2
+    expressions:
+      0: Instruction 'time_of' [output]
+  Operation 2: Merge (0, 1)
+      domain: union of the rows of operations 0 and 1
+      actual: 30 rows in (2 batches), 25 rows out (2 batches), busy 1.500ms, buffered 0 rows (0 bytes)
+    Operation 0: Scan 'Purchases'
+        domain: rows of table 'Purchases'
+        slice: all entities
+        estimated input: 25 rows in 2 prepared files
+    Operation 1: Scan 'Reviews'
+        domain: rows of table 'Reviews'
+        slice: all entities
+        estimated input: unknown (table not provided)
+"
+        );
+    }
+}
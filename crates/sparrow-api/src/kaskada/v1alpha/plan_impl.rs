@@ -237,7 +237,7 @@ impl<'a> std::fmt::Display for OperationName<'a> {
 }
 
 #[repr(transparent)]
-struct PrettyFmt<'a, T: Pretty + ?Sized>(&'a T);
+pub(super) struct PrettyFmt<'a, T: Pretty + ?Sized>(&'a T);
 
 impl<'a, T: Pretty> std::fmt::Display for PrettyFmt<'a, T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -245,7 +245,7 @@ impl<'a, T: Pretty> std::fmt::Display for PrettyFmt<'a, T> {
     }
 }
 
-trait Pretty {
+pub(super) trait Pretty {
     /// Pretty formatting for the given type.
     fn pretty(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result;
 
@@ -453,6 +453,17 @@ impl operation_plan::Operator {
         }
     }
 
+    /// Whether the rows produced by the operator are keyed differently than
+    /// the rows it consumes.
+    pub fn changes_key(&self) -> bool {
+        matches!(
+            self,
+            operation_plan::Operator::WithKey(_)
+                | operation_plan::Operator::LookupRequest(_)
+                | operation_plan::Operator::LookupResponse(_)
+        )
+    }
+
     pub fn input_op(&self, index: usize) -> anyhow::Result<u32> {
         match (self, index) {
             (operation_plan::Operator::Merge(merge), 0) => Ok(merge.left),
@@ -480,6 +491,7 @@ impl operation_plan::Operator {
             (operation_plan::Operator::Merge(merge), 0) => Ok(&mut merge.left),
             (operation_plan::Operator::Merge(merge), 1) => Ok(&mut merge.right),
             (operation_plan::Operator::Select(select), 0) => Ok(&mut select.input),
+            (operation_plan::Operator::WithKey(with_key), 0) => Ok(&mut with_key.input),
             (operation_plan::Operator::LookupRequest(lookup_request), 0) => {
                 Ok(&mut lookup_request.primary_operation)
             }
@@ -20,6 +20,59 @@ impl std::hash::Hash for slice_plan::Slice {
             slice_plan::Slice::EntityKeys(entity_keys) => {
                 entity_keys.entity_keys.hash(state);
             }
+            slice_plan::Slice::Sample(sample) => {
+                sample.percent.float_hash(state);
+                sample.seed.hash(state);
+            }
+            slice_plan::Slice::Stratified(stratified) => {
+                stratified.table_name.hash(state);
+                stratified.column.hash(state);
+                for stratum in &stratified.strata {
+                    stratum.value.hash(state);
+                    stratum.percent.float_hash(state);
+                }
+                stratified.default_percent.float_hash(state);
+                stratified.seed.hash(state);
+            }
+            slice_plan::Slice::Dimension(dimension) => {
+                dimension.table_name.hash(state);
+                dimension.column.hash(state);
+                dimension.values.hash(state);
+            }
+        }
+    }
+}
+
+impl slice_plan::Slice {
+    /// Return the table whose rows determine the entities in the slice, if any.
+    ///
+    /// Tables aren't prepared with such slices. Instead, every table is
+    /// prepared in full (shared with unsliced queries) and filtered to the
+    /// entities in the slice when it is read.
+    pub fn entity_table(&self) -> Option<&str> {
+        match self {
+            slice_plan::Slice::Stratified(stratified) => Some(&stratified.table_name),
+            slice_plan::Slice::Dimension(dimension) => Some(&dimension.table_name),
+            _ => None,
+        }
+    }
+
+    /// Return the slice tables are prepared with to read this slice.
+    pub fn prepared_slice(slice: &Option<Self>) -> Option<Self> {
+        slice
+            .as_ref()
+            .filter(|slice| slice.entity_table().is_none())
+            .cloned()
+    }
+
+    /// The position of the kind of slice in the ordering of slices.
+    fn kind_order(&self) -> u8 {
+        match self {
+            slice_plan::Slice::EntityKeys(_) => 0,
+            slice_plan::Slice::Percent(_) => 1,
+            slice_plan::Slice::Sample(_) => 2,
+            slice_plan::Slice::Stratified(_) => 3,
+            slice_plan::Slice::Dimension(_) => 4,
         }
     }
 }
@@ -37,19 +90,62 @@ impl Ord for slice_plan::Slice {
             (slice_plan::Slice::Percent(p1), slice_plan::Slice::Percent(p2)) => {
                 p1.percent.float_cmp(&p2.percent)
             }
-            (slice_plan::Slice::Percent(_), slice_plan::Slice::EntityKeys(_)) => {
-                std::cmp::Ordering::Greater
-            }
-            (slice_plan::Slice::EntityKeys(_), slice_plan::Slice::Percent(_)) => {
-                std::cmp::Ordering::Less
-            }
             (slice_plan::Slice::EntityKeys(e1), slice_plan::Slice::EntityKeys(e2)) => {
                 e1.entity_keys.cmp(&e2.entity_keys)
             }
+            (slice_plan::Slice::Sample(s1), slice_plan::Slice::Sample(s2)) => s1
+                .percent
+                .float_cmp(&s2.percent)
+                .then_with(|| s1.seed.cmp(&s2.seed)),
+            (slice_plan::Slice::Stratified(s1), slice_plan::Slice::Stratified(s2)) => s1
+                .table_name
+                .cmp(&s2.table_name)
+                .then_with(|| s1.column.cmp(&s2.column))
+                .then_with(|| {
+                    s1.strata
+                        .iter()
+                        .map(|stratum| (&stratum.value, FloatKey(stratum.percent)))
+                        .cmp(
+                            s2.strata
+                                .iter()
+                                .map(|stratum| (&stratum.value, FloatKey(stratum.percent))),
+                        )
+                })
+                .then_with(|| s1.default_percent.float_cmp(&s2.default_percent))
+                .then_with(|| s1.seed.cmp(&s2.seed)),
+            (slice_plan::Slice::Dimension(d1), slice_plan::Slice::Dimension(d2)) => d1
+                .table_name
+                .cmp(&d2.table_name)
+                .then_with(|| d1.column.cmp(&d2.column))
+                .then_with(|| d1.values.cmp(&d2.values)),
+            (s1, s2) => s1.kind_order().cmp(&s2.kind_order()),
         }
     }
 }
 
+/// Wrapper ordering an `f64` using decorum.
+struct FloatKey(f64);
+
+impl PartialEq for FloatKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for FloatKey {}
+
+impl PartialOrd for FloatKey {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FloatKey {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.float_cmp(&other.0)
+    }
+}
+
 impl PartialOrd for SlicePlan {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
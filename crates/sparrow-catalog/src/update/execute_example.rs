@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fs::File;
 
 use error_stack::{IntoReportCompat, ResultExt};
@@ -66,11 +67,15 @@ pub(super) async fn execute_example(
             feature_set: Some(FeatureSet {
                 formulas: vec![],
                 query,
+                outputs: vec![],
             }),
             slice_request: None,
             expression_kind: ExpressionKind::Complete as i32,
             experimental: false,
             per_entity_behavior: PerEntityBehavior::All as i32,
+            previous_plan: None,
+            explain: false,
+            lint_severities: vec![],
         },
         InternalCompileOptions::default(),
     )
@@ -105,6 +110,10 @@ pub(super) async fn execute_example(
             compute_snapshot_config: None,
             changed_since: None,
             final_result_time: None,
+            operation_progress: false,
+            start_time: None,
+            examples: None,
+            output_destinations: HashMap::new(),
         },
         s3_helper,
         None,
@@ -2,7 +2,7 @@
 
 mod ast_dfg;
 mod record_ops_to_dfg;
-mod window_args;
+pub(crate) mod window_args;
 
 #[cfg(test)]
 mod tests;
@@ -22,9 +22,9 @@ use sparrow_syntax::{
     ExprOp, FenlType, FormatDataType, LiteralValue, Located, Location, Resolved, ResolvedExpr,
 };
 
-use self::window_args::flatten_window_args;
+use self::window_args::{bind_window_args, flatten_window};
 use crate::dfg::{Dfg, Expression, Operation};
-use crate::diagnostics::DiagnosticCode;
+use crate::diagnostics::{lint_call, DiagnosticCode};
 use crate::time_domain::TimeDomain;
 use crate::types::inference::instantiate;
 use crate::{DataContext, DiagnosticBuilder, DiagnosticCollector};
@@ -64,6 +64,7 @@ pub(super) fn ast_to_dfg(
             // Skip the last argument (which will correspond to the let body).
             for (name, value) in izip!(names, arguments.values()).take(bindings) {
                 let value = ast_to_dfg(data_context, dfg, diagnostics, value)?;
+                diagnostics.annotate_type(name.location(), value.value_type());
                 dfg.bind(name.inner(), value);
             }
             let result = ast_to_dfg(
@@ -145,7 +146,10 @@ pub(super) fn ast_to_dfg(
             Ok(dfg.error_node())
         }
         ExprOp::Reference(reference) => match dfg.get_binding(reference) {
-            Ok(value) => Ok(value),
+            Ok(value) => {
+                diagnostics.annotate_type(reference.location(), value.value_type());
+                Ok(value)
+            }
             Err(nearest) => {
                 DiagnosticCode::UnboundReference
                     .builder()
@@ -219,6 +223,7 @@ pub(super) fn ast_to_dfg(
             )?;
             let is_new = base.is_new();
             let value_type = field_type.clone().into();
+            diagnostics.annotate_type(field.location(), &value_type);
             Ok(Rc::new(AstDfg::new(
                 value,
                 is_new,
@@ -298,6 +303,7 @@ pub(super) fn ast_to_dfg(
                         return Ok(dfg.error_node());
                     }
                 };
+            diagnostics.annotate_type(function_name.location(), &instantiated_result_type);
 
             // If any arguments were an error, bail out, returning the error node.
             // This may prevent us from recovering from an error in certain cases, but
@@ -308,6 +314,16 @@ pub(super) fn ast_to_dfg(
                 return Ok(dfg.error_node());
             }
 
+            lint_call(
+                function,
+                function_name,
+                expr,
+                &arguments,
+                data_context,
+                dfg,
+                diagnostics,
+            );
+
             // TODO: Drive grouping determination from the function itself.
             let grouping = match function.name() {
                 "lookup" => {
@@ -505,35 +521,15 @@ pub(super) fn ast_to_dfg(
 
             let args: Vec<_> = if function.is_aggregation() {
                 // If the function is an aggregation, we may need to flatten the window.
-                dfg.enter_env();
-                dfg.bind("$condition_input", args[0].inner().clone());
-
-                let window = &expr.args()[1];
-                let (condition, duration) = match window.op() {
-                    ExprOp::Call(window_name) => {
-                        flatten_window_args(window_name, window, dfg, data_context, diagnostics)?
-                    }
-                    ExprOp::Literal(v) if v.inner() == &LiteralValue::Null => {
-                        // Unwindowed aggregations just use nulls
-                        let null_arg = dfg.add_literal(LiteralValue::Null.to_scalar()?)?;
-                        let null_arg = Located::new(
-                            add_literal(
-                                dfg,
-                                null_arg,
-                                FenlType::Concrete(DataType::Null),
-                                window.location().clone(),
-                            )?,
-                            window.location().clone(),
-                        );
-
-                        (null_arg.clone(), null_arg)
-                    }
-                    unexpected => anyhow::bail!("expected window, found {:?}", unexpected),
-                };
-
-                dfg.exit_env();
-                // [agg_input, condition, duration]
-                vec![args[0].clone(), condition, duration]
+                let (condition, duration, gap) = flatten_window(
+                    &expr.args()[1],
+                    args[0].inner(),
+                    dfg,
+                    data_context,
+                    diagnostics,
+                )?;
+                // [agg_input, condition, duration, gap]
+                vec![args[0].clone(), condition, duration, gap]
             } else if function.name() == "when" || function.name() == "if" {
                 dfg.enter_env();
                 dfg.bind("$condition_input", args[1].inner().clone());
@@ -546,7 +542,28 @@ pub(super) fn ast_to_dfg(
                 args
             };
 
-            function.create_dfg_node(
+            // Rewrites which take a window (such as `session_id`) pass it on to
+            // the aggregations in their body. It is flattened here, where the
+            // arguments of the window are in scope, and bound for the body.
+            let window_index = function
+                .signature()
+                .arg_names()
+                .iter()
+                .position(|name| name.inner() == "window")
+                .filter(|_| !function.is_aggregation());
+            if let Some(window_index) = window_index {
+                let window_args = flatten_window(
+                    &expr.args()[window_index],
+                    args[0].inner(),
+                    dfg,
+                    data_context,
+                    diagnostics,
+                )?;
+                dfg.enter_env();
+                bind_window_args("window", window_args, dfg);
+            }
+
+            let result = function.create_dfg_node(
                 function_name.location(),
                 data_context,
                 dfg,
@@ -554,7 +571,11 @@ pub(super) fn ast_to_dfg(
                 &args,
                 instantiated_result_type,
                 grouping,
-            )
+            );
+            if window_index.is_some() {
+                dfg.exit_env();
+            }
+            result
         }
         ExprOp::Pipe(_) => Err(anyhow!("Unreachable: Pipe expression handled above")),
         ExprOp::Let(_, _) => Err(anyhow!("Unreachable: Let expression handled above")),
@@ -1,23 +1,79 @@
-use arrow::datatypes::DataType;
-use sparrow_syntax::{FenlType, LiteralValue, Located, ResolvedExpr};
+use arrow::datatypes::{DataType, IntervalUnit, TimeUnit};
+use sparrow_core::ScalarValue;
+use sparrow_syntax::{ExprOp, FenlType, LiteralValue, Located, ResolvedExpr};
 
 use crate::ast_to_dfg::add_literal;
 use crate::dfg::Dfg;
 use crate::{AstDfgRef, DataContext, DiagnosticCode, DiagnosticCollector};
 
-/// Flattens window arguments into condition and duration nodes.
+/// The flattened `(condition, duration, gap)` arguments of a window.
+pub(crate) type WindowArgs = (Located<AstDfgRef>, Located<AstDfgRef>, Located<AstDfgRef>);
+
+/// Flattens the window argument of an aggregation of `input`.
+///
+/// The window may be a call to a window function, `null` for unwindowed
+/// aggregations, or a reference to a window passed to a rewrite, which was
+/// flattened by the caller. See [bind_window_args].
+pub(crate) fn flatten_window(
+    window: &Located<Box<ResolvedExpr>>,
+    input: &AstDfgRef,
+    dfg: &mut Dfg,
+    data_context: &mut DataContext,
+    diagnostics: &mut DiagnosticCollector<'_>,
+) -> anyhow::Result<WindowArgs> {
+    match window.op() {
+        ExprOp::Call(window_name) => {
+            dfg.enter_env();
+            dfg.bind("$condition_input", input.clone());
+            let window_args =
+                flatten_window_args(window_name, window, dfg, data_context, diagnostics);
+            dfg.exit_env();
+            window_args
+        }
+        ExprOp::Literal(v) if v.inner() == &LiteralValue::Null => {
+            // Unwindowed aggregations just use nulls
+            let null_arg = null_arg(dfg, window)?;
+            Ok((null_arg.clone(), null_arg.clone(), null_arg))
+        }
+        ExprOp::Reference(name) => {
+            let part = |part: &str| -> anyhow::Result<_> {
+                let value = dfg
+                    .get_binding(&format!("${name}_{part}"))
+                    .map_err(|_| anyhow::anyhow!("expected window, found reference '{name}'"))?;
+                Ok(window.with_value(value))
+            };
+            Ok((part("condition")?, part("duration")?, part("gap")?))
+        }
+        unexpected => anyhow::bail!("expected window, found {:?}", unexpected),
+    }
+}
+
+/// Binds the flattened arguments of the window `name`.
+///
+/// Rewrites taking a window flatten it where it is called, since the
+/// arguments of the window may not be in scope within the rewrite. References
+/// to `name` within the rewrite use the bound arguments.
+pub(crate) fn bind_window_args(name: &str, window_args: WindowArgs, dfg: &mut Dfg) {
+    let (condition, duration, gap) = window_args;
+    dfg.bind(&format!("${name}_condition"), condition.inner().clone());
+    dfg.bind(&format!("${name}_duration"), duration.inner().clone());
+    dfg.bind(&format!("${name}_gap"), gap.inner().clone());
+}
+
+/// Flattens window arguments into condition, duration and gap nodes.
 ///
 /// Windows are flattened to components that are executable concepts.
 /// Ticks indicate at what times rows will be inserted into tables,
 /// and the duration configures when aggregations will reset their
-/// internal states.
-pub(crate) fn flatten_window_args(
+/// internal states. The gap is only set for session windows, and is the
+/// inactivity in nanoseconds after which a session closes.
+fn flatten_window_args(
     name: &Located<String>,
     window: &Located<Box<ResolvedExpr>>,
     dfg: &mut Dfg,
     data_context: &mut DataContext,
     diagnostics: &mut DiagnosticCollector<'_>,
-) -> anyhow::Result<(Located<AstDfgRef>, Located<AstDfgRef>)> {
+) -> anyhow::Result<WindowArgs> {
     if name.inner() == "since" {
         debug_assert!(
             window.args().len() == 1,
@@ -38,7 +94,8 @@ pub(crate) fn flatten_window_args(
         );
 
         let condition = crate::ast_to_dfg(data_context, dfg, diagnostics, &window.args()[0])?;
-        Ok((window.with_value(condition), duration))
+        let gap = null_arg(dfg, name)?;
+        Ok((window.with_value(condition), duration, gap))
     } else if name.inner() == "sliding" {
         debug_assert!(
             window.args().len() == 2,
@@ -47,10 +104,122 @@ pub(crate) fn flatten_window_args(
         );
         let duration = &window.args()[0];
         let duration_node = crate::ast_to_dfg(data_context, dfg, diagnostics, duration)?;
-        let duration = duration.with_value(duration_node);
 
-        let condition = crate::ast_to_dfg(data_context, dfg, diagnostics, &window.args()[1])?;
-        Ok((window.with_value(condition), duration))
+        let condition = &window.args()[1];
+        let has_condition = !matches!(condition.op(),
+            ExprOp::Literal(literal) if literal.inner() == &LiteralValue::Null);
+
+        match duration_node.value_type() {
+            FenlType::Concrete(DataType::Int64) => {
+                // Sliding over a count of the times the condition is true.
+                if !has_condition {
+                    return Ok(invalid_sliding_window(
+                        window,
+                        duration,
+                        "a sliding window over a count requires a condition".to_owned(),
+                        dfg,
+                        diagnostics,
+                    ));
+                }
+                let condition = crate::ast_to_dfg(data_context, dfg, diagnostics, condition)?;
+                let gap = null_arg(dfg, window)?;
+                Ok((
+                    window.with_value(condition),
+                    duration.with_value(duration_node),
+                    gap,
+                ))
+            }
+            FenlType::Concrete(data_type) if is_fixed_timedelta(data_type) => {
+                // Sliding over a span of time. Each input is evicted once it is
+                // older than the duration, so no condition is used.
+                if has_condition {
+                    return Ok(invalid_sliding_window(
+                        window,
+                        condition,
+                        "a sliding window over a duration of time does not take a condition"
+                            .to_owned(),
+                        dfg,
+                        diagnostics,
+                    ));
+                }
+                let nanos = dfg
+                    .literal(duration_node.value())
+                    .and_then(timedelta_nanos)
+                    .filter(|nanos| *nanos > 0);
+                let Some(nanos) = nanos else {
+                    return Ok(invalid_sliding_window(
+                        window,
+                        duration,
+                        "the duration of a sliding window must be positive".to_owned(),
+                        dfg,
+                        diagnostics,
+                    ));
+                };
+
+                let duration_id = dfg.add_literal(ScalarValue::Int64(Some(nanos)))?;
+                let duration = duration.with_value(add_literal(
+                    dfg,
+                    duration_id,
+                    FenlType::Concrete(DataType::Int64),
+                    duration.location().clone(),
+                )?);
+
+                let condition = null_arg(dfg, condition)?;
+                let gap = null_arg(dfg, window)?;
+                Ok((condition, duration, gap))
+            }
+            FenlType::Error => Ok(error_args(window, dfg)),
+            other => Ok(invalid_sliding_window(
+                window,
+                duration,
+                format!(
+                    "expected the duration of a sliding window to be an 'i64' count or a \
+                     fixed-length 'timedelta', but was '{other}'"
+                ),
+                dfg,
+                diagnostics,
+            )),
+        }
+    } else if name.inner() == "session" {
+        debug_assert!(
+            window.args().len() == 1,
+            "expected only one arg for session window, saw {}",
+            window.args().len()
+        );
+        let gap = &window.args()[0];
+        let gap_node = crate::ast_to_dfg(data_context, dfg, diagnostics, gap)?;
+
+        let nanos = match gap_node.value_type() {
+            FenlType::Concrete(data_type) if is_fixed_timedelta(data_type) => dfg
+                .literal(gap_node.value())
+                .and_then(timedelta_nanos)
+                .filter(|nanos| *nanos > 0),
+            FenlType::Error => return Ok(error_args(window, dfg)),
+            _ => None,
+        };
+        let Some(nanos) = nanos else {
+            DiagnosticCode::InvalidArgumentType
+                .builder()
+                .with_label(gap.location().primary_label().with_message(format!(
+                    "expected the gap of a session window to be a positive, fixed-length \
+                     'timedelta', but was '{}'",
+                    gap_node.value_type()
+                )))
+                .emit(diagnostics);
+            return Ok(error_args(window, dfg));
+        };
+
+        let gap_id = dfg.add_literal(ScalarValue::Int64(Some(nanos)))?;
+        let gap = gap.with_value(add_literal(
+            dfg,
+            gap_id,
+            FenlType::Concrete(DataType::Int64),
+            gap.location().clone(),
+        )?);
+
+        let condition = null_arg(dfg, window)?;
+        let duration = null_arg(dfg, window)?;
+        Ok((condition, duration, gap))
     } else {
         DiagnosticCode::InvalidArgumentType
             .builder()
@@ -59,11 +228,83 @@ pub(crate) fn flatten_window_args(
                     .primary_label()
                     .with_message(format!("Invalid window function: '{}'", name.inner())),
             )
-            .with_note("Supported windows: 'since', 'sliding'".to_string())
+            .with_note("Supported windows: 'since', 'sliding', 'session'".to_string())
             .emit(diagnostics);
-        Ok((
-            window.with_value(dfg.error_node()),
-            window.with_value(dfg.error_node()),
-        ))
+        Ok(error_args(window, dfg))
+    }
+}
+
+/// Return a null literal located at the given argument.
+///
+/// Used for the window arguments that don't apply to a window.
+fn null_arg<T>(dfg: &mut Dfg, argument: &Located<T>) -> anyhow::Result<Located<AstDfgRef>> {
+    let null_id = dfg.add_literal(LiteralValue::Null.to_scalar()?)?;
+    Ok(argument.with_value(add_literal(
+        dfg,
+        null_id,
+        FenlType::Concrete(DataType::Null),
+        argument.location().clone(),
+    )?))
+}
+
+/// Return error nodes for each of the flattened window arguments.
+fn error_args(window: &Located<Box<ResolvedExpr>>, dfg: &mut Dfg) -> WindowArgs {
+    (
+        window.with_value(dfg.error_node()),
+        window.with_value(dfg.error_node()),
+        window.with_value(dfg.error_node()),
+    )
+}
+
+/// Report an invalid sliding window and return error nodes for the flattened
+/// arguments.
+fn invalid_sliding_window<T>(
+    window: &Located<Box<ResolvedExpr>>,
+    argument: &Located<T>,
+    message: String,
+    dfg: &mut Dfg,
+    diagnostics: &mut DiagnosticCollector<'_>,
+) -> WindowArgs {
+    DiagnosticCode::InvalidArgumentType
+        .builder()
+        .with_label(argument.location().primary_label().with_message(message))
+        .with_note(
+            "Use `sliding(<count>, <condition>)` to slide over the times the condition is true, \
+             or `sliding(<timedelta>)` to slide over a duration of time"
+                .to_owned(),
+        )
+        .emit(diagnostics);
+    error_args(window, dfg)
+}
+
+/// Return true if values of the type are a fixed-length span of time.
+///
+/// Months are excluded, since their length depends on the time they are
+/// added to.
+fn is_fixed_timedelta(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Duration(_) | DataType::Interval(IntervalUnit::DayTime)
+    )
+}
+
+/// Return the number of nanoseconds in a literal `timedelta`.
+pub(crate) fn timedelta_nanos(literal: &ScalarValue) -> Option<i64> {
+    match literal {
+        ScalarValue::Duration(Some(value), unit) => {
+            let nanos_per_unit = match unit {
+                TimeUnit::Second => 1_000_000_000,
+                TimeUnit::Millisecond => 1_000_000,
+                TimeUnit::Microsecond => 1_000,
+                TimeUnit::Nanosecond => 1,
+            };
+            value.checked_mul(nanos_per_unit)
+        }
+        ScalarValue::IntervalDayTime(Some((days, millis))) => {
+            let days = (*days as i64).checked_mul(86_400_000_000_000)?;
+            let millis = (*millis as i64).checked_mul(1_000_000)?;
+            days.checked_add(millis)
+        }
+        _ => None,
     }
 }
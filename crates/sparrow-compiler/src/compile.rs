@@ -1,14 +1,20 @@
 use std::io::Write;
 
 use error_stack::{IntoReport, IntoReportCompat, ResultExt};
+use hashbrown::HashSet;
 use prost::Message;
 use sparrow_api::kaskada::v1alpha::compile_request::ExpressionKind;
+use sparrow_api::kaskada::v1alpha::feature_set::Output;
 use sparrow_api::kaskada::v1alpha::FenlDiagnostics;
-use sparrow_api::kaskada::v1alpha::{CompileRequest, CompileResponse, FeatureSet, PlanHash};
+use sparrow_api::kaskada::v1alpha::{
+    CompileRequest, CompileResponse, ComputeTable, FeatureSet, PerEntityBehavior, PlanHash,
+};
 use tracing::{error, info, info_span};
 
+use crate::plan::OutputExpr;
 use crate::{
     CompilerOptions, DataContext, Error, FrontendAnalysis, FrontendOutput, InternalCompileOptions,
+    LintOptions,
 };
 
 /// Compile the query in the `request` and return the `CompileResponse` proto.
@@ -22,6 +28,8 @@ pub async fn compile_proto(
 ) -> error_stack::Result<CompileResponse, Error> {
     let expression_kind = request.expression_kind();
     let per_entity_behavior = request.per_entity_behavior();
+    let previous_plan = request.previous_plan;
+    let explain = request.explain;
 
     let feature_set = request
         .feature_set
@@ -32,18 +40,66 @@ pub async fn compile_proto(
         experimental: request.experimental,
         per_entity_behavior,
         internal,
+        lints: LintOptions::try_from_proto(&request.lint_severities)?,
+    };
+
+    // The tables are only needed after compilation to explain the plan.
+    let tables = if explain {
+        request.tables.clone()
+    } else {
+        vec![]
     };
 
     let mut data_context = DataContext::try_from_tables(request.tables)
         .into_report()
         .change_context(Error::CompileError)?;
 
-    compile(
+    let mut response = compile(
         &compiler_options,
         &mut data_context,
         &feature_set,
         expression_kind,
+    )?;
+
+    // If the state of a previous plan is available, report which parts of it
+    // may be reused by the new plan.
+    if let (Some(previous_plan), Some(plan)) = (&previous_plan, &response.plan) {
+        let state_reuse = crate::plan_state_reuse(previous_plan, plan)
+            .into_report()
+            .change_context(Error::Internal("failed to determine state reuse"))?;
+        response.state_reuse = Some(state_reuse);
+    }
+
+    if explain {
+        if let Some(plan) = &response.plan {
+            response.explanation = plan.explain(&tables, &[]);
+        }
+    }
+
+    Ok(response)
+}
+
+/// Analyze the query in the `feature_set` without producing a plan.
+///
+/// This performs parsing and type-checking, reporting the diagnostics and the
+/// inferred types of each part of the query. It is intended for editor
+/// tooling, which re-analyzes the query on every change.
+pub fn analyze(
+    tables: Vec<ComputeTable>,
+    feature_set: &FeatureSet,
+) -> error_stack::Result<FrontendAnalysis, Error> {
+    let mut data_context = DataContext::try_from_tables(tables)
+        .into_report()
+        .change_context(Error::CompileError)?;
+    let FrontendOutput { analysis, .. } = FrontendOutput::try_compile(
+        &mut data_context,
+        feature_set,
+        &CompilerOptions::default(),
+        ExpressionKind::Formula,
     )
+    .into_report()
+    .change_context(Error::CompileError)?;
+    Ok(analysis)
 }
 
 /// Compile a feature set and return the corresponding compile response.
@@ -60,22 +116,47 @@ fn compile(
     let span = info_span!("Compiling query");
     let _enter = span.enter();
 
+    let mut output_names = HashSet::with_capacity(feature_set.outputs.len());
+    for output in &feature_set.outputs {
+        error_stack::ensure!(
+            !output.name.is_empty(),
+            Error::InvalidOutput("output name must not be empty".to_owned())
+        );
+        error_stack::ensure!(
+            output_names.insert(&output.name),
+            Error::InvalidOutput(format!("duplicate output name '{}'", output.name))
+        );
+    }
+
     // 1. Do the frontend analysis / compilation.
-    let FrontendOutput { analysis, expr } =
+    let FrontendOutput { mut analysis, expr } =
         FrontendOutput::try_compile(data_context, feature_set, options, expression_kind)
             .into_report()
             .change_context(Error::CompileError)?;
 
+    // Each named output is compiled separately. The analysis of each is
+    // combined with the analysis of the query.
+    let mut outputs = Vec::with_capacity(feature_set.outputs.len());
+    for output in &feature_set.outputs {
+        let FrontendOutput {
+            analysis: output_analysis,
+            expr,
+        } = compile_output(options, data_context, feature_set, output, expression_kind)?;
+        let grouping = output_analysis.primary_grouping;
+        merge_output_analysis(&mut analysis, output_analysis);
+        outputs.push((output, grouping, expr));
+    }
+
     // 2. Produce the plan (assuming there were no diagnostic errors).
-    let (plan, plan_hash) = if analysis.has_errors() {
+    let (plan, plan_hash, plan_statistics) = if analysis.has_errors() {
         info!("Not producing plan due to Fenl errors");
-        (None, None)
+        (None, None, None)
     } else if !matches!(expression_kind, ExpressionKind::Complete) {
         info!(
             "Not producing plan for incomplete expression kind {:?}",
             expression_kind
         );
-        (None, None)
+        (None, None, None)
     } else {
         let primary_grouping = analysis
             .primary_grouping
@@ -87,15 +168,36 @@ fn compile(
         let primary_grouping = primary_grouping_info.name().to_owned();
         let primary_grouping_key_type = primary_grouping_info.key_type();
 
-        let plan = crate::plan::extract_plan_proto(
+        let outputs = outputs
+            .into_iter()
+            .map(
+                |(output, grouping, expr)| -> error_stack::Result<_, Error> {
+                    let grouping = grouping.ok_or(Error::Internal("missing output grouping"))?;
+                    let grouping_info = data_context
+                        .group_info(grouping)
+                        .ok_or(Error::Internal("missing output group_info"))?;
+                    Ok(OutputExpr {
+                        name: output.name.clone(),
+                        expr,
+                        per_entity_behavior: output_behavior(options, output),
+                        grouping: grouping_info.name().to_owned(),
+                        grouping_key_type: grouping_info.key_type().clone(),
+                    })
+                },
+            )
+            .collect::<error_stack::Result<Vec<_>, Error>>()?;
+
+        let (mut plan, plan_statistics) = crate::plan::extract_plan_proto(
             data_context,
             expr,
             options.per_entity_behavior,
             primary_grouping,
             primary_grouping_key_type,
+            outputs,
         )
         .into_report()
         .change_context(Error::ExtractPlanProto)?;
+        plan.max_lookback_ns = analysis.max_lookback;
 
         let plan_hash = hash_compute_plan_proto(&plan);
 
@@ -108,7 +210,7 @@ fn compile(
             }
         }
 
-        (Some(plan), Some(plan_hash))
+        (Some(plan), Some(plan_hash), Some(plan_statistics))
     };
 
     // 3. Create the CompileResponse proto.
@@ -143,9 +245,75 @@ fn compile(
         table_slices: slice_plans,
         incremental_enabled,
         plan_hash,
+        state_reuse: None,
+        explanation: String::new(),
+        plan_statistics,
     })
 }
 
+/// Compile a named output of the feature set.
+///
+/// The output is compiled as the query of a feature set with the same
+/// formulas, using the per-entity behavior of the output.
+fn compile_output(
+    options: &CompilerOptions,
+    data_context: &mut DataContext,
+    feature_set: &FeatureSet,
+    output: &Output,
+    expression_kind: ExpressionKind,
+) -> error_stack::Result<FrontendOutput, Error> {
+    let options = CompilerOptions {
+        per_entity_behavior: output_behavior(options, output),
+        // The DFGs are only written for the query.
+        internal: InternalCompileOptions {
+            store_initial_dfg: None,
+            store_final_dfg: None,
+            ..options.internal.clone()
+        },
+        ..options.clone()
+    };
+    let output_feature_set = FeatureSet {
+        formulas: feature_set.formulas.clone(),
+        query: output.query.clone(),
+        outputs: vec![],
+    };
+    FrontendOutput::try_compile(data_context, &output_feature_set, &options, expression_kind)
+        .into_report()
+        .change_context(Error::CompileError)
+}
+
+/// Return the per-entity behavior of the output.
+///
+/// Outputs without a behavior use the behavior of the request.
+fn output_behavior(options: &CompilerOptions, output: &Output) -> PerEntityBehavior {
+    match output.per_entity_behavior() {
+        PerEntityBehavior::Unspecified => options.per_entity_behavior,
+        behavior => behavior,
+    }
+}
+
+/// Combine the analysis of a named output into the analysis of the query.
+///
+/// The result type and type annotations remain those of the query.
+fn merge_output_analysis(analysis: &mut FrontendAnalysis, output: FrontendAnalysis) {
+    analysis.num_errors += output.num_errors;
+    analysis.diagnostics.extend(output.diagnostics);
+    for slice_plan in output.slice_plans {
+        if !analysis.slice_plans.contains(&slice_plan) {
+            analysis.slice_plans.push(slice_plan);
+        }
+    }
+    analysis.free_names.extend(output.free_names);
+    analysis.defined_names.extend(output.defined_names);
+    analysis.must_start_before_changed_since_time |= output.must_start_before_changed_since_time;
+    analysis.incremental_enabled &= output.incremental_enabled;
+    // If either look-back is unbounded, the combined look-back is unbounded.
+    analysis.max_lookback = analysis
+        .max_lookback
+        .zip(output.max_lookback)
+        .map(|(query, output)| query.max(output));
+}
+
 pub fn hash_compute_plan_proto(plan: &sparrow_api::kaskada::v1alpha::ComputePlan) -> PlanHash {
     use sha2::Digest;
 
@@ -163,10 +331,12 @@ pub fn hash_compute_plan_proto(plan: &sparrow_api::kaskada::v1alpha::ComputePlan
 
 #[cfg(test)]
 mod tests {
-    use sparrow_api::kaskada::v1alpha::{data_type, schema, DataType, Schema};
+    use sparrow_api::kaskada::v1alpha::operation_plan::tick_operation::TickBehavior;
+    use sparrow_api::kaskada::v1alpha::{data_type, operation_plan, schema, DataType, Schema};
     use sparrow_api::kaskada::v1alpha::{
-        ComputeTable, Formula, PerEntityBehavior, TableConfig, TableMetadata,
+        ComputePlan, ComputeTable, Formula, PerEntityBehavior, TableConfig, TableMetadata,
     };
+    use sparrow_syntax::FeatureSetPart;
     use uuid::Uuid;
 
     use super::*;
@@ -268,6 +438,7 @@ mod tests {
         let feature_set = FeatureSet {
             formulas: vec![],
             query: "{x: Table1.str as i64, y: Table1.str }".to_owned(),
+            outputs: vec![],
         };
 
         let result1 = get_plan_hash(vec![table1.clone(), table2.clone()], &feature_set);
@@ -296,11 +467,13 @@ mod tests {
         let feature_set = FeatureSet {
             formulas: vec![],
             query: "{x: Table1.str as i64, y: Table1.str }".to_owned(),
+            outputs: vec![],
         };
         let feature_set_unused_bound = FeatureSet {
             formulas: vec![],
             query: "let foo = last(Table1.str) in {x: Table1.str as i64, y: Table1.str }"
                 .to_owned(),
+            outputs: vec![],
         };
 
         let result1 = get_plan_hash(vec![table1.clone()], &feature_set);
@@ -340,6 +513,7 @@ mod tests {
                 },
             ],
             query: "{x: foo, y: bar }".to_owned(),
+            outputs: vec![],
         };
         let feature_set_2 = FeatureSet {
             formulas: vec![
@@ -355,6 +529,7 @@ mod tests {
                 },
             ],
             query: "{x: foo, y: bar }".to_owned(),
+            outputs: vec![],
         };
 
         let result1 = get_plan_hash(vec![table1.clone()], &feature_set_1);
@@ -398,6 +573,7 @@ mod tests {
         let feature_set = FeatureSet {
             formulas: vec![],
             query: "{x: Table1.str as i64, y: Table1.str }".to_owned(),
+            outputs: vec![],
         };
 
         let result1 = get_plan_hash(vec![table1.clone()], &feature_set);
@@ -442,6 +618,7 @@ mod tests {
         let feature_set = FeatureSet {
             formulas: vec![],
             query: "{x: first(Table1.str), y: last(Table1.str) }".to_owned(),
+            outputs: vec![],
         };
 
         let options = CompilerOptions {
@@ -454,4 +631,230 @@ mod tests {
         let result2 = get_plan_hash_with_options(vec![table1], &feature_set, &options);
         assert_eq!(result1, result2);
     }
+
+    fn compile_plan(per_entity_behavior: PerEntityBehavior, query: &str) -> ComputePlan {
+        let feature_set = FeatureSet {
+            formulas: vec![],
+            query: query.to_owned(),
+            outputs: vec![],
+        };
+        compile_feature_set(per_entity_behavior, &feature_set)
+    }
+
+    fn compile_feature_set(
+        per_entity_behavior: PerEntityBehavior,
+        feature_set: &FeatureSet,
+    ) -> ComputePlan {
+        let table1 = ComputeTable {
+            config: Some(TableConfig::new_with_table_source(
+                "Table1",
+                &Uuid::parse_str("936DA01F9ABD4d9d80C702AF85C822A8").unwrap(),
+                "time",
+                Some("subsort"),
+                "entity",
+                "grouping",
+            )),
+            file_sets: vec![],
+            metadata: Some(TableMetadata {
+                schema: Some(analyze_input_schema()),
+                file_count: 0,
+            }),
+        };
+        let options = CompilerOptions {
+            per_entity_behavior,
+            ..Default::default()
+        };
+
+        let mut data_context = DataContext::try_from_tables(vec![table1]).unwrap();
+        compile(
+            &options,
+            &mut data_context,
+            feature_set,
+            ExpressionKind::Complete,
+        )
+        .unwrap()
+        .plan
+        .unwrap()
+    }
+
+    #[test]
+    fn test_state_reuse_added_stateless_feature() {
+        for per_entity_behavior in [PerEntityBehavior::All, PerEntityBehavior::Final] {
+            let previous = compile_plan(per_entity_behavior, "{x: sum(Table1.str as i64) }");
+            let plan = compile_plan(
+                per_entity_behavior,
+                "{x: sum(Table1.str as i64), y: sum(Table1.str as i64) + 1 }",
+            );
+
+            let state_reuse = crate::plan_state_reuse(&previous, &plan).unwrap();
+            assert_eq!(state_reuse.backfill, vec![], "{per_entity_behavior:?}");
+            assert_eq!(state_reuse.accumulators.len(), 1, "{per_entity_behavior:?}");
+            assert_eq!(
+                state_reuse.operations.len(),
+                plan.operations.len(),
+                "{per_entity_behavior:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_state_reuse_removed_feature() {
+        for per_entity_behavior in [PerEntityBehavior::All, PerEntityBehavior::Final] {
+            let previous = compile_plan(
+                per_entity_behavior,
+                "{x: sum(Table1.str as i64), y: last(Table1.str) }",
+            );
+            let plan = compile_plan(per_entity_behavior, "{x: sum(Table1.str as i64) }");
+
+            let state_reuse = crate::plan_state_reuse(&previous, &plan).unwrap();
+            assert_eq!(state_reuse.backfill, vec![], "{per_entity_behavior:?}");
+            assert_eq!(state_reuse.accumulators.len(), 1, "{per_entity_behavior:?}");
+        }
+    }
+
+    #[test]
+    fn test_state_reuse_added_aggregation() {
+        let previous = compile_plan(PerEntityBehavior::All, "{x: sum(Table1.str as i64) }");
+        let plan = compile_plan(
+            PerEntityBehavior::All,
+            "{x: sum(Table1.str as i64), y: max(Table1.str as i64) }",
+        );
+
+        let state_reuse = crate::plan_state_reuse(&previous, &plan).unwrap();
+        assert_eq!(state_reuse.accumulators.len(), 1);
+        assert_eq!(state_reuse.backfill.len(), 1);
+        assert!(state_reuse.backfill[0].expression.is_some());
+    }
+
+    #[test]
+    fn test_examples_plan() {
+        let plan = compile_plan(PerEntityBehavior::Examples, "{x: sum(Table1.str as i64) }");
+        assert_eq!(plan.per_entity_behavior(), PerEntityBehavior::Examples);
+
+        // The results are produced at the examples, rather than at the end.
+        let tick_behaviors: Vec<_> = plan
+            .operations
+            .iter()
+            .filter_map(|operation| match &operation.operator {
+                Some(operation_plan::Operator::Tick(tick)) => Some(tick.behavior()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(tick_behaviors, vec![TickBehavior::Examples]);
+    }
+
+    #[test]
+    fn test_named_outputs_plan() {
+        let feature_set = FeatureSet {
+            formulas: vec![],
+            query: "{x: sum(Table1.str as i64) }".to_owned(),
+            outputs: vec![Output {
+                name: "by_str".to_owned(),
+                query: "with_key(Table1.str, { n: count(Table1) })".to_owned(),
+                per_entity_behavior: PerEntityBehavior::Final as i32,
+            }],
+        };
+        let plan = compile_feature_set(PerEntityBehavior::All, &feature_set);
+
+        let output = &plan.outputs[0];
+        assert_eq!(plan.outputs.len(), 1);
+        assert_eq!(output.name, "by_str");
+        assert_eq!(output.per_entity_behavior(), PerEntityBehavior::Final);
+        assert_ne!(output.grouping, plan.primary_grouping);
+
+        // The output is produced by an operation other than the last, with
+        // the result as its only output.
+        let operation = output.operation as usize;
+        assert!(operation < plan.operations.len() - 1);
+        let num_outputs = plan.operations[operation]
+            .expressions
+            .iter()
+            .filter(|expression| expression.output)
+            .count();
+        assert_eq!(num_outputs, 1);
+
+        // The table is only scanned once.
+        let num_scans = plan
+            .operations
+            .iter()
+            .filter(|operation| {
+                matches!(operation.operator, Some(operation_plan::Operator::Scan(_)))
+            })
+            .count();
+        assert_eq!(num_scans, 1);
+    }
+
+    #[test]
+    fn test_duplicate_output_names() {
+        let output = Output {
+            name: "output".to_owned(),
+            query: "{ n: count(Table1) }".to_owned(),
+            per_entity_behavior: PerEntityBehavior::Unspecified as i32,
+        };
+        let feature_set = FeatureSet {
+            formulas: vec![],
+            query: "{ n: count(Table1) }".to_owned(),
+            outputs: vec![output.clone(), output],
+        };
+
+        let mut data_context = DataContext::default();
+        let error = compile(
+            &CompilerOptions::default(),
+            &mut data_context,
+            &feature_set,
+            ExpressionKind::Complete,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error.current_context(),
+            Error::InvalidOutput(message) if message == "duplicate output name 'output'"
+        ));
+    }
+
+    #[test]
+    fn test_analyze() {
+        let table1 = ComputeTable {
+            config: Some(TableConfig::new_with_table_source(
+                "Table1",
+                &Uuid::parse_str("936DA01F9ABD4d9d80C702AF85C822A8").unwrap(),
+                "time",
+                Some("subsort"),
+                "entity",
+                "grouping",
+            )),
+            file_sets: vec![],
+            metadata: Some(TableMetadata {
+                schema: Some(analyze_input_schema()),
+                file_count: 0,
+            }),
+        };
+
+        let feature_set = FeatureSet {
+            formulas: vec![],
+            query: "let x = Table1.subsort in { x, y: Table1.missing }".to_owned(),
+            outputs: vec![],
+        };
+        let analysis = analyze(vec![table1], &feature_set).unwrap();
+
+        // The `let` binding of `x` and the reference to it are both annotated.
+        let x_types: Vec<_> = analysis
+            .type_annotations()
+            .iter()
+            .filter(|annotation| {
+                &feature_set.query[annotation.location.start()..annotation.location.end()] == "x"
+            })
+            .map(|annotation| annotation.value_type.to_string())
+            .collect();
+        assert_eq!(x_types, vec!["i32", "i32"]);
+
+        // The missing field is reported at its location in the query.
+        assert_eq!(analysis.num_errors(), 1);
+        let labels = analysis.diagnostics()[0].primary_labels();
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].location().part(), FeatureSetPart::Query);
+        assert_eq!(
+            &feature_set.query[labels[0].location().start()..labels[0].location().end()],
+            "missing"
+        );
+    }
 }
@@ -3,7 +3,7 @@ use std::rc::Rc;
 use std::sync::Arc;
 
 use anyhow::Context;
-use arrow::datatypes::{DataType, SchemaRef};
+use arrow::datatypes::{DataType, Field, SchemaRef};
 use sparrow_api::kaskada::v1alpha::slice_plan::Slice;
 use sparrow_api::kaskada::v1alpha::{compute_table, ComputeTable, PreparedFile, TableConfig};
 use sparrow_core::context_code;
@@ -140,27 +140,66 @@ impl DataContext {
         };
 
         // 2. Get the key type from the table config and schema.
-        let key_type = schema
-            .field_with_name(&config.group_column_name)
-            .with_context(|| {
+        //
+        // A composite key is a struct containing each of the key columns.
+        let key_fields = config
+            .group_column_names()
+            .map(|name| {
+                schema.field_with_name(name).with_context(|| {
+                    context_code!(
+                        tonic::Code::InvalidArgument,
+                        "Grouping column name '{}' not defined in table '{}'",
+                        name,
+                        config.name
+                    )
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let key_type = if config.has_composite_key() {
+            DataType::Struct(
+                key_fields
+                    .iter()
+                    .map(|field| Field::new(field.name(), field.data_type().clone(), true))
+                    .collect(),
+            )
+        } else {
+            key_fields[0].data_type().clone()
+        };
+
+        // 3. Check the operation column of change-data-capture tables.
+        if let Some(operation_column_name) = config.operation_column_name() {
+            let operation_field =
+                schema
+                    .field_with_name(operation_column_name)
+                    .with_context(|| {
+                        context_code!(
+                            tonic::Code::InvalidArgument,
+                            "Operation column name '{}' not defined in table '{}'",
+                            operation_column_name,
+                            config.name
+                        )
+                    })?;
+            anyhow::ensure!(
+                operation_field.data_type() == &DataType::Utf8,
                 context_code!(
                     tonic::Code::InvalidArgument,
-                    "Grouping column name '{}' not defined in table '{}'",
-                    config.group_column_name,
-                    config.name
+                    "Operation column '{}' in table '{}' must be a string, but was {:?}",
+                    operation_column_name,
+                    config.name,
+                    operation_field.data_type()
                 )
-            })?
-            .data_type();
+            );
+        }
 
-        // 3. Get (or create) the group ID for the table grouping.
+        // 4. Get (or create) the group ID for the table grouping.
         let grouping_name = if config.grouping.is_empty() {
             &config.name
         } else {
             &config.grouping
         };
-        let group_id = self.get_or_create_group_id(grouping_name, key_type)?;
+        let group_id = self.get_or_create_group_id(grouping_name, &key_type)?;
 
-        // 4. Create the table info and add to the set.
+        // 5. Create the table info and add to the set.
         let table_uuid = Uuid::parse_str(&config.uuid).context("parsing string to table uuid")?;
         let table_id = TableId::new(table_uuid.to_owned());
         let table_info = TableInfo::try_new(table_id, group_id, schema, table)?;
@@ -398,6 +437,17 @@ impl TableInfo {
         &self.file_sets
     }
 
+    /// Return the (approximate) number of rows in the table.
+    ///
+    /// This is the number of rows in the largest file set.
+    pub fn num_rows(&self) -> i64 {
+        self.file_sets
+            .iter()
+            .map(|set| set.prepared_files.iter().map(|file| file.num_rows).sum())
+            .max()
+            .unwrap_or(0)
+    }
+
     pub fn prepared_files_for_slice(
         &self,
         requested_slice: &Option<Slice>,
@@ -130,6 +130,20 @@ impl Dfg {
         expression: Expression,
         mut children: ChildrenVec,
     ) -> anyhow::Result<Id> {
+        // Aggregations only have the session gap if they are session windowed.
+        // Omitting the null gap leaves the plans of other aggregations as they
+        // were before sessions took an explicit gap.
+        if let Expression::Inst(InstKind::Simple(op)) = &expression {
+            if op.is_aggregation()
+                && children.len() == 4
+                && self
+                    .literal(children[3])
+                    .map_or(false, ScalarValue::is_null)
+            {
+                children.pop();
+            }
+        }
+
         // First, determine the operation the expression should be in.
         // This is created by merging the operations from each of the arguments.
         let operation = self.infer_operation(children.iter().copied())?;
@@ -262,9 +276,15 @@ impl Dfg {
                             children.len(),
                         );
                     }
-                    Expression::Inst(InstKind::Simple(op)) => op
-                        .signature(sparrow_plan::Mode::Plan)
-                        .assert_valid_argument_count(children.len() - 1),
+                    Expression::Inst(InstKind::Simple(op)) => {
+                        let mut num_args = children.len() - 1;
+                        if op.is_aggregation() && num_args == 3 {
+                            // The gap is omitted unless session windowed.
+                            num_args += 1;
+                        }
+                        op.signature(sparrow_plan::Mode::Plan)
+                            .assert_valid_argument_count(num_args)
+                    }
                     Expression::Inst(InstKind::FieldRef) => {
                         anyhow::ensure!(
                             children.len() == 3,
@@ -13,7 +13,10 @@ mod builder;
 mod code;
 mod collector;
 mod feature_set_parts;
+mod lints;
 
 pub(crate) use builder::*;
 pub(crate) use code::*;
 pub use collector::*;
+pub(crate) use lints::lint_call;
+pub use lints::{LintOptions, LINTS};
@@ -10,7 +10,7 @@ use crate::diagnostics::DiagnosticBuilder;
 /// Macro for registering the diagonstic codes.
 macro_rules! register_diagnostics {
     ( $($name:ident ( $code:ident, $severity:ident, $message:expr ), )* ) => (
-        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
         pub enum DiagnosticCode {
             $( $name ),*
         }
@@ -69,6 +69,16 @@ UnusedBinding(W2001, Warning, "Unused binding"),
 // TODO: Change scheduling to support the same node being in multiple groups,
 // and then convert this back to a Warning.
 IncompatibleGrouping(W2001, Error, "Incompatible grouping"),
+
+// Lints: 2100 - 2199
+//
+// These are reported for queries which are valid but likely to be mistakes.
+// The severity they are reported at may be configured, see `LintOptions`.
+UnwindowedAggregation(W2100, Warning, "Aggregation without a window"),
+LookupGroupingMismatch(W2101, Warning, "Lookup with mismatched grouping"),
+ShiftToPast(W2102, Warning, "Shift to a time in the past"),
+MixedTimeUnitComparison(W2103, Warning, "Comparison between different time units"),
+CountOfLiteral(W2104, Warning, "Count over a literal"),
 }
 
 impl DiagnosticCode {
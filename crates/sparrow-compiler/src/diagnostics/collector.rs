@@ -1,12 +1,12 @@
-use codespan_reporting::diagnostic::{Diagnostic, Severity};
+use codespan_reporting::diagnostic::{Diagnostic, LabelStyle, Severity};
 use codespan_reporting::term::{self, Chars, Config, DisplayStyle, Styles};
 use sparrow_api::kaskada::v1alpha::FeatureSet;
 use sparrow_api::kaskada::v1alpha::FenlDiagnostic;
-use sparrow_syntax::FeatureSetPart;
+use sparrow_syntax::{FeatureSetPart, FenlType, Located, Location};
 use tracing::{error, info, warn};
 
 use crate::diagnostics::feature_set_parts::FeatureSetParts;
-use crate::diagnostics::DiagnosticCode;
+use crate::diagnostics::{DiagnosticCode, LintOptions};
 use crate::DiagnosticBuilder;
 
 /// Collects the diagnostic messages being reported.
@@ -14,6 +14,10 @@ pub(crate) struct DiagnosticCollector<'a> {
     feature_set: FeatureSetParts<'a>,
     /// Collect the diagnostic messages.
     collected: Vec<CollectedDiagnostic>,
+    /// Collect the inferred types of parts of the query.
+    type_annotations: Vec<TypeAnnotation>,
+    /// The configured severity of lints.
+    lint_options: LintOptions,
     config: Config,
 }
 
@@ -29,27 +33,67 @@ impl<'a> std::fmt::Debug for DiagnosticCollector<'a> {
 #[derive(Clone, Debug)]
 pub struct CollectedDiagnostic {
     code: DiagnosticCode,
+    /// The severity the diagnostic was reported at.
+    ///
+    /// This is usually the severity of the code, but may be configured
+    /// for lints.
+    severity: Severity,
     formatted: String,
+    /// The message for each primary label, located at the labeled code.
+    primary_labels: Vec<Located<String>>,
+    notes: Vec<String>,
 }
 
 impl CollectedDiagnostic {
+    fn failed_to_report() -> Self {
+        Self {
+            code: DiagnosticCode::FailedToReport,
+            severity: Severity::Bug,
+            formatted: "Failed to report diagnostic".to_owned(),
+            primary_labels: vec![],
+            notes: vec![],
+        }
+    }
+
+    /// The code identifying the kind of problem, such as `E0001`.
+    pub fn code_str(&self) -> &'static str {
+        self.code.code_str()
+    }
+
+    /// The message associated with the code.
+    pub fn message(&self) -> &'static str {
+        self.code.message()
+    }
+
+    /// The primary labels indicating where the problem is.
+    ///
+    /// Unlike the formatted diagnostic, these may be used to report the
+    /// problem against the original source, for instance in an editor.
+    pub fn primary_labels(&self) -> &[Located<String>] {
+        &self.primary_labels
+    }
+
+    pub fn notes(&self) -> &[String] {
+        &self.notes
+    }
+
     pub fn severity(&self) -> Severity {
-        self.code.severity()
+        self.severity
     }
 
     pub fn is_error(&self) -> bool {
-        self.code.severity() == Severity::Error
+        self.severity == Severity::Error
     }
 
     pub fn is_bug(&self) -> bool {
-        self.code.severity() == Severity::Bug
+        self.severity == Severity::Bug
     }
 }
 
 impl From<CollectedDiagnostic> for FenlDiagnostic {
     fn from(diagnostic: CollectedDiagnostic) -> Self {
         use sparrow_api::kaskada::v1alpha::Severity as ApiSeverity;
-        let severity = match diagnostic.code.severity() {
+        let severity = match diagnostic.severity {
             Severity::Bug => ApiSeverity::Error,
             Severity::Error => ApiSeverity::Error,
             Severity::Warning => ApiSeverity::Warning,
@@ -66,6 +110,16 @@ impl From<CollectedDiagnostic> for FenlDiagnostic {
     }
 }
 
+/// The inferred type of a part of the query, such as a reference.
+///
+/// These aren't diagnostics, but are collected alongside them so that
+/// editors can show the type of each part of the query.
+#[derive(Clone, Debug)]
+pub struct TypeAnnotation {
+    pub location: Location,
+    pub value_type: FenlType,
+}
+
 impl std::fmt::Display for CollectedDiagnostic {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(&self.formatted)
@@ -77,6 +131,8 @@ impl<'a> DiagnosticCollector<'a> {
         Self {
             feature_set: FeatureSetParts::new(feature_set),
             collected: Vec::new(),
+            type_annotations: Vec::new(),
+            lint_options: LintOptions::default(),
             config: Config {
                 chars: Chars::ascii(),
                 display_style: DisplayStyle::Rich,
@@ -88,6 +144,12 @@ impl<'a> DiagnosticCollector<'a> {
         }
     }
 
+    /// Configure the severity lints are reported at.
+    pub(crate) fn with_lint_options(mut self, lint_options: &LintOptions) -> Self {
+        self.lint_options = lint_options.clone();
+        self
+    }
+
     pub fn collect_all(&mut self, builders: impl IntoIterator<Item = DiagnosticBuilder>) {
         for builder in builders {
             builder.emit(self)
@@ -103,11 +165,18 @@ impl<'a> DiagnosticCollector<'a> {
     ///
     /// This makes it easier to use from non-erroring code and ensures that we
     /// don't fail to report any diagnostics in this exceptional situation.
+    ///
+    /// Diagnostics for lints which have been disabled are dropped.
     pub(super) fn add_diagnostic(
         &mut self,
         code: DiagnosticCode,
-        diagnostic: Diagnostic<FeatureSetPart>,
+        mut diagnostic: Diagnostic<FeatureSetPart>,
     ) {
+        let Some(severity) = self.lint_options.severity(code) else {
+            return;
+        };
+        diagnostic.severity = severity;
+
         let mut buffer = termcolor::Buffer::no_color();
 
         if let Err(err) = term::emit(&mut buffer, &self.config, &self.feature_set, &diagnostic) {
@@ -115,10 +184,7 @@ impl<'a> DiagnosticCollector<'a> {
                 "Unable to report diagnostic: {:?} due to {}",
                 diagnostic, err
             );
-            self.collected.push(CollectedDiagnostic {
-                code: DiagnosticCode::FailedToReport,
-                formatted: "Failed to report diagnostic".to_owned(),
-            });
+            self.collected.push(CollectedDiagnostic::failed_to_report());
             return;
         };
         let formatted = match String::from_utf8(buffer.into_inner()) {
@@ -128,17 +194,29 @@ impl<'a> DiagnosticCollector<'a> {
                     "Unable to report diagnostic: {:?} due to {}",
                     diagnostic, err
                 );
-                self.collected.push(CollectedDiagnostic {
-                    code: DiagnosticCode::FailedToReport,
-                    formatted: "Failed to report diagnostic".to_owned(),
-                });
+                self.collected.push(CollectedDiagnostic::failed_to_report());
                 return;
             }
         };
 
-        let diagnostic = CollectedDiagnostic { code, formatted };
+        let primary_labels = diagnostic
+            .labels
+            .into_iter()
+            .filter(|label| label.style == LabelStyle::Primary)
+            .map(|label| {
+                let location = Location::new(label.file_id, label.range.start, label.range.end);
+                Located::new(label.message, location)
+            })
+            .collect();
+        let diagnostic = CollectedDiagnostic {
+            code,
+            severity,
+            formatted,
+            primary_labels,
+            notes: diagnostic.notes,
+        };
 
-        match code.severity() {
+        match severity {
             Severity::Bug | Severity::Error => {
                 warn!("Collecting fatal diagnostic:\n{}", diagnostic)
             }
@@ -160,6 +238,28 @@ impl<'a> DiagnosticCollector<'a> {
             .count()
     }
 
+    /// Record the inferred type of the part of the query at `location`.
+    pub(crate) fn annotate_type(&mut self, location: &Location, value_type: &FenlType) {
+        self.type_annotations.push(TypeAnnotation {
+            location: location.clone(),
+            value_type: value_type.clone(),
+        });
+    }
+
+    /// Return the most recently recorded type of the part of the query at
+    /// `location`, if any.
+    pub(crate) fn annotated_type(&self, location: &Location) -> Option<&FenlType> {
+        self.type_annotations
+            .iter()
+            .rev()
+            .find(|annotation| &annotation.location == location)
+            .map(|annotation| &annotation.value_type)
+    }
+
+    pub fn take_type_annotations(&mut self) -> Vec<TypeAnnotation> {
+        std::mem::take(&mut self.type_annotations)
+    }
+
     pub fn finish(self) -> Vec<CollectedDiagnostic> {
         self.collected
     }
@@ -194,6 +294,7 @@ mod tests {
                 },
             ],
             query: "let foo = a\nlet bar = b\nin { foo, bar, baz }".to_owned(),
+            outputs: vec![],
         }
     }
 
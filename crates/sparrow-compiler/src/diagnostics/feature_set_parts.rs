@@ -196,6 +196,7 @@ mod tests {
                 },
             ],
             query: "let foo = a\nlet bar = b\nin { foo, bar, baz }".to_owned(),
+            outputs: vec![],
         }
     }
 
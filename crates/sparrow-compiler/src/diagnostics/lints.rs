@@ -0,0 +1,528 @@
+//! Lints for queries which are valid but likely to be mistakes.
+//!
+//! Each lint is reported as a warning-level [DiagnosticCode]. The severity
+//! of each lint may be changed (or the lint disabled entirely) using
+//! [LintOptions], which are configured from the `lint_severities` of the
+//! `CompileRequest`.
+//!
+//! Lints are checked as each call is converted to the DFG, so they have
+//! access to both the AST (to report against the code the user wrote) and
+//! the analysis of the arguments (types, groupings, literals, etc.).
+
+use arrow::datatypes::{DataType, TimeUnit};
+use codespan_reporting::diagnostic::Severity;
+use error_stack::{IntoReport, ResultExt};
+use hashbrown::HashMap;
+use sparrow_api::kaskada::v1alpha::{LintSeverity, Severity as ApiSeverity};
+use sparrow_core::ScalarValue;
+use sparrow_syntax::{
+    ExprOp, FeatureSetPart, FenlType, FormatDataType, LiteralValue, Located, Resolved, ResolvedExpr,
+};
+
+use crate::dfg::Dfg;
+use crate::diagnostics::DiagnosticCode;
+use crate::functions::Function;
+use crate::time_domain::TimeDomain;
+use crate::{AstDfgRef, DataContext, DiagnosticCollector, Error};
+
+/// The diagnostic codes which are lints, and may have their severity
+/// configured.
+pub const LINTS: &[DiagnosticCode] = &[
+    DiagnosticCode::UnwindowedAggregation,
+    DiagnosticCode::LookupGroupingMismatch,
+    DiagnosticCode::ShiftToPast,
+    DiagnosticCode::MixedTimeUnitComparison,
+    DiagnosticCode::CountOfLiteral,
+];
+
+/// The number of rows a table must have before an aggregation without a
+/// window is reported.
+///
+/// Aggregating over all time is reasonable for small tables, but over large
+/// tables it usually indicates a missing window.
+const UNWINDOWED_AGGREGATION_ROWS: i64 = 10_000_000;
+
+/// Configures the severity that each lint is reported at.
+#[derive(Clone, Debug, Default)]
+pub struct LintOptions {
+    /// Overridden severities. A severity of `None` disables the lint.
+    severities: HashMap<DiagnosticCode, Option<Severity>>,
+}
+
+impl LintOptions {
+    /// Create the lint options from the `lint_severities` of a request.
+    pub fn try_from_proto(lint_severities: &[LintSeverity]) -> error_stack::Result<Self, Error> {
+        let mut options = Self::default();
+        for lint_severity in lint_severities {
+            let code = LINTS
+                .iter()
+                .find(|code| code.code_str() == lint_severity.code)
+                .ok_or_else(|| Error::InvalidLint(lint_severity.code.clone()))
+                .into_report()
+                .attach_printable_lazy(|| {
+                    format!(
+                        "available lints: {}",
+                        LINTS
+                            .iter()
+                            .map(|code| code.code_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })?;
+
+            let severity = match ApiSeverity::from_i32(lint_severity.severity) {
+                Some(ApiSeverity::Unspecified) => None,
+                Some(ApiSeverity::Bug) => Some(Severity::Bug),
+                Some(ApiSeverity::Error) => Some(Severity::Error),
+                Some(ApiSeverity::Warning) => Some(Severity::Warning),
+                Some(ApiSeverity::Note) => Some(Severity::Note),
+                Some(ApiSeverity::Help) => Some(Severity::Help),
+                None => error_stack::bail!(Error::InvalidLint(lint_severity.code.clone())),
+            };
+            options.set_severity(*code, severity);
+        }
+        Ok(options)
+    }
+
+    /// Set the severity `code` is reported at. `None` disables the lint.
+    pub fn set_severity(&mut self, code: DiagnosticCode, severity: Option<Severity>) {
+        self.severities.insert(code, severity);
+    }
+
+    /// Return the severity `code` should be reported at, or `None` if it
+    /// should not be reported.
+    pub(crate) fn severity(&self, code: DiagnosticCode) -> Option<Severity> {
+        match self.severities.get(&code) {
+            Some(severity) => *severity,
+            None => Some(code.severity()),
+        }
+    }
+}
+
+/// Check the lints which apply to a call of `function`.
+///
+/// The `expr` is the (resolved) call expression and `arguments` the
+/// corresponding DFG nodes. This should only be called if the arguments
+/// were successfully converted.
+pub(crate) fn lint_call(
+    function: &Function,
+    name: &Located<String>,
+    expr: &ResolvedExpr,
+    arguments: &Resolved<Located<AstDfgRef>>,
+    data_context: &DataContext,
+    dfg: &Dfg,
+    diagnostics: &mut DiagnosticCollector<'_>,
+) {
+    // Only lint code the user wrote, rather than the internal decorations.
+    if !matches!(
+        name.location().part(),
+        FeatureSetPart::Query | FeatureSetPart::Formula(_)
+    ) {
+        return;
+    }
+
+    match function.name() {
+        "count" | "count_if" => lint_count_of_literal(name, arguments, dfg, diagnostics),
+        "lookup" => lint_lookup_grouping(expr, arguments, data_context, diagnostics),
+        "shift_to" | "shift_by" => lint_shift_to_past(function, arguments, dfg, diagnostics),
+        "eq" | "neq" | "lt" | "gt" | "lte" | "gte" => {
+            lint_mixed_time_units(name, expr, arguments, diagnostics)
+        }
+        _ => (),
+    }
+
+    if function.is_aggregation() {
+        lint_unwindowed_aggregation(name, expr, arguments, data_context, diagnostics);
+    }
+}
+
+/// Aggregations over all time of large tables are usually missing a window.
+fn lint_unwindowed_aggregation(
+    name: &Located<String>,
+    expr: &ResolvedExpr,
+    arguments: &Resolved<Located<AstDfgRef>>,
+    data_context: &DataContext,
+    diagnostics: &mut DiagnosticCollector<'_>,
+) {
+    let unwindowed = expr.args().values().get(1).map_or(false, |window| {
+        matches!(window.op(), ExprOp::Literal(literal) if literal.inner() == &LiteralValue::Null)
+    });
+    if !unwindowed {
+        return;
+    }
+
+    let TimeDomain::Table { table } = arguments[0].time_domain() else {
+        return;
+    };
+    let Some(table_info) = data_context.table_info(*table) else {
+        return;
+    };
+
+    let num_rows = table_info.num_rows();
+    if num_rows > UNWINDOWED_AGGREGATION_ROWS {
+        DiagnosticCode::UnwindowedAggregation
+            .builder()
+            .with_label(name.location().primary_label().with_message(format!(
+                "'{name}' aggregates all {num_rows} rows of '{}'",
+                table_info.name()
+            )))
+            .with_note("Consider adding a window, such as `window = since(daily())`".to_owned())
+            .emit(diagnostics);
+    }
+}
+
+/// Counting a literal counts the rows of whatever it is merged with, which
+/// is rarely what was intended.
+fn lint_count_of_literal(
+    name: &Located<String>,
+    arguments: &Resolved<Located<AstDfgRef>>,
+    dfg: &Dfg,
+    diagnostics: &mut DiagnosticCollector<'_>,
+) {
+    let input = &arguments[0];
+    if dfg.literal(input.value()).is_some() {
+        DiagnosticCode::CountOfLiteral
+            .builder()
+            .with_label(
+                input
+                    .location()
+                    .primary_label()
+                    .with_message(format!("'{name}' of a literal value")),
+            )
+            .with_note("Count the rows of a table, such as `count(Purchases)`".to_owned())
+            .emit(diagnostics);
+    }
+}
+
+/// A lookup whose key is the entity column of a table with a different
+/// grouping than the foreign value is likely using the wrong key.
+fn lint_lookup_grouping(
+    expr: &ResolvedExpr,
+    arguments: &Resolved<Located<AstDfgRef>>,
+    data_context: &DataContext,
+    diagnostics: &mut DiagnosticCollector<'_>,
+) {
+    let ExprOp::FieldRef(field, _) = expr.args()[0].op() else {
+        return;
+    };
+    let Some(foreign_grouping) = arguments[1].grouping() else {
+        return;
+    };
+
+    let mut key_groupings = data_context
+        .table_infos()
+        .filter(|table| &table.config().group_column_name == field.inner())
+        .map(|table| table.group_id())
+        .peekable();
+    if key_groupings.peek().is_none() || key_groupings.any(|grouping| grouping == foreign_grouping)
+    {
+        return;
+    }
+
+    let foreign_name = data_context
+        .group_info(foreign_grouping)
+        .map(|info| info.name())
+        .unwrap_or("unknown");
+    DiagnosticCode::LookupGroupingMismatch
+        .builder()
+        .with_label(
+            arguments[0]
+                .location()
+                .primary_label()
+                .with_message(format!(
+                    "'{field}' is the entity key of a different grouping"
+                )),
+        )
+        .with_label(
+            arguments[1]
+                .location()
+                .secondary_label()
+                .with_message(format!("Foreign value has grouping '{foreign_name}'")),
+        )
+        .emit(diagnostics);
+}
+
+/// Shifting to a fixed time, or by a negative amount, shifts (some) rows to
+/// a time before they occurred.
+fn lint_shift_to_past(
+    function: &Function,
+    arguments: &Resolved<Located<AstDfgRef>>,
+    dfg: &Dfg,
+    diagnostics: &mut DiagnosticCollector<'_>,
+) {
+    let time = &arguments[0];
+    let Some(literal) = dfg.literal(time.value()) else {
+        return;
+    };
+
+    let message = if function.name() == "shift_to" {
+        if literal.is_null() {
+            return;
+        }
+        "Shifting to a constant time shifts rows after it into the past"
+    } else {
+        let negative = match literal {
+            ScalarValue::Int64(Some(n)) | ScalarValue::Duration(Some(n), _) => *n < 0,
+            ScalarValue::Int32(Some(n)) | ScalarValue::IntervalMonths(Some(n)) => *n < 0,
+            ScalarValue::IntervalDayTime(Some((days, millis))) => *days < 0 || *millis < 0,
+            _ => false,
+        };
+        if !negative {
+            return;
+        }
+        "Shifting by a negative amount shifts rows into the past"
+    };
+
+    DiagnosticCode::ShiftToPast
+        .builder()
+        .with_label(time.location().primary_label().with_message(message))
+        .with_note("Rows may only be shifted forward in time".to_owned())
+        .emit(diagnostics);
+}
+
+/// Comparing temporal values which have been cast to numbers compares
+/// their raw values, which is incorrect if the units differ.
+fn lint_mixed_time_units(
+    name: &Located<String>,
+    expr: &ResolvedExpr,
+    arguments: &Resolved<Located<AstDfgRef>>,
+    diagnostics: &mut DiagnosticCollector<'_>,
+) {
+    if time_unit(arguments[0].value_type()).is_some() {
+        // Comparisons of temporal values handle the units.
+        return;
+    }
+
+    let lhs = uncast_type(&expr.args()[0], diagnostics);
+    let rhs = uncast_type(&expr.args()[1], diagnostics);
+    let (Some(lhs), Some(rhs)) = (lhs, rhs) else {
+        return;
+    };
+
+    match (time_unit(&lhs), time_unit(&rhs)) {
+        (Some(lhs_unit), Some(rhs_unit)) if lhs_unit != rhs_unit => {
+            let message = match (&lhs, &rhs) {
+                (FenlType::Concrete(lhs), FenlType::Concrete(rhs)) => format!(
+                    "Comparing '{}' and '{}' as numbers",
+                    FormatDataType(lhs),
+                    FormatDataType(rhs)
+                ),
+                _ => "Comparing different time units as numbers".to_owned(),
+            };
+            DiagnosticCode::MixedTimeUnitComparison
+                .builder()
+                .with_label(name.location().primary_label().with_message(message))
+                .with_note("Convert both sides to the same unit before comparing".to_owned())
+                .emit(diagnostics);
+        }
+        _ => (),
+    }
+}
+
+/// Return the type of `expr` before any casts were applied.
+fn uncast_type(expr: &ResolvedExpr, diagnostics: &DiagnosticCollector<'_>) -> Option<FenlType> {
+    let mut expr = expr;
+    while let ExprOp::Cast(_, _) = expr.op() {
+        expr = &**expr.args()[0].inner();
+    }
+
+    let location = match expr.op() {
+        ExprOp::FieldRef(field, _) => field.location(),
+        ExprOp::Call(name) => name.location(),
+        ExprOp::Reference(reference) => reference.location(),
+        _ => return None,
+    };
+    diagnostics.annotated_type(location).cloned()
+}
+
+/// Return the time unit of temporal types.
+fn time_unit(fenl_type: &FenlType) -> Option<&TimeUnit> {
+    match fenl_type {
+        FenlType::Concrete(
+            DataType::Timestamp(unit, _)
+            | DataType::Duration(unit)
+            | DataType::Time32(unit)
+            | DataType::Time64(unit),
+        ) => Some(unit),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::datatypes::Field;
+    use sparrow_api::kaskada::v1alpha::compile_request::ExpressionKind;
+    use sparrow_api::kaskada::v1alpha::compute_table::FileSet;
+    use sparrow_api::kaskada::v1alpha::{
+        ComputeTable, FeatureSet, PreparedFile, Schema, TableConfig, TableMetadata,
+    };
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{CompilerOptions, FrontendOutput};
+
+    fn add_table(
+        data_context: &mut DataContext,
+        name: &str,
+        key: &str,
+        grouping: &str,
+        num_rows: i64,
+    ) {
+        let schema = arrow::datatypes::Schema::new(vec![
+            Field::new("customer_id", DataType::Utf8, true),
+            Field::new("product_id", DataType::Utf8, true),
+            Field::new("price", DataType::Float64, true),
+        ]);
+        let schema = Schema::try_from(&schema).unwrap();
+
+        let index = data_context.table_infos().count() as u64;
+        data_context
+            .add_table(ComputeTable {
+                config: Some(TableConfig::new_with_table_source(
+                    name,
+                    &Uuid::from_u64_pair(1, index),
+                    "time",
+                    None,
+                    key,
+                    grouping,
+                )),
+                metadata: Some(TableMetadata {
+                    schema: Some(schema),
+                    file_count: 1,
+                }),
+                file_sets: vec![FileSet {
+                    slice_plan: None,
+                    prepared_files: vec![PreparedFile {
+                        num_rows,
+                        ..PreparedFile::default()
+                    }],
+                }],
+            })
+            .unwrap();
+    }
+
+    fn lint_codes(query: &str, options: &CompilerOptions) -> (Vec<&'static str>, usize) {
+        let mut data_context = DataContext::for_test();
+        add_table(
+            &mut data_context,
+            "Purchases",
+            "customer_id",
+            "customer",
+            20_000_000,
+        );
+        add_table(&mut data_context, "Products", "product_id", "product", 100);
+
+        let feature_set = FeatureSet {
+            formulas: vec![],
+            query: query.to_owned(),
+            outputs: vec![],
+        };
+        let output = FrontendOutput::try_compile(
+            &mut data_context,
+            &feature_set,
+            options,
+            ExpressionKind::Formula,
+        )
+        .unwrap();
+
+        let codes = output
+            .analysis
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.code_str())
+            .collect();
+        (codes, output.analysis.num_errors())
+    }
+
+    fn lints(query: &str) -> Vec<&'static str> {
+        let (codes, num_errors) = lint_codes(query, &CompilerOptions::default());
+        assert_eq!(num_errors, 0, "Unexpected errors in {query}: {codes:?}");
+        codes
+    }
+
+    #[test]
+    fn test_count_of_literal() {
+        assert_eq!(lints("count(5)"), vec!["W2104"]);
+        assert_eq!(lints("count(Products)"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_unwindowed_aggregation() {
+        assert_eq!(lints("sum(Purchases.price)"), vec!["W2100"]);
+        assert_eq!(
+            lints("sum(Purchases.price, window = since(daily()))"),
+            Vec::<&str>::new()
+        );
+        assert_eq!(lints("sum(Products.price)"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_lookup_grouping_mismatch() {
+        assert_eq!(
+            lints("lookup(Purchases.customer_id, last(Products.price))"),
+            vec!["W2101"]
+        );
+        assert_eq!(
+            lints("lookup(Purchases.product_id, last(Products.price))"),
+            Vec::<&str>::new()
+        );
+    }
+
+    #[test]
+    fn test_shift_to_past() {
+        assert_eq!(lints("shift_by(seconds(-10), Table1.x_i64)"), vec!["W2102"]);
+        assert_eq!(
+            lints("shift_by(seconds(10), Table1.x_i64)"),
+            Vec::<&str>::new()
+        );
+    }
+
+    #[test]
+    fn test_mixed_time_unit_comparison() {
+        assert_eq!(
+            lints("time_of(Table1) as i64 > seconds(10) as i64"),
+            vec!["W2103"]
+        );
+        assert_eq!(
+            lints("time_of(Table1) as i64 > time_of(Table2) as i64"),
+            Vec::<&str>::new()
+        );
+    }
+
+    #[test]
+    fn test_configured_severity() {
+        let mut options = CompilerOptions::default();
+        options
+            .lints
+            .set_severity(DiagnosticCode::CountOfLiteral, Some(Severity::Error));
+        assert_eq!(lint_codes("count(5)", &options), (vec!["W2104"], 1));
+
+        options
+            .lints
+            .set_severity(DiagnosticCode::CountOfLiteral, None);
+        assert_eq!(lint_codes("count(5)", &options), (vec![], 0));
+    }
+
+    #[test]
+    fn test_lint_options_from_proto() {
+        let options = LintOptions::try_from_proto(&[LintSeverity {
+            code: "W2100".to_owned(),
+            severity: ApiSeverity::Unspecified as i32,
+        }])
+        .unwrap();
+        assert_eq!(
+            options.severity(DiagnosticCode::UnwindowedAggregation),
+            None
+        );
+        assert_eq!(
+            options.severity(DiagnosticCode::CountOfLiteral),
+            Some(Severity::Warning)
+        );
+
+        assert!(LintOptions::try_from_proto(&[LintSeverity {
+            code: "E0001".to_owned(),
+            severity: ApiSeverity::Warning as i32,
+        }])
+        .is_err());
+    }
+}
@@ -10,6 +10,10 @@ pub enum Error {
     Internal(&'static str),
     #[display(fmt = "failed to extract plan protos")]
     ExtractPlanProto,
+    #[display(fmt = "invalid lint severity: {_0}")]
+    InvalidLint(String),
+    #[display(fmt = "invalid output: {_0}")]
+    InvalidOutput(String),
 }
 
 impl error_stack::Context for Error {}
@@ -17,7 +21,9 @@ impl error_stack::Context for Error {}
 impl ErrorCode for Error {
     fn error_code(&self) -> tonic::Code {
         match self {
-            Error::MissingField(_) => tonic::Code::InvalidArgument,
+            Error::MissingField(_) | Error::InvalidLint(_) | Error::InvalidOutput(_) => {
+                tonic::Code::InvalidArgument
+            }
             _ => tonic::Code::Internal,
         }
     }
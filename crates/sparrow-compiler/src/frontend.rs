@@ -3,6 +3,7 @@
 mod first_reference;
 mod free_variable;
 mod incremental_enabled;
+mod lookback_analysis;
 mod output_types;
 mod parse_expr;
 mod parse_feature_set;
@@ -12,12 +13,12 @@ mod slice_analysis;
 use std::collections::BTreeSet;
 use std::rc::Rc;
 
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use arrow::datatypes::{DataType, TimeUnit};
 use smallvec::smallvec;
 use sparrow_api::kaskada::v1alpha::compile_request::ExpressionKind;
 use sparrow_api::kaskada::v1alpha::{FeatureSet, LateBoundValue, PerEntityBehavior, SlicePlan};
-use sparrow_plan::GroupId;
+use sparrow_plan::{GroupId, InstKind};
 use sparrow_syntax::{FeatureSetPart, FenlType, Location};
 use tracing::error;
 
@@ -27,7 +28,7 @@ use crate::frontend::parse_feature_set::ParsedFeatureSet;
 use crate::time_domain::TimeDomain;
 use crate::{
     ast_to_dfg, AstDfg, AstDfgRef, CollectedDiagnostic, CompilerOptions, DataContext,
-    DiagnosticCode, DiagnosticCollector,
+    DiagnosticCode, DiagnosticCollector, TypeAnnotation,
 };
 
 /// The results of "frontend" compilation.
@@ -52,7 +53,15 @@ pub struct FrontendAnalysis {
     pub must_start_before_changed_since_time: bool,
     /// Whether incremental is enabled.
     pub incremental_enabled: bool,
+    /// The maximum look-back (in nanoseconds) needed to compute the result at
+    /// any time.
+    ///
+    /// Input rows more than this before the first result needed may be
+    /// skipped. If `None`, the look-back is unbounded and all rows are needed.
+    pub max_lookback: Option<i64>,
     pub primary_grouping: Option<GroupId>,
+    /// The inferred types of references, fields, calls and `let` bindings.
+    pub type_annotations: Vec<TypeAnnotation>,
 }
 
 impl FrontendAnalysis {
@@ -87,6 +96,10 @@ impl FrontendAnalysis {
     pub fn defined_names(&self) -> &BTreeSet<String> {
         &self.defined_names
     }
+
+    pub fn type_annotations(&self) -> &[TypeAnnotation] {
+        &self.type_annotations
+    }
 }
 
 pub(super) struct FrontendOutput {
@@ -102,6 +115,9 @@ const FINAL_QUERY_AT_TIME_DECORATION: &str = "result | when(time_of($input) <= _
                                               __changed_since_time__ and finished())";
 const FINAL_QUERY_DECORATION: &str =
     "result | last() | when(last(time_of(result)) >= __changed_since_time__ and finished())";
+const EXAMPLES_DECORATION: &str = "result | last() | when(examples())";
+const CHANGE_DATA_CAPTURE_DECORATION: &str =
+    "last(table) | if(last(__cdc_operation__) != \"delete\")";
 
 impl FrontendOutput {
     /// Perform frontend-compilation of the given feature set.
@@ -115,7 +131,9 @@ impl FrontendOutput {
         expression_kind: ExpressionKind,
     ) -> anyhow::Result<Self> {
         let mut dfg = data_context.create_dfg()?;
-        let mut diagnostics = DiagnosticCollector::new(feature_set);
+        let mut diagnostics =
+            DiagnosticCollector::new(feature_set).with_lint_options(&options.lints);
+        bind_change_data_capture_tables(data_context, &mut diagnostics, &mut dfg)?;
 
         let parsed = ParsedFeatureSet::try_new(feature_set, &mut diagnostics)?;
         for formula in parsed.formulas.into_iter() {
@@ -165,6 +183,15 @@ impl FrontendOutput {
             false
         };
 
+        // Determine the look-back of the query before it is decorated. The
+        // decorations only filter the results, so they don't change which
+        // inputs are needed for results at or after a given time.
+        let max_lookback = if executable {
+            lookback_analysis::max_lookback(&dfg.extract_simplest(query.value()))
+        } else {
+            None
+        };
+
         // Decorate the expression as needed for the query_type.
         let result_node = match options.per_entity_behavior {
             _ if !executable => {
@@ -227,6 +254,21 @@ impl FrontendOutput {
                 dfg.exit_env();
                 decorated.value()
             }
+            PerEntityBehavior::Examples => {
+                dfg.enter_env();
+                dfg.bind("result", query);
+
+                // The latest result of each entity is produced at the time of
+                // each example for that entity.
+                let decorated = add_decoration(
+                    data_context,
+                    &mut diagnostics,
+                    &mut dfg,
+                    EXAMPLES_DECORATION,
+                )?;
+                dfg.exit_env();
+                decorated.value()
+            }
             PerEntityBehavior::Unspecified => {
                 anyhow::bail!("Unspecified per entity behavior")
             }
@@ -234,6 +276,7 @@ impl FrontendOutput {
 
         // Create the basic analysis information.
         let num_errors = diagnostics.num_errors();
+        let type_annotations = diagnostics.take_type_annotations();
         let diagnostics = diagnostics.finish();
 
         // TODO: We should be able to use dependency information computed by
@@ -266,7 +309,12 @@ impl FrontendOutput {
         tracing::info!("Incremental compute is enabled: {:?}", incremental_enabled);
 
         // Perform the slice analysis and use it to rewrite the DFG.
-        let dfg = slice_analysis::rewrite_slices(dfg, &options.slice_request)?;
+        let dfg = slice_analysis::rewrite_slices(
+            dfg,
+            &options.slice_request,
+            data_context,
+            primary_grouping,
+        )?;
         let slice_plans = slice_analysis::slice_plans(&dfg, data_context)?;
 
         // Dump the final (post-optimization and rewriting) DFG.
@@ -300,6 +348,11 @@ impl FrontendOutput {
                 // TODO: Verify the logic of this variable with change since + queries final at
                 false
             }
+            PerEntityBehavior::Examples => {
+                // Examples may be at any time, so we need to replay all events
+                // which may precede them.
+                true
+            }
         };
 
         let analysis = FrontendAnalysis {
@@ -311,7 +364,9 @@ impl FrontendOutput {
             defined_names,
             must_start_before_changed_since_time,
             incremental_enabled,
+            max_lookback,
             primary_grouping,
+            type_annotations,
         };
         Ok(Self {
             analysis,
@@ -341,6 +396,75 @@ fn add_decoration(
     ast_to_dfg(data_context, dfg, diagnostics, &resolved)
 }
 
+/// Rebinds each change-data-capture table to the latest row for each key.
+///
+/// The rebound table is continuous, so references (including `lookup`) see
+/// the row as of each time. After a delete the row is `null`.
+fn bind_change_data_capture_tables(
+    data_context: &mut DataContext,
+    diagnostics: &mut DiagnosticCollector<'_>,
+    dfg: &mut Dfg,
+) -> anyhow::Result<()> {
+    let cdc_tables: Vec<_> = data_context
+        .table_infos()
+        .filter_map(|table_info| {
+            let operation_column_name = table_info.config().operation_column_name()?;
+            Some((
+                table_info.name().to_owned(),
+                operation_column_name.to_owned(),
+            ))
+        })
+        .collect();
+
+    for (table_name, operation_column_name) in cdc_tables {
+        let table = dfg
+            .get_binding(&table_name)
+            .map_err(|_| anyhow!("Table '{table_name}' not bound"))?;
+
+        let FenlType::Concrete(DataType::Struct(fields)) = table.value_type() else {
+            anyhow::bail!("Table '{table_name}' should have record type");
+        };
+        let operation_type = fields
+            .iter()
+            .find(|field| field.name() == &operation_column_name)
+            .with_context(|| {
+                format!("Operation column '{operation_column_name}' not in '{table_name}'")
+            })?
+            .data_type()
+            .clone();
+
+        let field_name = dfg.add_string_literal(&operation_column_name)?;
+        let value = dfg.add_expression(
+            Expression::Inst(InstKind::FieldRef),
+            smallvec![table.value(), field_name],
+        )?;
+        let operation = Rc::new(AstDfg::new(
+            value,
+            table.is_new(),
+            FenlType::Concrete(operation_type),
+            table.grouping(),
+            table.time_domain().clone(),
+            Location::internal_str("change_data_capture"),
+            None,
+        ));
+
+        dfg.enter_env();
+        dfg.bind("table", table);
+        dfg.bind("__cdc_operation__", operation);
+        let decorated = add_decoration(
+            data_context,
+            diagnostics,
+            dfg,
+            CHANGE_DATA_CAPTURE_DECORATION,
+        )?;
+        dfg.exit_env();
+
+        // Shadow the raw table with the latest row for each key.
+        dfg.bind(&table_name, decorated);
+    }
+    Ok(())
+}
+
 /// Creates a changed_since_time node in the dfg.
 ///
 /// This is a dynamically injectible node, where the value will be inserted
@@ -129,6 +129,7 @@ mod tests {
         FeatureSet {
             formulas: vec![],
             query: input.to_owned(),
+            outputs: vec![],
         }
     }
 
@@ -0,0 +1,205 @@
+//! Analysis of how far before a given time a query needs to read inputs.
+
+use egg::Id;
+use sparrow_core::ScalarValue;
+use sparrow_plan::{InstKind, InstOp};
+
+use crate::ast_to_dfg::window_args::timedelta_nanos;
+use crate::dfg::{DfgExpr, Expression, Operation, StepKind};
+
+/// Return the maximum look-back (in nanoseconds) needed by the result of
+/// `expr`.
+///
+/// The result at any time `t` depends only on input rows at or after
+/// `t - lookback`. Returns `None` if the look-back is unbounded, for instance
+/// because the query uses an unwindowed aggregation.
+///
+/// The result of `expr` is assumed to be the last node.
+pub(super) fn max_lookback(expr: &DfgExpr) -> Option<i64> {
+    // Nodes are topologically ordered, so the children of each node have been
+    // visited before the node itself.
+    let mut lookbacks: Vec<Option<i64>> = Vec::with_capacity(expr.len());
+    for id in expr.ids() {
+        let (kind, children) = expr.node(id);
+        let inputs = children
+            .iter()
+            .map(|child| lookbacks[usize::from(*child)])
+            .try_fold(0, |max, lookback| {
+                lookback.map(|lookback| max.max(lookback))
+            });
+        let lookback = inputs.and_then(|inputs| {
+            let own = node_lookback(expr, kind, children)?;
+            inputs.checked_add(own)
+        });
+        lookbacks.push(lookback);
+    }
+
+    lookbacks.last().copied().flatten()
+}
+
+/// Return the look-back a node adds to the look-back of its inputs.
+fn node_lookback(expr: &DfgExpr, kind: &StepKind, children: &[Id]) -> Option<i64> {
+    match kind {
+        // Aggregations have the arguments `(input, ticks, duration, gap)`,
+        // followed by the operation. The gap is omitted unless the aggregation
+        // is session windowed.
+        StepKind::Expression(Expression::Inst(InstKind::Simple(op))) if op.is_aggregation() => {
+            let args = &children[..children.len() - 1];
+
+            // Windows which reset when a condition is true (`since` and
+            // count-based `sliding`) may include arbitrarily old inputs, as may
+            // sessions, which last as long as inputs keep arriving.
+            let is_session = args.get(3).map_or(false, |gap| !is_null(expr, *gap));
+            if !is_null(expr, args[1]) || is_session {
+                return None;
+            }
+
+            // The duration is the width of a time-based sliding window.
+            // Unwindowed aggregations are unbounded.
+            match literal(expr, args[2]) {
+                Some(ScalarValue::Int64(Some(nanos))) if *nanos > 0 => Some(*nanos),
+                _ => None,
+            }
+        }
+        // The previous values of an entity may be arbitrarily old.
+        StepKind::Expression(Expression::Inst(InstKind::Simple(InstOp::Lag))) => None,
+        StepKind::Operation(Operation::ShiftTo) => shift_by_delta(expr, children[0]),
+        StepKind::Operation(Operation::ShiftUntil) => None,
+        // Ticks produce rows for every entity in the preceding inputs, however
+        // old those inputs are.
+        StepKind::Operation(Operation::Tick(_)) => None,
+        StepKind::Error => None,
+        _ => Some(0),
+    }
+}
+
+/// Return the delta of a `shift_by`, if `time` is the time it shifts to.
+///
+/// `shift_by(delta, value)` is rewritten to
+/// `shift_to(add_time(delta, time_of(value)), value)`. Other times may be
+/// arbitrarily far in the future, so they are unbounded.
+fn shift_by_delta(expr: &DfgExpr, time: Id) -> Option<i64> {
+    let (kind, children) = expr.node(time);
+    if !matches!(
+        kind,
+        StepKind::Expression(Expression::Inst(InstKind::Simple(InstOp::AddTime)))
+    ) {
+        return None;
+    }
+
+    let (time_of, _) = expr.node(children[1]);
+    if !matches!(
+        time_of,
+        StepKind::Expression(Expression::Inst(InstKind::Simple(InstOp::TimeOf)))
+    ) {
+        return None;
+    }
+
+    // Shifting into the past is reported as an error when executed, so
+    // negative deltas need no additional look-back.
+    literal(expr, children[0])
+        .and_then(timedelta_nanos)
+        .map(|delta| delta.max(0))
+}
+
+fn literal(expr: &DfgExpr, id: Id) -> Option<&ScalarValue> {
+    match expr.node(id) {
+        (StepKind::Expression(Expression::Literal(literal)), _) => Some(literal),
+        (StepKind::Transform, children) => literal(expr, children[0]),
+        _ => None,
+    }
+}
+
+fn is_null(expr: &DfgExpr, id: Id) -> bool {
+    literal(expr, id).map_or(false, |literal| literal.is_null())
+}
+
+#[cfg(test)]
+mod tests {
+    use sparrow_api::kaskada::v1alpha::compile_request::ExpressionKind;
+    use sparrow_api::kaskada::v1alpha::{FeatureSet, PerEntityBehavior};
+
+    use crate::{CompilerOptions, DataContext, FrontendOutput};
+
+    fn lookback(query: &str) -> Option<i64> {
+        let mut data_context = DataContext::for_test();
+        let options = CompilerOptions {
+            per_entity_behavior: PerEntityBehavior::All,
+            ..CompilerOptions::default()
+        };
+        let output = FrontendOutput::try_compile(
+            &mut data_context,
+            &FeatureSet::new(query, vec![]),
+            &options,
+            ExpressionKind::Complete,
+        )
+        .unwrap();
+        assert!(!output.analysis.has_errors(), "{query}");
+        output.analysis.max_lookback
+    }
+
+    #[test]
+    fn test_lookback_stateless() {
+        assert_eq!(lookback("{ x: Table1.x_i64 + 10 }"), Some(0));
+    }
+
+    #[test]
+    fn test_lookback_unwindowed_aggregation() {
+        assert_eq!(lookback("{ x: sum(Table1.x_i64) }"), None);
+    }
+
+    #[test]
+    fn test_lookback_lag() {
+        assert_eq!(lookback("{ x: lag(1, Table1.x_i64) }"), None);
+    }
+
+    #[test]
+    fn test_lookback_windows() {
+        const DAY: i64 = 86_400_000_000_000;
+        assert_eq!(
+            lookback("{ x: sum(Table1.x_i64, window=sliding(days(7))) }"),
+            Some(7 * DAY)
+        );
+        assert_eq!(
+            lookback("{ x: sum(Table1.x_i64, window=since(Table1.x_i64 > 10)) }"),
+            None
+        );
+        assert_eq!(
+            lookback("{ x: sum(Table1.x_i64, window=session(days(1))) }"),
+            None
+        );
+        // Ticks produce results for entities without recent inputs.
+        assert_eq!(
+            lookback("{ x: sum(Table1.x_i64, window=since(daily())) }"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_lookback_session_window() {
+        // Sessions have null ticks and duration, and only the gap is set. They
+        // last as long as inputs keep arriving within the gap, so they are
+        // unbounded even when shifted by a fixed delta.
+        assert_eq!(
+            lookback("{ x: count(Table1.x_i64, window=session(seconds(1800))) }"),
+            None
+        );
+        assert_eq!(
+            lookback("{ x: sum(Table1.x_i64, window=session(days(1))) | shift_by(days(1)) }"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_lookback_shift_by() {
+        const DAY: i64 = 86_400_000_000_000;
+        assert_eq!(
+            lookback("{ x: sum(Table1.x_i64, window=sliding(days(7))) | shift_by(days(1)) }"),
+            Some(8 * DAY)
+        );
+        assert_eq!(
+            lookback("{ x: Table1.x_i64 | shift_until(Table1.x_i64 > 10) }"),
+            None
+        );
+    }
+}
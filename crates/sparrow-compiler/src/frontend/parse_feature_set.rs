@@ -663,6 +663,7 @@ mod tests {
         insta::assert_snapshot!(run_parse_and_order(FeatureSet {
             formulas: vec![],
             query: "Foo + $$".to_owned(),
+            outputs: vec![],
         }), @r###"
         Diagnostics
         error[E0011]: Invalid syntax
@@ -685,6 +686,7 @@ mod tests {
                 source_location: "FormulaFoo view".to_owned(),
             }],
             query: "FormulaFoo".to_owned(),
+            outputs: vec![],
         }), @r###"
         Diagnostics
         error[E0011]: Invalid syntax
@@ -714,6 +716,7 @@ mod tests {
                 },
             ],
             query: "FormulaBar".to_owned(),
+            outputs: vec![],
         }), @"Ordered: FormulaBar");
     }
 
@@ -733,6 +736,7 @@ mod tests {
                 },
             ],
             query: "FormulaFoo".to_owned(),
+            outputs: vec![],
         }), @r###"
         Diagnostics
         error[E0004]: Formula already defined
@@ -750,6 +754,7 @@ mod tests {
                 source_location: "FormulaFoo view".to_owned(),
             }],
             query: "FormulaBar".to_owned(),
+            outputs: vec![],
         }), @r###"
         Diagnostics
         error[E0003]: Illegal identifier
@@ -767,6 +772,7 @@ mod tests {
                 source_location: "FormulaFoo view".to_owned(),
             }],
             query: "foo".to_owned(),
+            outputs: vec![],
         }), @r###"
         Diagnostics
         warning[W2001]: Unused binding
@@ -787,6 +793,7 @@ mod tests {
                 source_location: "FormulaFoo view".to_owned(),
             }],
             query: "foo".to_owned(),
+            outputs: vec![],
         }), @"Ordered: foo");
     }
 }
@@ -8,6 +8,7 @@ use smallvec::smallvec;
 use sparrow_api::kaskada::v1alpha::{slice_plan, SlicePlan};
 use sparrow_api::kaskada::v1alpha::{slice_request, SliceRequest};
 use sparrow_core::debug_println;
+use sparrow_plan::GroupId;
 
 use crate::dfg::{ChildrenVec, Dfg, DfgExpr, Operation, StepKind};
 use crate::DataContext;
@@ -18,13 +19,16 @@ const DEBUG_SLICE_ANALYSIS: bool = false;
 pub(super) fn rewrite_slices(
     original: DfgExpr,
     slice_request: &Option<SliceRequest>,
+    data_context: &DataContext,
+    primary_grouping: Option<GroupId>,
 ) -> anyhow::Result<DfgExpr> {
     debug_println!(DEBUG_SLICE_ANALYSIS, "Slicing:\n{}", original.dot_string()?);
 
     // The last node in the expression is the "result".
     let original_output_id: Id = (original.len() - 1).into();
 
-    let mut rewriter = SliceRewriter::new(&original, slice_request);
+    let mut rewriter =
+        SliceRewriter::try_new(&original, slice_request, data_context, primary_grouping)?;
     rewriter.rewrite(&original, original_output_id, 0)?;
     rewriter.finish(original_output_id)
 }
@@ -42,53 +46,160 @@ pub(super) fn slice_plans(
                 .context("missing table for ID")?
                 .name()
                 .to_owned();
-            let slice = slice.clone();
+
+            // Slices whose entities are determined by the rows of a table are
+            // applied as tables are read. The scanned table and the table
+            // determining the entities are both prepared in full.
+            if let Some(entity_table) = slice.as_ref().and_then(|slice| slice.entity_table()) {
+                slice_plans.push(SlicePlan {
+                    table_name: entity_table.to_owned(),
+                    slice: None,
+                });
+            }
+            let slice = slice_plan::Slice::prepared_slice(slice);
             slice_plans.push(SlicePlan { table_name, slice })
         }
     }
 
     // Sort so we have a deterministic ordering.
     slice_plans.sort();
+    slice_plans.dedup();
     Ok(slice_plans)
 }
 
-struct SliceRewriter {
+/// Return the slice plan for a requested slice.
+fn requested_slice_plan(slice: &slice_request::Slice) -> slice_plan::Slice {
+    match slice {
+        slice_request::Slice::Percent(slice_request::PercentSlice { percent }) => {
+            slice_plan::Slice::Percent(slice_plan::PercentSlice { percent: *percent })
+        }
+        slice_request::Slice::EntityKeys(e) => {
+            slice_plan::Slice::EntityKeys(slice_plan::EntityKeysSlice {
+                entity_keys: e.entity_keys.clone(),
+            })
+        }
+        slice_request::Slice::Sample(slice_request::SampleSlice { percent, seed }) => {
+            slice_plan::Slice::Sample(slice_plan::SampleSlice {
+                percent: *percent,
+                seed: *seed,
+            })
+        }
+        slice_request::Slice::Stratified(stratified) => {
+            slice_plan::Slice::Stratified(slice_plan::StratifiedSlice {
+                table_name: stratified.table_name.clone(),
+                column: stratified.column.clone(),
+                strata: stratified
+                    .strata
+                    .iter()
+                    .map(|stratum| slice_plan::stratified_slice::Stratum {
+                        value: stratum.value.clone(),
+                        percent: stratum.percent,
+                    })
+                    .collect(),
+                default_percent: stratified.default_percent,
+                seed: stratified.seed,
+            })
+        }
+        slice_request::Slice::Dimension(dimension) => {
+            // Sort the values so equivalent requests share a prepared slice.
+            let mut values = dimension.values.clone();
+            values.sort();
+            values.dedup();
+            slice_plan::Slice::Dimension(slice_plan::DimensionSlice {
+                table_name: dimension.table_name.clone(),
+                column: dimension.column.clone(),
+                values,
+            })
+        }
+    }
+}
+
+/// Return the grouping of the table determining the entities in a slice.
+fn entity_table_grouping(
+    data_context: &DataContext,
+    table_name: &str,
+    column: &str,
+    primary_grouping: Option<GroupId>,
+) -> anyhow::Result<GroupId> {
+    let table_info = data_context
+        .table_id(table_name)
+        .and_then(|table_id| data_context.table_info(table_id))
+        .with_context(|| format!("Slice table '{table_name}' not found"))?;
+    anyhow::ensure!(
+        table_info.schema().field_with_name(column).is_ok(),
+        "Slice column '{column}' not found in table '{table_name}'"
+    );
+    if let Some(primary_grouping) = primary_grouping {
+        anyhow::ensure!(
+            table_info.group_id() == primary_grouping,
+            "Slice table '{table_name}' must have the same grouping as the query"
+        );
+    }
+    Ok(table_info.group_id())
+}
+
+struct SliceRewriter<'a> {
+    data_context: &'a DataContext,
     primary_slice: Option<slice_plan::Slice>,
     foreign_slice: Option<slice_plan::Slice>,
+    /// The grouping whose entities are sliced, for slices applying to every
+    /// table of a grouping.
+    ///
+    /// These slices apply to primary and foreign tables alike, so a lookup
+    /// within the grouping sees the same entities as the primary tables.
+    /// Tables of other groupings are read in full.
+    sliced_grouping: Option<GroupId>,
     /// Map from `(original_id, lookup_count) -> rewritten_id`.
     rewritten_ids: HashMap<(Id, usize), Id>,
     /// The (mutable) DFG being produced.
     rewritten_dfg: Dfg,
 }
 
-impl SliceRewriter {
-    fn new(original: &DfgExpr, slice_request: &Option<SliceRequest>) -> Self {
+impl<'a> SliceRewriter<'a> {
+    fn try_new(
+        original: &DfgExpr,
+        slice_request: &Option<SliceRequest>,
+        data_context: &'a DataContext,
+        primary_grouping: Option<GroupId>,
+    ) -> anyhow::Result<Self> {
         // Determine the slice needed for the primary tables.
         // Currently, we don't push any predicates or projections down, so this
         // is computed directly from the slice request. This is likely to evolve,
         // and may (at some point) be per-table.
-        let primary_slice = slice_request.as_ref().and_then(|request| {
-            request.slice.as_ref().map(|slice| match slice {
-                slice_request::Slice::Percent(slice_request::PercentSlice { percent }) => {
-                    slice_plan::Slice::Percent(slice_plan::PercentSlice { percent: *percent })
-                }
-                slice_request::Slice::EntityKeys(e) => {
-                    slice_plan::Slice::EntityKeys(slice_plan::EntityKeysSlice {
-                        entity_keys: e.entity_keys.clone(),
-                    })
-                }
-            })
-        });
+        let primary_slice = slice_request
+            .as_ref()
+            .and_then(|request| request.slice.as_ref().map(requested_slice_plan));
 
         let foreign_slice = None;
 
+        // Sampled, stratified and dimension slices select entities of a
+        // specific grouping, and are applied to every table of the grouping.
+        let sliced_grouping = match &primary_slice {
+            Some(slice_plan::Slice::Sample(_)) => primary_grouping,
+            Some(slice_plan::Slice::Stratified(stratified)) => Some(entity_table_grouping(
+                data_context,
+                &stratified.table_name,
+                &stratified.column,
+                primary_grouping,
+            )?),
+            Some(slice_plan::Slice::Dimension(dimension)) => Some(entity_table_grouping(
+                data_context,
+                &dimension.table_name,
+                &dimension.column,
+                primary_grouping,
+            )?),
+            _ => None,
+        };
+
         let rewritten_ids = HashMap::with_capacity(original.len());
-        Self {
+        Ok(Self {
+            data_context,
             primary_slice,
             foreign_slice,
+            sliced_grouping,
             rewritten_ids,
             rewritten_dfg: Dfg::default(),
-        }
+        })
     }
 
     /// Return the ID of a rewritten node.
@@ -114,7 +225,17 @@ impl SliceRewriter {
 
         let rewritten_id = match kind {
             StepKind::Operation(Operation::Scan { table_id, .. }) => {
-                let slice_plan = if lookup_count == 0 {
+                let slice_plan = if let Some(sliced_grouping) = self.sliced_grouping {
+                    let table_info = self
+                        .data_context
+                        .table_info(*table_id)
+                        .context("missing table for ID")?;
+                    if table_info.group_id() == sliced_grouping {
+                        self.primary_slice.clone()
+                    } else {
+                        None
+                    }
+                } else if lookup_count == 0 {
                     self.primary_slice.clone()
                 } else {
                     self.foreign_slice.clone()
@@ -236,6 +357,7 @@ mod tests {
         let table_a_uuid = Uuid::parse_str("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA").unwrap();
         let table_b_uuid = Uuid::parse_str("BBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBB").unwrap();
         let table_c_uuid = Uuid::parse_str("CCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCC").unwrap();
+        let table_d_uuid = Uuid::parse_str("DDDDDDDDDDDDDDDDDDDDDDDDDDDDDDDD").unwrap();
 
         // Register tables (all have the same schema with a single `x` value).
         let schema = Schema::new(vec![
@@ -288,6 +410,25 @@ mod tests {
                     "key",
                     "grouping",
                 )),
+                metadata: Some(TableMetadata {
+                    schema: Some(schema.clone()),
+                    file_count: 0,
+                }),
+                file_sets: vec![],
+            })
+            .unwrap();
+
+        // Table `D` has a different grouping from the other tables.
+        data_context
+            .add_table(ComputeTable {
+                config: Some(TableConfig::new_with_table_source(
+                    "D",
+                    &table_d_uuid,
+                    "",
+                    Some(""),
+                    "key",
+                    "other_grouping",
+                )),
                 metadata: Some(TableMetadata {
                     schema: Some(schema),
                     file_count: 0,
@@ -307,6 +448,7 @@ mod tests {
         let feature_set = FeatureSet {
             formulas: vec![],
             query: expr.to_string(),
+            outputs: vec![],
         };
 
         let options = CompilerOptions {
@@ -343,6 +485,7 @@ mod tests {
         let feature_set = FeatureSet {
             formulas: vec![],
             query: expr.to_string(),
+            outputs: vec![],
         };
 
         let options = CompilerOptions {
@@ -413,6 +556,128 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_slice_plans_sample_lookup() {
+        // The lookup target `B` has the same grouping as `A`, so it is sampled
+        // consistently. The lookup target `D` has a different grouping.
+        let plans = apply_slice_analysis_get_plans(
+            "lookup(A.x, sum(B.x)) + lookup(A.x, sum(D.x))",
+            Some(SliceRequest {
+                slice: Some(slice_request::Slice::Sample(slice_request::SampleSlice {
+                    percent: 10.0,
+                    seed: 42,
+                })),
+            }),
+        );
+        insta::assert_yaml_snapshot!(plans, @r###"
+        ---
+        - table_name: A
+          slice:
+            Sample:
+              percent: 10.0
+              seed: 42
+        - table_name: B
+          slice:
+            Sample:
+              percent: 10.0
+              seed: 42
+        - table_name: D
+          slice: ~
+        "###);
+    }
+
+    #[test]
+    fn test_slice_stratified_lookup() {
+        // Every scan of the grouping is stratified, including the lookup target.
+        let slice = SliceRequest {
+            slice: Some(slice_request::Slice::Stratified(
+                slice_request::StratifiedSlice {
+                    table_name: "C".to_owned(),
+                    column: "x".to_owned(),
+                    strata: vec![slice_request::stratified_slice::Stratum {
+                        value: "1".to_owned(),
+                        percent: 100.0,
+                    }],
+                    default_percent: 10.0,
+                    seed: 7,
+                },
+            )),
+        };
+        let plans = apply_slice_analysis_get_plans("lookup(A.x, sum(B.x))", Some(slice.clone()));
+        insta::assert_yaml_snapshot!(plans, @r###"
+        ---
+        - table_name: A
+          slice: ~
+        - table_name: B
+          slice: ~
+        - table_name: C
+          slice: ~
+        "###);
+
+        // The tables are prepared in full, but the scans are stratified.
+        let expr = apply_slice_analysis_rewrite_expr("lookup(A.x, sum(B.x))", Some(slice));
+        assert!(expr.contains("scan:aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa:Stratified("));
+        assert!(expr.contains("scan:bbbbbbbb-bbbb-bbbb-bbbb-bbbbbbbbbbbb:Stratified("));
+    }
+
+    #[test]
+    fn test_slice_plans_dimension() {
+        let plans = apply_slice_analysis_get_plans(
+            "A.x",
+            Some(SliceRequest {
+                slice: Some(slice_request::Slice::Dimension(
+                    slice_request::DimensionSlice {
+                        table_name: "C".to_owned(),
+                        column: "x".to_owned(),
+                        values: vec!["5".to_owned(), "1".to_owned(), "5".to_owned()],
+                    },
+                )),
+            }),
+        );
+        // Both the scanned table and the dimension table are prepared in full.
+        insta::assert_yaml_snapshot!(plans, @r###"
+        ---
+        - table_name: A
+          slice: ~
+        - table_name: C
+          slice: ~
+        "###);
+    }
+
+    #[test]
+    fn test_slice_dimension_other_grouping() {
+        let slice = SliceRequest {
+            slice: Some(slice_request::Slice::Dimension(
+                slice_request::DimensionSlice {
+                    table_name: "D".to_owned(),
+                    column: "x".to_owned(),
+                    values: vec!["1".to_owned()],
+                },
+            )),
+        };
+        let options = CompilerOptions {
+            slice_request: Some(slice),
+            ..CompilerOptions::default()
+        };
+        let feature_set = FeatureSet {
+            formulas: vec![],
+            query: "A.x".to_owned(),
+            outputs: vec![],
+        };
+        let error = FrontendOutput::try_compile(
+            &mut data_context(),
+            &feature_set,
+            &options,
+            ExpressionKind::Formula,
+        )
+        .err()
+        .expect("slice table with a different grouping");
+        assert_eq!(
+            error.to_string(),
+            "Slice table 'D' must have the same grouping as the query"
+        );
+    }
+
     #[test]
     fn test_slice_plans_self_lookup_with_slicing() {
         let plans = apply_slice_analysis_get_plans(
@@ -11,10 +11,30 @@ pub(super) fn register(registry: &mut Registry) {
         .with_is_new(Implementation::new_pattern("?condition_value"));
 
     registry
-        .register("sliding(const duration: i64, condition: bool) -> window")
+        .register("sliding(const duration: any, condition: bool = null) -> window")
         .with_implementation(Implementation::Window(WindowBehavior::Sliding))
         .with_is_new(Implementation::new_pattern("?condition_value"));
 
+    registry
+        .register("session(const gap: timedelta) -> window")
+        .with_implementation(Implementation::Window(WindowBehavior::Session));
+
+    registry
+        .register("session_start(input: any, const gap: timedelta) -> timestamp_ns")
+        .with_implementation(Implementation::new_fenl_rewrite(
+            "first(time_of(input), window = session(gap))",
+        ));
+
+    // A new session starts with the first input, or an input more than `gap`
+    // after the previous input. Windowing the count of session starts counts
+    // the sessions starting within the window.
+    registry
+        .register("session_id(input: any, const gap: timedelta, window: window = null) -> u32")
+        .with_implementation(Implementation::new_fenl_rewrite(
+            "count_if(not(is_valid(lag(1, time_of(input)))) \
+             or time_of(input) > add_time(gap, lag(1, time_of(input))), window = window)",
+        ));
+
     registry
         .register("minutely() -> bool")
         .with_implementation(Implementation::Tick(TickBehavior::Minutely))
@@ -51,4 +71,11 @@ pub(super) fn register(registry: &mut Registry) {
         .with_is_new(Implementation::Tick(TickBehavior::Finished))
         .with_time_domain_check(TimeDomainCheck::Compatible)
         .set_internal();
+
+    registry
+        .register("examples() -> bool")
+        .with_implementation(Implementation::Tick(TickBehavior::Examples))
+        .with_is_new(Implementation::Tick(TickBehavior::Examples))
+        .with_time_domain_check(TimeDomainCheck::Compatible)
+        .set_internal();
 }
@@ -42,6 +42,7 @@ mod functions;
 mod nearest_matches;
 mod options;
 mod plan;
+mod state_reuse;
 mod time_domain;
 mod types;
 
@@ -54,3 +55,4 @@ pub use error::*;
 pub use frontend::*;
 pub use functions::*;
 pub use options::*;
+pub use state_reuse::*;
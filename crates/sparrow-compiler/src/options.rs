@@ -5,6 +5,8 @@ use clap::command;
 use sparrow_api::kaskada::v1alpha::PerEntityBehavior;
 use sparrow_api::kaskada::v1alpha::SliceRequest;
 
+use crate::LintOptions;
+
 #[derive(clap::Args, Debug, Clone)]
 #[command(rename_all = "kebab-case")]
 pub struct InternalCompileOptions {
@@ -72,6 +74,10 @@ pub struct CompilerOptions {
     /// Whether experimental behaviors should be enabled.
     #[arg(long, action)]
     pub experimental: bool,
+
+    /// The severity lints should be reported at.
+    #[arg(skip)]
+    pub lints: LintOptions,
 }
 
 const DEFAULT_PER_ENTITY_BEHAVIOR: PerEntityBehavior = PerEntityBehavior::All;
@@ -99,6 +105,7 @@ impl Default for CompilerOptions {
             per_entity_behavior: DEFAULT_PER_ENTITY_BEHAVIOR,
             slice_request: None,
             experimental: false,
+            lints: LintOptions::default(),
         }
     }
 }
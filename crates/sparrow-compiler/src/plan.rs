@@ -1,5 +1,8 @@
+use anyhow::Context;
 use arrow::datatypes::DataType;
-use sparrow_api::kaskada::v1alpha::{ComputePlan, PerEntityBehavior};
+use sparrow_api::kaskada::v1alpha::{
+    ComputePlan, OperationPlan, PerEntityBehavior, PlanOutput, PlanStatistics,
+};
 use sparrow_core::debug_println;
 
 use crate::dfg::{DfgExpr, Operation, StepKind};
@@ -12,6 +15,8 @@ mod interpolations;
 mod operation_schedule;
 mod operation_to_plan;
 mod plan_builder;
+mod prune_operations;
+mod scan_filter_pushdown;
 mod transform_to_plan;
 
 /// A constant to easily enable debug prints int he plan code.
@@ -19,8 +24,23 @@ mod transform_to_plan;
 /// This will be compiled away when `false`.
 const DBG_PRINT_PLAN: bool = false;
 
+/// A named output to include in the plan.
+pub(super) struct OutputExpr {
+    pub name: String,
+    /// The DFG producing the output, compiled separately from the query.
+    pub expr: DfgExpr,
+    pub per_entity_behavior: PerEntityBehavior,
+    pub grouping: String,
+    pub grouping_key_type: DataType,
+}
+
 /// Extracts a `ComputePlan` proto from a `DfgExpr`.
 ///
+/// The operations of each of the `outputs` are added to the same plan, and
+/// identical operations (such as scans of the same table) are shared.
+///
+/// Also returns statistics about the operations pruned from the plan.
+///
 /// TODO: The `DataContext` is used to get the table name from an ID, which is
 /// only necessary to create the `slice_plan` because it uses a name instead of
 /// an ID.
@@ -30,13 +50,51 @@ pub(super) fn extract_plan_proto(
     per_entity_behavior: PerEntityBehavior,
     primary_grouping: String,
     primary_grouping_key_type: &DataType,
-) -> anyhow::Result<ComputePlan> {
+    outputs: Vec<OutputExpr>,
+) -> anyhow::Result<(ComputePlan, PlanStatistics)> {
+    // The operations of the outputs are added before those of the query, so
+    // the last operation produces the result of the query.
+    let mut operations = Vec::new();
+    let mut plan_outputs = Vec::with_capacity(outputs.len());
+    for output in outputs {
+        let output_operations = extract_operations(data_context, &output.expr)
+            .with_context(|| format!("extracting operations for output '{}'", output.name))?;
+        plan_builder::append_operations(&mut operations, output_operations)?;
+        plan_outputs.push(PlanOutput {
+            name: output.name,
+            operation: operations.len() as u32 - 1,
+            per_entity_behavior: output.per_entity_behavior as i32,
+            grouping: output.grouping,
+            grouping_key_type: Some((&output.grouping_key_type).try_into()?),
+        });
+    }
+    plan_builder::append_operations(&mut operations, extract_operations(data_context, &expr)?)?;
+
+    let primary_grouping_key_type = primary_grouping_key_type.try_into()?;
+    plan_builder::finish_plan(
+        operations,
+        plan_outputs,
+        data_context,
+        per_entity_behavior,
+        primary_grouping,
+        primary_grouping_key_type,
+    )
+}
+
+/// Extracts the operations producing the result of a `DfgExpr`.
+///
+/// The result is the last expression of the last operation. The operations
+/// use absolute indices, and have not been pruned.
+fn extract_operations(
+    data_context: &DataContext,
+    expr: &DfgExpr,
+) -> anyhow::Result<Vec<OperationPlan>> {
     // TODO: Projection pushdown?
     // TODO: Slice analysis?
 
-    let mut transform_to_plan = transform_to_plan::TransformToPlan::new(&expr);
+    let mut transform_to_plan = transform_to_plan::TransformToPlan::new(expr);
 
-    let mut plan_builder = PlanBuilder::try_new(&expr)?;
+    let mut plan_builder = PlanBuilder::try_new(expr)?;
 
     for id in expr.ids() {
         let (kind, children) = expr.node(id);
@@ -79,7 +137,7 @@ pub(super) fn extract_plan_proto(
                     id,
                     operation,
                     children,
-                    &expr,
+                    expr,
                 )?;
                 // Add the generated operator to the transform infor.
                 transform_to_plan.register_operation(operation_index, operation.operator()?)?;
@@ -120,13 +178,7 @@ pub(super) fn extract_plan_proto(
     }
 
     let output_id = egg::Id::from(expr.len() - 1);
-    let primary_grouping_key_type = primary_grouping_key_type.try_into()?;
-    plan_builder.finish(
-        per_entity_behavior,
-        output_id,
-        primary_grouping,
-        primary_grouping_key_type,
-    )
+    plan_builder.into_operations(output_id)
 }
 
 #[cfg(test)]
@@ -226,6 +278,9 @@ mod tests {
                 expression_kind: ExpressionKind::Complete as i32,
                 experimental: false,
                 per_entity_behavior: per_entity_behavior as i32,
+                previous_plan: None,
+                explain: false,
+                lint_severities: vec![],
             },
             InternalCompileOptions {
                 store_final_dfg: Some(test_output_dir.join(format!("{name}_final_dfg.dot"))),
@@ -60,6 +60,8 @@ pub(super) fn dfg_to_plan<'plan>(
                     table_id: Some(table_id.as_proto()),
                     schema: Some(schema),
                     slice_plan: Some(slice_plan),
+                    // Populated by `push_down_scan_filters` once the plan is complete.
+                    filter: None,
                 }),
             )?;
 
@@ -3,13 +3,16 @@ use itertools::Itertools;
 use sparrow_api::kaskada::v1alpha::DataType;
 use sparrow_api::kaskada::v1alpha::{
     expression_plan, operation_input_ref, operation_plan, ComputePlan, ExpressionPlan,
-    OperationPlan, PerEntityBehavior,
+    OperationPlan, PerEntityBehavior, PlanOutput, PlanStatistics,
 };
 
 use crate::dfg::DfgExpr;
 use crate::plan::finalize_expression_indices::finalize_expression_indices;
 use crate::plan::interpolations::Interpolations;
 use crate::plan::operation_schedule::OperationSchedule;
+use crate::plan::prune_operations::{for_each_input_op, for_each_input_ref, prune_operations};
+use crate::plan::scan_filter_pushdown::push_down_scan_filters;
+use crate::DataContext;
 
 /// A wrapper around the operation protos being built.
 ///
@@ -255,13 +258,11 @@ impl PlanBuilder {
         Ok(expression_index)
     }
 
-    pub fn finish(
-        mut self,
-        per_entity_behavior: PerEntityBehavior,
-        output_id: egg::Id,
-        primary_grouping: String,
-        primary_grouping_key_type: DataType,
-    ) -> anyhow::Result<ComputePlan> {
+    /// Return the operations of the plan.
+    ///
+    /// The operations still use absolute indices, so they may be combined with
+    /// the operations of other outputs before the plan is finished.
+    pub fn into_operations(mut self, output_id: egg::Id) -> anyhow::Result<Vec<OperationPlan>> {
         // Make sure the expression for the output is exported, and is
         // the last expression in the last operation. Anything computed
         // *after* the output is ready would be wasted. Execution also
@@ -289,21 +290,82 @@ impl PlanBuilder {
             "Output should be last expression in last operation"
         );
 
-        // During construction, only the absolute operation and expression indices
-        // were populated. Finalize the input references by setting the relative
-        // indices. This populates the `producing_expression_output_index` to the
-        // index within only the output expressions, and `operation_input_index`
-        // to the index of the producing operation within the inputs to the
-        // consuming operation.
-        let operations = finalize_expression_indices(self.operation_plans)?;
-
-        Ok(ComputePlan {
-            per_entity_behavior: per_entity_behavior as i32,
-            operations,
-            primary_grouping,
-            primary_grouping_key_type: Some(primary_grouping_key_type),
-        })
+        Ok(self.operation_plans)
+    }
+}
+
+/// Append the `added` operations after the existing `operations`.
+///
+/// The (absolute) operation indices referenced by the added operations are
+/// shifted past the existing operations.
+pub(super) fn append_operations(
+    operations: &mut Vec<OperationPlan>,
+    added: Vec<OperationPlan>,
+) -> anyhow::Result<()> {
+    let offset = operations.len() as u32;
+    for mut operation in added {
+        for_each_input_op(&mut operation, |input| {
+            *input += offset;
+            Ok(())
+        })?;
+        for_each_input_ref(&mut operation, |input| {
+            input.producing_operation += offset;
+            Ok(())
+        })?;
+        operations.push(operation);
     }
+    Ok(())
+}
+
+/// Create the plan from the operations built for the query and outputs.
+///
+/// The last operation produces the result of the query, and each of the
+/// `outputs` references the operation producing it.
+pub(super) fn finish_plan(
+    mut operations: Vec<OperationPlan>,
+    mut outputs: Vec<PlanOutput>,
+    data_context: &DataContext,
+    per_entity_behavior: PerEntityBehavior,
+    primary_grouping: String,
+    primary_grouping_key_type: DataType,
+) -> anyhow::Result<(ComputePlan, PlanStatistics)> {
+    // Remove redundant operations while the absolute indices are available.
+    // The last operation and the output operations are never removed, and
+    // their expressions aren't changed, so each output remains the only
+    // exported expression of its operation.
+    let num_operations_before_pruning = operations.len() as u32;
+    let mut output_operations: Vec<u32> = outputs.iter().map(|output| output.operation).collect();
+    prune_operations(&mut operations, &mut output_operations, data_context)?;
+    for (output, operation) in outputs.iter_mut().zip(output_operations) {
+        output.operation = operation;
+    }
+
+    // During construction, only the absolute operation and expression indices
+    // were populated. Finalize the input references by setting the relative
+    // indices. This populates the `producing_expression_output_index` to the
+    // index within only the output expressions, and `operation_input_index`
+    // to the index of the producing operation within the inputs to the
+    // consuming operation.
+    let mut operations = finalize_expression_indices(operations)?;
+
+    // Filter rows within scans when possible, so they aren't sent to
+    // later operations.
+    push_down_scan_filters(&mut operations)?;
+
+    let statistics = PlanStatistics {
+        num_operations_before_pruning,
+        num_operations: operations.len() as u32,
+    };
+    let plan = ComputePlan {
+        per_entity_behavior: per_entity_behavior as i32,
+        operations,
+        primary_grouping,
+        primary_grouping_key_type: Some(primary_grouping_key_type),
+        // Determined by the frontend analysis of the query.
+        max_lookback_ns: None,
+        outputs,
+    };
+    Ok((plan, statistics))
 }
 
 enum ValueRef {
@@ -0,0 +1,830 @@
+use anyhow::Context;
+use sparrow_api::kaskada::v1alpha::operation_input_ref::Column;
+use sparrow_api::kaskada::v1alpha::operation_plan::{ScanOperation, SelectOperation};
+use sparrow_api::kaskada::v1alpha::{
+    data_type, expression_plan, operation_plan, DataType, ExpressionPlan, OperationInputRef,
+    OperationPlan,
+};
+use sparrow_plan::TableId;
+
+use crate::DataContext;
+
+/// Prune redundant operations from the plan.
+///
+/// The operations are created directly from the DFG, so each reference to a
+/// table in a different domain may produce separate scans, merges and
+/// selects. This applies the following rewrites until none apply:
+///
+/// 1. The inputs to each merge are ordered so the input with more (estimated)
+///    rows is on the left. Estimates are based on the prepared files for the
+///    slice of each scan.
+/// 2. Identical operations (such as two scans of the same table slice) are
+///    combined into one, with the expressions of both.
+/// 3. A `select` reading from a `select` which only passes values through
+///    is collapsed into a single `select` on the conjunction of the
+///    conditions.
+/// 4. Operations which are not consumed are removed.
+///
+/// The last operation produces the output, and is never combined or removed.
+/// The same applies to the `outputs`, which are the operations producing
+/// the named outputs of the plan. Their indices are updated as earlier
+/// operations are removed.
+///
+/// This must run before the expression indices have been finalized, since it
+/// relies on the absolute index of each expression.
+pub(super) fn prune_operations(
+    operations: &mut Vec<OperationPlan>,
+    outputs: &mut [u32],
+    data_context: &DataContext,
+) -> anyhow::Result<()> {
+    loop {
+        order_merge_inputs(operations, data_context)?;
+
+        let consumers = consumers(operations)?;
+        if let Some((into, from)) = find_duplicate(operations, &consumers, outputs) {
+            combine_operations(operations, into, from, outputs)?;
+        } else if let Some((outer, inner)) = find_select_chain(operations, &consumers, outputs)? {
+            collapse_selects(operations, outer, inner)?;
+        } else if let Some(unused) = find_unused(&consumers, outputs) {
+            remove_operation(operations, unused, outputs)?;
+        } else {
+            return Ok(());
+        }
+    }
+}
+
+/// Put the input with more estimated rows on the left of each merge.
+fn order_merge_inputs(
+    operations: &mut [OperationPlan],
+    data_context: &DataContext,
+) -> anyhow::Result<()> {
+    let rows = estimate_rows(operations, data_context)?;
+    for operation in operations.iter_mut() {
+        if let Some(operation_plan::Operator::Merge(merge)) = &mut operation.operator {
+            if rows[merge.left as usize] < rows[merge.right as usize] {
+                std::mem::swap(&mut merge.left, &mut merge.right);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Estimate the number of rows produced by each operation.
+///
+/// Scans produce the rows in the prepared files for their slice. Other
+/// operations are estimated to produce as many rows as their inputs.
+fn estimate_rows(
+    operations: &[OperationPlan],
+    data_context: &DataContext,
+) -> anyhow::Result<Vec<i64>> {
+    let mut rows: Vec<i64> = Vec::with_capacity(operations.len());
+    for (index, operation) in operations.iter().enumerate() {
+        let estimate = match operation.operator()? {
+            operation_plan::Operator::Scan(scan) => scan_rows(scan, data_context),
+            operator => {
+                let mut estimate = 0;
+                for input in operator.input_ops_iter() {
+                    estimate += rows.get(input as usize).with_context(|| {
+                        format!("Operation {index} references later operation {input}")
+                    })?;
+                }
+                estimate
+            }
+        };
+        rows.push(estimate);
+    }
+    Ok(rows)
+}
+
+fn scan_rows(scan: &ScanOperation, data_context: &DataContext) -> i64 {
+    let Some(table_info) = scan
+        .table_id
+        .as_ref()
+        .and_then(|table_id| data_context.table_info(TableId::new(table_id.into())))
+    else {
+        return 0;
+    };
+
+    let slice = scan
+        .slice_plan
+        .as_ref()
+        .and_then(|slice_plan| slice_plan.slice.clone());
+    match table_info.prepared_files_for_slice(&slice) {
+        Ok(prepared_files) => prepared_files.iter().map(|file| file.num_rows).sum(),
+        // The table may not have been prepared with the slice yet.
+        Err(_) => table_info.num_rows(),
+    }
+}
+
+/// Return the operations consuming the output of each operation.
+fn consumers(operations: &[OperationPlan]) -> anyhow::Result<Vec<Vec<usize>>> {
+    let mut consumers = vec![Vec::new(); operations.len()];
+    for (index, operation) in operations.iter().enumerate() {
+        let operator = operation.operator()?;
+
+        let mut inputs: Vec<u32> = operator.input_ops_iter().collect();
+        for input_index in 0..operator.operation_input_ref_len() {
+            inputs.push(
+                operator
+                    .operation_input_ref(input_index)?
+                    .producing_operation,
+            );
+        }
+        for expression in &operation.expressions {
+            if let Some(expression_plan::Operator::Input(input)) = &expression.operator {
+                // Scans reference their own records, which isn't a consumer.
+                if input.producing_operation as usize != index {
+                    inputs.push(input.producing_operation);
+                }
+            }
+        }
+
+        inputs.sort_unstable();
+        inputs.dedup();
+        for input in inputs {
+            consumers
+                .get_mut(input as usize)
+                .with_context(|| format!("Operation {index} references invalid operation {input}"))?
+                .push(index);
+        }
+    }
+    Ok(consumers)
+}
+
+/// Find an operation (`from`) identical to an earlier operation (`into`).
+fn find_duplicate(
+    operations: &[OperationPlan],
+    consumers: &[Vec<usize>],
+    outputs: &[u32],
+) -> Option<(usize, usize)> {
+    let last = operations.len().saturating_sub(1);
+    for from in 1..last {
+        if is_output(outputs, from) {
+            continue;
+        }
+        for into in 0..from {
+            if operations[into].operator != operations[from].operator || is_output(outputs, into) {
+                continue;
+            }
+
+            // Each input to an operation is received separately, so an
+            // operation consuming both would receive the same rows twice.
+            if consumers[from]
+                .iter()
+                .any(|consumer| consumers[into].contains(consumer))
+            {
+                continue;
+            }
+
+            // The expressions are moved to `into`, so they may only reference
+            // operations before it.
+            let references_between = operations[from].expressions.iter().any(|expression| {
+                matches!(&expression.operator, Some(expression_plan::Operator::Input(input))
+                    if input.producing_operation as usize >= into
+                        && input.producing_operation as usize != from)
+            });
+            if references_between {
+                continue;
+            }
+
+            return Some((into, from));
+        }
+    }
+    None
+}
+
+/// Combine the operation `from` into the identical operation `into`.
+///
+/// The expressions of `from` are added to `into` (re-using identical
+/// expressions) and the consumers of `from` are redirected to `into`.
+fn combine_operations(
+    operations: &mut Vec<OperationPlan>,
+    into: usize,
+    from: usize,
+    outputs: &mut [u32],
+) -> anyhow::Result<()> {
+    let from_expressions = std::mem::take(&mut operations[from].expressions);
+
+    // The index within `into` of each expression in `from`.
+    let mut expression_map: Vec<u32> = Vec::with_capacity(from_expressions.len());
+    for (index, mut expression) in from_expressions.into_iter().enumerate() {
+        for argument in expression.arguments.iter_mut() {
+            let referenced = *argument;
+            *argument = *expression_map.get(referenced as usize).with_context(|| {
+                format!("Expression {from}.{index} references later expression {referenced}")
+            })?;
+        }
+        if let Some(expression_plan::Operator::Input(input)) = &mut expression.operator {
+            redirect_input_ref(input, from, into, &expression_map)?;
+        }
+
+        let expressions = &mut operations[into].expressions;
+        let into_index = if let Some(existing) = expressions
+            .iter()
+            .position(|existing| same_expression(existing, &expression))
+        {
+            expressions[existing].output |= expression.output;
+            existing
+        } else {
+            expressions.push(expression);
+            expressions.len() - 1
+        };
+        expression_map.push(into_index as u32);
+    }
+
+    // Only later operations may consume `from`.
+    for operation in operations[from + 1..].iter_mut() {
+        for_each_input_op(operation, |input| {
+            if *input as usize == from {
+                *input = into as u32;
+            }
+            Ok(())
+        })?;
+        for_each_input_ref(operation, |input| {
+            redirect_input_ref(input, from, into, &expression_map)
+        })?;
+    }
+
+    remove_operation(operations, from, outputs)
+}
+
+fn redirect_input_ref(
+    input: &mut OperationInputRef,
+    from: usize,
+    into: usize,
+    expression_map: &[u32],
+) -> anyhow::Result<()> {
+    if input.producing_operation as usize != from {
+        return Ok(());
+    }
+
+    input.producing_operation = into as u32;
+    if let Some(Column::ProducerExpression(expression)) = &mut input.column {
+        let referenced = *expression;
+        *expression = *expression_map
+            .get(referenced as usize)
+            .with_context(|| format!("Reference to invalid expression {from}.{referenced}"))?;
+    }
+    Ok(())
+}
+
+/// Whether two expressions compute the same values, regardless of output.
+fn same_expression(a: &ExpressionPlan, b: &ExpressionPlan) -> bool {
+    a.operator == b.operator && a.arguments == b.arguments && a.result_type == b.result_type
+}
+
+/// Find a `select` (`outer`) reading from a `select` (`inner`) which may be
+/// collapsed into one.
+///
+/// This requires that the `outer` select is the only consumer of the `inner`
+/// select, and that the `inner` select only passes through values from its
+/// input. Both conditions must be computed in the input to the `inner`
+/// select.
+fn find_select_chain(
+    operations: &[OperationPlan],
+    consumers: &[Vec<usize>],
+    outputs: &[u32],
+) -> anyhow::Result<Option<(usize, usize)>> {
+    for (outer, operation) in operations.iter().enumerate() {
+        let Some(operation_plan::Operator::Select(outer_select)) = &operation.operator else {
+            continue;
+        };
+        let inner = outer_select.input as usize;
+        let Some(operation_plan::Operator::Select(inner_select)) = &operations[inner].operator
+        else {
+            continue;
+        };
+        if consumers[inner] != [outer] || is_output(outputs, inner) {
+            continue;
+        }
+
+        if !passes_through(&operations[inner], inner_select.input) {
+            continue;
+        }
+        if !matches!(
+            inner_select
+                .condition
+                .as_ref()
+                .and_then(|c| c.column.as_ref()),
+            Some(Column::ProducerExpression(_))
+        ) {
+            continue;
+        }
+
+        // The outer condition must be a value from the input to the inner
+        // select, so the conjunction may be computed there.
+        let outer_condition = outer_select
+            .condition
+            .as_ref()
+            .with_context(|| format!("Select operation {outer} missing condition"))?;
+        let Some(Column::ProducerExpression(outer_condition)) = &outer_condition.column else {
+            continue;
+        };
+        let outer_condition = operations[inner]
+            .expressions
+            .get(*outer_condition as usize)
+            .and_then(|expression| expression.operator.as_ref());
+        let Some(expression_plan::Operator::Input(outer_condition)) = outer_condition else {
+            continue;
+        };
+        if !matches!(outer_condition.column, Some(Column::ProducerExpression(_))) {
+            continue;
+        }
+
+        // References from the outer select must be to values or keys of the
+        // inner select, which are available from the input.
+        let mut supported = true;
+        for_each_input_ref_of(operation, |input| {
+            if input.producing_operation as usize == inner {
+                supported &= matches!(
+                    input.column,
+                    Some(Column::ProducerExpression(_) | Column::KeyColumn(_))
+                );
+            }
+        })?;
+        if supported {
+            return Ok(Some((outer, inner)));
+        }
+    }
+    Ok(None)
+}
+
+/// Whether every expression in the operation is an input from `input`.
+fn passes_through(operation: &OperationPlan, input: u32) -> bool {
+    operation.expressions.iter().all(|expression| {
+        matches!(&expression.operator, Some(expression_plan::Operator::Input(reference))
+            if reference.producing_operation == input)
+    })
+}
+
+/// Collapse the `outer` select into the `inner` select it reads from.
+///
+/// The conjunction of the conditions is added to the input of the `inner`
+/// select, and the `outer` select reads directly from that input. The
+/// `inner` select is left without consumers.
+fn collapse_selects(
+    operations: &mut [OperationPlan],
+    outer: usize,
+    inner: usize,
+) -> anyhow::Result<()> {
+    let Some(operation_plan::Operator::Select(inner_select)) = &operations[inner].operator else {
+        anyhow::bail!("Operation {inner} is not a select");
+    };
+    let input = inner_select.input;
+    let inner_condition = inner_select
+        .condition
+        .clone()
+        .with_context(|| format!("Select operation {inner} missing condition"))?;
+    let Some(Column::ProducerExpression(inner_condition)) = inner_condition.column else {
+        anyhow::bail!("Select operation {inner} condition is not an expression");
+    };
+
+    // Each reference from the outer select to the inner select is replaced
+    // with the reference the inner select passed through.
+    let inner_expressions: Vec<OperationInputRef> = operations[inner]
+        .expressions
+        .iter()
+        .map(|expression| match &expression.operator {
+            Some(expression_plan::Operator::Input(input)) => Ok(input.clone()),
+            _ => Err(anyhow::anyhow!("Expected input expression in {inner}")),
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let passthrough = |reference: &mut OperationInputRef| -> anyhow::Result<()> {
+        if reference.producing_operation as usize != inner {
+            return Ok(());
+        }
+        match reference.column {
+            Some(Column::ProducerExpression(expression)) => {
+                let interpolation = reference.interpolation;
+                *reference = inner_expressions
+                    .get(expression as usize)
+                    .with_context(|| {
+                        format!("Reference to invalid expression {inner}.{expression}")
+                    })?
+                    .clone();
+                reference.interpolation = interpolation;
+            }
+            _ => reference.producing_operation = input,
+        }
+        Ok(())
+    };
+
+    let Some(operation_plan::Operator::Select(outer_select)) = &mut operations[outer].operator
+    else {
+        anyhow::bail!("Operation {outer} is not a select");
+    };
+    let mut condition = outer_select
+        .condition
+        .clone()
+        .with_context(|| format!("Select operation {outer} missing condition"))?;
+    passthrough(&mut condition)?;
+    let Some(Column::ProducerExpression(outer_condition)) = condition.column else {
+        anyhow::bail!("Select operation {outer} condition is not an expression");
+    };
+
+    let input_expressions = &mut operations[input as usize].expressions;
+    input_expressions.push(ExpressionPlan {
+        arguments: vec![inner_condition, outer_condition],
+        result_type: Some(DataType::new_primitive(data_type::PrimitiveType::Bool)),
+        output: true,
+        operator: Some(expression_plan::Operator::Instruction(
+            "logical_and".to_owned(),
+        )),
+    });
+    condition.column = Some(Column::ProducerExpression(
+        input_expressions.len() as u32 - 1,
+    ));
+
+    let outer_operation = &mut operations[outer];
+    outer_operation.operator = Some(operation_plan::Operator::Select(SelectOperation {
+        input,
+        condition: Some(condition),
+    }));
+    for expression in outer_operation.expressions.iter_mut() {
+        if let Some(expression_plan::Operator::Input(reference)) = &mut expression.operator {
+            passthrough(reference)?;
+        }
+    }
+    Ok(())
+}
+
+/// Find an operation (other than the outputs) which is not consumed.
+fn find_unused(consumers: &[Vec<usize>], outputs: &[u32]) -> Option<usize> {
+    let last = consumers.len().saturating_sub(1);
+    (0..last).find(|index| consumers[*index].is_empty() && !is_output(outputs, *index))
+}
+
+/// Whether the operation produces one of the named outputs.
+fn is_output(outputs: &[u32], index: usize) -> bool {
+    outputs.contains(&(index as u32))
+}
+
+/// Remove an operation which is not consumed, shifting later operations.
+fn remove_operation(
+    operations: &mut Vec<OperationPlan>,
+    removed: usize,
+    outputs: &mut [u32],
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !is_output(outputs, removed),
+        "Removed operation {removed} produces an output"
+    );
+    operations.remove(removed);
+    for output in outputs.iter_mut() {
+        if *output as usize > removed {
+            *output -= 1;
+        }
+    }
+
+    let shift = |input: &mut u32| -> anyhow::Result<()> {
+        let index = *input as usize;
+        anyhow::ensure!(index != removed, "Removed operation {removed} is consumed");
+        if index > removed {
+            *input -= 1;
+        }
+        Ok(())
+    };
+    for operation in operations[removed..].iter_mut() {
+        for_each_input_op(operation, shift)?;
+        for_each_input_ref(operation, |input| shift(&mut input.producing_operation))?;
+    }
+    Ok(())
+}
+
+/// Apply `f` to the index of each input operation of the operator.
+pub(super) fn for_each_input_op(
+    operation: &mut OperationPlan,
+    mut f: impl FnMut(&mut u32) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let operator = operation.operator.as_mut().context("missing operator")?;
+    for index in 0..operator.input_len() {
+        f(operator.input_op_mut(index)?)?;
+    }
+    Ok(())
+}
+
+/// Apply `f` to each input reference of the operator and expressions.
+pub(super) fn for_each_input_ref(
+    operation: &mut OperationPlan,
+    mut f: impl FnMut(&mut OperationInputRef) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let operator = operation.operator.as_mut().context("missing operator")?;
+    for index in 0..operator.operation_input_ref_len() {
+        f(operator.operation_input_ref_mut(index)?)?;
+    }
+    for expression in operation.expressions.iter_mut() {
+        if let Some(expression_plan::Operator::Input(input)) = &mut expression.operator {
+            f(input)?;
+        }
+    }
+    Ok(())
+}
+
+/// Apply `f` to each input reference of the operator and expressions.
+fn for_each_input_ref_of(
+    operation: &OperationPlan,
+    mut f: impl FnMut(&OperationInputRef),
+) -> anyhow::Result<()> {
+    let operator = operation.operator()?;
+    for index in 0..operator.operation_input_ref_len() {
+        f(operator.operation_input_ref(index)?);
+    }
+    for expression in &operation.expressions {
+        if let Some(expression_plan::Operator::Input(input)) = &expression.operator {
+            f(input);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sparrow_api::kaskada::v1alpha::operation_input_ref::Interpolation;
+    use sparrow_api::kaskada::v1alpha::operation_plan::{MergeOperation, TickOperation};
+    use sparrow_api::kaskada::v1alpha::{
+        compute_table, schema, ComputeTable, PreparedFile, Schema, TableConfig, TableMetadata,
+    };
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn input(producing_operation: u32, column: Column) -> ExpressionPlan {
+        ExpressionPlan {
+            arguments: vec![],
+            result_type: Some(DataType::new_primitive(data_type::PrimitiveType::Bool)),
+            output: true,
+            operator: Some(expression_plan::Operator::Input(OperationInputRef {
+                producing_operation,
+                column: Some(column),
+                input_column: u32::MAX,
+                interpolation: Interpolation::Null as i32,
+            })),
+        }
+    }
+
+    fn scan(index: u32, table_id: Option<TableId>) -> OperationPlan {
+        OperationPlan {
+            operator: Some(operation_plan::Operator::Scan(ScanOperation {
+                table_id: table_id.map(|table_id| table_id.as_proto()),
+                ..ScanOperation::default()
+            })),
+            expressions: vec![input(index, Column::ScanRecord(()))],
+        }
+    }
+
+    fn operation(operator: operation_plan::Operator) -> OperationPlan {
+        OperationPlan {
+            operator: Some(operator),
+            expressions: vec![],
+        }
+    }
+
+    fn select(input: u32, condition: u32) -> SelectOperation {
+        SelectOperation {
+            input,
+            condition: Some(OperationInputRef {
+                producing_operation: input,
+                column: Some(Column::ProducerExpression(condition)),
+                input_column: u32::MAX,
+                interpolation: Interpolation::Null as i32,
+            }),
+        }
+    }
+
+    fn table(name: &str, num_rows: i64) -> ComputeTable {
+        let field = |name: &str, primitive: data_type::PrimitiveType| schema::Field {
+            name: name.to_owned(),
+            data_type: Some(DataType::new_primitive(primitive)),
+        };
+        ComputeTable {
+            config: Some(TableConfig::new_with_table_source(
+                name,
+                &Uuid::new_v4(),
+                "time",
+                Some("subsort"),
+                "entity",
+                "grouping",
+            )),
+            metadata: Some(TableMetadata {
+                schema: Some(Schema {
+                    fields: vec![
+                        field("time", data_type::PrimitiveType::TimestampNanosecond),
+                        field("subsort", data_type::PrimitiveType::U64),
+                        field("entity", data_type::PrimitiveType::String),
+                    ],
+                }),
+                file_count: 1,
+            }),
+            file_sets: vec![compute_table::FileSet {
+                slice_plan: None,
+                prepared_files: vec![PreparedFile {
+                    num_rows,
+                    ..PreparedFile::default()
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_order_merge_inputs() {
+        let mut data_context = DataContext::default();
+        let small = data_context.add_table(table("Small", 10)).unwrap();
+        let large = data_context.add_table(table("Large", 100)).unwrap();
+
+        let mut operations = vec![
+            scan(0, Some(small)),
+            scan(1, Some(large)),
+            operation(operation_plan::Operator::Merge(MergeOperation {
+                left: 0,
+                right: 1,
+            })),
+        ];
+        prune_operations(&mut operations, &mut [], &data_context).unwrap();
+
+        assert_eq!(
+            operations[2].operator,
+            Some(operation_plan::Operator::Merge(MergeOperation {
+                left: 1,
+                right: 0
+            }))
+        );
+    }
+
+    #[test]
+    fn test_order_nested_merge_inputs() {
+        let mut data_context = DataContext::default();
+        let small = data_context.add_table(table("Small", 10)).unwrap();
+        let large = data_context.add_table(table("Large", 100)).unwrap();
+        let medium = data_context.add_table(table("Medium", 50)).unwrap();
+
+        let mut operations = vec![
+            scan(0, Some(small)),
+            scan(1, Some(large)),
+            operation(operation_plan::Operator::Merge(MergeOperation {
+                left: 0,
+                right: 1,
+            })),
+            scan(3, Some(medium)),
+            operation(operation_plan::Operator::Merge(MergeOperation {
+                left: 3,
+                right: 2,
+            })),
+        ];
+        prune_operations(&mut operations, &mut [], &data_context).unwrap();
+
+        // The first merge is estimated to produce the rows of both scans, so
+        // it is larger than the medium scan.
+        assert_eq!(
+            operations[2].operator,
+            Some(operation_plan::Operator::Merge(MergeOperation {
+                left: 1,
+                right: 0
+            }))
+        );
+        assert_eq!(
+            operations[4].operator,
+            Some(operation_plan::Operator::Merge(MergeOperation {
+                left: 2,
+                right: 3
+            }))
+        );
+    }
+
+    #[test]
+    fn test_combine_duplicate_scans() {
+        let mut operations = vec![
+            scan(0, None),
+            scan(1, None),
+            operation(operation_plan::Operator::Tick(TickOperation {
+                behavior: 0,
+                input: 0,
+            })),
+            OperationPlan {
+                operator: Some(operation_plan::Operator::Merge(MergeOperation {
+                    left: 2,
+                    right: 1,
+                })),
+                expressions: vec![input(1, Column::ProducerExpression(0))],
+            },
+        ];
+        prune_operations(&mut operations, &mut [], &DataContext::default()).unwrap();
+
+        // The second scan is combined with the first, re-using the record.
+        assert_eq!(operations.len(), 3);
+        assert_eq!(operations[0].expressions.len(), 1);
+        assert_eq!(
+            operations[2].operator,
+            Some(operation_plan::Operator::Merge(MergeOperation {
+                left: 1,
+                right: 0
+            }))
+        );
+        assert_eq!(
+            operations[2].expressions,
+            vec![input(0, Column::ProducerExpression(0))]
+        );
+    }
+
+    #[test]
+    fn test_collapse_select_chain() {
+        let mut operations = vec![
+            OperationPlan {
+                expressions: vec![
+                    input(0, Column::ScanRecord(())),
+                    ExpressionPlan {
+                        arguments: vec![0],
+                        operator: Some(expression_plan::Operator::Instruction(
+                            "is_valid".to_owned(),
+                        )),
+                        ..input(0, Column::ScanRecord(()))
+                    },
+                    ExpressionPlan {
+                        arguments: vec![1],
+                        operator: Some(expression_plan::Operator::Instruction("not".to_owned())),
+                        ..input(0, Column::ScanRecord(()))
+                    },
+                ],
+                ..scan(0, None)
+            },
+            OperationPlan {
+                operator: Some(operation_plan::Operator::Select(select(0, 1))),
+                expressions: vec![
+                    input(0, Column::ProducerExpression(2)),
+                    input(0, Column::ProducerExpression(0)),
+                ],
+            },
+            OperationPlan {
+                operator: Some(operation_plan::Operator::Select(select(1, 0))),
+                expressions: vec![input(1, Column::ProducerExpression(1))],
+            },
+        ];
+        prune_operations(&mut operations, &mut [], &DataContext::default()).unwrap();
+
+        assert_eq!(operations.len(), 2);
+        let conjunction = &operations[0].expressions[3];
+        assert_eq!(
+            conjunction.operator,
+            Some(expression_plan::Operator::Instruction(
+                "logical_and".to_owned()
+            ))
+        );
+        assert_eq!(conjunction.arguments, vec![1, 2]);
+        assert_eq!(
+            operations[1].operator,
+            Some(operation_plan::Operator::Select(select(0, 3)))
+        );
+        assert_eq!(
+            operations[1].expressions,
+            vec![input(0, Column::ProducerExpression(0))]
+        );
+    }
+
+    #[test]
+    fn test_remove_unused_operations() {
+        let mut data_context = DataContext::default();
+        let table_id = data_context.add_table(table("Table", 10)).unwrap();
+
+        let mut operations = vec![
+            scan(0, None),
+            scan(1, Some(table_id)),
+            operation(operation_plan::Operator::Tick(TickOperation {
+                behavior: 0,
+                input: 1,
+            })),
+        ];
+        prune_operations(&mut operations, &mut [], &data_context).unwrap();
+
+        assert_eq!(operations.len(), 2);
+        assert_eq!(
+            operations[0].expressions,
+            vec![input(0, Column::ScanRecord(()))]
+        );
+        assert_eq!(
+            operations[1].operator,
+            Some(operation_plan::Operator::Tick(TickOperation {
+                behavior: 0,
+                input: 0,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_retain_output_operations() {
+        let tick = |input| {
+            operation(operation_plan::Operator::Tick(TickOperation {
+                behavior: 0,
+                input,
+            }))
+        };
+        let mut operations = vec![scan(0, None), scan(1, None), tick(1), tick(0)];
+        let mut outputs = [2];
+        prune_operations(&mut operations, &mut outputs, &DataContext::default()).unwrap();
+
+        // The scans are combined, but the output isn't combined with the
+        // identical last operation or removed despite not being consumed.
+        assert_eq!(operations.len(), 3);
+        assert_eq!(outputs, [1]);
+        assert_eq!(operations[1], tick(0));
+        assert_eq!(operations[2], tick(0));
+    }
+}
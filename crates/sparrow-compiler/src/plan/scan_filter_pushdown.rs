@@ -0,0 +1,145 @@
+use anyhow::Context;
+use sparrow_api::kaskada::v1alpha::{expression_plan, operation_plan, OperationPlan};
+
+/// Push the conditions of `select` operations down into the scans they read.
+///
+/// A `select` reading directly from a scan has a condition computed within
+/// the scan, which means it depends only on the columns of that table. If the
+/// `select` is the only consumer of the scan, every row the scan produces
+/// is filtered by the condition, so the scan may drop the rows itself rather
+/// than sending them on.
+///
+/// The `select` remains in the plan. After pushdown it only receives rows
+/// satisfying the condition, and the values it transforms from the scan are
+/// unchanged since the scan still evaluates its expressions on every row.
+///
+/// This runs on the plan rather than as a DFG simplification because the DFG
+/// has no scan or select operations -- they are only created when the plan
+/// is built, and whether a scan has a single consumer isn't known until the
+/// operations are complete. It must also run after the expression indices
+/// have been finalized, since the filter uses the finalized `input_column`
+/// of the condition.
+pub(super) fn push_down_scan_filters(operations: &mut [OperationPlan]) -> anyhow::Result<()> {
+    let num_consumers = count_consumers(operations)?;
+
+    for index in 0..operations.len() {
+        let Some(operation_plan::Operator::Select(select)) = &operations[index].operator else {
+            continue;
+        };
+        let scan_index = select.input as usize;
+        let Some(condition) = &select.condition else {
+            anyhow::bail!("Select operation {index} missing condition");
+        };
+        if condition.producing_operation != select.input || num_consumers[scan_index] != 1 {
+            continue;
+        }
+        let condition = condition.clone();
+
+        if let Some(operation_plan::Operator::Scan(scan)) = &mut operations[scan_index].operator {
+            debug_assert!(scan.filter.is_none(), "scan with multiple filters");
+            scan.filter = Some(condition);
+        }
+    }
+
+    Ok(())
+}
+
+/// Return the number of operations consuming the output of each operation.
+fn count_consumers(operations: &[OperationPlan]) -> anyhow::Result<Vec<usize>> {
+    let mut num_consumers = vec![0; operations.len()];
+    for (index, operation) in operations.iter().enumerate() {
+        let operator = operation
+            .operator
+            .as_ref()
+            .with_context(|| format!("Operation {index} missing operator"))?;
+
+        let mut inputs: Vec<u32> = operator.input_ops_iter().collect();
+        for expression in &operation.expressions {
+            if let Some(expression_plan::Operator::Input(input)) = &expression.operator {
+                // Scans reference their own records, which isn't a consumer.
+                if input.producing_operation as usize != index {
+                    inputs.push(input.producing_operation);
+                }
+            }
+        }
+
+        inputs.sort_unstable();
+        inputs.dedup();
+        for input in inputs {
+            let Some(count) = num_consumers.get_mut(input as usize) else {
+                anyhow::bail!("Operation {index} references invalid operation {input}");
+            };
+            *count += 1;
+        }
+    }
+    Ok(num_consumers)
+}
+
+#[cfg(test)]
+mod tests {
+    use sparrow_api::kaskada::v1alpha::operation_plan::{
+        MergeOperation, ScanOperation, SelectOperation,
+    };
+    use sparrow_api::kaskada::v1alpha::{operation_input_ref, OperationInputRef};
+
+    use super::*;
+
+    fn scan() -> OperationPlan {
+        OperationPlan {
+            operator: Some(operation_plan::Operator::Scan(ScanOperation::default())),
+            ..OperationPlan::default()
+        }
+    }
+
+    fn select(input: u32) -> OperationPlan {
+        OperationPlan {
+            operator: Some(operation_plan::Operator::Select(SelectOperation {
+                input,
+                condition: Some(OperationInputRef {
+                    producing_operation: input,
+                    column: Some(operation_input_ref::Column::ProducerExpression(2)),
+                    input_column: 3,
+                    interpolation: operation_input_ref::Interpolation::Null as i32,
+                }),
+            })),
+            ..OperationPlan::default()
+        }
+    }
+
+    fn scan_filter(operation: &OperationPlan) -> Option<&OperationInputRef> {
+        match &operation.operator {
+            Some(operation_plan::Operator::Scan(scan)) => scan.filter.as_ref(),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_push_down_single_consumer() {
+        let mut operations = vec![scan(), select(0)];
+        push_down_scan_filters(&mut operations).unwrap();
+
+        let filter = scan_filter(&operations[0]).expect("filter pushed down");
+        assert_eq!(filter.producing_operation, 0);
+        assert_eq!(filter.input_column, 3);
+    }
+
+    #[test]
+    fn test_no_push_down_multiple_consumers() {
+        let mut operations = vec![
+            scan(),
+            scan(),
+            select(0),
+            OperationPlan {
+                operator: Some(operation_plan::Operator::Merge(MergeOperation {
+                    left: 0,
+                    right: 1,
+                })),
+                ..OperationPlan::default()
+            },
+        ];
+        push_down_scan_filters(&mut operations).unwrap();
+
+        assert_eq!(scan_filter(&operations[0]), None);
+        assert_eq!(scan_filter(&operations[1]), None);
+    }
+}
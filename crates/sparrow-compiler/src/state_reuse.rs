@@ -0,0 +1,382 @@
+//! Determine which state in a snapshot may be reused by a different plan.
+//!
+//! Resuming from a snapshot normally requires the snapshot to have been
+//! written by a plan with the same `PlanHash`. Many changes to a feature set
+//! (such as adding a stateless feature or removing a feature) leave most of
+//! the state unchanged, but still change the hash and shift the indices of
+//! the operations and expressions.
+//!
+//! To recognize unchanged state, each operation and expression is assigned
+//! a structural fingerprint describing what it computes and from which
+//! inputs, independent of its position within the plan. State is reused
+//! when an operation (or stateful expression) in the new plan has the same
+//! fingerprint as one in the previous plan.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::Context;
+use prost::Message;
+use sha2::Digest;
+use sparrow_api::kaskada::v1alpha::expression_plan::Operator;
+use sparrow_api::kaskada::v1alpha::operation_input_ref::Column;
+use sparrow_api::kaskada::v1alpha::operation_plan::shift_to_operation::Time;
+use sparrow_api::kaskada::v1alpha::{
+    operation_plan, state_reuse, ComputePlan, OperationInputRef, OperationPlan, StateReuse,
+};
+use sparrow_plan::InstOp;
+
+/// Determine which state written by the `previous` plan may be used by `plan`.
+///
+/// State that may be reused is reported as a mapping from the operation (and
+/// expression) in the `previous` plan to the corresponding one in `plan`.
+/// State needed by `plan` that is not available from the `previous` plan is
+/// reported as needing a backfill.
+pub fn plan_state_reuse(previous: &ComputePlan, plan: &ComputePlan) -> anyhow::Result<StateReuse> {
+    let new_fingerprints = PlanFingerprints::try_new(plan)?;
+
+    // If the grouping or behavior changed, none of the state is reusable.
+    let previous_fingerprints = if previous.primary_grouping == plan.primary_grouping
+        && previous.per_entity_behavior == plan.per_entity_behavior
+    {
+        Some(PlanFingerprints::try_new(previous)?)
+    } else {
+        None
+    };
+
+    let mut previous_operations = HashMap::new();
+    if let Some(previous_fingerprints) = &previous_fingerprints {
+        for (index, fingerprint) in previous_fingerprints.operations.iter().enumerate() {
+            previous_operations.entry(fingerprint).or_insert(index);
+        }
+    }
+
+    let mut state_reuse = StateReuse::default();
+    for (operation_index, operation) in plan.operations.iter().enumerate() {
+        let fingerprint = &new_fingerprints.operations[operation_index];
+        let stateful_expressions = operation
+            .expressions
+            .iter()
+            .enumerate()
+            .filter(|(_, expression)| is_stateful(expression.operator.as_ref()))
+            .map(|(index, _)| index);
+
+        let previous_operation_index = previous_operations.get(fingerprint).copied();
+        let previous_operation_index = if let Some(index) = previous_operation_index {
+            index
+        } else {
+            state_reuse.backfill.push(state_reuse::StateRef {
+                operation: operation_index as u32,
+                expression: None,
+            });
+            for expression_index in stateful_expressions {
+                state_reuse.backfill.push(state_reuse::StateRef {
+                    operation: operation_index as u32,
+                    expression: Some(expression_index as u32),
+                })
+            }
+            continue;
+        };
+
+        state_reuse.operations.push(state_reuse::OperationMapping {
+            previous_operation: previous_operation_index as u32,
+            operation: operation_index as u32,
+        });
+
+        // Index the stateful expressions within the matching previous operation.
+        let previous_fingerprints = previous_fingerprints
+            .as_ref()
+            .expect("previous operation matched");
+        let previous_operation = &previous.operations[previous_operation_index];
+        let previous_expressions: HashMap<_, _> = previous_operation
+            .expressions
+            .iter()
+            .enumerate()
+            .filter(|(_, expression)| is_stateful(expression.operator.as_ref()))
+            .map(|(index, _)| {
+                (
+                    &previous_fingerprints.expressions[previous_operation_index][index],
+                    index,
+                )
+            })
+            .collect();
+
+        for expression_index in stateful_expressions {
+            let fingerprint = &new_fingerprints.expressions[operation_index][expression_index];
+            if let Some(previous_expression_index) = previous_expressions.get(fingerprint) {
+                state_reuse.accumulators.push(state_reuse::AccumulatorMapping {
+                    previous_operation: previous_operation_index as u32,
+                    previous_expression: *previous_expression_index as u32,
+                    operation: operation_index as u32,
+                    expression: expression_index as u32,
+                });
+            } else {
+                state_reuse.backfill.push(state_reuse::StateRef {
+                    operation: operation_index as u32,
+                    expression: Some(expression_index as u32),
+                });
+            }
+        }
+    }
+
+    Ok(state_reuse)
+}
+
+/// Return the index of the accumulator state for the given expression.
+///
+/// Only instructions are executed (and may have accumulators), so the state
+/// for an expression is identified by its position among the instructions
+/// in the operation.
+pub fn accumulator_index(operation: &OperationPlan, expression_index: u32) -> u32 {
+    operation.expressions[0..expression_index as usize]
+        .iter()
+        .filter(|expression| matches!(expression.operator, Some(Operator::Instruction(_))))
+        .count() as u32
+}
+
+/// Whether the expression has accumulated state stored in a snapshot.
+fn is_stateful(operator: Option<&Operator>) -> bool {
+    match operator {
+        Some(Operator::Instruction(inst)) => InstOp::from_str(inst)
+            .map(|op| op.is_aggregation() || op == InstOp::Lag)
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Fingerprints of the operations and expressions within a plan.
+struct PlanFingerprints {
+    /// The fingerprint of each operation.
+    operations: Vec<Vec<u8>>,
+    /// The fingerprint of each expression within each operation.
+    expressions: Vec<Vec<Vec<u8>>>,
+}
+
+impl PlanFingerprints {
+    fn try_new(plan: &ComputePlan) -> anyhow::Result<Self> {
+        let mut fingerprints = Self {
+            operations: Vec::with_capacity(plan.operations.len()),
+            expressions: Vec::with_capacity(plan.operations.len()),
+        };
+
+        for (operation_index, operation) in plan.operations.iter().enumerate() {
+            let expressions = fingerprints.expressions(operation_index, operation)?;
+            fingerprints.expressions.push(expressions);
+            let operation = fingerprints.operation(operation_index, operation)?;
+            fingerprints.operations.push(operation);
+        }
+
+        Ok(fingerprints)
+    }
+
+    fn expressions(
+        &self,
+        operation_index: usize,
+        operation: &OperationPlan,
+    ) -> anyhow::Result<Vec<Vec<u8>>> {
+        let mut expressions: Vec<Vec<u8>> = Vec::with_capacity(operation.expressions.len());
+        for expression in operation.expressions.iter() {
+            let mut fingerprint = Fingerprint::default();
+            match expression.operator.as_ref().context("missing operator")? {
+                Operator::Instruction(inst) => {
+                    fingerprint.add(b"instruction");
+                    fingerprint.add(inst.as_bytes());
+                    fingerprint.add_proto(expression.result_type.as_ref());
+                    for argument in expression.arguments.iter() {
+                        let argument = expressions
+                            .get(*argument as usize)
+                            .context("argument must reference earlier expression")?;
+                        fingerprint.add(argument);
+                    }
+                }
+                Operator::Input(input_ref) => {
+                    // The type of inputs is not included, since adding fields to
+                    // the projected schema of a scan changes the record type
+                    // without changing the values of the existing fields.
+                    fingerprint.add(b"input");
+                    fingerprint.add(&self.input_ref(operation_index, input_ref)?);
+                }
+                Operator::Literal(literal) => {
+                    fingerprint.add(b"literal");
+                    fingerprint.add(&literal.encode_to_vec());
+                    fingerprint.add_proto(expression.result_type.as_ref());
+                }
+                Operator::LateBound(late_bound) => {
+                    fingerprint.add(b"late_bound");
+                    fingerprint.add(&late_bound.to_le_bytes());
+                    fingerprint.add_proto(expression.result_type.as_ref());
+                }
+            }
+            expressions.push(fingerprint.finish());
+        }
+        Ok(expressions)
+    }
+
+    fn operation(
+        &self,
+        operation_index: usize,
+        operation: &OperationPlan,
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut fingerprint = Fingerprint::default();
+        match operation.operator.as_ref().context("missing operator")? {
+            operation_plan::Operator::Scan(scan) => {
+                // The projected schema is not included, so that using additional
+                // columns from a table doesn't prevent reusing state.
+                fingerprint.add(b"scan");
+                fingerprint.add_proto(scan.table_id.as_ref());
+                fingerprint.add_proto(scan.slice_plan.as_ref());
+            }
+            operation_plan::Operator::Merge(merge) => {
+                fingerprint.add(b"merge");
+                fingerprint.add(self.operation_ref(merge.left)?);
+                fingerprint.add(self.operation_ref(merge.right)?);
+            }
+            operation_plan::Operator::Select(select) => {
+                fingerprint.add(b"select");
+                fingerprint.add(self.operation_ref(select.input)?);
+                fingerprint.add(&self.required_input_ref(operation_index, &select.condition)?);
+            }
+            operation_plan::Operator::Tick(tick) => {
+                fingerprint.add(b"tick");
+                fingerprint.add(&tick.behavior.to_le_bytes());
+                fingerprint.add(self.operation_ref(tick.input)?);
+            }
+            operation_plan::Operator::WithKey(with_key) => {
+                fingerprint.add(b"with_key");
+                fingerprint.add(self.operation_ref(with_key.input)?);
+                fingerprint.add(&self.required_input_ref(operation_index, &with_key.new_key)?);
+                fingerprint.add(with_key.grouping.as_bytes());
+            }
+            operation_plan::Operator::LookupRequest(lookup_request) => {
+                fingerprint.add(b"lookup_request");
+                fingerprint.add(self.operation_ref(lookup_request.primary_operation)?);
+                fingerprint.add(
+                    &self.required_input_ref(operation_index, &lookup_request.foreign_key_hash)?,
+                );
+            }
+            operation_plan::Operator::LookupResponse(lookup_response) => {
+                fingerprint.add(b"lookup_response");
+                fingerprint.add(self.operation_ref(lookup_response.foreign_operation)?);
+                fingerprint.add(
+                    &self
+                        .required_input_ref(operation_index, &lookup_response.requesting_key_hash)?,
+                );
+            }
+            operation_plan::Operator::ShiftTo(shift_to) => {
+                fingerprint.add(b"shift_to");
+                fingerprint.add(self.operation_ref(shift_to.input)?);
+                match shift_to.time.as_ref().context("missing shift time")? {
+                    Time::Computed(computed) => {
+                        fingerprint.add(&self.input_ref(operation_index, computed)?)
+                    }
+                    Time::Literal(literal) => fingerprint.add(&literal.encode_to_vec()),
+                }
+            }
+            operation_plan::Operator::ShiftUntil(shift_until) => {
+                fingerprint.add(b"shift_until");
+                fingerprint.add(self.operation_ref(shift_until.input)?);
+                fingerprint.add(&self.required_input_ref(operation_index, &shift_until.condition)?);
+            }
+        }
+
+        // Merges and shifts store the values of their input columns (in order)
+        // as part of their state, so the state is only reusable if the columns
+        // are unchanged.
+        if matches!(
+            operation.operator,
+            Some(
+                operation_plan::Operator::Merge(_)
+                    | operation_plan::Operator::ShiftTo(_)
+                    | operation_plan::Operator::ShiftUntil(_)
+            )
+        ) {
+            let expressions = &self.expressions[operation_index];
+            for (index, expression) in operation.expressions.iter().enumerate() {
+                if matches!(expression.operator, Some(Operator::Input(_))) {
+                    fingerprint.add(&expressions[index]);
+                    fingerprint.add_proto(expression.result_type.as_ref());
+                }
+            }
+        }
+
+        Ok(fingerprint.finish())
+    }
+
+    fn operation_ref(&self, operation_index: u32) -> anyhow::Result<&[u8]> {
+        self.operations
+            .get(operation_index as usize)
+            .map(|fingerprint| fingerprint.as_ref())
+            .with_context(|| {
+                format!("operation {operation_index} must reference earlier operation")
+            })
+    }
+
+    fn required_input_ref(
+        &self,
+        operation_index: usize,
+        input_ref: &Option<OperationInputRef>,
+    ) -> anyhow::Result<Vec<u8>> {
+        let input_ref = input_ref.as_ref().context("missing input ref")?;
+        self.input_ref(operation_index, input_ref)
+    }
+
+    fn input_ref(
+        &self,
+        operation_index: usize,
+        input_ref: &OperationInputRef,
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut fingerprint = Fingerprint::default();
+        let producing_operation = input_ref.producing_operation as usize;
+        if producing_operation == operation_index {
+            // Scans reference themselves to read the scanned record.
+            fingerprint.add(b"self");
+        } else {
+            fingerprint.add(self.operation_ref(input_ref.producing_operation)?);
+        }
+
+        // The `input_column` is not included, since it depends on the position
+        // of the referenced expression amongst the outputs of the producer.
+        match input_ref.column.as_ref().context("missing column")? {
+            Column::KeyColumn(key_column) => {
+                fingerprint.add(b"key_column");
+                fingerprint.add(&key_column.to_le_bytes());
+            }
+            Column::ProducerExpression(expression) => {
+                let expression = self
+                    .expressions
+                    .get(producing_operation)
+                    .and_then(|expressions| expressions.get(*expression as usize))
+                    .context("missing producer expression")?;
+                fingerprint.add(b"producer_expression");
+                fingerprint.add(expression);
+            }
+            Column::ScanRecord(_) => fingerprint.add(b"scan_record"),
+            Column::Tick(_) => fingerprint.add(b"tick"),
+        }
+        fingerprint.add(&input_ref.interpolation.to_le_bytes());
+        Ok(fingerprint.finish())
+    }
+}
+
+/// Hashes the components of a structural fingerprint.
+#[derive(Default)]
+struct Fingerprint(sha2::Sha224);
+
+impl Fingerprint {
+    fn add(&mut self, bytes: &[u8]) {
+        // Prefix each component with the length to avoid ambiguity.
+        self.0.update((bytes.len() as u64).to_le_bytes());
+        self.0.update(bytes);
+    }
+
+    fn add_proto(&mut self, message: Option<&impl Message>) {
+        match message {
+            Some(message) => self.add(&message.encode_to_vec()),
+            None => self.add(b"none"),
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.0.finalize().to_vec()
+    }
+}
@@ -18,13 +18,20 @@ use crate::types::inference::validate_instantiation;
 /// This returns the (instantiated) result type.
 pub(crate) fn typecheck_inst(
     inst: &InstKind,
-    argument_types: ArgVec<FenlType>,
+    mut argument_types: ArgVec<FenlType>,
     argument_literals: &[Option<ScalarValue>],
     mode: Mode,
 ) -> anyhow::Result<FenlType> {
     match inst {
         InstKind::Simple(instruction) => {
             let signature = instruction.signature(mode);
+            if matches!(mode, Mode::Plan)
+                && instruction.is_aggregation()
+                && argument_types.len() == 3
+            {
+                // The gap is omitted unless the aggregation is session windowed.
+                argument_types.push(FenlType::Concrete(DataType::Null));
+            }
             let argument_types = Resolved::new(
                 Cow::Borrowed(signature.parameters().names()),
                 argument_types,
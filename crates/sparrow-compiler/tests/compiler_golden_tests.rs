@@ -60,6 +60,9 @@ impl TestScript {
                 expression_kind: ExpressionKind::Complete as i32,
                 experimental: false,
                 per_entity_behavior: PerEntityBehavior::All as i32,
+                previous_plan: None,
+                explain: false,
+                lint_severities: vec![],
             },
             InternalCompileOptions::default(),
         )
@@ -223,6 +226,7 @@ async fn test_basic_sum() {
                         formula("f2", "Sent.amount | sum($input)"),
                     ],
                     query: "{f1, f2}".to_owned(),
+                    outputs: vec![],
                 },
             },
             None
@@ -240,6 +244,7 @@ async fn test_projection_pushdown() {
                 feature_set: FeatureSet {
                     formulas: vec![],
                     query: "{ amount: Sent | first() | $input.amount  }".to_owned(),
+                    outputs: vec![],
                 },
             },
             None
@@ -256,6 +261,7 @@ async fn test_unexported() {
             feature_set: FeatureSet {
                 formulas: vec![formula("f1", "sum(Sent.amount)"), formula("f2", "f1 + 1")],
                 query: "{f2}".to_owned(),
+                outputs: vec![],
             },
         },
         None,
@@ -273,6 +279,7 @@ async fn test_basic_sum_when_event() {
             feature_set: FeatureSet {
                 formulas: vec![formula("sum_amount", "sum(Sent.amount)")],
                 query: "{ sum_amount } | when(Sent.amount > 10)".to_owned(),
+                outputs: vec![],
             },
         },
         None,
@@ -292,6 +299,7 @@ async fn test_count_since_window() {
                     "count(Sent.amount, window=since(Sent.amount > 10))",
                 )],
                 query: "{ count_amount }".to_owned(),
+                outputs: vec![],
             },
         },
         None,
@@ -309,6 +317,7 @@ async fn test_basic_sum_when_aggregate() {
             feature_set: FeatureSet {
                 formulas: vec![formula("sum_amount", "sum(Sent.amount)")],
                 query: "{ sum_amount } | when($input.sum_amount > 10)".to_owned(),
+                outputs: vec![],
             },
         },
         None,
@@ -332,6 +341,7 @@ async fn test_lookup_same_grouping() {
                     ),
                 ],
                 query: "{ sum_amount, sum_received }".to_owned(),
+                outputs: vec![],
             },
         },
         None,
@@ -354,6 +364,7 @@ async fn test_with_key() {
                 query: "{ sum_sent: Sent.amount | sum(), sum_store_received: \
                         StoreReceivedBySender.amount | sum() }"
                     .to_owned(),
+                outputs: vec![],
             },
         },
         None,
@@ -374,6 +385,7 @@ async fn test_with_key_default_grouping() {
                     "StoreReceived | with_key($input.sender_id)",
                 )],
                 query: "{ sum_store_received: StoreReceivedBySender.amount | sum() }".to_owned(),
+                outputs: vec![],
             },
         },
         None,
@@ -397,6 +409,7 @@ async fn test_lookup_same_grouping_with_slicing() {
                     ),
                 ],
                 query: "{ sum_amount, sum_received }".to_owned(),
+                outputs: vec![],
             },
         },
         Some(SliceRequest {
@@ -424,6 +437,7 @@ async fn test_lookup_diff_grouping() {
                     ),
                 ],
                 query: "{ sum_amount, sum_store_received }".to_owned(),
+                outputs: vec![],
             },
         },
         None,
@@ -441,6 +455,7 @@ async fn test_cast_in_record() {
             feature_set: FeatureSet {
                 formulas: Vec::new(),
                 query: "{ x: Primitive.i64 } | else ({ x: Primitive.f64 })".to_owned(),
+                outputs: vec![],
             },
         },
         None,
@@ -458,6 +473,7 @@ async fn test_null_value() {
             feature_set: FeatureSet {
                 formulas: Vec::new(),
                 query: "{ x: null | else(Sent.amount) }".to_owned(),
+                outputs: vec![],
             },
         },
         None,
@@ -475,6 +491,7 @@ async fn test_null_record_field() {
             feature_set: FeatureSet {
                 formulas: Vec::new(),
                 query: "null | else({ a: Sent.amount })".to_owned(),
+                outputs: vec![],
             },
         },
         None,
@@ -492,6 +509,7 @@ async fn test_null_record() {
             feature_set: FeatureSet {
                 formulas: Vec::new(),
                 query: "{a: Sent.amount, b: null } | else({ a: Sent.amount, b: 7 })".to_owned(),
+                outputs: vec![],
             },
         },
         None,
@@ -512,6 +530,7 @@ async fn test_valid_time_domains() {
                     "Sent.amount | shift_until(Sent.amount > 0) | shift_until(Sent.amount < 24)",
                 )],
                 query: "{ a: shift + shift }".to_owned(),
+                outputs: vec![],
             },
         },
         None,
@@ -532,6 +551,7 @@ async fn test_since_daily() {
                     "count(Sent.amount, window=since(daily()))",
                 )],
                 query: "{ a: daily }".to_owned(),
+                outputs: vec![],
             },
         },
         None,
@@ -554,6 +574,7 @@ async fn test_since_daily_multiple_passes() {
                 query: "{ a: count_since | shift_until(Sent.amount > 10) | sum($input, \
                         window=since(daily())) }"
                     .to_owned(),
+                outputs: vec![],
             },
         },
         None,
@@ -571,6 +592,7 @@ async fn test_tab_characters_are_allowed() {
             feature_set: FeatureSet {
                 formulas: vec![],
                 query: "	{ a: 	sum(Sent.amount) }".to_owned(),
+                outputs: vec![],
             },
         },
         None,
@@ -594,6 +616,7 @@ async fn test_extend_infers_implicit_input() {
             feature_set: FeatureSet {
                 formulas: vec![],
                 query: query.to_owned(),
+                outputs: vec![],
             },
         },
         None,
@@ -608,6 +631,7 @@ async fn test_incompatible_err() {
         feature_set: FeatureSet {
             formulas: vec![],
             query: "Sent.amount == \"hello\"".to_owned(),
+            outputs: vec![],
         },
     })
     .await;
@@ -635,6 +659,7 @@ async fn test_incompatible_shift_functions() {
             query: "{x: (Sent.amount | shift_until($input > 0)) + (Sent.amount | \
                     shift_until($input > 1)) }"
                 .to_owned(),
+            outputs: vec![],
         },
     })
     .await;
@@ -658,6 +683,7 @@ async fn test_unused_lhs_pipe() {
         feature_set: FeatureSet {
             formulas: vec![],
             query: "{ x: Sent.amount | sum(Sent.amount) }".to_owned(),
+            outputs: vec![],
         },
     })
     .await;
@@ -678,6 +704,7 @@ async fn test_unused_let_binding() {
         feature_set: FeatureSet {
             formulas: vec![],
             query: "let unused = sum(Sent.amount) in { x: Sent.amount }".to_owned(),
+            outputs: vec![],
         },
     })
     .await;
@@ -698,6 +725,7 @@ async fn test_incompatible_time_domain_tables_add() {
         feature_set: FeatureSet {
             formulas: vec![],
             query: "{x: Sent.amount + Received.amount }".to_owned(),
+            outputs: vec![],
         },
     })
     .await;
@@ -721,6 +749,7 @@ async fn test_undefined_field_err() {
         feature_set: FeatureSet {
             formulas: vec![],
             query: "Sent.amt == 5".to_owned(),
+            outputs: vec![],
         },
     })
     .await;
@@ -743,6 +772,7 @@ async fn test_invalid_field_ref_base_err() {
         feature_set: FeatureSet {
             formulas: vec![],
             query: "Sent.amount.foo".to_owned(),
+            outputs: vec![],
         },
     })
     .await;
@@ -763,6 +793,7 @@ async fn test_incompatible_grouping_add() {
         feature_set: FeatureSet {
             formulas: vec![],
             query: "{ amount: sum(Received.amount) + sum(StoreReceived.amount) }".to_owned(),
+            outputs: vec![],
         },
     })
     .await;
@@ -787,6 +818,7 @@ async fn test_incompatible_grouping_record() {
             formulas: vec![],
             query: "{ sum_account: sum(Received.amount), sum_store: sum(StoreReceived.amount) }"
                 .to_owned(),
+            outputs: vec![],
         },
     })
     .await;
@@ -814,6 +846,7 @@ async fn test_duplicate_formula() {
                 formula("a", "Received.amount"),
             ],
             query: "{ a }".to_owned(),
+            outputs: vec![],
         },
     })
     .await;
@@ -831,6 +864,7 @@ async fn test_undefined_formula() {
         feature_set: FeatureSet {
             formulas: vec![],
             query: "{ a }".to_owned(),
+            outputs: vec![],
         },
     })
     .await;
@@ -855,6 +889,7 @@ async fn test_invalid_rewritten_function() {
         feature_set: FeatureSet {
             formulas: vec![],
             query: "{ a: sqrt(Sent.amount, Sent.amount) }".to_owned(),
+            outputs: vec![],
         },
     })
     .await;
@@ -877,6 +912,7 @@ async fn test_output_type_is_struct_diagnostic() {
         feature_set: FeatureSet {
             formulas: vec![],
             query: "Received.amount".to_owned(),
+            outputs: vec![],
         },
     })
     .await;
@@ -1,13 +1,13 @@
-use anyhow::anyhow;
 use sparrow_plan::ValueRef;
 
 use crate::StaticArg;
 
 /// Enum for working with the arguments to an aggregation in plans.
 ///
-/// Specifically, there are 3 arguments -- `input`, `ticks` and `duration`.
+/// Specifically, there are 4 arguments -- `input`, `ticks`, `duration` and
+/// `gap`. The `gap` is omitted unless the aggregation is session windowed.
 pub enum AggregationArgs<T> {
-    /// Unwindowed aggregations have null ticks and duration.
+    /// Unwindowed aggregations have null ticks, duration and gap.
     NoWindow { input: T },
     /// Since windowed aggregations have non-null ticks and null duration.
     ///
@@ -15,6 +15,12 @@ pub enum AggregationArgs<T> {
     Since { input: T, ticks: T },
     /// Sliding windowed aggregations have both non-null ticks and duration.
     Sliding { input: T, ticks: T, duration: T },
+    /// Time-based sliding windowed aggregations have null ticks and a non-null
+    /// duration, which is the width of the window in nanoseconds.
+    SlidingTime { input: T, duration: T },
+    /// Session windowed aggregations have a non-null gap, which is the
+    /// inactivity in nanoseconds after which a session closes.
+    Session { input: T, gap: T },
 }
 
 impl AggregationArgs<ValueRef> {
@@ -22,13 +28,24 @@ impl AggregationArgs<ValueRef> {
     /// should be applied to this aggregation based on the `input`.
     pub fn from_input(input: Vec<StaticArg>) -> anyhow::Result<Self> {
         // With the new operation-based plan, we flatten the arguments in the dfg.
-        // [input, tick, duration]
+        // [input, tick, duration, gap?]
         anyhow::ensure!(
-            input.len() == 3,
-            "Aggregations should have 3 arguments. Saw {:?}",
+            input.len() == 3 || input.len() == 4,
+            "Aggregations should have 3 or 4 arguments. Saw {:?}",
             input.len()
         );
 
+        if let Some(gap) = input.get(3).filter(|gap| !gap.is_literal_null()) {
+            anyhow::ensure!(
+                input[1].is_literal_null() && input[2].is_literal_null(),
+                "Session windowed aggregations should have null ticks and duration"
+            );
+            return Ok(AggregationArgs::Session {
+                input: input[0].value_ref.clone(),
+                gap: gap.value_ref.clone(),
+            });
+        }
+
         match (input[1].is_literal_null(), input[2].is_literal_null()) {
             (true, true) => Ok(AggregationArgs::NoWindow {
                 input: input[0].value_ref.clone(),
@@ -42,11 +59,10 @@ impl AggregationArgs<ValueRef> {
                 ticks: input[1].value_ref.clone(),
                 duration: input[2].value_ref.clone(),
             }),
-
-            invalid => Err(anyhow!(
-                "Saw invalid combination of windowed aggregation parameters: {:?}",
-                invalid
-            )),
+            (true, false) => Ok(AggregationArgs::SlidingTime {
+                input: input[0].value_ref.clone(),
+                duration: input[2].value_ref.clone(),
+            }),
         }
     }
 }
@@ -54,13 +70,17 @@ impl AggregationArgs<ValueRef> {
 impl<T> AggregationArgs<T> {
     pub fn to_arg_vec(self) -> Vec<Option<T>> {
         match self {
-            AggregationArgs::NoWindow { input } => vec![Some(input), None, None],
-            AggregationArgs::Since { input, ticks } => vec![Some(input), Some(ticks), None],
+            AggregationArgs::NoWindow { input } => vec![Some(input), None, None, None],
+            AggregationArgs::Since { input, ticks } => vec![Some(input), Some(ticks), None, None],
             AggregationArgs::Sliding {
                 input,
                 ticks,
                 duration,
-            } => vec![Some(input), Some(ticks), Some(duration)],
+            } => vec![Some(input), Some(ticks), Some(duration), None],
+            AggregationArgs::SlidingTime { input, duration } => {
+                vec![Some(input), None, Some(duration), None]
+            }
+            AggregationArgs::Session { input, gap } => vec![Some(input), None, None, Some(gap)],
         }
     }
 }
@@ -1,13 +1,15 @@
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::Context;
+use prost::Message;
 use prost_wkt_types::Timestamp;
 use rocksdb::DBPinnableSlice;
-use sparrow_api::kaskada::v1alpha::PlanHash;
+use sparrow_api::kaskada::v1alpha::{ComputePlan, PlanHash};
 use tracing::info;
 
-use crate::StoreKey;
+use crate::{StoreKey, StoreKeyKind};
 
 /// Storage layer responsible for caching query metadata and results.
 ///
@@ -21,10 +23,15 @@ pub struct ComputeStore {
     pub is_resumed: bool,
 }
 
-const STORE_VERSION_KEY: &[u8] = b"_store_version";
+pub(crate) const STORE_VERSION_KEY: &[u8] = b"_store_version";
 const STORE_VERSION: i32 = 0;
 
-const PLAN_HASH_KEY: &[u8] = b"_plan_hash";
+pub(crate) const PLAN_HASH_KEY: &[u8] = b"_plan_hash";
+
+pub(crate) const PLAN_KEY: &[u8] = b"_plan";
+
+/// A raw key and value read from the store.
+pub type RawEntry = (Box<[u8]>, Box<[u8]>);
 
 impl ComputeStore {
     pub fn try_new(
@@ -138,11 +145,163 @@ impl ComputeStore {
         }
     }
 
+    /// Opens an existing database at the given path for inspection.
+    ///
+    /// Unlike [ComputeStore::try_new_from_path], this does not create the
+    /// database if it is missing, does not require the stored version to match
+    /// the current version and never writes to the store. Attempting to `put`
+    /// values into a read-only store will fail.
+    pub fn try_open_read_only(path: &Path) -> anyhow::Result<Self> {
+        let options = rocksdb::Options::default();
+        let rocksdb = rocksdb::DB::open_for_read_only(&options, path, false)
+            .with_context(|| format!("Open rocksdb read-only at '{path:?}'"))?;
+        anyhow::ensure!(
+            rocksdb
+                .get_pinned(STORE_VERSION_KEY)
+                .context("Get stored version")?
+                .is_some(),
+            "Missing store version in compute store at '{:?}'",
+            path
+        );
+
+        Ok(Self {
+            rocksdb,
+            write_options: rocksdb::WriteOptions::default(),
+            is_resumed: true,
+        })
+    }
+
     /// Returns the current version.
     pub fn current_version() -> i32 {
         STORE_VERSION
     }
 
+    /// Returns the version the store was written with.
+    pub fn stored_version(&self) -> anyhow::Result<i32> {
+        let bytes = self
+            .rocksdb
+            .get_pinned(STORE_VERSION_KEY)
+            .context("Get stored version")?
+            .context("missing stored version")?;
+        bincode::deserialize(&bytes).context("Deserialize stored version")
+    }
+
+    /// Returns the hash of the plan the store was written for.
+    pub fn get_plan_hash(&self) -> anyhow::Result<Option<PlanHash>> {
+        self.get_proto(&PLAN_HASH_KEY)
+    }
+
+    /// Returns the plan the store was written for, if it was recorded.
+    ///
+    /// Stores written before the plan was recorded will return `None`.
+    pub fn get_plan(&self) -> anyhow::Result<Option<ComputePlan>> {
+        // This doesn't use `get_proto` since the plan may be missing from
+        // resumed stores.
+        let bytes = self
+            .rocksdb
+            .get_pinned(PLAN_KEY)
+            .context("Read plan from rocksdb")?;
+        bytes
+            .map(|bytes| ComputePlan::decode(bytes.as_ref()).context("Deserialize plan"))
+            .transpose()
+    }
+
+    /// Record the plan the store is being written for.
+    pub fn put_plan(&self, plan: &ComputePlan) -> anyhow::Result<()> {
+        self.put_proto(&PLAN_KEY, plan)
+    }
+
+    /// Re-key the state in the store for use by a different plan.
+    ///
+    /// `operations` maps the index of each operation in the stored plan to
+    /// the index of the operation using that state in the new plan.
+    /// `accumulators` does the same for accumulators, identified by operation
+    /// and instruction index. State which isn't mapped is discarded.
+    ///
+    /// After re-keying the state, the plan and plan hash are updated.
+    pub fn reuse_state(
+        &self,
+        operations: &HashMap<u8, u8>,
+        accumulators: &HashMap<(u8, u32), (u8, u32)>,
+        plan: &ComputePlan,
+        plan_hash: &PlanHash,
+    ) -> anyhow::Result<()> {
+        let mut batch = rocksdb::WriteBatch::default();
+        let mut moved = Vec::new();
+        for entry in self.raw_entries() {
+            let (key, value) = entry?;
+            let new_key = match StoreKeyKind::from_bytes(&key) {
+                StoreKeyKind::Accumulator {
+                    operation_index,
+                    inst_index,
+                } => accumulators.get(&(operation_index, inst_index)).map(
+                    |(operation_index, inst_index)| {
+                        StoreKey::new_accumulator(*operation_index, *inst_index)
+                    },
+                ),
+                kind => match kind.operation_index() {
+                    Some(operation_index) => operations
+                        .get(&operation_index)
+                        .and_then(|operation_index| kind.with_operation_index(*operation_index)),
+                    // State not associated with an operation is retained as-is.
+                    None => continue,
+                },
+            };
+
+            // Deletes are applied before the puts, so state moved to a key that
+            // was previously used by different state isn't lost.
+            batch.delete(&key);
+            if let Some(new_key) = new_key {
+                moved.push((new_key, value));
+            }
+        }
+        for (key, value) in moved {
+            batch.put(key, value);
+        }
+        batch.put(PLAN_HASH_KEY, plan_hash.encode_to_vec());
+        batch.put(PLAN_KEY, plan.encode_to_vec());
+
+        self.rocksdb
+            .write_opt(batch, &self.write_options)
+            .context("Re-key state")?;
+        Ok(())
+    }
+
+    /// Add the state from `other` which isn't present in this store.
+    ///
+    /// This is used to combine state reused from a snapshot with state that
+    /// was backfilled for a new plan. State present in both stores is kept
+    /// as-is.
+    pub fn add_missing_state(&self, other: &ComputeStore) -> anyhow::Result<()> {
+        let mut batch = rocksdb::WriteBatch::default();
+        for entry in other.raw_entries() {
+            let (key, value) = entry?;
+            let present = self
+                .rocksdb
+                .get_pinned(&key)
+                .context("Read key from rocksdb")?
+                .is_some();
+            if !present {
+                batch.put(key, value);
+            }
+        }
+
+        self.rocksdb
+            .write_opt(batch, &self.write_options)
+            .context("Add missing state")?;
+        Ok(())
+    }
+
+    /// Returns an iterator over the raw keys and values in the store.
+    ///
+    /// This is intended for inspecting and debugging snapshots. The keys
+    /// may be decoded using [crate::StoreKeyKind::from_bytes].
+    pub fn raw_entries(&self) -> impl Iterator<Item = anyhow::Result<RawEntry>> + '_ {
+        self.rocksdb
+            .iterator(rocksdb::IteratorMode::Start)
+            .map(|entry| entry.context("Read entry from rocksdb"))
+    }
+
     fn get_bytes(&self, key_bytes: &[u8]) -> anyhow::Result<Option<DBPinnableSlice<'_>>> {
         // Note: use `get_pinned` to improve memory usage.
         let bytes = self
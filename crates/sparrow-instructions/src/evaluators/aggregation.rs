@@ -3,7 +3,9 @@ mod function;
 mod generic;
 mod numeric_properties;
 mod primitive;
+mod session_window;
 mod string;
+mod time_window;
 mod token;
 mod two_stacks;
 
@@ -12,6 +14,8 @@ pub use function::*;
 pub use generic::*;
 pub use numeric_properties::*;
 pub use primitive::*;
+pub use session_window::*;
 pub use string::*;
+pub use time_window::*;
 pub use token::*;
 pub use two_stacks::*;
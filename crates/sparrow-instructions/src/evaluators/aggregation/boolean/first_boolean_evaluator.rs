@@ -7,9 +7,12 @@ use sparrow_kernels::BitBufferIterator;
 use sparrow_plan::ValueRef;
 
 use super::two_stacks_first_boolean_evaluator::TwoStacksFirstBooleanEvaluator;
+use crate::evaluators::aggregation::generic::{
+    BooleanInput, SessionEvaluator, TimeWindowEvaluator,
+};
 use crate::{
-    AggregationArgs, BooleanAccumToken, Evaluator, EvaluatorFactory, RuntimeInfo, StateToken,
-    StaticInfo, TwoStacksBooleanAccumToken,
+    AggregationArgs, BooleanAccumToken, Evaluator, EvaluatorFactory, FirstBoolean, RuntimeInfo,
+    StateToken, StaticInfo, TwoStacksBooleanAccumToken,
 };
 
 /// Evaluator for the `First` instruction on booleans.
@@ -45,7 +48,9 @@ impl Evaluator for FirstBooleanEvaluator {
                 );
                 result
             }
-            AggregationArgs::Sliding { .. } => {
+            AggregationArgs::Sliding { .. }
+            | AggregationArgs::SlidingTime { .. }
+            | AggregationArgs::Session { .. } => {
                 unreachable!("Expected Non-windowed or Since windowed aggregation, saw Sliding.")
             }
         }
@@ -72,6 +77,12 @@ impl EvaluatorFactory for FirstBooleanEvaluator {
                 let token = TwoStacksBooleanAccumToken::new();
                 Ok(Box::new(TwoStacksFirstBooleanEvaluator { token, args }))
             }
+            AggregationArgs::SlidingTime { .. } => Ok(Box::new(TimeWindowEvaluator::<
+                BooleanInput<FirstBoolean>,
+            >::new(args))),
+            AggregationArgs::Session { .. } => Ok(Box::new(SessionEvaluator::<
+                BooleanInput<FirstBoolean>,
+            >::new(args))),
         }
     }
 }
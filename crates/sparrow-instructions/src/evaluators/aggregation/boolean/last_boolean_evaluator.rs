@@ -7,9 +7,12 @@ use sparrow_kernels::BitBufferIterator;
 use sparrow_plan::ValueRef;
 
 use super::two_stacks_last_boolean_evaluator::TwoStacksLastBooleanEvaluator;
+use crate::evaluators::aggregation::generic::{
+    BooleanInput, SessionEvaluator, TimeWindowEvaluator,
+};
 use crate::{
-    AggregationArgs, BooleanAccumToken, Evaluator, EvaluatorFactory, RuntimeInfo, StateToken,
-    StaticInfo, TwoStacksBooleanAccumToken,
+    AggregationArgs, BooleanAccumToken, Evaluator, EvaluatorFactory, LastBoolean, RuntimeInfo,
+    StateToken, StaticInfo, TwoStacksBooleanAccumToken,
 };
 
 /// Evaluator for the `last` instruction on booleans.
@@ -45,7 +48,9 @@ impl Evaluator for LastBooleanEvaluator {
                 );
                 result
             }
-            AggregationArgs::Sliding { .. } => {
+            AggregationArgs::Sliding { .. }
+            | AggregationArgs::SlidingTime { .. }
+            | AggregationArgs::Session { .. } => {
                 unreachable!("Expected Non-windowed or Since windowed aggregation, saw Sliding.")
             }
         }
@@ -72,6 +77,12 @@ impl EvaluatorFactory for LastBooleanEvaluator {
                 let token = TwoStacksBooleanAccumToken::new();
                 Ok(Box::new(TwoStacksLastBooleanEvaluator { token, args }))
             }
+            AggregationArgs::SlidingTime { .. } => Ok(Box::new(TimeWindowEvaluator::<
+                BooleanInput<LastBoolean>,
+            >::new(args))),
+            AggregationArgs::Session { .. } => Ok(Box::new(SessionEvaluator::<
+                BooleanInput<LastBoolean>,
+            >::new(args))),
         }
     }
 }
@@ -1,6 +1,10 @@
 //! Generic aggregation evaluators.
 
 mod count_evaluator;
+mod session_evaluator;
+mod time_window_evaluator;
 mod two_stacks_count_evaluator;
 
 pub use count_evaluator::*;
+pub(crate) use session_evaluator::*;
+pub(crate) use time_window_evaluator::*;
@@ -8,6 +8,9 @@ use sparrow_kernels::BitBufferIterator;
 use sparrow_plan::ValueRef;
 
 use super::two_stacks_count_evaluator::TwoStacksCountIfEvaluator;
+use crate::evaluators::aggregation::generic::{
+    CountIfInput, SessionEvaluator, TimeWindowEvaluator,
+};
 use crate::{
     AggregationArgs, CountAccumToken, Evaluator, EvaluatorFactory, RuntimeInfo, StateToken,
     StaticInfo, TwoStacksCountAccumToken,
@@ -46,7 +49,9 @@ impl Evaluator for CountIfEvaluator {
                 );
                 result
             }
-            AggregationArgs::Sliding { .. } => {
+            AggregationArgs::Sliding { .. }
+            | AggregationArgs::SlidingTime { .. }
+            | AggregationArgs::Session { .. } => {
                 unreachable!("Expected Non-windowed or Since windowed aggregation, saw Sliding.")
             }
         }
@@ -73,6 +78,12 @@ impl EvaluatorFactory for CountIfEvaluator {
                 let token = TwoStacksCountAccumToken::new();
                 Ok(Box::new(TwoStacksCountIfEvaluator { token, args }))
             }
+            AggregationArgs::SlidingTime { .. } => {
+                Ok(Box::new(TimeWindowEvaluator::<CountIfInput>::new(args)))
+            }
+            AggregationArgs::Session { .. } => {
+                Ok(Box::new(SessionEvaluator::<CountIfInput>::new(args)))
+            }
         }
     }
 }
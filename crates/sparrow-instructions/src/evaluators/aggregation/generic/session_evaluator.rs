@@ -0,0 +1,194 @@
+use anyhow::anyhow;
+use arrow::array::{ArrayRef, TimestampNanosecondArray, UInt32Array};
+use arrow::datatypes::{Int64Type, TimestampNanosecondType};
+use itertools::izip;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sparrow_plan::ValueRef;
+
+use super::TimeWindowInput;
+use crate::{AggFn, AggregationArgs, Evaluator, RuntimeInfo, SessionAccumToken, StateToken};
+
+/// Evaluator for aggregations using a `session` window.
+///
+/// The result at each row is the aggregation of the inputs in the current
+/// session of the entity. A session is closed once more than the gap has
+/// elapsed since its most recent input.
+pub(crate) struct SessionEvaluator<I: TimeWindowInput> {
+    pub args: AggregationArgs<ValueRef>,
+    pub token: SessionAccumToken<I::AggF>,
+}
+
+impl<I> Evaluator for SessionEvaluator<I>
+where
+    I: TimeWindowInput,
+    <I::AggF as AggFn>::AccT: Serialize + DeserializeOwned + Sync,
+{
+    fn evaluate(&mut self, info: &dyn RuntimeInfo) -> anyhow::Result<ArrayRef> {
+        match &self.args {
+            AggregationArgs::Session { input, gap } => {
+                let grouping = info.grouping();
+                let input_vals = info.value(input)?.array_ref()?;
+                let times = info
+                    .time_column()
+                    .primitive_array::<TimestampNanosecondType>()?;
+                let gap = info
+                    .value(gap)?
+                    .try_primitive_literal::<Int64Type>()?
+                    .ok_or_else(|| anyhow!("Expected non-null literal gap"))?;
+                anyhow::ensure!(gap > 0, "Expected positive session gap, saw {:?}", gap);
+
+                Self::aggregate(
+                    &mut self.token,
+                    grouping.num_groups(),
+                    grouping.group_indices(),
+                    times.as_ref(),
+                    &input_vals,
+                    gap,
+                )
+            }
+            AggregationArgs::NoWindow { .. }
+            | AggregationArgs::Since { .. }
+            | AggregationArgs::Sliding { .. }
+            | AggregationArgs::SlidingTime { .. } => {
+                unreachable!(
+                    "Expected session window aggregation, saw non-windowed, since windowed or \
+                     sliding windowed."
+                )
+            }
+        }
+    }
+
+    fn state_token(&self) -> Option<&dyn StateToken> {
+        Some(&self.token)
+    }
+
+    fn state_token_mut(&mut self) -> Option<&mut dyn StateToken> {
+        Some(&mut self.token)
+    }
+}
+
+impl<I: TimeWindowInput> SessionEvaluator<I> {
+    pub(crate) fn new(args: AggregationArgs<ValueRef>) -> Self {
+        Self {
+            args,
+            token: SessionAccumToken::new(),
+        }
+    }
+
+    /// Update the aggregation state with the given inputs and return the
+    /// aggregation.
+    ///
+    /// The `key_capacity` must be greater than all values in the
+    /// `key_indices`.
+    ///
+    /// # Window Behavior
+    /// This aggregation uses the `session` window behavior. The session of an
+    /// entity is closed at the first row more than `gap` nanoseconds after the
+    /// most recent input, and a new session is opened by the next input.
+    ///
+    /// # Assumptions
+    /// This assumes that the input data has been sorted by occurrence time.
+    fn aggregate(
+        token: &mut SessionAccumToken<I::AggF>,
+        key_capacity: usize,
+        key_indices: &UInt32Array,
+        times: &TimestampNanosecondArray,
+        input: &ArrayRef,
+        gap: i64,
+    ) -> anyhow::Result<ArrayRef> {
+        assert_eq!(key_indices.len(), input.len());
+        assert_eq!(times.len(), input.len());
+
+        token.resize(key_capacity);
+
+        let outputs = izip!(key_indices.values(), times.values(), I::inputs(input)?)
+            .map(|(entity_index, time, input)| {
+                let session = token.window_mut(*entity_index);
+                session.close_if_inactive(*time, gap);
+                if let Some(input) = input {
+                    session.add_input(*time, &input);
+                }
+                I::AggF::extract(session.accum_value())
+            })
+            .collect();
+
+        Ok(I::output(outputs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{BooleanArray, Int64Array};
+    use sparrow_core::downcast_primitive_array;
+
+    use super::*;
+    use crate::evaluators::aggregation::generic::{CountIfInput, PrimitiveInput};
+    use crate::Sum;
+
+    #[test]
+    fn test_session_count() {
+        let key_indices = UInt32Array::from(vec![0, 1, 0, 0, 1, 0]);
+        let times = TimestampNanosecondArray::from(vec![0, 1, 5, 10, 11, 20]);
+        let input: ArrayRef = Arc::new(BooleanArray::from(vec![
+            Some(true),
+            Some(true),
+            Some(true),
+            None,
+            Some(true),
+            Some(true),
+        ]));
+        let mut token = SessionAccumToken::new();
+
+        let output = SessionEvaluator::<CountIfInput>::aggregate(
+            &mut token,
+            2,
+            &key_indices,
+            &times,
+            &input,
+            5,
+        )
+        .unwrap();
+        let output =
+            downcast_primitive_array::<arrow::datatypes::UInt32Type>(output.as_ref()).unwrap();
+        // At time 10, the session for entity 0 is still open since the last
+        // input was at time 5. At time 11, entity 1 starts a new session.
+        assert_eq!(output, &UInt32Array::from(vec![1, 1, 2, 2, 1, 1]));
+    }
+
+    #[test]
+    fn test_session_sum_across_batches() {
+        let mut token = SessionAccumToken::new();
+        let key_indices = UInt32Array::from(vec![0, 0]);
+
+        let times = TimestampNanosecondArray::from(vec![0, 3]);
+        let input: ArrayRef = Arc::new(Int64Array::from(vec![Some(1), Some(2)]));
+        let output = SessionEvaluator::<PrimitiveInput<Sum<Int64Type>>>::aggregate(
+            &mut token,
+            1,
+            &key_indices,
+            &times,
+            &input,
+            3,
+        )
+        .unwrap();
+        let output = downcast_primitive_array::<Int64Type>(output.as_ref()).unwrap();
+        assert_eq!(output, &Int64Array::from(vec![Some(1), Some(3)]));
+
+        let times = TimestampNanosecondArray::from(vec![6, 10]);
+        let input: ArrayRef = Arc::new(Int64Array::from(vec![None, Some(4)]));
+        let output = SessionEvaluator::<PrimitiveInput<Sum<Int64Type>>>::aggregate(
+            &mut token,
+            1,
+            &key_indices,
+            &times,
+            &input,
+            3,
+        )
+        .unwrap();
+        let output = downcast_primitive_array::<Int64Type>(output.as_ref()).unwrap();
+        assert_eq!(output, &Int64Array::from(vec![Some(3), Some(4)]));
+    }
+}
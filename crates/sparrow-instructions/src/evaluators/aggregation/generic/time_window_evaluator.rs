@@ -0,0 +1,404 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use arrow::array::{
+    ArrayRef, BooleanArray, PrimitiveArray, StringArray, TimestampNanosecondArray, UInt32Array,
+};
+use arrow::datatypes::{Int64Type, TimestampNanosecondType};
+use itertools::izip;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sparrow_core::{downcast_boolean_array, downcast_primitive_array, downcast_string_array};
+use sparrow_plan::ValueRef;
+
+use crate::{
+    AggFn, AggregationArgs, ArrowAggFn, Count, Evaluator, RuntimeInfo, StateToken,
+    TimeWindowAccumToken,
+};
+
+/// Converts between Arrow arrays and the inputs and outputs of an aggregation
+/// function.
+///
+/// This allows the [TimeWindowEvaluator] to be shared by aggregations over
+/// every type of input.
+pub(crate) trait TimeWindowInput: Send + Sync + 'static {
+    type AggF: AggFn;
+
+    /// Return the input to add for each row, or `None` if nothing is added.
+    fn inputs(input: &ArrayRef) -> anyhow::Result<Vec<Option<<Self::AggF as AggFn>::InT>>>;
+
+    /// Return the array containing the output for each row.
+    fn output(outputs: Vec<Option<<Self::AggF as AggFn>::OutT>>) -> ArrayRef;
+}
+
+/// Inputs to `count_if`, where each `true` input is counted.
+pub(crate) struct CountIfInput;
+
+impl TimeWindowInput for CountIfInput {
+    type AggF = Count;
+
+    fn inputs(input: &ArrayRef) -> anyhow::Result<Vec<Option<u32>>> {
+        let input = downcast_boolean_array(input.as_ref())?;
+        Ok(input
+            .iter()
+            .map(|input| (input == Some(true)).then_some(1))
+            .collect())
+    }
+
+    fn output(outputs: Vec<Option<u32>>) -> ArrayRef {
+        Arc::new(UInt32Array::from(outputs))
+    }
+}
+
+/// Inputs to aggregations over primitive arrays.
+pub(crate) struct PrimitiveInput<AggF>(PhantomData<fn() -> AggF>);
+
+impl<AggF> TimeWindowInput for PrimitiveInput<AggF>
+where
+    AggF: ArrowAggFn + 'static,
+{
+    type AggF = AggF;
+
+    fn inputs(input: &ArrayRef) -> anyhow::Result<Vec<Option<AggF::InT>>> {
+        let input = downcast_primitive_array::<AggF::InArrowT>(input.as_ref())?;
+        Ok(input.iter().collect())
+    }
+
+    fn output(outputs: Vec<Option<AggF::OutT>>) -> ArrayRef {
+        Arc::new(PrimitiveArray::<AggF::OutArrowT>::from_iter(outputs))
+    }
+}
+
+/// Inputs to aggregations over string arrays.
+pub(crate) struct StringInput<AggF>(PhantomData<fn() -> AggF>);
+
+impl<AggF> TimeWindowInput for StringInput<AggF>
+where
+    AggF: AggFn<InT = String, OutT = String> + 'static,
+{
+    type AggF = AggF;
+
+    fn inputs(input: &ArrayRef) -> anyhow::Result<Vec<Option<String>>> {
+        let input: &StringArray = downcast_string_array(input.as_ref())?;
+        Ok(input.iter().map(|input| input.map(str::to_owned)).collect())
+    }
+
+    fn output(outputs: Vec<Option<String>>) -> ArrayRef {
+        Arc::new(outputs.into_iter().collect::<StringArray>())
+    }
+}
+
+/// Inputs to aggregations over boolean arrays.
+pub(crate) struct BooleanInput<AggF>(PhantomData<fn() -> AggF>);
+
+impl<AggF> TimeWindowInput for BooleanInput<AggF>
+where
+    AggF: AggFn<InT = bool, OutT = bool> + 'static,
+{
+    type AggF = AggF;
+
+    fn inputs(input: &ArrayRef) -> anyhow::Result<Vec<Option<bool>>> {
+        let input = downcast_boolean_array(input.as_ref())?;
+        Ok(input.iter().collect())
+    }
+
+    fn output(outputs: Vec<Option<bool>>) -> ArrayRef {
+        Arc::new(BooleanArray::from(outputs))
+    }
+}
+
+/// Evaluator for aggregations using a time-based `sliding` window.
+///
+/// Each input is evicted from the window once it is older than the duration
+/// of the window, so the result at each row is the aggregation of the inputs
+/// for the same entity within `(time - duration, time]`.
+pub(crate) struct TimeWindowEvaluator<I: TimeWindowInput> {
+    pub args: AggregationArgs<ValueRef>,
+    pub token: TimeWindowAccumToken<I::AggF>,
+}
+
+impl<I> Evaluator for TimeWindowEvaluator<I>
+where
+    I: TimeWindowInput,
+    <I::AggF as AggFn>::AccT: Serialize + DeserializeOwned + Sync,
+{
+    fn evaluate(&mut self, info: &dyn RuntimeInfo) -> anyhow::Result<ArrayRef> {
+        match &self.args {
+            AggregationArgs::SlidingTime { input, duration } => {
+                let grouping = info.grouping();
+                let input_vals = info.value(input)?.array_ref()?;
+                let times = info
+                    .time_column()
+                    .primitive_array::<TimestampNanosecondType>()?;
+                let duration = info
+                    .value(duration)?
+                    .try_primitive_literal::<Int64Type>()?
+                    .ok_or_else(|| anyhow!("Expected non-null literal duration"))?;
+                if duration <= 0 {
+                    anyhow::bail!(
+                        "Expected positive duration for sliding window, saw {:?}",
+                        duration
+                    );
+                }
+
+                Self::aggregate(
+                    &mut self.token,
+                    grouping.num_groups(),
+                    grouping.group_indices(),
+                    times.as_ref(),
+                    &input_vals,
+                    duration,
+                )
+            }
+            AggregationArgs::NoWindow { .. }
+            | AggregationArgs::Since { .. }
+            | AggregationArgs::Sliding { .. }
+            | AggregationArgs::Session { .. } => {
+                unreachable!(
+                    "Expected time-based sliding window aggregation, saw non-windowed, since \
+                     windowed, sliding windowed or session windowed."
+                )
+            }
+        }
+    }
+
+    fn state_token(&self) -> Option<&dyn StateToken> {
+        Some(&self.token)
+    }
+
+    fn state_token_mut(&mut self) -> Option<&mut dyn StateToken> {
+        Some(&mut self.token)
+    }
+}
+
+impl<I: TimeWindowInput> TimeWindowEvaluator<I> {
+    pub(crate) fn new(args: AggregationArgs<ValueRef>) -> Self {
+        Self {
+            args,
+            token: TimeWindowAccumToken::new(),
+        }
+    }
+
+    /// Update the aggregation state with the given inputs and return the
+    /// aggregation.
+    ///
+    /// The `key_capacity` must be greater than all values in the
+    /// `key_indices`.
+    ///
+    /// # Window Behavior
+    /// This aggregation uses the time-based `sliding` window behavior. Inputs
+    /// are evicted when they are `duration` nanoseconds older than the row
+    /// being computed, resulting in exclusive start bounds and inclusive end
+    /// bounds.
+    ///
+    /// # Assumptions
+    /// This assumes that the input data has been sorted by occurrence time.
+    fn aggregate(
+        token: &mut TimeWindowAccumToken<I::AggF>,
+        key_capacity: usize,
+        key_indices: &UInt32Array,
+        times: &TimestampNanosecondArray,
+        input: &ArrayRef,
+        duration: i64,
+    ) -> anyhow::Result<ArrayRef> {
+        assert_eq!(key_indices.len(), input.len());
+        assert_eq!(times.len(), input.len());
+
+        token.resize(key_capacity);
+
+        let outputs = izip!(key_indices.values(), times.values(), I::inputs(input)?)
+            .map(|(entity_index, time, input)| {
+                token
+                    .window_mut(*entity_index)
+                    .evict_until(time.saturating_sub(duration));
+                if let Some(input) = input {
+                    token.add_input(*entity_index, *time, &input);
+                }
+                I::AggF::extract(&token.window_mut(*entity_index).accum_value())
+            })
+            .collect();
+
+        // Later rows (including the final results) occur no earlier than the
+        // last row of this batch, so inputs older than the window preceding it
+        // may be evicted from every entity. Otherwise, the state of entities
+        // without new rows would never be evicted. The token only visits the
+        // windows which have inputs to evict.
+        if let Some(last_time) = times.values().last() {
+            token.evict_until(last_time.saturating_sub(duration));
+        }
+
+        Ok(I::output(outputs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Array, Int64Array};
+    use sparrow_core::downcast_primitive_array;
+
+    use super::*;
+    use crate::{LastString, Sum};
+
+    #[test]
+    fn test_time_window_count() {
+        let key_indices = UInt32Array::from(vec![0, 1, 0, 0, 1, 0]);
+        let times = TimestampNanosecondArray::from(vec![0, 1, 5, 10, 11, 20]);
+        let input: ArrayRef = Arc::new(BooleanArray::from(vec![
+            Some(true),
+            Some(true),
+            Some(true),
+            None,
+            Some(false),
+            Some(true),
+        ]));
+        let mut token = TimeWindowAccumToken::new();
+
+        let output = TimeWindowEvaluator::<CountIfInput>::aggregate(
+            &mut token,
+            2,
+            &key_indices,
+            &times,
+            &input,
+            10,
+        )
+        .unwrap();
+        let output =
+            downcast_primitive_array::<arrow::datatypes::UInt32Type>(output.as_ref()).unwrap();
+        // At time 10, the input at time 0 has left the window.
+        // At time 11, the input at time 1 has left the window.
+        assert_eq!(output, &UInt32Array::from(vec![1, 1, 2, 1, 0, 1]));
+    }
+
+    #[test]
+    fn test_time_window_sum_across_batches() {
+        let mut token = TimeWindowAccumToken::new();
+        let key_indices = UInt32Array::from(vec![0, 0, 0]);
+
+        let times = TimestampNanosecondArray::from(vec![0, 3, 6]);
+        let input: ArrayRef = Arc::new(Int64Array::from(vec![Some(1), None, Some(2)]));
+        let output = TimeWindowEvaluator::<PrimitiveInput<Sum<Int64Type>>>::aggregate(
+            &mut token,
+            1,
+            &key_indices,
+            &times,
+            &input,
+            5,
+        )
+        .unwrap();
+        let output = downcast_primitive_array::<Int64Type>(output.as_ref()).unwrap();
+        assert_eq!(output, &Int64Array::from(vec![Some(1), Some(1), Some(2)]));
+
+        let times = TimestampNanosecondArray::from(vec![7, 11, 20]);
+        let input: ArrayRef = Arc::new(Int64Array::from(vec![Some(4), None, None]));
+        let output = TimeWindowEvaluator::<PrimitiveInput<Sum<Int64Type>>>::aggregate(
+            &mut token,
+            1,
+            &key_indices,
+            &times,
+            &input,
+            5,
+        )
+        .unwrap();
+        let output = downcast_primitive_array::<Int64Type>(output.as_ref()).unwrap();
+        assert_eq!(output, &Int64Array::from(vec![Some(6), Some(4), None]));
+    }
+
+    #[test]
+    fn test_time_window_evicts_entities_without_rows() {
+        let mut token = TimeWindowAccumToken::new();
+        let key_indices = UInt32Array::from(vec![0, 1]);
+        let times = TimestampNanosecondArray::from(vec![0, 1]);
+        let input: ArrayRef = Arc::new(Int64Array::from(vec![Some(1), Some(2)]));
+        TimeWindowEvaluator::<PrimitiveInput<Sum<Int64Type>>>::aggregate(
+            &mut token,
+            2,
+            &key_indices,
+            &times,
+            &input,
+            5,
+        )
+        .unwrap();
+        assert_eq!(token.window_mut(1).accum_value(), Some(2));
+
+        // Entity 1 has no rows in the second batch, but its input is no
+        // longer within the window of any later row.
+        let key_indices = UInt32Array::from(vec![0]);
+        let times = TimestampNanosecondArray::from(vec![6]);
+        let input: ArrayRef = Arc::new(Int64Array::from(vec![Some(3)]));
+        let output = TimeWindowEvaluator::<PrimitiveInput<Sum<Int64Type>>>::aggregate(
+            &mut token,
+            2,
+            &key_indices,
+            &times,
+            &input,
+            5,
+        )
+        .unwrap();
+        let output = downcast_primitive_array::<Int64Type>(output.as_ref()).unwrap();
+        assert_eq!(output, &Int64Array::from(vec![Some(3)]));
+        assert_eq!(token.window_mut(0).accum_value(), Some(3));
+        assert_eq!(token.window_mut(1).accum_value(), None);
+    }
+
+    #[test]
+    fn test_time_window_evicts_only_expired_parts() {
+        let mut token = TimeWindowAccumToken::new();
+        let key_indices = UInt32Array::from_iter_values(0..100);
+        let times = TimestampNanosecondArray::from_iter_values(0..100);
+        let input: ArrayRef = Arc::new(Int64Array::from_iter_values(0..100));
+        TimeWindowEvaluator::<PrimitiveInput<Sum<Int64Type>>>::aggregate(
+            &mut token,
+            100,
+            &key_indices,
+            &times,
+            &input,
+            10,
+        )
+        .unwrap();
+
+        // The inputs at or before time 89 were evicted after the batch, and
+        // only the parts within the window remain to be evicted.
+        assert_eq!(token.num_parts(), 10);
+        assert_eq!(token.window_mut(89).accum_value(), None);
+        assert_eq!(token.window_mut(90).accum_value(), Some(90));
+
+        let key_indices = UInt32Array::from(vec![0]);
+        let times = TimestampNanosecondArray::from(vec![105]);
+        let input: ArrayRef = Arc::new(Int64Array::from(vec![Some(1)]));
+        TimeWindowEvaluator::<PrimitiveInput<Sum<Int64Type>>>::aggregate(
+            &mut token,
+            100,
+            &key_indices,
+            &times,
+            &input,
+            10,
+        )
+        .unwrap();
+        assert_eq!(token.num_parts(), 5);
+        assert_eq!(token.window_mut(95).accum_value(), None);
+        assert_eq!(token.window_mut(96).accum_value(), Some(96));
+        assert_eq!(token.window_mut(0).accum_value(), Some(1));
+    }
+
+    #[test]
+    fn test_time_window_last_string() {
+        let mut token = TimeWindowAccumToken::new();
+        let key_indices = UInt32Array::from(vec![0, 0, 0]);
+        let times = TimestampNanosecondArray::from(vec![0, 2, 4]);
+        let input: ArrayRef = Arc::new(StringArray::from(vec![Some("a"), None, None]));
+
+        let output = TimeWindowEvaluator::<StringInput<LastString>>::aggregate(
+            &mut token,
+            1,
+            &key_indices,
+            &times,
+            &input,
+            3,
+        )
+        .unwrap();
+        let output: &StringArray = downcast_string_array(output.as_ref()).unwrap();
+        assert_eq!(output, &StringArray::from(vec![Some("a"), Some("a"), None]));
+        assert_eq!(output.null_count(), 1);
+    }
+}
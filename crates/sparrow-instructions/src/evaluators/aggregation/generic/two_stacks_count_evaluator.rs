@@ -48,7 +48,10 @@ impl Evaluator for TwoStacksCountIfEvaluator {
 
                 result
             }
-            AggregationArgs::Since { .. } | AggregationArgs::NoWindow { .. } => {
+            AggregationArgs::Since { .. }
+            | AggregationArgs::NoWindow { .. }
+            | AggregationArgs::SlidingTime { .. }
+            | AggregationArgs::Session { .. } => {
                 unreachable!(
                     "Expected sliding-windowed aggregation, saw non-windowed or since windowed."
                 )
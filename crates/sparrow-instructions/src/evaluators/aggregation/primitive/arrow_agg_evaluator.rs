@@ -10,6 +10,9 @@ use sparrow_plan::ValueRef;
 
 use super::two_stacks_arrow_agg_evaluator::TwoStacksArrowAggEvaluator;
 use crate::evaluators::aggregation::function::agg_fn::ArrowAggFn;
+use crate::evaluators::aggregation::generic::{
+    PrimitiveInput, SessionEvaluator, TimeWindowEvaluator,
+};
 use crate::{
     AggregationArgs, Evaluator, EvaluatorFactory, PrimitiveAccumToken, RuntimeInfo, StateToken,
     StaticInfo, TwoStacksPrimitiveAccumToken,
@@ -75,7 +78,9 @@ where
 
                 result
             }
-            AggregationArgs::Sliding { .. } => {
+            AggregationArgs::Sliding { .. }
+            | AggregationArgs::SlidingTime { .. }
+            | AggregationArgs::Session { .. } => {
                 unreachable!("Expected Non-windowed or Since windowed aggregation, saw Sliding.")
             }
         }
@@ -106,6 +111,12 @@ where
                 let token = TwoStacksPrimitiveAccumToken::new();
                 Ok(Box::new(TwoStacksArrowAggEvaluator::<AggF> { token, args }))
             }
+            AggregationArgs::SlidingTime { .. } => Ok(Box::new(TimeWindowEvaluator::<
+                PrimitiveInput<AggF>,
+            >::new(args))),
+            AggregationArgs::Session { .. } => Ok(Box::new(
+                SessionEvaluator::<PrimitiveInput<AggF>>::new(args),
+            )),
         }
     }
 }
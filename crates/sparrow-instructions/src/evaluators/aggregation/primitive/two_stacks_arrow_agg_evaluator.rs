@@ -74,7 +74,10 @@ where
 
                 result
             }
-            AggregationArgs::NoWindow { .. } | AggregationArgs::Since { .. } => {
+            AggregationArgs::NoWindow { .. }
+            | AggregationArgs::Since { .. }
+            | AggregationArgs::SlidingTime { .. }
+            | AggregationArgs::Session { .. } => {
                 unreachable!(
                     "Expected Sliding Window aggregation, saw Non-windowed or Since window \
                      aggregation."
@@ -0,0 +1,110 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::aggregation::function::AggFn;
+
+/// Accumulator for session windows.
+///
+/// A session contains the inputs for an entity which each occurred within
+/// the gap of the previous input. Once the gap has elapsed without an input
+/// the session is closed and the accumulator is reset.
+///
+/// The bound indicates that the serde on this struct requires only
+/// `AggF::AccT` to implement `Serialize` and `DeserializeOwned`.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "AggF: AggFn, AggF::AccT: Serialize",
+    deserialize = "AggF: AggFn, AggF::AccT: DeserializeOwned"
+))]
+pub struct SessionWindow<AggF: AggFn> {
+    /// The time of the most recent input in the session, if it is open.
+    last_input: Option<i64>,
+    accum: AggF::AccT,
+}
+
+impl<AggF: AggFn> std::fmt::Debug for SessionWindow<AggF> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionWindow")
+            .field("last_input", &self.last_input)
+            .field("accum", &self.accum)
+            .finish()
+    }
+}
+
+impl<AggF> Clone for SessionWindow<AggF>
+where
+    AggF: AggFn,
+    AggF::AccT: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            last_input: self.last_input,
+            accum: self.accum.clone(),
+        }
+    }
+}
+
+impl<AggF: AggFn> Default for SessionWindow<AggF> {
+    fn default() -> Self {
+        Self {
+            last_input: None,
+            accum: AggF::zero(),
+        }
+    }
+}
+
+impl<AggF: AggFn> SessionWindow<AggF> {
+    /// Returns the aggregate value of the inputs within the session.
+    pub fn accum_value(&self) -> &AggF::AccT {
+        &self.accum
+    }
+
+    /// Closes the session if more than `gap` has elapsed between the last
+    /// input and `time`.
+    pub fn close_if_inactive(&mut self, time: i64, gap: i64) {
+        if let Some(last_input) = self.last_input {
+            if time.saturating_sub(last_input) > gap {
+                self.last_input = None;
+                self.accum = AggF::zero();
+            }
+        }
+    }
+
+    /// Adds an input occurring at `time` to the session, opening a new session
+    /// if needed.
+    ///
+    /// Inputs must be added in non-decreasing order of time.
+    pub fn add_input(&mut self, time: i64, input: &AggF::InT) {
+        debug_assert!(self.last_input.map_or(true, |last| last <= time));
+        self.last_input = Some(time);
+        AggF::add_one(&mut self.accum, input);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::datatypes::Int64Type;
+
+    use super::*;
+    use crate::Sum;
+
+    #[test]
+    fn test_session_window_sum() {
+        let mut window = SessionWindow::<Sum<Int64Type>>::default();
+        assert_eq!(window.accum_value(), &None);
+
+        window.add_input(1, &1);
+        window.close_if_inactive(3, 2);
+        window.add_input(3, &2);
+        assert_eq!(window.accum_value(), &Some(3));
+
+        // Exactly `gap` after the last input is still within the session.
+        window.close_if_inactive(5, 2);
+        assert_eq!(window.accum_value(), &Some(3));
+
+        window.close_if_inactive(6, 2);
+        assert_eq!(window.accum_value(), &None);
+        window.add_input(6, &10);
+        assert_eq!(window.accum_value(), &Some(10));
+    }
+}
@@ -7,8 +7,9 @@ use sparrow_kernels::BitBufferIterator;
 use sparrow_plan::ValueRef;
 
 use super::two_stacks_first_string_evaluator::TwoStacksFirstStringEvaluator;
+use crate::evaluators::aggregation::generic::{SessionEvaluator, StringInput, TimeWindowEvaluator};
 use crate::{
-    AggregationArgs, Evaluator, EvaluatorFactory, RuntimeInfo, StateToken, StaticInfo,
+    AggregationArgs, Evaluator, EvaluatorFactory, FirstString, RuntimeInfo, StateToken, StaticInfo,
     StringAccumToken, TwoStacksStringAccumToken,
 };
 
@@ -47,7 +48,9 @@ impl Evaluator for FirstStringEvaluator {
 
                 result
             }
-            AggregationArgs::Sliding { .. } => {
+            AggregationArgs::Sliding { .. }
+            | AggregationArgs::SlidingTime { .. }
+            | AggregationArgs::Session { .. } => {
                 unreachable!("Expected Non-windowed or Since windowed aggregation, saw Sliding.")
             }
         }
@@ -74,6 +77,12 @@ impl EvaluatorFactory for FirstStringEvaluator {
                 let token = TwoStacksStringAccumToken::new();
                 Ok(Box::new(TwoStacksFirstStringEvaluator { token, args }))
             }
+            AggregationArgs::SlidingTime { .. } => Ok(Box::new(TimeWindowEvaluator::<
+                StringInput<FirstString>,
+            >::new(args))),
+            AggregationArgs::Session { .. } => Ok(Box::new(SessionEvaluator::<
+                StringInput<FirstString>,
+            >::new(args))),
         }
     }
 }
@@ -7,8 +7,9 @@ use sparrow_kernels::BitBufferIterator;
 use sparrow_plan::ValueRef;
 
 use super::two_stacks_last_string_evaluator::TwoStacksLastStringEvaluator;
+use crate::evaluators::aggregation::generic::{SessionEvaluator, StringInput, TimeWindowEvaluator};
 use crate::{
-    AggregationArgs, Evaluator, EvaluatorFactory, RuntimeInfo, StateToken, StaticInfo,
+    AggregationArgs, Evaluator, EvaluatorFactory, LastString, RuntimeInfo, StateToken, StaticInfo,
     StringAccumToken, TwoStacksStringAccumToken,
 };
 
@@ -47,7 +48,9 @@ impl Evaluator for LastStringEvaluator {
 
                 result
             }
-            AggregationArgs::Sliding { .. } => {
+            AggregationArgs::Sliding { .. }
+            | AggregationArgs::SlidingTime { .. }
+            | AggregationArgs::Session { .. } => {
                 unreachable!("Expected Non-windowed or Since windowed aggregation, saw Sliding.")
             }
         }
@@ -74,6 +77,12 @@ impl EvaluatorFactory for LastStringEvaluator {
                 let token = TwoStacksStringAccumToken::new();
                 Ok(Box::new(TwoStacksLastStringEvaluator { token, args }))
             }
+            AggregationArgs::SlidingTime { .. } => Ok(Box::new(TimeWindowEvaluator::<
+                StringInput<LastString>,
+            >::new(args))),
+            AggregationArgs::Session { .. } => Ok(Box::new(SessionEvaluator::<
+                StringInput<LastString>,
+            >::new(args))),
         }
     }
 }
@@ -0,0 +1,211 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::aggregation::function::AggFn;
+
+/// The inputs to a time-based window which occurred at the same time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TimedPart<AccT> {
+    /// The time of the inputs in this part.
+    time: i64,
+    accum: AccT,
+    cumulative: AccT,
+}
+
+/// Accumulator for time-based sliding windows.
+///
+/// This uses the same two stacks approach as [crate::TwoStacks], but each
+/// window part contains the inputs at a single time. Rather than evicting a
+/// part each time the window slides, parts are evicted once they fall out of
+/// the window. This allows computing the exact aggregation over the inputs
+/// within a duration of the current time, with amortized constant cost per
+/// input.
+///
+/// The bound indicates that the serde on this struct requires only
+/// `AggF::AccT` to implement `Serialize` and `DeserializeOwned`.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "AggF: AggFn, AggF::AccT: Serialize",
+    deserialize = "AggF: AggFn, AggF::AccT: DeserializeOwned"
+))]
+pub struct TimeWindow<AggF: AggFn> {
+    /// Window parts that haven't been "flipped". Newest at the end.
+    incoming: Vec<TimedPart<AggF::AccT>>,
+    /// Window parts that have been "flipped". Oldest at the end.
+    outgoing: Vec<TimedPart<AggF::AccT>>,
+}
+
+impl<AggF: AggFn> std::fmt::Debug for TimeWindow<AggF> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TimeWindow")
+            .field("incoming", &self.incoming)
+            .field("outgoing", &self.outgoing)
+            .finish()
+    }
+}
+
+impl<AggF> Clone for TimeWindow<AggF>
+where
+    AggF: AggFn,
+    AggF::AccT: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            incoming: self.incoming.clone(),
+            outgoing: self.outgoing.clone(),
+        }
+    }
+}
+
+impl<AggF: AggFn> Default for TimeWindow<AggF> {
+    fn default() -> Self {
+        Self {
+            incoming: Vec::new(),
+            outgoing: Vec::new(),
+        }
+    }
+}
+
+impl<AggF: AggFn> TimeWindow<AggF> {
+    /// Returns the aggregate value of the inputs within the window.
+    pub fn accum_value(&self) -> AggF::AccT {
+        // Note the `outgoing` stack contains values occurring earlier than the
+        // `incoming` stack, so we merge the `incoming` into the `outgoing`.
+        match (self.outgoing.last(), self.incoming.last()) {
+            (Some(outgoing), Some(incoming)) => {
+                let mut accum = outgoing.cumulative.clone();
+                AggF::merge(&mut accum, &incoming.cumulative);
+                accum
+            }
+            (Some(part), None) | (None, Some(part)) => part.cumulative.clone(),
+            (None, None) => AggF::zero(),
+        }
+    }
+
+    /// Returns the time of the newest input within the window, if any.
+    pub fn newest_time(&self) -> Option<i64> {
+        self.incoming
+            .last()
+            .or_else(|| self.outgoing.first())
+            .map(|part| part.time)
+    }
+
+    /// Returns the times of the inputs within the window.
+    ///
+    /// Each time is returned once, regardless of the inputs at that time.
+    pub fn part_times(&self) -> impl Iterator<Item = i64> + '_ {
+        self.outgoing
+            .iter()
+            .chain(self.incoming.iter())
+            .map(|part| part.time)
+    }
+
+    /// Adds an input occurring at `time`.
+    ///
+    /// Inputs must be added in non-decreasing order of time.
+    pub fn add_input(&mut self, time: i64, input: &AggF::InT) {
+        match self.incoming.last_mut() {
+            Some(part) if part.time == time => {
+                AggF::add_one(&mut part.accum, input);
+                AggF::add_one(&mut part.cumulative, input);
+            }
+            last => {
+                debug_assert!(last.map_or(true, |part| part.time < time));
+                let mut cumulative = match last {
+                    Some(part) => part.cumulative.clone(),
+                    None => AggF::zero(),
+                };
+                AggF::add_one(&mut cumulative, input);
+                self.incoming.push(TimedPart {
+                    time,
+                    accum: AggF::one(input),
+                    cumulative,
+                });
+            }
+        }
+    }
+
+    /// Evicts the inputs occurring at or before `time`.
+    pub fn evict_until(&mut self, time: i64) {
+        loop {
+            if self.outgoing.is_empty() {
+                // Only flip if the oldest incoming part needs to be evicted.
+                match self.incoming.first() {
+                    Some(part) if part.time <= time => self.flip(),
+                    _ => return,
+                }
+            }
+
+            match self.outgoing.last() {
+                Some(part) if part.time <= time => {
+                    self.outgoing.pop();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn flip(&mut self) {
+        debug_assert!(self.outgoing.is_empty());
+        std::mem::swap(&mut self.incoming, &mut self.outgoing);
+        self.outgoing.reverse();
+
+        // Fix up the cumulatives to reflect the new reversed order.
+        // Each item should be the sum of its accumulator and the cumulative
+        // values below it.
+        let mut accum = AggF::zero();
+        for outgoing in &mut self.outgoing {
+            AggF::merge(&mut accum, &outgoing.accum);
+            outgoing.cumulative = accum.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::datatypes::Int64Type;
+
+    use super::*;
+    use crate::{Max, Sum};
+
+    #[test]
+    fn test_time_window_sum() {
+        let mut window = TimeWindow::<Sum<Int64Type>>::default();
+        assert_eq!(window.accum_value(), None);
+
+        window.add_input(1, &1);
+        window.add_input(2, &2);
+        window.add_input(2, &3);
+        assert_eq!(window.accum_value(), Some(6));
+
+        window.evict_until(0);
+        assert_eq!(window.accum_value(), Some(6));
+        window.evict_until(1);
+        assert_eq!(window.accum_value(), Some(5));
+
+        window.add_input(5, &10);
+        assert_eq!(window.accum_value(), Some(15));
+        window.evict_until(4);
+        assert_eq!(window.accum_value(), Some(10));
+        window.evict_until(5);
+        assert_eq!(window.accum_value(), None);
+    }
+
+    #[test]
+    fn test_time_window_max_evicts_oldest() {
+        let mut window = TimeWindow::<Max<Int64Type>>::default();
+        window.add_input(1, &10);
+        window.add_input(2, &5);
+        window.add_input(3, &7);
+        assert_eq!(window.accum_value(), Some(10));
+
+        window.evict_until(1);
+        assert_eq!(window.accum_value(), Some(7));
+
+        // Adding after a flip merges the incoming and outgoing stacks.
+        window.add_input(4, &6);
+        assert_eq!(window.accum_value(), Some(7));
+        window.evict_until(3);
+        assert_eq!(window.accum_value(), Some(6));
+    }
+}
@@ -4,7 +4,9 @@ mod boolean_accum_token;
 mod count_accum_token;
 pub mod lag_token;
 mod primitive_accum_token;
+mod session_accum_token;
 mod string_accum_token;
+mod time_window_accum_token;
 mod two_stacks_boolean_accum_token;
 mod two_stacks_count_accum_token;
 mod two_stacks_primitive_accum_token;
@@ -13,7 +15,9 @@ mod two_stacks_string_accum_token;
 pub use boolean_accum_token::*;
 pub use count_accum_token::*;
 pub use primitive_accum_token::*;
+pub use session_accum_token::*;
 pub use string_accum_token::*;
+pub use time_window_accum_token::*;
 pub use two_stacks_boolean_accum_token::*;
 pub use two_stacks_count_accum_token::*;
 pub use two_stacks_primitive_accum_token::*;
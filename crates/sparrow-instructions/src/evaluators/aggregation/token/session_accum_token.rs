@@ -0,0 +1,45 @@
+use crate::{AggFn, ComputeStore, SessionWindow, StateToken, StoreKey};
+
+/// Key used for accumulators of session windows.
+///
+/// Stored as `[pass_id, inst_id] -> Vec<SessionWindow<AggF>>`
+pub struct SessionAccumToken<AggF>
+where
+    AggF: AggFn,
+{
+    /// Stores the state for in-memory usage.
+    accum: Vec<SessionWindow<AggF>>,
+}
+
+impl<AggF> StateToken for SessionAccumToken<AggF>
+where
+    AggF: AggFn,
+    Vec<SessionWindow<AggF>>: serde::ser::Serialize + serde::de::DeserializeOwned,
+{
+    fn restore(&mut self, key: &StoreKey, store: &ComputeStore) -> anyhow::Result<()> {
+        store.get_to_vec(key, &mut self.accum)
+    }
+
+    fn store(&self, key: &StoreKey, store: &ComputeStore) -> anyhow::Result<()> {
+        store.put(key, &self.accum)
+    }
+}
+
+impl<AggF> SessionAccumToken<AggF>
+where
+    AggF: AggFn,
+{
+    pub(crate) fn new() -> Self {
+        Self { accum: Vec::new() }
+    }
+
+    pub(crate) fn resize(&mut self, len: usize) {
+        if len > self.accum.len() {
+            self.accum.resize(len, SessionWindow::default());
+        }
+    }
+
+    pub(crate) fn window_mut(&mut self, entity_index: u32) -> &mut SessionWindow<AggF> {
+        &mut self.accum[entity_index as usize]
+    }
+}
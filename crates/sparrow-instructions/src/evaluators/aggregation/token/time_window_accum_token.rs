@@ -0,0 +1,104 @@
+use std::collections::VecDeque;
+
+use crate::{AggFn, ComputeStore, StateToken, StoreKey, TimeWindow};
+
+/// Key used for accumulators of time-based sliding windows.
+///
+/// Stored as `[pass_id, inst_id] -> Vec<TimeWindow<AggF>>`
+pub struct TimeWindowAccumToken<AggF>
+where
+    AggF: AggFn,
+{
+    /// Stores the state for in-memory usage.
+    accum: Vec<TimeWindow<AggF>>,
+    /// The time of each window part and the index of its entity, oldest first.
+    ///
+    /// Inputs are added in order of time, so this is ordered by time. It
+    /// allows evicting from only the windows with parts to evict. Parts
+    /// which have already been evicted from the window are removed once
+    /// their time is reached.
+    ///
+    /// This isn't stored, since it is determined by the windows.
+    parts: VecDeque<(i64, u32)>,
+}
+
+impl<AggF> StateToken for TimeWindowAccumToken<AggF>
+where
+    AggF: AggFn,
+    Vec<TimeWindow<AggF>>: serde::ser::Serialize + serde::de::DeserializeOwned,
+{
+    fn restore(&mut self, key: &StoreKey, store: &ComputeStore) -> anyhow::Result<()> {
+        store.get_to_vec(key, &mut self.accum)?;
+
+        let mut parts: Vec<_> = self
+            .accum
+            .iter()
+            .enumerate()
+            .flat_map(|(entity_index, window)| {
+                window
+                    .part_times()
+                    .map(move |time| (time, entity_index as u32))
+            })
+            .collect();
+        parts.sort_unstable();
+        self.parts = parts.into();
+        Ok(())
+    }
+
+    fn store(&self, key: &StoreKey, store: &ComputeStore) -> anyhow::Result<()> {
+        store.put(key, &self.accum)
+    }
+}
+
+impl<AggF> TimeWindowAccumToken<AggF>
+where
+    AggF: AggFn,
+{
+    pub(crate) fn new() -> Self {
+        Self {
+            accum: Vec::new(),
+            parts: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn resize(&mut self, len: usize) {
+        if len > self.accum.len() {
+            self.accum.resize(len, TimeWindow::default());
+        }
+    }
+
+    pub(crate) fn window_mut(&mut self, entity_index: u32) -> &mut TimeWindow<AggF> {
+        &mut self.accum[entity_index as usize]
+    }
+
+    /// Adds an input occurring at `time` to the window of the entity.
+    ///
+    /// Inputs must be added in non-decreasing order of time.
+    pub(crate) fn add_input(&mut self, entity_index: u32, time: i64, input: &AggF::InT) {
+        let window = &mut self.accum[entity_index as usize];
+        if window.newest_time() != Some(time) {
+            debug_assert!(self.parts.back().map_or(true, |(last, _)| *last <= time));
+            self.parts.push_back((time, entity_index));
+        }
+        window.add_input(time, input);
+    }
+
+    /// Evicts the inputs occurring at or before `time` from every window.
+    ///
+    /// Only the windows containing inputs at or before `time` are visited.
+    pub(crate) fn evict_until(&mut self, time: i64) {
+        while let Some((part_time, entity_index)) = self.parts.front() {
+            if *part_time > time {
+                break;
+            }
+            self.accum[*entity_index as usize].evict_until(time);
+            self.parts.pop_front();
+        }
+    }
+
+    /// Return the number of window parts which may need to be evicted.
+    #[cfg(test)]
+    pub(crate) fn num_parts(&self) -> usize {
+        self.parts.len()
+    }
+}
@@ -5,6 +5,8 @@ use smallvec::SmallVec;
 /// Currently used keys:
 ///
 /// - `met` for the max event time in the snapshot.
+/// - `khi` for the key hash inverse of the primary grouping.
+/// - `khi<grouping>` for the key hash inverse of a named output's grouping.
 /// - `oia<operation_index><instruction_id>` for accumulators for a specific
 ///   instruction.
 /// - `ok<operation_index>` for the key-hash to entity-index map for the given
@@ -101,6 +103,17 @@ impl StoreKey {
         Self { key }
     }
 
+    /// Create a `StoreKey` for the key hash inverse of a grouping other than
+    /// the primary grouping.
+    ///
+    /// Keys are encoded as `khi<grouping>`.
+    pub fn new_grouping_key_hash_inverse(grouping: &str) -> Self {
+        let mut key = SmallVec::with_capacity(3 + grouping.len());
+        key.extend_from_slice(b"khi"); // 3
+        key.extend_from_slice(grouping.as_bytes());
+        Self { key }
+    }
+
     /// Create a `StoreKey` for a shift's retained batches.
     ///
     /// The array stored is encoded as a `RecordBatch` and written as a Vec<u8>.
@@ -120,8 +133,199 @@ impl StoreKey {
     }
 }
 
+/// The kind of value stored at a given key, decoded from the raw key bytes.
+///
+/// Used when inspecting the contents of a snapshot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StoreKeyKind {
+    StoreVersion,
+    PlanHash,
+    Plan,
+    MaxEventTime,
+    KeyHashInverse,
+    GroupingKeyHashInverse,
+    Accumulator {
+        operation_index: u8,
+        inst_index: u32,
+    },
+    KeyHashToIndex {
+        operation_index: u8,
+    },
+    KeyHashSet {
+        operation_index: u8,
+    },
+    TickState {
+        operation_index: u8,
+    },
+    MergeState {
+        operation_index: u8,
+    },
+    ShiftUntilRetainedBatches {
+        operation_index: u8,
+    },
+    ShiftToSubsort {
+        operation_index: u8,
+    },
+    Unknown,
+}
+
+impl StoreKeyKind {
+    /// Decodes the kind of a raw key from the compute store.
+    pub fn from_bytes(key: &[u8]) -> Self {
+        match key {
+            crate::compute_store::STORE_VERSION_KEY => Self::StoreVersion,
+            crate::compute_store::PLAN_HASH_KEY => Self::PlanHash,
+            crate::compute_store::PLAN_KEY => Self::Plan,
+            b"met" => Self::MaxEventTime,
+            b"khi" => Self::KeyHashInverse,
+            [b'k', b'h', b'i', ..] => Self::GroupingKeyHashInverse,
+            [b'o', b'i', b'a', operation_index, inst_index @ ..] if inst_index.len() == 4 => {
+                Self::Accumulator {
+                    operation_index: *operation_index,
+                    inst_index: u32::from_be_bytes(inst_index.try_into().expect("4 bytes")),
+                }
+            }
+            [b'o', b'k', operation_index] => Self::KeyHashToIndex {
+                operation_index: *operation_index,
+            },
+            [b'o', b't', b'k', operation_index] => Self::KeyHashSet {
+                operation_index: *operation_index,
+            },
+            [b'o', b't', b's', operation_index] => Self::TickState {
+                operation_index: *operation_index,
+            },
+            [b'o', b'm', b's', operation_index] => Self::MergeState {
+                operation_index: *operation_index,
+            },
+            [b'o', b's', b'r', b'b', operation_index] => Self::ShiftUntilRetainedBatches {
+                operation_index: *operation_index,
+            },
+            [b'o', b's', b's', operation_index] => Self::ShiftToSubsort {
+                operation_index: *operation_index,
+            },
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Returns the operation this key belongs to, if any.
+    pub fn operation_index(&self) -> Option<u8> {
+        match self {
+            Self::Accumulator {
+                operation_index, ..
+            }
+            | Self::KeyHashToIndex { operation_index }
+            | Self::KeyHashSet { operation_index }
+            | Self::TickState { operation_index }
+            | Self::MergeState { operation_index }
+            | Self::ShiftUntilRetainedBatches { operation_index }
+            | Self::ShiftToSubsort { operation_index } => Some(*operation_index),
+            _ => None,
+        }
+    }
+
+    /// Returns the key for the same state belonging to a different operation.
+    ///
+    /// Returns `None` if the key doesn't belong to an operation.
+    pub fn with_operation_index(&self, operation_index: u8) -> Option<StoreKey> {
+        match self {
+            Self::Accumulator { inst_index, .. } => {
+                Some(StoreKey::new_accumulator(operation_index, *inst_index))
+            }
+            Self::KeyHashToIndex { .. } => Some(StoreKey::new_key_hash_to_index(operation_index)),
+            Self::KeyHashSet { .. } => Some(StoreKey::new_key_hash_set(operation_index)),
+            Self::TickState { .. } => Some(StoreKey::new_tick_state(operation_index)),
+            Self::MergeState { .. } => Some(StoreKey::new_merge_state(operation_index)),
+            Self::ShiftUntilRetainedBatches { .. } => {
+                Some(StoreKey::new_shift_until_retained_batches(operation_index))
+            }
+            Self::ShiftToSubsort { .. } => Some(StoreKey::new_shift_to_subsort(operation_index)),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for StoreKeyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::StoreVersion => write!(f, "store version"),
+            Self::PlanHash => write!(f, "plan hash"),
+            Self::Plan => write!(f, "plan"),
+            Self::MaxEventTime => write!(f, "max event time"),
+            Self::KeyHashInverse => write!(f, "key hash inverse"),
+            Self::GroupingKeyHashInverse => write!(f, "grouping key hash inverse"),
+            Self::Accumulator {
+                operation_index,
+                inst_index,
+            } => write!(
+                f,
+                "accumulator (operation {operation_index}, instruction {inst_index})"
+            ),
+            Self::KeyHashToIndex { operation_index } => {
+                write!(f, "key hash index (operation {operation_index})")
+            }
+            Self::KeyHashSet { operation_index } => {
+                write!(f, "tick key hashes (operation {operation_index})")
+            }
+            Self::TickState { operation_index } => {
+                write!(f, "tick state (operation {operation_index})")
+            }
+            Self::MergeState { operation_index } => {
+                write!(f, "merge state (operation {operation_index})")
+            }
+            Self::ShiftUntilRetainedBatches { operation_index } => {
+                write!(f, "shift retained batches (operation {operation_index})")
+            }
+            Self::ShiftToSubsort { operation_index } => {
+                write!(f, "shift subsort (operation {operation_index})")
+            }
+            Self::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
 impl AsRef<[u8]> for StoreKey {
     fn as_ref(&self) -> &[u8] {
         &self.key
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_key_kind_round_trip() {
+        assert_eq!(
+            StoreKeyKind::from_bytes(StoreKey::new_accumulator(3, 258).as_ref()),
+            StoreKeyKind::Accumulator {
+                operation_index: 3,
+                inst_index: 258
+            }
+        );
+        assert_eq!(
+            StoreKeyKind::from_bytes(StoreKey::new_key_hash_to_index(7).as_ref()),
+            StoreKeyKind::KeyHashToIndex { operation_index: 7 }
+        );
+        assert_eq!(
+            StoreKeyKind::from_bytes(StoreKey::new_shift_until_retained_batches(2).as_ref()),
+            StoreKeyKind::ShiftUntilRetainedBatches { operation_index: 2 }
+        );
+        assert_eq!(
+            StoreKeyKind::from_bytes(StoreKey::new_shift_to_subsort(2).as_ref()),
+            StoreKeyKind::ShiftToSubsort { operation_index: 2 }
+        );
+        assert_eq!(
+            StoreKeyKind::from_bytes(StoreKey::new_max_event_time().as_ref()),
+            StoreKeyKind::MaxEventTime
+        );
+        assert_eq!(
+            StoreKeyKind::from_bytes(StoreKey::new_key_hash_inverse().as_ref()),
+            StoreKeyKind::KeyHashInverse
+        );
+        assert_eq!(
+            StoreKeyKind::from_bytes(StoreKey::new_grouping_key_hash_inverse("user").as_ref()),
+            StoreKeyKind::GroupingKeyHashInverse
+        );
+        assert_eq!(StoreKeyKind::from_bytes(b"bogus"), StoreKeyKind::Unknown);
+    }
+}
@@ -8,9 +8,18 @@ use arrow::datatypes::{
     ArrowPrimitiveType, DataType, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type,
     UInt32Type, UInt64Type,
 };
-use sparrow_core::{downcast_boolean_array, downcast_primitive_array, downcast_string_array};
+use itertools::Itertools;
+use sparrow_core::{
+    downcast_boolean_array, downcast_primitive_array, downcast_string_array, downcast_struct_array,
+};
 
 pub fn can_hash(data_type: &DataType) -> bool {
+    if let DataType::Struct(fields) = data_type {
+        // Structs are used for composite keys, and may be hashed if each of
+        // the component fields may be hashed.
+        return fields.iter().all(|field| can_hash(field.data_type()));
+    }
+
     matches!(
         data_type,
         DataType::Null
@@ -45,6 +54,7 @@ pub fn hash(array: &dyn Array) -> anyhow::Result<UInt64Array> {
         DataType::UInt64 => hash_primitive::<UInt64Type>(array),
         DataType::Utf8 => hash_string::<i32>(array),
         DataType::LargeUtf8 => hash_string::<i64>(array),
+        DataType::Struct(_) => hash_struct(array),
         todo => Err(anyhow!("Hashing of type {:?}", todo)),
     }
 }
@@ -102,9 +112,40 @@ fn hash_boolean(array: &dyn Array) -> anyhow::Result<UInt64Array> {
     Ok(builder.finish())
 }
 
+/// Hash each row of a struct by combining the hashes of each field.
+///
+/// A null struct hashes differently from a struct with all null fields.
+fn hash_struct(array: &dyn Array) -> anyhow::Result<UInt64Array> {
+    let struct_array = downcast_struct_array(array)?;
+    let field_hashes: Vec<UInt64Array> = struct_array
+        .columns()
+        .iter()
+        .map(|field| hash(field.as_ref()))
+        .try_collect()?;
+
+    let mut builder = UInt64Array::builder(array.len());
+
+    for index in 0..struct_array.len() {
+        let mut hasher = fixed_seed_hasher();
+        let is_valid = struct_array.is_valid(index);
+        is_valid.hash(&mut hasher);
+        if is_valid {
+            for field_hash in field_hashes.iter() {
+                field_hash.value(index).hash(&mut hasher);
+            }
+        }
+        builder.append_value(hasher.finish());
+    }
+
+    Ok(builder.finish())
+}
+
 #[cfg(test)]
 mod tests {
-    use arrow::array::{StringArray, UInt64Array};
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, StringArray, StructArray, UInt64Array};
+    use arrow::datatypes::Field;
 
     use super::*;
 
@@ -150,4 +191,48 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_can_hash_struct() {
+        let hashable = DataType::Struct(vec![
+            Field::new("merchant", DataType::Utf8, true),
+            Field::new("country", DataType::Int64, true),
+        ]);
+        assert!(can_hash(&hashable));
+
+        let not_hashable = DataType::Struct(vec![
+            Field::new("merchant", DataType::Utf8, true),
+            Field::new("amount", DataType::Float64, true),
+        ]);
+        assert!(!can_hash(&not_hashable));
+    }
+
+    #[test]
+    fn test_hash_struct() {
+        let merchants: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("a"),
+            Some("a"),
+            Some("b"),
+            Some("a"),
+            None,
+        ]));
+        let countries: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("us"),
+            Some("uk"),
+            Some("us"),
+            Some("us"),
+            None,
+        ]));
+        let array = StructArray::from(vec![
+            (Field::new("merchant", DataType::Utf8, true), merchants),
+            (Field::new("country", DataType::Utf8, true), countries),
+        ]);
+
+        let hashes = hash(&array).unwrap();
+        assert_eq!(hashes.value(0), hashes.value(3));
+        assert_ne!(hashes.value(0), hashes.value(1));
+        assert_ne!(hashes.value(0), hashes.value(2));
+        assert_ne!(hashes.value(1), hashes.value(2));
+        assert_ne!(hashes.value(0), hashes.value(4));
+    }
 }
@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+use std::{env, fs};
+
+/// Embed the function catalog for use by the language server.
+///
+/// The catalog is a directory of TOML files in `sparrow-catalog`, which can't
+/// be a dependency since it depends on `sparrow-main` to run examples. Instead,
+/// this generates a list of `include_str!` for each file in the catalog.
+fn main() {
+    let catalog_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap())
+        .join("../sparrow-catalog/catalog")
+        .canonicalize()
+        .expect("catalog directory");
+    println!("cargo:rerun-if-changed={}", catalog_dir.display());
+
+    let mut entries: Vec<_> = fs::read_dir(&catalog_dir)
+        .expect("read catalog directory")
+        .map(|entry| entry.expect("catalog entry").path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("toml"))
+        .collect();
+    entries.sort();
+
+    let mut generated = String::from("&[\n");
+    for path in entries {
+        generated.push_str(&format!(
+            "    include_str!({:?}),\n",
+            path.display().to_string()
+        ));
+    }
+    generated.push_str("]\n");
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    fs::write(out_dir.join("catalog_entries.rs"), generated).expect("write catalog entries");
+}
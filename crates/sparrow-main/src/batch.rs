@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use error_stack::{IntoReport, ResultExt};
@@ -31,6 +32,15 @@ pub struct BatchCommand {
     #[arg(long, action)]
     pub compile_only: bool,
 
+    /// Print an explanation of the compiled plan.
+    #[arg(long, action)]
+    pub explain: bool,
+
+    /// Print an explanation of the plan annotated with the actual rows and
+    /// time of each operation after executing the query.
+    #[arg(long, action)]
+    pub explain_analyze: bool,
+
     /// File containing the schema definitions for the script.
     #[arg(long)]
     pub schema: PathBuf,
@@ -96,6 +106,9 @@ impl BatchCommand {
                 expression_kind: ExpressionKind::Complete as i32,
                 experimental: false,
                 per_entity_behavior: self.compiler_options.per_entity_behavior as i32,
+                previous_plan: None,
+                explain: self.explain,
+                lint_severities: vec![],
             },
             self.compiler_options.internal,
         )
@@ -106,6 +119,9 @@ impl BatchCommand {
         #[allow(clippy::print_stdout)]
         let plan = if let Some(plan) = compile_result.plan {
             println!("{diagnostics}");
+            if self.explain {
+                println!("{}", compile_result.explanation);
+            }
             plan
         } else {
             error_stack::bail!(Error::InvalidQuery(diagnostics));
@@ -123,6 +139,9 @@ impl BatchCommand {
 
             error_stack::ensure!(self.output_dir.is_dir(), Error::OutputIsNotDirectory);
 
+            // Keep a copy of the plan and tables to explain after execution.
+            let explain_analyze = self.explain_analyze.then(|| (plan.clone(), tables.clone()));
+
             let result_stream = sparrow_runtime::execute::execute(
                 ExecuteRequest {
                     plan: Some(plan),
@@ -132,6 +151,10 @@ impl BatchCommand {
                     compute_snapshot_config: None,
                     changed_since: None,
                     final_result_time: None,
+                    operation_progress: self.explain_analyze,
+                    start_time: None,
+                    examples: None,
+                    output_destinations: HashMap::new(),
                 },
                 s3_helper,
                 None,
@@ -144,15 +167,12 @@ impl BatchCommand {
             // For the CLI, we use stdout to print information.
             #[allow(clippy::print_stdout)]
             {
+                let mut operation_progress = Vec::new();
                 let output_files: Vec<_> = result_stream
                     .inspect_ok(|next| {
-                        println!(
-                            "{} rows produced so far",
-                            next.progress
-                                .as_ref()
-                                .expect("progress")
-                                .produced_output_rows
-                        )
+                        let progress = next.progress.as_ref().expect("progress");
+                        println!("{} rows produced so far", progress.produced_output_rows);
+                        operation_progress = progress.operations.clone();
                     })
                     .try_filter_map(|next| async move {
                         match next.output_paths() {
@@ -165,6 +185,10 @@ impl BatchCommand {
                     .change_context(Error::Execution)?;
 
                 println!("Output files: {output_files:?}");
+
+                if let Some((plan, tables)) = explain_analyze {
+                    println!("{}", plan.explain(&tables, &operation_progress));
+                }
             }
         }
 
@@ -0,0 +1,98 @@
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use error_stack::{IntoReport, ResultExt};
+
+/// Options for the `fmt` command.
+#[derive(clap::Args, Debug)]
+#[command(version, rename_all = "kebab-case")]
+pub struct FmtCommand {
+    /// Fenl files to format in place.
+    ///
+    /// If no files are specified, the expression is read from stdin and the
+    /// formatted expression is written to stdout.
+    pub files: Vec<PathBuf>,
+
+    /// Check whether the input is formatted, without modifying any files.
+    ///
+    /// Lists the files which would be changed, and fails if there are any.
+    #[arg(long)]
+    pub check: bool,
+}
+
+#[derive(derive_more::Display, Debug)]
+pub enum Error {
+    #[display(fmt = "failed to read input")]
+    ReadingInput,
+    #[display(fmt = "failed to write output")]
+    WritingOutput,
+    #[display(fmt = "failed to parse Fenl")]
+    Parsing,
+    #[display(fmt = "{_0} input(s) not formatted")]
+    Unformatted(usize),
+}
+
+impl error_stack::Context for Error {}
+
+impl FmtCommand {
+    #[allow(clippy::print_stdout)]
+    pub fn execute(self) -> error_stack::Result<(), Error> {
+        if self.files.is_empty() {
+            let mut source = String::new();
+            std::io::stdin()
+                .read_to_string(&mut source)
+                .into_report()
+                .change_context(Error::ReadingInput)?;
+            let formatted = format_source(&source)?;
+
+            if self.check {
+                error_stack::ensure!(formatted == source, Error::Unformatted(1));
+            } else {
+                std::io::stdout()
+                    .write_all(formatted.as_bytes())
+                    .into_report()
+                    .change_context(Error::WritingOutput)?;
+            }
+            return Ok(());
+        }
+
+        let mut unformatted = 0;
+        for path in &self.files {
+            let source = std::fs::read_to_string(path)
+                .into_report()
+                .change_context(Error::ReadingInput)
+                .attach_printable_lazy(|| path.display().to_string())?;
+            let formatted =
+                format_source(&source).attach_printable_lazy(|| path.display().to_string())?;
+            if formatted == source {
+                continue;
+            }
+
+            if self.check {
+                println!("{}", path.display());
+                unformatted += 1;
+            } else {
+                std::fs::write(path, formatted)
+                    .into_report()
+                    .change_context(Error::WritingOutput)
+                    .attach_printable_lazy(|| path.display().to_string())?;
+            }
+        }
+
+        error_stack::ensure!(unformatted == 0, Error::Unformatted(unformatted));
+        Ok(())
+    }
+}
+
+fn format_source(source: &str) -> error_stack::Result<String, Error> {
+    sparrow_syntax::format_expr(source).map_err(|errors| {
+        let mut report = error_stack::Report::new(Error::Parsing);
+        for error in errors {
+            let error = error
+                .map_token(|token| token.to_string())
+                .map_error(|(_, message, _)| message);
+            report = report.attach_printable(error.to_string());
+        }
+        report
+    })
+}
@@ -9,16 +9,27 @@
 )]
 
 pub(crate) mod batch;
+mod fmt;
+mod local_files;
+mod lsp;
 mod materialize;
 mod prepare;
+mod repl;
 mod script;
 mod serve;
+mod snapshot;
+mod test_runner;
 pub mod tracing_setup;
 
 pub use batch::BatchCommand;
+pub use fmt::FmtCommand;
+pub use lsp::LspCommand;
 pub use materialize::MaterializeCommand;
 pub use prepare::PrepareCommand;
+pub use repl::ReplCommand;
 pub use serve::*;
+pub use snapshot::SnapshotCommand;
+pub use test_runner::TestCommand;
 
 #[derive(Debug)]
 pub(crate) struct BuildInfo {
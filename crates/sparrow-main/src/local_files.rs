@@ -0,0 +1,154 @@
+//! Helpers for running queries over local files.
+//!
+//! These are used by commands such as `repl` and `test` which prepare
+//! local CSV or Parquet files as tables and read the results back, rather
+//! than running against a configured object store.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use arrow::datatypes::SchemaRef;
+use error_stack::{IntoReport, IntoReportCompat, ResultExt};
+use futures::TryStreamExt;
+use sparrow_api::kaskada::v1alpha::execute_request::Limits;
+use sparrow_api::kaskada::v1alpha::{
+    compute_table, destination, ComputePlan, ComputeTable, Destination, ExecuteRequest, FileType,
+    ObjectStoreDestination, SourceData, TableConfig, TableMetadata,
+};
+use sparrow_qfr::kaskada::sparrow::v1alpha::FlightRecordHeader;
+use sparrow_runtime::s3::S3Helper;
+use sparrow_runtime::stores::ObjectStoreRegistry;
+
+#[derive(derive_more::Display, Debug)]
+pub enum Error {
+    #[display(fmt = "failed to prepare table")]
+    Preparing,
+    #[display(fmt = "failed to execute query")]
+    Execution,
+    #[display(fmt = "internal error")]
+    Internal,
+}
+
+impl error_stack::Context for Error {}
+
+/// Prepare the local file at `path` as a table with the given `config`.
+///
+/// The prepared files are written to `output_dir`, which must outlive any
+/// use of the returned table.
+pub(crate) async fn prepare_local_table(
+    object_store_registry: &ObjectStoreRegistry,
+    path: &Path,
+    config: TableConfig,
+    output_dir: &Path,
+) -> error_stack::Result<(ComputeTable, SchemaRef), Error> {
+    let path = path
+        .canonicalize()
+        .into_report()
+        .change_context(Error::Preparing)
+        .attach_printable_lazy(|| path.display().to_string())?;
+    let source_data = SourceData {
+        source: Some(
+            SourceData::try_from_local(&path)
+                .into_report()
+                .change_context(Error::Preparing)?,
+        ),
+    };
+
+    std::fs::create_dir_all(output_dir)
+        .into_report()
+        .change_context(Error::Internal)?;
+    let (prepared_metadata, prepared_files) = sparrow_runtime::prepare::prepare_file(
+        object_store_registry,
+        &source_data,
+        &format!("file://{}", output_dir.display()),
+        &config.name,
+        &config,
+        &None,
+        &None,
+    )
+    .await
+    .change_context(Error::Preparing)
+    .attach_printable_lazy(|| path.display().to_string())?;
+
+    let schema = prepared_metadata
+        .first()
+        .ok_or(Error::Preparing)
+        .attach_printable("no rows in table")?
+        .table_schema
+        .clone();
+    let table = ComputeTable {
+        config: Some(config),
+        metadata: Some(TableMetadata {
+            schema: Some(
+                schema
+                    .as_ref()
+                    .try_into()
+                    .into_report()
+                    .change_context(Error::Internal)?,
+            ),
+            file_count: prepared_files.len() as i64,
+        }),
+        file_sets: vec![compute_table::FileSet {
+            slice_plan: None,
+            prepared_files,
+        }],
+    };
+    Ok((table, schema))
+}
+
+/// Execute the `plan` writing CSV to `output_dir` and return the output.
+///
+/// If the query produced multiple files, they are concatenated.
+pub(crate) async fn execute_to_csv(
+    plan: Option<ComputePlan>,
+    tables: Vec<ComputeTable>,
+    limits: Option<Limits>,
+    s3_helper: S3Helper,
+    output_dir: &Path,
+) -> error_stack::Result<String, Error> {
+    let destination = ObjectStoreDestination {
+        output_prefix_uri: format!("file://{}", output_dir.display()),
+        file_type: FileType::Csv.into(),
+        output_paths: None,
+    };
+
+    let output_paths: Vec<String> = sparrow_runtime::execute::execute(
+        ExecuteRequest {
+            plan,
+            tables,
+            destination: Some(Destination {
+                destination: Some(destination::Destination::ObjectStore(destination)),
+            }),
+            limits,
+            compute_snapshot_config: None,
+            changed_since: None,
+            final_result_time: None,
+            operation_progress: false,
+            start_time: None,
+            examples: None,
+            output_destinations: HashMap::new(),
+        },
+        s3_helper,
+        None,
+        None,
+        FlightRecordHeader::default(),
+    )
+    .await
+    .change_context(Error::Execution)?
+    .map_ok(|response| response.output_paths().unwrap_or_default())
+    .try_concat()
+    .await
+    .change_context(Error::Execution)?;
+
+    let mut output = String::new();
+    for path in output_paths {
+        let path = PathBuf::from(path.strip_prefix("file://").unwrap_or(&path));
+        output.push_str(
+            &std::fs::read_to_string(&path)
+                .into_report()
+                .change_context(Error::Internal)
+                .attach_printable_lazy(|| path.display().to_string())?,
+        );
+    }
+    Ok(output)
+}
@@ -0,0 +1,61 @@
+//! A language server for Fenl, providing diagnostics, hover, completion and
+//! go-to-definition to editors.
+//!
+//! The server communicates over stdin and stdout, so logs must be written to
+//! stderr while it is running.
+
+mod backend;
+mod catalog;
+mod position;
+mod symbols;
+mod workspace;
+
+use std::path::PathBuf;
+
+use error_stack::ResultExt;
+use tower_lsp::{LspService, Server};
+
+use crate::lsp::backend::Backend;
+use crate::lsp::workspace::Workspace;
+use crate::script::{Schema, ScriptPath};
+
+/// Options for the language server.
+#[derive(clap::Args, Debug)]
+#[command(version, rename_all = "kebab-case")]
+pub struct LspCommand {
+    /// File containing the schema definitions for the tables.
+    ///
+    /// This uses the same format as the `--schema` for `batch`. If not
+    /// specified, no tables are available to queries.
+    #[arg(long)]
+    pub schema: Option<PathBuf>,
+}
+
+#[derive(derive_more::Display, Debug)]
+pub enum Error {
+    #[display(fmt = "invalid schema")]
+    InvalidSchema,
+}
+
+impl error_stack::Context for Error {}
+
+impl LspCommand {
+    pub async fn execute(self) -> error_stack::Result<(), Error> {
+        let tables = match &self.schema {
+            Some(schema) => {
+                Schema::try_from(schema)
+                    .attach_printable_lazy(|| ScriptPath(schema.clone()))
+                    .change_context(Error::InvalidSchema)?
+                    .tables
+            }
+            None => vec![],
+        };
+
+        let (service, socket) =
+            LspService::new(|client| Backend::new(client, Workspace::new(tables)));
+        Server::new(tokio::io::stdin(), tokio::io::stdout(), socket)
+            .serve(service)
+            .await;
+        Ok(())
+    }
+}
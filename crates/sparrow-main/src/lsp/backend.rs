@@ -0,0 +1,130 @@
+use std::sync::{Mutex, MutexGuard};
+
+use tower_lsp::jsonrpc::Result;
+use tower_lsp::lsp_types::{
+    CompletionOptions, CompletionParams, CompletionResponse, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, GotoDefinitionParams,
+    GotoDefinitionResponse, Hover, HoverParams, HoverProviderCapability, InitializeParams,
+    InitializeResult, InitializedParams, MessageType, OneOf, ServerCapabilities, ServerInfo,
+    TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+use tower_lsp::{Client, LanguageServer};
+
+use crate::lsp::workspace::Workspace;
+
+/// The language server, which responds to requests from the editor.
+pub(super) struct Backend {
+    client: Client,
+    workspace: Mutex<Workspace>,
+}
+
+impl Backend {
+    pub fn new(client: Client, workspace: Workspace) -> Self {
+        Self {
+            client,
+            workspace: Mutex::new(workspace),
+        }
+    }
+
+    fn workspace(&self) -> MutexGuard<'_, Workspace> {
+        self.workspace.lock().expect("workspace lock poisoned")
+    }
+
+    /// Update the text of a document and publish the resulting diagnostics.
+    async fn update(&self, url: Url, text: String, version: i32) {
+        let diagnostics = self
+            .workspace()
+            .update_document(url.clone(), text)
+            .diagnostics
+            .clone();
+        self.client
+            .publish_diagnostics(url, diagnostics, Some(version))
+            .await;
+    }
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for Backend {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        if let Some(root) = params.root_uri.and_then(|uri| uri.to_file_path().ok()) {
+            self.workspace().load_directory(&root);
+        }
+
+        Ok(InitializeResult {
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::FULL,
+                )),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                completion_provider: Some(CompletionOptions {
+                    trigger_characters: Some(vec![".".to_owned()]),
+                    ..CompletionOptions::default()
+                }),
+                definition_provider: Some(OneOf::Left(true)),
+                ..ServerCapabilities::default()
+            },
+            server_info: Some(ServerInfo {
+                name: "sparrow".to_owned(),
+                version: Some(env!("CARGO_PKG_VERSION").to_owned()),
+            }),
+        })
+    }
+
+    async fn initialized(&self, _: InitializedParams) {
+        self.client
+            .log_message(MessageType::INFO, "Fenl language server initialized")
+            .await;
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let document = params.text_document;
+        self.update(document.uri, document.text, document.version)
+            .await;
+    }
+
+    async fn did_change(&self, mut params: DidChangeTextDocumentParams) {
+        // We use full synchronization, so the last change contains the
+        // complete text of the document.
+        if let Some(change) = params.content_changes.pop() {
+            let document = params.text_document;
+            self.update(document.uri, change.text, document.version)
+                .await;
+        }
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let url = params.text_document.uri;
+        self.workspace().close_document(&url);
+        self.client.publish_diagnostics(url, vec![], None).await;
+    }
+
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let position = params.text_document_position_params;
+        Ok(self
+            .workspace()
+            .hover(&position.text_document.uri, position.position))
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let position = params.text_document_position;
+        let items = self
+            .workspace()
+            .completion(&position.text_document.uri, position.position);
+        Ok(Some(CompletionResponse::Array(items)))
+    }
+
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        let position = params.text_document_position_params;
+        Ok(self
+            .workspace()
+            .definition(&position.text_document.uri, position.position)
+            .map(GotoDefinitionResponse::Scalar))
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use static_init::dynamic;
+
+/// The TOML source of each entry in the function catalog.
+const CATALOG_ENTRIES: &[&str] = include!(concat!(env!("OUT_DIR"), "/catalog_entries.rs"));
+
+/// Documentation for a function, from the catalog.
+///
+/// This only deserializes the parts of the catalog entry needed to describe
+/// the function. Examples are ignored.
+#[derive(Deserialize, Debug)]
+pub(super) struct FunctionDoc {
+    pub name: String,
+    pub signature: Option<String>,
+    pub operator: Option<String>,
+    pub short_doc: Option<String>,
+    pub long_doc: Option<String>,
+}
+
+#[dynamic]
+static CATALOG: BTreeMap<String, FunctionDoc> = CATALOG_ENTRIES
+    .iter()
+    .filter_map(|entry| match toml::from_str::<FunctionDoc>(entry) {
+        Ok(doc) => Some((doc.name.clone(), doc)),
+        Err(err) => {
+            tracing::error!("Invalid catalog entry: {err}");
+            None
+        }
+    })
+    .collect();
+
+/// Return the documentation for the named function, if it is in the catalog.
+pub(super) fn function_doc(name: &str) -> Option<&'static FunctionDoc> {
+    CATALOG.get(name)
+}
+
+/// Return the signature of the named function.
+///
+/// This prefers the signature of the registered function, falling back to
+/// the catalog for functions that aren't registered (such as `extend`).
+pub(super) fn function_signature(name: &str) -> Option<String> {
+    match sparrow_compiler::get_function(name) {
+        Ok(function) => Some(function.signature_str().to_owned()),
+        Err(_) => function_doc(name).and_then(|doc| doc.signature.clone()),
+    }
+}
+
+/// Return the markdown describing the named function.
+pub(super) fn function_markdown(name: &str) -> Option<String> {
+    let signature = function_signature(name);
+    let doc = function_doc(name);
+    if signature.is_none() && doc.is_none() {
+        return None;
+    }
+
+    let mut markdown = String::new();
+    if let Some(signature) = signature {
+        markdown.push_str(&format!("```fenl\n{signature}\n```\n"));
+    }
+    if let Some(doc) = doc {
+        if let Some(operator) = &doc.operator {
+            markdown.push_str(&format!("\nOperator: `{operator}`\n"));
+        }
+        if let Some(short_doc) = &doc.short_doc {
+            markdown.push_str(&format!("\n{short_doc}\n"));
+        }
+        if let Some(long_doc) = &doc.long_doc {
+            markdown.push_str(&format!("\n{long_doc}"));
+        }
+    }
+    Some(markdown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_entries_parse() {
+        assert_eq!(CATALOG.len(), CATALOG_ENTRIES.len());
+        let sum = function_doc("sum").unwrap();
+        assert_eq!(
+            sum.short_doc.as_deref(),
+            Some("Computes the sum of values across the input.")
+        );
+    }
+}
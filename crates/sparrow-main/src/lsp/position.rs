@@ -0,0 +1,74 @@
+//! Conversion between byte offsets and LSP positions.
+//!
+//! The compiler reports locations as byte offsets within the source, while
+//! LSP positions are a line and a character offset in UTF-16 code units.
+
+use tower_lsp::lsp_types::{Position, Range};
+
+/// Return the position of the byte `offset` within `text`.
+///
+/// Offsets past the end of the text are clamped to the end.
+pub(super) fn offset_to_position(text: &str, offset: usize) -> Position {
+    let mut line = 0;
+    let mut character = 0;
+    for (index, c) in text.char_indices() {
+        if index >= offset {
+            break;
+        }
+        if c == '\n' {
+            line += 1;
+            character = 0;
+        } else {
+            character += c.len_utf16() as u32;
+        }
+    }
+    Position { line, character }
+}
+
+/// Return the byte offset of the `position` within `text`.
+///
+/// Positions past the end of a line are clamped to the end of the line.
+pub(super) fn position_to_offset(text: &str, position: Position) -> usize {
+    let mut line = 0;
+    let mut character = 0;
+    for (index, c) in text.char_indices() {
+        if line == position.line && (character >= position.character || c == '\n') {
+            return index;
+        }
+        if c == '\n' {
+            line += 1;
+            character = 0;
+        } else if line == position.line {
+            character += c.len_utf16() as u32;
+        }
+    }
+    text.len()
+}
+
+/// Return the range of bytes `start..end` within `text`.
+pub(super) fn offsets_to_range(text: &str, start: usize, end: usize) -> Range {
+    Range {
+        start: offset_to_position(text, start),
+        end: offset_to_position(text, end),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_position_round_trip() {
+        let text = "let x = Foo.ä\nin { x }";
+        for (offset, _) in text.char_indices() {
+            let position = offset_to_position(text, offset);
+            assert_eq!(position_to_offset(text, position), offset);
+        }
+
+        assert_eq!(offset_to_position(text, 15), Position::new(1, 0));
+        // `ä` is two bytes in UTF-8, but one UTF-16 code unit.
+        assert_eq!(offset_to_position(text, 14), Position::new(0, 13));
+        assert_eq!(position_to_offset(text, Position::new(0, 100)), 14);
+        assert_eq!(position_to_offset(text, Position::new(5, 0)), text.len());
+    }
+}
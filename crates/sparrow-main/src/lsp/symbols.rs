@@ -0,0 +1,146 @@
+//! Syntactic analysis of the names within a Fenl document.
+//!
+//! This doesn't require type-checking, so it may be used to find the
+//! definition of `let` bound names even if the query has type errors.
+
+use itertools::izip;
+use sparrow_syntax::{Expr, ExprOp, FeatureSetPart, Located, Location};
+
+#[derive(Debug, PartialEq)]
+pub(super) enum SymbolKind {
+    /// A reference to a name, which may be bound by a `let`, a formula or a
+    /// table.
+    ///
+    /// If it is bound by a `let`, the location of that binding is included.
+    Reference { binding: Option<Location> },
+    /// The name of a field being accessed.
+    Field,
+    /// The name of a function being called.
+    Function,
+    /// The name being bound by a `let`.
+    LetBinding,
+}
+
+/// A name appearing within the query.
+#[derive(Debug)]
+pub(super) struct Symbol {
+    pub name: String,
+    pub location: Location,
+    pub kind: SymbolKind,
+}
+
+/// Return the symbols within the query.
+pub(super) fn query_symbols(expr: &Expr) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    visit(expr, &mut Vec::new(), &mut symbols);
+    symbols.retain(|symbol| symbol.location.part() == FeatureSetPart::Query);
+    symbols
+}
+
+/// Return the innermost symbol containing the byte `offset`.
+pub(super) fn symbol_at(symbols: &[Symbol], offset: usize) -> Option<&Symbol> {
+    symbols
+        .iter()
+        .filter(|symbol| symbol.location.start() <= offset && offset <= symbol.location.end())
+        .min_by_key(|symbol| symbol.location.end() - symbol.location.start())
+}
+
+fn visit(expr: &Expr, scope: &mut Vec<Located<String>>, symbols: &mut Vec<Symbol>) {
+    let mut push = |name: &str, location: &Location, kind: SymbolKind| {
+        symbols.push(Symbol {
+            name: name.to_owned(),
+            location: location.clone(),
+            kind,
+        })
+    };
+
+    match expr.op() {
+        ExprOp::Let(names, _) => {
+            // The last name (and argument) correspond to the body. Each binding
+            // is in scope for later bindings and the body.
+            let depth = scope.len();
+            let bindings = names.len() - 1;
+            for (name, arg) in izip!(names, expr.args().iter()).take(bindings) {
+                visit(arg.value(), scope, symbols);
+                symbols.push(Symbol {
+                    name: name.inner().clone(),
+                    location: name.location().clone(),
+                    kind: SymbolKind::LetBinding,
+                });
+                scope.push(name.clone());
+            }
+            if let Some(body) = expr.arg(bindings) {
+                visit(body, scope, symbols);
+            }
+            scope.truncate(depth);
+            return;
+        }
+        ExprOp::Reference(name) => {
+            let binding = scope
+                .iter()
+                .rev()
+                .find(|bound| bound.inner() == name.inner())
+                .map(|bound| bound.location().clone());
+            push(
+                name.inner(),
+                name.location(),
+                SymbolKind::Reference { binding },
+            );
+        }
+        ExprOp::FieldRef(field, _) => push(field.inner(), field.location(), SymbolKind::Field),
+        ExprOp::Call(function) => push(function.inner(), function.location(), SymbolKind::Function),
+        ExprOp::ExtendRecord(location) => push("extend", location, SymbolKind::Function),
+        ExprOp::RemoveFields(location) => push("remove_fields", location, SymbolKind::Function),
+        ExprOp::SelectFields(location) => push("select_fields", location, SymbolKind::Function),
+        _ => {}
+    }
+
+    for arg in expr.args().iter() {
+        visit(arg.value(), scope, symbols);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_let_bindings_are_scoped() {
+        let query = "let x = Foo.a\nlet y = sum(x)\nin { x, y, z: x | extend({}) }";
+        let expr = Expr::try_from_str(FeatureSetPart::Query, query).unwrap();
+        let symbols = query_symbols(&expr);
+
+        let described: Vec<_> = symbols
+            .iter()
+            .map(|symbol| {
+                let text = &query[symbol.location.start()..symbol.location.end()];
+                match &symbol.kind {
+                    SymbolKind::Reference {
+                        binding: Some(binding),
+                    } => format!("{text} -> {}", binding.start()),
+                    kind => format!("{text} {kind:?}"),
+                }
+            })
+            .collect();
+        assert_eq!(
+            described,
+            vec![
+                "a Field",
+                "Foo Reference { binding: None }",
+                "x LetBinding",
+                "sum Function",
+                "x -> 4",
+                "y LetBinding",
+                "x -> 4",
+                "y -> 18",
+                "x -> 4",
+                "extend Function",
+            ]
+        );
+
+        let at = |offset| symbol_at(&symbols, offset).map(|symbol| symbol.name.as_str());
+        assert_eq!(at(10), Some("Foo"));
+        assert_eq!(at(12), Some("a"));
+        assert_eq!(at(7), None);
+    }
+}
@@ -0,0 +1,572 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use sparrow_api::kaskada::v1alpha::{ComputeTable, FeatureSet, FenlDiagnostic, Formula, Severity};
+use sparrow_compiler::{CollectedDiagnostic, TypeAnnotation};
+use sparrow_syntax::{Expr, FeatureSetPart, FenlType};
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, Diagnostic, DiagnosticSeverity, Documentation, Hover,
+    HoverContents, Location, MarkupContent, MarkupKind, NumberOrString, Position, Range, Url,
+};
+use tracing::{error, warn};
+
+use crate::lsp::catalog::{function_doc, function_markdown, function_signature};
+use crate::lsp::position::{offsets_to_range, position_to_offset};
+use crate::lsp::symbols::{query_symbols, symbol_at, Symbol, SymbolKind};
+
+/// The extension of Fenl documents.
+const FENL_EXTENSION: &str = "fenl";
+
+/// Functions that aren't in the registry, but may be called.
+const SPECIAL_FUNCTIONS: &[&str] = &["lookup", "extend", "remove_fields", "select_fields"];
+
+/// The state of the documents being edited.
+///
+/// Each Fenl document contains a single query. The other documents in the
+/// workspace are available to that query as formulas, named after the file
+/// (so `revenue.fenl` may be referenced as `revenue`).
+pub(super) struct Workspace {
+    tables: Vec<ComputeTable>,
+    /// The current text of each document, keyed by URL.
+    documents: BTreeMap<Url, String>,
+    /// The most recent analysis of each open document.
+    analyses: BTreeMap<Url, DocumentAnalysis>,
+}
+
+/// The results of analyzing a document.
+#[derive(Default)]
+pub(super) struct DocumentAnalysis {
+    pub diagnostics: Vec<Diagnostic>,
+    /// The inferred types of parts of the document.
+    type_annotations: Vec<TypeAnnotation>,
+    /// The text that was analyzed.
+    ///
+    /// Used to determine the names of annotated parts of the document, even
+    /// if the document has been edited since.
+    text: String,
+}
+
+impl Workspace {
+    pub fn new(tables: Vec<ComputeTable>) -> Self {
+        Self {
+            tables,
+            documents: BTreeMap::new(),
+            analyses: BTreeMap::new(),
+        }
+    }
+
+    /// Load the Fenl documents within `dir` (recursively).
+    ///
+    /// This makes them available as formulas before they are opened.
+    pub fn load_directory(&mut self, dir: &Path) {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                warn!("Unable to read directory {dir:?}: {err}");
+                return;
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                self.load_directory(&path);
+            } else if path.extension().and_then(|ext| ext.to_str()) == Some(FENL_EXTENSION) {
+                match (Url::from_file_path(&path), std::fs::read_to_string(&path)) {
+                    (Ok(url), Ok(text)) => {
+                        self.documents.insert(url, text);
+                    }
+                    (_, Err(err)) => warn!("Unable to read {path:?}: {err}"),
+                    (Err(_), _) => warn!("Unable to convert {path:?} to a URL"),
+                }
+            }
+        }
+    }
+
+    /// Set the text of a document and return the resulting analysis.
+    pub fn update_document(&mut self, url: Url, text: String) -> &DocumentAnalysis {
+        self.documents.insert(url.clone(), text);
+        let analysis = self.analyze(&url);
+        self.analyses.insert(url.clone(), analysis);
+        &self.analyses[&url]
+    }
+
+    /// Close a document.
+    ///
+    /// If the document still exists on disk, it remains available as a
+    /// formula (using the saved contents).
+    pub fn close_document(&mut self, url: &Url) {
+        self.analyses.remove(url);
+        match url.to_file_path().map(std::fs::read_to_string) {
+            Ok(Ok(text)) => {
+                self.documents.insert(url.clone(), text);
+            }
+            _ => {
+                self.documents.remove(url);
+            }
+        }
+    }
+
+    /// Return the name of the formula defined by the document, if it is valid.
+    fn formula_name(url: &Url) -> Option<&str> {
+        let name = url.path_segments()?.last()?;
+        let name = name.strip_suffix(FENL_EXTENSION)?.strip_suffix('.')?;
+        sparrow_syntax::is_valid_ident(name).then_some(name)
+    }
+
+    /// Return the document defining the named formula.
+    fn formula_url(&self, name: &str) -> Option<&Url> {
+        self.documents
+            .keys()
+            .find(|url| Self::formula_name(url) == Some(name))
+    }
+
+    fn analyze(&self, url: &Url) -> DocumentAnalysis {
+        let Some(text) = self.documents.get(url) else {
+            return DocumentAnalysis::default();
+        };
+
+        // Every other document is available as a formula.
+        let formulas = self
+            .documents
+            .iter()
+            .filter(|(other, _)| *other != url)
+            .filter_map(|(other, formula)| {
+                Some(Formula {
+                    name: Self::formula_name(other)?.to_owned(),
+                    formula: formula.clone(),
+                    source_location: other.to_string(),
+                })
+            })
+            .collect();
+        let feature_set = FeatureSet {
+            formulas,
+            query: text.clone(),
+            outputs: vec![],
+        };
+
+        let analysis = match sparrow_compiler::analyze(self.tables.clone(), &feature_set) {
+            Ok(analysis) => analysis,
+            Err(err) => {
+                error!("Failed to analyze {url}: {err:?}");
+                return DocumentAnalysis {
+                    text: text.clone(),
+                    ..DocumentAnalysis::default()
+                };
+            }
+        };
+
+        let type_annotations = analysis
+            .type_annotations()
+            .iter()
+            .filter(|annotation| annotation.location.part() == FeatureSetPart::Query)
+            .cloned()
+            .collect();
+        let diagnostics = analysis
+            .diagnostics()
+            .iter()
+            .filter_map(|diagnostic| to_lsp_diagnostic(text, diagnostic))
+            .collect();
+        DocumentAnalysis {
+            diagnostics,
+            type_annotations,
+            text: text.clone(),
+        }
+    }
+
+    fn symbols(&self, url: &Url) -> Vec<Symbol> {
+        let Some(text) = self.documents.get(url) else {
+            return vec![];
+        };
+        match Expr::try_from_str(FeatureSetPart::Query, text) {
+            Ok(expr) => query_symbols(&expr),
+            Err(_) => vec![],
+        }
+    }
+
+    /// Return the hover information for the given position.
+    pub fn hover(&self, url: &Url, position: Position) -> Option<Hover> {
+        let text = self.documents.get(url)?;
+        let offset = position_to_offset(text, position);
+        let symbols = self.symbols(url);
+        let symbol = symbol_at(&symbols, offset)?;
+
+        let value_type = self.analyses.get(url).and_then(|analysis| {
+            analysis
+                .type_annotations
+                .iter()
+                .find(|annotation| annotation.location == symbol.location)
+                .map(|annotation| &annotation.value_type)
+        });
+
+        let mut markdown = String::new();
+        match (&symbol.kind, value_type) {
+            (SymbolKind::Function, value_type) => {
+                if let Some(function) = function_markdown(&symbol.name) {
+                    markdown.push_str(&function);
+                }
+                if let Some(value_type) = value_type {
+                    markdown.push_str(&format!("\nResult type: `{value_type}`\n"));
+                }
+            }
+            (_, Some(value_type)) => {
+                markdown.push_str(&format!("```fenl\n{}: {value_type}\n```\n", symbol.name));
+            }
+            (_, None) => return None,
+        }
+        if matches!(symbol.kind, SymbolKind::Reference { binding: None }) {
+            if let Some(formula_url) = self.formula_url(&symbol.name) {
+                markdown.push_str(&format!("\nFormula defined in `{formula_url}`\n"));
+            }
+        }
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: markdown,
+            }),
+            range: Some(offsets_to_range(
+                text,
+                symbol.location.start(),
+                symbol.location.end(),
+            )),
+        })
+    }
+
+    /// Return the definition of the name at the given position.
+    ///
+    /// Names bound by `let` are defined by the binding, while formulas are
+    /// defined by the corresponding document.
+    pub fn definition(&self, url: &Url, position: Position) -> Option<Location> {
+        let text = self.documents.get(url)?;
+        let offset = position_to_offset(text, position);
+        let symbols = self.symbols(url);
+        let symbol = symbol_at(&symbols, offset)?;
+        match &symbol.kind {
+            SymbolKind::Reference {
+                binding: Some(binding),
+            } => Some(Location {
+                uri: url.clone(),
+                range: offsets_to_range(text, binding.start(), binding.end()),
+            }),
+            SymbolKind::Reference { binding: None } => {
+                self.formula_url(&symbol.name).map(|formula_url| Location {
+                    uri: formula_url.clone(),
+                    range: Range::default(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Return the completions for the given position.
+    ///
+    /// After a `.` this completes the fields of the preceding table or
+    /// record. Otherwise, it completes tables, formulas, `let` bound names
+    /// and functions.
+    pub fn completion(&self, url: &Url, position: Position) -> Vec<CompletionItem> {
+        let Some(text) = self.documents.get(url) else {
+            return vec![];
+        };
+        let offset = position_to_offset(text, position);
+
+        // Skip back over the partial name being completed.
+        let prefix_start = text[..offset]
+            .rfind(|c: char| !is_ident_char(c))
+            .map_or(0, |index| index + 1);
+        if let Some(base) = text[..prefix_start].strip_suffix('.') {
+            let base_start = base
+                .rfind(|c: char| !is_ident_char(c))
+                .map_or(0, |index| index + 1);
+            return self.field_completions(url, &base[base_start..]);
+        }
+
+        let mut items = Vec::new();
+        for table in &self.tables {
+            items.push(CompletionItem {
+                label: table.name().to_owned(),
+                kind: Some(CompletionItemKind::CLASS),
+                detail: Some("table".to_owned()),
+                ..CompletionItem::default()
+            });
+        }
+        for formula_url in self.documents.keys() {
+            if let Some(name) = Self::formula_name(formula_url).filter(|_| formula_url != url) {
+                items.push(CompletionItem {
+                    label: name.to_owned(),
+                    kind: Some(CompletionItemKind::VARIABLE),
+                    detail: Some(format!("formula from {formula_url}")),
+                    ..CompletionItem::default()
+                });
+            }
+        }
+        for symbol in self.symbols(url) {
+            if symbol.kind == SymbolKind::LetBinding && symbol.location.end() <= offset {
+                items.push(CompletionItem {
+                    label: symbol.name,
+                    kind: Some(CompletionItemKind::VARIABLE),
+                    detail: Some("let binding".to_owned()),
+                    ..CompletionItem::default()
+                });
+            }
+        }
+
+        let functions = sparrow_compiler::registered_functions()
+            .filter(|function| !function.is_internal())
+            .map(|function| function.name())
+            .chain(SPECIAL_FUNCTIONS.iter().copied());
+        for name in functions {
+            items.push(CompletionItem {
+                label: name.to_owned(),
+                kind: Some(CompletionItemKind::FUNCTION),
+                detail: function_signature(name),
+                documentation: function_doc(name)
+                    .and_then(|doc| doc.short_doc.clone())
+                    .map(Documentation::String),
+                ..CompletionItem::default()
+            });
+        }
+        items
+    }
+
+    /// Return the fields of the table or record named `base`.
+    fn field_completions(&self, url: &Url, base: &str) -> Vec<CompletionItem> {
+        let field_item = |name: &str, value_type: String| CompletionItem {
+            label: name.to_owned(),
+            kind: Some(CompletionItemKind::FIELD),
+            detail: Some(value_type),
+            ..CompletionItem::default()
+        };
+
+        if let Some(table) = self.tables.iter().find(|table| table.name() == base) {
+            let fields = table
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.schema.as_ref())
+                .map(|schema| schema.fields.as_slice())
+                .unwrap_or_default();
+            return fields
+                .iter()
+                .map(|field| {
+                    let value_type = field
+                        .data_type
+                        .as_ref()
+                        .and_then(|data_type| FenlType::try_from(data_type).ok())
+                        .map_or_else(|| "unknown".to_owned(), |t| t.to_string());
+                    field_item(&field.name, value_type)
+                })
+                .collect();
+        }
+
+        // Otherwise, use the type of the most recent annotation of the name.
+        let Some(analysis) = self.analyses.get(url) else {
+            return vec![];
+        };
+        let record_type = analysis
+            .type_annotations
+            .iter()
+            .rev()
+            .find_map(|annotation| {
+                let name = analysis
+                    .text
+                    .get(annotation.location.start()..annotation.location.end());
+                match &annotation.value_type {
+                    FenlType::Concrete(arrow::datatypes::DataType::Struct(fields))
+                        if name == Some(base) =>
+                    {
+                        Some(fields)
+                    }
+                    _ => None,
+                }
+            });
+        record_type
+            .map(|fields| {
+                fields
+                    .iter()
+                    .map(|field| field_item(field.name(), FenlType::from(field).to_string()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Convert a diagnostic to an LSP diagnostic within the query `text`.
+///
+/// Returns `None` if the diagnostic is only reported against other parts of
+/// the feature set, since those belong to other documents.
+fn to_lsp_diagnostic(text: &str, diagnostic: &CollectedDiagnostic) -> Option<Diagnostic> {
+    let labels = diagnostic.primary_labels();
+    let query_label = labels
+        .iter()
+        .find(|label| label.location().part() == FeatureSetPart::Query);
+    if query_label.is_none()
+        && labels
+            .iter()
+            .any(|label| matches!(label.location().part(), FeatureSetPart::Formula(_)))
+    {
+        return None;
+    }
+
+    let range = query_label.map_or_else(Range::default, |label| {
+        offsets_to_range(text, label.location().start(), label.location().end())
+    });
+
+    let mut message = diagnostic.message().to_owned();
+    if let Some(label) = query_label.filter(|label| !label.inner().is_empty()) {
+        message.push_str(&format!(": {}", label.inner()));
+    }
+    for note in diagnostic.notes() {
+        message.push_str(&format!("\n{note}"));
+    }
+
+    let severity = match FenlDiagnostic::from(diagnostic.clone()).severity() {
+        Severity::Error => DiagnosticSeverity::ERROR,
+        Severity::Warning => DiagnosticSeverity::WARNING,
+        _ => DiagnosticSeverity::INFORMATION,
+    };
+
+    Some(Diagnostic {
+        range,
+        severity: Some(severity),
+        code: Some(NumberOrString::String(diagnostic.code_str().to_owned())),
+        source: Some("fenl".to_owned()),
+        message,
+        ..Diagnostic::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use sparrow_api::kaskada::v1alpha::{
+        data_type, schema, DataType, Schema, TableConfig, TableMetadata,
+    };
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn primitive_field(name: &str, primitive: data_type::PrimitiveType) -> schema::Field {
+        schema::Field {
+            name: name.to_owned(),
+            data_type: Some(DataType {
+                kind: Some(data_type::Kind::Primitive(primitive as i32)),
+            }),
+        }
+    }
+
+    fn workspace() -> Workspace {
+        let table = ComputeTable {
+            config: Some(TableConfig::new_with_table_source(
+                "Purchases",
+                &Uuid::new_v4(),
+                "time",
+                None,
+                "key",
+                "user",
+            )),
+            metadata: Some(TableMetadata {
+                schema: Some(Schema {
+                    fields: vec![
+                        primitive_field("time", data_type::PrimitiveType::TimestampNanosecond),
+                        primitive_field("key", data_type::PrimitiveType::String),
+                        primitive_field("amount", data_type::PrimitiveType::I64),
+                    ],
+                }),
+                file_count: 0,
+            }),
+            file_sets: vec![],
+        };
+        Workspace::new(vec![table])
+    }
+
+    fn url(name: &str) -> Url {
+        Url::parse(&format!("file:///workspace/{name}")).unwrap()
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let mut workspace = workspace();
+        let analysis = workspace.update_document(
+            url("query.fenl"),
+            "{ total: sum(Purchases.amont) }".to_owned(),
+        );
+
+        assert_eq!(analysis.diagnostics.len(), 1);
+        let diagnostic = &analysis.diagnostics[0];
+        assert_eq!(
+            diagnostic.range,
+            Range::new(Position::new(0, 23), Position::new(0, 28))
+        );
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::ERROR));
+        assert!(
+            diagnostic.message.contains("'amount'"),
+            "{}",
+            diagnostic.message
+        );
+    }
+
+    #[test]
+    fn test_hover_and_definition() {
+        let mut workspace = workspace();
+        workspace.update_document(url("total.fenl"), "sum(Purchases.amount)".to_owned());
+        let query = url("query.fenl");
+        let analysis = workspace.update_document(
+            query.clone(),
+            "let doubled = total * 2\nin { doubled, total }".to_owned(),
+        );
+        assert_eq!(analysis.diagnostics, vec![]);
+
+        // Hover on the reference to `doubled` in the record.
+        let hover = workspace.hover(&query, Position::new(1, 6)).unwrap();
+        let HoverContents::Markup(contents) = hover.contents else {
+            panic!("expected markup")
+        };
+        assert_eq!(contents.value, "```fenl\ndoubled: i64\n```\n");
+
+        // Go to the `let` binding of `doubled`.
+        let definition = workspace.definition(&query, Position::new(1, 6)).unwrap();
+        assert_eq!(definition.uri, query);
+        assert_eq!(
+            definition.range,
+            Range::new(Position::new(0, 4), Position::new(0, 11))
+        );
+
+        // Go to the formula document for `total`.
+        let definition = workspace.definition(&query, Position::new(1, 16)).unwrap();
+        assert_eq!(definition.uri, url("total.fenl"));
+
+        // Hover on a function includes the catalog documentation.
+        let hover = workspace
+            .hover(&url("total.fenl"), Position::new(0, 1))
+            .unwrap();
+        let HoverContents::Markup(contents) = hover.contents else {
+            panic!("expected markup")
+        };
+        assert!(contents
+            .value
+            .contains("Computes the sum of values across the input."));
+        assert!(contents.value.contains("Result type: `i64`"));
+    }
+
+    #[test]
+    fn test_completion() {
+        let mut workspace = workspace();
+        let query = url("query.fenl");
+        workspace.update_document(query.clone(), "{ x: Purchases. }".to_owned());
+
+        let labels = |items: Vec<CompletionItem>| -> Vec<String> {
+            items.into_iter().map(|item| item.label).collect()
+        };
+        assert_eq!(
+            labels(workspace.completion(&query, Position::new(0, 15))),
+            vec!["time", "key", "amount"]
+        );
+
+        let items = labels(workspace.completion(&query, Position::new(0, 5)));
+        assert!(items.contains(&"Purchases".to_owned()));
+        assert!(items.contains(&"sum".to_owned()));
+        assert!(items.contains(&"extend".to_owned()));
+    }
+}
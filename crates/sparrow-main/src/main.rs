@@ -12,7 +12,10 @@ use clap::{command, Parser};
 use error_stack::{FutureExt, ResultExt};
 use opentelemetry::global;
 use sparrow_main::tracing_setup::{setup_tracing, TracingOptions};
-use sparrow_main::{BatchCommand, MaterializeCommand, PrepareCommand, ServeCommand};
+use sparrow_main::{
+    BatchCommand, FmtCommand, LspCommand, MaterializeCommand, PrepareCommand, ReplCommand,
+    ServeCommand, SnapshotCommand, TestCommand,
+};
 use tracing::error;
 
 #[cfg(not(target_os = "windows"))]
@@ -49,13 +52,25 @@ enum Command {
     Prepare(PrepareCommand),
     /// Create a long-running process that materializes results to a destination.
     Materialize(MaterializeCommand),
+    /// List, inspect and prune compute snapshots.
+    Snapshot(SnapshotCommand),
+    /// Start an interactive Fenl REPL over local files.
+    Repl(ReplCommand),
+    /// Run the Fenl language server over stdin and stdout.
+    Lsp(LspCommand),
+    /// Format Fenl source files.
+    Fmt(FmtCommand),
+    /// Run golden tests of queries against expected results.
+    Test(TestCommand),
     /// License report and notice.
     License,
 }
 
 #[tokio::main]
 async fn main() {
-    let options = SparrowOptions::parse();
+    let mut options = SparrowOptions::parse();
+    // The language server uses stdout for the protocol.
+    options.tracing_options.log_to_stderr = matches!(options.command, Command::Lsp(_));
     setup_tracing(&options.tracing_options);
 
     if !options.disable_log_panic_handler {
@@ -89,6 +104,11 @@ async fn main_body(options: SparrowOptions) -> error_stack::Result<(), Error> {
             println!("{NOTICE}");
         }
         Command::Materialize(materialize) => materialize.execute().await.change_context(Error)?,
+        Command::Snapshot(snapshot) => snapshot.execute().await.change_context(Error)?,
+        Command::Repl(repl) => repl.execute().await.change_context(Error)?,
+        Command::Lsp(lsp) => lsp.execute().await.change_context(Error)?,
+        Command::Fmt(fmt) => fmt.execute().change_context(Error)?,
+        Command::Test(test) => test.execute().await.change_context(Error)?,
     };
 
     Ok(())
@@ -148,6 +168,14 @@ mod tests {
         insta::assert_snapshot!(output);
     }
 
+    #[test]
+    fn test_snapshot_help_stdout() {
+        let mut cmd = assert_cmd::Command::cargo_bin("sparrow-main").unwrap();
+        let assert = cmd.arg("snapshot").arg("--help").assert().success();
+        let output = std::str::from_utf8(&assert.get_output().stdout).unwrap();
+        insta::assert_snapshot!(output);
+    }
+
     #[test]
     fn test_serve_help_stdout() {
         let mut cmd = assert_cmd::Command::cargo_bin("sparrow-main").unwrap();
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use error_stack::ResultExt;
@@ -92,6 +93,9 @@ impl MaterializeCommand {
                 expression_kind: ExpressionKind::Complete as i32,
                 experimental: false,
                 per_entity_behavior: self.compiler_options.per_entity_behavior as i32,
+                previous_plan: None,
+                explain: false,
+                lint_severities: vec![],
             },
             self.compiler_options.internal,
         )
@@ -121,6 +125,10 @@ impl MaterializeCommand {
                 compute_snapshot_config: None,
                 changed_since: None,
                 final_result_time: None,
+                operation_progress: false,
+                start_time: None,
+                examples: None,
+                output_destinations: HashMap::new(),
             },
             s3_helper,
             Some(script.bounded_lateness_ns),
@@ -5,12 +5,14 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use error_stack::{IntoReport, IntoReportCompat, ResultExt};
-use sparrow_api::kaskada::v1alpha::{source_data, PrepareDataRequest, PulsarConfig, SlicePlan};
+use sparrow_api::kaskada::v1alpha::{
+    source_data, DataProfileOptions, PrepareDataRequest, PulsarConfig, SlicePlan,
+};
 use sparrow_api::kaskada::v1alpha::{PulsarSubscription, SourceData};
 
 use sparrow_runtime::stores::ObjectStoreRegistry;
 
-use crate::script::{Schema, ScriptPath};
+use crate::script::{DataQualityRules, Schema, ScriptPath};
 use crate::serve;
 
 /// Options for the Prepare command.
@@ -37,6 +39,21 @@ pub struct PrepareCommand {
     /// This must be defined in the schema.
     #[arg(long)]
     pub table: String,
+
+    /// Profile the prepared data, storing the profile with each prepared file.
+    #[arg(long)]
+    pub profile: bool,
+
+    /// Number of buckets in the event-time histogram of the profile.
+    #[arg(long, default_value = "10")]
+    pub time_histogram_buckets: u32,
+
+    /// Path to a YAML or JSON file containing data quality rules.
+    ///
+    /// The rules are checked against the profile of each prepared file.
+    /// Implies `--profile`.
+    #[arg(long)]
+    pub data_quality_rules: Option<PathBuf>,
 }
 
 #[derive(derive_more::Display, Debug)]
@@ -53,6 +70,8 @@ pub enum Error {
     Canonicalize,
     #[display(fmt = "unrecognized input format")]
     UnrecognizedInputFormat,
+    #[display(fmt = "invalid data quality rules")]
+    InvalidDataQualityRules,
 }
 
 impl error_stack::Context for Error {}
@@ -219,17 +238,47 @@ impl PrepareCommand {
             output_path_prefix: self.output_path.to_string_lossy().to_string(),
             file_prefix: file_prefix.to_string(),
             slice_plan: Some(sp),
+            data_profile: self.data_profile_options()?,
         };
         let object_store_registry = Arc::new(ObjectStoreRegistry::new());
 
-        serve::preparation_service::prepare_data(object_store_registry, tonic::Request::new(pdr))
-            .await
-            .change_context(Error::Preparing)
-            .attach_printable_lazy(|| ScriptPath(self.schema.clone()))
-            .attach_printable_lazy(|| TableName(self.table.clone()))?;
+        let response = serve::preparation_service::prepare_data(
+            object_store_registry,
+            tonic::Request::new(pdr),
+        )
+        .await
+        .change_context(Error::Preparing)
+        .attach_printable_lazy(|| ScriptPath(self.schema.clone()))
+        .attach_printable_lazy(|| TableName(self.table.clone()))?
+        .into_inner();
+
+        for warning in response.data_quality_warnings {
+            println!(
+                "Data quality warning for '{}': {}",
+                warning.path, warning.message
+            );
+        }
 
         Ok(())
     }
+
+    fn data_profile_options(&self) -> error_stack::Result<Option<DataProfileOptions>, Error> {
+        let rules = match &self.data_quality_rules {
+            Some(path) => {
+                DataQualityRules::try_from(path)
+                    .change_context(Error::InvalidDataQualityRules)
+                    .attach_printable_lazy(|| LabeledPath::new("rules path", path.clone()))?
+                    .rules
+            }
+            None if self.profile => vec![],
+            None => return Ok(None),
+        };
+
+        Ok(Some(DataProfileOptions {
+            time_histogram_buckets: self.time_histogram_buckets,
+            rules,
+        }))
+    }
 }
 
 // Hack to remove auth fields from log output.
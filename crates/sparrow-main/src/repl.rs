@@ -0,0 +1,403 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+use arrow::datatypes::SchemaRef;
+use error_stack::{IntoReport, ResultExt};
+use sparrow_api::kaskada::v1alpha::compile_request::ExpressionKind;
+use sparrow_api::kaskada::v1alpha::execute_request::Limits;
+use sparrow_api::kaskada::v1alpha::{
+    CompileRequest, CompileResponse, ComputeTable, FeatureSet, Formula, PerEntityBehavior,
+    TableConfig,
+};
+use sparrow_compiler::InternalCompileOptions;
+use sparrow_runtime::s3::S3Helper;
+use sparrow_runtime::stores::ObjectStoreRegistry;
+use sparrow_syntax::FenlType;
+use tempfile::TempDir;
+use uuid::Uuid;
+
+use crate::local_files;
+
+/// Options for the REPL command.
+#[derive(clap::Args, Debug)]
+#[command(version, rename_all = "kebab-case")]
+pub struct ReplCommand {
+    /// Tables to load, as `NAME=PATH` to a local Parquet or CSV file.
+    ///
+    /// Tables may also be loaded from within the REPL using `:load NAME PATH`.
+    #[arg(long = "table", value_name = "NAME=PATH")]
+    pub tables: Vec<String>,
+
+    /// The column containing the time of each row in the loaded tables.
+    #[arg(long, default_value = "time")]
+    pub time_column: String,
+
+    /// The column containing the entity key of each row in the loaded tables.
+    #[arg(long, default_value = "key")]
+    pub entity_column: String,
+
+    /// The number of rows to preview for each expression.
+    #[arg(long, default_value = "20")]
+    pub preview_rows: i64,
+}
+
+#[derive(derive_more::Display, Debug)]
+pub enum Error {
+    #[display(fmt = "invalid table '{_0}': expected 'NAME=PATH'")]
+    InvalidTable(String),
+    #[display(fmt = "invalid command: {_0}")]
+    InvalidCommand(String),
+    #[display(fmt = "unknown table '{_0}'")]
+    UnknownTable(String),
+    #[display(fmt = "failed to prepare table")]
+    Preparing,
+    #[display(fmt = "failed to compile query")]
+    Compilation,
+    #[display(fmt = "failed to execute query")]
+    Execution,
+    #[display(fmt = "internal error")]
+    Internal,
+}
+
+impl error_stack::Context for Error {}
+
+const HELP: &str = "\
+Enter a Fenl expression to preview its results, or one of:
+  let NAME = EXPR     define NAME for use in later expressions
+  :type EXPR          print the type of EXPR
+  :plan EXPR          print an explanation of the plan for EXPR
+  :schema [TABLE]     print the schema of TABLE (or all tables)
+  :load NAME PATH     load a table from a local Parquet or CSV file
+  :help               print this message
+  :quit               exit the REPL";
+
+impl ReplCommand {
+    #[allow(clippy::print_stdout)]
+    pub async fn execute(self) -> error_stack::Result<(), Error> {
+        let mut session =
+            ReplSession::try_new(self.time_column, self.entity_column, self.preview_rows).await?;
+        for table in &self.tables {
+            let (name, path) = table
+                .split_once('=')
+                .ok_or_else(|| Error::InvalidTable(table.clone()))?;
+            println!("{}", session.load(name, Path::new(path)).await?);
+        }
+
+        println!("Fenl REPL. Enter `:help` for a list of commands.");
+        let mut line = String::new();
+        loop {
+            print!("fenl> ");
+            std::io::stdout()
+                .flush()
+                .into_report()
+                .change_context(Error::Internal)?;
+
+            line.clear();
+            let read = tokio::task::block_in_place(|| std::io::stdin().read_line(&mut line))
+                .into_report()
+                .change_context(Error::Internal)?;
+            let input = line.trim();
+            if read == 0 || input == ":quit" || input == ":q" {
+                break;
+            }
+
+            // Errors from a single line are reported, but don't end the session.
+            match session.eval(input).await {
+                Ok(output) => println!("{output}"),
+                Err(err) => println!("{err:?}"),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The state of a REPL session.
+///
+/// This holds the loaded tables and the `let` bindings, which are provided
+/// as formulas when compiling each expression.
+struct ReplSession {
+    time_column: String,
+    entity_column: String,
+    preview_rows: i64,
+    tables: Vec<ComputeTable>,
+    schemas: BTreeMap<String, SchemaRef>,
+    formulas: Vec<Formula>,
+    object_store_registry: ObjectStoreRegistry,
+    s3_helper: S3Helper,
+    /// Directory containing the prepared tables and query outputs.
+    ///
+    /// This is deleted when the session ends.
+    data_dir: TempDir,
+}
+
+impl ReplSession {
+    async fn try_new(
+        time_column: String,
+        entity_column: String,
+        preview_rows: i64,
+    ) -> error_stack::Result<Self, Error> {
+        let data_dir = tempfile::Builder::new()
+            .prefix("sparrow-repl")
+            .tempdir()
+            .into_report()
+            .change_context(Error::Internal)?;
+        Ok(Self {
+            time_column,
+            entity_column,
+            preview_rows,
+            tables: vec![],
+            schemas: BTreeMap::new(),
+            formulas: vec![],
+            object_store_registry: ObjectStoreRegistry::new(),
+            s3_helper: S3Helper::new().await,
+            data_dir,
+        })
+    }
+
+    /// Evaluate a line of input and return the output to print.
+    async fn eval(&mut self, input: &str) -> error_stack::Result<String, Error> {
+        if input.is_empty() {
+            return Ok(String::new());
+        }
+
+        if let Some(command) = input.strip_prefix(':') {
+            let (command, argument) = command
+                .split_once(char::is_whitespace)
+                .map_or((command, ""), |(command, argument)| {
+                    (command, argument.trim())
+                });
+            match command {
+                "help" => Ok(HELP.to_owned()),
+                "type" => self.type_of(argument).await,
+                "plan" => self.plan(argument).await,
+                "schema" => self.schema(argument),
+                "load" => {
+                    let (name, path) = argument
+                        .split_once(char::is_whitespace)
+                        .ok_or_else(|| Error::InvalidCommand(input.to_owned()))?;
+                    self.load(name, Path::new(path.trim())).await
+                }
+                _ => error_stack::bail!(Error::InvalidCommand(input.to_owned())),
+            }
+        } else if let Some(binding) = input.strip_prefix("let ") {
+            let (name, expr) = binding
+                .split_once('=')
+                .ok_or_else(|| Error::InvalidCommand(input.to_owned()))?;
+            self.define(name.trim(), expr.trim()).await
+        } else {
+            self.preview(input).await
+        }
+    }
+
+    /// Prepare the table at `path` and make it available as `name`.
+    async fn load(&mut self, name: &str, path: &Path) -> error_stack::Result<String, Error> {
+        let config = TableConfig::new_with_table_source(
+            name,
+            &Uuid::new_v4(),
+            &self.time_column,
+            None,
+            &self.entity_column,
+            &self.entity_column,
+        );
+
+        let output_dir = self.data_dir.path().join(format!("table-{name}"));
+        let (table, schema) = local_files::prepare_local_table(
+            &self.object_store_registry,
+            path,
+            config,
+            &output_dir,
+        )
+        .await
+        .change_context(Error::Preparing)?;
+
+        // Replace any previous table with the same name.
+        self.tables.retain(|table| table.name() != name);
+        self.tables.push(table);
+        self.schemas.insert(name.to_owned(), schema);
+
+        Ok(format!("Loaded table '{name}' from '{}'", path.display()))
+    }
+
+    async fn define(&mut self, name: &str, expr: &str) -> error_stack::Result<String, Error> {
+        let response = self.compile(expr, ExpressionKind::Formula, false).await?;
+        if let Some(diagnostics) = errors(&response) {
+            return Ok(diagnostics);
+        }
+
+        self.formulas.retain(|formula| formula.name != name);
+        self.formulas.push(Formula {
+            name: name.to_owned(),
+            formula: expr.to_owned(),
+            source_location: format!("let {name}"),
+        });
+        Ok(format!("{name}: {}", result_type(&response)))
+    }
+
+    async fn type_of(&self, expr: &str) -> error_stack::Result<String, Error> {
+        let response = self.compile(expr, ExpressionKind::Formula, false).await?;
+        Ok(errors(&response).unwrap_or_else(|| result_type(&response)))
+    }
+
+    async fn plan(&self, expr: &str) -> error_stack::Result<String, Error> {
+        let query = self.query(expr).await?;
+        let response = self.compile(&query, ExpressionKind::Complete, true).await?;
+        Ok(errors(&response).unwrap_or(response.explanation))
+    }
+
+    fn schema(&self, table: &str) -> error_stack::Result<String, Error> {
+        let mut output = String::new();
+        for (name, schema) in &self.schemas {
+            if !table.is_empty() && name != table {
+                continue;
+            }
+            output.push_str(&format!("{name}:\n"));
+            for field in schema.fields() {
+                let data_type = FenlType::Concrete(field.data_type().clone());
+                output.push_str(&format!("  {}: {data_type}\n", field.name()));
+            }
+        }
+
+        error_stack::ensure!(
+            table.is_empty() || !output.is_empty(),
+            Error::UnknownTable(table.to_owned())
+        );
+        Ok(output)
+    }
+
+    async fn preview(&self, expr: &str) -> error_stack::Result<String, Error> {
+        let query = self.query(expr).await?;
+        let response = self
+            .compile(&query, ExpressionKind::Complete, false)
+            .await?;
+        if let Some(diagnostics) = errors(&response) {
+            return Ok(diagnostics);
+        }
+
+        let output_dir = tempfile::Builder::new()
+            .prefix("preview")
+            .tempdir_in(self.data_dir.path())
+            .into_report()
+            .change_context(Error::Internal)?;
+        local_files::execute_to_csv(
+            response.plan,
+            self.tables.clone(),
+            Some(Limits {
+                preview_rows: self.preview_rows,
+                ..Limits::default()
+            }),
+            self.s3_helper.clone(),
+            output_dir.path(),
+        )
+        .await
+        .change_context(Error::Execution)
+    }
+
+    /// Return a complete query for the expression.
+    ///
+    /// Expressions which don't produce a record are wrapped in one.
+    async fn query(&self, expr: &str) -> error_stack::Result<String, Error> {
+        let response = self.compile(expr, ExpressionKind::Formula, false).await?;
+        let is_record = response.result_type.as_ref().map_or(false, |result_type| {
+            matches!(
+                arrow::datatypes::DataType::try_from(result_type),
+                Ok(arrow::datatypes::DataType::Struct(_))
+            )
+        });
+        if is_record {
+            Ok(expr.to_owned())
+        } else {
+            Ok(format!("{{ result: {expr} }}"))
+        }
+    }
+
+    async fn compile(
+        &self,
+        query: &str,
+        expression_kind: ExpressionKind,
+        explain: bool,
+    ) -> error_stack::Result<CompileResponse, Error> {
+        sparrow_compiler::compile_proto(
+            CompileRequest {
+                tables: self.tables.clone(),
+                feature_set: Some(FeatureSet {
+                    formulas: self.formulas.clone(),
+                    query: query.to_owned(),
+                    outputs: vec![],
+                }),
+                slice_request: None,
+                expression_kind: expression_kind as i32,
+                experimental: false,
+                per_entity_behavior: PerEntityBehavior::All as i32,
+                previous_plan: None,
+                explain,
+                lint_severities: vec![],
+            },
+            InternalCompileOptions::default(),
+        )
+        .await
+        .change_context(Error::Compilation)
+    }
+}
+
+/// Return the formatted diagnostics if the response has errors.
+fn errors(response: &CompileResponse) -> Option<String> {
+    let diagnostics = response.fenl_diagnostics.as_ref()?;
+    (diagnostics.num_errors > 0).then(|| diagnostics.to_string())
+}
+
+fn result_type(response: &CompileResponse) -> String {
+    match response.result_type.as_ref().map(FenlType::try_from) {
+        Some(Ok(result_type)) => result_type.to_string(),
+        _ => "unknown".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT_CSV: &str = "\
+time,key,amount
+2023-01-01T00:00:00Z,a,5
+2023-01-02T00:00:00Z,b,7
+2023-01-03T00:00:00Z,a,11
+";
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_repl_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("purchases.csv");
+        std::fs::write(&input, INPUT_CSV).unwrap();
+
+        let mut session = ReplSession::try_new("time".to_owned(), "key".to_owned(), 10)
+            .await
+            .unwrap();
+        session
+            .eval(&format!(":load Purchases {}", input.display()))
+            .await
+            .unwrap();
+
+        let schema = session.eval(":schema Purchases").await.unwrap();
+        assert!(schema.starts_with("Purchases:\n"), "{schema}");
+        assert!(schema.contains("  amount: i64\n"), "{schema}");
+        assert_eq!(
+            session
+                .eval("let total = sum(Purchases.amount)")
+                .await
+                .unwrap(),
+            "total: i64"
+        );
+        assert_eq!(session.eval(":type total + 1").await.unwrap(), "i64");
+
+        let preview = session.eval("total").await.unwrap();
+        assert_eq!(preview.lines().count(), 4, "header and 3 rows: {preview}");
+        assert!(
+            preview.lines().last().unwrap().ends_with(",16"),
+            "{preview}"
+        );
+
+        assert!(session.eval(":schema Missing").await.is_err());
+    }
+}
@@ -4,7 +4,7 @@ use std::path::{Path, PathBuf};
 
 use error_stack::{IntoReport, ResultExt};
 use serde::{Deserialize, Serialize};
-use sparrow_api::kaskada::v1alpha::{ComputeTable, Destination, FeatureSet};
+use sparrow_api::kaskada::v1alpha::{ComputeTable, DataQualityRule, Destination, FeatureSet};
 
 /// A serializable description of schema for a set of tables.
 #[derive(Debug, Serialize, Deserialize)]
@@ -26,6 +26,13 @@ pub(crate) struct Script {
     pub(crate) bounded_lateness_ns: i64,
 }
 
+/// A serializable set of data quality rules to check during preparation.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct DataQualityRules {
+    /// The rules to check against the profile of each prepared file.
+    pub(crate) rules: Vec<DataQualityRule>,
+}
+
 #[derive(derive_more::Display, Debug)]
 pub enum Error {
     #[display(fmt = "failed to open script file")]
@@ -78,3 +85,9 @@ impl Schema {
         deserialize_from_path(path)
     }
 }
+
+impl DataQualityRules {
+    pub fn try_from(path: &Path) -> error_stack::Result<Self, Error> {
+        deserialize_from_path(path)
+    }
+}
@@ -4,6 +4,7 @@
 mod compute_service;
 mod error_status;
 mod file_service;
+mod metrics;
 pub(crate) mod preparation_service;
 use error_stack::{IntoReport, IntoReportCompat, ResultExt};
 pub use error_status::*;
@@ -40,6 +41,13 @@ pub struct ServeCommand {
     /// `flight_records` prefix of the given S3 bucket.
     #[arg(long, env = "SPARROW_FLIGHT_RECORD_PATH")]
     flight_record_path: Option<String>,
+
+    /// Address to serve Prometheus metrics on.
+    ///
+    /// If `None`, metrics will not be served. Otherwise, metrics are available
+    /// at `/metrics` on the given address.
+    #[arg(long, env = "SPARROW_METRICS_ADDR")]
+    metrics_addr: Option<SocketAddr>,
 }
 
 #[derive(derive_more::Display, Debug)]
@@ -48,6 +56,8 @@ pub enum Error {
     InvalidFlightRecordPath,
     #[display(fmt = "error running Tonic server")]
     ServerError,
+    #[display(fmt = "error running metrics server")]
+    MetricsServerError,
 }
 
 impl error_stack::Context for Error {}
@@ -90,6 +100,14 @@ impl ServeCommand {
             .build()
             .unwrap();
 
+        let metrics_future = async {
+            if let Some(metrics_addr) = self.metrics_addr {
+                metrics::serve_metrics(metrics_addr).await
+            } else {
+                futures::future::pending().await
+            }
+        };
+
         info!("Starting Sparrow listening on {}", self.service_addr);
         let server_future = Server::builder()
             .trace_fn(propagate_span)
@@ -105,10 +123,15 @@ impl ServeCommand {
             .set_serving::<ComputeServiceServer<ComputeServiceImpl>>()
             .await;
 
-        server_future
-            .await
-            .into_report()
-            .change_context(Error::ServerError)?;
+        // The metrics server runs until the gRPC server stops (or fails).
+        tokio::select! {
+            result = server_future => {
+                result.into_report().change_context(Error::ServerError)?
+            }
+            result = metrics_future => {
+                result.into_report().change_context(Error::MetricsServerError)?
+            }
+        }
 
         Ok(())
     }
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use error_stack::{IntoReport, ResultExt};
 use futures::stream::BoxStream;
 use futures::{Stream, StreamExt};
@@ -23,6 +25,7 @@ use tracing::{error, info, Instrument};
 use uuid::Uuid;
 
 use crate::serve::error_status::IntoStatus;
+use crate::serve::metrics::{self, ServiceMethod};
 use crate::BuildInfo;
 
 #[derive(Debug)]
@@ -59,6 +62,7 @@ impl ComputeService for ComputeServiceImpl {
     ) -> Result<Response<CompileResponse>, Status> {
         let span = tracing::info_span!("Compile");
         let _enter = span.enter();
+        let _timer = metrics::start_request(ServiceMethod::Compile);
 
         match tokio::spawn(compile_impl(request).in_current_span())
             .in_current_span()
@@ -78,6 +82,8 @@ impl ComputeService for ComputeServiceImpl {
     ) -> Result<Response<Self::ExecuteStream>, Status> {
         let span = tracing::info_span!("Execute");
         let _enter = span.enter();
+        // The execution is timed until the response stream is dropped.
+        let timer = metrics::start_request(ServiceMethod::Execute);
 
         let handle = tokio::spawn(
             execute_impl(
@@ -89,7 +95,10 @@ impl ComputeService for ComputeServiceImpl {
         );
         match handle.in_current_span().await {
             Ok(result) => {
-                let stream = result.into_status()?;
+                let stream = result.into_status()?.map(move |response| {
+                    let _ = &timer;
+                    response
+                });
                 Ok(Response::new(Box::pin(stream)))
             }
             Err(panic) => {
@@ -265,6 +274,7 @@ async fn debug_message(
         flight_record_path: uploaded_flight_record_path,
         plan_yaml_path: uploaded_plan_yaml_path,
         compute_snapshots: Vec::new(),
+        output_destinations: HashMap::new(),
     })
 }
 
@@ -403,11 +413,15 @@ mod tests {
             feature_set: Some(FeatureSet {
                 formulas: vec![],
                 query: "{x: Table1.str as i64, y: Table1.str }".to_owned(),
+                outputs: vec![],
             }),
             slice_request: None,
             expression_kind: ExpressionKind::Complete as i32,
             experimental: true,
             per_entity_behavior: PerEntityBehavior::Final as i32,
+            previous_plan: None,
+            explain: false,
+            lint_severities: vec![],
         }))
         .await
         .unwrap();
@@ -438,11 +452,15 @@ mod tests {
             feature_set: Some(FeatureSet {
                 formulas: vec![],
                 query: "{x: Table1.str as i64, y: lag(Table1.str, 1) }".to_owned(),
+                outputs: vec![],
             }),
             slice_request: None,
             expression_kind: ExpressionKind::Complete as i32,
             experimental: true,
             per_entity_behavior: PerEntityBehavior::Final as i32,
+            previous_plan: None,
+            explain: false,
+            lint_severities: vec![],
         }))
         .await
         .unwrap();
@@ -473,11 +491,15 @@ mod tests {
             feature_set: Some(FeatureSet {
                 formulas: vec![],
                 query: "Table1.str as i64".to_owned(),
+                outputs: vec![],
             }),
             slice_request: None,
             expression_kind: ExpressionKind::Formula as i32,
             experimental: false,
             per_entity_behavior: PerEntityBehavior::All as i32,
+            previous_plan: None,
+            explain: false,
+            lint_severities: vec![],
         }))
         .await
         .unwrap();
@@ -499,6 +521,9 @@ mod tests {
             slice: ~
         incremental_enabled: false
         plan_hash: ~
+        state_reuse: ~
+        explanation: ""
+        plan_statistics: ~
         "###)
     }
 
@@ -625,6 +650,7 @@ mod tests {
             feature_set: Some(FeatureSet {
                 formulas: vec![],
                 query: "Events".to_owned(),
+                outputs: vec![],
             }),
             slice_request: Some(SliceRequest {
                 slice: Some(slice_request::Slice::Percent(slice_request::PercentSlice {
@@ -634,6 +660,9 @@ mod tests {
             expression_kind: ExpressionKind::Complete as i32,
             experimental: false,
             per_entity_behavior: PerEntityBehavior::All as i32,
+            previous_plan: None,
+            explain: false,
+            lint_severities: vec![],
         }))
         .await
         .unwrap()
@@ -670,6 +699,10 @@ mod tests {
                 compute_snapshot_config: None,
                 changed_since: None,
                 final_result_time: None,
+                operation_progress: false,
+                start_time: None,
+                examples: None,
+                output_destinations: HashMap::new(),
             },
         )
         .await
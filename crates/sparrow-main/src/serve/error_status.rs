@@ -1,6 +1,8 @@
 use sparrow_core::{ContextCode, ErrorCode};
 use tracing::{error, info};
 
+use crate::serve::metrics;
+
 /// Trait for converting arbitrary types to a `tonic::Status`.
 ///
 /// This allows converting external types (such as `anyhow::Error`)
@@ -75,6 +77,8 @@ where
 }
 
 fn report(code: tonic::Code, message: &str, error: &dyn std::fmt::Debug) {
+    metrics::record_error(code);
+
     // Determine the log level based on the code.
     // The `event!` macro requires the log_level to be a constant, so
     // there is no real benefit to compute the level and sharing the log.
@@ -1,16 +1,20 @@
+use std::path::Path;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use error_stack::{IntoReport, ResultExt};
 use sparrow_api::kaskada::v1alpha::file_service_server::FileService;
 use sparrow_api::kaskada::v1alpha::Schema;
 use sparrow_api::kaskada::v1alpha::{
-    GetMetadataRequest, GetMetadataResponse, MergeMetadataRequest, MergeMetadataResponse,
-    SourceData, SourceMetadata,
+    DataProfile, GetDataProfileRequest, GetDataProfileResponse, GetMetadataRequest,
+    GetMetadataResponse, MergeMetadataRequest, MergeMetadataResponse, SourceData, SourceMetadata,
 };
 
 use sparrow_runtime::RawMetadata;
 
-use sparrow_runtime::stores::ObjectStoreRegistry;
+use sparrow_runtime::stores::object_store_url::ObjectStoreKey;
+use sparrow_runtime::stores::{ObjectStoreRegistry, ObjectStoreUrl};
+use tempfile::NamedTempFile;
 use tonic::Response;
 
 use crate::serve::error_status::IntoStatus;
@@ -54,6 +58,21 @@ impl FileService for FileServiceImpl {
             "merge_metadata not implemented",
         ))
     }
+
+    #[tracing::instrument]
+    async fn get_data_profile(
+        &self,
+        request: tonic::Request<GetDataProfileRequest>,
+    ) -> Result<tonic::Response<GetDataProfileResponse>, tonic::Status> {
+        let object_store = self.object_store_registry.clone();
+        match tokio::spawn(get_data_profile(object_store, request)).await {
+            Ok(result) => result.into_status(),
+            Err(panic) => {
+                tracing::error!("Panic during get data profile: {panic}");
+                Err(tonic::Status::internal("panic during get data profile"))
+            }
+        }
+    }
 }
 
 async fn get_metadata(
@@ -73,12 +92,69 @@ async fn get_metadata(
     }))
 }
 
+async fn get_data_profile(
+    object_store_registry: Arc<ObjectStoreRegistry>,
+    request: tonic::Request<GetDataProfileRequest>,
+) -> anyhow::Result<tonic::Response<GetDataProfileResponse>> {
+    let request = request.into_inner();
+
+    let mut data_profiles = Vec::with_capacity(request.prepared_files.len());
+    for prepared_file in request.prepared_files {
+        let data_profile = read_prepared_data_profile(&object_store_registry, &prepared_file.path)
+            .await
+            .or_else(|e| anyhow::bail!("failed getting data profile: {:?}", e))?;
+        // Files prepared without profiling are reported with an empty profile.
+        data_profiles.push(data_profile.unwrap_or_default());
+    }
+
+    Ok(Response::new(GetDataProfileResponse { data_profiles }))
+}
+
+/// Read the data profile from the prepared file at `path`.
+///
+/// Remote files are downloaded to a temporary file first.
+async fn read_prepared_data_profile(
+    object_store_registry: &ObjectStoreRegistry,
+    path: &str,
+) -> error_stack::Result<Option<DataProfile>, Error> {
+    let data_profile_error = || Error::DataProfile(path.to_owned());
+
+    // Prepared files written locally have a plain path rather than a URL.
+    let Ok(url) = ObjectStoreUrl::from_str(path) else {
+        return sparrow_runtime::prepare::read_data_profile(Path::new(path))
+            .into_report()
+            .change_context_lazy(data_profile_error);
+    };
+
+    match url.key().change_context_lazy(data_profile_error)? {
+        ObjectStoreKey::Local => {
+            let local_path = format!("/{}", url.path().change_context_lazy(data_profile_error)?);
+            sparrow_runtime::prepare::read_data_profile(Path::new(&local_path))
+                .into_report()
+                .change_context_lazy(data_profile_error)
+        }
+        _ => {
+            let temp_file = NamedTempFile::new()
+                .into_report()
+                .change_context_lazy(data_profile_error)?;
+            url.download(object_store_registry, temp_file.path())
+                .await
+                .change_context_lazy(data_profile_error)?;
+            sparrow_runtime::prepare::read_data_profile(temp_file.path())
+                .into_report()
+                .change_context_lazy(data_profile_error)
+        }
+    }
+}
+
 #[derive(derive_more::Display, Debug)]
 pub enum Error {
     #[display(fmt = "unable to get source path from request")]
     SourcePath,
     #[display(fmt = "schema error: '{_0}'")]
     Schema(String),
+    #[display(fmt = "unable to read data profile from '{_0}'")]
+    DataProfile(String),
 }
 impl error_stack::Context for Error {}
 
@@ -177,4 +253,59 @@ mod tests {
 
         insta::assert_yaml_snapshot!(result);
     }
+
+    #[tokio::test]
+    async fn test_get_data_profile() {
+        let csv_data = "time,key,value\n\
+            1996-12-19T16:39:57Z,a,1\n\
+            1996-12-19T16:40:57Z,b,\n\
+            1996-12-19T16:41:57Z,a,3\n";
+        let table_config = sparrow_api::kaskada::v1alpha::TableConfig::new_with_table_source(
+            "Table",
+            &uuid::Uuid::new_v4(),
+            "time",
+            None,
+            "key",
+            "",
+        );
+        let object_store_registry = Arc::new(ObjectStoreRegistry::new());
+        let output_dir = tempfile::tempdir().unwrap();
+        let (_, prepared_files) = sparrow_runtime::prepare::prepare_file(
+            &object_store_registry,
+            &SourceData {
+                source: Some(source_data::Source::CsvData(csv_data.to_owned())),
+            },
+            &format!("file://{}", output_dir.path().display()),
+            "prepared",
+            &table_config,
+            &None,
+            &Some(sparrow_api::kaskada::v1alpha::DataProfileOptions {
+                time_histogram_buckets: 2,
+                rules: vec![],
+            }),
+        )
+        .await
+        .unwrap();
+
+        let file_service = FileServiceImpl::new(object_store_registry);
+        let result = file_service
+            .get_data_profile(tonic::Request::new(GetDataProfileRequest {
+                prepared_files,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(result.data_profiles.len(), 1);
+        let profile = &result.data_profiles[0];
+        assert_eq!(profile.num_rows, 3);
+        assert_eq!(profile.distinct_entities, 2);
+        assert_eq!(profile.duplicate_rows, 0);
+        assert_eq!(profile.time_histogram.as_ref().unwrap().counts, vec![2, 1]);
+
+        let value = profile.columns.iter().find(|c| c.name == "value").unwrap();
+        assert_eq!(value.null_count, 1);
+        assert_eq!(value.distinct_count, 2);
+        assert_eq!((value.min.as_str(), value.max.as_str()), ("1", "3"));
+    }
 }
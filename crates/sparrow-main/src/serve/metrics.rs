@@ -0,0 +1,130 @@
+//! Prometheus metrics for the gRPC services, and the `/metrics` endpoint.
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, StatusCode};
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, Encoder, HistogramTimer, HistogramVec,
+    IntCounterVec, TextEncoder,
+};
+use tracing::info;
+
+#[static_init::dynamic]
+static REQUESTS: IntCounterVec = register_int_counter_vec!(
+    "sparrow_requests_total",
+    "Number of requests received, by method.",
+    &["method"]
+)
+.expect("register requests");
+
+#[static_init::dynamic]
+static REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+    "sparrow_request_duration_seconds",
+    "Time spent handling requests, by method.",
+    &["method"],
+    vec![0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 60.0, 300.0, 1800.0]
+)
+.expect("register request duration");
+
+#[static_init::dynamic]
+static ERRORS: IntCounterVec = register_int_counter_vec!(
+    "sparrow_errors_total",
+    "Number of errors returned, by status code.",
+    &["code"]
+)
+.expect("register errors");
+
+/// The methods for which requests are counted and timed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ServiceMethod {
+    Compile,
+    Execute,
+    Prepare,
+}
+
+impl ServiceMethod {
+    fn label(&self) -> &'static str {
+        match self {
+            ServiceMethod::Compile => "compile",
+            ServiceMethod::Execute => "execute",
+            ServiceMethod::Prepare => "prepare",
+        }
+    }
+}
+
+/// Count a request for the given method and start timing it.
+///
+/// The duration is recorded when the returned timer is dropped.
+pub(crate) fn start_request(method: ServiceMethod) -> HistogramTimer {
+    REQUESTS.with_label_values(&[method.label()]).inc();
+    REQUEST_DURATION
+        .with_label_values(&[method.label()])
+        .start_timer()
+}
+
+/// Count an error returned with the given status code.
+pub(crate) fn record_error(code: tonic::Code) {
+    ERRORS.with_label_values(&[&format!("{code:?}")]).inc();
+}
+
+/// Serve the metrics from the default registry at `/metrics` on `addr`.
+pub(super) async fn serve_metrics(addr: SocketAddr) -> hyper::Result<()> {
+    // Register the metrics so they are reported before they are first used.
+    sparrow_runtime::metrics::register();
+    for method in [
+        ServiceMethod::Compile,
+        ServiceMethod::Execute,
+        ServiceMethod::Prepare,
+    ] {
+        REQUESTS.with_label_values(&[method.label()]);
+        REQUEST_DURATION.with_label_values(&[method.label()]);
+    }
+
+    info!("Serving metrics on http://{addr}/metrics");
+    let make_service =
+        make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle_metrics_request)) });
+    hyper::Server::try_bind(&addr)?.serve(make_service).await
+}
+
+async fn handle_metrics_request(request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let response = match (request.method(), request.uri().path()) {
+        (&hyper::Method::GET, "/metrics") => match encode_metrics() {
+            Ok(metrics) => Response::builder()
+                .header(
+                    hyper::header::CONTENT_TYPE,
+                    TextEncoder::new().format_type(),
+                )
+                .body(Body::from(metrics)),
+            Err(e) => Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::from(e.to_string())),
+        },
+        _ => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty()),
+    };
+    Ok(response.expect("valid response"))
+}
+
+fn encode_metrics() -> prometheus::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&prometheus::gather(), &mut buffer)?;
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_metrics() {
+        let _timer = start_request(ServiceMethod::Compile);
+        record_error(tonic::Code::InvalidArgument);
+
+        let metrics = String::from_utf8(encode_metrics().unwrap()).unwrap();
+        assert!(metrics.contains("sparrow_requests_total{method=\"compile\"}"));
+        assert!(metrics.contains("sparrow_errors_total{code=\"InvalidArgument\"}"));
+    }
+}
@@ -9,13 +9,14 @@ use sparrow_api::kaskada::v1alpha::{
     source_data, GetCurrentPrepIdRequest, GetCurrentPrepIdResponse, PrepareDataRequest,
     PrepareDataResponse, SourceData,
 };
-use sparrow_runtime::prepare::{prepare_file, Error};
+use sparrow_runtime::prepare::{check_data_quality, prepare_file, Error};
 
 use sparrow_runtime::stores::object_store_url::ObjectStoreKey;
 use sparrow_runtime::stores::{ObjectStoreRegistry, ObjectStoreUrl};
 use tempfile::NamedTempFile;
 use tonic::Response;
 
+use crate::serve::metrics::{self, ServiceMethod};
 use crate::IntoStatus;
 
 // The current preparation ID of the data preparation service
@@ -42,6 +43,7 @@ impl PreparationService for PreparationServiceImpl {
         request: tonic::Request<PrepareDataRequest>,
     ) -> Result<tonic::Response<PrepareDataResponse>, tonic::Status> {
         let object_store = self.object_store_registry.clone();
+        let _timer = metrics::start_request(ServiceMethod::Prepare);
 
         let handle = tokio::spawn(prepare_data(object_store, request));
         match handle.await {
@@ -95,19 +97,37 @@ pub async fn prepare_data(
         temp_file.path(),
     )
     .await?;
-    let (_prepared_metadata, prepared_files) = prepare_file(
+    let (prepared_metadata, prepared_files) = prepare_file(
         &object_store_registry,
         &source_data,
         &prepare_request.output_path_prefix,
         &prepare_request.file_prefix,
         &table_config,
         &slice_plan.slice,
+        &prepare_request.data_profile,
     )
     .await?;
 
+    // Any violations that would have failed preparation have already been
+    // reported, so the remaining violations are warnings.
+    let rules = prepare_request
+        .data_profile
+        .as_ref()
+        .map(|options| options.rules.as_slice())
+        .unwrap_or_default();
+    let data_quality_warnings = prepared_metadata
+        .iter()
+        .zip(prepared_files.iter())
+        .flat_map(|(metadata, file)| match &metadata.data_profile {
+            Some(profile) => check_data_quality(&file.path, profile, rules),
+            None => vec![],
+        })
+        .collect();
+
     Ok(Response::new(PrepareDataResponse {
         prep_id: CURRENT_PREP_ID,
         prepared_files,
+        data_quality_warnings,
     }))
 }
 
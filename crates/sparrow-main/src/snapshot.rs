@@ -0,0 +1,335 @@
+use std::time::{Duration, SystemTime};
+
+use error_stack::{IntoReportCompat, ResultExt};
+use sparrow_instructions::{ComputeStore, StoreKeyKind};
+use sparrow_runtime::s3::S3Helper;
+use sparrow_runtime::snapshot::{
+    delete_snapshot, describe_snapshot, entity_state, fetch_snapshot, list_snapshots,
+    verify_snapshot, AccumulatorType, AccumulatorValue, SnapshotInfo,
+};
+
+/// Options for the Snapshot command.
+#[derive(clap::Args, Debug)]
+#[command(version, rename_all = "kebab-case")]
+pub struct SnapshotCommand {
+    #[command(subcommand)]
+    command: SnapshotSubcommand,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum SnapshotSubcommand {
+    /// List the snapshots written to an output prefix.
+    List {
+        /// The `output_prefix` of the `ComputeSnapshotConfig`.
+        ///
+        /// May be a local path or an S3 URI.
+        output_prefix: String,
+    },
+    /// Describe the plan hash, max event time and version of a snapshot.
+    Describe {
+        /// Path (or S3 URI) of the snapshot.
+        snapshot: String,
+    },
+    /// Verify that a snapshot is readable and could be resumed from.
+    Verify {
+        /// Path (or S3 URI) of the snapshot.
+        snapshot: String,
+
+        /// If set, the plan hash the snapshot is expected to be for.
+        #[arg(long)]
+        plan_hash: Option<String>,
+    },
+    /// Delete snapshots that are no longer needed.
+    ///
+    /// At least one of `--older-than` or `--plan-hash` must be specified.
+    /// Snapshots that can't be read are reported and skipped.
+    Prune {
+        /// The `output_prefix` of the `ComputeSnapshotConfig`.
+        ///
+        /// May be a local path or an S3 URI.
+        output_prefix: String,
+
+        /// Delete snapshots written longer ago than this.
+        ///
+        /// A number followed by a unit of `s`, `m`, `h` or `d`, such as `7d`.
+        #[arg(long, value_parser = parse_age)]
+        older_than: Option<Duration>,
+
+        /// Delete snapshots that were not written for this plan hash.
+        ///
+        /// Checking the plan hash requires reading the snapshot, so this
+        /// downloads snapshots stored in S3 which aren't already being
+        /// deleted for their age.
+        #[arg(long)]
+        plan_hash: Option<String>,
+
+        /// Report the snapshots that would be deleted without deleting them.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Dump the contents of a snapshot.
+    ///
+    /// By default, lists every key in the snapshot. If `--entity` is set, the
+    /// state stored for that entity is printed instead.
+    Dump {
+        /// Path (or S3 URI) of the snapshot.
+        snapshot: String,
+
+        /// The key of the entity to dump the state of.
+        #[arg(long)]
+        entity: Option<String>,
+
+        /// The type to decode the accumulators of the entity as.
+        ///
+        /// If not set, only the size of each accumulator is printed.
+        #[arg(long, value_enum, requires = "entity")]
+        accumulator_type: Option<AccumulatorType>,
+    },
+}
+
+#[derive(derive_more::Display, Debug)]
+pub enum Error {
+    #[display(fmt = "failed to list snapshots")]
+    ListSnapshots,
+    #[display(fmt = "failed to read snapshot '{_0}'")]
+    ReadSnapshot(String),
+    #[display(fmt = "snapshot '{_0}' is invalid")]
+    InvalidSnapshot(String),
+    #[display(fmt = "failed to delete snapshot '{_0}'")]
+    DeleteSnapshot(String),
+    #[display(fmt = "prune requires at least one of '--older-than' or '--plan-hash'")]
+    MissingPruneCriteria,
+}
+
+impl error_stack::Context for Error {}
+
+impl SnapshotCommand {
+    #[allow(clippy::print_stdout)]
+    pub async fn execute(self) -> error_stack::Result<(), Error> {
+        let s3_helper = S3Helper::new().await;
+
+        match self.command {
+            SnapshotSubcommand::List { output_prefix } => {
+                let snapshots = list_snapshots(&s3_helper, &output_prefix)
+                    .await
+                    .change_context(Error::ListSnapshots)?;
+                for snapshot in snapshots {
+                    println!("{}", snapshot.path);
+                }
+            }
+            SnapshotSubcommand::Describe { snapshot } => {
+                let info = describe(&s3_helper, &snapshot).await?;
+                println!("Snapshot: {snapshot}");
+                print_info(&info);
+            }
+            SnapshotSubcommand::Verify {
+                snapshot,
+                plan_hash,
+            } => {
+                let local = fetch_snapshot(&s3_helper, &snapshot)
+                    .await
+                    .change_context_lazy(|| Error::ReadSnapshot(snapshot.clone()))?;
+                let mut problems = verify_snapshot(local.path(), None)
+                    .change_context_lazy(|| Error::ReadSnapshot(snapshot.clone()))?;
+                if let Some(expected) = &plan_hash {
+                    let info = describe_snapshot(local.path())
+                        .change_context_lazy(|| Error::ReadSnapshot(snapshot.clone()))?;
+                    if !matches_plan_hash(&info, expected) {
+                        problems.push(format!(
+                            "snapshot plan hash {} does not match expected plan hash {expected}",
+                            format_plan_hash(&info)
+                        ));
+                    }
+                }
+
+                if problems.is_empty() {
+                    println!("Snapshot '{snapshot}' is valid");
+                } else {
+                    let mut report = error_stack::report!(Error::InvalidSnapshot(snapshot));
+                    for problem in problems {
+                        report = report.attach_printable(problem);
+                    }
+                    return Err(report);
+                }
+            }
+            SnapshotSubcommand::Prune {
+                output_prefix,
+                older_than,
+                plan_hash,
+                dry_run,
+            } => {
+                error_stack::ensure!(
+                    older_than.is_some() || plan_hash.is_some(),
+                    Error::MissingPruneCriteria
+                );
+
+                let snapshots = list_snapshots(&s3_helper, &output_prefix)
+                    .await
+                    .change_context(Error::ListSnapshots)?;
+                let now = SystemTime::now();
+                let mut to_prune = Vec::new();
+                let mut skipped = 0;
+                for snapshot in snapshots {
+                    if older_than.map_or(false, |age| snapshot.is_older_than(now, age)) {
+                        to_prune.push(snapshot.path);
+                        continue;
+                    }
+
+                    if let Some(expected) = &plan_hash {
+                        match describe(&s3_helper, &snapshot.path).await {
+                            Ok(info) if !matches_plan_hash(&info, expected) => {
+                                to_prune.push(snapshot.path);
+                            }
+                            Ok(_) => {}
+                            Err(e) => {
+                                println!("Skipping unreadable snapshot {}: {e:?}", snapshot.path);
+                                skipped += 1;
+                            }
+                        }
+                    }
+                }
+
+                for snapshot in to_prune {
+                    if dry_run {
+                        println!("Would delete {snapshot}");
+                    } else {
+                        delete_snapshot(&s3_helper, &snapshot)
+                            .await
+                            .change_context_lazy(|| Error::DeleteSnapshot(snapshot.clone()))?;
+                        println!("Deleted {snapshot}");
+                    }
+                }
+                if skipped > 0 {
+                    println!("Skipped {skipped} unreadable snapshots");
+                }
+            }
+            SnapshotSubcommand::Dump {
+                snapshot,
+                entity,
+                accumulator_type,
+            } => {
+                let local = fetch_snapshot(&s3_helper, &snapshot)
+                    .await
+                    .change_context_lazy(|| Error::ReadSnapshot(snapshot.clone()))?;
+
+                if let Some(entity) = entity {
+                    let state = entity_state(local.path(), &entity, accumulator_type)
+                        .change_context_lazy(|| Error::ReadSnapshot(snapshot.clone()))?;
+                    println!("Entity '{entity}' (key hash {})", state.key_hash);
+                    for operation in state.operations {
+                        println!(
+                            "  operation {} (entity index {})",
+                            operation.operation_index, operation.entity_index
+                        );
+                        for accumulator in operation.accumulators {
+                            let value = match accumulator.value {
+                                AccumulatorValue::Value(value) => value,
+                                AccumulatorValue::Missing => "<missing>".to_owned(),
+                                AccumulatorValue::Invalid(e) => format!("<invalid: {e}>"),
+                                AccumulatorValue::Undecoded { total_bytes } => {
+                                    format!("<{total_bytes} bytes total>")
+                                }
+                            };
+                            println!("    instruction {}: {value}", accumulator.inst_index);
+                        }
+                    }
+                } else {
+                    let store = ComputeStore::try_open_read_only(local.path())
+                        .into_report()
+                        .change_context_lazy(|| Error::ReadSnapshot(snapshot.clone()))?;
+                    for entry in store.raw_entries() {
+                        let (key, value) = entry
+                            .into_report()
+                            .change_context_lazy(|| Error::ReadSnapshot(snapshot.clone()))?;
+                        println!(
+                            "{}: {} ({} bytes)",
+                            hex::encode(&key),
+                            StoreKeyKind::from_bytes(&key),
+                            value.len()
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+async fn describe(
+    s3_helper: &S3Helper,
+    snapshot: &str,
+) -> error_stack::Result<SnapshotInfo, Error> {
+    let local = fetch_snapshot(s3_helper, snapshot)
+        .await
+        .change_context_lazy(|| Error::ReadSnapshot(snapshot.to_owned()))?;
+    describe_snapshot(local.path()).change_context_lazy(|| Error::ReadSnapshot(snapshot.to_owned()))
+}
+
+#[allow(clippy::print_stdout)]
+fn print_info(info: &SnapshotInfo) {
+    println!("  Version: {}", info.snapshot_version);
+    println!("  Plan hash: {}", format_plan_hash(info));
+    let max_event_time = info
+        .max_event_time
+        .as_ref()
+        .and_then(|t| chrono::NaiveDateTime::from_timestamp_opt(t.seconds, t.nanos as u32))
+        .map(|t| t.to_string())
+        .unwrap_or_else(|| "<missing>".to_owned());
+    println!("  Max event time: {max_event_time}");
+    let num_entities = info
+        .num_entities
+        .map(|n| n.to_string())
+        .unwrap_or_else(|| "<missing>".to_owned());
+    println!("  Entities: {num_entities}");
+    println!("  Keys: {} ({} bytes)", info.num_keys, info.value_bytes);
+}
+
+fn format_plan_hash(info: &SnapshotInfo) -> String {
+    info.plan_hash
+        .as_ref()
+        .map(|hash| hash.to_string())
+        .unwrap_or_else(|| "<missing>".to_owned())
+}
+
+fn matches_plan_hash(info: &SnapshotInfo, expected: &str) -> bool {
+    info.plan_hash
+        .as_ref()
+        .map(|hash| hash.to_string().eq_ignore_ascii_case(expected))
+        .unwrap_or(false)
+}
+
+/// Parses an age such as `90s`, `30m`, `12h` or `7d`.
+fn parse_age(age: &str) -> Result<Duration, String> {
+    let unit_index = age
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| format!("missing unit in '{age}'"))?;
+    let (count, unit) = age.split_at(unit_index);
+    let count: u64 = count
+        .parse()
+        .map_err(|e| format!("invalid number in '{age}': {e}"))?;
+    let unit_seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        unit => return Err(format!("unknown unit '{unit}' in '{age}'")),
+    };
+    Ok(Duration::from_secs(count * unit_seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_age("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_age("12h"), Ok(Duration::from_secs(12 * 60 * 60)));
+        assert_eq!(parse_age("7d"), Ok(Duration::from_secs(7 * 24 * 60 * 60)));
+        assert!(parse_age("7").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("7w").is_err());
+    }
+}
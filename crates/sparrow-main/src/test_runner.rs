@@ -0,0 +1,612 @@
+use std::path::{Path, PathBuf};
+
+use error_stack::{IntoReport, ResultExt};
+use itertools::Itertools;
+use serde::Deserialize;
+use sparrow_api::kaskada::v1alpha::compile_request::ExpressionKind;
+use sparrow_api::kaskada::v1alpha::{
+    CompileRequest, FeatureSet, Formula, PerEntityBehavior, TableConfig,
+};
+use sparrow_compiler::InternalCompileOptions;
+use sparrow_runtime::s3::S3Helper;
+use sparrow_runtime::stores::ObjectStoreRegistry;
+use uuid::Uuid;
+
+use crate::local_files;
+
+/// Options for the `test` command.
+#[derive(clap::Args, Debug)]
+#[command(version, rename_all = "kebab-case")]
+pub struct TestCommand {
+    /// Test case files, or directories to search for test cases.
+    ///
+    /// Directories are searched recursively for files ending in
+    /// `.test.yaml`.
+    #[arg(default_value = ".")]
+    pub paths: Vec<PathBuf>,
+
+    /// Only run test cases whose path contains this string.
+    #[arg(long)]
+    pub filter: Option<String>,
+
+    /// Overwrite the expected output of each test case with the actual
+    /// output, rather than comparing them.
+    #[arg(long)]
+    pub update: bool,
+
+    /// The maximum number of differing rows to report for each test case.
+    #[arg(long, default_value = "20")]
+    pub max_diff_rows: usize,
+}
+
+#[derive(derive_more::Display, Debug)]
+pub enum Error {
+    #[display(fmt = "failed to find test cases")]
+    FindingTests,
+    #[display(fmt = "no test cases found")]
+    NoTests,
+    #[display(fmt = "invalid test case")]
+    InvalidTestCase,
+    #[display(fmt = "failed to read '{}'", "_0.display()")]
+    ReadingFile(PathBuf),
+    #[display(fmt = "failed to write '{}'", "_0.display()")]
+    WritingFile(PathBuf),
+    #[display(fmt = "failed to prepare table '{_0}'")]
+    Preparing(String),
+    #[display(fmt = "failed to compile query")]
+    Compilation,
+    #[display(fmt = "errors in query:\n{_0}")]
+    InvalidQuery(String),
+    #[display(fmt = "failed to execute query")]
+    Execution,
+    #[display(fmt = "internal error")]
+    Internal,
+    #[display(fmt = "{_0} test case(s) failed")]
+    Failed(usize),
+}
+
+impl error_stack::Context for Error {}
+
+/// A test case, deserialized from a `.test.yaml` file.
+///
+/// Paths within the test case are relative to the file.
+///
+/// ```yaml
+/// tables:
+///   - name: Purchases
+///     path: purchases.csv
+///     entity_column: customer_id
+/// formulas:
+///   - name: total
+///     formula: sum(Purchases.amount)
+/// query: "{ total, max: max(Purchases.amount) }"
+/// expected: expected.csv
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TestCase {
+    /// The input tables.
+    tables: Vec<TestTable>,
+    /// Formulas which may be referenced by the query.
+    #[serde(default)]
+    formulas: Vec<TestFormula>,
+    /// The query to compute.
+    query: String,
+    /// Whether to produce results for all times or only the final results.
+    #[serde(default)]
+    per_entity_behavior: TestBehavior,
+    /// CSV file containing the expected results.
+    ///
+    /// Only the columns in this file are compared, so columns such as
+    /// `_subsort` and `_key_hash` may be omitted.
+    expected: PathBuf,
+    /// If true, rows are expected in the order given. Otherwise, the rows
+    /// are sorted before comparison.
+    #[serde(default)]
+    ordered: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TestTable {
+    name: String,
+    /// Path to a CSV or Parquet file containing the rows of the table.
+    path: PathBuf,
+    #[serde(default = "default_time_column")]
+    time_column: String,
+    #[serde(default = "default_entity_column")]
+    entity_column: String,
+    subsort_column: Option<String>,
+    /// The grouping of the table. Defaults to the name of the entity column.
+    grouping: Option<String>,
+}
+
+fn default_time_column() -> String {
+    "time".to_owned()
+}
+
+fn default_entity_column() -> String {
+    "key".to_owned()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TestFormula {
+    name: String,
+    formula: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TestBehavior {
+    #[default]
+    All,
+    Final,
+}
+
+/// The outcome of running a single test case.
+enum Outcome {
+    Passed,
+    Updated,
+    /// The output differed from the expected output, with the given diff.
+    Failed(String),
+}
+
+impl TestCommand {
+    #[allow(clippy::print_stdout)]
+    pub async fn execute(self) -> error_stack::Result<(), Error> {
+        let mut test_cases = Vec::new();
+        for path in &self.paths {
+            find_test_cases(path, &mut test_cases)
+                .into_report()
+                .change_context(Error::FindingTests)
+                .attach_printable_lazy(|| path.display().to_string())?;
+        }
+        if let Some(filter) = &self.filter {
+            test_cases.retain(|path| path.to_string_lossy().contains(filter.as_str()));
+        }
+        test_cases.sort();
+        error_stack::ensure!(!test_cases.is_empty(), Error::NoTests);
+
+        println!("running {} test case(s)", test_cases.len());
+        let s3_helper = S3Helper::new().await;
+        let object_store_registry = ObjectStoreRegistry::new();
+
+        let mut failures = Vec::new();
+        for path in &test_cases {
+            let outcome = self
+                .run_test_case(path, &object_store_registry, &s3_helper)
+                .await;
+            match outcome {
+                Ok(Outcome::Passed) => println!("test {} ... ok", path.display()),
+                Ok(Outcome::Updated) => println!("test {} ... updated", path.display()),
+                Ok(Outcome::Failed(diff)) => {
+                    println!("test {} ... FAILED", path.display());
+                    failures.push((path, diff));
+                }
+                Err(err) => {
+                    println!("test {} ... ERROR", path.display());
+                    failures.push((path, format!("{err:?}")));
+                }
+            }
+        }
+
+        for (path, failure) in &failures {
+            println!("\n---- {} ----\n{failure}", path.display());
+        }
+
+        println!(
+            "\ntest result: {}. {} passed; {} failed",
+            if failures.is_empty() { "ok" } else { "FAILED" },
+            test_cases.len() - failures.len(),
+            failures.len()
+        );
+        error_stack::ensure!(failures.is_empty(), Error::Failed(failures.len()));
+        Ok(())
+    }
+
+    async fn run_test_case(
+        &self,
+        path: &Path,
+        object_store_registry: &ObjectStoreRegistry,
+        s3_helper: &S3Helper,
+    ) -> error_stack::Result<Outcome, Error> {
+        let test_case: TestCase = serde_yaml::from_str(&read_file(path)?)
+            .into_report()
+            .change_context(Error::InvalidTestCase)?;
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+
+        let data_dir = tempfile::Builder::new()
+            .prefix("sparrow-test")
+            .tempdir()
+            .into_report()
+            .change_context(Error::Internal)?;
+
+        let mut tables = Vec::with_capacity(test_case.tables.len());
+        for table in &test_case.tables {
+            let config = TableConfig::new_with_table_source(
+                &table.name,
+                &Uuid::new_v4(),
+                &table.time_column,
+                table.subsort_column.as_deref(),
+                &table.entity_column,
+                table.grouping.as_ref().unwrap_or(&table.entity_column),
+            );
+            let output_dir = data_dir.path().join(format!("table-{}", table.name));
+            let (table, _) = local_files::prepare_local_table(
+                object_store_registry,
+                &base_dir.join(&table.path),
+                config,
+                &output_dir,
+            )
+            .await
+            .change_context_lazy(|| Error::Preparing(table.name.clone()))?;
+            tables.push(table);
+        }
+
+        let per_entity_behavior = match test_case.per_entity_behavior {
+            TestBehavior::All => PerEntityBehavior::All,
+            TestBehavior::Final => PerEntityBehavior::Final,
+        };
+        let formulas = test_case
+            .formulas
+            .into_iter()
+            .map(|formula| Formula {
+                source_location: format!("Formula: {}", formula.name),
+                name: formula.name,
+                formula: formula.formula,
+            })
+            .collect();
+        let response = sparrow_compiler::compile_proto(
+            CompileRequest {
+                tables: tables.clone(),
+                feature_set: Some(FeatureSet {
+                    formulas,
+                    query: test_case.query,
+                    outputs: vec![],
+                }),
+                slice_request: None,
+                expression_kind: ExpressionKind::Complete as i32,
+                experimental: false,
+                per_entity_behavior: per_entity_behavior as i32,
+                previous_plan: None,
+                explain: false,
+                lint_severities: vec![],
+            },
+            InternalCompileOptions::default(),
+        )
+        .await
+        .change_context(Error::Compilation)?;
+
+        let diagnostics = response.fenl_diagnostics.unwrap_or_default();
+        error_stack::ensure!(
+            diagnostics.num_errors == 0,
+            Error::InvalidQuery(diagnostics.to_string())
+        );
+
+        let output_dir = data_dir.path().join("output");
+        std::fs::create_dir_all(&output_dir)
+            .into_report()
+            .change_context(Error::Internal)?;
+        let actual = local_files::execute_to_csv(
+            response.plan,
+            tables,
+            None,
+            s3_helper.clone(),
+            &output_dir,
+        )
+        .await
+        .change_context(Error::Execution)?;
+        let actual = CsvRows::parse(&actual);
+
+        let expected_path = base_dir.join(&test_case.expected);
+        if self.update {
+            // Keep the columns of the existing expected output, if any.
+            let columns = if expected_path.exists() {
+                CsvRows::parse(&read_file(&expected_path)?).header
+            } else {
+                actual
+                    .header
+                    .iter()
+                    .filter(|column| !matches!(column.as_str(), "_subsort" | "_key_hash"))
+                    .cloned()
+                    .collect()
+            };
+            let actual = actual.project(&columns).map_err(|message| {
+                error_stack::report!(Error::InvalidTestCase).attach_printable(message)
+            })?;
+            std::fs::write(&expected_path, actual.to_csv())
+                .into_report()
+                .change_context_lazy(|| Error::WritingFile(expected_path.clone()))?;
+            return Ok(Outcome::Updated);
+        }
+
+        let mut expected = CsvRows::parse(&read_file(&expected_path)?);
+        let mut actual = match actual.project(&expected.header) {
+            Ok(actual) => actual,
+            Err(message) => return Ok(Outcome::Failed(message)),
+        };
+        if !test_case.ordered {
+            expected.rows.sort();
+            actual.rows.sort();
+        }
+
+        match expected.diff(&actual, self.max_diff_rows) {
+            None => Ok(Outcome::Passed),
+            Some(diff) => Ok(Outcome::Failed(diff)),
+        }
+    }
+}
+
+/// Add the test case at `path` (or found within `path`) to `test_cases`.
+fn find_test_cases(path: &Path, test_cases: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if path.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            let entry_path = entry?.path();
+            let is_test_case = entry_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| name.ends_with(".test.yaml"));
+            if entry_path.is_dir() || is_test_case {
+                find_test_cases(&entry_path, test_cases)?;
+            }
+        }
+    } else {
+        test_cases.push(path.to_owned());
+    }
+    Ok(())
+}
+
+fn read_file(path: &Path) -> error_stack::Result<String, Error> {
+    std::fs::read_to_string(path)
+        .into_report()
+        .change_context_lazy(|| Error::ReadingFile(path.to_owned()))
+}
+
+/// The header and rows of a CSV file, as strings.
+///
+/// Values are compared as they are written, so the expected output should
+/// use the same formatting as the CSV output of Sparrow.
+#[derive(Debug, PartialEq)]
+struct CsvRows {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl CsvRows {
+    /// Parse the CSV in `input`.
+    ///
+    /// If the input is the concatenation of multiple files with the same
+    /// header, the repeated headers are skipped.
+    fn parse(input: &str) -> Self {
+        let mut records = parse_csv_records(input).into_iter();
+        let header = records.next().unwrap_or_default();
+        let rows = records.filter(|record| record != &header).collect();
+        Self { header, rows }
+    }
+
+    /// Return the rows with only the given `columns`, in that order.
+    fn project(&self, columns: &[String]) -> Result<Self, String> {
+        let indices: Vec<_> = columns
+            .iter()
+            .map(|column| {
+                self.header
+                    .iter()
+                    .position(|name| name == column)
+                    .ok_or_else(|| {
+                        format!(
+                            "expected column '{column}' not in output columns [{}]",
+                            self.header.join(", ")
+                        )
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                indices
+                    .iter()
+                    .map(|index| row.get(*index).cloned().unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+        Ok(Self {
+            header: columns.to_vec(),
+            rows,
+        })
+    }
+
+    /// Return a row-level diff from `self` (expected) to `actual`, or `None`
+    /// if they are the same.
+    fn diff(&self, actual: &Self, max_diff_rows: usize) -> Option<String> {
+        if self == actual {
+            return None;
+        }
+
+        let expected_lines: Vec<_> = self.rows.iter().map(|row| format_row(row)).collect();
+        let actual_lines: Vec<_> = actual.rows.iter().map(|row| format_row(row)).collect();
+        let diff =
+            similar::capture_diff_slices(similar::Algorithm::Myers, &expected_lines, &actual_lines);
+
+        let mut output = format!(
+            "expected {} row(s), actual {} row(s)\n  {}\n",
+            self.rows.len(),
+            actual.rows.len(),
+            format_row(&self.header)
+        );
+        let mut reported = 0;
+        for op in diff {
+            for change in op.iter_changes(&expected_lines, &actual_lines) {
+                let sign = match change.tag() {
+                    similar::ChangeTag::Equal => continue,
+                    similar::ChangeTag::Delete => "-",
+                    similar::ChangeTag::Insert => "+",
+                };
+                if reported == max_diff_rows {
+                    output.push_str("  ... (further differences omitted)\n");
+                    return Some(output);
+                }
+                output.push_str(&format!("{sign} {}\n", change.value()));
+                reported += 1;
+            }
+        }
+        Some(output)
+    }
+
+    fn to_csv(&self) -> String {
+        let mut output = format_row(&self.header);
+        output.push('\n');
+        for row in &self.rows {
+            output.push_str(&format_row(row));
+            output.push('\n');
+        }
+        output
+    }
+}
+
+fn format_row(row: &[String]) -> String {
+    row.iter()
+        .map(|value| {
+            if value.contains([',', '"', '\n']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.clone()
+            }
+        })
+        .join(",")
+}
+
+/// Parse CSV records, handling quoted values.
+fn parse_csv_records(input: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut value = String::new();
+    let mut in_quotes = false;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                value.push('"');
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => record.push(std::mem::take(&mut value)),
+            '\r' if !in_quotes => (),
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut value));
+                records.push(std::mem::take(&mut record));
+            }
+            c => value.push(c),
+        }
+    }
+    if !value.is_empty() || !record.is_empty() {
+        record.push(value);
+        records.push(record);
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT_CSV: &str = "\
+time,key,amount
+2023-01-01T00:00:00Z,a,5
+2023-01-02T00:00:00Z,b,7
+2023-01-03T00:00:00Z,a,11
+";
+
+    const TEST_CASE: &str = "\
+tables:
+  - name: Purchases
+    path: purchases.csv
+query: \"{ total: sum(Purchases.amount) }\"
+expected: expected.csv
+";
+
+    #[test]
+    fn test_parse_csv() {
+        let rows = CsvRows::parse("a,b\n1,\"x, \"\"y\"\"\"\n2,\na,b\n3,z\n");
+        assert_eq!(rows.header, vec!["a", "b"]);
+        assert_eq!(
+            rows.rows,
+            vec![vec!["1", "x, \"y\""], vec!["2", ""], vec!["3", "z"]]
+        );
+        assert_eq!(
+            CsvRows::parse(&rows.to_csv()),
+            rows,
+            "formatting should round-trip"
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let expected = CsvRows::parse("a,b\n1,x\n2,y\n3,z\n");
+        let actual = CsvRows::parse("b,a,c\nx,1,p\ny,5,q\nz,3,r\n");
+        let actual = actual.project(&expected.header).unwrap();
+        insta::assert_snapshot!(expected.diff(&actual, 10).unwrap(), @r###"
+        expected 3 row(s), actual 3 row(s)
+          a,b
+        - 2,y
+        + 5,y
+        "###);
+
+        assert_eq!(expected.diff(&expected, 10), None);
+        assert!(expected.project(&["c".to_owned()]).is_err());
+    }
+
+    async fn run(dir: &Path, expected_csv: &str, update: bool) -> Outcome {
+        std::fs::write(dir.join("expected.csv"), expected_csv).unwrap();
+        let command = TestCommand {
+            paths: vec![dir.to_owned()],
+            filter: None,
+            update,
+            max_diff_rows: 20,
+        };
+        command
+            .run_test_case(
+                &dir.join("total.test.yaml"),
+                &ObjectStoreRegistry::new(),
+                &S3Helper::new().await,
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_run_test_case() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("purchases.csv"), INPUT_CSV).unwrap();
+        std::fs::write(dir.path().join("total.test.yaml"), TEST_CASE).unwrap();
+
+        let expected = "_key,total\na,5\na,16\nb,7\n";
+        assert!(matches!(
+            run(dir.path(), expected, false).await,
+            Outcome::Passed
+        ));
+
+        let Outcome::Failed(diff) = run(dir.path(), "_key,total\na,5\na,15\nb,7\n", false).await
+        else {
+            panic!("expected failure")
+        };
+        assert!(diff.contains("- a,15\n+ a,16\n"), "{diff}");
+
+        assert!(matches!(
+            run(dir.path(), "_key,total\n", true).await,
+            Outcome::Updated
+        ));
+        let updated = std::fs::read_to_string(dir.path().join("expected.csv")).unwrap();
+        let mut updated = CsvRows::parse(&updated).rows;
+        updated.sort();
+        let mut expected = CsvRows::parse(expected).rows;
+        expected.sort();
+        assert_eq!(updated, expected);
+
+        let mut command_paths = Vec::new();
+        find_test_cases(dir.path(), &mut command_paths).unwrap();
+        assert_eq!(command_paths, vec![dir.path().join("total.test.yaml")]);
+    }
+}
@@ -10,6 +10,7 @@ use opentelemetry_otlp::WithExportConfig;
 use request_id_format::RequestIdFormat;
 use tonic::codegen::http;
 use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::EnvFilter;
@@ -56,6 +57,13 @@ pub struct TracingOptions {
     /// Set to `1` to disable color output.
     #[arg(long, default_value = "0", env = "NO_COLOR")]
     log_no_color: i8,
+
+    /// Whether logs should be written to stderr rather than stdout.
+    ///
+    /// This is set by commands which use stdout for other purposes, such as
+    /// the language server protocol.
+    #[arg(skip)]
+    pub log_to_stderr: bool,
 }
 
 impl TracingOptions {
@@ -108,11 +116,18 @@ pub fn setup_tracing(config: &TracingOptions) {
         .with(EnvFilter::try_new(&config.log_filters).unwrap())
         .with(tracing_error::ErrorLayer::default());
 
+    let writer = if config.log_to_stderr {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
+
     // Add logging and install the handler. We do this in one block
     // since the type of logs (JSON or not) affects the type of the
     // format layer and registry.
     if config.log_json {
         let fmt_layer = tracing_subscriber::fmt::layer()
+            .with_writer(writer)
             .with_ansi(config.log_no_color != 1)
             .event_format(RequestIdFormat);
         registry
@@ -122,6 +137,7 @@ pub fn setup_tracing(config: &TracingOptions) {
             .unwrap();
     } else {
         let fmt_layer = tracing_subscriber::fmt::layer()
+            .with_writer(writer)
             .with_ansi(config.log_no_color != 1)
             .with_target(false);
         registry
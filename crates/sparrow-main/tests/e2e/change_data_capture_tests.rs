@@ -0,0 +1,80 @@
+//! e2e tests for change-data-capture tables.
+
+use indoc::indoc;
+use sparrow_api::kaskada::v1alpha::TableConfig;
+use uuid::Uuid;
+
+use crate::{DataFixture, QueryFixture};
+
+/// Fixture for testing change-data-capture tables.
+///
+/// Includes a change-data-capture table `Account` containing the inserts,
+/// updates and deletes of each account, and a `Transfer` table of transfers
+/// between accounts. Both are grouped by account ID.
+async fn change_data_capture_fixture() -> DataFixture {
+    let accounts = indoc! {"
+        id,time,subsort,op,name
+        0,1996-12-18T16:39:57-08:00,0,insert,Alice
+        1,1996-12-18T16:39:58-08:00,1,insert,Bob
+        0,1997-12-18T16:39:57-08:00,2,update,Alicia
+        1,1998-12-18T16:39:57-08:00,3,delete,Bob
+        0,2000-12-18T16:39:57-08:00,4,update,Alice
+    "};
+
+    let transfers = indoc! {"
+        from,to,time,subsort,amount
+        2,0,1996-12-19T16:39:57-08:00,0,50
+        2,1,1997-12-19T16:39:57-08:00,1,25
+        2,0,1998-12-19T16:39:57-08:00,2,10
+        2,1,1999-12-19T16:39:57-08:00,3,5
+    "};
+
+    DataFixture::new()
+        .with_table_from_csv(
+            TableConfig::new_with_table_source(
+                "Account",
+                &Uuid::new_v4(),
+                "time",
+                Some("subsort"),
+                "id",
+                "account",
+            )
+            .with_change_data_capture(Some("op")),
+            accounts,
+        )
+        .await
+        .unwrap()
+        .with_table_from_csv(
+            TableConfig::new_with_table_source(
+                "Transfer",
+                &Uuid::new_v4(),
+                "time",
+                Some("subsort"),
+                "from",
+                "account",
+            ),
+            transfers,
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_lookup_change_data_capture_table() {
+    insta::assert_snapshot!(QueryFixture::new("{ amount: Transfer.amount, to_name: lookup(Transfer.to, Account.name) }").run_to_csv(&change_data_capture_fixture().await).await.unwrap(), @r###"
+    _time,_subsort,_key_hash,_key,amount,to_name
+    1996-12-20T00:39:57.000000000,9223372036854775808,1575016611515860288,2,50,Alice
+    1997-12-20T00:39:57.000000000,9223372036854775809,1575016611515860288,2,25,Bob
+    1998-12-20T00:39:57.000000000,9223372036854775810,1575016611515860288,2,10,Alicia
+    1999-12-20T00:39:57.000000000,9223372036854775811,1575016611515860288,2,5,
+    "###);
+}
+
+#[tokio::test]
+async fn test_change_data_capture_table_final_results() {
+    insta::assert_snapshot!(QueryFixture::new("{ name: Account.name }").with_final_results().run_to_csv(&change_data_capture_fixture().await).await.unwrap(), @r###"
+    _time,_subsort,_key_hash,_key,name
+    2000-12-19T00:39:57.000000001,18446744073709551615,2359047937476779835,1,
+    2000-12-19T00:39:57.000000001,18446744073709551615,14253486467890685049,0,Alice
+    "###);
+}
@@ -125,3 +125,100 @@ async fn test_lookup_with_key_entity_keys() {
     1996-12-20T00:40:02.000000000,9223372036854775808,3650215962958587783,A,35.2
     "###);
 }
+
+/// Create a table keyed by the composite of `merchant` and `country`.
+///
+/// ```csv
+/// time,subsort,merchant,country,amount
+/// 1996-12-19T16:39:57-08:00,0,acme,us,5
+/// 1996-12-19T16:39:58-08:00,0,acme,uk,24
+/// 1996-12-19T16:39:59-08:00,0,globex,us,17
+/// 1996-12-19T16:40:00-08:00,0,acme,us,9
+/// 1996-12-19T16:40:01-08:00,0,globex,us,12
+/// ```
+async fn composite_key_fixture() -> DataFixture {
+    DataFixture::new()
+        .with_table_from_csv(
+            TableConfig::new_with_table_source(
+                "Purchases",
+                &Uuid::new_v4(),
+                "time",
+                Some("subsort"),
+                "merchant",
+                "merchant_country",
+            )
+            .with_additional_group_columns(&["country"]),
+            indoc! {"
+    time,subsort,merchant,country,amount
+    1996-12-19T16:39:57-08:00,0,acme,us,5
+    1996-12-19T16:39:58-08:00,0,acme,uk,24
+    1996-12-19T16:39:59-08:00,0,globex,us,17
+    1996-12-19T16:40:00-08:00,0,acme,us,9
+    1996-12-19T16:40:01-08:00,0,globex,us,12
+    "},
+        )
+        .await
+        .unwrap()
+}
+
+/// Return the `_key_hash` column of the CSV output.
+fn key_hashes(csv: &str) -> Vec<&str> {
+    csv.lines()
+        .skip(1)
+        .map(|line| line.split(',').nth(2).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_composite_entity_keys() {
+    let csv = QueryFixture::new("{ total: sum(Purchases.amount) }")
+        .run_to_csv(&composite_key_fixture().await)
+        .await
+        .unwrap();
+
+    // The key hash depends on all components of the key.
+    let hashes = key_hashes(&csv);
+    assert_eq!(hashes[0], hashes[3]);
+    assert_eq!(hashes[2], hashes[4]);
+    assert_ne!(hashes[0], hashes[1]);
+    assert_ne!(hashes[0], hashes[2]);
+
+    // Each component of the key is output as a separate column.
+    let without_hashes = csv
+        .lines()
+        .map(|line| {
+            let mut fields: Vec<_> = line.split(',').collect();
+            fields.remove(2);
+            fields.join(",")
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    insta::assert_snapshot!(without_hashes, @r###"
+    _time,_subsort,_key_merchant,_key_country,total
+    1996-12-20T00:39:57.000000000,9223372036854775808,acme,us,5
+    1996-12-20T00:39:58.000000000,9223372036854775808,acme,uk,24
+    1996-12-20T00:39:59.000000000,9223372036854775808,globex,us,17
+    1996-12-20T00:40:00.000000000,9223372036854775808,acme,us,14
+    1996-12-20T00:40:01.000000000,9223372036854775808,globex,us,29
+    "###);
+}
+
+#[tokio::test]
+async fn test_with_key_composite_matches_table_key() {
+    let data = composite_key_fixture().await;
+    let table = QueryFixture::new("{ amount: Purchases.amount }")
+        .run_to_csv(&data)
+        .await
+        .unwrap();
+    let rekeyed = QueryFixture::new(
+        "{ amount: Purchases.amount } | with_key({ merchant: Purchases.merchant, country: \
+         Purchases.country })",
+    )
+    .run_to_csv(&data)
+    .await
+    .unwrap();
+
+    // Re-keying by a record of the key columns produces the same entities.
+    assert_eq!(key_hashes(&table), key_hashes(&rekeyed));
+    assert_eq!(table, rekeyed);
+}
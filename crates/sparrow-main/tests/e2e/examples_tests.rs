@@ -0,0 +1,60 @@
+//! e2e tests for producing results at the examples in a table.
+
+use indoc::indoc;
+use sparrow_api::kaskada::v1alpha::TableConfig;
+use uuid::Uuid;
+
+use crate::fixtures::i64_data_fixture;
+use crate::{DataFixture, QueryFixture};
+
+/// Create the `Numbers` data with a table of `Labels` to use as examples.
+async fn examples_data_fixture() -> DataFixture {
+    i64_data_fixture()
+        .await
+        .with_table_from_csv(
+            TableConfig::new_with_table_source(
+                "Labels",
+                &Uuid::new_v4(),
+                "time",
+                Some("subsort"),
+                "key",
+                "Numbers",
+            ),
+            indoc! {"
+    time,subsort,key,label
+    1996-12-19T16:39:00-08:00,0,C,false
+    1996-12-19T16:39:58-08:00,0,A,true
+    1996-12-19T16:39:58-08:00,1,A,false
+    1996-12-19T16:39:59-08:00,0,A,false
+    1996-12-19T16:40:30-08:00,0,B,true
+    "},
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_examples() {
+    // Inputs at the time of an example are included in the result, and
+    // entities without inputs produce null results. Each example produces a
+    // row with its label, including examples at the same time for the same
+    // entity.
+    insta::assert_snapshot!(QueryFixture::new("{ sum_m: sum(Numbers.m), last_n: last(Numbers.n) }").with_examples("Labels").run_to_csv(&examples_data_fixture().await).await.unwrap(), @r###"
+    _time,_subsort,_key_hash,_key,sum_m,last_n,label
+    1996-12-20T00:39:00.000000000,18446744073709551615,9192031977313001967,C,,,false
+    1996-12-20T00:39:58.000000000,18446744073709551615,3650215962958587783,A,5,10,true
+    1996-12-20T00:39:58.000000000,18446744073709551615,3650215962958587783,A,5,10,false
+    1996-12-20T00:39:59.000000000,18446744073709551615,3650215962958587783,A,22,6,false
+    1996-12-20T00:40:30.000000000,18446744073709551615,11753611437813598533,B,24,3,true
+    "###);
+}
+
+#[tokio::test]
+async fn test_examples_partitioned() {
+    let query = QueryFixture::new("{ sum_m: sum(Numbers.m), last_n: last(Numbers.n) }")
+        .with_examples("Labels");
+    let data = examples_data_fixture().await;
+    let expected = query.clone().run_to_csv(&data).await.unwrap();
+    let actual = query.with_partitions(2).run_to_csv(&data).await.unwrap();
+    assert_eq!(actual, expected);
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::path::PathBuf;
 
@@ -10,6 +11,7 @@ use parquet::arrow::ArrowWriter;
 use parquet::file::properties::WriterProperties;
 use sparrow_api::kaskada::v1alpha::compile_request::ExpressionKind;
 use sparrow_api::kaskada::v1alpha::execute_request::Limits;
+use sparrow_api::kaskada::v1alpha::feature_set::Output;
 use sparrow_api::kaskada::v1alpha::ComputeSnapshot;
 use sparrow_api::kaskada::v1alpha::ComputeSnapshotConfig;
 use sparrow_api::kaskada::v1alpha::{destination, Destination};
@@ -29,12 +31,16 @@ pub(crate) struct QueryFixture {
     compile_request: CompileRequest,
     execute_request: ExecuteRequest,
     internal_compile_options: InternalCompileOptions,
+    /// The name of the table in the data fixture to use as the examples.
+    examples_table: Option<String>,
 }
 
 #[derive(Debug)]
 pub(crate) struct RunResult<T> {
     pub inner: T,
     pub snapshots: Vec<ComputeSnapshot>,
+    /// The results of each named output.
+    pub outputs: BTreeMap<String, T>,
 }
 
 impl QueryFixture {
@@ -43,6 +49,7 @@ impl QueryFixture {
             feature_set: Some(FeatureSet {
                 formulas: vec![],
                 query: query.to_owned(),
+                outputs: vec![],
             }),
             expression_kind: ExpressionKind::Complete as i32,
             per_entity_behavior: PerEntityBehavior::All as i32,
@@ -58,6 +65,7 @@ impl QueryFixture {
             compile_request,
             execute_request,
             internal_compile_options: InternalCompileOptions::default(),
+            examples_table: None,
         }
     }
 
@@ -98,6 +106,43 @@ impl QueryFixture {
         self
     }
 
+    pub fn with_start_time(mut self, start_time: NaiveDateTime) -> Self {
+        self.execute_request.start_time = Some(start_time.into());
+        self
+    }
+
+    /// Produce results for the examples in the given table of the data.
+    ///
+    /// The table is provided as the examples, rather than as a table.
+    pub fn with_examples(mut self, table_name: &str) -> Self {
+        self.compile_request.per_entity_behavior = PerEntityBehavior::Examples as i32;
+        self.examples_table = Some(table_name.to_owned());
+        self
+    }
+
+    /// Add a named output producing the results of `query`.
+    ///
+    /// If `per_entity_behavior` is unspecified, the behavior of the query is
+    /// used.
+    pub fn with_output(
+        mut self,
+        name: &str,
+        query: &str,
+        per_entity_behavior: PerEntityBehavior,
+    ) -> Self {
+        self.compile_request
+            .feature_set
+            .as_mut()
+            .unwrap()
+            .outputs
+            .push(Output {
+                name: name.to_owned(),
+                query: query.to_owned(),
+                per_entity_behavior: per_entity_behavior as i32,
+            });
+        self
+    }
+
     pub fn with_formula(mut self, name: &str, formula: &str) -> Self {
         self.compile_request
             .feature_set
@@ -133,6 +178,20 @@ impl QueryFixture {
         self
     }
 
+    pub fn with_partitions(mut self, partitions: u32) -> Self {
+        self.execute_request.limits.as_mut().unwrap().partitions = partitions;
+        self
+    }
+
+    pub fn with_max_memory_bytes(mut self, max_memory_bytes: u64) -> Self {
+        self.execute_request
+            .limits
+            .as_mut()
+            .unwrap()
+            .max_memory_bytes = max_memory_bytes;
+        self
+    }
+
     /// Modify the compile options to disable simplification.
     pub fn without_simplification(mut self) -> Self {
         self.internal_compile_options.simplifier_iteration_limit = 0;
@@ -165,24 +224,22 @@ impl QueryFixture {
     }
 
     /// Run a query and return the results as a CSV string along with a snapshot
-    /// path.
+    /// path and the results of each named output.
     pub async fn run_snapshot_to_csv(
         &self,
         data: &DataFixture,
     ) -> Result<RunResult<String>, crate::EndToEndError> {
         let output_dir = tempfile::TempDir::new().unwrap();
         let result = self.run(data, FileType::Csv, output_dir.path()).await?;
-        let output_file = result
-            .inner
-            .into_iter()
-            .exactly_one()
-            .expect("multiple output file not yet supported");
-        let output_file = output_file.to_string_lossy().to_string();
-        let output_file = output_file.strip_prefix("file://").expect("file:// prefix");
 
         Ok(RunResult {
-            inner: std::fs::read_to_string(output_file).unwrap(),
+            inner: read_csv_output(result.inner),
             snapshots: result.snapshots,
+            outputs: result
+                .outputs
+                .into_iter()
+                .map(|(name, output_files)| (name, read_csv_output(output_files)))
+                .collect(),
         })
     }
 
@@ -248,8 +305,13 @@ impl QueryFixture {
         output_format: FileType,
         output_dir: &std::path::Path,
     ) -> Result<RunResult<Vec<PathBuf>>, crate::EndToEndError> {
+        let (examples, tables): (Vec<_>, Vec<_>) = data.tables().into_iter().partition(|table| {
+            let name = table.config.as_ref().map(|config| config.name.as_str());
+            name.is_some() && name == self.examples_table.as_deref()
+        });
+
         let request = CompileRequest {
-            tables: data.tables(),
+            tables: tables.clone(),
             ..self.compile_request.clone()
         };
 
@@ -277,11 +339,34 @@ impl QueryFixture {
             destination: Some(destination::Destination::ObjectStore(destination)),
         };
 
+        // Each named output is written to a separate directory.
+        let output_destinations = self
+            .compile_request
+            .feature_set
+            .iter()
+            .flat_map(|feature_set| feature_set.outputs.iter())
+            .map(|output| {
+                let destination = ObjectStoreDestination {
+                    output_prefix_uri: format!(
+                        "file:///{}",
+                        output_dir.join(&output.name).display()
+                    ),
+                    file_type: output_format.into(),
+                    output_paths: None,
+                };
+                let destination = Destination {
+                    destination: Some(destination::Destination::ObjectStore(destination)),
+                };
+                (output.name.clone(), destination)
+            })
+            .collect();
+
         let request = ExecuteRequest {
             plan: Some(plan),
             destination: Some(output_to),
-            tables: data.tables(),
-
+            tables,
+            examples: examples.into_iter().next(),
+            output_destinations,
             ..self.execute_request.clone()
         };
 
@@ -297,24 +382,49 @@ impl QueryFixture {
 
         let mut output_files = Vec::new();
         let mut snapshots = Vec::new();
+        let mut outputs: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
         while let Some(next) = stream.try_next().await? {
             if let Some(output_paths) = next.output_paths() {
                 output_files.extend(output_paths.into_iter().map(PathBuf::from));
             }
+            for name in next.output_destinations.keys() {
+                if let Some(output_paths) = next.named_output_paths(name) {
+                    outputs
+                        .entry(name.clone())
+                        .or_default()
+                        .extend(output_paths.into_iter().map(PathBuf::from));
+                }
+            }
 
             snapshots.extend(next.compute_snapshots);
         }
 
         output_files.sort();
         output_files.dedup();
+        for output_files in outputs.values_mut() {
+            output_files.sort();
+            output_files.dedup();
+        }
 
         Ok(RunResult {
             inner: output_files,
             snapshots,
+            outputs,
         })
     }
 }
 
+/// Read the contents of the single CSV file in `output_files`.
+fn read_csv_output(output_files: Vec<PathBuf>) -> String {
+    let output_file = output_files
+        .into_iter()
+        .exactly_one()
+        .expect("multiple output file not yet supported");
+    let output_file = output_file.to_string_lossy().to_string();
+    let output_file = output_file.strip_prefix("file://").expect("file:// prefix");
+    std::fs::read_to_string(output_file).unwrap()
+}
+
 /// Return the hash of a parquet file as an uppercase hex string.
 fn hash_parquet_file(file: &std::path::Path) -> String {
     // Read the file, concatenate all the batches, and write it back.
@@ -10,7 +10,7 @@ use crate::{DataFixture, QueryFixture};
 ///
 /// Includes two tables `Sent` and `Received` both grouped by account ID.
 /// Also includes a `CodeName` table used for lookups on a different type.
-async fn lookup_account_data_fixture() -> DataFixture {
+pub(crate) async fn lookup_account_data_fixture() -> DataFixture {
     let transactions = indoc! {"
         from,to,time,subsort,amount,description,order_time,code
         0,2,1996-12-19T16:39:57-08:00,0,50,food,2005-12-19T16:39:57-08:00,5
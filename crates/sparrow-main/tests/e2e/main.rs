@@ -18,11 +18,13 @@ mod prepare_tests;
 mod aggregation_tests;
 mod basic_error_tests;
 mod cast_tests;
+mod change_data_capture_tests;
 mod coalesce_tests;
 mod comparison_tests;
 mod decoration_tests;
 mod entity_key_output_tests;
 mod equality_tests;
+mod examples_tests;
 mod formula_tests;
 mod general_tests;
 mod json_tests;
@@ -30,6 +32,8 @@ mod logical_tests;
 mod lookup_tests;
 mod math_tests;
 mod multiple_tables;
+mod named_output_tests;
+mod partition_tests;
 mod record_tests;
 mod resumeable_tests;
 mod shift_tests;
@@ -0,0 +1,70 @@
+//! e2e tests for producing multiple named outputs from a single query.
+
+use sparrow_api::kaskada::v1alpha::PerEntityBehavior;
+
+use crate::with_key_tests::with_key_data_fixture;
+use crate::QueryFixture;
+
+#[tokio::test]
+async fn test_named_output_final_results() {
+    let result = QueryFixture::new("{ sum_n: sum(Table.n) }")
+        .with_output("final", "{ sum_n: sum(Table.n) }", PerEntityBehavior::Final)
+        .run_snapshot_to_csv(&with_key_data_fixture().await)
+        .await
+        .unwrap();
+
+    insta::assert_snapshot!(result.inner, @r###"
+    _time,_subsort,_key_hash,_key,sum_n
+    1996-12-20T00:39:57.000000000,9223372036854775808,3650215962958587783,A,0
+    1996-12-20T00:39:58.000000000,9223372036854775808,11753611437813598533,B,1
+    1996-12-20T00:39:59.000000000,9223372036854775808,3650215962958587783,A,0
+    1996-12-20T00:40:00.000000000,9223372036854775808,3650215962958587783,A,2
+    1996-12-20T00:40:01.000000000,9223372036854775808,3650215962958587783,A,5
+    1996-12-20T00:40:02.000000000,9223372036854775808,3650215962958587783,A,9
+    "###);
+    insta::assert_snapshot!(result.outputs["final"], @r###"
+    _time,_subsort,_key_hash,_key,sum_n
+    1996-12-20T00:40:02.000000001,18446744073709551615,3650215962958587783,A,9
+    1996-12-20T00:40:02.000000001,18446744073709551615,11753611437813598533,B,1
+    "###);
+}
+
+#[tokio::test]
+async fn test_named_output_with_key() {
+    // The named output is keyed by the foreign key, while the query is keyed
+    // by the key of the table.
+    let result = QueryFixture::new("{ sum_n: sum(Table.n) }")
+        .with_output(
+            "by_foreign_key",
+            "with_key(Table.foreign_key_i64, { count: count(Table) })",
+            PerEntityBehavior::Final,
+        )
+        .run_snapshot_to_csv(&with_key_data_fixture().await)
+        .await
+        .unwrap();
+
+    insta::assert_snapshot!(result.outputs["by_foreign_key"], @r###"
+    _time,_subsort,_key_hash,_key,count
+    1996-12-20T00:40:02.000000001,18446744073709551615,1575016611515860288,2,3
+    1996-12-20T00:40:02.000000001,18446744073709551615,2359047937476779835,1,4
+    1996-12-20T00:40:02.000000001,18446744073709551615,14253486467890685049,0,5
+    "###);
+}
+
+#[tokio::test]
+async fn test_named_outputs_partitioned() {
+    let query = QueryFixture::new("{ sum_n: sum(Table.n) }").with_output(
+        "by_foreign_key",
+        "with_key(Table.foreign_key_i64, { count: count(Table) })",
+        PerEntityBehavior::Unspecified,
+    );
+    let data = with_key_data_fixture().await;
+    let expected = query.clone().run_snapshot_to_csv(&data).await.unwrap();
+    let actual = query
+        .with_partitions(2)
+        .run_snapshot_to_csv(&data)
+        .await
+        .unwrap();
+    assert_eq!(actual.inner, expected.inner);
+    assert_eq!(actual.outputs, expected.outputs);
+}
@@ -0,0 +1,67 @@
+//! e2e tests for executing queries with multiple entity-key partitions.
+//!
+//! Each query is executed with a single partition and with multiple
+//! partitions, and the results are expected to be identical.
+
+use crate::fixtures::i64_data_fixture;
+use crate::lookup_tests::lookup_account_data_fixture;
+use crate::with_key_tests::with_key_data_fixture;
+use crate::{DataFixture, QueryFixture};
+
+async fn assert_same_partitioned(query: QueryFixture, data: &DataFixture) {
+    let expected = query.clone().run_to_csv(data).await.unwrap();
+    for partitions in [2, 3, 8] {
+        let actual = query
+            .clone()
+            .with_partitions(partitions)
+            .run_to_csv(data)
+            .await
+            .unwrap();
+        assert_eq!(actual, expected, "with {partitions} partitions");
+    }
+}
+
+#[tokio::test]
+async fn test_partitioned_aggregation() {
+    assert_same_partitioned(
+        QueryFixture::new("{ m: Numbers.m, sum_m: sum(Numbers.m), max_n: max(Numbers.n) }"),
+        &i64_data_fixture().await,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_partitioned_final_results() {
+    assert_same_partitioned(
+        QueryFixture::new("{ sum_m: sum(Numbers.m), count_n: count(Numbers.n) }")
+            .with_final_results(),
+        &i64_data_fixture().await,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_partitioned_with_key() {
+    assert_same_partitioned(
+        QueryFixture::new(
+            "Table | with_key($input.foreign_key_i64) | last() | \
+             lookup(Table.foreign_key_i64) | when($input.foreign_key_i64 > 0)",
+        ),
+        &with_key_data_fixture().await,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_partitioned_lookup() {
+    assert_same_partitioned(
+        QueryFixture::new(
+            "let sum_sent = sum(Sent.amount)
+            let last_sender = last(Received.from)
+            let last_sender_sum_sent = lookup(last(Received.from), sum_sent)
+            in { sum_sent, last_sender, last_sender_sum_sent }",
+        ),
+        &lookup_account_data_fixture().await,
+    )
+    .await;
+}
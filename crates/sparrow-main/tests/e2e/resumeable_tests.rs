@@ -590,6 +590,47 @@ async fn test_resumeable_lag_basic() {
     "###);
 }
 
+#[tokio::test]
+async fn test_resumeable_session() {
+    // Test for resuming sessions which continue across the snapshot. The
+    // session of `A` continues with the second file, while `B` starts a new
+    // session.
+    let query_fixture = QueryFixture::new(
+        "{ sum_m: sum(Numbers.m, window=session(seconds(30))), session_id: \
+         session_id(Numbers, seconds(30)) }",
+    );
+    let result = assert_final_incremental_same_as_complete(
+        query_fixture,
+        TableConfig::new_with_table_source(
+            "Numbers",
+            &Uuid::new_v4(),
+            "time",
+            Some("subsort"),
+            "key",
+            "",
+        ),
+        indoc! {"
+        time,subsort,key,m,n
+        1996-12-19T16:00:00-08:00,0,A,5,
+        1996-12-19T16:00:05-08:00,0,B,24,
+        1996-12-19T16:00:20-08:00,0,A,17,
+        "},
+        indoc! {"
+        time,subsort,key,m,n
+        1996-12-19T16:00:40-08:00,0,A,12,
+        1996-12-19T16:01:00-08:00,0,B,2,
+        1996-12-19T16:01:05-08:00,0,A,3,
+    "},
+    )
+    .await;
+
+    insta::assert_snapshot!(result, @r###"
+    _time,_subsort,_key_hash,_key,sum_m,session_id
+    1996-12-20T00:01:05.000000001,18446744073709551615,3650215962958587783,A,37,1
+    1996-12-20T00:01:05.000000001,18446744073709551615,11753611437813598533,B,2,2
+    "###);
+}
+
 #[tokio::test]
 async fn test_resumeable_final_no_new_data() {
     // Test that producing final results from a snapshot with no new data works.
@@ -766,6 +807,81 @@ async fn test_shift_until() {
     "###);
 }
 
+#[tokio::test]
+async fn test_resumeable_backfills_new_state() {
+    // Resuming with a plan that adds an aggregation reuses the state of the
+    // existing aggregation and backfills the state of the new one.
+    let snapshot_dir = tempfile::Builder::new()
+        .prefix("snapshots_")
+        .tempdir()
+        .unwrap();
+
+    let mut data_fixture = DataFixture::new()
+        .with_table_from_csv(
+            TableConfig::new_with_table_source(
+                "Numbers",
+                &Uuid::new_v4(),
+                "time",
+                Some("subsort"),
+                "key",
+                "",
+            ),
+            indoc! {"
+            time,subsort,key,m,n
+            1996-12-19T16:39:57-08:00,0,A,5,10
+            1996-12-19T16:39:58-08:00,0,B,24,3
+            1996-12-19T16:39:59-08:00,0,A,17,6
+            1996-12-19T16:40:00-08:00,0,A,,9
+            "},
+        )
+        .await
+        .unwrap();
+
+    let snapshot_path = QueryFixture::new("{ sum_m: sum(Numbers.m) }")
+        .with_final_results()
+        .with_rocksdb(snapshot_dir.path(), None)
+        .run_snapshot_to_csv(&data_fixture)
+        .await
+        .unwrap()
+        .snapshots
+        .remove(0)
+        .path;
+
+    data_fixture
+        .table_mut("Numbers")
+        .add_file_source(&source_data::Source::CsvData(
+            indoc! {"
+            time,subsort,key,m,n
+            1996-12-19T16:40:01-08:00,0,A,12,
+            1997-12-19T16:40:01-08:00,0,B,2,5
+            1997-12-19T16:40:02-08:00,0,B,2,
+            "}
+            .to_owned(),
+        ))
+        .await
+        .unwrap();
+
+    // The backfill needs the history, so the first file is kept.
+    let query =
+        QueryFixture::new("{ sum_m: sum(Numbers.m), max_n: max(Numbers.n) }").with_final_results();
+    let complete_results = query.clone().run_to_csv(&data_fixture).await.unwrap();
+    let resumed_results = query
+        .with_rocksdb(
+            snapshot_dir.path(),
+            Some(std::path::Path::new(&snapshot_path)),
+        )
+        .run_to_csv(&data_fixture)
+        .await
+        .unwrap();
+
+    similar_asserts::assert_eq!(&resumed_results, &complete_results);
+    insta::assert_snapshot!(resumed_results, @r###"
+    _time,_subsort,_key_hash,_key,sum_m,max_n
+    1997-12-20T00:40:02.000000001,18446744073709551615,3650215962958587783,A,34,10
+    1997-12-20T00:40:02.000000001,18446744073709551615,11753611437813598533,B,28,5
+    "###);
+}
+
 #[tokio::test]
 #[ignore = "Persisting partially processed input files unsupported"]
 async fn test_resumeable_partial_overlap() {
@@ -73,6 +73,36 @@ async fn test_shift_to_plus_seconds() {
     "###)
 }
 
+#[tokio::test]
+async fn test_shift_to_within_memory_budget() {
+    insta::assert_snapshot!(QueryFixture::new("{ i64: ShiftFixture.i64 | shift_to(add_time(seconds(5), time_of($input))) }").with_max_memory_bytes(1 << 30).run_to_csv(&shift_data_fixture().await).await.unwrap(), @r###"
+    _time,_subsort,_key_hash,_key,i64
+    1996-12-20T00:40:02.000000000,0,3650215962958587783,A,57
+    1996-12-20T00:40:03.000000000,1,11753611437813598533,B,58
+    1996-12-20T00:40:04.000000000,2,3650215962958587783,A,59
+    1996-12-20T00:40:05.000000000,3,11753611437813598533,B,
+    1996-12-20T00:40:06.000000000,4,3650215962958587783,A,
+    1996-12-20T00:40:07.000000000,5,3650215962958587783,A,2
+    "###)
+}
+
+#[tokio::test]
+async fn test_shift_to_exceeds_memory_budget() {
+    let error = QueryFixture::new(
+        "{ i64: ShiftFixture.i64 | shift_to(add_time(seconds(5), time_of($input))) }",
+    )
+    .with_max_memory_bytes(1)
+    .run_to_csv(&shift_data_fixture().await)
+    .await
+    .unwrap_err()
+    .to_string();
+
+    assert!(
+        error.contains("(shift_to) exceeded the memory budget of 1 bytes"),
+        "unexpected error: {error}"
+    );
+}
+
 #[tokio::test]
 async fn test_shift_until_data_i64() {
     insta::assert_snapshot!(QueryFixture::new("{ i64: ShiftFixture.i64 | shift_until(ShiftFixture.cond) }").run_to_csv(&shift_data_fixture().await).await.unwrap(), @r###"
@@ -3,6 +3,7 @@
 // ordered: i64, f64, timestamp
 // number: i64, f64
 
+use chrono::NaiveDate;
 use indoc::indoc;
 use sparrow_api::kaskada::v1alpha::TableConfig;
 use uuid::Uuid;
@@ -609,3 +610,85 @@ async fn test_final_sliding_window_constant() {
     1996-12-20T00:40:04.000000001,18446744073709551615,11753611437813598533,B,3.9
     "###);
 }
+
+#[tokio::test]
+async fn test_sliding_time_window() {
+    insta::assert_snapshot!(QueryFixture::new("{ n: Foo.n, count: count(Foo.n, window=sliding(seconds(2))), sum: sum(Foo.n, window=sliding(seconds(2))) }").run_to_csv(&window_data_fixture().await).await.unwrap(), @r###"
+    _time,_subsort,_key_hash,_key,n,count,sum
+    1996-12-20T00:39:57.000000000,9223372036854775808,3650215962958587783,A,10.0,1,10.0
+    1996-12-20T00:39:58.000000000,9223372036854775808,11753611437813598533,B,3.9,1,3.9
+    1996-12-20T00:39:59.000000000,9223372036854775808,3650215962958587783,A,6.2,1,6.2
+    1996-12-20T00:40:00.000000000,9223372036854775808,3650215962958587783,A,9.25,2,15.45
+    1996-12-20T00:40:01.000000000,9223372036854775808,3650215962958587783,A,3.0,2,12.25
+    1996-12-20T00:40:02.000000000,9223372036854775808,3650215962958587783,A,8.0,2,11.0
+    1996-12-20T00:40:03.000000000,9223372036854775808,3650215962958587783,A,,1,8.0
+    1996-12-20T00:40:04.000000000,9223372036854775808,3650215962958587783,A,10.0,1,10.0
+    "###);
+}
+
+#[tokio::test]
+async fn test_sliding_time_window_start_time() {
+    // Inputs before the window preceding the start time are skipped, without
+    // changing the results at or after the start time.
+    let start_time = NaiveDate::from_ymd_opt(1996, 12, 20)
+        .unwrap()
+        .and_hms_opt(0, 40, 1)
+        .unwrap();
+    insta::assert_snapshot!(QueryFixture::new("{ n: Foo.n, count: count(Foo.n, window=sliding(seconds(2))), sum: sum(Foo.n, window=sliding(seconds(2))) }").with_start_time(start_time).run_to_csv(&window_data_fixture().await).await.unwrap(), @r###"
+    _time,_subsort,_key_hash,_key,n,count,sum
+    1996-12-20T00:40:01.000000000,9223372036854775808,3650215962958587783,A,3.0,2,12.25
+    1996-12-20T00:40:02.000000000,9223372036854775808,3650215962958587783,A,8.0,2,11.0
+    1996-12-20T00:40:03.000000000,9223372036854775808,3650215962958587783,A,,1,8.0
+    1996-12-20T00:40:04.000000000,9223372036854775808,3650215962958587783,A,10.0,1,10.0
+    "###);
+}
+
+async fn session_data_fixture() -> DataFixture {
+    DataFixture::new()
+        .with_table_from_csv(
+            TableConfig::new_with_table_source(
+                "Foo",
+                &Uuid::new_v4(),
+                "time",
+                Some("subsort"),
+                "key",
+                "",
+            ),
+            indoc! {"
+    time,subsort,key,n
+    1996-12-19T16:00:00-08:00,0,A,1
+    1996-12-19T16:00:10-08:00,0,A,2
+    1996-12-19T16:00:20-08:00,0,B,5
+    1996-12-19T16:01:00-08:00,0,A,3
+    1996-12-19T16:01:20-08:00,0,A,4
+    "},
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_session_window() {
+    insta::assert_snapshot!(QueryFixture::new("{ n: Foo.n, count: count(Foo, window=session(seconds(30))), sum: sum(Foo.n, window=session(seconds(30))), session_id: session_id(Foo, seconds(30)), session_start: session_start(Foo, seconds(30)) }").run_to_csv(&session_data_fixture().await).await.unwrap(), @r###"
+    _time,_subsort,_key_hash,_key,n,count,sum,session_id,session_start
+    1996-12-20T00:00:00.000000000,9223372036854775808,3650215962958587783,A,1,1,1,1,1996-12-20T00:00:00.000000000
+    1996-12-20T00:00:10.000000000,9223372036854775808,3650215962958587783,A,2,2,3,1,1996-12-20T00:00:00.000000000
+    1996-12-20T00:00:20.000000000,9223372036854775808,11753611437813598533,B,5,1,5,1,1996-12-20T00:00:20.000000000
+    1996-12-20T00:01:00.000000000,9223372036854775808,3650215962958587783,A,3,1,3,2,1996-12-20T00:01:00.000000000
+    1996-12-20T00:01:20.000000000,9223372036854775808,3650215962958587783,A,4,2,7,2,1996-12-20T00:01:00.000000000
+    "###);
+}
+
+#[tokio::test]
+async fn test_session_id_window() {
+    // Counts the sessions starting within the last 70 seconds. The first
+    // session of `A` leaves the window before its last row.
+    insta::assert_snapshot!(QueryFixture::new("{ n: Foo.n, session_id: session_id(Foo, seconds(30)), recent_sessions: session_id(Foo, seconds(30), window=sliding(seconds(70))) }").run_to_csv(&session_data_fixture().await).await.unwrap(), @r###"
+    _time,_subsort,_key_hash,_key,n,session_id,recent_sessions
+    1996-12-20T00:00:00.000000000,9223372036854775808,3650215962958587783,A,1,1,1
+    1996-12-20T00:00:10.000000000,9223372036854775808,3650215962958587783,A,2,1,1
+    1996-12-20T00:00:20.000000000,9223372036854775808,11753611437813598533,B,5,1,1
+    1996-12-20T00:01:00.000000000,9223372036854775808,3650215962958587783,A,3,2,2
+    1996-12-20T00:01:20.000000000,9223372036854775808,3650215962958587783,A,4,2,1
+    "###);
+}
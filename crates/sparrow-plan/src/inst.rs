@@ -62,8 +62,8 @@ pub enum InstOp {
     Coalesce,
     #[strum(props(
         dfg_signature = "count_if(input: any, window: window = null) -> u32",
-        plan_signature = "count_if(input: any, ticks: bool = null, slide_duration: i64 = null) -> \
-                          u32"
+        plan_signature = "count_if(input: any, ticks: bool = null, slide_duration: i64 = null, \
+                          session_gap: i64 = null) -> u32"
     ))]
     CountIf,
     #[strum(props(signature = "day_of_month(time: timestamp_ns) -> u32"))]
@@ -88,7 +88,8 @@ pub enum InstOp {
     Exp,
     #[strum(props(
         dfg_signature = "first(input: any, window: window = null) -> any",
-        plan_signature = "first(input: any, ticks: bool = null, slide_duration: i64 = null) -> any"
+        plan_signature = "first(input: any, ticks: bool = null, slide_duration: i64 = null, \
+                          session_gap: i64 = null) -> any"
     ))]
     First,
     #[strum(props(signature = "floor(n: number) -> number"))]
@@ -113,7 +114,8 @@ pub enum InstOp {
     Lag,
     #[strum(props(
         dfg_signature = "last(input: any, window: window = null) -> any",
-        plan_signature = "last(input: any, ticks: bool = null, slide_duration: i64 = null) -> any"
+        plan_signature = "last(input: any, ticks: bool = null, slide_duration: i64 = null, \
+                          session_gap: i64 = null) -> any"
     ))]
     Last,
     #[strum(props(signature = "len(s: string) -> i32"))]
@@ -130,20 +132,20 @@ pub enum InstOp {
     Lte,
     #[strum(props(
         dfg_signature = "max(input: ordered, window: window = null) -> ordered",
-        plan_signature = "max(input: ordered, ticks: bool = null, slide_duration: i64 = null) -> \
-                          ordered"
+        plan_signature = "max(input: ordered, ticks: bool = null, slide_duration: i64 = null, \
+                          session_gap: i64 = null) -> ordered"
     ))]
     Max,
     #[strum(props(
         dfg_signature = "mean(input: number, window: window = null) -> f64",
-        plan_signature = "mean(input: number, ticks: bool = null, slide_duration: i64 = null) -> \
-                          f64"
+        plan_signature = "mean(input: number, ticks: bool = null, slide_duration: i64 = null, \
+                          session_gap: i64 = null) -> f64"
     ))]
     Mean,
     #[strum(props(
         dfg_signature = "min(input: ordered, window: window = null) -> ordered",
-        plan_signature = "min(input: ordered, ticks: bool = null, slide_duration: i64 = null) -> \
-                          ordered"
+        plan_signature = "min(input: ordered, ticks: bool = null, slide_duration: i64 = null, \
+                          session_gap: i64 = null) -> ordered"
     ))]
     Min,
     #[strum(props(signature = "month_of_year(time: timestamp_ns) -> u32"))]
@@ -184,8 +186,8 @@ pub enum InstOp {
     Substring,
     #[strum(props(
         dfg_signature = "sum(input: number, window: window = null) -> number",
-        plan_signature = "sum(input: number, ticks: bool = null, slide_duration: i64 = null) -> \
-                          number"
+        plan_signature = "sum(input: number, ticks: bool = null, slide_duration: i64 = null, \
+                          session_gap: i64 = null) -> number"
     ))]
     Sum,
     #[strum(props(signature = "time_of(input: any) -> timestamp_ns"))]
@@ -194,8 +196,8 @@ pub enum InstOp {
     Upper,
     #[strum(props(
         dfg_signature = "variance(input: number, window: window = null) -> f64",
-        plan_signature = "variance(input: number, ticks: bool = null, slide_duration: i64 = null) \
-                          -> f64"
+        plan_signature = "variance(input: number, ticks: bool = null, slide_duration: i64 = null, \
+                          session_gap: i64 = null) -> f64"
     ))]
     Variance,
     #[strum(props(signature = "year(time: timestamp_ns) -> i32"))]
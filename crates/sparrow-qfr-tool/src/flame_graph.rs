@@ -0,0 +1,32 @@
+use std::path::PathBuf;
+
+use error_stack::{IntoReport, ResultExt};
+
+use crate::error::Error;
+use crate::profile::Profile;
+
+/// Write the collapsed stacks for use with flame-graph tools.
+///
+/// The output may be rendered with `flamegraph.pl` or `inferno-flamegraph`.
+#[derive(Debug, clap::Args)]
+#[command(version, rename_all = "kebab-case")]
+pub(crate) struct FlameGraphCommand {
+    /// Input file containing the Flight records.
+    #[arg(long, value_name = "FILE")]
+    pub input: PathBuf,
+
+    /// Output file to write the collapsed stacks to.
+    #[arg(long, value_name = "FILE")]
+    pub output: PathBuf,
+}
+
+impl FlameGraphCommand {
+    pub fn run(&self) -> error_stack::Result<(), Error> {
+        let profile = Profile::try_from_path(&self.input)?;
+        std::fs::write(&self.output, profile.collapsed_stacks())
+            .into_report()
+            .change_context(Error::Internal)
+            .attach_printable_lazy(|| format!("Output Path: {}", self.output.display()))?;
+        Ok(())
+    }
+}
@@ -13,6 +13,9 @@ mod chrome;
 mod chrome_tracing;
 mod dot;
 mod error;
+mod flame_graph;
+mod profile;
+mod summary;
 
 use crate::error::Error;
 
@@ -27,6 +30,9 @@ pub struct QfrOptions {
 enum Command {
     Chrome(chrome::ChromeCommand),
     DotPlan(dot::DotPlanCommand),
+    Summary(summary::SummaryCommand),
+    Diff(summary::DiffCommand),
+    FlameGraph(flame_graph::FlameGraphCommand),
 }
 
 fn main() -> error_stack::Result<(), Error> {
@@ -35,5 +41,8 @@ fn main() -> error_stack::Result<(), Error> {
     match options.command {
         Command::Chrome(command) => command.run(),
         Command::DotPlan(command) => command.run(),
+        Command::Summary(command) => command.run(),
+        Command::Diff(command) => command.run(),
+        Command::FlameGraph(command) => command.run(),
     }
 }
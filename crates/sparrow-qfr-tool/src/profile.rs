@@ -0,0 +1,547 @@
+//! Aggregation of flight records into per-activity and per-operation totals.
+//!
+//! This is used by the `summary` and `diff` commands to report where time was
+//! spent, and by the `flame-graph` command to produce collapsed stacks.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use error_stack::ResultExt;
+use fallible_iterator::FallibleIterator;
+use hashbrown::HashMap;
+use itertools::Itertools;
+use sparrow_qfr::io::reader::FlightRecordReader;
+use sparrow_qfr::kaskada::sparrow::v1alpha::flight_record::{Record, ReportActivity};
+use sparrow_qfr::kaskada::sparrow::v1alpha::{
+    metric_value, FlightRecord, FlightRecordHeader, MetricValue,
+};
+
+use crate::error::Error;
+
+/// Aggregated statistics from a flight record.
+#[derive(Debug, Default)]
+pub(crate) struct Profile {
+    /// Statistics for each activity, keyed by the path of activity labels.
+    pub activities: BTreeMap<String, DurationStats>,
+    /// Statistics for each thread (operation), keyed by the thread label.
+    ///
+    /// This only includes root activities, since the duration of a child
+    /// activity is included in the duration of its parent.
+    pub operations: BTreeMap<String, DurationStats>,
+    /// Statistics for each metric, keyed by the metric label.
+    pub metrics: BTreeMap<String, MetricStats>,
+    /// The total wall time (in microseconds) of each stack.
+    ///
+    /// Each stack is the thread label followed by the activity path, separated
+    /// by `;`.
+    stacks: BTreeMap<String, u64>,
+}
+
+/// Statistics on the durations an activity was executed for.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct DurationStats {
+    /// Wall time (in microseconds) of each execution.
+    wall_durations_us: Vec<u64>,
+    /// Total CPU time (in microseconds) of all executions.
+    pub cpu_us: u64,
+}
+
+impl DurationStats {
+    fn add(&mut self, wall_duration_us: u64, cpu_duration_us: u64) {
+        self.wall_durations_us.push(wall_duration_us);
+        self.cpu_us += cpu_duration_us;
+    }
+
+    pub fn count(&self) -> usize {
+        self.wall_durations_us.len()
+    }
+
+    pub fn wall_us(&self) -> u64 {
+        self.wall_durations_us.iter().sum()
+    }
+
+    /// Return the wall time of the given percentile (between 0 and 100).
+    ///
+    /// Uses the nearest-rank method.
+    pub fn percentile_us(&self, percentile: usize) -> u64 {
+        if self.wall_durations_us.is_empty() {
+            return 0;
+        }
+        let mut durations = self.wall_durations_us.clone();
+        durations.sort_unstable();
+        let rank = (percentile * durations.len() + 99) / 100;
+        durations[rank.clamp(1, durations.len()) - 1]
+    }
+}
+
+/// Aggregated values reported for a metric.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MetricStats {
+    pub count: usize,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl MetricStats {
+    fn new(value: f64) -> Self {
+        Self {
+            count: 1,
+            sum: value,
+            min: value,
+            max: value,
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+}
+
+/// Builds a [Profile] from the header and records of a flight record.
+struct ProfileBuilder {
+    /// The path of labels to each activity, separated by `;`.
+    activity_paths: HashMap<u32, String>,
+    /// Whether each activity is a root activity.
+    root_activities: HashMap<u32, bool>,
+    metric_names: HashMap<u32, String>,
+    thread_labels: HashMap<u32, String>,
+    profile: Profile,
+}
+
+impl ProfileBuilder {
+    fn new(header: &FlightRecordHeader) -> Self {
+        let parents: HashMap<u32, Option<u32>> = header
+            .activities
+            .iter()
+            .map(|activity| (activity.activity_id, activity.parent_activity_id))
+            .collect();
+        let labels: HashMap<u32, &str> = header
+            .activities
+            .iter()
+            .map(|activity| (activity.activity_id, activity.label.as_str()))
+            .collect();
+
+        let mut activity_paths = HashMap::with_capacity(labels.len());
+        for activity_id in labels.keys() {
+            let mut path = Vec::new();
+            let mut next = Some(*activity_id);
+            while let Some(id) = next {
+                // Stop if the parent isn't registered, or there is a cycle.
+                let Some(label) = labels.get(&id) else { break };
+                if path.len() > labels.len() {
+                    break;
+                }
+                path.push(frame_name(label));
+                next = parents.get(&id).copied().flatten();
+            }
+            path.reverse();
+            activity_paths.insert(*activity_id, path.join(";"));
+        }
+
+        let root_activities = parents
+            .iter()
+            .map(|(id, parent)| (*id, parent.is_none()))
+            .collect();
+        let metric_names = header
+            .metrics
+            .iter()
+            .map(|metric| (metric.metric_id, metric.label.clone()))
+            .collect();
+
+        Self {
+            activity_paths,
+            root_activities,
+            metric_names,
+            thread_labels: HashMap::new(),
+            profile: Profile::default(),
+        }
+    }
+
+    fn add(&mut self, record: FlightRecord) -> error_stack::Result<(), Error> {
+        match record.record {
+            Some(Record::RegisterThread(thread)) => {
+                self.thread_labels.insert(thread.thread_id, thread.label);
+                Ok(())
+            }
+            Some(Record::ReportActivity(activity)) => self.add_activity(activity),
+            Some(Record::ReportMetrics(metrics)) => self.add_metrics(metrics.metrics),
+            unsupported => error_stack::bail!(Error::UnsupportedRecord(unsupported)),
+        }
+    }
+
+    fn add_activity(&mut self, activity: ReportActivity) -> error_stack::Result<(), Error> {
+        let Some(path) = self.activity_paths.get(&activity.activity_id) else {
+            error_stack::bail!(Error::UndefinedActivityId)
+        };
+        let thread = self.thread_label(activity.thread_id);
+
+        self.profile
+            .activities
+            .entry(path.replace(';', "/"))
+            .or_default()
+            .add(activity.wall_duration_us, activity.cpu_duration_us);
+        if self.root_activities[&activity.activity_id] {
+            self.profile
+                .operations
+                .entry(thread.clone())
+                .or_default()
+                .add(activity.wall_duration_us, activity.cpu_duration_us);
+        }
+        *self
+            .profile
+            .stacks
+            .entry(format!("{};{}", frame_name(&thread), path))
+            .or_default() += activity.wall_duration_us;
+
+        self.add_metrics(activity.metrics)
+    }
+
+    fn add_metrics(&mut self, metrics: Vec<MetricValue>) -> error_stack::Result<(), Error> {
+        for metric in metrics {
+            let Some(name) = self.metric_names.get(&metric.metric_id) else {
+                error_stack::bail!(Error::UndefinedMetricId);
+            };
+            let value = match metric.value {
+                Some(metric_value::Value::U64Value(n)) => n as f64,
+                Some(metric_value::Value::I64Value(n)) => n as f64,
+                Some(metric_value::Value::F64Value(n)) => n,
+                None => error_stack::bail!(Error::MissingMetricValue),
+            };
+            match self.profile.metrics.get_mut(name) {
+                Some(stats) => stats.add(value),
+                None => {
+                    self.profile
+                        .metrics
+                        .insert(name.clone(), MetricStats::new(value));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn thread_label(&self, thread_id: u32) -> String {
+        self.thread_labels
+            .get(&thread_id)
+            .cloned()
+            .unwrap_or_else(|| format!("thread {thread_id}"))
+    }
+}
+
+impl Profile {
+    /// Read and aggregate the flight record at the given path.
+    pub fn try_from_path(path: &Path) -> error_stack::Result<Self, Error> {
+        let reader = FlightRecordReader::try_new(path)
+            .change_context(Error::Internal)
+            .attach_printable_lazy(|| format!("Input Path: {}", path.display()))?;
+        let records = reader.records().change_context(Error::Internal)?;
+        Self::try_new(reader.header(), records)
+    }
+
+    pub fn try_new(
+        header: &FlightRecordHeader,
+        mut records: impl FallibleIterator<
+            Item = FlightRecord,
+            Error = error_stack::Report<sparrow_qfr::io::reader::Error>,
+        >,
+    ) -> error_stack::Result<Self, Error> {
+        let mut builder = ProfileBuilder::new(header);
+        while let Some(record) = records.next().change_context(Error::Internal)? {
+            builder.add(record)?;
+        }
+        Ok(builder.profile)
+    }
+
+    /// Return the collapsed stacks, for use with flame-graph tools.
+    ///
+    /// Each line contains a stack followed by the wall time (in microseconds)
+    /// spent in that stack, excluding time spent in child activities.
+    pub fn collapsed_stacks(&self) -> String {
+        let mut child_totals: BTreeMap<&str, u64> = BTreeMap::new();
+        for (stack, total) in &self.stacks {
+            if let Some((parent, _)) = stack.rsplit_once(';') {
+                *child_totals.entry(parent).or_default() += total;
+            }
+        }
+
+        let mut output = String::new();
+        for (stack, total) in &self.stacks {
+            let children = child_totals.get(stack.as_str()).copied().unwrap_or(0);
+            let self_time = total.saturating_sub(children);
+            if self_time > 0 {
+                writeln!(output, "{stack} {self_time}").expect("write to string");
+            }
+        }
+        output
+    }
+
+    /// Return a human-readable summary of the profile.
+    pub fn summary(&self) -> String {
+        let mut output = String::new();
+        write_duration_table(&mut output, "Operation", &self.operations);
+        output.push('\n');
+        write_duration_table(&mut output, "Activity", &self.activities);
+        output.push('\n');
+
+        writeln!(
+            output,
+            "{:<40} {:>8} {:>14} {:>14} {:>14}",
+            "Metric", "Count", "Sum", "Min", "Max"
+        )
+        .expect("write to string");
+        for (name, stats) in &self.metrics {
+            writeln!(
+                output,
+                "{:<40} {:>8} {:>14} {:>14} {:>14}",
+                name, stats.count, stats.sum, stats.min, stats.max
+            )
+            .expect("write to string");
+        }
+        output
+    }
+
+    /// Return a human-readable comparison of this profile to a baseline.
+    pub fn diff(&self, baseline: &Profile) -> String {
+        let mut output = String::new();
+        write_duration_diff(
+            &mut output,
+            "Operation",
+            &baseline.operations,
+            &self.operations,
+        );
+        output.push('\n');
+        write_duration_diff(
+            &mut output,
+            "Activity",
+            &baseline.activities,
+            &self.activities,
+        );
+        output.push('\n');
+
+        writeln!(
+            output,
+            "{:<40} {:>14} {:>14} {:>9}",
+            "Metric (sum)", "Baseline", "Current", "Change"
+        )
+        .expect("write to string");
+        for name in union_keys(&baseline.metrics, &self.metrics) {
+            let before = baseline.metrics.get(name).map_or(0.0, |stats| stats.sum);
+            let after = self.metrics.get(name).map_or(0.0, |stats| stats.sum);
+            writeln!(
+                output,
+                "{:<40} {:>14} {:>14} {:>9}",
+                name,
+                before,
+                after,
+                percent_change(before, after)
+            )
+            .expect("write to string");
+        }
+        output
+    }
+}
+
+fn write_duration_table(output: &mut String, kind: &str, stats: &BTreeMap<String, DurationStats>) {
+    writeln!(
+        output,
+        "{:<40} {:>8} {:>12} {:>12} {:>10} {:>10} {:>10} {:>10}",
+        kind, "Count", "Wall (us)", "CPU (us)", "p50 (us)", "p90 (us)", "p99 (us)", "Max (us)"
+    )
+    .expect("write to string");
+    for (name, stats) in stats {
+        writeln!(
+            output,
+            "{:<40} {:>8} {:>12} {:>12} {:>10} {:>10} {:>10} {:>10}",
+            name,
+            stats.count(),
+            stats.wall_us(),
+            stats.cpu_us,
+            stats.percentile_us(50),
+            stats.percentile_us(90),
+            stats.percentile_us(99),
+            stats.percentile_us(100)
+        )
+        .expect("write to string");
+    }
+}
+
+fn write_duration_diff(
+    output: &mut String,
+    kind: &str,
+    baseline: &BTreeMap<String, DurationStats>,
+    current: &BTreeMap<String, DurationStats>,
+) {
+    writeln!(
+        output,
+        "{:<40} {:>14} {:>14} {:>9}",
+        format!("{kind} wall (us)"),
+        "Baseline",
+        "Current",
+        "Change"
+    )
+    .expect("write to string");
+    for name in union_keys(baseline, current) {
+        let before = baseline.get(name).map_or(0, DurationStats::wall_us);
+        let after = current.get(name).map_or(0, DurationStats::wall_us);
+        writeln!(
+            output,
+            "{:<40} {:>14} {:>14} {:>9}",
+            name,
+            before,
+            after,
+            percent_change(before as f64, after as f64)
+        )
+        .expect("write to string");
+    }
+}
+
+fn union_keys<'a, T, U>(
+    baseline: &'a BTreeMap<String, T>,
+    current: &'a BTreeMap<String, U>,
+) -> impl Iterator<Item = &'a String> {
+    baseline.keys().merge(current.keys()).dedup()
+}
+
+fn percent_change(before: f64, after: f64) -> String {
+    if before == 0.0 {
+        if after == 0.0 {
+            "0.0%".to_owned()
+        } else {
+            "new".to_owned()
+        }
+    } else {
+        format!("{:+.1}%", (after - before) / before * 100.0)
+    }
+}
+
+/// Return the name to use for a frame in a collapsed stack.
+///
+/// The collapsed stack format separates frames with `;`, so these are
+/// replaced within the label.
+fn frame_name(label: &str) -> String {
+    label.replace(';', ":")
+}
+
+#[cfg(test)]
+mod tests {
+    use sparrow_qfr::kaskada::sparrow::v1alpha::flight_record::RegisterThread;
+    use sparrow_qfr::kaskada::sparrow::v1alpha::flight_record_header::register_metric::MetricKind;
+    use sparrow_qfr::kaskada::sparrow::v1alpha::flight_record_header::{
+        RegisterActivity, RegisterMetric,
+    };
+
+    use super::*;
+
+    fn test_profile(root_us: u64, child_us: u64) -> Profile {
+        let header = FlightRecordHeader {
+            version: sparrow_qfr::QFR_VERSION,
+            activities: vec![
+                RegisterActivity {
+                    activity_id: 1,
+                    label: "scan".to_owned(),
+                    parent_activity_id: None,
+                },
+                RegisterActivity {
+                    activity_id: 2,
+                    label: "read_file".to_owned(),
+                    parent_activity_id: Some(1),
+                },
+            ],
+            metrics: vec![RegisterMetric {
+                metric_id: 7,
+                label: "rows".to_owned(),
+                kind: MetricKind::U64Counter as i32,
+            }],
+            ..FlightRecordHeader::default()
+        };
+
+        let activity = |activity_id, wall_duration_us, rows| {
+            Record::ReportActivity(ReportActivity {
+                activity_id,
+                thread_id: 3,
+                wall_timestamp_us: 0,
+                wall_duration_us,
+                cpu_duration_us: wall_duration_us / 2,
+                metrics: vec![MetricValue {
+                    metric_id: 7,
+                    value: Some(metric_value::Value::U64Value(rows)),
+                }],
+            })
+        };
+        let records = vec![
+            Record::RegisterThread(RegisterThread {
+                thread_id: 3,
+                label: "operation 0".to_owned(),
+            }),
+            activity(2, child_us, 10),
+            activity(2, child_us * 3, 30),
+            activity(1, root_us, 40),
+        ];
+        let records = fallible_iterator::convert::<
+            _,
+            error_stack::Report<sparrow_qfr::io::reader::Error>,
+            _,
+        >(
+            records
+                .into_iter()
+                .map(|record| FlightRecord {
+                    record: Some(record),
+                })
+                .map(Ok),
+        );
+        Profile::try_new(&header, records).unwrap()
+    }
+
+    #[test]
+    fn test_profile_totals() {
+        let profile = test_profile(1000, 100);
+
+        let read_file = &profile.activities["scan/read_file"];
+        assert_eq!(read_file.count(), 2);
+        assert_eq!(read_file.wall_us(), 400);
+        assert_eq!(read_file.cpu_us, 200);
+        assert_eq!(read_file.percentile_us(50), 100);
+        assert_eq!(read_file.percentile_us(99), 300);
+
+        // Only the root activity counts towards the operation.
+        let operation = &profile.operations["operation 0"];
+        assert_eq!(operation.count(), 1);
+        assert_eq!(operation.wall_us(), 1000);
+
+        assert_eq!(
+            profile.metrics["rows"],
+            MetricStats {
+                count: 3,
+                sum: 80.0,
+                min: 10.0,
+                max: 40.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_collapsed_stacks() {
+        let profile = test_profile(1000, 100);
+        assert_eq!(
+            profile.collapsed_stacks(),
+            "operation 0;scan 600\noperation 0;scan;read_file 400\n"
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let baseline = test_profile(1000, 100);
+        let current = test_profile(1500, 100);
+        let diff = current.diff(&baseline);
+        assert!(diff
+            .lines()
+            .any(|line| line.starts_with("operation 0") && line.ends_with("+50.0%")));
+        assert!(diff
+            .lines()
+            .any(|line| line.starts_with("scan/read_file") && line.ends_with("+0.0%")));
+    }
+}
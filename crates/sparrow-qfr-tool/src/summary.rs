@@ -0,0 +1,43 @@
+use std::path::PathBuf;
+
+use crate::error::Error;
+use crate::profile::Profile;
+
+#[derive(Debug, clap::Args)]
+#[command(version, rename_all = "kebab-case")]
+pub(crate) struct SummaryCommand {
+    /// Input file containing the Flight records.
+    #[arg(long, value_name = "FILE")]
+    pub input: PathBuf,
+}
+
+impl SummaryCommand {
+    #[allow(clippy::print_stdout)]
+    pub fn run(&self) -> error_stack::Result<(), Error> {
+        let profile = Profile::try_from_path(&self.input)?;
+        println!("{}", profile.summary());
+        Ok(())
+    }
+}
+
+#[derive(Debug, clap::Args)]
+#[command(version, rename_all = "kebab-case")]
+pub(crate) struct DiffCommand {
+    /// File containing the Flight records to compare against.
+    #[arg(long, value_name = "FILE")]
+    pub baseline: PathBuf,
+
+    /// File containing the Flight records to compare.
+    #[arg(long, value_name = "FILE")]
+    pub input: PathBuf,
+}
+
+impl DiffCommand {
+    #[allow(clippy::print_stdout)]
+    pub fn run(&self) -> error_stack::Result<(), Error> {
+        let baseline = Profile::try_from_path(&self.baseline)?;
+        let profile = Profile::try_from_path(&self.input)?;
+        println!("{}", profile.diff(&baseline));
+        Ok(())
+    }
+}
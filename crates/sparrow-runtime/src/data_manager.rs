@@ -12,6 +12,7 @@ use sparrow_api::kaskada::v1alpha::PreparedFile;
 use tempfile::NamedTempFile;
 use tracing::{debug, error};
 
+use crate::metrics;
 use crate::s3::{self, S3Helper, S3Object};
 
 /// Manages the data files on disk and being downloaded.
@@ -255,6 +256,9 @@ impl DataPath {
                         error!("Failed to download '{:?}' to tempfile: {}", s3_object, err);
                         DownloadError
                     })?;
+                if let Ok(metadata) = temp_file.as_file().metadata() {
+                    metrics::BYTES_DOWNLOADED.inc_by(metadata.len());
+                }
 
                 Ok(temp_file)
             }
@@ -1,20 +1,27 @@
+use std::collections::BTreeMap;
+use std::path::Path;
 use std::sync::Arc;
 
 use chrono::NaiveDateTime;
 use error_stack::{IntoReport, IntoReportCompat, ResultExt};
-use futures::Stream;
+use futures::{Stream, StreamExt};
 use prost_wkt_types::Timestamp;
 use sparrow_api::kaskada::v1alpha::{
-    ExecuteRequest, ExecuteResponse, LateBoundValue, PerEntityBehavior,
+    destination, ComputePlan, ComputeSnapshotConfig, Destination, ExecuteRequest, ExecuteResponse,
+    FileType, LateBoundValue, ObjectStoreDestination, PerEntityBehavior,
 };
 use sparrow_compiler::{hash_compute_plan_proto, DataContext};
 use sparrow_core::ScalarValue;
 use sparrow_instructions::ComputeStore;
 use sparrow_qfr::kaskada::sparrow::v1alpha::FlightRecordHeader;
+use tempfile::TempDir;
 
 use crate::data_manager::DataManager;
 use crate::execute::key_hash_inverse::{KeyHashInverse, ThreadSafeKeyHashInverse};
+use crate::execute::memory::MemoryBudget;
 use crate::execute::operation::OperationContext;
+use crate::execute::operation_stats::PlanStats;
+use crate::metrics::ActiveMaterialization;
 use crate::s3::S3Helper;
 use crate::RuntimeOptions;
 
@@ -22,10 +29,14 @@ mod compute_executor;
 mod error;
 mod input_prefetch;
 pub(crate) mod key_hash_inverse;
+pub(crate) mod memory;
 pub(crate) mod operation;
+mod operation_stats;
 pub mod output;
+mod partition;
 mod progress_reporter;
 mod spawner;
+mod state_reuse;
 
 pub use compute_executor::*;
 pub use error::*;
@@ -46,6 +57,215 @@ pub async fn execute(
     bounded_lateness_ns: Option<i64>,
     _flight_record_local_path: Option<std::path::PathBuf>,
     _flight_record_header: FlightRecordHeader,
+) -> error_stack::Result<impl Stream<Item = error_stack::Result<ExecuteResponse, Error>>, Error> {
+    let plan = request.plan.as_ref().ok_or(Error::MissingField("plan"))?;
+    let plan_hash = hash_compute_plan_proto(plan);
+    check_final_result_time(&request, plan)?;
+    let min_input_time = min_input_time(&request, plan)?;
+
+    // If the snapshot config exists, sparrow should attempt to resume from state,
+    // and store new state. Create a new storage path for the local store to
+    // exist.
+    let storage_dir = if let Some(config) = &request.compute_snapshot_config {
+        let dir = tempfile::Builder::new()
+            .prefix(&STORE_PATH_PREFIX)
+            .tempdir()
+            .into_report()
+            .change_context(Error::internal_msg("create snapshot dir"))?;
+
+        // If a `resume_from` path is specified, download the existing state from s3.
+        if config.resume_from.is_some() {
+            crate::s3::download_snapshot(&s3_helper, dir.path(), config)
+                .await
+                .into_report()
+                .change_context(Error::internal_msg("download snapshot"))?;
+
+            // The snapshot may have been written by a different plan. If so,
+            // reuse the state that is compatible with this plan, and backfill
+            // the rest.
+            let backfill_until = state_reuse::reuse_compatible_state(dir.path(), plan, &plan_hash)
+                .into_report()
+                .change_context(Error::internal_msg("reuse snapshot state"))?;
+            if let Some(backfill_until) = backfill_until {
+                backfill_state(&request, &s3_helper, dir.path(), backfill_until).await?;
+            }
+        };
+
+        Some(dir)
+    } else {
+        None
+    };
+
+    start_execution(
+        request,
+        s3_helper,
+        bounded_lateness_ns,
+        storage_dir,
+        min_input_time,
+    )
+    .await
+}
+
+/// Return the behaviors of the result of the query and each named output.
+fn per_entity_behaviors(plan: &ComputePlan) -> Vec<PerEntityBehavior> {
+    std::iter::once(plan.per_entity_behavior())
+        .chain(
+            plan.outputs
+                .iter()
+                .map(|output| output.per_entity_behavior()),
+        )
+        .collect()
+}
+
+/// Check that the final result time is set if any behavior requires it.
+///
+/// This is checked before executing, since the time is needed to choose the
+/// snapshot and to produce the results of any behavior at the final time.
+fn check_final_result_time(
+    request: &ExecuteRequest,
+    plan: &ComputePlan,
+) -> error_stack::Result<(), Error> {
+    error_stack::ensure!(
+        request.final_result_time.is_some()
+            || !per_entity_behaviors(plan).contains(&PerEntityBehavior::FinalAtTime),
+        Error::MissingField("final_result_time")
+    );
+    Ok(())
+}
+
+/// Return the earliest time of inputs which may affect the results.
+///
+/// Inputs older than the look-back of the plan before the start time can't
+/// affect the results, so they don't need to be read. Snapshots contain the
+/// state of every input, so they can't be used when inputs are skipped.
+fn min_input_time(
+    request: &ExecuteRequest,
+    plan: &ComputePlan,
+) -> error_stack::Result<Option<NaiveDateTime>, Error> {
+    let (Some(start_time), Some(max_lookback_ns)) = (&request.start_time, plan.max_lookback_ns)
+    else {
+        return Ok(None);
+    };
+
+    error_stack::ensure!(
+        request.compute_snapshot_config.is_none(),
+        Error::InvalidStartTime(
+            "snapshots can't be used with a start time and bounded look-back".to_owned()
+        )
+    );
+
+    let min_input_time =
+        NaiveDateTime::from_timestamp_opt(start_time.seconds, start_time.nanos as u32)
+            .ok_or_else(|| Error::InvalidStartTime(format!("{start_time:?}")))?
+            .checked_sub_signed(chrono::Duration::nanoseconds(max_lookback_ns));
+    if let Some(min_input_time) = min_input_time {
+        tracing::info!("Reading inputs at or after {min_input_time}");
+    }
+    Ok(min_input_time)
+}
+
+/// Compute the state needed by the plan which couldn't be reused from the
+/// snapshot at `storage_path`, and add it to the snapshot.
+///
+/// The plan is executed without a snapshot over the inputs up to (and
+/// including) `backfill_until`, which is the max event time of the snapshot.
+/// The results are discarded. The state it produces which isn't in the
+/// snapshot is then added to the snapshot.
+async fn backfill_state(
+    request: &ExecuteRequest,
+    s3_helper: &S3Helper,
+    storage_path: &Path,
+    backfill_until: Timestamp,
+) -> error_stack::Result<(), Error> {
+    tracing::info!("Backfilling snapshot state up to {backfill_until:?}");
+    let backfill_dir = tempfile::Builder::new()
+        .prefix("backfill_")
+        .tempdir()
+        .into_report()
+        .change_context(Error::internal_msg("create backfill dir"))?;
+    let snapshot_prefix = backfill_dir.path().join("snapshot");
+    std::fs::create_dir(&snapshot_prefix)
+        .into_report()
+        .change_context(Error::internal_msg("create backfill dir"))?;
+    let discarded_results = |name: &str| Destination {
+        destination: Some(destination::Destination::ObjectStore(
+            ObjectStoreDestination {
+                output_prefix_uri: format!(
+                    "file:///{}",
+                    backfill_dir.path().join("results").join(name).display()
+                ),
+                file_type: FileType::Csv.into(),
+                output_paths: None,
+            },
+        )),
+    };
+
+    // Inputs after the final result time aren't read, so it limits the
+    // backfill to the inputs included in the snapshot.
+    let backfill_request = ExecuteRequest {
+        plan: request.plan.clone(),
+        tables: request.tables.clone(),
+        destination: Some(discarded_results("result")),
+        limits: request.limits.clone(),
+        compute_snapshot_config: Some(ComputeSnapshotConfig {
+            output_prefix: snapshot_prefix.to_string_lossy().into_owned(),
+            resume_from: None,
+        }),
+        final_result_time: Some(backfill_until),
+        examples: request.examples.clone(),
+        output_destinations: request
+            .output_destinations
+            .keys()
+            .map(|name| (name.clone(), discarded_results(&format!("output_{name}"))))
+            .collect(),
+        ..ExecuteRequest::default()
+    };
+    let storage_dir = tempfile::Builder::new()
+        .prefix(&STORE_PATH_PREFIX)
+        .tempdir()
+        .into_report()
+        .change_context(Error::internal_msg("create snapshot dir"))?;
+
+    let responses = start_execution(
+        backfill_request,
+        s3_helper.clone(),
+        None,
+        Some(storage_dir),
+        None,
+    )
+    .await
+    .change_context(Error::internal_msg("backfill snapshot state"))?;
+    let mut responses = Box::pin(responses);
+    let mut snapshot = None;
+    while let Some(response) = responses.next().await {
+        let response = response.change_context(Error::internal_msg("backfill snapshot state"))?;
+        snapshot = snapshot.or(response.compute_snapshots.into_iter().next());
+    }
+    let snapshot = snapshot.ok_or_else(|| Error::internal_msg("missing backfilled snapshot"))?;
+
+    let store = ComputeStore::try_new_from_path(storage_path)
+        .into_report()
+        .change_context(Error::internal_msg("open snapshot"))?;
+    let backfilled = ComputeStore::try_open_read_only(Path::new(&snapshot.path))
+        .into_report()
+        .change_context(Error::internal_msg("open backfilled snapshot"))?;
+    store
+        .add_missing_state(&backfilled)
+        .into_report()
+        .change_context(Error::internal_msg("add backfilled state"))
+}
+
+/// Start executing the request.
+///
+/// If `storage_dir` is set, the execution resumes from the snapshot in it
+/// (if any) and writes a new snapshot to it. Inputs before `min_input_time`
+/// (if any) are not read.
+async fn start_execution(
+    request: ExecuteRequest,
+    s3_helper: S3Helper,
+    bounded_lateness_ns: Option<i64>,
+    storage_dir: Option<TempDir>,
+    min_input_time: Option<NaiveDateTime>,
 ) -> error_stack::Result<impl Stream<Item = error_stack::Result<ExecuteResponse, Error>>, Error> {
     let plan = request.plan.ok_or(Error::MissingField("plan"))?;
 
@@ -53,6 +273,25 @@ pub async fn execute(
         .destination
         .ok_or(Error::MissingField("destination"))?;
 
+    // Each named output is written to its own destination.
+    let output_destinations = request.output_destinations;
+    for output in &plan.outputs {
+        error_stack::ensure!(
+            output_destinations.contains_key(&output.name),
+            Error::InvalidOutputDestinations(format!("missing destination for '{}'", output.name))
+        );
+    }
+    if let Some(name) = output_destinations
+        .keys()
+        .find(|name| !plan.outputs.iter().any(|output| &output.name == *name))
+    {
+        error_stack::bail!(Error::InvalidOutputDestinations(format!(
+            "no output named '{name}'"
+        )))
+    }
+
+    let per_entity_behaviors = per_entity_behaviors(&plan);
+
     let changed_since_time = request.changed_since.unwrap_or(Timestamp {
         seconds: 0,
         nanos: 0,
@@ -64,9 +303,20 @@ pub async fn execute(
     let mut late_bindings = enum_map::enum_map! {
         _ => None
     };
+    // Results before the start time are omitted the same way as results before
+    // the changed since time.
+    let output_since_time = match &request.start_time {
+        Some(start_time)
+            if (start_time.seconds, start_time.nanos)
+                > (changed_since_time.seconds, changed_since_time.nanos) =>
+        {
+            start_time
+        }
+        _ => &changed_since_time,
+    };
     late_bindings[LateBoundValue::ChangedSinceTime] = Some(ScalarValue::timestamp(
-        changed_since_time.seconds,
-        changed_since_time.nanos,
+        output_since_time.seconds,
+        output_since_time.nanos,
         None,
     ));
 
@@ -86,25 +336,26 @@ pub async fn execute(
         .into_report()
         .change_context(Error::internal_msg("create data context"))?;
 
-    // If the snapshot config exists, sparrow should attempt to resume from state,
-    // and store new state. Create a new storage path for the local store to
-    // exist.
-    let storage_dir = if let Some(config) = &request.compute_snapshot_config {
-        let dir = tempfile::Builder::new()
-            .prefix(&STORE_PATH_PREFIX)
-            .tempdir()
+    // The examples are read like the other tables, but aren't referenced by
+    // the plan. They are used by the operation producing the examples.
+    let examples = if let Some(examples) = request.examples {
+        let name = examples
+            .config
+            .as_ref()
+            .ok_or(Error::MissingField("examples.config"))?
+            .name
+            .clone();
+        error_stack::ensure!(
+            data_context.table_id(&name).is_none(),
+            Error::InvalidExamples(format!("table '{name}' is also used by the query"))
+        );
+        let table_id = data_context
+            .add_table(examples)
             .into_report()
-            .change_context(Error::internal_msg("create snapshot dir"))?;
-
-        // If a `resume_from` path is specified, download the existing state from s3.
-        if config.resume_from.is_some() {
-            crate::s3::download_snapshot(&s3_helper, dir.path(), config)
-                .await
-                .into_report()
-                .change_context(Error::internal_msg("download snapshot"))?;
-        };
-
-        Some(dir)
+            .change_context(Error::internal_msg("add examples table"))?;
+        Some(table_id)
+    } else if per_entity_behaviors.contains(&PerEntityBehavior::Examples) {
+        error_stack::bail!(Error::MissingField("examples"))
     } else {
         None
     };
@@ -112,45 +363,33 @@ pub async fn execute(
     let plan_hash = hash_compute_plan_proto(&plan);
 
     let compute_store = if let Some(dir) = &storage_dir {
-        let max_allowed_max_event_time = match plan.per_entity_behavior() {
-            PerEntityBehavior::Unspecified => {
-                error_stack::bail!(Error::UnspecifiedPerEntityBehavior)
-            }
-            PerEntityBehavior::All => {
-                // For all results, we need a snapshot with a maximum event time
-                // no larger than the changed_since time, since we need to replay
-                // (and recompute the results for) all events after the changed
-                // since time.
-                changed_since_time.clone()
-            }
-            PerEntityBehavior::Final => {
-                // This is a bit confusing. Right now, the manager is responsible for
-                // choosing a valid snapshot to resume from. Thus, the work of choosing
-                // a valid snapshot with regard to any new input data is already done.
-                // However, the engine does a sanity check here to ensure the snapshot's
-                // max event time is before the allowed max event time the engine supports,
-                // dependent on the entity behavior of the query.
-                //
-                // For FinalResults, the snapshot can have a max event time of "any time",
-                // so we set this to Timestamp::MAX. This is because we just need to be able
-                // to produce results once after all new events have been processed, and
-                // we can already assume a valid snapshot is chosen and the correct input
-                // files are being processed.
-                Timestamp {
-                    seconds: i64::MAX,
-                    nanos: i32::MAX,
-                }
-            }
-            PerEntityBehavior::FinalAtTime => {
-                output_at_time.as_ref().expect("final at time").clone()
-            }
-        };
+        // The snapshot must be usable by the result of the query and each of
+        // the named outputs, so use the earliest allowed time.
+        let max_allowed_max_event_time = per_entity_behaviors
+            .iter()
+            .map(|per_entity_behavior| {
+                max_allowed_max_event_time_for(
+                    *per_entity_behavior,
+                    &changed_since_time,
+                    output_at_time.as_ref(),
+                )
+            })
+            .collect::<error_stack::Result<Vec<_>, Error>>()?
+            .into_iter()
+            .min_by_key(|time| (time.seconds, time.nanos))
+            .expect("at least one behavior");
 
-        Some(
+        let compute_store =
             ComputeStore::try_new(dir.path(), &max_allowed_max_event_time, &plan_hash)
                 .into_report()
-                .change_context(Error::internal_msg("loading compute store"))?,
-        )
+                .change_context(Error::internal_msg("loading compute store"))?;
+
+        // Record the plan, so later plans can determine which state to reuse.
+        compute_store
+            .put_plan(&plan)
+            .into_report()
+            .change_context(Error::internal_msg("storing plan"))?;
+        Some(compute_store)
     } else {
         None
     };
@@ -174,6 +413,19 @@ pub async fn execute(
         .get_or_create_group_id(&plan.primary_grouping, &primary_grouping_key_type)
         .into_report()
         .change_context(Error::internal_msg("get primary grouping ID"))?;
+    if let Some(examples) = examples {
+        let table_info = data_context
+            .table_info(examples)
+            .ok_or_else(|| Error::internal_msg("missing examples table"))?;
+        error_stack::ensure!(
+            table_info.group_id() == primary_group_id,
+            Error::InvalidExamples(format!(
+                "table '{}' is not grouped by '{}'",
+                table_info.name(),
+                plan.primary_grouping
+            ))
+        );
+    }
 
     key_hash_inverse
         .add_from_data_context(&data_context, primary_group_id, s3_helper.clone())
@@ -182,6 +434,45 @@ pub async fn execute(
         .change_context(Error::internal_msg("initialize key hash inverse"))?;
     let key_hash_inverse = Arc::new(ThreadSafeKeyHashInverse::new(key_hash_inverse));
 
+    // Named outputs with a different grouping need their own key hash inverse.
+    let mut output_key_hash_inverses = BTreeMap::new();
+    for output in &plan.outputs {
+        if output.grouping == plan.primary_grouping
+            || output_key_hash_inverses.contains_key(&output.grouping)
+        {
+            continue;
+        }
+
+        let grouping_key_type = output
+            .grouping_key_type
+            .as_ref()
+            .ok_or(Error::MissingField("grouping_key_type"))?;
+        let grouping_key_type = arrow::datatypes::DataType::try_from(grouping_key_type)
+            .into_report()
+            .change_context(Error::internal_msg("decode grouping_key_type"))?;
+        let mut output_key_hash_inverse = KeyHashInverse::from_data_type(grouping_key_type.clone());
+        if let Some(compute_store) = &compute_store {
+            if let Ok(restored) =
+                KeyHashInverse::restore_grouping_from(compute_store, &output.grouping)
+            {
+                output_key_hash_inverse = restored
+            }
+        }
+        let group_id = data_context
+            .get_or_create_group_id(&output.grouping, &grouping_key_type)
+            .into_report()
+            .change_context(Error::internal_msg("get output grouping ID"))?;
+        output_key_hash_inverse
+            .add_from_data_context(&data_context, group_id, s3_helper.clone())
+            .await
+            .into_report()
+            .change_context(Error::internal_msg("initialize output key hash inverse"))?;
+        output_key_hash_inverses.insert(
+            output.grouping.clone(),
+            Arc::new(ThreadSafeKeyHashInverse::new(output_key_hash_inverse)),
+        );
+    }
+
     // Channel for the output stats.
     let (progress_updates_tx, progress_updates_rx) =
         tokio::sync::mpsc::channel(29.max(plan.operations.len() * 2));
@@ -195,9 +486,12 @@ pub async fn execute(
         None
     };
 
+    let limits = request.limits.unwrap_or_default();
+
     // We use the plan hash for validating the snapshot is as expected.
     // Rather than accepting it as input (which could lead to us getting
     // a correct hash but an incorrect plan) we re-hash the plan.
+    let operation_stats = request.operation_progress.then(|| PlanStats::new(&plan));
     let context = OperationContext {
         plan,
         plan_hash,
@@ -205,10 +499,18 @@ pub async fn execute(
         data_context,
         compute_store,
         key_hash_inverse,
+        output_key_hash_inverses,
         max_event_in_snapshot: None,
+        min_input_time,
+        examples,
+        example_labels: vec![],
+        partition: (0, 1),
+        slice_entities: None,
         progress_updates_tx,
         output_at_time: output_datetime,
         bounded_lateness_ns,
+        memory_budget: MemoryBudget::new(limits.max_memory_bytes),
+        operation_stats,
     };
 
     // Start executing the query. We pass the response channel to the
@@ -216,7 +518,7 @@ pub async fn execute(
     tracing::debug!("Starting query execution");
 
     let runtime_options = RuntimeOptions {
-        limits: request.limits.unwrap_or_default(),
+        limits,
         flight_record_path: None,
     };
 
@@ -226,13 +528,148 @@ pub async fn execute(
         &runtime_options,
         progress_updates_rx,
         destination,
+        output_destinations,
     )
     .await
     .change_context(Error::internal_msg("spawn compute executor"))?;
 
-    Ok(compute_executor.execute_with_progress(
-        s3_helper,
-        storage_dir,
-        request.compute_snapshot_config,
-    ))
+    // Streaming executions (with a bounded lateness) are materializations,
+    // which are counted as active until the response stream is dropped.
+    let active_materialization = bounded_lateness_ns.map(|_| ActiveMaterialization::start());
+
+    Ok(compute_executor
+        .execute_with_progress(s3_helper, storage_dir, request.compute_snapshot_config)
+        .map(move |response| {
+            let _ = &active_materialization;
+            response
+        }))
+}
+
+/// Return the maximum event time a snapshot may contain to be used for
+/// producing results with the given behavior.
+fn max_allowed_max_event_time_for(
+    per_entity_behavior: PerEntityBehavior,
+    changed_since_time: &Timestamp,
+    output_at_time: Option<&Timestamp>,
+) -> error_stack::Result<Timestamp, Error> {
+    let max_allowed_max_event_time = match per_entity_behavior {
+        PerEntityBehavior::Unspecified => {
+            error_stack::bail!(Error::UnspecifiedPerEntityBehavior)
+        }
+        PerEntityBehavior::All => {
+            // For all results, we need a snapshot with a maximum event time
+            // no larger than the changed_since time, since we need to replay
+            // (and recompute the results for) all events after the changed
+            // since time.
+            changed_since_time.clone()
+        }
+        PerEntityBehavior::Final => {
+            // This is a bit confusing. Right now, the manager is responsible for
+            // choosing a valid snapshot to resume from. Thus, the work of choosing
+            // a valid snapshot with regard to any new input data is already done.
+            // However, the engine does a sanity check here to ensure the snapshot's
+            // max event time is before the allowed max event time the engine supports,
+            // dependent on the entity behavior of the query.
+            //
+            // For FinalResults, the snapshot can have a max event time of "any time",
+            // so we set this to Timestamp::MAX. This is because we just need to be able
+            // to produce results once after all new events have been processed, and
+            // we can already assume a valid snapshot is chosen and the correct input
+            // files are being processed.
+            Timestamp {
+                seconds: i64::MAX,
+                nanos: i32::MAX,
+            }
+        }
+        PerEntityBehavior::FinalAtTime => output_at_time
+            .ok_or(Error::MissingField("final_result_time"))?
+            .clone(),
+        PerEntityBehavior::Examples => {
+            // Examples may be at any time, so we need to replay all events
+            // which may precede them.
+            Timestamp {
+                seconds: 0,
+                nanos: 0,
+            }
+        }
+    };
+    Ok(max_allowed_max_event_time)
+}
+
+#[cfg(test)]
+mod tests {
+    use sparrow_api::kaskada::v1alpha::PlanOutput;
+
+    use super::*;
+
+    fn request(max_lookback_ns: Option<i64>, snapshot: bool) -> ExecuteRequest {
+        ExecuteRequest {
+            plan: Some(ComputePlan {
+                max_lookback_ns,
+                ..ComputePlan::default()
+            }),
+            start_time: Some(Timestamp {
+                seconds: 100,
+                nanos: 0,
+            }),
+            compute_snapshot_config: snapshot.then(|| ComputeSnapshotConfig {
+                output_prefix: "snapshot".to_owned(),
+                resume_from: None,
+            }),
+            ..ExecuteRequest::default()
+        }
+    }
+
+    fn min_input_time_for(
+        request: &ExecuteRequest,
+    ) -> error_stack::Result<Option<NaiveDateTime>, Error> {
+        min_input_time(request, request.plan.as_ref().unwrap())
+    }
+
+    #[test]
+    fn test_check_final_result_time() {
+        let plan = ComputePlan {
+            per_entity_behavior: PerEntityBehavior::All as i32,
+            outputs: vec![PlanOutput {
+                name: "at_time".to_owned(),
+                per_entity_behavior: PerEntityBehavior::FinalAtTime as i32,
+                ..PlanOutput::default()
+            }],
+            ..ComputePlan::default()
+        };
+        let mut request = ExecuteRequest::default();
+
+        // A named output at the final time requires the time.
+        let error = check_final_result_time(&request, &plan).unwrap_err();
+        assert!(matches!(
+            error.current_context(),
+            Error::MissingField("final_result_time")
+        ));
+
+        request.final_result_time = Some(Timestamp {
+            seconds: 100,
+            nanos: 0,
+        });
+        check_final_result_time(&request, &plan).unwrap();
+    }
+
+    #[test]
+    fn test_min_input_time() {
+        let min_input_time = min_input_time_for(&request(Some(10_000_000_000), false)).unwrap();
+        assert_eq!(min_input_time, NaiveDateTime::from_timestamp_opt(90, 0),);
+    }
+
+    #[test]
+    fn test_min_input_time_unbounded_lookback_allows_snapshots() {
+        assert_eq!(min_input_time_for(&request(None, true)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_min_input_time_rejects_snapshots() {
+        let error = min_input_time_for(&request(Some(10_000_000_000), true)).unwrap_err();
+        assert!(matches!(
+            error.current_context(),
+            Error::InvalidStartTime(_)
+        ));
+    }
 }
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fs::File;
 use std::sync::Arc;
 
@@ -8,7 +9,9 @@ use futures::{FutureExt, Stream, TryFutureExt};
 use prost_wkt_types::Timestamp;
 use sparrow_api::kaskada::v1alpha::ComputeSnapshot;
 use sparrow_api::kaskada::v1alpha::ComputeSnapshotConfig;
-use sparrow_api::kaskada::v1alpha::{self, ExecuteResponse, LateBoundValue, PlanHash};
+use sparrow_api::kaskada::v1alpha::{
+    self, operation_plan, ExecuteResponse, LateBoundValue, PerEntityBehavior, PlanHash,
+};
 use sparrow_core::ScalarValue;
 use sparrow_instructions::ComputeStore;
 use sparrow_qfr::io::writer::FlightRecordWriter;
@@ -19,20 +22,26 @@ use tokio_stream::wrappers::UnboundedReceiverStream;
 use tokio_stream::StreamExt;
 use tracing::{error, info, info_span};
 
-use crate::execute::operation::{OperationContext, OperationExecutor};
+use crate::execute::operation::{example_label_fields, OperationContext, OperationExecutor};
+use crate::execute::operation_stats::PlanStats;
+use crate::execute::output::ExampleLabels;
+use crate::execute::partition;
 use crate::execute::progress_reporter::{progress_stream, ProgressUpdate};
 use crate::execute::spawner::ComputeTaskSpawner;
 use crate::execute::Error;
 use crate::execute::Error::Internal;
+use crate::metrics;
 use crate::s3::S3Helper;
 use crate::util::JoinTask;
-use crate::{Batch, RuntimeOptions};
+use crate::{BatchSender, RuntimeOptions};
 
 pub(crate) struct ComputeExecutor {
     compute_store: Option<Arc<ComputeStore>>,
     plan_hash: PlanHash,
     futures: FuturesUnordered<JoinTask<()>>,
     progress_updates_rx: tokio::sync::mpsc::Receiver<ProgressUpdate>,
+    /// Statistics for each operation, if they should be reported.
+    operation_stats: Option<PlanStats>,
     /// Receiver for the max event timestamp seen by Scan Operations.
     max_event_time_rx: tokio::sync::mpsc::UnboundedReceiver<Timestamp>,
 }
@@ -54,41 +63,138 @@ impl ComputeExecutor {
         runtime_options: &RuntimeOptions,
         progress_updates_rx: tokio::sync::mpsc::Receiver<ProgressUpdate>,
         destination: v1alpha::Destination,
+        mut output_destinations: HashMap<String, v1alpha::Destination>,
     ) -> error_stack::Result<Self, Error> {
         let mut spawner = ComputeTaskSpawner::new();
 
-        // Create the list of consumers for each operation.
+        let num_partitions = num_partitions(&context, runtime_options);
+        let num_operations = context.plan.operations.len();
+
+        // Create the list of consumers for each operation in each partition.
         //
-        // `consumers[operation_index]` contains the channels that consume (receive)
-        // the output of `operation_index`. So, the general logic below for creating
-        // operations is:
+        // `consumers[partition][operation_index]` contains the channels that consume
+        // (receive) the output of `operation_index` in `partition`. So, the general
+        // logic below for creating operations is:
         // 1. Process operations in reverse order (so that all consumers are
         //    registered before creating the operation).
         // 2. Add each of the consumers for the operation being created
-        //    (`consumers[index]`) as consumers of the operation being created.
+        //    (`consumers[partition][index]`) as consumers of the operation being
+        //    created.
         // 3. For each input of an operation, create a channel and add the `sender`
         //    to the consumers of the operation that produces the input, and use
         //    the `receiver` to receive input from that producer.
-        let mut consumers: Vec<Vec<tokio::sync::mpsc::Sender<Batch>>> =
-            vec![vec![]; context.plan.operations.len()];
-
-        // Add a consumer for the output channel.
-        let (output_tx, output_rx) = tokio::sync::mpsc::channel(13);
-        consumers[context.plan.operations.len() - 1].push(output_tx.clone());
-
-        spawner.spawn(
-            "output".to_owned(),
-            info_span!("Output Writer", ?destination),
-            crate::execute::output::write(
-                &context,
-                runtime_options.limits.clone(),
-                futures::StreamExt::boxed(tokio_stream::wrappers::ReceiverStream::new(output_rx)),
-                context.progress_updates_tx.clone(),
+        //
+        // When executing a single partition, this is the same as connecting the
+        // operations directly. See `partition` for how rows are routed between
+        // partitions when executing multiple partitions.
+        let mut consumers: Vec<Vec<Vec<BatchSender>>> =
+            vec![vec![vec![]; num_operations]; num_partitions];
+
+        // The labels of the examples are added to the outputs produced at them.
+        // Each of these outputs receives the labels from the examples
+        // operation of each partition.
+        let example_label_fields = match context.examples {
+            Some(examples) => {
+                let table_info = context
+                    .data_context
+                    .table_info(examples)
+                    .ok_or(Internal("missing examples table"))?;
+                Some(example_label_fields(table_info))
+            }
+            None => None,
+        };
+        let mut example_labels: Vec<Vec<BatchSender>> = vec![vec![]; num_partitions];
+        let mut labels_for = |per_entity_behavior: PerEntityBehavior| {
+            let fields = match (per_entity_behavior, &example_label_fields) {
+                (PerEntityBehavior::Examples, Some(fields)) => fields.clone(),
+                _ => return None,
+            };
+            let receivers = example_labels
+                .iter_mut()
+                .map(|senders| {
+                    let (sender, receiver) = tokio::sync::mpsc::channel(7);
+                    senders.push(sender);
+                    receiver
+                })
+                .collect();
+            Some(ExampleLabels::new(fields, receivers))
+        };
+
+        // The outputs to write, as the operation producing each output, the
+        // name of the output, the key hash inverse for its grouping, the
+        // labels to add to it and the destination to write it to. The result
+        // of the query is produced by the last operation.
+        let mut outputs = Vec::with_capacity(context.plan.outputs.len() + 1);
+        for output in &context.plan.outputs {
+            let key_hash_inverse = context
+                .key_hash_inverse_for(&output.grouping)
+                .ok_or(Internal("missing key hash inverse for output"))?
+                .clone();
+            let destination = output_destinations
+                .remove(&output.name)
+                .ok_or(Internal("missing destination for output"))?;
+            outputs.push((
+                output.operation as usize,
+                Some(output.name.clone()),
+                key_hash_inverse,
+                labels_for(output.per_entity_behavior()),
                 destination,
-            )
-            .change_context(Internal("error writing output"))?
-            .map_err(|e| e.change_context(Internal("error writing output"))),
-        );
+            ));
+        }
+        outputs.push((
+            num_operations - 1,
+            None,
+            context.key_hash_inverse.clone(),
+            labels_for(context.plan.per_entity_behavior()),
+            destination,
+        ));
+
+        context.example_labels = example_labels;
+
+        // Add a consumer for each output channel.
+        let mut output_tx = None;
+        for (operation_index, output, key_hash_inverse, example_labels, destination) in outputs {
+            let (tx, rx) = tokio::sync::mpsc::channel(13);
+            if num_partitions == 1 {
+                consumers[0][operation_index].push(tx.clone());
+            } else {
+                // Merge the output of the operation in each partition.
+                let inputs = consumers
+                    .iter_mut()
+                    .map(|consumers| {
+                        let (sender, receiver) = tokio::sync::mpsc::channel(7);
+                        consumers[operation_index].push(sender);
+                        receiver
+                    })
+                    .collect();
+                spawner.spawn(
+                    format!("merge_partitions[output={operation_index}]"),
+                    info_span!("Merge Partitions", num_partitions, operation_index),
+                    partition::merge_partitions(inputs, vec![tx.clone()]),
+                );
+            }
+
+            spawner.spawn(
+                format!("output[op={operation_index}]"),
+                info_span!("Output Writer", ?output, ?destination),
+                crate::execute::output::write(
+                    &context,
+                    operation_index,
+                    key_hash_inverse,
+                    example_labels,
+                    output,
+                    runtime_options.limits.clone(),
+                    futures::StreamExt::boxed(tokio_stream::wrappers::ReceiverStream::new(rx)),
+                    context.progress_updates_tx.clone(),
+                    destination,
+                )
+                .change_context(Internal("error writing output"))?
+                .map_err(|e| e.change_context(Internal("error writing output"))),
+            );
+            output_tx = Some(tx);
+        }
+        // The result of the query is the last output.
+        let output_tx = output_tx.expect("result of the query");
 
         // Channel for the max event time seen by a Scan Operation
         //
@@ -109,43 +215,116 @@ impl ComputeExecutor {
                 .operator()
                 .into_report()
                 .change_context(Internal("missing operator"))?;
-            let inputs: Vec<_> = operator
-                .input_ops_iter()
-                .flat_map(|input_index| {
-                    let input_index = input_index as usize;
-                    debug_assert!(input_index < index);
-
-                    // Create a channel and add it to the list of consumers for earlier
-                    // operations.
-                    //
-                    // We'd like to have a `single-publisher, multiple-consumer` channel,
-                    // but couldn't find one. So instead, we create separate channels for
-                    // each consumer and publish to each of them.
-                    let (sender, receiver) = tokio::sync::mpsc::channel(7);
-                    consumers[input_index].push(sender);
-                    Some(receiver)
-                })
-                .collect();
             let operation_label = operator.label();
 
-            let mut operation = OperationExecutor::new(op);
-            for consumer in consumers[index].drain(0..) {
-                operation.add_consumer(consumer);
-            }
+            // Scans are executed once, with the rows routed to the partition
+            // owning each entity.
+            let is_scan = matches!(operator, operation_plan::Operator::Scan(_));
+            let num_instances = if is_scan { 1 } else { num_partitions };
+
+            // The senders for the consumers of the operation executed in each
+            // partition (or the single scan).
+            let mut operation_consumers: Vec<Vec<BatchSender>> = if num_partitions == 1 {
+                vec![std::mem::take(&mut consumers[0][index])]
+            } else if is_scan {
+                let outputs = consumers
+                    .iter_mut()
+                    .map(|consumers| std::mem::take(&mut consumers[index]))
+                    .collect();
+                let (sender, receiver) = tokio::sync::mpsc::channel(7);
+                spawner.spawn(
+                    format!("split_partitions[op={index}]"),
+                    info_span!("Split Partitions", ?index, operation_label),
+                    partition::split_partitions(receiver, outputs),
+                );
+                vec![vec![sender]]
+            } else if operator.changes_key() {
+                // The re-keyed rows produced in each (source) partition are split
+                // amongst the destination partitions, and each destination merges
+                // the rows from all the source partitions.
+                let mut exchange: Vec<Vec<BatchSender>> = vec![vec![]; num_partitions];
+                for (destination, consumers) in consumers.iter_mut().enumerate() {
+                    let inputs = exchange
+                        .iter_mut()
+                        .map(|source| {
+                            let (sender, receiver) = tokio::sync::mpsc::channel(7);
+                            source.push(sender);
+                            receiver
+                        })
+                        .collect();
+                    spawner.spawn(
+                        format!("merge_partitions[op={index},partition={destination}]"),
+                        info_span!("Merge Partitions", ?index, destination),
+                        partition::merge_partitions(inputs, std::mem::take(&mut consumers[index])),
+                    );
+                }
 
-            spawner.spawn(
-                format!("{operation_label}[op={index}]"),
-                info_span!("Operation", ?index, operation_label),
-                operation
-                    .execute(
-                        index,
-                        &mut context,
-                        inputs,
-                        max_event_time_tx.clone(),
-                        late_bindings,
-                    )
-                    .await?,
-            );
+                exchange
+                    .into_iter()
+                    .enumerate()
+                    .map(|(source, outputs)| {
+                        let outputs = outputs.into_iter().map(|output| vec![output]).collect();
+                        let (sender, receiver) = tokio::sync::mpsc::channel(7);
+                        spawner.spawn(
+                            format!("split_partitions[op={index},partition={source}]"),
+                            info_span!("Split Partitions", ?index, source),
+                            partition::split_partitions(receiver, outputs),
+                        );
+                        vec![sender]
+                    })
+                    .collect()
+            } else {
+                consumers
+                    .iter_mut()
+                    .map(|consumers| std::mem::take(&mut consumers[index]))
+                    .collect()
+            };
+            debug_assert_eq!(operation_consumers.len(), num_instances);
+
+            for (partition, partition_consumers) in operation_consumers.iter_mut().enumerate() {
+                let inputs: Vec<_> = operator
+                    .input_ops_iter()
+                    .flat_map(|input_index| {
+                        let input_index = input_index as usize;
+                        debug_assert!(input_index < index);
+
+                        // Create a channel and add it to the list of consumers for earlier
+                        // operations.
+                        //
+                        // We'd like to have a `single-publisher, multiple-consumer` channel,
+                        // but couldn't find one. So instead, we create separate channels for
+                        // each consumer and publish to each of them.
+                        let (sender, receiver) = tokio::sync::mpsc::channel(7);
+                        consumers[partition][input_index].push(sender);
+                        Some(receiver)
+                    })
+                    .collect();
+
+                let mut operation = OperationExecutor::new(op.clone());
+                for consumer in partition_consumers.drain(0..) {
+                    operation.add_consumer(consumer);
+                }
+
+                let name = if num_instances == 1 {
+                    format!("{operation_label}[op={index}]")
+                } else {
+                    format!("{operation_label}[op={index},partition={partition}]")
+                };
+                context.partition = (partition, num_instances);
+                spawner.spawn(
+                    name,
+                    info_span!("Operation", ?index, ?partition, operation_label),
+                    operation
+                        .execute(
+                            index,
+                            &mut context,
+                            inputs,
+                            max_event_time_tx.clone(),
+                            late_bindings,
+                        )
+                        .await?,
+                );
+            }
         }
 
         // Spawn a task to pre-fetch the data files.
@@ -165,6 +344,7 @@ impl ComputeExecutor {
             plan_hash: context.plan_hash,
             futures: spawner.finish(),
             progress_updates_rx,
+            operation_stats: context.operation_stats,
             max_event_time_rx,
         })
     }
@@ -184,6 +364,7 @@ impl ComputeExecutor {
             plan_hash,
             futures,
             progress_updates_rx,
+            operation_stats,
             max_event_time_rx,
         } = self;
 
@@ -196,10 +377,12 @@ impl ComputeExecutor {
         let final_result_fut = async move {
             // Waits for all operations to complete
             let final_update: Result<ProgressUpdate, ProgressUpdate> = {
-                let compute_result = join(futures, max_event_time_rx, plan_hash)
-                    .await
-                    .change_context(Error::Internal("failed to join compute threads"))
-                    .map_err(|e| ProgressUpdate::ExecutionFailed { error: e });
+                let compute_result =
+                    join(futures, max_event_time_rx, plan_hash)
+                        .await
+                        .map_err(|e| ProgressUpdate::ExecutionFailed {
+                            error: join_error(e),
+                        });
 
                 // Return early if join fails
                 if let Err(compute_result) = compute_result {
@@ -256,7 +439,20 @@ impl ComputeExecutor {
         // Biases to the progress update stream to ensure all updates are received before completion
         let progress_updates = select_biased(progress_updates_rx, compute_stream);
 
-        progress_stream(progress_updates)
+        progress_stream(progress_updates, operation_stats)
+    }
+}
+
+/// Determine the number of entity-key partitions to execute the query in.
+fn num_partitions(context: &OperationContext, runtime_options: &RuntimeOptions) -> usize {
+    let requested = runtime_options.limits.partitions.max(1) as usize;
+    if requested > 1 && context.compute_store.is_some() {
+        // The state of each operation is stored under a single key, so it
+        // can't (currently) be split across partitions.
+        info!("Executing a single partition, since snapshots are enabled");
+        1
+    } else {
+        requested
     }
 }
 
@@ -290,16 +486,29 @@ async fn upload_compute_snapshots(
     if let Some(snapshot_config) = compute_snapshot_config {
         let storage_dir = storage_dir.ok_or(Error::Internal("missing storage dir"))?;
 
+        let upload_timer = metrics::SNAPSHOT_UPLOAD_DURATION.start_timer();
         let snapshot_metadata =
             crate::s3::upload_snapshot(s3_helper, storage_dir, snapshot_config, compute_result)
                 .await
                 .change_context(Error::Internal("uploading snapshot"))?;
+        upload_timer.observe_duration();
         snapshots.push(snapshot_metadata);
     }
 
     Ok(snapshots)
 }
 
+/// Add context to an error from joining the compute tasks.
+///
+/// Exceeding the memory budget is reported directly, since it is actionable
+/// by the user.
+fn join_error(error: error_stack::Report<Error>) -> error_stack::Report<Error> {
+    match error.current_context() {
+        Error::MemoryBudgetExceeded { .. } => error,
+        _ => error.change_context(Error::Internal("failed to join compute threads")),
+    }
+}
+
 async fn join(
     mut futures: FuturesUnordered<JoinTask<()>>,
     max_event_time_rx: tokio::sync::mpsc::UnboundedReceiver<Timestamp>,
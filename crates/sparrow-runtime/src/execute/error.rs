@@ -6,6 +6,12 @@ pub enum Error {
     MissingField(&'static str),
     #[display(fmt = "invalid output path '{_0}'")]
     InvalidOutputPath(String),
+    #[display(fmt = "invalid examples: {_0}")]
+    InvalidExamples(String),
+    #[display(fmt = "invalid output destinations: {_0}")]
+    InvalidOutputDestinations(String),
+    #[display(fmt = "invalid start time: {_0}")]
+    InvalidStartTime(String),
     #[display(fmt = "unspecified per-entity behavior")]
     UnspecifiedPerEntityBehavior,
     #[display(fmt = "unspecified output format")]
@@ -26,6 +32,14 @@ pub enum Error {
     FeatureNotEnabled { feature: &'static str },
     #[display(fmt = "output '{output}' is not supported")]
     UnsupportedOutput { output: &'static str },
+    #[display(
+        fmt = "operation {operation} exceeded the memory budget of {limit} bytes (needed {requested} bytes)"
+    )]
+    MemoryBudgetExceeded {
+        operation: String,
+        requested: usize,
+        limit: usize,
+    },
 }
 
 macro_rules! invalid_operation {
@@ -58,7 +72,12 @@ impl error_stack::Context for Error {}
 impl ErrorCode for Error {
     fn error_code(&self) -> tonic::Code {
         match self {
-            Error::MissingField(_) | Error::InvalidOutputPath(_) => tonic::Code::InvalidArgument,
+            Error::MissingField(_)
+            | Error::InvalidOutputPath(_)
+            | Error::InvalidExamples(_)
+            | Error::InvalidOutputDestinations(_)
+            | Error::InvalidStartTime(_) => tonic::Code::InvalidArgument,
+            Error::MemoryBudgetExceeded { .. } => tonic::Code::ResourceExhausted,
             _ => tonic::Code::Internal,
         }
     }
@@ -50,6 +50,25 @@ impl KeyHashInverse {
         Ok(())
     }
 
+    /// Restores the KeyHashInverse for a non-primary grouping from the
+    /// compute store.
+    pub fn restore_grouping_from(store: &ComputeStore, grouping: &str) -> anyhow::Result<Self> {
+        store
+            .get(&StoreKey::new_grouping_key_hash_inverse(grouping))?
+            .with_context(|| format!("unable to get key hash inverse for '{grouping}' from store"))
+    }
+
+    /// Stores the KeyHashInverse for a non-primary grouping to the compute
+    /// store.
+    pub fn store_grouping_to(
+        &self,
+        compute_store: &ComputeStore,
+        grouping: &str,
+    ) -> anyhow::Result<()> {
+        compute_store.put(&StoreKey::new_grouping_key_hash_inverse(grouping), &self)?;
+        Ok(())
+    }
+
     pub fn key_type(&self) -> &DataType {
         self.key.data_type()
     }
@@ -105,7 +124,7 @@ impl KeyHashInverse {
     /// values are aligned to map from a key to a hash per index. The
     /// current implementation eagerly adds the keys and hashes to the
     /// inverse but can be optimized to perform the addition lazily.
-    fn add(&mut self, keys: ArrayRef, key_hashes: &UInt64Array) -> anyhow::Result<()> {
+    pub(crate) fn add(&mut self, keys: ArrayRef, key_hashes: &UInt64Array) -> anyhow::Result<()> {
         // Since the keys map to the key hashes directly, both arrays need to be the
         // same length
         anyhow::ensure!(keys.len() == key_hashes.len());
@@ -161,6 +180,25 @@ impl KeyHashInverse {
         let result = arrow::compute::take(&self.key, &key_hash_indices, None)?;
         Ok(result)
     }
+
+    /// Returns the key hash of the entity whose key displays as `key`.
+    ///
+    /// This performs a linear scan over the keys, and is intended for
+    /// debugging tools rather than query execution.
+    pub fn find_key_hash(&self, key: &str) -> anyhow::Result<Option<u64>> {
+        for (key_hash, index) in self.key_hash_to_indices.iter() {
+            let value = arrow::util::display::array_value_to_string(&self.key, *index)?;
+            if value == key {
+                return Ok(Some(*key_hash));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the number of entities in the inverse.
+    pub fn len(&self) -> usize {
+        self.key_hash_to_indices.len()
+    }
 }
 
 /// A thread-safe wrapper around the key hash inverse implemented with tokio
@@ -239,6 +277,19 @@ impl ThreadSafeKeyHashInverse {
         let read = self.key_map.read().await;
         read.store_to(compute_store)
     }
+
+    /// Stores the KeyHashInverse for a non-primary grouping to the compute
+    /// store.
+    ///
+    /// This method is thread-safe and acquires the read-lock.
+    pub async fn store_grouping_to(
+        &self,
+        compute_store: &ComputeStore,
+        grouping: &str,
+    ) -> anyhow::Result<()> {
+        let read = self.key_map.read().await;
+        read.store_grouping_to(compute_store, grouping)
+    }
 }
 
 /// Return the file at a given path.
@@ -285,6 +336,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_find_key_hash() {
+        let keys = Arc::new(Int32Array::from(vec![100, 200]));
+        let key_hashes = UInt64Array::from(vec![1, 2]);
+
+        let mut key_hash = KeyHashInverse::from_data_type(DataType::Int32);
+        key_hash.add(keys, &key_hashes).unwrap();
+
+        assert_eq!(key_hash.find_key_hash("200").unwrap(), Some(2));
+        assert_eq!(key_hash.find_key_hash("300").unwrap(), None);
+    }
+
     #[test]
     fn test_has_new_keys_no_new_keys() {
         let keys = Arc::new(Int32Array::from(vec![100, 200]));
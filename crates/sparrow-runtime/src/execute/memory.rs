@@ -0,0 +1,340 @@
+//! Tracking of the memory used by operations which buffer rows.
+//!
+//! Most operations stream batches through, but `shift_to` and `shift_until`
+//! need to buffer rows until they may be emitted. With skewed data these
+//! buffers can grow without bound. Each such operation holds a
+//! [MemoryReservation] from the query-wide [MemoryBudget], and resizes it as
+//! the buffered rows change. When a reservation can't grow, the operation
+//! spills the buffered rows to disk, and reports [MemoryBudgetExceeded] if
+//! even that doesn't bring it within the budget.
+//!
+//! Operations such as `lookup_response` (and the sorting within a batch it
+//! uses) only hold the batch being processed, and `spread` only holds the
+//! latest value of each entity, so they don't reserve memory.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayRef};
+use error_stack::{IntoReportCompat, Report, ResultExt};
+
+use crate::execute::operation_stats::OperationStats;
+use crate::execute::Error;
+
+/// The memory budget shared by all operations within a query.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MemoryBudget(Arc<BudgetState>);
+
+#[derive(Debug, Default)]
+struct BudgetState {
+    /// The maximum number of bytes which may be reserved, if limited.
+    limit: Option<usize>,
+    /// The number of bytes currently reserved by all operations.
+    used: AtomicUsize,
+}
+
+impl MemoryBudget {
+    /// Create a memory budget with the given limit.
+    ///
+    /// A limit of `0` indicates the memory used is not limited.
+    pub fn new(limit_bytes: u64) -> Self {
+        let limit = if limit_bytes == 0 {
+            None
+        } else {
+            Some(usize::try_from(limit_bytes).unwrap_or(usize::MAX))
+        };
+        Self(Arc::new(BudgetState {
+            limit,
+            used: AtomicUsize::new(0),
+        }))
+    }
+
+    /// Create an (initially empty) reservation for the given operation.
+    pub fn reservation(
+        &self,
+        operation_index: usize,
+        operation_label: &'static str,
+    ) -> MemoryReservation {
+        MemoryReservation {
+            budget: self.clone(),
+            operation_index,
+            operation_label,
+            size: 0,
+            rows: 0,
+            stats: None,
+        }
+    }
+
+    /// Return the number of bytes currently reserved.
+    #[cfg(test)]
+    pub fn used(&self) -> usize {
+        self.0.used.load(Ordering::Acquire)
+    }
+
+    /// Return `true` if the memory used is limited.
+    pub fn is_limited(&self) -> bool {
+        self.0.limit.is_some()
+    }
+}
+
+/// The memory reserved by a single operation.
+///
+/// The reserved memory is returned to the budget when this is dropped.
+#[derive(Debug)]
+pub(crate) struct MemoryReservation {
+    budget: MemoryBudget,
+    operation_index: usize,
+    operation_label: &'static str,
+    size: usize,
+    /// The number of rows buffered by the operation.
+    rows: usize,
+    /// Statistics to report the reserved size and buffered rows to, if any.
+    stats: Option<Arc<OperationStats>>,
+}
+
+impl MemoryReservation {
+    /// Report the size of this reservation and the rows buffered by the
+    /// operation in the given statistics.
+    pub fn with_stats(mut self, stats: Arc<OperationStats>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Return the number of bytes currently reserved.
+    #[cfg(test)]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Return `true` if the memory used by the owning operation is limited.
+    pub fn is_limited(&self) -> bool {
+        self.budget.is_limited()
+    }
+
+    /// Return the maximum number of bytes which may be reserved, if limited.
+    pub fn limit(&self) -> Option<usize> {
+        self.budget.0.limit
+    }
+
+    /// Resize this reservation to `size` bytes.
+    ///
+    /// Shrinking always succeeds. Growing fails (leaving the reservation
+    /// unchanged) if it would exceed the budget.
+    pub fn try_resize(&mut self, size: usize) -> Result<(), MemoryBudgetExceeded> {
+        let state = &self.budget.0;
+        let current = self.size;
+        let result = state
+            .used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                let used = used - current + size;
+                match state.limit {
+                    Some(limit) if size > current && used > limit => None,
+                    _ => Some(used),
+                }
+            });
+
+        match result {
+            Ok(_) => {
+                self.size = size;
+                if let Some(stats) = &self.stats {
+                    stats.add_buffered(size as i64 - current as i64, 0);
+                }
+                Ok(())
+            }
+            Err(used) => Err(MemoryBudgetExceeded {
+                operation_index: self.operation_index,
+                operation_label: self.operation_label,
+                requested: size,
+                used_by_others: used - current,
+                limit: state.limit.unwrap_or(usize::MAX),
+            }),
+        }
+    }
+
+    /// Release all memory held by this reservation.
+    pub fn free(&mut self) {
+        self.try_resize(0).expect("shrinking always succeeds")
+    }
+
+    /// Record the number of rows currently buffered by the operation.
+    ///
+    /// Rows don't count against the budget, but are reported in the
+    /// statistics of the operation.
+    pub fn set_rows(&mut self, rows: usize) {
+        if let Some(stats) = &self.stats {
+            stats.add_buffered(0, rows as i64 - self.rows as i64);
+        }
+        self.rows = rows;
+    }
+}
+
+impl Drop for MemoryReservation {
+    fn drop(&mut self) {
+        self.free();
+        self.set_rows(0);
+    }
+}
+
+/// Error reported when an operation needs more memory than the budget allows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MemoryBudgetExceeded {
+    pub operation_index: usize,
+    pub operation_label: &'static str,
+    /// The number of bytes the operation requested.
+    pub requested: usize,
+    /// The number of bytes reserved by other operations.
+    pub used_by_others: usize,
+    /// The total memory budget.
+    pub limit: usize,
+}
+
+impl std::fmt::Display for MemoryBudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "operation {} ({}) needs {} bytes, but only {} of the {} byte memory budget is \
+             available",
+            self.operation_index,
+            self.operation_label,
+            self.requested,
+            self.limit.saturating_sub(self.used_by_others),
+            self.limit
+        )
+    }
+}
+
+impl std::error::Error for MemoryBudgetExceeded {}
+
+impl From<MemoryBudgetExceeded> for Error {
+    fn from(value: MemoryBudgetExceeded) -> Self {
+        Error::MemoryBudgetExceeded {
+            operation: format!("{} ({})", value.operation_index, value.operation_label),
+            requested: value.requested,
+            limit: value.limit,
+        }
+    }
+}
+
+/// Convert the result of an operation to a report.
+///
+/// Unlike `change_context(Error::internal())` this preserves
+/// [MemoryBudgetExceeded] errors, so they are reported as such.
+pub(crate) fn into_operation_report<T>(result: anyhow::Result<T>) -> error_stack::Result<T, Error> {
+    match result {
+        Ok(value) => Ok(value),
+        Err(err) => match err.downcast::<MemoryBudgetExceeded>() {
+            Ok(exceeded) => {
+                Err(Report::new(Error::from(exceeded.clone())).attach_printable(exceeded))
+            }
+            Err(err) => Err(err).into_report().change_context(Error::internal()),
+        },
+    }
+}
+
+/// Return the memory used by the given arrays.
+///
+/// Arrays which are slices of larger arrays report the size of the entire
+/// underlying buffers, since slicing doesn't release the remaining memory.
+pub(crate) fn arrays_memory_size<'a>(arrays: impl IntoIterator<Item = &'a ArrayRef>) -> usize {
+    arrays
+        .into_iter()
+        .map(|array| array.get_array_memory_size())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlimited_budget() {
+        let budget = MemoryBudget::new(0);
+        let mut reservation = budget.reservation(0, "shift_to");
+        reservation.try_resize(usize::MAX / 2).unwrap();
+        assert_eq!(budget.used(), usize::MAX / 2);
+        reservation.free();
+        assert_eq!(budget.used(), 0);
+    }
+
+    #[test]
+    fn test_reservations_share_budget() {
+        let budget = MemoryBudget::new(100);
+        let mut first = budget.reservation(1, "shift_to");
+        let mut second = budget.reservation(2, "shift_until");
+
+        first.try_resize(60).unwrap();
+        second.try_resize(40).unwrap();
+        assert_eq!(budget.used(), 100);
+
+        let error = second.try_resize(41).unwrap_err();
+        assert_eq!(
+            error,
+            MemoryBudgetExceeded {
+                operation_index: 2,
+                operation_label: "shift_until",
+                requested: 41,
+                used_by_others: 60,
+                limit: 100,
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "operation 2 (shift_until) needs 41 bytes, but only 40 of the 100 byte memory \
+             budget is available"
+        );
+
+        // The failed resize leaves the reservation unchanged.
+        assert_eq!(second.size(), 40);
+
+        // Shrinking releases memory for other operations.
+        first.try_resize(10).unwrap();
+        second.try_resize(90).unwrap();
+        assert_eq!(budget.used(), 100);
+
+        std::mem::drop(first);
+        assert_eq!(budget.used(), 90);
+        std::mem::drop(second);
+        assert_eq!(budget.used(), 0);
+    }
+
+    #[test]
+    fn test_reservations_report_buffered_deltas() {
+        use sparrow_api::kaskada::v1alpha::operation_plan::{Operator, ShiftToOperation};
+        use sparrow_api::kaskada::v1alpha::{ComputePlan, OperationPlan};
+
+        use crate::execute::operation_stats::PlanStats;
+
+        let plan = ComputePlan {
+            operations: vec![OperationPlan {
+                operator: Some(Operator::ShiftTo(ShiftToOperation::default())),
+                ..OperationPlan::default()
+            }],
+            ..ComputePlan::default()
+        };
+        let stats = PlanStats::new(&plan);
+        let budget = MemoryBudget::new(0);
+
+        // Each partition of the operation holds its own reservation.
+        let mut first = budget
+            .reservation(0, "shift_to")
+            .with_stats(stats.operation(0));
+        let mut second = budget
+            .reservation(0, "shift_to")
+            .with_stats(stats.operation(0));
+        first.try_resize(100).unwrap();
+        first.set_rows(10);
+        second.try_resize(50).unwrap();
+        second.set_rows(4);
+        first.try_resize(30).unwrap();
+        first.set_rows(3);
+
+        let progress = &stats.to_progress()[0];
+        assert_eq!(progress.buffered_bytes, 80);
+        assert_eq!(progress.buffered_rows, 7);
+
+        std::mem::drop(first);
+        let progress = &stats.to_progress()[0];
+        assert_eq!(progress.buffered_bytes, 50);
+        assert_eq!(progress.buffered_rows, 4);
+    }
+}
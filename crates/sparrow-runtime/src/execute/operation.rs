@@ -14,6 +14,7 @@
 //! an output batch corresponding to a subset of the columns
 //! (original and computed).
 
+mod examples;
 mod expression_executor;
 mod final_tick;
 mod input_batch;
@@ -25,7 +26,9 @@ mod select;
 mod shift_to;
 mod shift_until;
 mod single_consumer_helper;
+mod slice_entities;
 mod sorted_key_hash_map;
+mod spill;
 mod spread;
 mod spread_zip;
 #[cfg(test)]
@@ -34,6 +37,7 @@ mod tick;
 mod tick_producer;
 mod with_key;
 
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::sync::Arc;
 
@@ -48,11 +52,14 @@ use sparrow_api::kaskada::v1alpha::{
     operation_plan, ComputePlan, LateBoundValue, OperationPlan, PlanHash,
 };
 use sparrow_compiler::DataContext;
-use sparrow_core::ScalarValue;
+use sparrow_core::{downcast_boolean_array, ScalarValue};
 use sparrow_instructions::ComputeStore;
+use sparrow_plan::TableId;
 use tokio::task::JoinHandle;
 use tracing::Instrument;
 
+pub(crate) use self::examples::example_label_fields;
+use self::examples::ExamplesOperation;
 use self::final_tick::FinalTickOperation;
 use self::input_batch::InputBatch;
 use self::lookup_request::LookupRequestOperation;
@@ -60,14 +67,17 @@ use self::lookup_response::LookupResponseOperation;
 use self::merge::MergeOperation;
 use self::scan::ScanOperation;
 use self::select::SelectOperation;
+use self::slice_entities::SliceEntities;
 use self::tick::TickOperation;
 use self::with_key::WithKeyOperation;
 use crate::data_manager::DataManager;
 use crate::execute::key_hash_inverse::ThreadSafeKeyHashInverse;
+use crate::execute::memory::{MemoryBudget, MemoryReservation};
 use crate::execute::operation::expression_executor::{ExpressionExecutor, InputColumn};
 use crate::execute::operation::shift_until::ShiftUntilOperation;
+use crate::execute::operation_stats::{PlanStats, RecordBusy};
 use crate::execute::Error;
-use crate::Batch;
+use crate::{Batch, BatchSender};
 
 /// Information used while creating operations.
 ///
@@ -86,8 +96,30 @@ pub(crate) struct OperationContext {
     pub compute_store: Option<Arc<ComputeStore>>,
     /// The key hash inverse to produce the output results, if one exists.
     pub key_hash_inverse: Arc<ThreadSafeKeyHashInverse>,
+    /// The key hash inverses for the groupings of named outputs which differ
+    /// from the primary grouping.
+    pub output_key_hash_inverses: BTreeMap<String, Arc<ThreadSafeKeyHashInverse>>,
     /// The max input event time in the restored snapshot, if one exists.
     pub max_event_in_snapshot: Option<NaiveDateTime>,
+    /// The earliest input time needed by the query, if results are only
+    /// needed after a start time.
+    ///
+    /// This is the start time minus the maximum look-back of the plan.
+    pub min_input_time: Option<NaiveDateTime>,
+    /// The table containing the examples to produce results for, if any.
+    pub examples: Option<TableId>,
+    /// The senders for the labels of the examples in each partition.
+    ///
+    /// There is a sender for each output produced at the examples. They are
+    /// taken by the first examples operation created for the partition.
+    pub example_labels: Vec<Vec<BatchSender>>,
+    /// The partition operations are being created for, and the number of
+    /// partitions the query is executed in.
+    pub partition: (usize, usize),
+    /// The entities in the slice, if it is determined by the rows of a table.
+    ///
+    /// Computed by the first scan of a sliced table, and shared by later scans.
+    pub slice_entities: Option<Arc<SliceEntities>>,
     /// Channel for sending progress updates.
     pub progress_updates_tx:
         tokio::sync::mpsc::Sender<crate::execute::progress_reporter::ProgressUpdate>,
@@ -99,12 +131,26 @@ pub(crate) struct OperationContext {
     ///
     /// If not set, defaults to the [BOUNDED_LATENESS_NS] const.
     pub bounded_lateness_ns: Option<i64>,
+    /// The memory budget for operations which buffer rows.
+    pub memory_budget: MemoryBudget,
+    /// Statistics for each operation, if they should be reported.
+    pub operation_stats: Option<PlanStats>,
 }
 
 impl OperationContext {
     pub fn primary_grouping(&self) -> &str {
         &self.plan.primary_grouping
     }
+
+    /// Return the key hash inverse for the given grouping, if one is needed
+    /// to produce output for it.
+    pub fn key_hash_inverse_for(&self, grouping: &str) -> Option<&Arc<ThreadSafeKeyHashInverse>> {
+        if grouping == self.primary_grouping() {
+            Some(&self.key_hash_inverse)
+        } else {
+            self.output_key_hash_inverses.get(grouping)
+        }
+    }
 }
 
 /// Trait representing an input stream of batches for an operation.
@@ -182,6 +228,16 @@ impl OperationExecutor {
 
         let operation_label = operator.label();
 
+        // Scans may have a filter pushed down from a later `select`, which is
+        // applied to the output after computing the expressions.
+        let output_filter = match &operator {
+            operation_plan::Operator::Scan(scan) => scan
+                .filter
+                .as_ref()
+                .map(|filter| filter.input_column as usize),
+            _ => None,
+        };
+
         let mut expression_executor =
             ExpressionExecutor::try_new(operation_label, operation.expressions, late_bindings)
                 .into_report()
@@ -195,6 +251,7 @@ impl OperationExecutor {
         // from a snapshot, but for now we just manually pass in the max event time.
         let compute_store = context.compute_store.clone();
         let key_hash_inverse = context.key_hash_inverse.clone();
+        let output_key_hash_inverses = context.output_key_hash_inverses.clone();
         let max_event_in_snapshot: Option<NaiveDateTime> =
             if let Some(compute_store) = &compute_store {
                 compute_store
@@ -207,11 +264,22 @@ impl OperationExecutor {
             };
         context.max_event_in_snapshot = max_event_in_snapshot;
 
+        let operation_stats = context
+            .operation_stats
+            .as_ref()
+            .map(|stats| stats.operation(operation_index));
+        let mut memory = context
+            .memory_budget
+            .reservation(operation_index, operation_label);
+        if let Some(stats) = &operation_stats {
+            memory = memory.with_stats(stats.clone());
+        }
         let mut operation = create_operation(
             context,
             operator,
             input_channels,
             expression_executor.input_columns(),
+            memory,
         )
         .await?;
 
@@ -221,7 +289,11 @@ impl OperationExecutor {
 
         let operation_index = operation_index as u8;
 
-        Ok(async move {
+        // Time spent polling the operation (merging, shifting, reading inputs,
+        // etc.) and computing its expressions is recorded as busy time.
+        let operation_busy_stats = operation_stats.clone();
+        let executor_busy_stats = operation_stats.clone();
+        let execution = async move {
             if let Some(store) = &compute_store {
                 let _span = tracing::debug_span!("Restoring state").entered();
                 expression_executor
@@ -237,11 +309,14 @@ impl OperationExecutor {
             }
 
             let operation_handle: JoinHandle<error_stack::Result<_, Error>> = tokio::spawn(
-                async move {
-                    tracing::debug!("Full operation is {:?}", operation);
-                    operation.execute(send).await?;
-                    Ok(operation)
-                }
+                RecordBusy::new(
+                    async move {
+                        tracing::debug!("Full operation is {:?}", operation);
+                        operation.execute(send).await?;
+                        Ok(operation)
+                    },
+                    operation_busy_stats,
+                )
                 .in_current_span(),
             );
 
@@ -268,10 +343,21 @@ impl OperationExecutor {
                 }
                 last_upper_bound = Some(input.upper_bound);
 
+                let input_rows = input.len();
+                let watermark = input.upper_bound.time;
                 let output = expression_executor
                     .execute(input)
                     .into_report()
                     .change_context(Error::internal())?;
+                let output = match output_filter {
+                    Some(filter_column) => filter_output(output, filter_column)
+                        .into_report()
+                        .change_context(Error::internal())?,
+                    None => output,
+                };
+                if let Some(stats) = &operation_stats {
+                    stats.record_batch(input_rows, output.num_rows(), watermark);
+                }
 
                 // For each batch produced by the operation, write it to each channel.
                 // We currently do this synchronously in the order channels subscribed.
@@ -328,7 +414,7 @@ impl OperationExecutor {
                     let report = Report::new(Error::internal()).attach_printable(join_error);
                     return Err(report);
                 }
-                Ok(operation_result) => operation_result.change_context(Error::internal())?,
+                Ok(operation_result) => operation_result?,
             };
 
             if let Some(store) = &compute_store {
@@ -349,6 +435,9 @@ impl OperationExecutor {
                 async move {
                     if let Some(store) = &compute_store {
                         key_hash_inverse.clone().store_to(store).await?;
+                        for (grouping, key_hash_inverse) in output_key_hash_inverses {
+                            key_hash_inverse.store_grouping_to(store, &grouping).await?;
+                        }
                     }
                     Ok(())
                 }
@@ -362,10 +451,21 @@ impl OperationExecutor {
                 .into_report()
                 .change_context(Error::internal())?;
             Ok(())
-        })
+        };
+        Ok(RecordBusy::new(execution, executor_busy_stats))
     }
 }
 
+/// Drop the rows of `output` for which the boolean `filter_column` isn't true.
+///
+/// The bounds of the batch are preserved.
+fn filter_output(output: Batch, filter_column: usize) -> anyhow::Result<Batch> {
+    let filter = output.column(filter_column);
+    let filter = downcast_boolean_array(filter.as_ref())?;
+    let data = arrow::compute::filter_record_batch(output.data(), filter)?;
+    Ok(output.with_data(data))
+}
+
 /// Executes an operation.
 ///
 /// Returns a stream of the resulting record batches.
@@ -381,13 +481,14 @@ async fn create_operation(
     operator: operation_plan::Operator,
     incoming_channels: Vec<tokio::sync::mpsc::Receiver<Batch>>,
     input_columns: &[InputColumn],
+    memory: MemoryReservation,
 ) -> Result<BoxedOperation, Error> {
     match operator {
         operation_plan::Operator::Scan(scan_operation) => {
             ScanOperation::create(context, scan_operation, incoming_channels, input_columns).await
         }
         operation_plan::Operator::Merge(merge_operation) => {
-            MergeOperation::create(merge_operation, incoming_channels, input_columns)
+            MergeOperation::create(merge_operation, incoming_channels, input_columns, memory)
         }
         operation_plan::Operator::Select(select_operation) => {
             SelectOperation::create(select_operation, incoming_channels, input_columns)
@@ -398,13 +499,15 @@ async fn create_operation(
             incoming_channels,
             input_columns,
         ),
-        operation_plan::Operator::Tick(tick_operation) => {
-            if matches!(tick_operation.behavior(), TickBehavior::Finished) {
+        operation_plan::Operator::Tick(tick_operation) => match tick_operation.behavior() {
+            TickBehavior::Finished => {
                 FinalTickOperation::create(context, incoming_channels, input_columns)
-            } else {
-                TickOperation::create(tick_operation, incoming_channels, input_columns)
             }
-        }
+            TickBehavior::Examples => {
+                ExamplesOperation::create(context, incoming_channels, input_columns).await
+            }
+            _ => TickOperation::create(tick_operation, incoming_channels, input_columns),
+        },
         operation_plan::Operator::LookupRequest(lookup_request) => {
             LookupRequestOperation::create(lookup_request, incoming_channels, input_columns)
         }
@@ -412,10 +515,10 @@ async fn create_operation(
             LookupResponseOperation::create(lookup_response, incoming_channels, input_columns)
         }
         operation_plan::Operator::ShiftTo(shift_to) => {
-            shift_to::create(shift_to, incoming_channels, input_columns)
+            shift_to::create(shift_to, incoming_channels, input_columns, memory)
         }
         operation_plan::Operator::ShiftUntil(shift_until) => {
-            ShiftUntilOperation::create(shift_until, incoming_channels, input_columns)
+            ShiftUntilOperation::create(shift_until, incoming_channels, input_columns, memory)
         }
     }
     .change_context(Error::internal_msg("unable to create operation"))
@@ -0,0 +1,522 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, BooleanArray, TimestampNanosecondArray, UInt32Array, UInt64Array};
+use arrow::datatypes::{
+    ArrowPrimitiveType, DataType, Field, Schema, SchemaRef, TimestampNanosecondType,
+};
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use error_stack::{IntoReport, IntoReportCompat, ResultExt};
+use futures::{StreamExt, TryStreamExt};
+use itertools::{izip, Itertools};
+use sparrow_compiler::TableInfo;
+use sparrow_core::{downcast_primitive_array, KeyTriple};
+use sparrow_instructions::ComputeStore;
+use sparrow_qfr::FlightRecorder;
+
+use super::expression_executor::InputColumn;
+use super::sorted_key_hash_map::SortedKeyHashMap;
+use super::{BoxedOperation, Operation, OperationContext};
+use crate::execute::operation::InputBatch;
+use crate::execute::partition::partition_of;
+use crate::execute::Error;
+use crate::table_reader::table_reader;
+use crate::{Batch, BatchSender};
+
+/// Max number of rows an examples batch produces at once.
+const MAX_EXAMPLE_ROWS: usize = 100_000;
+
+#[static_init::dynamic]
+static EXAMPLES_SCHEMA: SchemaRef = Arc::new(Schema::new(vec![
+    Field::new("_time", TimestampNanosecondType::DATA_TYPE, false),
+    Field::new("_subsort", DataType::UInt64, false),
+    Field::new("_key_hash", DataType::UInt64, false),
+    Field::new("_tick", DataType::Boolean, false),
+]));
+
+/// Produces a row at the time of each example, for the entity of the example.
+///
+/// This is a tick operation, but rather than producing rows for every entity
+/// in the input, it produces rows for the examples provided with the request.
+/// Like other ticks, the rows use the maximum subsort so they are processed
+/// after all inputs at the same time.
+///
+/// The examples are read when the operation is created. Each example is sent
+/// once the input has progressed past it, so that all inputs at or before the
+/// example have been processed before the example.
+///
+/// The labels of the examples (the columns of the examples table other than
+/// its time, subsort and entity key) are sent to the outputs produced at the
+/// examples alongside each example. Each output has a result row for each
+/// example, in the same order as the examples of the partition are sent, so
+/// the output adds the next labels from the partition to each row.
+pub(super) struct ExamplesOperation {
+    /// Stream of input batches.
+    input_stream: Pin<Box<dyn futures::Stream<Item = Batch> + Send>>,
+
+    /// The `(time, key_hash, label_index)` of the examples yet to be sent, in
+    /// order.
+    examples: VecDeque<(i64, u64, u32)>,
+
+    /// The schema of the batches of labels.
+    labels_schema: SchemaRef,
+
+    /// The label columns, with a row for each example of the partition.
+    labels: Vec<ArrayRef>,
+
+    /// The senders for the labels of the examples sent by this operation.
+    label_senders: Vec<BatchSender>,
+
+    /// The indices of the entities with examples.
+    key_hashes: SortedKeyHashMap,
+}
+
+impl std::fmt::Debug for ExamplesOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExamplesOperation")
+            .field("examples", &format!("{} remaining", self.examples.len()))
+            .finish_non_exhaustive()
+    }
+}
+
+impl ExamplesOperation {
+    /// Create the operation, reading the examples for the partition.
+    pub(super) async fn create(
+        context: &mut OperationContext,
+        input_channels: Vec<tokio::sync::mpsc::Receiver<Batch>>,
+        input_columns: &[InputColumn],
+    ) -> error_stack::Result<BoxedOperation, super::Error> {
+        let input_channel = input_channels
+            .into_iter()
+            .exactly_one()
+            .into_report()
+            .change_context(Error::internal_msg("expected one channel"))?;
+        let input_stream = tokio_stream::wrappers::ReceiverStream::new(input_channel).boxed();
+
+        debug_assert!(
+            input_columns[0].input_ref.input_column == 0,
+            "Tick column should have 0th input index"
+        );
+
+        let table_id = context.examples.ok_or(Error::MissingField("examples"))?;
+        let table_info = context
+            .data_context
+            .table_info(table_id)
+            .ok_or_else(|| Error::internal_msg("missing examples table"))?;
+
+        let label_fields = example_label_fields(table_info);
+        let mut batches = table_reader(
+            &mut context.data_manager,
+            table_info,
+            &None,
+            Some(
+                std::iter::once(table_info.config().group_column_name.clone())
+                    .chain(label_fields.iter().map(|field| field.name().clone()))
+                    .collect(),
+            ),
+            FlightRecorder::disabled(),
+            None,
+            None,
+            None,
+        )
+        .change_context(Error::internal_msg("failed to create examples reader"))?
+        .boxed();
+
+        // Each partition only produces the examples for the entities it owns.
+        let (partition, num_partitions) = context.partition;
+        let mut examples = Vec::new();
+        let mut label_batches = Vec::new();
+        while let Some(batch) = batches
+            .try_next()
+            .await
+            .change_context(Error::internal_msg("failed to read examples"))?
+        {
+            let times: &TimestampNanosecondArray =
+                downcast_primitive_array(batch.column(0).as_ref())
+                    .into_report()
+                    .change_context(Error::internal_msg("downcasting time"))?;
+            let key_hashes: &UInt64Array = downcast_primitive_array(batch.column(2).as_ref())
+                .into_report()
+                .change_context(Error::internal_msg("downcasting key hash"))?;
+            let is_partition: BooleanArray = key_hashes
+                .values()
+                .iter()
+                .map(|key_hash| Some(partition_of(*key_hash, num_partitions) == partition))
+                .collect();
+
+            let first_label = examples.len() as u32;
+            examples.extend(
+                izip!(times.values(), key_hashes.values())
+                    .filter(|(_, key_hash)| partition_of(**key_hash, num_partitions) == partition)
+                    .enumerate()
+                    .map(|(index, (time, key_hash))| {
+                        (*time, *key_hash, first_label + index as u32)
+                    }),
+            );
+            label_batches.push(
+                partition_labels(&batch, &label_fields, &is_partition)
+                    .into_report()
+                    .change_context(Error::internal_msg("reading example labels"))?,
+            );
+        }
+        let labels = concat_labels(&label_fields, label_batches)
+            .into_report()
+            .change_context(Error::internal_msg("reading example labels"))?;
+
+        // The examples are ordered by subsort within each time, but are
+        // produced with the same subsort, so they need to be ordered by key.
+        // Multiple examples for the same entity at the same time each produce
+        // a row, so the output has a row for each example. They remain in the
+        // order of the examples table.
+        examples.sort_unstable();
+
+        // The first examples operation of the partition sends the labels.
+        // Every examples operation sends the same examples, so the labels are
+        // only needed from one of them.
+        let label_senders = context
+            .example_labels
+            .get_mut(partition)
+            .map(std::mem::take)
+            .unwrap_or_default();
+
+        Ok(Box::new(Self {
+            input_stream,
+            examples: examples.into(),
+            labels_schema: labels_schema(label_fields),
+            labels,
+            label_senders,
+            key_hashes: SortedKeyHashMap::new(),
+        }))
+    }
+
+    /// Send the examples at or before the `bound`.
+    ///
+    /// If `bound` is `None`, all remaining examples are sent.
+    async fn send_examples(
+        &mut self,
+        bound: Option<KeyTriple>,
+        sender: &tokio::sync::mpsc::Sender<InputBatch>,
+    ) -> error_stack::Result<(), super::Error> {
+        let num_examples = match bound {
+            Some(bound) => self.examples.partition_point(|(time, key_hash, _)| {
+                example_key_triple(*time, *key_hash) <= bound
+            }),
+            None => self.examples.len(),
+        };
+
+        let mut ready = self.examples.drain(..num_examples).peekable();
+        while ready.peek().is_some() {
+            let chunk: Vec<_> = ready.by_ref().take(MAX_EXAMPLE_ROWS).collect();
+            let (first_time, first_key, _) = chunk[0];
+            let (last_time, last_key, _) = chunk[chunk.len() - 1];
+            let lower_bound = example_key_triple(first_time, first_key);
+            let upper_bound = example_key_triple(last_time, last_key);
+
+            let time = TimestampNanosecondArray::from_iter_values(chunk.iter().map(|(t, _, _)| *t));
+            let key_hash = UInt64Array::from_iter_values(chunk.iter().map(|(_, k, _)| *k));
+            let grouping = self
+                .key_hashes
+                .get_or_update_indices(&key_hash)
+                .into_report()
+                .change_context(Error::internal())?;
+
+            // The subsort value is set to `u64::MAX` in order to ensure examples
+            // are processed after all other rows at the same time.
+            let subsort =
+                UInt64Array::from_iter_values(std::iter::repeat(u64::MAX).take(chunk.len()));
+            let tick: BooleanArray = std::iter::repeat(Some(true)).take(chunk.len()).collect();
+
+            let time: ArrayRef = Arc::new(time);
+            let subsort: ArrayRef = Arc::new(subsort);
+            let key_hash: ArrayRef = Arc::new(key_hash);
+            let labels = if self.label_senders.is_empty() {
+                None
+            } else {
+                let label_indices = UInt32Array::from_iter_values(chunk.iter().map(|(_, _, l)| *l));
+                Some(
+                    self.labels_batch(
+                        &[time.clone(), subsort.clone(), key_hash.clone()],
+                        &label_indices,
+                        lower_bound,
+                        upper_bound,
+                    )
+                    .into_report()
+                    .change_context(Error::internal_msg("creating example labels"))?,
+                )
+            };
+
+            let input_batch = InputBatch {
+                time,
+                subsort,
+                key_hash,
+                grouping,
+                input_columns: vec![Arc::new(tick) as ArrayRef],
+                lower_bound,
+                upper_bound,
+            };
+
+            sender
+                .send(input_batch)
+                .await
+                .into_report()
+                .change_context(Error::internal_msg("sending examples batch"))?;
+
+            if let Some(labels) = labels {
+                // An output stops receiving labels if it stops early (for
+                // instance, to produce a preview), so that isn't an error.
+                for label_sender in &self.label_senders {
+                    let _ = label_sender.send(labels.clone()).await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Create the batch of labels for the examples with the given key columns.
+    fn labels_batch(
+        &self,
+        key_columns: &[ArrayRef],
+        label_indices: &UInt32Array,
+        lower_bound: KeyTriple,
+        upper_bound: KeyTriple,
+    ) -> anyhow::Result<Batch> {
+        let columns = key_columns
+            .iter()
+            .cloned()
+            .map(Ok)
+            .chain(
+                self.labels
+                    .iter()
+                    .map(|labels| arrow::compute::take(labels.as_ref(), label_indices, None)),
+            )
+            .collect::<Result<_, _>>()?;
+        let data = RecordBatch::try_new(self.labels_schema.clone(), columns)?;
+        Batch::try_new_with_bounds(data, lower_bound, upper_bound)
+    }
+}
+
+/// Return the fields of the labels of the examples in the given table.
+///
+/// The labels are the columns other than the time, subsort and entity key.
+pub(crate) fn example_label_fields(table_info: &TableInfo) -> Vec<Field> {
+    let config = table_info.config();
+    let key_columns = [
+        Some(&config.time_column_name),
+        config.subsort_column_name.as_ref(),
+        Some(&config.group_column_name),
+    ];
+    table_info
+        .schema()
+        .fields()
+        .iter()
+        .filter(|field| !key_columns.contains(&Some(field.name())))
+        .map(|field| Field::new(field.name(), field.data_type().clone(), true))
+        .collect()
+}
+
+/// The schema of the batches of labels sent to the outputs.
+fn labels_schema(label_fields: Vec<Field>) -> SchemaRef {
+    let fields = EXAMPLES_SCHEMA.fields()[0..3]
+        .iter()
+        .cloned()
+        .chain(label_fields)
+        .collect();
+    Arc::new(Schema::new(fields))
+}
+
+/// Return the labels of the rows belonging to the partition.
+///
+/// The labels are cast to the type of the label fields.
+fn partition_labels(
+    batch: &Batch,
+    label_fields: &[Field],
+    is_partition: &BooleanArray,
+) -> anyhow::Result<Vec<ArrayRef>> {
+    let schema = batch.schema();
+    label_fields
+        .iter()
+        .map(|field| {
+            let labels = batch.column(schema.index_of(field.name())?);
+            let labels = arrow::compute::filter(labels.as_ref(), is_partition)?;
+            Ok(arrow::compute::cast(&labels, field.data_type())?)
+        })
+        .collect()
+}
+
+fn concat_labels(
+    label_fields: &[Field],
+    label_batches: Vec<Vec<ArrayRef>>,
+) -> anyhow::Result<Vec<ArrayRef>> {
+    label_fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            if label_batches.is_empty() {
+                return Ok(arrow::array::new_empty_array(field.data_type()));
+            }
+            let labels: Vec<_> = label_batches
+                .iter()
+                .map(|labels| labels[index].as_ref())
+                .collect();
+            Ok(arrow::compute::concat(&labels)?)
+        })
+        .collect()
+}
+
+fn example_key_triple(time: i64, key_hash: u64) -> KeyTriple {
+    KeyTriple {
+        time,
+        subsort: u64::MAX,
+        key_hash,
+    }
+}
+
+#[async_trait]
+impl Operation for ExamplesOperation {
+    fn restore_from(
+        &mut self,
+        _operation_index: u8,
+        _compute_store: &ComputeStore,
+    ) -> anyhow::Result<()> {
+        // The examples are provided with each request, so there is no state.
+        Ok(())
+    }
+
+    fn store_to(&self, _operation_index: u8, _compute_store: &ComputeStore) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn execute(
+        &mut self,
+        sender: tokio::sync::mpsc::Sender<InputBatch>,
+    ) -> error_stack::Result<(), super::Error> {
+        while let Some(incoming) = self.input_stream.next().await {
+            // Later inputs are after the upper bound, so examples at or before
+            // it won't be affected by them.
+            self.send_examples(Some(incoming.upper_bound), &sender)
+                .await?;
+
+            // Send an empty batch with bounds to allow downstream consumers to progress
+            let empty_batch = InputBatch::new_empty(
+                EXAMPLES_SCHEMA.clone(),
+                incoming.upper_bound,
+                incoming.upper_bound,
+            );
+            sender
+                .send(empty_batch)
+                .await
+                .into_report()
+                .change_context(Error::internal())?;
+        }
+
+        // Once all inputs have been processed, the remaining examples are after
+        // all of the inputs.
+        self.send_examples(None, &sender).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Array, Int64Array};
+
+    use super::*;
+
+    fn operation(
+        input_stream: Pin<Box<dyn futures::Stream<Item = Batch> + Send>>,
+        examples: Vec<(i64, u64)>,
+    ) -> ExamplesOperation {
+        ExamplesOperation {
+            input_stream,
+            examples: examples
+                .into_iter()
+                .enumerate()
+                .map(|(index, (time, key_hash))| (time, key_hash, index as u32))
+                .collect(),
+            labels_schema: labels_schema(vec![]),
+            labels: vec![],
+            label_senders: vec![],
+            key_hashes: SortedKeyHashMap::new(),
+        }
+    }
+
+    async fn collect(mut operation: ExamplesOperation) -> Vec<InputBatch> {
+        let (sender, receiver) = tokio::sync::mpsc::channel(10);
+        tokio::spawn(async move { operation.execute(sender).await.unwrap() });
+        tokio_stream::wrappers::ReceiverStream::new(receiver)
+            .collect()
+            .await
+    }
+
+    fn times(batch: &InputBatch) -> Vec<i64> {
+        let times: &TimestampNanosecondArray =
+            downcast_primitive_array(batch.time.as_ref()).unwrap();
+        times.values().to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_examples_interleaved_with_input() {
+        let input = Batch::test_batch(TimestampNanosecondArray::from(vec![1000, 2000]), 0, 5);
+        let input_stream = futures::stream::iter(vec![input.clone()]).boxed();
+
+        // One example within the input, one at the end of the input and one
+        // after the input.
+        let examples = vec![(1500, 7), (2000, 3), (3000, 7)];
+        let batches = collect(operation(input_stream, examples)).await;
+
+        assert_eq!(batches.len(), 3);
+        assert_eq!(times(&batches[0]), vec![1500]);
+        assert_eq!(batches[1].len(), 0);
+        assert_eq!(batches[1].upper_bound, input.upper_bound);
+        assert_eq!(times(&batches[2]), vec![2000, 3000]);
+
+        // Examples for the same entity use the same group index.
+        assert_eq!(
+            batches[2].grouping.group_indices().values().to_vec(),
+            vec![1, 0]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_examples() {
+        let input_stream = futures::stream::iter(vec![]).boxed();
+
+        // Each example produces a row, even if it has the same time and
+        // entity as another example.
+        let examples = vec![(1500, 7), (1500, 7), (2000, 7)];
+        let batches = collect(operation(input_stream, examples)).await;
+
+        assert_eq!(batches.len(), 1);
+        assert_eq!(times(&batches[0]), vec![1500, 1500, 2000]);
+    }
+
+    #[tokio::test]
+    async fn test_example_labels() {
+        let input_stream = futures::stream::iter(vec![]).boxed();
+
+        // The labels are sent in the order of the examples, which is the
+        // order of the examples table for examples at the same time and
+        // entity.
+        let mut operation = operation(input_stream, vec![(1500, 7), (1500, 3), (1500, 7)]);
+        operation.labels_schema = labels_schema(vec![Field::new("label", DataType::Int64, true)]);
+        operation.labels = vec![Arc::new(Int64Array::from(vec![10, 11, 12]))];
+        operation.examples.make_contiguous().sort_unstable();
+        let (label_sender, mut label_receiver) = tokio::sync::mpsc::channel(10);
+        operation.label_senders = vec![label_sender];
+
+        let batches = collect(operation).await;
+        assert_eq!(batches.len(), 1);
+        assert_eq!(times(&batches[0]), vec![1500, 1500, 1500]);
+
+        let labels = label_receiver.recv().await.unwrap();
+        assert_eq!(labels.lower_bound, batches[0].lower_bound);
+        assert_eq!(labels.upper_bound, batches[0].upper_bound);
+        assert_eq!(labels.column(2).as_ref(), batches[0].key_hash.as_ref());
+        assert_eq!(
+            labels.column(3).as_ref(),
+            &Int64Array::from(vec![11, 10, 12]) as &dyn Array
+        );
+        assert!(label_receiver.recv().await.is_none());
+    }
+}
@@ -1,10 +1,11 @@
 use std::sync::Arc;
 
 use anyhow::Context;
-use arrow::array::ArrayRef;
+use arrow::array::{Array, ArrayRef, UInt32Array};
 use arrow::datatypes::SchemaRef;
 use arrow::record_batch::RecordBatch;
-use sparrow_core::{KeyTriple, KeyTriples};
+use itertools::Itertools;
+use sparrow_core::{downcast_primitive_array, KeyTriple, KeyTriples};
 use sparrow_instructions::GroupingIndices;
 
 use crate::merge::BinaryMergeInput;
@@ -69,6 +70,37 @@ impl InputBatch {
         self.time.len()
     }
 
+    /// Return the memory used by the columns of this batch.
+    pub fn memory_size(&self) -> usize {
+        let key_columns = [&self.time, &self.subsort, &self.key_hash];
+        crate::execute::memory::arrays_memory_size(
+            key_columns.into_iter().chain(self.input_columns.iter()),
+        ) + self.grouping.group_indices().get_array_memory_size()
+    }
+
+    /// Copy the rows of this batch into newly allocated arrays.
+    ///
+    /// Slicing a batch retains the buffers of the original arrays. This
+    /// allows the remaining rows to be released once the original is dropped.
+    pub fn compact(&self) -> anyhow::Result<Self> {
+        let indices = UInt32Array::from_iter_values(0..self.len() as u32);
+        let take = |column: &ArrayRef| -> anyhow::Result<ArrayRef> {
+            arrow::compute::take(column.as_ref(), &indices, None).context("compacting input batch")
+        };
+        let group_indices = arrow::compute::take(self.grouping.group_indices(), &indices, None)?;
+        let group_indices: &UInt32Array = downcast_primitive_array(group_indices.as_ref())?;
+
+        Ok(Self {
+            time: take(&self.time)?,
+            subsort: take(&self.subsort)?,
+            key_hash: take(&self.key_hash)?,
+            grouping: GroupingIndices::new(self.grouping.num_groups(), group_indices.clone()),
+            input_columns: self.input_columns.iter().map(take).try_collect()?,
+            lower_bound: self.lower_bound,
+            upper_bound: self.upper_bound,
+        })
+    }
+
     pub fn as_merge_input(&self) -> anyhow::Result<BinaryMergeInput<'_>> {
         BinaryMergeInput::from_array_refs(&self.time, &self.subsort, &self.key_hash)
     }
@@ -15,6 +15,7 @@ use sparrow_instructions::{ComputeStore, GroupingIndices, StoreKey};
 use tokio_stream::wrappers::ReceiverStream;
 
 use super::BoxedOperation;
+use crate::execute::memory::MemoryReservation;
 use crate::execute::operation::expression_executor::InputColumn;
 use crate::execute::operation::spread::Spread;
 use crate::execute::operation::{InputBatch, Operation};
@@ -42,6 +43,11 @@ pub(super) struct MergeOperation {
     left_stream: ReceiverStream<Batch>,
     right_stream: ReceiverStream<Batch>,
     key_hash_index: KeyHashIndex,
+    /// Reports the rows buffered on either side of the merge.
+    ///
+    /// At most one batch is buffered on each side, so these don't count
+    /// against the memory budget.
+    memory: MemoryReservation,
 }
 
 #[async_trait]
@@ -86,6 +92,8 @@ impl Operation for MergeOperation {
             .into_report()
             .change_context(Error::internal())?
         {
+            self.memory
+                .set_rows(self.left_state.num_rows() + self.right_state.num_rows());
             sender
                 .send(input)
                 .await
@@ -131,6 +139,7 @@ impl MergeOperation {
         merge_operation: operation_plan::MergeOperation,
         input_channels: Vec<tokio::sync::mpsc::Receiver<Batch>>,
         input_columns: &[InputColumn],
+        memory: MemoryReservation,
     ) -> error_stack::Result<BoxedOperation, super::Error> {
         let (left_rx, right_rx) = input_channels
             .into_iter()
@@ -187,6 +196,7 @@ impl MergeOperation {
             left_stream,
             right_stream,
             key_hash_index: KeyHashIndex::default(),
+            memory,
         }))
     }
 
@@ -464,6 +474,14 @@ impl MergeState {
     fn is_none(&self) -> bool {
         matches!(self, Self::None)
     }
+
+    /// The number of rows buffered on this side.
+    fn num_rows(&self) -> usize {
+        match self {
+            Self::Some(keyed_batch) => keyed_batch.batch.num_rows(),
+            Self::Done | Self::None => 0,
+        }
+    }
 }
 
 /// A batch to-be-merged associated with the keys.
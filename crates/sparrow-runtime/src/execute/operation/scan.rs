@@ -7,6 +7,7 @@ use async_trait::async_trait;
 use error_stack::{IntoReport, IntoReportCompat, ResultExt};
 use futures::{Stream, StreamExt, TryStreamExt};
 use itertools::Itertools;
+use sparrow_api::kaskada::v1alpha::slice_plan::Slice;
 use sparrow_api::kaskada::v1alpha::{self, operation_input_ref, operation_plan};
 use sparrow_core::downcast_primitive_array;
 use sparrow_instructions::ComputeStore;
@@ -15,6 +16,7 @@ use sparrow_qfr::FlightRecorder;
 
 use super::BoxedOperation;
 use crate::execute::operation::expression_executor::InputColumn;
+use crate::execute::operation::slice_entities::SliceEntities;
 use crate::execute::operation::{InputBatch, Operation, OperationContext};
 use crate::execute::progress_reporter::ProgressUpdate;
 use crate::execute::{error, Error};
@@ -173,6 +175,10 @@ impl ScanOperation {
                 .collect(),
         );
 
+        // Slices determined by the rows of a table are applied as the table is
+        // read, so tables are prepared in full (and shared between slices).
+        let prepared_slice = Slice::prepared_slice(&requested_slice);
+
         // Scans can read from tables (files) or streams.
         let backing_source = match table_info.config().source.as_ref() {
             Some(v1alpha::Source { source }) => source.as_ref().expect("source"),
@@ -183,9 +189,9 @@ impl ScanOperation {
             v1alpha::source::Source::Kaskada(_) => {
                 // Send initial progress information.
                 let total_num_rows = table_info
-                    .prepared_files_for_slice(&requested_slice)
+                    .prepared_files_for_slice(&prepared_slice)
                     .into_report()
-                    .change_context(error::invalid_operation!("scan operation references undefined slice {requested_slice:?} for table '{}'", table_info.name()))?
+                    .change_context(error::invalid_operation!("scan operation references undefined slice {prepared_slice:?} for table '{}'", table_info.name()))?
                     .iter()
                     .map(|file| file.num_rows as usize)
                     .sum();
@@ -199,11 +205,12 @@ impl ScanOperation {
                 let input_stream = table_reader(
                     &mut context.data_manager,
                     table_info,
-                    &requested_slice,
+                    &prepared_slice,
                     projected_columns,
                     // TODO: Fix flight recorder
                     FlightRecorder::disabled(),
                     context.max_event_in_snapshot,
+                    context.min_input_time,
                     context.output_at_time,
                 )
                 .change_context(Error::internal_msg("failed to create table reader"))?
@@ -215,7 +222,7 @@ impl ScanOperation {
                 let input_stream = stream_reader(
                     context,
                     table_info,
-                    requested_slice.as_ref(),
+                    prepared_slice.as_ref(),
                     projected_columns,
                     // TODO: Fix flight recorder
                     FlightRecorder::disabled(),
@@ -229,6 +236,32 @@ impl ScanOperation {
             }
         };
 
+        let input_stream = match &requested_slice {
+            Some(slice) if slice.entity_table().is_some() => {
+                let slice_entities = match &context.slice_entities {
+                    Some(slice_entities) => slice_entities.clone(),
+                    None => {
+                        let slice_entities = Arc::new(
+                            SliceEntities::try_new(
+                                &mut context.data_manager,
+                                &context.data_context,
+                                slice,
+                            )
+                            .await?,
+                        );
+                        context.slice_entities = Some(slice_entities.clone());
+                        slice_entities
+                    }
+                };
+                input_stream
+                    .and_then(move |batch| {
+                        futures::future::ready(slice_entities.filter_batch(batch))
+                    })
+                    .boxed()
+            }
+            _ => input_stream,
+        };
+
         Ok(Box::new(Self {
             projected_schema,
             input_stream,
@@ -301,6 +334,7 @@ mod tests {
 
     use crate::data_manager::DataManager;
     use crate::execute::key_hash_inverse::{KeyHashInverse, ThreadSafeKeyHashInverse};
+    use crate::execute::memory::MemoryBudget;
     use crate::execute::operation::testing::batches_to_csv;
     use crate::execute::operation::{OperationContext, OperationExecutor};
     use crate::read::testing::write_parquet_file;
@@ -340,8 +374,10 @@ mod tests {
                     time_column_name: "time".to_owned(),
                     subsort_column_name: None,
                     group_column_name: "key".to_owned(),
+                    additional_group_column_names: vec![],
                     grouping: "grouping".to_owned(),
                     source: Some(source),
+                    change_data_capture: None,
                 }),
                 metadata: Some(TableMetadata {
                     schema: Some(table_schema),
@@ -407,6 +443,7 @@ mod tests {
                     table_name: "table".to_owned(),
                     slice: None,
                 }),
+                filter: None,
             })),
         };
 
@@ -431,10 +468,18 @@ mod tests {
             data_context,
             compute_store: None,
             key_hash_inverse,
+            output_key_hash_inverses: Default::default(),
             max_event_in_snapshot: None,
+            min_input_time: None,
+            examples: None,
+            example_labels: vec![],
+            partition: (0, 1),
+            slice_entities: None,
             progress_updates_tx,
             output_at_time: None,
             bounded_lateness_ns: None,
+            memory_budget: MemoryBudget::default(),
+            operation_stats: None,
         };
 
         executor
@@ -477,6 +522,216 @@ mod tests {
         temp_file.close().unwrap();
     }
 
+    #[tokio::test]
+    async fn test_scan_execution_with_filter() {
+        let mut data_context = DataContext::default();
+        let table_id = Uuid::new_v4();
+
+        let (temp_file, prepared_file) = mk_file(&[
+            (0, 1, 1, "a", "b"),
+            (0, 2, 0, "c", "d"),
+            (1, 0, 1, "e", "f"),
+        ]);
+
+        let table_schema = Schema::new(vec![
+            Field::new(
+                "time",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                true,
+            ),
+            Field::new("key", DataType::UInt64, true),
+            Field::new("a", DataType::Utf8, true),
+            Field::new("b", DataType::Utf8, true),
+        ]);
+        let table_schema = v1alpha::Schema::try_from(&table_schema).unwrap();
+        let source = v1alpha::Source {
+            source: Some(v1alpha::source::Source::Kaskada(KaskadaSource {})),
+        };
+
+        data_context
+            .add_table(ComputeTable {
+                config: Some(TableConfig {
+                    name: "table".to_owned(),
+                    uuid: table_id.to_string(),
+                    time_column_name: "time".to_owned(),
+                    subsort_column_name: None,
+                    group_column_name: "key".to_owned(),
+                    additional_group_column_names: vec![],
+                    grouping: "grouping".to_owned(),
+                    source: Some(source),
+                    change_data_capture: None,
+                }),
+                metadata: Some(TableMetadata {
+                    schema: Some(table_schema),
+                    file_count: 1,
+                }),
+                file_sets: vec![FileSet {
+                    slice_plan: Some(SlicePlan {
+                        table_name: "table".to_owned(),
+                        slice: None,
+                    }),
+                    prepared_files: vec![prepared_file],
+                }],
+            })
+            .unwrap();
+
+        let scan_schema = Schema::new(vec![Field::new("b", DataType::Utf8, true)]);
+        let scan_schema = v1alpha::Schema::try_from(&scan_schema).unwrap();
+        let scan_record_schema = v1alpha::DataType {
+            kind: Some(data_type::Kind::Struct(scan_schema.clone())),
+        };
+        let plan = OperationPlan {
+            expressions: vec![
+                ExpressionPlan {
+                    arguments: vec![],
+                    result_type: Some(scan_record_schema),
+                    output: false,
+                    operator: Some(expression_plan::Operator::Input(OperationInputRef {
+                        producing_operation: 0,
+                        column: Some(Column::ScanRecord(())),
+                        input_column: 0,
+                        interpolation: operation_input_ref::Interpolation::Null as i32,
+                    })),
+                },
+                ExpressionPlan {
+                    arguments: vec![],
+                    result_type: Some(v1alpha::DataType {
+                        kind: Some(data_type::Kind::Primitive(
+                            data_type::PrimitiveType::String as i32,
+                        )),
+                    }),
+                    output: false,
+                    operator: Some(expression_plan::Operator::Literal(Literal {
+                        literal: Some(literal::Literal::Utf8("b".to_owned())),
+                    })),
+                },
+                ExpressionPlan {
+                    arguments: vec![0, 1],
+                    result_type: Some(v1alpha::DataType {
+                        kind: Some(data_type::Kind::Primitive(
+                            data_type::PrimitiveType::String as i32,
+                        )),
+                    }),
+                    output: true,
+                    operator: Some(expression_plan::Operator::Instruction(
+                        "field_ref".to_owned(),
+                    )),
+                },
+                ExpressionPlan {
+                    arguments: vec![],
+                    result_type: Some(v1alpha::DataType {
+                        kind: Some(data_type::Kind::Primitive(
+                            data_type::PrimitiveType::String as i32,
+                        )),
+                    }),
+                    output: false,
+                    operator: Some(expression_plan::Operator::Literal(Literal {
+                        literal: Some(literal::Literal::Utf8("d".to_owned())),
+                    })),
+                },
+                ExpressionPlan {
+                    arguments: vec![2, 3],
+                    result_type: Some(v1alpha::DataType {
+                        kind: Some(data_type::Kind::Primitive(
+                            data_type::PrimitiveType::Bool as i32,
+                        )),
+                    }),
+                    output: true,
+                    operator: Some(expression_plan::Operator::Instruction("neq".to_owned())),
+                },
+            ],
+            operator: Some(operation_plan::Operator::Scan(ScanOperation {
+                table_id: Some(table_id.into()),
+                schema: Some(scan_schema),
+                slice_plan: Some(SlicePlan {
+                    table_name: "table".to_owned(),
+                    slice: None,
+                }),
+                // Filter to the rows where `e2 != "d"`.
+                filter: Some(OperationInputRef {
+                    producing_operation: 0,
+                    column: Some(Column::ProducerExpression(4)),
+                    input_column: 4,
+                    interpolation: operation_input_ref::Interpolation::Null as i32,
+                }),
+            })),
+        };
+
+        let key_hash_inverse = KeyHashInverse::from_data_type(DataType::Utf8);
+        let key_hash_inverse = Arc::new(ThreadSafeKeyHashInverse::new(key_hash_inverse));
+
+        let (max_event_tx, mut max_event_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (sender, receiver) = tokio::sync::mpsc::channel(10);
+        let mut executor = OperationExecutor::new(plan.clone());
+        executor.add_consumer(sender);
+
+        // Channel for the output stats.
+        let (progress_updates_tx, mut progress_updates_rx) = tokio::sync::mpsc::channel(29);
+        let s3_helper = S3Helper::new().await;
+        let mut context = OperationContext {
+            plan: ComputePlan {
+                operations: vec![plan],
+                ..ComputePlan::default()
+            },
+            plan_hash: PlanHash::default(),
+            data_manager: DataManager::new(s3_helper),
+            data_context,
+            compute_store: None,
+            key_hash_inverse,
+            output_key_hash_inverses: Default::default(),
+            max_event_in_snapshot: None,
+            min_input_time: None,
+            examples: None,
+            example_labels: vec![],
+            partition: (0, 1),
+            slice_entities: None,
+            progress_updates_tx,
+            output_at_time: None,
+            bounded_lateness_ns: None,
+            memory_budget: MemoryBudget::default(),
+            operation_stats: None,
+        };
+
+        executor
+            .execute(0, &mut context, vec![], max_event_tx, &Default::default())
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+
+        max_event_rx.close();
+
+        let csv_string = batches_to_csv(receiver).await.unwrap();
+
+        progress_updates_rx.close();
+        let progress_updates: Vec<_> =
+            tokio_stream::wrappers::ReceiverStream::new(progress_updates_rx)
+                .collect()
+                .await;
+
+        // Filtered rows are dropped from the output, but still count as input.
+        insta::assert_snapshot!(csv_string, @r###"
+        _time,_subsort,_key_hash,e2,e4
+        1970-01-01T00:00:00.000000000,1,1,b,true
+        1970-01-01T00:00:00.000000001,0,1,f,true
+        "###);
+
+        insta::assert_debug_snapshot!(progress_updates, @r###"
+        [
+            InputMetadata {
+                total_num_rows: 3,
+            },
+            Input {
+                num_rows: 2,
+            },
+            Input {
+                num_rows: 1,
+            },
+        ]
+        "###);
+        temp_file.close().unwrap();
+    }
+
     // TODO: This testing helper is copied from `table_reader.rs`
     // Should try to refactor into a shareable place.
     fn mk_file(
@@ -1,10 +1,12 @@
 use std::cmp;
+use std::collections::VecDeque;
 use std::sync::Arc;
 
 use crate::execute::{invalid_operation, Error};
 use anyhow::Context;
 use arrow::array::{Array, ArrayRef, TimestampNanosecondArray, UInt32Array, UInt64Array};
 use arrow::compute::SortColumn;
+use arrow::datatypes::SchemaRef;
 use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
 use chrono::NaiveDateTime;
@@ -13,18 +15,23 @@ use futures::StreamExt;
 use itertools::Itertools;
 use sparrow_api::kaskada::v1alpha::operation_plan;
 use sparrow_api::kaskada::v1alpha::operation_plan::shift_to_operation::Time;
-use sparrow_core::{downcast_boolean_array, downcast_primitive_array, KeyTriples};
+use sparrow_core::{downcast_boolean_array, downcast_primitive_array, KeyTriple, KeyTriples};
 use sparrow_instructions::{ComputeStore, GroupingIndices, StoreKey};
 use tokio_stream::wrappers::ReceiverStream;
 use tracing::info;
 
 use super::BoxedOperation;
+use crate::execute::memory::{into_operation_report, MemoryReservation};
 use crate::execute::operation::expression_executor::InputColumn;
 use crate::execute::operation::single_consumer_helper::SingleConsumerHelper;
+use crate::execute::operation::spill::SpilledBatch;
 use crate::execute::operation::spread_zip::spread_zip;
 use crate::execute::operation::{InputBatch, Operation};
 use crate::Batch;
 
+/// The fraction of the memory budget to spill or output in a single batch.
+const CHUNK_FRACTION: usize = 8;
+
 /// Implementation of Shift To for a literal.
 #[derive(Debug)]
 struct ShiftToLiteralOperation {
@@ -60,11 +67,26 @@ struct ShiftToLiteralOperation {
 /// row-level encoding) in RocksDB keyed by time, so we could later iterate over
 /// a specific time range. We may be able to use the Data Fusion row-based
 /// format for this: https://github.com/apache/arrow-datafusion/blob/master/datafusion/row/src/lib.rs.
+///
+/// The pending rows are tracked against the query's memory budget. When they
+/// exceed it, they are spilled to local disk. The spilled rows are loaded
+/// back one spilled batch at a time as the incoming batches reach their time,
+/// and output in chunks which fit within the budget.
 #[derive(Debug)]
 struct ShiftToColumnOperation {
     shift_time_column: usize,
     /// The pending data for the shift.
     pending: Option<InputBatch>,
+    /// Pending rows which have been spilled to disk.
+    ///
+    /// The spilled batches form a single sorted run: every row of a spilled
+    /// batch is before the rows of the following batches. They may overlap
+    /// with `pending`.
+    spilled: VecDeque<SpilledBatch>,
+    /// The rows currently being output, if any.
+    output_range: Option<OutputRange>,
+    /// The memory reserved for the pending rows.
+    memory: MemoryReservation,
     incoming_stream: ReceiverStream<Batch>,
     helper: SingleConsumerHelper,
 }
@@ -74,6 +96,7 @@ pub(super) fn create(
     operation: operation_plan::ShiftToOperation,
     incoming_channels: Vec<tokio::sync::mpsc::Receiver<Batch>>,
     input_columns: &[InputColumn],
+    memory: MemoryReservation,
 ) -> error_stack::Result<BoxedOperation, super::Error> {
     let input_channel = incoming_channels
         .into_iter()
@@ -98,7 +121,7 @@ pub(super) fn create(
                 )
             );
             let time_input_column = computed.input_column as usize;
-            ShiftToColumnOperation::try_new(time_input_column, incoming_stream, helper)
+            ShiftToColumnOperation::try_new(time_input_column, incoming_stream, helper, memory)
                 .into_report()
                 .change_context(Error::internal_msg("failed to create operation"))
         }
@@ -283,10 +306,14 @@ impl ShiftToColumnOperation {
         time_input_column: usize,
         incoming_stream: ReceiverStream<Batch>,
         helper: SingleConsumerHelper,
+        memory: MemoryReservation,
     ) -> anyhow::Result<BoxedOperation> {
         Ok(Box::new(Self {
             shift_time_column: time_input_column,
             pending: None,
+            spilled: VecDeque::new(),
+            output_range: None,
+            memory,
             incoming_stream,
             helper,
         }))
@@ -294,20 +321,19 @@ impl ShiftToColumnOperation {
 
     fn create_input(&mut self, incoming: Batch) -> anyhow::Result<Option<InputBatch>> {
         // The incoming batch's upper bound is the max time we can output to.
-        let lower_bound = incoming.lower_bound;
-        let upper_bound = incoming.upper_bound;
-
-        let lower_bound_time = lower_bound.time;
-        let upper_bound_time = upper_bound.time;
-
+        // If nothing is buffered, an empty incoming batch is passed through.
         if incoming.num_rows() == 0 {
-            if self.pending.is_none() {
+            if self.pending.is_none() && self.spilled.is_empty() {
                 return Ok(Some(
                     self.helper
                         .new_input_batch(incoming, |input| Ok(input.clone()))?,
                 ));
             } else {
-                return self.split_output(lower_bound_time, upper_bound_time);
+                return self.start_output(
+                    incoming.schema(),
+                    incoming.lower_bound,
+                    incoming.upper_bound,
+                );
             }
         }
         // Create the "input" batch to be buffered. This requires
@@ -396,160 +422,229 @@ impl ShiftToColumnOperation {
             .helper
             .new_input_batch_with_keys(&incoming, time, subsort, key_hash, transform)?;
 
-        if self.pending.is_none() {
-            // 1. Pending set is empty
-            if let Some(input) = input {
-                // 1a. New input is non-empty
-                self.add_input(input)?;
-                self.split_output(lower_bound_time, upper_bound_time)
-            } else {
-                // 1b. New input is also empty
-                Ok(Some(InputBatch::new_empty(
-                    incoming.schema(),
-                    incoming.lower_bound,
-                    incoming.upper_bound,
-                )))
-            }
-        } else {
-            // 2. Pending set is non-empty
-            if let Some(input) = input {
-                // 2a. New input is non-empty
-                // Merge new input to pending set, then split to get output.
-                self.add_input(input)?;
-                self.split_output(lower_bound_time, upper_bound_time)
-            } else {
-                // 2b. New input is empty
-                // No need to add an empty input. Split at the incoming
-                // batch's upper bound.
-                self.split_output(lower_bound_time, upper_bound_time)
-            }
+        if let Some(input) = input {
+            self.add_input(input)?;
         }
+        self.start_output(
+            incoming.schema(),
+            incoming.lower_bound,
+            incoming.upper_bound,
+        )
     }
 
     /// Adds an incoming record batch to the pending batch.
+    ///
+    /// The memory of the pending rows is reserved. If they don't fit within
+    /// the budget, they are spilled to disk.
     fn add_input(&mut self, input: InputBatch) -> anyhow::Result<()> {
-        self.pending = Some(if let Some(right) = self.pending.take() {
-            let left = input;
-            let merge_result =
-                crate::merge::binary_merge(left.as_merge_input()?, right.as_merge_input()?)?;
-
-            // TODO: Binary merge optimization opportunity.
-            // HACK: We'd like the binary merge to return a boolean array `BooleanArray`
-            // indicating whether to use values from the left/right. Since it doesn't, we
-            // create one using the `is_not_null` kernel.
-            //
-            // NOTE: since we generated unique subsort indices, we know that any shifted
-            // row is either on the left or right, but not both. Thus, we only need to
-            // examine the left.
-            let take_left = arrow::compute::is_not_null(&merge_result.take_a)?;
-            let group_indices = spread_zip(
-                &take_left,
-                left.grouping.group_indices(),
-                right.grouping.group_indices(),
-            )?;
-            let group_indices: &UInt32Array = downcast_primitive_array(group_indices.as_ref())?;
-            // TODO: We shouldn't need to clone the array. Instead, we should be able
-            // to pass an owned reference to the grouping indices. But... the clone
-            // shouldn't be too expensive, and that would require API hanges.
-            let group_indices: UInt32Array = UInt32Array::from(group_indices.data().clone());
-            let num_groups = left.grouping.num_groups().max(right.grouping.num_groups());
-            let grouping = GroupingIndices::new(num_groups, group_indices);
-
-            let input_columns = left
-                .input_columns
-                .iter()
-                .zip_eq(right.input_columns)
-                .map(|(left, right)| {
-                    spread_zip(&take_left, left.as_ref(), right.as_ref())
-                        .context("zip for shift_to")
-                })
-                .try_collect()?;
-
-            anyhow::ensure!(
-                !merge_result.time.is_empty(),
-                "Expected non-empty merge result"
-            );
-
-            let time = Arc::new(merge_result.time);
-            let subsort = Arc::new(merge_result.subsort);
-            let key_hash = Arc::new(merge_result.key_hash);
-            let key_triples = KeyTriples::try_new(time.clone(), subsort.clone(), key_hash.clone())?;
-            let lower_bound = key_triples.value(0);
-            let upper_bound = key_triples.value(key_triples.len() - 1);
-            InputBatch {
-                time,
-                subsort,
-                key_hash,
-                grouping,
-                input_columns,
-                lower_bound,
-                upper_bound,
-            }
+        let pending = if let Some(pending) = self.pending.take() {
+            merge_batches(input, pending)?
         } else {
             input
-        });
-
+        };
+        let pending_size = pending.memory_size();
+        self.pending = Some(pending);
+        if self.memory.try_resize(pending_size).is_err() {
+            self.spill_pending()?;
+        }
         Ok(())
     }
 
-    fn split_output(
+    /// Start outputting the rows before the upper bound of the incoming batch.
+    ///
+    /// Returns the first chunk of rows to output. If there are none, returns
+    /// an empty batch so consumers may progress to the incoming bounds.
+    fn start_output(
         &mut self,
-        incoming_lower_bound_time: i64,
-        incoming_upper_bound_time: i64,
+        schema: SchemaRef,
+        lower_bound: KeyTriple,
+        upper_bound: KeyTriple,
     ) -> anyhow::Result<Option<InputBatch>> {
-        if let Some(pending) = self.pending.take() {
-            // Create the input batch from by slicing the pending rows up to the max
-            // timestamp.
-            let pending_times: &TimestampNanosecondArray =
-                downcast_primitive_array(pending.time.as_ref())?;
-            let pending_times = pending_times.values();
-            let split_length = match pending_times.binary_search(&incoming_upper_bound_time) {
-                Ok(mut found) => {
-                    // We found the time. We need to run back from that to find the *first*
-                    // occurrence of that time.
-                    while found > 0 && pending_times[found - 1] == incoming_upper_bound_time {
-                        found -= 1;
+        self.output_range = Some(self.output_range(lower_bound.time, upper_bound.time));
+        if let Some(output) = self.next_output()? {
+            return Ok(Some(output));
+        }
+        Ok(Some(InputBatch::new_empty(
+            schema,
+            lower_bound,
+            upper_bound,
+        )))
+    }
+
+    fn output_range(&self, lower_bound_time: i64, upper_bound_time: i64) -> OutputRange {
+        OutputRange {
+            lower_bound_time,
+            upper_bound_time,
+            next_subsort: 0,
+            chunk_rows: self
+                .pending
+                .as_ref()
+                .map_or(usize::MAX, |pending| self.chunk_rows(pending)),
+        }
+    }
+
+    /// Return the next chunk of rows in the output range, if any.
+    ///
+    /// Pending rows are output up to the earliest spilled row. Once they have
+    /// been output, the first spilled batch is loaded, so the spilled rows are
+    /// brought back into memory one batch at a time. When all rows in the
+    /// range have been output, the range is cleared.
+    fn next_output(&mut self) -> anyhow::Result<Option<InputBatch>> {
+        let Some(mut range) = self.output_range else {
+            return Ok(None);
+        };
+
+        loop {
+            let spilled_time = self.spilled.front().map(SpilledBatch::min_time);
+            let limit = spilled_time.map_or(range.upper_bound_time, |spilled_time| {
+                spilled_time.min(range.upper_bound_time)
+            });
+            let length = match &self.pending {
+                Some(pending) => rows_before(pending, limit)?,
+                None => 0,
+            };
+
+            if length > 0 {
+                let pending = self.pending.take().expect("pending rows");
+                let (prefix, suffix) = pending.split(length.min(range.chunk_rows))?;
+                self.pending = (suffix.len() > 0).then_some(suffix);
+
+                let output = range.output_batch(prefix)?;
+                self.output_range = Some(range);
+                return Ok(Some(output));
+            }
+
+            match spilled_time {
+                Some(spilled_time) if spilled_time < range.upper_bound_time => {
+                    self.load_spilled()?;
+                    if let Some(pending) = &self.pending {
+                        range.chunk_rows = self.chunk_rows(pending);
                     }
-                    found
                 }
-                Err(not_found) => not_found,
-            };
-            let (prefix, suffix) = pending.split(split_length)?;
-            // TODO: What if suffix is empty?
-            self.pending = Some(suffix);
-
-            // The subsort column can naively monotonically increase and preserve the
-            // uniqueness invariant.
-            let subsort = Arc::new(UInt64Array::from_iter_values(0..(prefix.time.len() as u64)));
-
-            // Fix the bounds based on the generated subsort column above.
-            let mut lower_bound = prefix.lower_bound;
-            lower_bound.time = cmp::min(incoming_lower_bound_time, lower_bound.time);
-            lower_bound.subsort = 0;
-            let mut upper_bound = prefix.upper_bound;
-            upper_bound.time = cmp::min(incoming_upper_bound_time, upper_bound.time);
-
-            let upper_subsort = if prefix.time.len() == 0 {
-                0
-            } else {
-                // 0-indexed, so sub 1
-                prefix.time.len() as u64 - 1
-            };
-            upper_bound.subsort = upper_subsort;
-
-            let prefix = InputBatch {
-                time: prefix.time,
-                subsort,
-                key_hash: prefix.key_hash,
-                grouping: prefix.grouping,
-                input_columns: prefix.input_columns,
-                lower_bound,
-                upper_bound,
+                _ => break,
+            }
+        }
+
+        // The remaining pending rows are slices of the rows which were output,
+        // so copy them to release the memory of the output rows.
+        self.output_range = None;
+        if let Some(pending) = self.pending.take() {
+            let pending = pending.compact()?;
+            let pending_size = pending.memory_size();
+            self.pending = Some(pending);
+            if self.memory.try_resize(pending_size).is_err() {
+                self.spill_pending()?;
+            }
+        } else {
+            self.memory.free();
+        }
+        Ok(None)
+    }
+
+    /// Return the number of rows of the batch to output or spill at once.
+    ///
+    /// This is a fraction of the memory budget, so a spilled batch may be
+    /// loaded alongside the other pending rows.
+    fn chunk_rows(&self, batch: &InputBatch) -> usize {
+        match self.memory.limit() {
+            Some(limit) => {
+                let row_size = (batch.memory_size() / batch.len().max(1)).max(1);
+                (limit / CHUNK_FRACTION / row_size).max(1)
+            }
+            None => usize::MAX,
+        }
+    }
+
+    /// Merge the pending rows into the spilled rows, releasing their memory.
+    ///
+    /// The spilled rows remain a single sorted run. The pending rows before
+    /// each spilled batch's successor are merged into it, loading and
+    /// rewriting only the spilled batches which gain rows.
+    fn spill_pending(&mut self) -> anyhow::Result<()> {
+        let Some(pending) = self.pending.take() else {
+            return Ok(());
+        };
+        info!(
+            num_rows = pending.len(),
+            num_bytes = pending.memory_size(),
+            "shift_to spilling pending rows"
+        );
+
+        let chunk_rows = self.chunk_rows(&pending);
+        let mut pending = Some(pending);
+        let mut spilled = std::mem::take(&mut self.spilled);
+        while let Some(batch) = spilled.pop_front() {
+            let next_time = spilled.front().map_or(i64::MAX, SpilledBatch::min_time);
+            let length = match &pending {
+                Some(rows) => rows_before(rows, next_time)?,
+                None => 0,
             };
-            Ok(Some(prefix))
+            if length == 0 {
+                // None of the pending rows belong in this batch, so it doesn't
+                // need to be rewritten.
+                self.spilled.push_back(batch);
+                continue;
+            }
+
+            let rows = pending.take().expect("pending rows");
+            let (before, after) = rows.split(length)?;
+            pending = (after.len() > 0).then_some(after);
+            let batch = merge_batches(before, batch.try_load()?)?;
+            self.push_spilled(batch, chunk_rows)?;
+        }
+        if let Some(rows) = pending {
+            self.push_spilled(rows, chunk_rows)?;
+        }
+
+        self.memory.free();
+        Ok(())
+    }
+
+    /// Spill the rows after the other spilled rows.
+    ///
+    /// The rows are spilled in batches of about `chunk_rows`. Rows at the same
+    /// time are kept in the same batch, so every row of a spilled batch is
+    /// before the rows of the following batch.
+    fn push_spilled(&mut self, mut rows: InputBatch, chunk_rows: usize) -> anyhow::Result<()> {
+        while rows.len() > 0 {
+            let times: &TimestampNanosecondArray = downcast_primitive_array(rows.time.as_ref())?;
+            let times = times.values();
+            let mut length = chunk_rows.min(times.len());
+            while length < times.len() && times[length] == times[length - 1] {
+                length += 1;
+            }
+
+            let (prefix, suffix) = rows.split(length)?;
+            self.spilled.push_back(SpilledBatch::try_spill(&prefix)?);
+            rows = suffix;
+        }
+        Ok(())
+    }
+
+    /// Load the first spilled batch and add it to the pending rows.
+    ///
+    /// If they don't fit within the budget together, the pending rows are
+    /// spilled along with the loaded rows, and the next load is of a single
+    /// spilled batch. This reports `MemoryBudgetExceeded` if even that
+    /// doesn't fit.
+    fn load_spilled(&mut self) -> anyhow::Result<()> {
+        let Some(spilled) = self.spilled.pop_front() else {
+            return Ok(());
+        };
+        let spilled = spilled.try_load()?;
+
+        if let Some(pending) = self.pending.take() {
+            let pending = merge_batches(spilled, pending)?;
+            let pending_size = pending.memory_size();
+            self.pending = Some(pending);
+            if self.memory.try_resize(pending_size).is_err() {
+                self.spill_pending()?;
+            }
         } else {
-            Ok(None)
+            self.memory.try_resize(spilled.memory_size())?;
+            self.pending = Some(spilled);
         }
+        Ok(())
     }
 
     /// TODO: This is an artifact of the old operation API. It may be possible
@@ -557,6 +652,11 @@ impl ShiftToColumnOperation {
     /// within the `execute` logic.
     async fn try_next(&mut self) -> anyhow::Result<Option<InputBatch>> {
         loop {
+            // Continue outputting the rows for the previous incoming batch.
+            if let Some(output) = self.next_output()? {
+                return Ok(Some(output));
+            }
+
             if let Some(incoming) = self.incoming_stream.next().await {
                 let result = self.create_input(incoming)?;
                 if result.is_some() {
@@ -567,44 +667,131 @@ impl ShiftToColumnOperation {
                     // incoming batch.
                     return Ok(result);
                 }
+            } else if self.pending.is_some() || !self.spilled.is_empty() {
+                // Once the incoming stream has ended, all of the remaining
+                // rows may be output. They're output in chunks, loading the
+                // spilled rows as they are reached.
+                self.output_range = Some(self.output_range(i64::MAX, i64::MAX));
             } else {
-                // If there is anything in the pending batch, this will output it.
-                // We'll only reach this case if the incoming stream has reached the end,
-                // so we can output the pending batch. The next call to `try_next` should
-                // see that `computed.pending.take()` returns `None`, indicating there is
-                // nothing else.
-
-                let result = self.pending.take();
-                // Update the subsort column to be monotonically increasing from 0,
-                // to match the pattern of the merged results. We don't update the
-                // subsort column in the pending batch, as it helps keep the correct
-                // ordering before they're output.
-                if let Some(result) = result {
-                    let subsort =
-                        Arc::new(UInt64Array::from_iter_values(0..(result.time.len() as u64)));
-                    // Fix the bounds based on the generated subsort column above.
-                    let mut lower_bound = result.lower_bound;
-                    lower_bound.subsort = 0;
-                    let mut upper_bound = result.upper_bound;
-                    upper_bound.subsort = result.time.len() as u64;
-
-                    return Ok(Some(InputBatch {
-                        time: result.time,
-                        subsort,
-                        key_hash: result.key_hash,
-                        grouping: result.grouping,
-                        input_columns: result.input_columns,
-                        lower_bound,
-                        upper_bound,
-                    }));
-                } else {
-                    return Ok(None);
-                }
+                return Ok(None);
             }
         }
     }
 }
 
+/// The rows being output in response to an incoming batch.
+#[derive(Debug, Clone, Copy)]
+struct OutputRange {
+    /// The lower bound time of the next output.
+    lower_bound_time: i64,
+    /// Pending rows before this time are output.
+    upper_bound_time: i64,
+    /// The subsort of the next row output.
+    ///
+    /// Rows output in response to later incoming batches have later times,
+    /// so the subsort starts from 0 for each range.
+    next_subsort: u64,
+    /// The maximum number of rows to output at once.
+    chunk_rows: usize,
+}
+
+impl OutputRange {
+    /// Create the output batch from (non-empty) rows in the range.
+    fn output_batch(&mut self, rows: InputBatch) -> anyhow::Result<InputBatch> {
+        let num_rows = rows.len() as u64;
+        let subsort: ArrayRef = Arc::new(UInt64Array::from_iter_values(
+            self.next_subsort..(self.next_subsort + num_rows),
+        ));
+
+        // Fix the bounds based on the generated subsort column above. The
+        // bounds of the rows may be those of a larger batch they were split
+        // from, so they are determined from the first and last rows.
+        let key_triples =
+            KeyTriples::try_new(rows.time.clone(), subsort.clone(), rows.key_hash.clone())?;
+        let mut lower_bound = key_triples.value(0);
+        lower_bound.time = cmp::min(self.lower_bound_time, lower_bound.time);
+        let mut upper_bound = key_triples.value(key_triples.len() - 1);
+        upper_bound.time = cmp::min(self.upper_bound_time, upper_bound.time);
+
+        // Only the first output in the range extends to its lower bound.
+        self.lower_bound_time = i64::MAX;
+        self.next_subsort += num_rows;
+
+        Ok(InputBatch {
+            time: rows.time,
+            subsort,
+            key_hash: rows.key_hash,
+            grouping: rows.grouping,
+            input_columns: rows.input_columns,
+            lower_bound,
+            upper_bound,
+        })
+    }
+}
+
+/// Return the number of rows in the (sorted) batch before `time`.
+fn rows_before(batch: &InputBatch, time: i64) -> anyhow::Result<usize> {
+    let times: &TimestampNanosecondArray = downcast_primitive_array(batch.time.as_ref())?;
+    Ok(times.values().partition_point(|row_time| *row_time < time))
+}
+
+/// Merge two (sorted, non-empty) batches of pending rows.
+fn merge_batches(left: InputBatch, right: InputBatch) -> anyhow::Result<InputBatch> {
+    let merge_result = crate::merge::binary_merge(left.as_merge_input()?, right.as_merge_input()?)?;
+
+    // TODO: Binary merge optimization opportunity.
+    // HACK: We'd like the binary merge to return a boolean array `BooleanArray`
+    // indicating whether to use values from the left/right. Since it doesn't, we
+    // create one using the `is_not_null` kernel.
+    //
+    // NOTE: since we generated unique subsort indices, we know that any shifted
+    // row is either on the left or right, but not both. Thus, we only need to
+    // examine the left.
+    let take_left = arrow::compute::is_not_null(&merge_result.take_a)?;
+    let group_indices = spread_zip(
+        &take_left,
+        left.grouping.group_indices(),
+        right.grouping.group_indices(),
+    )?;
+    let group_indices: &UInt32Array = downcast_primitive_array(group_indices.as_ref())?;
+    // TODO: We shouldn't need to clone the array. Instead, we should be able
+    // to pass an owned reference to the grouping indices. But... the clone
+    // shouldn't be too expensive, and that would require API hanges.
+    let group_indices: UInt32Array = UInt32Array::from(group_indices.data().clone());
+    let num_groups = left.grouping.num_groups().max(right.grouping.num_groups());
+    let grouping = GroupingIndices::new(num_groups, group_indices);
+
+    let input_columns = left
+        .input_columns
+        .iter()
+        .zip_eq(right.input_columns)
+        .map(|(left, right)| {
+            spread_zip(&take_left, left.as_ref(), right.as_ref()).context("zip for shift_to")
+        })
+        .try_collect()?;
+
+    anyhow::ensure!(
+        !merge_result.time.is_empty(),
+        "Expected non-empty merge result"
+    );
+
+    let time = Arc::new(merge_result.time);
+    let subsort = Arc::new(merge_result.subsort);
+    let key_hash = Arc::new(merge_result.key_hash);
+    let key_triples = KeyTriples::try_new(time.clone(), subsort.clone(), key_hash.clone())?;
+    let lower_bound = key_triples.value(0);
+    let upper_bound = key_triples.value(key_triples.len() - 1);
+    Ok(InputBatch {
+        time,
+        subsort,
+        key_hash,
+        grouping,
+        input_columns,
+        lower_bound,
+        upper_bound,
+    })
+}
+
 #[async_trait]
 impl Operation for ShiftToColumnOperation {
     fn restore_from(
@@ -625,12 +812,14 @@ impl Operation for ShiftToColumnOperation {
         &mut self,
         sender: tokio::sync::mpsc::Sender<InputBatch>,
     ) -> error_stack::Result<(), Error> {
-        while let Some(input) = self
-            .try_next()
-            .await
-            .into_report()
-            .change_context(Error::internal())?
-        {
+        while let Some(input) = into_operation_report(self.try_next().await)? {
+            let buffered_rows = self.pending.as_ref().map_or(0, InputBatch::len)
+                + self
+                    .spilled
+                    .iter()
+                    .map(SpilledBatch::num_rows)
+                    .sum::<usize>();
+            self.memory.set_rows(buffered_rows);
             sender
                 .send(input)
                 .await
@@ -640,3 +829,198 @@ impl Operation for ShiftToColumnOperation {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::Int64Array;
+    use arrow::datatypes::{ArrowPrimitiveType, DataType, Field, Schema, TimestampNanosecondType};
+
+    use super::*;
+    use crate::execute::memory::{MemoryBudget, MemoryBudgetExceeded};
+
+    fn input_batch(times: &[i64]) -> InputBatch {
+        let time: ArrayRef = Arc::new(TimestampNanosecondArray::from(times.to_vec()));
+        let subsort: ArrayRef = Arc::new(UInt64Array::from_iter_values(0..times.len() as u64));
+        let key_hash: ArrayRef = Arc::new(UInt64Array::from_iter_values(
+            std::iter::repeat(7).take(times.len()),
+        ));
+        let values: ArrayRef = Arc::new(Int64Array::from(times.to_vec()));
+        let key_triples =
+            KeyTriples::try_new(time.clone(), subsort.clone(), key_hash.clone()).unwrap();
+        InputBatch {
+            time,
+            subsort,
+            key_hash,
+            grouping: GroupingIndices::new(1, UInt32Array::from(vec![0; times.len()])),
+            input_columns: vec![values],
+            lower_bound: key_triples.value(0),
+            upper_bound: key_triples.value(times.len() - 1),
+        }
+    }
+
+    fn operation(memory_budget: &MemoryBudget) -> ShiftToColumnOperation {
+        let (_, receiver) = tokio::sync::mpsc::channel(1);
+        operation_with_input(memory_budget, receiver)
+    }
+
+    fn operation_with_input(
+        memory_budget: &MemoryBudget,
+        receiver: tokio::sync::mpsc::Receiver<Batch>,
+    ) -> ShiftToColumnOperation {
+        ShiftToColumnOperation {
+            shift_time_column: 3,
+            pending: None,
+            spilled: VecDeque::new(),
+            output_range: None,
+            memory: memory_budget.reservation(1, "shift_to"),
+            incoming_stream: ReceiverStream::new(receiver),
+            helper: SingleConsumerHelper::try_new(0, &[]).unwrap(),
+        }
+    }
+
+    /// Create an incoming batch with rows at `times` shifted to `shifted_times`.
+    ///
+    /// The times are also used as the subsort, so the shifted rows are unique.
+    fn incoming_batch(times: &[i64], shifted_times: &[i64]) -> Batch {
+        let schema = Schema::new(vec![
+            Field::new("_time", TimestampNanosecondType::DATA_TYPE, false),
+            Field::new("_subsort", DataType::UInt64, false),
+            Field::new("_key_hash", DataType::UInt64, false),
+            Field::new("shifted", TimestampNanosecondType::DATA_TYPE, true),
+        ]);
+        let record_batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(TimestampNanosecondArray::from(times.to_vec())),
+                Arc::new(UInt64Array::from_iter_values(
+                    times.iter().map(|time| *time as u64),
+                )),
+                Arc::new(UInt64Array::from_iter_values(
+                    std::iter::repeat(7).take(times.len()),
+                )),
+                Arc::new(TimestampNanosecondArray::from(shifted_times.to_vec())),
+            ],
+        )
+        .unwrap();
+        Batch::try_new_from_batch(record_batch).unwrap()
+    }
+
+    fn times(batch: &InputBatch) -> Vec<i64> {
+        let times: &TimestampNanosecondArray =
+            downcast_primitive_array(batch.time.as_ref()).unwrap();
+        times.values().to_vec()
+    }
+
+    #[test]
+    fn test_spill_pending_rows_over_budget() {
+        let pending = input_batch(&[5, 6, 6, 8]);
+        let row_size = pending.compact().unwrap().memory_size() / 4;
+
+        // The budget only allows a few rows, so the pending rows must be
+        // spilled.
+        let memory_budget = MemoryBudget::new((3 * row_size) as u64);
+        let mut operation = operation(&memory_budget);
+        operation.add_input(pending).unwrap();
+        assert!(operation.pending.is_none());
+        assert_eq!(memory_budget.used(), 0);
+
+        // Spilling more rows merges them into the spilled rows, which are
+        // spilled in small batches without splitting a time.
+        operation
+            .add_input(input_batch(&[1, 2, 3, 6, 7, 9]))
+            .unwrap();
+        assert!(operation.pending.is_none());
+        let spilled: Vec<_> = std::mem::take(&mut operation.spilled)
+            .into_iter()
+            .map(|spilled| times(&spilled.try_load().unwrap()))
+            .collect();
+        assert_eq!(
+            spilled,
+            vec![
+                vec![1],
+                vec![2],
+                vec![3],
+                vec![5],
+                vec![6, 6, 6],
+                vec![7],
+                vec![8],
+                vec![9]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_output_over_budget() {
+        let pending = input_batch(&[1, 2]);
+        let row_size = pending.compact().unwrap().memory_size() / 2;
+
+        // A single row doesn't fit within the budget, so the spilled rows
+        // can't be loaded.
+        let memory_budget = MemoryBudget::new(row_size as u64 - 1);
+        let mut operation = operation(&memory_budget);
+        operation.add_input(pending).unwrap();
+        operation.output_range = Some(operation.output_range(0, 5));
+        let error = operation.next_output().unwrap_err();
+
+        let error = error.downcast::<MemoryBudgetExceeded>().unwrap();
+        assert_eq!(error.operation_label, "shift_to");
+        assert_eq!(memory_budget.used(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_spilled_rows_several_times_budget() {
+        // Shift every row far into the future, so nothing is output until
+        // the end of the input and all of the rows are buffered.
+        let num_batches = 40;
+        let batch_rows = 100;
+        let batches: Vec<_> = (0..num_batches)
+            .map(|batch| {
+                let times: Vec<_> = (0..batch_rows)
+                    .map(|row| batch * batch_rows + row)
+                    .collect();
+                let shifted_times: Vec<_> = times
+                    .iter()
+                    .map(|time| 1_000_000 + (time * 7919) % 2000)
+                    .collect();
+                incoming_batch(&times, &shifted_times)
+            })
+            .collect();
+
+        let row_size = input_batch(&[0; 100]).compact().unwrap().memory_size() / 100;
+        let total_size = (num_batches * batch_rows) as usize * row_size;
+        let budget = total_size / 10;
+        let memory_budget = MemoryBudget::new(budget as u64);
+
+        let (input_tx, input_rx) = tokio::sync::mpsc::channel(batches.len());
+        for batch in batches {
+            input_tx.send(batch).await.unwrap();
+        }
+        drop(input_tx);
+
+        let (output_tx, mut output_rx) = tokio::sync::mpsc::channel(10);
+        let mut operation = operation_with_input(&memory_budget, input_rx);
+        let execute = tokio::spawn(async move { operation.execute(output_tx).await });
+
+        let mut output_times = Vec::new();
+        let mut last_bound = None;
+        while let Some(output) = output_rx.recv().await {
+            if output.len() == 0 {
+                continue;
+            }
+            assert!(output.compact().unwrap().memory_size() <= budget);
+            if let Some(last_bound) = last_bound {
+                assert!(last_bound < output.lower_bound);
+            }
+            last_bound = Some(output.upper_bound);
+            output_times.extend(times(&output));
+        }
+        execute.await.unwrap().unwrap();
+
+        let mut expected: Vec<_> = (0..num_batches * batch_rows)
+            .map(|time| 1_000_000 + (time * 7919) % 2000)
+            .collect();
+        expected.sort();
+        assert_eq!(output_times, expected);
+        assert_eq!(memory_budget.used(), 0);
+    }
+}
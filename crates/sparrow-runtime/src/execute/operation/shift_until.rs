@@ -8,15 +8,18 @@ use async_trait::async_trait;
 use error_stack::{IntoReport, IntoReportCompat, ResultExt};
 use futures::StreamExt;
 use itertools::Itertools;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use sparrow_api::kaskada::v1alpha::operation_plan;
 use sparrow_core::{downcast_boolean_array, downcast_primitive_array, KeyTriples};
 use sparrow_instructions::{ComputeStore, StoreKey};
 use tokio_stream::wrappers::ReceiverStream;
+use tracing::info;
 
 use super::BoxedOperation;
+use crate::execute::memory::{arrays_memory_size, into_operation_report, MemoryReservation};
 use crate::execute::operation::expression_executor::InputColumn;
 use crate::execute::operation::single_consumer_helper::SingleConsumerHelper;
+use crate::execute::operation::spill::SpilledRecordBatch;
 use crate::execute::operation::{InputBatch, Operation};
 use crate::execute::{invalid_operation, Error};
 use crate::key_hash_index::KeyHashIndex;
@@ -41,6 +44,12 @@ pub(super) struct ShiftUntilOperation {
     outgoing_schema: Arc<Schema>,
     pending: Vec<RetainedBatch>,
     subsort_start: u64,
+    /// The memory reserved for the retained batches held in memory.
+    ///
+    /// When the retained batches exceed the budget they are spilled to disk,
+    /// and loaded back once a row with one of their keys satisfies the
+    /// condition.
+    memory: MemoryReservation,
 }
 
 impl ShiftUntilOperation {
@@ -48,6 +57,7 @@ impl ShiftUntilOperation {
         operation: operation_plan::ShiftUntilOperation,
         input_channels: Vec<tokio::sync::mpsc::Receiver<Batch>>,
         input_columns: &[InputColumn],
+        memory: MemoryReservation,
     ) -> error_stack::Result<BoxedOperation, super::Error> {
         let mut pending_schema_fields = vec![Field::new(
             "entity_hash",
@@ -94,6 +104,7 @@ impl ShiftUntilOperation {
             outgoing_schema,
             pending: vec![],
             subsort_start: 0,
+            memory,
         }))
     }
 
@@ -155,10 +166,14 @@ impl ShiftUntilOperation {
             self.pending.push(retained);
         }
 
+        // Retained batches with no remaining rows will never be emitted.
+        self.pending.retain(|retained| retained.remaining_rows > 0);
+
         let final_output_record_batch = arrow::compute::kernels::concat::concat_batches(
             &self.outgoing_schema,
             &output_batches,
         )?;
+        self.fit_to_budget(&final_output_record_batch)?;
 
         if final_output_record_batch.num_rows() == 0 {
             let empty_record_batch = RecordBatch::new_empty(self.outgoing_schema.clone());
@@ -169,6 +184,29 @@ impl ShiftUntilOperation {
         }
     }
 
+    /// Update the memory reserved for the retained rows and the `output`.
+    ///
+    /// If they don't fit within the budget, the retained rows are spilled to
+    /// disk. The output rows are needed now, so if they don't fit after
+    /// spilling this reports `MemoryBudgetExceeded`.
+    fn fit_to_budget(&mut self, output: &RecordBatch) -> anyhow::Result<()> {
+        let output_size = arrays_memory_size(output.columns());
+        let retained_size: usize = self.pending.iter().map(RetainedBatch::memory_size).sum();
+        if self.memory.try_resize(retained_size + output_size).is_ok() {
+            return Ok(());
+        }
+
+        info!(
+            num_bytes = retained_size,
+            "shift_until spilling retained rows"
+        );
+        for retained in self.pending.iter_mut() {
+            retained.batch.spill()?;
+        }
+        self.memory.try_resize(output_size)?;
+        Ok(())
+    }
+
     // `self.helper` contains functions that rely on the original input column index
     // given at the operation's creation time. We altered the indeces thus this
     // helper function.
@@ -294,11 +332,13 @@ impl Operation for ShiftUntilOperation {
         sender: tokio::sync::mpsc::Sender<InputBatch>,
     ) -> error_stack::Result<(), Error> {
         while let Some(incoming) = self.incoming_stream.next().await {
-            if let Some(input) = self
-                .create_input(incoming)
-                .into_report()
-                .change_context(Error::internal())?
-            {
+            if let Some(input) = into_operation_report(self.create_input(incoming))? {
+                let buffered_rows = self
+                    .pending
+                    .iter()
+                    .map(|retained| retained.remaining_rows)
+                    .sum();
+                self.memory.set_rows(buffered_rows);
                 sender
                     .send(input)
                     .await
@@ -398,7 +438,7 @@ impl CurrentBatch {
         let keys: HashSet<u64> = keys.iter().flatten().collect();
 
         Ok(Some(RetainedBatch {
-            batch: remaining,
+            batch: RetainedRows::InMemory(remaining),
             keys,
             remaining_rows,
         }))
@@ -409,15 +449,15 @@ impl CurrentBatch {
 ///
 /// Rows in this batch are stored until such time the predicate for a given
 /// entity evaluates to true.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct RetainedBatch {
     /// The retained data.
     ///
     /// This may include data that has already been emitted (with keys that are
     /// no longer in the key set). This allows us to avoid excessive
     /// recreation of the batch.
-    #[serde(with = "sparrow_arrow::serde::record_batch")]
-    batch: RecordBatch,
+    #[serde(with = "RetainedRows")]
+    batch: RetainedRows,
     /// Keys part of the retained batch that have not been emitted.
     keys: HashSet<u64>,
     /// The number of rows in the batch that have not been emitted.
@@ -428,6 +468,14 @@ struct RetainedBatch {
 }
 
 impl RetainedBatch {
+    /// Return the memory used by the rows of this batch.
+    fn memory_size(&self) -> usize {
+        match &self.batch {
+            RetainedRows::InMemory(batch) => arrays_memory_size(batch.columns()),
+            RetainedRows::Spilled(_) => 0,
+        }
+    }
+
     /// Return the batch to emit for the given key hash.
     ///
     /// Includes all rows from this batch that haven't already been emitted,
@@ -439,10 +487,11 @@ impl RetainedBatch {
 
             // If the key was present, we haven't sent rows for this key prior.
             // Find all rows matching the key, and return them.
-            let key_arr: &UInt64Array = downcast_primitive_array(self.batch.columns()[0].as_ref())?;
+            let batch = self.batch.load()?;
+            let key_arr: &UInt64Array = downcast_primitive_array(batch.columns()[0].as_ref())?;
             let row_filter = arrow::compute::eq_scalar(key_arr, key_hash)?;
             let rows_to_emit =
-                arrow::compute::kernels::filter::filter_record_batch(&self.batch, &row_filter)?;
+                arrow::compute::kernels::filter::filter_record_batch(batch, &row_filter)?;
 
             // Subtract the number of rows we're emitting; allows management/optimization of
             // retained batches.
@@ -455,6 +504,53 @@ impl RetainedBatch {
     }
 }
 
+/// The rows of a [RetainedBatch], which may have been spilled to disk.
+///
+/// Spilled rows are loaded back into memory when they need to be emitted or
+/// stored in a snapshot.
+#[derive(Debug)]
+enum RetainedRows {
+    InMemory(RecordBatch),
+    Spilled(SpilledRecordBatch),
+}
+
+impl RetainedRows {
+    /// Return the rows, loading them into memory if they were spilled.
+    fn load(&mut self) -> anyhow::Result<&RecordBatch> {
+        if let Self::Spilled(spilled) = self {
+            *self = Self::InMemory(spilled.try_load()?);
+        }
+        match self {
+            Self::InMemory(batch) => Ok(batch),
+            Self::Spilled(_) => unreachable!("spilled rows were loaded"),
+        }
+    }
+
+    /// Spill the rows to disk, if they are in memory.
+    fn spill(&mut self) -> anyhow::Result<()> {
+        if let Self::InMemory(batch) = self {
+            *self = Self::Spilled(SpilledRecordBatch::try_spill(batch)?);
+        }
+        Ok(())
+    }
+
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::InMemory(batch) => {
+                sparrow_arrow::serde::record_batch::serialize(batch, serializer)
+            }
+            Self::Spilled(spilled) => {
+                let batch = spilled.try_load().map_err(serde::ser::Error::custom)?;
+                sparrow_arrow::serde::record_batch::serialize(&batch, serializer)
+            }
+        }
+    }
+
+    fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        sparrow_arrow::serde::record_batch::deserialize(deserializer).map(Self::InMemory)
+    }
+}
+
 /// Given a record batch with the `retained_schema`, add the output time and
 /// subsort to make a batch with `output_schema`
 fn retained_to_output_batch(
@@ -480,3 +576,112 @@ fn retained_to_output_batch(
     }
     Ok(RecordBatch::try_new(output_schema.clone(), columns)?)
 }
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{ArrayRef, Int64Array};
+    use arrow::datatypes::DataType;
+
+    use super::*;
+    use crate::execute::memory::{MemoryBudget, MemoryBudgetExceeded};
+
+    fn record_batch(keys: &[u64], values: &[i64]) -> RecordBatch {
+        RecordBatch::try_from_iter([
+            (
+                "entity_hash",
+                Arc::new(UInt64Array::from(keys.to_vec())) as ArrayRef,
+            ),
+            ("0", Arc::new(Int64Array::from(values.to_vec())) as ArrayRef),
+        ])
+        .unwrap()
+    }
+
+    fn retained_batch(keys: &[u64], values: &[i64]) -> RetainedBatch {
+        let batch = record_batch(keys, values);
+        CurrentBatch {
+            already_emitted: BooleanArray::from(vec![false; batch.num_rows()]),
+            batch,
+        }
+        .retain()
+        .unwrap()
+        .unwrap()
+    }
+
+    fn operation(memory_budget: &MemoryBudget) -> ShiftUntilOperation {
+        let (_, receiver) = tokio::sync::mpsc::channel(1);
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("entity_hash", DataType::UInt64, false),
+            Field::new("0", DataType::Int64, true),
+        ]));
+        ShiftUntilOperation {
+            condition_input_column: 4,
+            incoming_stream: ReceiverStream::new(receiver),
+            helper: SingleConsumerHelper::try_new(0, &[]).unwrap(),
+            retained_schema: schema.clone(),
+            outgoing_schema: schema,
+            pending: vec![],
+            subsort_start: 0,
+            memory: memory_budget.reservation(1, "shift_until"),
+        }
+    }
+
+    #[test]
+    fn test_spill_retained_rows_over_budget() {
+        let output = record_batch(&[3], &[10]);
+        let output_size = arrays_memory_size(output.columns());
+
+        // The budget only allows the output rows, so the retained rows must
+        // be spilled.
+        let memory_budget = MemoryBudget::new(output_size as u64);
+        let mut operation = operation(&memory_budget);
+        operation.pending = vec![retained_batch(&[1, 2, 1], &[5, 6, 7])];
+        operation.fit_to_budget(&output).unwrap();
+
+        assert!(matches!(
+            operation.pending[0].batch,
+            RetainedRows::Spilled(_)
+        ));
+        assert_eq!(operation.pending[0].memory_size(), 0);
+        assert_eq!(memory_budget.used(), output_size);
+
+        // Snapshots include the spilled rows.
+        let bytes = bincode::serialize(&operation.pending).unwrap();
+        let restored: Vec<RetainedBatch> = bincode::deserialize(&bytes).unwrap();
+        assert!(matches!(
+            &restored[0].batch,
+            RetainedRows::InMemory(batch) if batch == &record_batch(&[1, 2, 1], &[5, 6, 7])
+        ));
+
+        // Emitting a key loads the spilled rows.
+        let emitted = operation.pending[0].batch_to_emit(1).unwrap().unwrap();
+        assert_eq!(emitted, record_batch(&[1, 1], &[5, 7]));
+        assert_eq!(operation.pending[0].remaining_rows, 1);
+        assert!(matches!(
+            operation.pending[0].batch,
+            RetainedRows::InMemory(_)
+        ));
+        assert!(operation.pending[0].batch_to_emit(1).unwrap().is_none());
+        assert_eq!(
+            operation.pending[0].batch_to_emit(2).unwrap().unwrap(),
+            record_batch(&[2], &[6])
+        );
+    }
+
+    #[test]
+    fn test_output_over_budget() {
+        let output = record_batch(&[3], &[10]);
+        let output_size = arrays_memory_size(output.columns());
+
+        // Spilling the retained rows doesn't help, since the output rows
+        // don't fit within the budget.
+        let memory_budget = MemoryBudget::new(output_size as u64 - 1);
+        let mut operation = operation(&memory_budget);
+        operation.pending = vec![retained_batch(&[1, 2, 1], &[5, 6, 7])];
+        let error = operation.fit_to_budget(&output).unwrap_err();
+
+        let error = error.downcast::<MemoryBudgetExceeded>().unwrap();
+        assert_eq!(error.operation_label, "shift_until");
+        assert_eq!(error.requested, output_size);
+        assert_eq!(memory_budget.used(), 0);
+    }
+}
@@ -0,0 +1,232 @@
+use arrow::array::{BooleanArray, StringArray, UInt64Array};
+use arrow::datatypes::DataType;
+use error_stack::{IntoReport, IntoReportCompat, ResultExt};
+use futures::{StreamExt, TryStreamExt};
+use hashbrown::{HashMap, HashSet};
+use sparrow_api::kaskada::v1alpha::slice_plan::Slice;
+use sparrow_compiler::DataContext;
+use sparrow_core::{downcast_primitive_array, downcast_string_array};
+use sparrow_qfr::FlightRecorder;
+
+use crate::data_manager::DataManager;
+use crate::execute::Error;
+use crate::prepare::slice_preparer::{percent_upper_bound, sample_hash};
+use crate::table_reader::table_reader;
+use crate::Batch;
+
+/// The entities in a slice which is determined by the rows of a table.
+///
+/// This is computed once per query by reading the table named in the slice,
+/// and used to filter every scan of the sliced grouping, so each table is
+/// sliced to the same entities.
+#[derive(Debug)]
+pub(crate) enum SliceEntities {
+    /// The entities with a row matching the dimension slice.
+    Dimension { key_hashes: HashSet<u64> },
+    /// The upper bound of the sample for each entity with a stratum.
+    ///
+    /// Entities without a stratum use the `default_upper_bound`.
+    Stratified {
+        upper_bounds: HashMap<u64, u64>,
+        default_upper_bound: u64,
+        seed: u64,
+    },
+}
+
+impl SliceEntities {
+    /// Read the entities in the slice from the table it references.
+    ///
+    /// The entire table is read, regardless of the snapshot or time bounds
+    /// of the query, so the entities are the same when resuming.
+    pub(super) async fn try_new(
+        data_manager: &mut DataManager,
+        data_context: &DataContext,
+        slice: &Slice,
+    ) -> error_stack::Result<Self, Error> {
+        let (table_name, column) = match slice {
+            Slice::Stratified(stratified) => (&stratified.table_name, &stratified.column),
+            Slice::Dimension(dimension) => (&dimension.table_name, &dimension.column),
+            _ => error_stack::bail!(Error::internal_msg("slice isn't determined by a table")),
+        };
+
+        let table_info = data_context
+            .table_id(table_name)
+            .and_then(|table_id| data_context.table_info(table_id))
+            .ok_or_else(|| {
+                crate::execute::error::invalid_operation!(
+                    "slice references undefined table '{table_name}'"
+                )
+            })?;
+
+        let mut batches = table_reader(
+            data_manager,
+            table_info,
+            &None,
+            Some(vec![column.clone()]),
+            FlightRecorder::disabled(),
+            None,
+            None,
+            None,
+        )
+        .change_context(Error::internal_msg("failed to create slice table reader"))?
+        .boxed();
+
+        let dimension_values: HashSet<&str> = match slice {
+            Slice::Dimension(dimension) => dimension.values.iter().map(String::as_str).collect(),
+            _ => HashSet::new(),
+        };
+        let mut key_hashes = HashSet::new();
+        let mut strata = HashMap::new();
+        while let Some(batch) = batches
+            .try_next()
+            .await
+            .change_context(Error::internal_msg("failed to read slice table"))?
+        {
+            let batch_key_hashes: &UInt64Array = downcast_primitive_array(batch.column(2).as_ref())
+                .into_report()
+                .change_context(Error::internal())?;
+            let batch_values = arrow::compute::cast(batch.column(3), &DataType::Utf8)
+                .into_report()
+                .change_context(Error::internal())?;
+            let batch_values: &StringArray = downcast_string_array(batch_values.as_ref())
+                .into_report()
+                .change_context(Error::internal())?;
+
+            for (key_hash, value) in batch_key_hashes.values().iter().zip(batch_values.iter()) {
+                let Some(value) = value else { continue };
+                if matches!(slice, Slice::Dimension(_)) {
+                    if dimension_values.contains(value) {
+                        key_hashes.insert(*key_hash);
+                    }
+                } else {
+                    // Rows are read in order, so this keeps the most recent value.
+                    strata.insert(*key_hash, value.to_owned());
+                }
+            }
+        }
+
+        match slice {
+            Slice::Stratified(stratified) => {
+                let stratum_upper_bounds: HashMap<&str, u64> = stratified
+                    .strata
+                    .iter()
+                    .map(|stratum| (stratum.value.as_str(), percent_upper_bound(stratum.percent)))
+                    .collect();
+                let default_upper_bound = percent_upper_bound(stratified.default_percent);
+                let upper_bounds = strata
+                    .into_iter()
+                    .map(|(key_hash, value)| {
+                        let upper_bound = stratum_upper_bounds
+                            .get(value.as_str())
+                            .copied()
+                            .unwrap_or(default_upper_bound);
+                        (key_hash, upper_bound)
+                    })
+                    .collect();
+                Ok(Self::Stratified {
+                    upper_bounds,
+                    default_upper_bound,
+                    seed: stratified.seed,
+                })
+            }
+            _ => Ok(Self::Dimension { key_hashes }),
+        }
+    }
+
+    /// Return whether the entity with the given key hash is in the slice.
+    fn contains(&self, key_hash: u64) -> bool {
+        match self {
+            Self::Dimension { key_hashes } => key_hashes.contains(&key_hash),
+            Self::Stratified {
+                upper_bounds,
+                default_upper_bound,
+                seed,
+            } => {
+                let upper_bound = upper_bounds.get(&key_hash).unwrap_or(default_upper_bound);
+                sample_hash(key_hash, *seed) <= *upper_bound
+            }
+        }
+    }
+
+    /// Filter the batch to rows of entities in the slice.
+    pub(super) fn filter_batch(&self, batch: Batch) -> error_stack::Result<Batch, Error> {
+        let key_hashes: &UInt64Array = downcast_primitive_array(batch.column(2).as_ref())
+            .into_report()
+            .change_context(Error::internal())?;
+        let include: BooleanArray = key_hashes
+            .values()
+            .iter()
+            .map(|key_hash| Some(self.contains(*key_hash)))
+            .collect();
+        let data = arrow::compute::filter_record_batch(batch.data(), &include)
+            .into_report()
+            .change_context(Error::internal())?;
+        Ok(batch.with_data(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::TimestampNanosecondArray;
+    use arrow::datatypes::{ArrowPrimitiveType, Field, Schema, TimestampNanosecondType};
+    use arrow::record_batch::RecordBatch;
+
+    use super::*;
+
+    fn batch(key_hashes: Vec<u64>) -> Batch {
+        let len = key_hashes.len();
+        let schema = Schema::new(vec![
+            Field::new("_time", TimestampNanosecondType::DATA_TYPE, false),
+            Field::new("_subsort", DataType::UInt64, false),
+            Field::new("_key_hash", DataType::UInt64, false),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(TimestampNanosecondArray::from_iter_values(0..len as i64)),
+                Arc::new(UInt64Array::from_iter_values(0..len as u64)),
+                Arc::new(UInt64Array::from(key_hashes)),
+            ],
+        )
+        .unwrap();
+        Batch::try_new_from_batch(batch).unwrap()
+    }
+
+    fn filtered_key_hashes(slice_entities: &SliceEntities, key_hashes: Vec<u64>) -> Vec<u64> {
+        let filtered = slice_entities.filter_batch(batch(key_hashes)).unwrap();
+        let filtered: &UInt64Array = downcast_primitive_array(filtered.column(2).as_ref()).unwrap();
+        filtered.values().to_vec()
+    }
+
+    #[test]
+    fn test_filter_dimension() {
+        let slice_entities = SliceEntities::Dimension {
+            key_hashes: HashSet::from_iter([1, 3]),
+        };
+        assert_eq!(
+            filtered_key_hashes(&slice_entities, vec![1, 2, 3, 1, 4]),
+            vec![1, 3, 1]
+        );
+    }
+
+    #[test]
+    fn test_filter_stratified() {
+        // Entity 1 is in a stratum including all entities, and entity 2 in a
+        // stratum including none. Others use the default.
+        let all_or_nothing = |default_percent| SliceEntities::Stratified {
+            upper_bounds: HashMap::from_iter([(1, u64::MAX), (2, 0)]),
+            default_upper_bound: percent_upper_bound(default_percent),
+            seed: 7,
+        };
+        assert_eq!(
+            filtered_key_hashes(&all_or_nothing(0.0), vec![1, 2, 3, 1]),
+            vec![1, 1]
+        );
+        assert_eq!(
+            filtered_key_hashes(&all_or_nothing(100.0), vec![1, 2, 3, 1]),
+            vec![1, 3, 1]
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Seek, Write};
+use std::sync::Arc;
+
+use anyhow::Context;
+use arrow::array::{Array, ArrayRef, TimestampNanosecondArray, UInt32Array};
+use arrow::datatypes::{Field, Schema};
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use itertools::Itertools;
+use sparrow_core::{downcast_primitive_array, KeyTriple};
+use sparrow_instructions::GroupingIndices;
+
+use crate::execute::operation::InputBatch;
+
+/// A record batch spilled to a local temporary file.
+///
+/// Operations which buffer rows (such as `shift_until`) may spill them when
+/// the buffered rows exceed the memory budget, and load them once they are
+/// needed.
+#[derive(Debug)]
+pub(super) struct SpilledRecordBatch {
+    /// The temporary file containing the rows in Arrow IPC format.
+    ///
+    /// The file is deleted when it is closed.
+    file: File,
+    num_rows: usize,
+}
+
+impl SpilledRecordBatch {
+    /// Write the given batch to a temporary file.
+    ///
+    /// Sliced arrays should be compacted first, since the IPC writer may
+    /// write all of their underlying buffers.
+    pub fn try_spill(record_batch: &RecordBatch) -> anyhow::Result<Self> {
+        let mut file = tempfile::tempfile().context("creating spill file")?;
+        let mut writer = FileWriter::try_new(BufWriter::new(&mut file), &record_batch.schema())?;
+        writer.write(record_batch)?;
+        writer.finish()?;
+        writer
+            .into_inner()?
+            .flush()
+            .context("flushing spill file")?;
+
+        Ok(Self {
+            file,
+            num_rows: record_batch.num_rows(),
+        })
+    }
+
+    /// The number of spilled rows.
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// Read the spilled rows back into memory.
+    ///
+    /// The rows remain in the file, so they may be read again.
+    pub fn try_load(&self) -> anyhow::Result<RecordBatch> {
+        let mut file = self.file.try_clone().context("opening spill file")?;
+        file.rewind()?;
+        let reader = FileReader::try_new(BufReader::new(file), None)?;
+        let schema = reader.schema();
+        let batches: Vec<_> = reader.into_iter().try_collect()?;
+        let record_batch = arrow::compute::concat_batches(&schema, &batches)?;
+        anyhow::ensure!(
+            record_batch.num_rows() == self.num_rows,
+            "Expected {} spilled rows, but read {}",
+            self.num_rows,
+            record_batch.num_rows()
+        );
+        Ok(record_batch)
+    }
+}
+
+/// The rows of an [InputBatch] spilled to a local temporary file.
+///
+/// Used by `shift_to` to spill the pending rows.
+#[derive(Debug)]
+pub(super) struct SpilledBatch {
+    rows: SpilledRecordBatch,
+    num_groups: usize,
+    /// The time of the first row in the batch.
+    min_time: i64,
+    lower_bound: KeyTriple,
+    upper_bound: KeyTriple,
+}
+
+impl SpilledBatch {
+    /// Write the rows of the given (non-empty) batch to a temporary file.
+    pub fn try_spill(batch: &InputBatch) -> anyhow::Result<Self> {
+        anyhow::ensure!(batch.len() > 0, "Unable to spill empty batch");
+
+        let times: &TimestampNanosecondArray = downcast_primitive_array(batch.time.as_ref())?;
+        let min_time = times.value(0);
+
+        // Write the compacted batch, since the IPC writer may write all of the
+        // buffers underlying a sliced array.
+        let batch = batch.compact()?;
+        let group_indices: ArrayRef = Arc::new(batch.grouping.group_indices().clone());
+        let columns: Vec<ArrayRef> = [
+            batch.time.clone(),
+            batch.subsort.clone(),
+            batch.key_hash.clone(),
+            group_indices,
+        ]
+        .into_iter()
+        .chain(batch.input_columns.iter().cloned())
+        .collect();
+        let fields = columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| {
+                Field::new(&format!("{index}"), column.data_type().clone(), true)
+            })
+            .collect();
+        let record_batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?;
+
+        Ok(Self {
+            rows: SpilledRecordBatch::try_spill(&record_batch)?,
+            num_groups: batch.grouping.num_groups(),
+            min_time,
+            lower_bound: batch.lower_bound,
+            upper_bound: batch.upper_bound,
+        })
+    }
+
+    /// The time of the earliest row in the spilled batch.
+    pub fn min_time(&self) -> i64 {
+        self.min_time
+    }
+
+    /// The number of spilled rows.
+    pub fn num_rows(&self) -> usize {
+        self.rows.num_rows()
+    }
+
+    /// Read the spilled rows back into memory.
+    pub fn try_load(self) -> anyhow::Result<InputBatch> {
+        let record_batch = self.rows.try_load()?;
+        let columns = record_batch.columns();
+        let group_indices: &UInt32Array = downcast_primitive_array(columns[3].as_ref())?;
+        Ok(InputBatch {
+            time: columns[0].clone(),
+            subsort: columns[1].clone(),
+            key_hash: columns[2].clone(),
+            grouping: GroupingIndices::new(self.num_groups, group_indices.clone()),
+            input_columns: columns[4..].to_vec(),
+            lower_bound: self.lower_bound,
+            upper_bound: self.upper_bound,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Int64Array, StringArray, UInt64Array};
+
+    use super::*;
+
+    #[test]
+    fn test_spill_round_trip() {
+        let time: ArrayRef = Arc::new(TimestampNanosecondArray::from(vec![5, 6, 7, 8]));
+        let subsort: ArrayRef = Arc::new(UInt64Array::from(vec![0, 1, 2, 3]));
+        let key_hash: ArrayRef = Arc::new(UInt64Array::from(vec![10, 11, 10, 12]));
+        let grouping = GroupingIndices::new(3, UInt32Array::from(vec![0, 1, 0, 2]));
+        let values: ArrayRef = Arc::new(Int64Array::from(vec![Some(1), None, Some(3), Some(4)]));
+        let names: ArrayRef = Arc::new(StringArray::from(vec!["a", "b", "c", "d"]));
+        let batch = InputBatch {
+            time,
+            subsort,
+            key_hash,
+            grouping,
+            input_columns: vec![values, names],
+            lower_bound: KeyTriple {
+                time: 5,
+                subsort: 0,
+                key_hash: 10,
+            },
+            upper_bound: KeyTriple {
+                time: 8,
+                subsort: 3,
+                key_hash: 12,
+            },
+        };
+
+        // Spill a slice, to check only the sliced rows are written.
+        let (_, batch) = batch.split(1).unwrap();
+        let spilled = SpilledBatch::try_spill(&batch).unwrap();
+        assert_eq!(spilled.min_time(), 6);
+
+        let loaded = spilled.try_load().unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded.time.as_ref(), batch.time.as_ref());
+        assert_eq!(loaded.subsort.as_ref(), batch.subsort.as_ref());
+        assert_eq!(loaded.key_hash.as_ref(), batch.key_hash.as_ref());
+        assert_eq!(
+            loaded.grouping.group_indices(),
+            batch.grouping.group_indices()
+        );
+        assert_eq!(loaded.grouping.num_groups(), 3);
+        assert_eq!(loaded.input_columns.len(), 2);
+        for (loaded, original) in loaded.input_columns.iter().zip(batch.input_columns.iter()) {
+            assert_eq!(loaded.as_ref(), original.as_ref());
+        }
+        assert_eq!(loaded.lower_bound, batch.lower_bound);
+        assert_eq!(loaded.upper_bound, batch.upper_bound);
+    }
+
+    #[test]
+    fn test_spilled_record_batch_loads_repeatedly() {
+        let record_batch = RecordBatch::try_from_iter([
+            (
+                "key",
+                Arc::new(UInt64Array::from(vec![1, 2, 1])) as ArrayRef,
+            ),
+            (
+                "value",
+                Arc::new(Int64Array::from(vec![Some(5), None, Some(7)])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        let spilled = SpilledRecordBatch::try_spill(&record_batch).unwrap();
+        assert_eq!(spilled.try_load().unwrap(), record_batch);
+        assert_eq!(spilled.try_load().unwrap(), record_batch);
+    }
+}
@@ -9,6 +9,7 @@ use sparrow_compiler::DataContext;
 
 use crate::data_manager::DataManager;
 use crate::execute::key_hash_inverse::{KeyHashInverse, ThreadSafeKeyHashInverse};
+use crate::execute::memory::MemoryBudget;
 use crate::execute::operation::{OperationContext, OperationExecutor};
 use crate::s3::S3Helper;
 use crate::Batch;
@@ -204,10 +205,18 @@ pub(super) async fn run_operation(
         data_context: DataContext::default(),
         compute_store: None,
         key_hash_inverse,
+        output_key_hash_inverses: Default::default(),
         max_event_in_snapshot: None,
+        min_input_time: None,
+        examples: None,
+        example_labels: vec![],
+        partition: (0, 1),
+        slice_entities: None,
         progress_updates_tx,
         output_at_time: None,
         bounded_lateness_ns: None,
+        memory_budget: MemoryBudget::default(),
+        operation_stats: None,
     };
     executor
         .execute(0, &mut context, inputs, max_event_tx, &Default::default())
@@ -257,10 +266,18 @@ pub(super) async fn run_operation_json(
         data_context: DataContext::default(),
         compute_store: None,
         key_hash_inverse,
+        output_key_hash_inverses: Default::default(),
         max_event_in_snapshot: None,
+        min_input_time: None,
+        examples: None,
+        example_labels: vec![],
+        partition: (0, 1),
+        slice_entities: None,
         progress_updates_tx,
         output_at_time: None,
         bounded_lateness_ns: None,
+        memory_budget: MemoryBudget::default(),
+        operation_stats: None,
     };
     executor
         .execute(0, &mut context, inputs, max_event_tx, &Default::default())
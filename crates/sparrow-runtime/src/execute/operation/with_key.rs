@@ -23,8 +23,9 @@ pub(super) struct WithKeyOperation {
     /// The input stream of batches.
     input_stream: ReceiverStream<Batch>,
     helper: SingleConsumerHelper,
-    key_hash_inverse: Arc<ThreadSafeKeyHashInverse>,
-    is_primary_grouping: bool,
+    /// The key hash inverse to add the new keys to, if the new grouping is
+    /// used to produce output.
+    key_hash_inverse: Option<Arc<ThreadSafeKeyHashInverse>>,
 }
 
 #[async_trait]
@@ -84,7 +85,7 @@ impl WithKeyOperation {
             .new_key
             .ok_or_else(|| invalid_operation!("missing new key"))?
             .input_column as usize;
-        let is_primary_grouping = context.primary_grouping() == operation.grouping;
+        let key_hash_inverse = context.key_hash_inverse_for(&operation.grouping).cloned();
 
         Ok(Box::new(Self {
             new_key_input_index,
@@ -92,8 +93,7 @@ impl WithKeyOperation {
             helper: SingleConsumerHelper::try_new(operation.input, input_columns)
                 .into_report()
                 .change_context(Error::internal_msg("error creating single consumer helper"))?,
-            key_hash_inverse: context.key_hash_inverse.clone(),
-            is_primary_grouping,
+            key_hash_inverse,
         }))
     }
 
@@ -112,9 +112,9 @@ impl WithKeyOperation {
         let subsort = downcast_primitive_array(subsort)?;
 
         // The key hash inverse only needs to know about keys/hashes related to the
-        // primary grouping to produce the key hash inverse for output.
-        if self.is_primary_grouping {
-            self.key_hash_inverse
+        // groupings of the outputs to produce the key hash inverse for output.
+        if let Some(key_hash_inverse) = &self.key_hash_inverse {
+            key_hash_inverse
                 .add(new_keys.to_owned(), &new_key_hashes)
                 .await?;
         }
@@ -0,0 +1,244 @@
+//! Live statistics for each operation, reported in progress messages.
+//!
+//! These are only collected when the request asks for `operation_progress`.
+//! When executing in multiple partitions, each instance of an operation
+//! updates the same statistics, so they are aggregated across partitions.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use pin_project::pin_project;
+use sparrow_api::kaskada::v1alpha::{ComputePlan, OperationProgress};
+
+/// The statistics for each operation in a plan.
+#[derive(Clone, Debug)]
+pub(crate) struct PlanStats(Arc<[Arc<OperationStats>]>);
+
+impl PlanStats {
+    /// Create (empty) statistics for each operation in the plan.
+    pub fn new(plan: &ComputePlan) -> Self {
+        let operations = plan
+            .operations
+            .iter()
+            .map(|operation| {
+                let label = operation
+                    .operator
+                    .as_ref()
+                    .map(|operator| operator.label())
+                    .unwrap_or("unknown");
+                Arc::new(OperationStats::new(label))
+            })
+            .collect();
+        Self(operations)
+    }
+
+    /// Return the statistics for the given operation.
+    pub fn operation(&self, operation_index: usize) -> Arc<OperationStats> {
+        self.0[operation_index].clone()
+    }
+
+    /// Return the current progress of each operation.
+    pub fn to_progress(&self) -> Vec<OperationProgress> {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(index, stats)| stats.to_progress(index as u32))
+            .collect()
+    }
+}
+
+/// The statistics for a single operation.
+#[derive(Debug)]
+pub(crate) struct OperationStats {
+    label: &'static str,
+    input_rows: AtomicI64,
+    input_batches: AtomicI64,
+    output_rows: AtomicI64,
+    output_batches: AtomicI64,
+    busy_nanos: AtomicI64,
+    buffered_bytes: AtomicI64,
+    buffered_rows: AtomicI64,
+    watermark: AtomicI64,
+}
+
+impl OperationStats {
+    fn new(label: &'static str) -> Self {
+        Self {
+            label,
+            input_rows: AtomicI64::new(0),
+            input_batches: AtomicI64::new(0),
+            output_rows: AtomicI64::new(0),
+            output_batches: AtomicI64::new(0),
+            busy_nanos: AtomicI64::new(0),
+            buffered_bytes: AtomicI64::new(0),
+            buffered_rows: AtomicI64::new(0),
+            watermark: AtomicI64::new(0),
+        }
+    }
+
+    /// Record a batch processed by the operation.
+    ///
+    /// `watermark` is the time up to which the input has been processed.
+    pub fn record_batch(&self, input_rows: usize, output_rows: usize, watermark: i64) {
+        self.input_rows
+            .fetch_add(input_rows as i64, Ordering::Relaxed);
+        self.input_batches.fetch_add(1, Ordering::Relaxed);
+        self.output_rows
+            .fetch_add(output_rows as i64, Ordering::Relaxed);
+        self.output_batches.fetch_add(1, Ordering::Relaxed);
+        self.watermark.fetch_max(watermark, Ordering::Relaxed);
+    }
+
+    /// Record time spent executing the operation.
+    pub fn add_busy(&self, busy: Duration) {
+        self.busy_nanos
+            .fetch_add(busy.as_nanos() as i64, Ordering::Relaxed);
+    }
+
+    /// Record a change in the bytes and rows buffered by the operation.
+    ///
+    /// Each partition reports the changes to its own buffers, so the totals
+    /// include the rows buffered by every partition.
+    pub fn add_buffered(&self, bytes: i64, rows: i64) {
+        self.buffered_bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.buffered_rows.fetch_add(rows, Ordering::Relaxed);
+    }
+
+    fn to_progress(&self, operation_index: u32) -> OperationProgress {
+        OperationProgress {
+            operation_index,
+            operation_label: self.label.to_owned(),
+            input_rows: self.input_rows.load(Ordering::Relaxed),
+            input_batches: self.input_batches.load(Ordering::Relaxed),
+            output_rows: self.output_rows.load(Ordering::Relaxed),
+            output_batches: self.output_batches.load(Ordering::Relaxed),
+            busy_nanos: self.busy_nanos.load(Ordering::Relaxed),
+            buffered_bytes: self.buffered_bytes.load(Ordering::Relaxed),
+            buffered_rows: self.buffered_rows.load(Ordering::Relaxed),
+            watermark: self.watermark.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A future recording the time spent polling it as busy time of an operation.
+///
+/// Time spent waiting for inputs (or for consumers to accept outputs) isn't
+/// included, since the future isn't polled while it waits.
+#[pin_project]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub(crate) struct RecordBusy<F> {
+    #[pin]
+    future: F,
+    stats: Option<Arc<OperationStats>>,
+}
+
+impl<F> RecordBusy<F> {
+    pub fn new(future: F, stats: Option<Arc<OperationStats>>) -> Self {
+        Self { future, stats }
+    }
+}
+
+impl<F: Future> Future for RecordBusy<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        match this.stats {
+            Some(stats) => {
+                let start = Instant::now();
+                let result = this.future.poll(cx);
+                stats.add_busy(start.elapsed());
+                result
+            }
+            None => this.future.poll(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sparrow_api::kaskada::v1alpha::operation_plan::{MergeOperation, Operator, ScanOperation};
+    use sparrow_api::kaskada::v1alpha::OperationPlan;
+
+    use super::*;
+
+    #[test]
+    fn test_plan_stats() {
+        let plan = ComputePlan {
+            operations: vec![
+                OperationPlan {
+                    operator: Some(Operator::Scan(ScanOperation::default())),
+                    ..OperationPlan::default()
+                },
+                OperationPlan {
+                    operator: Some(Operator::Merge(MergeOperation::default())),
+                    ..OperationPlan::default()
+                },
+            ],
+            ..ComputePlan::default()
+        };
+        let stats = PlanStats::new(&plan);
+
+        // Two partitions of the merge report to the same statistics.
+        let merge = stats.operation(1);
+        merge.record_batch(10, 4, 100);
+        merge.add_busy(Duration::from_nanos(5));
+        merge.add_buffered(64, 3);
+        merge.record_batch(6, 6, 80);
+        merge.add_busy(Duration::from_nanos(7));
+        merge.add_buffered(32, 2);
+        // The first partition emits its buffered rows.
+        merge.add_buffered(-64, -3);
+
+        assert_eq!(
+            stats.to_progress(),
+            vec![
+                OperationProgress {
+                    operation_index: 0,
+                    operation_label: "scan".to_owned(),
+                    ..OperationProgress::default()
+                },
+                OperationProgress {
+                    operation_index: 1,
+                    operation_label: "merge".to_owned(),
+                    input_rows: 16,
+                    input_batches: 2,
+                    output_rows: 10,
+                    output_batches: 2,
+                    busy_nanos: 12,
+                    buffered_bytes: 32,
+                    buffered_rows: 2,
+                    watermark: 100,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_busy() {
+        let plan = ComputePlan {
+            operations: vec![OperationPlan {
+                operator: Some(Operator::Scan(ScanOperation::default())),
+                ..OperationPlan::default()
+            }],
+            ..ComputePlan::default()
+        };
+        let stats = PlanStats::new(&plan);
+
+        let result = RecordBusy::new(
+            async {
+                std::thread::sleep(Duration::from_millis(2));
+                tokio::task::yield_now().await;
+                5
+            },
+            Some(stats.operation(0)),
+        )
+        .await;
+        assert_eq!(result, 5);
+        assert!(stats.to_progress()[0].busy_nanos >= 2_000_000);
+    }
+}
@@ -1,7 +1,7 @@
 use std::future::Future;
 use std::sync::Arc;
 
-use arrow::array::UInt64Array;
+use arrow::array::{ArrayRef, UInt64Array};
 use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
 use arrow::record_batch::RecordBatch;
 use error_stack::{FutureExt as ESFutureExt, IntoReport, Result, ResultExt};
@@ -19,12 +19,15 @@ use crate::execute::progress_reporter::ProgressUpdate;
 use crate::Batch;
 
 mod csv;
+mod example_labels;
 mod object_store;
 mod parquet;
 mod redis;
 
 pub mod pulsar;
 
+pub(crate) use example_labels::ExampleLabels;
+
 #[derive(Debug, derive_more::Display)]
 pub enum Error {
     Schema {
@@ -43,22 +46,37 @@ pub enum Error {
 impl error_stack::Context for Error {}
 
 /// Write the batches to the given output destination.
+///
+/// The batches are produced by the operation at `operation_index`, and are
+/// written as the named `output` (or the result of the query, if `None`).
+/// The `key_hash_inverse` is used to add the entity keys to the output. If
+/// the output is produced at examples, the `example_labels` are added to it.
+#[allow(clippy::too_many_arguments)]
 pub(super) fn write(
     context: &OperationContext,
+    operation_index: usize,
+    key_hash_inverse: Arc<ThreadSafeKeyHashInverse>,
+    example_labels: Option<ExampleLabels>,
+    output: Option<String>,
     limits: Limits,
     batches: BoxStream<'static, Batch>,
     progress_updates_tx: tokio::sync::mpsc::Sender<ProgressUpdate>,
     destination: v1alpha::Destination,
 ) -> error_stack::Result<impl Future<Output = Result<(), Error>>, Error> {
-    let sink_schema = determine_output_schema(context)?;
+    let sink_schema = determine_output_schema(
+        context,
+        operation_index,
+        key_hash_inverse.key_type.clone(),
+        example_labels.as_ref().map(ExampleLabels::fields),
+    )?;
 
     // Clone things that need to move into the async stream.
     let sink_schema_clone = sink_schema.clone();
-    let key_hash_inverse = context.key_hash_inverse.clone();
     let batches = async_stream::stream! {
         // Move / copy into the stream.
         let sink_schema = sink_schema_clone;
         let key_hash_inverse = key_hash_inverse;
+        let mut example_labels = example_labels;
 
         let limit_rows = limits.preview_rows > 0;
         let mut remaining = limits.preview_rows as usize;
@@ -76,7 +94,16 @@ pub(super) fn write(
                 batch
             };
 
-            yield post_process_batch(&sink_schema, batch, &key_hash_inverse).await;
+            let labels = match &mut example_labels {
+                Some(example_labels) => Some(
+                    example_labels
+                        .next_labels(&batch)
+                        .await
+                        .expect("labels for each example"),
+                ),
+                None => None,
+            };
+            yield post_process_batch(&sink_schema, batch, &key_hash_inverse, labels).await;
 
             if limit_rows && remaining == 0 {
                 break;
@@ -91,7 +118,7 @@ pub(super) fn write(
     match destination {
         Destination::ObjectStore(store) => {
             Ok(
-                object_store::write(store, sink_schema, progress_updates_tx, batches)
+                object_store::write(output, store, sink_schema, progress_updates_tx, batches)
                     .change_context(Error::WritingToDestination {
                         dest_name: "object_store".to_owned(),
                     })
@@ -116,7 +143,7 @@ pub(super) fn write(
         #[cfg(feature = "pulsar")]
         Destination::Pulsar(pulsar) => {
             Ok(
-                pulsar::write(pulsar, sink_schema, progress_updates_tx, batches)
+                pulsar::write(output, pulsar, sink_schema, progress_updates_tx, batches)
                     .change_context(Error::WritingToDestination {
                         dest_name: "pulsar".to_owned(),
                     })
@@ -127,10 +154,13 @@ pub(super) fn write(
 }
 
 /// Adds additional information to an output batch.
+///
+/// The `labels` (if any) are added after the values of the output.
 async fn post_process_batch(
     sink_schema: &SchemaRef,
     batch: Batch,
     key_hash_inverse: &Arc<ThreadSafeKeyHashInverse>,
+    labels: Option<Vec<ArrayRef>>,
 ) -> RecordBatch {
     // TODO: Move this into the writer once it's standard.
     // TODO: Support a single output column?
@@ -147,7 +177,13 @@ async fn post_process_batch(
         .inverse(key_col)
         .await
         .expect("inverses are defined");
-    fields.extend_from_slice(&[key_col]);
+    if let DataType::Struct(_) = key_col.data_type() {
+        // Composite keys are output as a column for each component.
+        let key_struct = downcast_struct_array(key_col.as_ref()).expect("key is struct array");
+        fields.extend_from_slice(key_struct.columns());
+    } else {
+        fields.extend_from_slice(&[key_col]);
+    }
 
     let struct_array =
         downcast_struct_array(batch.columns()[3].as_ref()).expect("value is struct array");
@@ -163,33 +199,62 @@ async fn post_process_batch(
     } else {
         fields.extend_from_slice(struct_array.columns());
     }
+    fields.extend(labels.into_iter().flatten());
 
     RecordBatch::try_new(sink_schema.clone(), fields).expect("resulting batch is valid")
 }
 
+/// Return the output fields containing the entity key.
+///
+/// Composite keys are flattened into a `_key_<name>` column for each component,
+/// since not all destinations support struct columns.
+fn key_fields(key_type: DataType) -> Vec<Field> {
+    match key_type {
+        DataType::Struct(components) => components
+            .iter()
+            .map(|component| {
+                Field::new(
+                    &format!("_key_{}", component.name()),
+                    component.data_type().clone(),
+                    true,
+                )
+            })
+            .collect(),
+        key_type => vec![Field::new("_key", key_type, true)],
+    }
+}
+
 /// Determine the output schema.
 ///
-/// This uses the `plan` to locate the result type.
+/// This uses the `plan` to locate the result type of the operation at
+/// `operation_index`.
 ///
-/// This uses the `key_hash_inverse
+/// This uses the `key_type` of the output's grouping.
 /// This currently requires knowledge of how we will post-process output batches
-/// (by adding the key column back).
-fn determine_output_schema(context: &OperationContext) -> Result<SchemaRef, Error> {
-    let key_type = context.key_hash_inverse.key_type.clone();
-
+/// (by adding the key column and any example labels back).
+fn determine_output_schema(
+    context: &OperationContext,
+    operation_index: usize,
+    key_type: DataType,
+    example_labels: Option<&[Field]>,
+) -> Result<SchemaRef, Error> {
     // There should be cleaner ways to determine the output schema.
-    // But -- find the only output expression in the last operation.
+    // But -- find the only output expression in the output operation.
     // It should produce a record, which should be the sink schema.
-    let last_op = context.plan.operations.last().ok_or(Error::Schema {
-        detail: "no operations in plan".to_owned(),
-    })?;
-    let result_type = last_op
+    let output_op = context
+        .plan
+        .operations
+        .get(operation_index)
+        .ok_or_else(|| Error::Schema {
+            detail: format!("no operation {operation_index} in plan"),
+        })?;
+    let result_type = output_op
         .expressions
         .iter()
         .filter(|expr| expr.output)
         .exactly_one()
         .map_err(|e| Error::Schema {
-            detail: format!("expected one output in last operation, but got {e:?}"),
+            detail: format!("expected one output in operation {operation_index}, but got {e:?}"),
         })?
         .result_type
         .as_ref()
@@ -209,8 +274,8 @@ fn determine_output_schema(context: &OperationContext) -> Result<SchemaRef, Erro
             ),
             Field::new("_subsort", DataType::UInt64, false),
             Field::new("_key_hash", DataType::UInt64, false),
-            Field::new("_key", key_type, true),
         ]);
+        fields.extend(key_fields(key_type));
 
         for field in data_fields.fields.iter() {
             fields.push(Field::new(
@@ -230,6 +295,21 @@ fn determine_output_schema(context: &OperationContext) -> Result<SchemaRef, Erro
             ));
         }
 
+        if let Some(example_labels) = example_labels {
+            for label in example_labels {
+                error_stack::ensure!(
+                    !fields.iter().any(|field| field.name() == label.name()),
+                    Error::Schema {
+                        detail: format!(
+                            "example label '{}' has the same name as an output column",
+                            label.name()
+                        )
+                    }
+                );
+                fields.push(label.clone());
+            }
+        }
+
         Ok(Arc::new(Schema::new(fields)))
     } else {
         error_stack::bail!(Error::Schema {
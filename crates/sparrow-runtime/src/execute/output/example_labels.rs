@@ -0,0 +1,198 @@
+use arrow::array::{ArrayRef, UInt64Array};
+use arrow::datatypes::Field;
+use arrow::record_batch::RecordBatch;
+use sparrow_core::downcast_primitive_array;
+
+use crate::execute::partition::partition_of;
+use crate::{Batch, BatchReceiver};
+
+/// The labels of the examples, added to the results produced at them.
+///
+/// The labels are sent by the examples operation of each partition, in the
+/// order it sends the examples. Each example produces one result row, and the
+/// results of each partition are produced in the same order, so the labels of
+/// each result row are the next labels from the partition owning its entity.
+#[derive(Debug)]
+pub(crate) struct ExampleLabels {
+    /// The fields of the label columns.
+    fields: Vec<Field>,
+    /// The labels from each partition.
+    partitions: Vec<PartitionLabels>,
+}
+
+#[derive(Debug)]
+struct PartitionLabels {
+    receiver: BatchReceiver,
+    /// The labels received but not yet added to results.
+    pending: Option<Batch>,
+}
+
+impl ExampleLabels {
+    /// Create the labels received from each partition.
+    pub(crate) fn new(fields: Vec<Field>, receivers: Vec<BatchReceiver>) -> Self {
+        let partitions = receivers
+            .into_iter()
+            .map(|receiver| PartitionLabels {
+                receiver,
+                pending: None,
+            })
+            .collect();
+        Self { fields, partitions }
+    }
+
+    /// The fields of the label columns.
+    pub(super) fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Return the label columns for the results in the batch.
+    pub(super) async fn next_labels(&mut self, batch: &Batch) -> anyhow::Result<Vec<ArrayRef>> {
+        let key_hashes: &UInt64Array = downcast_primitive_array(batch.column(2).as_ref())?;
+        let key_hashes = key_hashes.values();
+        let num_partitions = self.partitions.len();
+
+        // Take the labels for each run of results in the same partition.
+        let mut labels = Vec::new();
+        let mut start = 0;
+        while start < key_hashes.len() {
+            let partition = partition_of(key_hashes[start], num_partitions);
+            let end = key_hashes[start..]
+                .iter()
+                .position(|key_hash| partition_of(*key_hash, num_partitions) != partition)
+                .map_or(key_hashes.len(), |length| start + length);
+            self.partitions[partition]
+                .take(end - start, &mut labels)
+                .await?;
+            start = end;
+        }
+
+        let columns = self
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                if labels.is_empty() {
+                    return Ok(arrow::array::new_empty_array(field.data_type()));
+                }
+                let columns: Vec<_> = labels
+                    .iter()
+                    .map(|labels| labels.column(3 + index).as_ref())
+                    .collect();
+                Ok(arrow::compute::concat(&columns)?)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        debug_assert!(
+            labels.is_empty() || {
+                let keys: Vec<_> = labels
+                    .iter()
+                    .map(|labels| labels.column(2).as_ref())
+                    .collect();
+                arrow::compute::concat(&keys).ok().as_deref() == Some(batch.column(2).as_ref())
+            },
+            "labels should be for the examples of the results"
+        );
+        Ok(columns)
+    }
+}
+
+impl PartitionLabels {
+    /// Take the next `length` labels, adding them to `labels`.
+    async fn take(
+        &mut self,
+        mut length: usize,
+        labels: &mut Vec<RecordBatch>,
+    ) -> anyhow::Result<()> {
+        while length > 0 {
+            let pending = match self.pending.take() {
+                Some(pending) => pending,
+                None => self
+                    .receiver
+                    .recv()
+                    .await
+                    .ok_or_else(|| anyhow::anyhow!("missing labels for examples"))?,
+            };
+
+            let taken = length.min(pending.num_rows());
+            labels.push(pending.data().slice(0, taken));
+            if taken < pending.num_rows() {
+                self.pending = Some(pending.slice(taken, pending.num_rows() - taken));
+            }
+            length -= taken;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{Array, BooleanArray, TimestampNanosecondArray};
+    use arrow::datatypes::{ArrowPrimitiveType, DataType, Schema, TimestampNanosecondType};
+
+    use super::*;
+
+    fn batch(key_hashes: Vec<u64>, labels: Option<Vec<bool>>) -> Batch {
+        let mut fields = vec![
+            Field::new("_time", TimestampNanosecondType::DATA_TYPE, false),
+            Field::new("_subsort", DataType::UInt64, false),
+            Field::new("_key_hash", DataType::UInt64, false),
+        ];
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(TimestampNanosecondArray::from_iter_values(
+                (0..key_hashes.len() as i64).map(|index| index * 10),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                std::iter::repeat(u64::MAX).take(key_hashes.len()),
+            )),
+            Arc::new(UInt64Array::from(key_hashes)),
+        ];
+        if let Some(labels) = labels {
+            fields.push(Field::new("label", DataType::Boolean, true));
+            columns.push(Arc::new(BooleanArray::from(labels)));
+        }
+        let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap();
+        Batch::try_new_from_batch(batch).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_labels_from_partitions() {
+        let (even_sender, even_receiver) = tokio::sync::mpsc::channel(10);
+        let (odd_sender, odd_receiver) = tokio::sync::mpsc::channel(10);
+        let mut labels = ExampleLabels::new(
+            vec![Field::new("label", DataType::Boolean, true)],
+            vec![even_receiver, odd_receiver],
+        );
+
+        // Each partition sends the labels of its examples, in order.
+        even_sender
+            .send(batch(vec![2, 4, 2], Some(vec![true, false, false])))
+            .await
+            .unwrap();
+        odd_sender
+            .send(batch(vec![1, 3], Some(vec![false, true])))
+            .await
+            .unwrap();
+
+        // The results of the partitions are interleaved, and the labels of
+        // each partition may be split across the batches of results.
+        let columns = labels
+            .next_labels(&batch(vec![2, 1, 4], None))
+            .await
+            .unwrap();
+        assert_eq!(
+            columns[0].as_ref(),
+            &BooleanArray::from(vec![true, false, false]) as &dyn Array
+        );
+        let columns = labels.next_labels(&batch(vec![3, 2], None)).await.unwrap();
+        assert_eq!(
+            columns[0].as_ref(),
+            &BooleanArray::from(vec![true, false]) as &dyn Array
+        );
+
+        // A result without an example is an error.
+        drop(odd_sender);
+        assert!(labels.next_labels(&batch(vec![1], None)).await.is_err());
+    }
+}
@@ -29,7 +29,12 @@ pub enum Error {
 
 impl error_stack::Context for Error {}
 
+/// Write the batches to the object store.
+///
+/// The `output` is the name of the named output being written, or `None` for
+/// the result of the query.
 pub(super) async fn write(
+    output: Option<String>,
     object_store: ObjectStoreDestination,
     schema: SchemaRef,
     progress_updates_tx: tokio::sync::mpsc::Sender<ProgressUpdate>,
@@ -38,6 +43,7 @@ pub(super) async fn write(
     // Inform tracker of destination type
     progress_updates_tx
         .send(ProgressUpdate::Destination {
+            output: output.clone(),
             destination: destination::Destination::ObjectStore(object_store.clone()),
         })
         .await
@@ -91,6 +97,7 @@ pub(super) async fn write(
 
         progress_updates_tx
             .try_send(ProgressUpdate::FilesProduced {
+                output,
                 paths: vec![output_path],
             })
             .into_report()
@@ -145,6 +152,7 @@ pub(super) async fn write(
 
         progress_updates_tx
             .try_send(ProgressUpdate::FilesProduced {
+                output,
                 paths: vec![output_uri],
             })
             .into_report()
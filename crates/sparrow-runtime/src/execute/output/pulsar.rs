@@ -58,6 +58,7 @@ const DEFAULT_PULSAR_NAMESPACE: &str = "default";
 const BATCH_SIZE: u32 = 1000;
 
 pub(super) async fn write(
+    output: Option<String>,
     pulsar: PulsarDestination,
     schema: SchemaRef,
     progress_updates_tx: tokio::sync::mpsc::Sender<ProgressUpdate>,
@@ -91,6 +92,7 @@ pub(super) async fn write(
     // Inform tracker of output type
     progress_updates_tx
         .send(ProgressUpdate::Destination {
+            output,
             destination: destination::Destination::Pulsar(PulsarDestination {
                 config: Some(pulsar.clone()),
             }),
@@ -0,0 +1,268 @@
+//! Routing of batches between entity-key partitions.
+//!
+//! When a query is executed with multiple partitions, each partition runs an
+//! independent copy of the operations, and receives only the rows for the
+//! entities assigned to it. Entities are assigned to partitions based on their
+//! key hash, so all state for an entity lives in a single partition.
+//!
+//! Rows move between partitions in two places:
+//!
+//! 1. Scans are executed once, and [split_partitions] routes each row of the
+//!    scanned batches to the partition owning its entity.
+//! 2. Operations which change the key of rows (`with_key` and `lookup`) are
+//!    executed in every partition, but the re-keyed rows may belong to a
+//!    different partition. The output of each partition is routed by
+//!    [split_partitions] to each destination partition, which uses
+//!    [merge_partitions] to combine the rows from every source partition back
+//!    in time order.
+//!
+//! The output of the final operation in each partition is also combined using
+//! [merge_partitions] before being written.
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use arrow::array::{ArrayRef, BooleanArray, UInt64Array};
+use arrow::compute::SortColumn;
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use error_stack::{IntoReportCompat, ResultExt};
+use futures::StreamExt;
+use itertools::Itertools;
+use sparrow_core::{downcast_primitive_array, KeyTriple};
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::execute::Error;
+use crate::merge::{GatheredBatches, Gatherer};
+use crate::{Batch, BatchReceiver, BatchSender};
+
+/// Return the partition owning the given key hash.
+pub(super) fn partition_of(key_hash: u64, num_partitions: usize) -> usize {
+    (key_hash % num_partitions as u64) as usize
+}
+
+/// Split a batch into the rows belonging to each partition.
+///
+/// Each of the resulting batches has the same bounds as the input, even if
+/// it contains no rows, so that every partition continues to make progress.
+pub(super) fn split_batch(batch: &Batch, num_partitions: usize) -> anyhow::Result<Vec<Batch>> {
+    let key_hashes: &UInt64Array = downcast_primitive_array(batch.column(2).as_ref())?;
+    let partitions: Vec<usize> = key_hashes
+        .values()
+        .iter()
+        .map(|key_hash| partition_of(*key_hash, num_partitions))
+        .collect();
+
+    (0..num_partitions)
+        .map(|partition| -> anyhow::Result<Batch> {
+            let filter: BooleanArray = partitions
+                .iter()
+                .map(|row_partition| Some(*row_partition == partition))
+                .collect();
+            let data = arrow::compute::kernels::filter::filter_record_batch(batch.data(), &filter)
+                .context("filter partition rows")?;
+            Ok(batch.with_data(data))
+        })
+        .try_collect()
+}
+
+/// Route each batch received from `input` to the partitions owning its rows.
+///
+/// `outputs[partition]` contains the senders for the consumers in each
+/// partition.
+pub(super) async fn split_partitions(
+    mut input: BatchReceiver,
+    outputs: Vec<Vec<BatchSender>>,
+) -> error_stack::Result<(), Error> {
+    while let Some(batch) = input.recv().await {
+        let batches = split_batch(&batch, outputs.len())
+            .into_report()
+            .change_context(Error::internal_msg("splitting batch into partitions"))?;
+
+        for (batch, consumers) in batches.into_iter().zip(outputs.iter()) {
+            for consumer in consumers {
+                if consumer.send(batch.clone()).await.is_err() {
+                    tracing::debug!("Downstream receiver closed; stopping partition split");
+                    return Ok(());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Merge the batches received from each partition into a single stream in
+/// time order, sending the merged batches to each of the `consumers`.
+pub(super) async fn merge_partitions(
+    inputs: Vec<BatchReceiver>,
+    consumers: Vec<BatchSender>,
+) -> error_stack::Result<(), Error> {
+    // Split batches (which may be empty) are used to report progress, so start
+    // with a last output time to allow emitting empty ranges.
+    let mut gatherer = Gatherer::new(inputs.len(), Some(i64::MIN));
+
+    // Each input produces `Some(batch)` for each batch, followed by `None`
+    // when it is finished.
+    let mut inputs =
+        futures::stream::select_all(inputs.into_iter().enumerate().map(|(index, input)| {
+            ReceiverStream::new(input)
+                .map(move |batch| (index, Some(batch)))
+                .chain(futures::stream::once(async move { (index, None) }))
+        }));
+
+    let mut schema = None;
+    while let Some((index, batch)) = inputs.next().await {
+        if let Some(batch) = &batch {
+            schema.get_or_insert_with(|| batch.schema());
+        }
+
+        let gathered = gatherer
+            .add_batch(index, batch)
+            .into_report()
+            .change_context(Error::internal_msg("gathering partition batches"))?;
+
+        // There is nothing to emit until a batch has been received from any of
+        // the partitions.
+        let (Some(gathered), Some(schema)) = (gathered, &schema) else {
+            continue;
+        };
+
+        let batch = merge_gathered(schema, gathered)
+            .into_report()
+            .change_context(Error::internal_msg("merging partition batches"))?;
+        for consumer in consumers.iter() {
+            if consumer.send(batch.clone()).await.is_err() {
+                tracing::debug!("Downstream receiver closed; stopping partition merge");
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Merge the gathered batches into a single batch sorted by key triple.
+///
+/// This sorts the concatenated rows rather than using `homogeneous_merge`,
+/// since rows from different partitions may have equal key triples after
+/// being re-keyed, and all of them should be retained.
+fn merge_gathered(schema: &SchemaRef, gathered: GatheredBatches<Batch>) -> anyhow::Result<Batch> {
+    let batches: Vec<RecordBatch> = gathered
+        .batches
+        .into_iter()
+        .flat_map(|batches| batches.into_iter().map(|batch| batch.data))
+        .collect();
+    let data = arrow::compute::concat_batches(schema, &batches)?;
+
+    let sort_columns: Vec<_> = data.columns()[0..3]
+        .iter()
+        .map(|column| SortColumn {
+            values: column.clone(),
+            options: None,
+        })
+        .collect();
+    let indices = arrow::compute::lexsort_to_indices(&sort_columns, None)?;
+    let columns: Vec<ArrayRef> = data
+        .columns()
+        .iter()
+        .map(|column| arrow::compute::take(column.as_ref(), &indices, None))
+        .try_collect()?;
+    let data = RecordBatch::try_new(Arc::clone(schema), columns)?;
+
+    // The gathered batches contain all rows in the inclusive time range.
+    let lower_bound = KeyTriple {
+        time: gathered.min_time_inclusive,
+        subsort: 0,
+        key_hash: 0,
+    };
+    let upper_bound = KeyTriple {
+        time: gathered.max_time_inclusive,
+        subsort: u64::MAX,
+        key_hash: u64::MAX,
+    };
+    Batch::try_new_with_bounds(data, lower_bound, upper_bound)
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::TimestampNanosecondArray;
+
+    use super::*;
+
+    fn batch(rows: &[(i64, u64)]) -> Batch {
+        let time = TimestampNanosecondArray::from_iter_values(rows.iter().map(|row| row.0));
+        let batch = Batch::test_batch(time, 0, 0);
+        let key_hash: ArrayRef =
+            Arc::new(UInt64Array::from_iter_values(rows.iter().map(|row| row.1)));
+        let data = RecordBatch::try_new(
+            batch.schema(),
+            vec![batch.column(0).clone(), batch.column(1).clone(), key_hash],
+        )
+        .unwrap();
+        Batch::try_new_from_batch(data).unwrap()
+    }
+
+    fn rows(batch: &Batch) -> Vec<(i64, u64)> {
+        let key_hashes: &UInt64Array = downcast_primitive_array(batch.column(2).as_ref()).unwrap();
+        batch
+            .times()
+            .unwrap()
+            .iter()
+            .copied()
+            .zip(key_hashes.values().iter().copied())
+            .collect()
+    }
+
+    #[test]
+    fn test_split_batch() {
+        let input = batch(&[(1, 0), (1, 3), (2, 1), (3, 2), (4, 4)]);
+        let split = split_batch(&input, 2).unwrap();
+
+        assert_eq!(split.len(), 2);
+        assert_eq!(rows(&split[0]), vec![(1, 0), (3, 2), (4, 4)]);
+        assert_eq!(rows(&split[1]), vec![(1, 3), (2, 1)]);
+        for partition in split {
+            assert_eq!(partition.lower_bound, input.lower_bound);
+            assert_eq!(partition.upper_bound, input.upper_bound);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_split_merge_round_trip() {
+        let inputs = vec![
+            batch(&[(1, 0), (1, 3), (2, 1)]),
+            batch(&[(3, 2), (3, 5), (5, 4)]),
+            batch(&[(6, 1), (7, 7)]),
+        ];
+
+        let (input_tx, input_rx) = tokio::sync::mpsc::channel(10);
+        let (split_txs, split_rxs): (Vec<_>, Vec<_>) =
+            (0..3).map(|_| tokio::sync::mpsc::channel(10)).unzip();
+        let (output_tx, mut output_rx) = tokio::sync::mpsc::channel(10);
+
+        let split = tokio::spawn(split_partitions(
+            input_rx,
+            split_txs.into_iter().map(|tx| vec![tx]).collect(),
+        ));
+        let merge = tokio::spawn(merge_partitions(split_rxs, vec![output_tx]));
+
+        for input in inputs.iter() {
+            input_tx.send(input.clone()).await.unwrap();
+        }
+        std::mem::drop(input_tx);
+
+        let mut merged = Vec::new();
+        let mut last_upper_bound: Option<KeyTriple> = None;
+        while let Some(output) = output_rx.recv().await {
+            if let Some(last_upper_bound) = last_upper_bound {
+                assert!(last_upper_bound < output.lower_bound);
+            }
+            last_upper_bound = Some(output.upper_bound);
+            merged.extend(rows(&output));
+        }
+        split.await.unwrap().unwrap();
+        merge.await.unwrap().unwrap();
+
+        let expected: Vec<_> = inputs.iter().flat_map(rows).collect();
+        assert_eq!(merged, expected);
+    }
+}
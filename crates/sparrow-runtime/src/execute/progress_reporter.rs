@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::time::Duration;
 
 use futures::Stream;
@@ -15,6 +16,8 @@ use tokio_stream::StreamExt;
 use sparrow_api::kaskada::v1alpha::PulsarDestination;
 
 use super::Error;
+use crate::execute::operation_stats::PlanStats;
+use crate::metrics;
 
 /// Report progress every 10 seconds.
 const PROGRESS_PERIOD: Duration = Duration::from_secs(10);
@@ -35,12 +38,21 @@ struct ProgressTracker {
     output_paths: Vec<String>,
     /// Information on where the outputs are materialized to.
     destination: Option<destination::Destination>,
+    /// The destination and output paths produced so far for each named
+    /// output.
+    named_outputs: HashMap<String, (destination::Destination, Vec<String>)>,
+    /// Statistics for each operation, if they should be reported.
+    operation_stats: Option<PlanStats>,
 }
 
 #[derive(Debug)]
 pub(crate) enum ProgressUpdate {
     /// Informs the progress tracker of the output destination.
+    ///
+    /// The `output` is the name of the named output being written, or `None`
+    /// for the result of the query.
     Destination {
+        output: Option<String>,
         destination: destination::Destination,
     },
     /// Progress update reported for each table indicating total size.
@@ -50,7 +62,10 @@ pub(crate) enum ProgressUpdate {
     /// Progress update indicating the given number of rows have been output.
     Output { num_rows: usize },
     /// Progress update reporting the output files produced.
-    FilesProduced { paths: Vec<String> },
+    FilesProduced {
+        output: Option<String>,
+        paths: Vec<String>,
+    },
     /// Sent to indicate all operations have completed.
     ///
     /// For now, contains the compute snapshots, as we only snapshot
@@ -64,7 +79,7 @@ pub(crate) enum ProgressUpdate {
 }
 
 impl ProgressTracker {
-    fn new() -> Self {
+    fn new(operation_stats: Option<PlanStats>) -> Self {
         Self {
             // We start this as `1` so the first progress period doesn't
             // log the "no progress" message. This gives time for things to "warm up".
@@ -79,30 +94,55 @@ impl ProgressTracker {
                 max_event_time: 0,
                 output_time: 0,
                 produced_output_rows: 0,
+                operations: vec![],
             },
             output_paths: vec![],
             destination: None,
+            named_outputs: HashMap::new(),
+            operation_stats,
         }
     }
 
     fn process_update(&mut self, stats: ProgressUpdate) {
         match stats {
-            ProgressUpdate::Destination { destination } => {
+            ProgressUpdate::Destination {
+                output: None,
+                destination,
+            } => {
                 self.destination = Some(destination);
             }
+            ProgressUpdate::Destination {
+                output: Some(output),
+                destination,
+            } => {
+                self.named_outputs.insert(output, (destination, vec![]));
+            }
             ProgressUpdate::InputMetadata { total_num_rows } => {
                 self.progress.total_input_rows += total_num_rows as i64;
             }
             ProgressUpdate::Input { num_rows } => {
                 self.progress.processed_input_rows += num_rows as i64;
+                metrics::ROWS_READ.inc_by(num_rows as u64);
             }
             ProgressUpdate::Output { num_rows } => {
                 self.output_batches_since_progress += 1;
                 self.progress.produced_output_rows += num_rows as i64;
+                metrics::ROWS_PRODUCED.inc_by(num_rows as u64);
             }
-            ProgressUpdate::FilesProduced { mut paths } => {
+            ProgressUpdate::FilesProduced {
+                output: None,
+                mut paths,
+            } => {
                 self.output_paths.append(&mut paths);
             }
+            ProgressUpdate::FilesProduced {
+                output: Some(output),
+                mut paths,
+            } => {
+                if let Some((_, output_paths)) = self.named_outputs.get_mut(&output) {
+                    output_paths.append(&mut paths);
+                }
+            }
             ProgressUpdate::ExecutionComplete { .. } | ProgressUpdate::ExecutionFailed { .. } => {
                 panic!("Shouldn't update process on final message")
             }
@@ -122,8 +162,10 @@ impl ProgressTracker {
         }
 
         self.output_batches_since_progress = 0;
+        self.update_operations();
 
         let destination = self.destination_to_output()?;
+        let output_destinations = self.named_outputs_to_output()?;
         Ok(ExecuteResponse {
             state: LongQueryState::Running as i32,
             is_query_done: false,
@@ -132,60 +174,89 @@ impl ProgressTracker {
             plan_yaml_path: None,
             compute_snapshots: Vec::new(),
             destination: Some(destination),
+            output_destinations,
         })
     }
 
+    /// Update the progress of each operation from the current statistics.
+    fn update_operations(&mut self) {
+        if let Some(operation_stats) = &self.operation_stats {
+            self.progress.operations = operation_stats.to_progress();
+        }
+    }
+
     fn destination_to_output(&mut self) -> error_stack::Result<Destination, Error> {
         // Clone the output paths in for object store destinations
         let destination = self
             .destination
             .as_ref()
             .ok_or(Error::Internal("expected destination"))?;
-        match destination {
-            destination::Destination::ObjectStore(store) => Ok(Destination {
-                destination: Some(destination::Destination::ObjectStore(
-                    ObjectStoreDestination {
-                        file_type: store.file_type,
-                        output_prefix_uri: store.output_prefix_uri.clone(),
-                        output_paths: Some(ResultPaths {
-                            paths: self.output_paths.clone(),
-                        }),
-                    },
-                )),
-            }),
-            #[cfg(not(feature = "pulsar"))]
-            output_to::Destination::Pulsar(pulsar) => {
-                error_stack::bail!(Error::FeatureNotEnabled { feature: "pulsar" })
-            }
-            #[cfg(feature = "pulsar")]
-            destination::Destination::Pulsar(pulsar) => {
-                let config = pulsar
-                    .config
-                    .as_ref()
-                    .ok_or(Error::internal_msg("missing config"))?;
-                Ok(Destination {
-                    destination: Some(destination::Destination::Pulsar(PulsarDestination {
-                        config: Some(PulsarConfig {
-                            broker_service_url: config.broker_service_url.clone(),
-                            auth_plugin: config.auth_plugin.clone(),
-                            auth_params: config.auth_params.clone(),
-                            tenant: config.tenant.clone(),
-                            namespace: config.namespace.clone(),
-                            topic_name: config.topic_name.clone(),
-                            admin_service_url: config.admin_service_url.clone(),
-                        }),
-                    })),
-                })
-            }
-            destination::Destination::Redis(_) => {
-                error_stack::bail!(Error::UnsupportedOutput { output: "redis" })
-            }
+        destination_with_paths(destination, &self.output_paths)
+    }
+
+    fn named_outputs_to_output(&self) -> error_stack::Result<HashMap<String, Destination>, Error> {
+        self.named_outputs
+            .iter()
+            .map(|(name, (destination, output_paths))| {
+                Ok((
+                    name.clone(),
+                    destination_with_paths(destination, output_paths)?,
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Return the destination to report, including the output paths produced.
+fn destination_with_paths(
+    destination: &destination::Destination,
+    output_paths: &[String],
+) -> error_stack::Result<Destination, Error> {
+    match destination {
+        destination::Destination::ObjectStore(store) => Ok(Destination {
+            destination: Some(destination::Destination::ObjectStore(
+                ObjectStoreDestination {
+                    file_type: store.file_type,
+                    output_prefix_uri: store.output_prefix_uri.clone(),
+                    output_paths: Some(ResultPaths {
+                        paths: output_paths.to_vec(),
+                    }),
+                },
+            )),
+        }),
+        #[cfg(not(feature = "pulsar"))]
+        output_to::Destination::Pulsar(pulsar) => {
+            error_stack::bail!(Error::FeatureNotEnabled { feature: "pulsar" })
+        }
+        #[cfg(feature = "pulsar")]
+        destination::Destination::Pulsar(pulsar) => {
+            let config = pulsar
+                .config
+                .as_ref()
+                .ok_or(Error::internal_msg("missing config"))?;
+            Ok(Destination {
+                destination: Some(destination::Destination::Pulsar(PulsarDestination {
+                    config: Some(PulsarConfig {
+                        broker_service_url: config.broker_service_url.clone(),
+                        auth_plugin: config.auth_plugin.clone(),
+                        auth_params: config.auth_params.clone(),
+                        tenant: config.tenant.clone(),
+                        namespace: config.namespace.clone(),
+                        topic_name: config.topic_name.clone(),
+                        admin_service_url: config.admin_service_url.clone(),
+                    }),
+                })),
+            })
+        }
+        destination::Destination::Redis(_) => {
+            error_stack::bail!(Error::UnsupportedOutput { output: "redis" })
         }
     }
 }
 
 pub(super) fn progress_stream(
     mut progress_updates_rx: futures::stream::BoxStream<'static, ProgressUpdate>,
+    operation_stats: Option<PlanStats>,
 ) -> impl Stream<Item = error_stack::Result<ExecuteResponse, Error>> {
     // Create a stream of ticks starting at `now + PROGRESS_PERIOD`, ticking every
     // `PROGRESS_PERIOD`.
@@ -198,7 +269,7 @@ pub(super) fn progress_stream(
     // Ideally, we'd use `try_stream!` instead of `stream` so we could use the `?`
     // to produce errors. However, we can't do that due to https://github.com/tokio-rs/async-stream/issues/63.
     async_stream::stream! {
-        let mut tracker = ProgressTracker::new();
+        let mut tracker = ProgressTracker::new(operation_stats);
 
         loop {
             tokio::select! {
@@ -241,6 +312,15 @@ pub(super) fn progress_stream(
                                     }
                                 };
 
+                                let output_destinations = match tracker.named_outputs_to_output() {
+                                    Ok(output_destinations) => output_destinations,
+                                    Err(e) => {
+                                        yield Err(e);
+                                        continue;
+                                    }
+                                };
+
+                                tracker.update_operations();
                                 let final_result = Ok(ExecuteResponse {
                                     state: LongQueryState::Running as i32,
                                     is_query_done: true,
@@ -249,6 +329,7 @@ pub(super) fn progress_stream(
                                     plan_yaml_path: None,
                                     compute_snapshots,
                                     destination: Some(output),
+                                    output_destinations,
                                 });
                                 yield final_result;
                                 break
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use prost_wkt_types::Timestamp;
+use sparrow_api::kaskada::v1alpha::{ComputePlan, PlanHash};
+use sparrow_compiler::{accumulator_index, plan_state_reuse};
+use sparrow_instructions::ComputeStore;
+use tracing::info;
+
+/// Prepare a downloaded snapshot for use by the given plan.
+///
+/// If the snapshot was written by a different plan, the state that is
+/// unchanged between the plans is re-keyed for use by the new plan. If
+/// the new plan needs state that can't be reused, this returns the max
+/// event time of the snapshot. The missing state must then be backfilled
+/// from the history up to that time (see [ComputeStore::add_missing_state])
+/// before resuming.
+///
+/// Snapshots which didn't record the plan they were written for are left
+/// as-is, and will only be resumed from by the same plan.
+pub(super) fn reuse_compatible_state(
+    path: &Path,
+    plan: &ComputePlan,
+    plan_hash: &PlanHash,
+) -> anyhow::Result<Option<Timestamp>> {
+    // There is nothing to reuse if no snapshot was downloaded.
+    if !path.join("CURRENT").exists() {
+        return Ok(None);
+    }
+
+    let (stored_plan_hash, previous, max_event_time) = {
+        let store = ComputeStore::try_open_read_only(path)?;
+        let Some(stored_plan_hash) = store.get_plan_hash()? else {
+            return Ok(None);
+        };
+        if &stored_plan_hash == plan_hash {
+            return Ok(None);
+        }
+
+        let Some(previous) = store.get_plan()? else {
+            return Ok(None);
+        };
+        (stored_plan_hash, previous, store.get_max_event_time()?)
+    };
+
+    let state_reuse = plan_state_reuse(&previous, plan)?;
+    info!(
+        "Reusing {} operations and {} accumulators from snapshot for plan {stored_plan_hash}, \
+         backfilling {} states",
+        state_reuse.operations.len(),
+        state_reuse.accumulators.len(),
+        state_reuse.backfill.len()
+    );
+
+    let operations: HashMap<u8, u8> = state_reuse
+        .operations
+        .iter()
+        .map(|mapping| (mapping.previous_operation as u8, mapping.operation as u8))
+        .collect();
+    let accumulators: HashMap<(u8, u32), (u8, u32)> = state_reuse
+        .accumulators
+        .iter()
+        .map(|mapping| {
+            let previous_operation = &previous.operations[mapping.previous_operation as usize];
+            let operation = &plan.operations[mapping.operation as usize];
+            (
+                (
+                    mapping.previous_operation as u8,
+                    accumulator_index(previous_operation, mapping.previous_expression),
+                ),
+                (
+                    mapping.operation as u8,
+                    accumulator_index(operation, mapping.expression),
+                ),
+            )
+        })
+        .collect();
+
+    let store = ComputeStore::try_new_from_path(path)?;
+    store.reuse_state(&operations, &accumulators, plan, plan_hash)?;
+
+    if state_reuse.backfill.is_empty() {
+        Ok(None)
+    } else {
+        // A snapshot without a max event time hasn't processed any input, so
+        // the backfill covers everything before the beginning of time.
+        Ok(Some(max_event_time.unwrap_or(Timestamp {
+            seconds: 0,
+            nanos: 0,
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+    use prost_wkt_types::Timestamp;
+    use sparrow_api::kaskada::v1alpha::compile_request::ExpressionKind;
+    use sparrow_api::kaskada::v1alpha::{
+        CompileRequest, ComputeTable, FeatureSet, PerEntityBehavior, TableConfig, TableMetadata,
+    };
+    use sparrow_compiler::{hash_compute_plan_proto, InternalCompileOptions};
+    use sparrow_instructions::{StoreKey, StoreKeyKind};
+    use uuid::Uuid;
+
+    use super::*;
+
+    async fn compile(query: &str) -> ComputePlan {
+        let schema = Schema::new(vec![
+            Field::new(
+                "time",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+            Field::new("subsort", DataType::UInt64, false),
+            Field::new("key", DataType::UInt64, false),
+            Field::new("n", DataType::Int64, true),
+        ]);
+        let table = ComputeTable {
+            config: Some(TableConfig::new_with_table_source(
+                "Table",
+                &Uuid::parse_str("936DA01F9ABD4d9d80C702AF85C822A8").unwrap(),
+                "time",
+                Some("subsort"),
+                "key",
+                "grouping",
+            )),
+            metadata: Some(TableMetadata {
+                schema: Some((&schema).try_into().unwrap()),
+                file_count: 0,
+            }),
+            file_sets: vec![],
+        };
+
+        sparrow_compiler::compile_proto(
+            CompileRequest {
+                tables: vec![table],
+                feature_set: Some(FeatureSet {
+                    formulas: vec![],
+                    query: query.to_owned(),
+                    outputs: vec![],
+                }),
+                slice_request: None,
+                expression_kind: ExpressionKind::Complete as i32,
+                experimental: false,
+                per_entity_behavior: PerEntityBehavior::All as i32,
+                previous_plan: None,
+                explain: false,
+                lint_severities: vec![],
+            },
+            InternalCompileOptions::default(),
+        )
+        .await
+        .unwrap()
+        .plan
+        .unwrap()
+    }
+
+    /// Write a snapshot for the plan, with (fake) accumulators containing
+    /// `value + inst_index`.
+    fn write_snapshot(path: &Path, plan: &ComputePlan, value: i64) {
+        let plan_hash = hash_compute_plan_proto(plan);
+        let max_event_time = Timestamp {
+            seconds: i64::MAX,
+            nanos: i32::MAX,
+        };
+        let store = ComputeStore::try_new(path, &max_event_time, &plan_hash).unwrap();
+        store.put_plan(plan).unwrap();
+        store
+            .put_max_event_time(&Timestamp {
+                seconds: 100,
+                nanos: 0,
+            })
+            .unwrap();
+        for (operation_index, operation) in plan.operations.iter().enumerate() {
+            let operation_index = operation_index as u8;
+            store
+                .put(
+                    &StoreKey::new_key_hash_to_index(operation_index),
+                    &HashMap::<u64, u32>::new(),
+                )
+                .unwrap();
+            // Write (fake) accumulators for each instruction.
+            let num_instructions = accumulator_index(operation, operation.expressions.len() as u32);
+            for inst_index in 0..num_instructions {
+                store
+                    .put(
+                        &StoreKey::new_accumulator(operation_index, inst_index),
+                        &vec![value + inst_index as i64],
+                    )
+                    .unwrap();
+            }
+        }
+    }
+
+    fn accumulators(path: &Path) -> Vec<StoreKeyKind> {
+        let store = ComputeStore::try_open_read_only(path).unwrap();
+        store
+            .raw_entries()
+            .map(|entry| StoreKeyKind::from_bytes(&entry.unwrap().0))
+            .filter(|kind| matches!(kind, StoreKeyKind::Accumulator { .. }))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_reuse_compatible_state() {
+        let previous = compile("{ sum: sum(Table.n), last: last(Table.n) }").await;
+        let plan = compile("{ plus: Table.n + 1, sum: sum(Table.n) }").await;
+        let plan_hash = hash_compute_plan_proto(&plan);
+
+        let dir = tempfile::tempdir().unwrap();
+        write_snapshot(dir.path(), &previous, 0);
+        let backfill_until = reuse_compatible_state(dir.path(), &plan, &plan_hash).unwrap();
+        assert_eq!(backfill_until, None);
+
+        // Only the accumulator for the sum should be retained, at the position
+        // of the sum within the new plan.
+        let state_reuse = plan_state_reuse(&previous, &plan).unwrap();
+        assert_eq!(state_reuse.accumulators.len(), 1);
+        let mapping = &state_reuse.accumulators[0];
+        let operation = &plan.operations[mapping.operation as usize];
+        assert_eq!(
+            accumulators(dir.path()),
+            vec![StoreKeyKind::Accumulator {
+                operation_index: mapping.operation as u8,
+                inst_index: accumulator_index(operation, mapping.expression),
+            }]
+        );
+
+        let store = ComputeStore::try_open_read_only(dir.path()).unwrap();
+        assert_eq!(store.get_plan_hash().unwrap(), Some(plan_hash));
+        assert_eq!(store.get_plan().unwrap(), Some(plan));
+    }
+
+    #[tokio::test]
+    async fn test_backfill_incompatible_state() {
+        let previous = compile("{ sum: sum(Table.n) }").await;
+        let plan = compile("{ max: max(Table.n), sum: sum(Table.n) }").await;
+        let plan_hash = hash_compute_plan_proto(&plan);
+
+        let dir = tempfile::tempdir().unwrap();
+        write_snapshot(dir.path(), &previous, 0);
+        let backfill_until = reuse_compatible_state(dir.path(), &plan, &plan_hash).unwrap();
+
+        // The `sum` is reused, while the `max` needs to be backfilled from the
+        // history up to the snapshot.
+        assert_eq!(backfill_until.map(|time| time.seconds), Some(100));
+        let state_reuse = plan_state_reuse(&previous, &plan).unwrap();
+        assert_eq!(state_reuse.accumulators.len(), 1);
+        let mapping = &state_reuse.accumulators[0];
+        let operation = &plan.operations[mapping.operation as usize];
+        let operation_index = mapping.operation as u8;
+        let inst_index = accumulator_index(operation, mapping.expression);
+        assert_eq!(
+            accumulators(dir.path()),
+            vec![StoreKeyKind::Accumulator {
+                operation_index,
+                inst_index,
+            }]
+        );
+
+        // Adding the backfilled state retains the reused state.
+        let backfill_dir = tempfile::tempdir().unwrap();
+        write_snapshot(backfill_dir.path(), &plan, 1000);
+        {
+            let store = ComputeStore::try_new_from_path(dir.path()).unwrap();
+            let backfill = ComputeStore::try_open_read_only(backfill_dir.path()).unwrap();
+            store.add_missing_state(&backfill).unwrap();
+        }
+        assert_eq!(accumulators(dir.path()), accumulators(backfill_dir.path()));
+
+        let store = ComputeStore::try_open_read_only(dir.path()).unwrap();
+        let previous_operation = &previous.operations[mapping.previous_operation as usize];
+        let previous_inst_index =
+            accumulator_index(previous_operation, mapping.previous_expression);
+        let reused: Vec<i64> = store
+            .get(&StoreKey::new_accumulator(operation_index, inst_index))
+            .unwrap()
+            .unwrap();
+        assert_eq!(reused, vec![previous_inst_index as i64]);
+        assert_eq!(store.get_plan_hash().unwrap(), Some(plan_hash));
+    }
+}
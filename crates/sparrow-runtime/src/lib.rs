@@ -28,10 +28,12 @@ pub mod execute;
 mod key_hash_index;
 pub mod merge;
 mod metadata;
+pub mod metrics;
 mod min_heap;
 pub mod prepare;
 mod read;
 pub mod s3;
+pub mod snapshot;
 pub mod stores;
 mod streams;
 mod util;
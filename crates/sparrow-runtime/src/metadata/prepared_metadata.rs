@@ -5,7 +5,7 @@ use arrow::datatypes::{Schema, SchemaRef};
 use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
 use parquet::file::metadata::ParquetMetaData;
 use parquet::file::statistics::ValueStatistics;
-use sparrow_api::kaskada::v1alpha::PreparedFile;
+use sparrow_api::kaskada::v1alpha::{DataProfile, PreparedFile};
 use sparrow_core::TableSchema;
 
 use crate::metadata::file_from_path;
@@ -40,6 +40,11 @@ pub struct PreparedMetadata {
 
     /// The path to the metadata file.
     pub metadata_path: String,
+
+    /// The profile of the data in the prepared file.
+    ///
+    /// This is only present if the file was profiled during preparation.
+    pub data_profile: Option<DataProfile>,
 }
 
 fn get_time_statistics(
@@ -95,16 +100,24 @@ impl PreparedMetadata {
             // Empty files contain no stats. We default to assuming the min time.
             .unwrap_or(i64::MIN);
 
+        let data_profile = crate::prepare::data_profile::from_key_value(
+            metadata.file_metadata().key_value_metadata(),
+        )?;
+
         let path = parquet_path.to_string_lossy().into_owned();
         let metadata_path = metadata_parquet_path.to_string_lossy().into_owned();
-        Self::try_from_prepared_schema(
+        let prepared_metadata = Self::try_from_prepared_schema(
             path,
             prepared_schema.clone(),
             min_time,
             max_time,
             num_rows,
             metadata_path,
-        )
+        )?;
+        Ok(Self {
+            data_profile,
+            ..prepared_metadata
+        })
     }
 
     fn try_from_prepared_schema(
@@ -129,6 +142,7 @@ impl PreparedMetadata {
             max_time,
             num_rows,
             metadata_path,
+            data_profile: None,
         })
     }
 
@@ -0,0 +1,76 @@
+//! Prometheus metrics reported by the runtime.
+//!
+//! Metrics are registered with the default Prometheus registry, which is
+//! exposed by the `serve` command. Processes which don't expose metrics still
+//! update them, but they are never reported.
+
+use prometheus::{
+    register_histogram, register_int_counter, register_int_gauge, Histogram, IntCounter, IntGauge,
+};
+
+#[static_init::dynamic]
+pub(crate) static ROWS_READ: IntCounter = register_int_counter!(
+    "sparrow_rows_read_total",
+    "Number of input rows read by queries."
+)
+.expect("register rows read");
+
+#[static_init::dynamic]
+pub(crate) static ROWS_PRODUCED: IntCounter = register_int_counter!(
+    "sparrow_rows_produced_total",
+    "Number of output rows produced by queries."
+)
+.expect("register rows produced");
+
+#[static_init::dynamic]
+pub(crate) static BYTES_DOWNLOADED: IntCounter = register_int_counter!(
+    "sparrow_data_manager_downloaded_bytes_total",
+    "Number of bytes of prepared files downloaded by the data manager."
+)
+.expect("register bytes downloaded");
+
+#[static_init::dynamic]
+pub(crate) static SNAPSHOT_UPLOAD_DURATION: Histogram = register_histogram!(
+    "sparrow_snapshot_upload_duration_seconds",
+    "Time spent uploading compute snapshots.",
+    vec![0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0]
+)
+.expect("register snapshot upload duration");
+
+#[static_init::dynamic]
+pub(crate) static ACTIVE_MATERIALIZATIONS: IntGauge = register_int_gauge!(
+    "sparrow_active_materializations",
+    "Number of materializations currently executing."
+)
+.expect("register active materializations");
+
+/// Register all runtime metrics.
+///
+/// Metrics are otherwise registered when first used, so this ensures they are
+/// reported (as zero) before any queries are executed.
+pub fn register() {
+    let _ = &*ROWS_READ;
+    let _ = &*ROWS_PRODUCED;
+    let _ = &*BYTES_DOWNLOADED;
+    let _ = &*SNAPSHOT_UPLOAD_DURATION;
+    let _ = &*ACTIVE_MATERIALIZATIONS;
+}
+
+/// Tracks an executing materialization in [ACTIVE_MATERIALIZATIONS].
+///
+/// The materialization is no longer counted once this is dropped.
+#[derive(Debug)]
+pub(crate) struct ActiveMaterialization(());
+
+impl ActiveMaterialization {
+    pub fn start() -> Self {
+        ACTIVE_MATERIALIZATIONS.inc();
+        Self(())
+    }
+}
+
+impl Drop for ActiveMaterialization {
+    fn drop(&mut self) {
+        ACTIVE_MATERIALIZATIONS.dec();
+    }
+}
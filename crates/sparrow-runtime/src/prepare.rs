@@ -12,20 +12,27 @@ use futures::stream::BoxStream;
 use futures::{StreamExt, TryStreamExt};
 use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
 use parquet::arrow::ArrowWriter;
+use parquet::file::properties::{EnabledStatistics, WriterProperties};
+use parquet::schema::types::ColumnPath;
 
 use serde_yaml;
 use sha2::Digest;
 use sparrow_api::kaskada::v1alpha::{
-    slice_plan, source_data, PreparedFile, PulsarSubscription, SourceData, TableConfig,
+    slice_plan, source_data, DataProfileOptions, PreparedFile, PulsarSubscription, SourceData,
+    TableConfig,
 };
 
+mod change_data_capture;
 mod column_behavior;
+pub(crate) mod data_profile;
+mod entity_key;
 mod error;
 pub(crate) mod execute_input_stream;
 mod prepare_input_stream;
 mod prepare_metadata;
-mod slice_preparer;
+pub(crate) mod slice_preparer;
 
+pub use data_profile::{check_data_quality, is_failure, read_data_profile};
 pub use error::*;
 pub(crate) use prepare_metadata::*;
 use sparrow_api::kaskada::v1alpha::slice_plan::Slice;
@@ -138,6 +145,11 @@ impl<'a> fmt::Display for SourceDataWrapper<'a> {
 }
 
 /// Prepare the given file and return the list of prepared files.
+///
+/// If `data_profile` is set, each prepared file is profiled and checked
+/// against the configured data quality rules. The profile is stored with the
+/// prepared file. Violations of rules with the `FAIL` action cause preparation
+/// to fail, while others are logged and reported with the prepared metadata.
 pub async fn prepare_file(
     object_store_registry: &ObjectStoreRegistry,
     source_data: &SourceData,
@@ -145,6 +157,7 @@ pub async fn prepare_file(
     output_file_prefix: &str,
     table_config: &TableConfig,
     slice: &Option<slice_plan::Slice>,
+    data_profile: &Option<DataProfileOptions>,
 ) -> error_stack::Result<(Vec<PreparedMetadata>, Vec<PreparedFile>), Error> {
     let output_url = ObjectStoreUrl::from_str(output_path_prefix)
         .change_context_lazy(|| Error::InvalidUrl(output_path_prefix.to_owned()))?;
@@ -193,9 +206,23 @@ pub async fn prepare_file(
         ));
         let metadata_yaml_output_file = create_file(&metadata_yaml_output)?;
 
+        // Page statistics and a bloom filter on the `_key_hash` allow readers to
+        // skip row groups and pages which aren't needed by a query.
+        let mut props = WriterProperties::builder()
+            .set_statistics_enabled(EnabledStatistics::Page)
+            .set_column_bloom_filter_enabled(ColumnPath::from("_key_hash"), true);
+
+        // Profile the batch, and store the profile in the Parquet footer.
+        if let Some(options) = data_profile {
+            let profile = data_profile::profile_batch(&records, options)?;
+            check_profile(&local_result_path, &profile, options)?;
+            props = props.set_key_value_metadata(Some(vec![data_profile::to_key_value(&profile)?]));
+        }
+
         // Write batches to the local output files
-        write_batch(local_result_file, records).change_context(Error::WriteParquetData)?;
-        write_batch(local_metadata_file, metadata).change_context(Error::WriteMetadata)?;
+        write_batch(local_result_file, records, Some(props.build()))
+            .change_context(Error::WriteParquetData)?;
+        write_batch(local_metadata_file, metadata, None).change_context(Error::WriteMetadata)?;
 
         let prepared_metadata = PreparedMetadata::try_from_local_parquet_path(
             &local_result_path,
@@ -344,11 +371,43 @@ fn create_file(path: &Path) -> error_stack::Result<File, Error> {
         })
 }
 
+/// Check the data quality rules against the profile of a prepared file.
+///
+/// Fails if any rule with the `FAIL` action is violated, and logs a warning
+/// for any other violations.
+fn check_profile(
+    path: &Path,
+    profile: &sparrow_api::kaskada::v1alpha::DataProfile,
+    options: &DataProfileOptions,
+) -> error_stack::Result<(), Error> {
+    let path = path.display().to_string();
+    let violations = check_data_quality(&path, profile, &options.rules);
+
+    let mut failures = Vec::new();
+    for violation in violations {
+        if is_failure(&violation) {
+            failures.push(violation.message);
+        } else {
+            tracing::warn!("Data quality warning for '{path}': {}", violation.message);
+        }
+    }
+
+    if !failures.is_empty() {
+        let mut report = error_stack::report!(Error::DataQuality { path });
+        for failure in failures {
+            report = report.attach_printable(failure);
+        }
+        return Err(report);
+    }
+    Ok(())
+}
+
 fn write_batch(
     file: File,
     record_batch: RecordBatch,
+    props: Option<WriterProperties>,
 ) -> error_stack::Result<(), parquet::errors::ParquetError> {
-    let mut writer = ArrowWriter::try_new(file, record_batch.schema(), None)?;
+    let mut writer = ArrowWriter::try_new(file, record_batch.schema(), props)?;
     writer.write(&record_batch)?;
     writer.close()?;
     Ok(())
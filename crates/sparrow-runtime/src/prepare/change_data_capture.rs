@@ -0,0 +1,180 @@
+use anyhow::Context;
+use arrow::array::{Array, StringArray};
+use arrow::datatypes::{DataType, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use error_stack::IntoReport;
+use sparrow_api::kaskada::v1alpha::TableConfig;
+use sparrow_core::context_code;
+
+use crate::prepare::Error;
+
+/// The operations allowed in the operation column of a change-data-capture
+/// table.
+const OPERATIONS: [&str; 3] = ["insert", "update", "delete"];
+
+/// The column of a raw batch containing change-data-capture operations.
+#[derive(Debug, Clone)]
+pub(super) struct OperationColumn {
+    /// The index of the operation column in the raw schema.
+    index: usize,
+    /// The name of the operation column.
+    name: String,
+}
+
+impl OperationColumn {
+    /// Locate the operation column of the table in the given raw schema.
+    ///
+    /// Returns `None` if the table is not a change-data-capture table.
+    ///
+    /// # Errors
+    /// Invalid argument if the operation column doesn't exist or isn't a
+    /// string.
+    pub fn try_new(
+        source_schema: &SchemaRef,
+        config: &TableConfig,
+    ) -> anyhow::Result<Option<Self>> {
+        let Some(name) = config.operation_column_name() else {
+            return Ok(None);
+        };
+
+        let (index, field) = source_schema.column_with_name(name).with_context(|| {
+            context_code!(
+                tonic::Code::InvalidArgument,
+                "operation column '{}' not present in schema {:?}",
+                name,
+                source_schema
+            )
+        })?;
+        anyhow::ensure!(
+            field.data_type() == &DataType::Utf8,
+            context_code!(
+                tonic::Code::InvalidArgument,
+                "operation column '{}' must be a string, but was {:?}",
+                name,
+                field.data_type()
+            )
+        );
+
+        Ok(Some(Self {
+            index,
+            name: name.to_owned(),
+        }))
+    }
+
+    /// Check that every row of the raw batch has a valid operation.
+    pub fn validate(&self, batch: &RecordBatch) -> error_stack::Result<(), Error> {
+        let operations = batch
+            .column(self.index)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| self.invalid("expected string column".to_owned()))
+            .into_report()?;
+
+        error_stack::ensure!(
+            operations.null_count() == 0,
+            self.invalid(format!("{} null operations", operations.null_count()))
+        );
+        if let Some(operation) = operations
+            .iter()
+            .flatten()
+            .find(|operation| !OPERATIONS.contains(operation))
+        {
+            error_stack::bail!(self.invalid(format!(
+                "expected one of {OPERATIONS:?}, but was '{operation}'"
+            )));
+        }
+        Ok(())
+    }
+
+    fn invalid(&self, detail: String) -> Error {
+        Error::InvalidChangeDataCapture {
+            column: self.name.clone(),
+            detail,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use sparrow_api::kaskada::v1alpha::TableConfig;
+    use uuid::Uuid;
+
+    use super::OperationColumn;
+
+    fn operation_column() -> (Arc<Schema>, OperationColumn) {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Int64, false),
+            Field::new("op", DataType::Utf8, true),
+        ]));
+        let config =
+            TableConfig::new_with_table_source("Table", &Uuid::new_v4(), "time", None, "key", "")
+                .with_change_data_capture(Some("op"));
+        let operation_column = OperationColumn::try_new(&schema, &config).unwrap().unwrap();
+        (schema, operation_column)
+    }
+
+    fn batch(schema: Arc<Schema>, operations: Vec<Option<&str>>) -> RecordBatch {
+        let keys = Int64Array::from_iter_values(0..operations.len() as i64);
+        RecordBatch::try_new(
+            schema,
+            vec![Arc::new(keys), Arc::new(StringArray::from(operations))],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_append_only_table_has_no_operation_column() {
+        let schema = Arc::new(Schema::new(vec![Field::new("key", DataType::Int64, false)]));
+        let config =
+            TableConfig::new_with_table_source("Table", &Uuid::new_v4(), "time", None, "key", "");
+        assert!(OperationColumn::try_new(&schema, &config)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_operation_column_must_be_string() {
+        let schema = Arc::new(Schema::new(vec![Field::new("op", DataType::Int64, true)]));
+        let config =
+            TableConfig::new_with_table_source("Table", &Uuid::new_v4(), "time", None, "key", "")
+                .with_change_data_capture(Some("op"));
+        assert!(OperationColumn::try_new(&schema, &config).is_err());
+    }
+
+    #[test]
+    fn test_validate_operations() {
+        let (schema, operation_column) = operation_column();
+
+        let valid = batch(
+            schema.clone(),
+            vec![Some("insert"), Some("update"), Some("delete")],
+        );
+        operation_column.validate(&valid).unwrap();
+
+        let null = batch(schema.clone(), vec![Some("insert"), None]);
+        assert_eq!(
+            operation_column
+                .validate(&null)
+                .unwrap_err()
+                .current_context()
+                .to_string(),
+            "invalid change-data-capture operation in column 'op': 1 null operations"
+        );
+
+        let unknown = batch(schema, vec![Some("insert"), Some("upsert")]);
+        assert_eq!(
+            operation_column
+                .validate(&unknown)
+                .unwrap_err()
+                .current_context()
+                .to_string(),
+            "invalid change-data-capture operation in column 'op': expected one of [\"insert\", \
+             \"update\", \"delete\"], but was 'upsert'"
+        );
+    }
+}
@@ -15,6 +15,7 @@ use error_stack::{IntoReport, IntoReportCompat, ResultExt};
 use sparrow_core::utils::make_null_array;
 use sparrow_kernels::order_preserving_cast_to_u64;
 
+use crate::prepare::entity_key::EntityKeyColumns;
 use crate::prepare::Error;
 
 /// Defines how each column in the resulting prepared batch
@@ -31,8 +32,11 @@ pub enum ColumnBehavior {
     OrderPreservingCastToU64 { index: usize, nullable: bool },
     /// Reference the given column.
     Reference { index: usize, nullable: bool },
-    /// Hash the given column.
-    EntityKey { index: usize, nullable: bool },
+    /// Hash the given (possibly composite) entity key.
+    EntityKey {
+        key: EntityKeyColumns,
+        nullable: bool,
+    },
     /// Generates a row of monotically increasing u64s, starting
     /// at the defined offset.
     SequentialU64 { next_offset: u64 },
@@ -149,31 +153,10 @@ impl ColumnBehavior {
         })
     }
 
-    /// Create a column behavior that hashes the given field (and index) to
+    /// Create a column behavior that hashes the entity key column(s) to
     /// `u64`. This is only used for the entity key.
-    ///
-    /// # Errors
-    /// Internal error if the source field doesn't exist.
-    pub fn try_new_entity_key(
-        source_schema: &SchemaRef,
-        source_name: &str,
-        nullable: bool,
-    ) -> anyhow::Result<Self> {
-        let (source_index, _) = source_schema
-            .column_with_name(source_name)
-            .with_context(|| {
-                context_code!(
-                    tonic::Code::Internal,
-                    "entity key column '{}' not present in schema {:?}",
-                    source_name,
-                    source_schema
-                )
-            })?;
-
-        Ok(Self::EntityKey {
-            index: source_index,
-            nullable,
-        })
+    pub fn new_entity_key(key: EntityKeyColumns, nullable: bool) -> Self {
+        Self::EntityKey { key, nullable }
     }
 
     /// Create a behavior that projects a field from the source schema to the
@@ -272,17 +255,20 @@ impl ColumnBehavior {
                 );
                 column.clone()
             }
-            ColumnBehavior::EntityKey { index, nullable } => {
-                let column = batch.column(*index);
-                error_stack::ensure!(
-                    *nullable || column.null_count() == 0,
-                    Error::NullInNonNullableColumn {
-                        field: batch.schema().field(*index).name().to_owned(),
-                        null_count: column.null_count()
-                    }
-                );
+            ColumnBehavior::EntityKey { key, nullable } => {
+                for index in key.indices() {
+                    let column = batch.column(*index);
+                    error_stack::ensure!(
+                        *nullable || column.null_count() == 0,
+                        Error::NullInNonNullableColumn {
+                            field: batch.schema().field(*index).name().to_owned(),
+                            null_count: column.null_count()
+                        }
+                    );
+                }
 
-                let entity_column = sparrow_kernels::hash::hash(column)
+                let column = key.key_column(batch);
+                let entity_column = sparrow_kernels::hash::hash(column.as_ref())
                     .into_report()
                     .change_context(Error::PreparingColumn)?;
 
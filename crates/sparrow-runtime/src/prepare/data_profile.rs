@@ -0,0 +1,517 @@
+//! Profiling of the data being prepared.
+//!
+//! The profile of each prepared file is computed from the prepared batch
+//! before it is written, and stored as JSON in the key-value metadata of the
+//! prepared Parquet file. This allows it to be retrieved later without
+//! needing to store (or upload) additional files.
+
+use std::collections::BinaryHeap;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::path::Path;
+
+use arrow::array::{Array, ArrayRef, TimestampNanosecondArray, UInt64Array};
+use arrow::compute::SortOptions;
+use arrow::record_batch::RecordBatch;
+use arrow::row::{RowConverter, SortField};
+use error_stack::{IntoReport, ResultExt};
+use hashbrown::HashSet;
+use parquet::file::metadata::KeyValue;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use sparrow_api::kaskada::v1alpha::data_profile::{ColumnProfile, TimeHistogram};
+use sparrow_api::kaskada::v1alpha::data_quality_rule::{Action, Check};
+use sparrow_api::kaskada::v1alpha::{
+    DataProfile, DataProfileOptions, DataQualityRule, DataQualityViolation,
+};
+use sparrow_core::TableSchema;
+
+use super::Error;
+
+/// The key the profile is stored under in the Parquet key-value metadata.
+pub(crate) const DATA_PROFILE_METADATA_KEY: &str = "sparrow.data_profile";
+
+const DEFAULT_TIME_HISTOGRAM_BUCKETS: u32 = 10;
+
+/// The number of hashes retained for estimating distinct counts.
+///
+/// Counts up to this are exact. Above this, the standard error of the
+/// estimate is approximately `1 / sqrt(DISTINCT_SAMPLE_SIZE)`, or about 3%.
+const DISTINCT_SAMPLE_SIZE: usize = 1024;
+
+/// Compute the profile of a prepared batch.
+pub(crate) fn profile_batch(
+    records: &RecordBatch,
+    options: &DataProfileOptions,
+) -> error_stack::Result<DataProfile, Error> {
+    let schema = records.schema();
+    let time = records
+        .column(0)
+        .as_any()
+        .downcast_ref::<TimestampNanosecondArray>()
+        .ok_or(Error::Internal)
+        .into_report()
+        .attach_printable("expected '_time' column")?;
+    let key_hash = records
+        .column(2)
+        .as_any()
+        .downcast_ref::<UInt64Array>()
+        .ok_or(Error::Internal)
+        .into_report()
+        .attach_printable("expected '_key_hash' column")?;
+
+    let mut distinct_entities = DistinctEstimator::default();
+    key_hash
+        .values()
+        .iter()
+        .for_each(|hash| distinct_entities.add(*hash));
+
+    let buckets = match options.time_histogram_buckets {
+        0 => DEFAULT_TIME_HISTOGRAM_BUCKETS,
+        buckets => buckets,
+    };
+
+    // The data columns follow the key columns.
+    let columns = records
+        .columns()
+        .iter()
+        .zip(schema.fields())
+        .skip(TableSchema::NUM_KEY_COLUMNS)
+        .map(|(column, field)| profile_column(field.name(), column))
+        .collect();
+
+    // Duplicates are determined from everything except the subsort, which
+    // is unique to each row.
+    let mut duplicate_columns = vec![records.column(0).clone(), records.column(2).clone()];
+    duplicate_columns.extend(
+        records
+            .columns()
+            .iter()
+            .skip(TableSchema::NUM_KEY_COLUMNS)
+            .cloned(),
+    );
+
+    Ok(DataProfile {
+        num_rows: records.num_rows() as i64,
+        distinct_entities: distinct_entities.estimate(),
+        duplicate_rows: count_duplicate_rows(&duplicate_columns).unwrap_or(0),
+        time_histogram: time_histogram(time, buckets),
+        columns,
+    })
+}
+
+fn profile_column(name: &str, column: &ArrayRef) -> ColumnProfile {
+    let (min, max) = min_max(column).unwrap_or_default();
+    ColumnProfile {
+        name: name.to_owned(),
+        null_count: column.null_count() as i64,
+        distinct_count: distinct_count(column).unwrap_or(0),
+        min,
+        max,
+    }
+}
+
+/// Return the formatted minimum and maximum non-null values.
+///
+/// Returns `None` if the column is all null or can't be sorted.
+fn min_max(column: &ArrayRef) -> Option<(String, String)> {
+    if column.null_count() == column.len() {
+        return None;
+    }
+
+    let value_at = |descending| -> Option<String> {
+        let options = SortOptions {
+            descending,
+            nulls_first: false,
+        };
+        let indices = arrow::compute::sort_to_indices(column, Some(options), Some(1)).ok()?;
+        arrow::util::display::array_value_to_string(column, indices.value(0) as usize).ok()
+    };
+    Some((value_at(false)?, value_at(true)?))
+}
+
+/// Estimate the number of distinct non-null values in the column.
+fn distinct_count(column: &ArrayRef) -> Option<i64> {
+    let mut converter = RowConverter::new(vec![SortField::new(column.data_type().clone())]).ok()?;
+    let rows = converter.convert_columns(&[column.clone()]).ok()?;
+
+    let mut estimator = DistinctEstimator::default();
+    for (index, row) in rows.iter().enumerate() {
+        if column.is_valid(index) {
+            estimator.add(hash_bytes(row.as_ref()));
+        }
+    }
+    Some(estimator.estimate())
+}
+
+/// Count the rows which are identical to an earlier row.
+fn count_duplicate_rows(columns: &[ArrayRef]) -> Option<i64> {
+    let fields = columns
+        .iter()
+        .map(|column| SortField::new(column.data_type().clone()))
+        .collect();
+    let mut converter = RowConverter::new(fields).ok()?;
+    let rows = converter.convert_columns(columns).ok()?;
+
+    let mut seen = HashSet::with_capacity(rows.num_rows());
+    let duplicates = rows.iter().filter(|row| !seen.insert(row.as_ref())).count();
+    Some(duplicates as i64)
+}
+
+fn time_histogram(time: &TimestampNanosecondArray, buckets: u32) -> Option<TimeHistogram> {
+    // Prepared batches are sorted by time, and `_time` is non-null.
+    let values = time.values();
+    let (min, max) = (*values.first()?, *values.last()?);
+
+    // Round up, so that the maximum time falls within the last bucket.
+    let bucket_width_ns = ((max - min) / buckets as i64) + 1;
+    let mut counts = vec![0; buckets as usize];
+    for value in values.iter() {
+        counts[((value - min) / bucket_width_ns) as usize] += 1;
+    }
+
+    let start = arrow::temporal_conversions::timestamp_ns_to_datetime(min)?;
+    let end = arrow::temporal_conversions::timestamp_ns_to_datetime(max)?;
+    Some(TimeHistogram {
+        start: Some(start.into()),
+        bucket_width_ns,
+        counts,
+        end: Some(end.into()),
+    })
+}
+
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = ahash::RandomState::with_seeds(1234, 5678, 9012, 3456).build_hasher();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Estimates the number of distinct values using the "K minimum values"
+/// sketch.
+///
+/// This retains the smallest `DISTINCT_SAMPLE_SIZE` hashes. If there are
+/// fewer distinct hashes than that, the count is exact. Otherwise, the
+/// density of the retained hashes is used to estimate the total.
+#[derive(Default)]
+struct DistinctEstimator {
+    /// Max-heap of the smallest hashes seen.
+    smallest: BinaryHeap<u64>,
+    /// The hashes in `smallest`, to avoid adding duplicates.
+    retained: HashSet<u64>,
+}
+
+impl DistinctEstimator {
+    fn add(&mut self, hash: u64) {
+        if self.retained.contains(&hash) {
+            return;
+        }
+
+        if self.smallest.len() < DISTINCT_SAMPLE_SIZE {
+            self.smallest.push(hash);
+            self.retained.insert(hash);
+        } else if let Some(largest) = self.smallest.peek().copied() {
+            if hash < largest {
+                self.smallest.pop();
+                self.retained.remove(&largest);
+                self.smallest.push(hash);
+                self.retained.insert(hash);
+            }
+        }
+    }
+
+    fn estimate(&self) -> i64 {
+        match self.smallest.peek() {
+            Some(largest) if self.smallest.len() == DISTINCT_SAMPLE_SIZE => {
+                let fraction = *largest as f64 / u64::MAX as f64;
+                ((DISTINCT_SAMPLE_SIZE - 1) as f64 / fraction) as i64
+            }
+            _ => self.smallest.len() as i64,
+        }
+    }
+}
+
+/// Return the key-value metadata for storing `profile` in a Parquet file.
+pub(crate) fn to_key_value(profile: &DataProfile) -> error_stack::Result<KeyValue, Error> {
+    let value = serde_json::to_string(profile)
+        .into_report()
+        .change_context(Error::Internal)?;
+    Ok(KeyValue::new(DATA_PROFILE_METADATA_KEY.to_owned(), value))
+}
+
+/// Read the profile from the key-value metadata of a prepared Parquet file.
+///
+/// Returns `None` if the file was prepared without profiling.
+pub(crate) fn from_key_value(
+    key_value_metadata: Option<&Vec<KeyValue>>,
+) -> anyhow::Result<Option<DataProfile>> {
+    let value = key_value_metadata.and_then(|key_values| {
+        key_values
+            .iter()
+            .find(|key_value| key_value.key == DATA_PROFILE_METADATA_KEY)
+            .and_then(|key_value| key_value.value.as_ref())
+    });
+    match value {
+        None => Ok(None),
+        Some(value) => Ok(Some(serde_json::from_str(value)?)),
+    }
+}
+
+/// Read the profile from the prepared Parquet file at `path`.
+///
+/// Returns `None` if the file was prepared without profiling.
+pub fn read_data_profile(path: &Path) -> anyhow::Result<Option<DataProfile>> {
+    let file = std::fs::File::open(path)?;
+    let reader = SerializedFileReader::new(file)?;
+    from_key_value(reader.metadata().file_metadata().key_value_metadata())
+}
+
+/// Check the `rules` against the `profile` of the prepared file at `path`.
+///
+/// Returns the violated rules.
+pub fn check_data_quality(
+    path: &str,
+    profile: &DataProfile,
+    rules: &[DataQualityRule],
+) -> Vec<DataQualityViolation> {
+    rules
+        .iter()
+        .filter_map(|rule| {
+            let message = check_rule(profile, rule)?;
+            Some(DataQualityViolation {
+                path: path.to_owned(),
+                rule: Some(rule.clone()),
+                message,
+            })
+        })
+        .collect()
+}
+
+/// Return a message describing the failure if the rule is violated.
+fn check_rule(profile: &DataProfile, rule: &DataQualityRule) -> Option<String> {
+    let histogram = profile.time_histogram.as_ref();
+    let min_time = histogram.and_then(|histogram| histogram.start.as_ref());
+    let max_time = histogram.and_then(|histogram| histogram.end.as_ref());
+
+    match rule.check.as_ref()? {
+        Check::MaxNullFraction(max_fraction) => {
+            let Some(column) = profile.columns.iter().find(|c| c.name == rule.column) else {
+                return Some(format!("column '{}' does not exist", rule.column));
+            };
+            let fraction = column.null_count as f64 / profile.num_rows.max(1) as f64;
+            (fraction > *max_fraction).then(|| {
+                format!(
+                    "column '{}' is {:.1}% null, exceeding the limit of {:.1}%",
+                    rule.column,
+                    fraction * 100.0,
+                    max_fraction * 100.0
+                )
+            })
+        }
+        Check::MinEventTime(limit) => {
+            let min_time = min_time?;
+            (timestamp_ns(min_time) < timestamp_ns(limit)).then(|| {
+                format!(
+                    "event time {} is before the earliest allowed time {}",
+                    format_timestamp(min_time),
+                    format_timestamp(limit)
+                )
+            })
+        }
+        Check::MaxEventTime(limit) => {
+            let max_time = max_time?;
+            (timestamp_ns(max_time) > timestamp_ns(limit)).then(|| {
+                format!(
+                    "event time {} is after the latest allowed time {}",
+                    format_timestamp(max_time),
+                    format_timestamp(limit)
+                )
+            })
+        }
+        Check::MaxDuplicateRows(limit) => (profile.duplicate_rows > *limit).then(|| {
+            format!(
+                "{} duplicate rows, exceeding the limit of {limit}",
+                profile.duplicate_rows
+            )
+        }),
+        Check::MaxDistinctEntities(limit) => (profile.distinct_entities > *limit).then(|| {
+            format!(
+                "approximately {} distinct entities, exceeding the limit of {limit}",
+                profile.distinct_entities
+            )
+        }),
+    }
+}
+
+fn timestamp_ns(timestamp: &prost_wkt_types::Timestamp) -> i64 {
+    timestamp.seconds * 1_000_000_000 + timestamp.nanos as i64
+}
+
+fn format_timestamp(timestamp: &prost_wkt_types::Timestamp) -> String {
+    match arrow::temporal_conversions::timestamp_ns_to_datetime(timestamp_ns(timestamp)) {
+        Some(datetime) => datetime.to_string(),
+        None => format!("{timestamp:?}"),
+    }
+}
+
+/// Return true if the violation should fail preparation.
+pub fn is_failure(violation: &DataQualityViolation) -> bool {
+    violation
+        .rule
+        .as_ref()
+        .map_or(false, |rule| rule.action() == Action::Fail)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{Float64Array, StringArray, UInt64Array};
+    use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+
+    use super::*;
+
+    fn prepared_batch() -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new(
+                "_time",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+            Field::new("_subsort", DataType::UInt64, false),
+            Field::new("_key_hash", DataType::UInt64, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("amount", DataType::Float64, true),
+        ]);
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(TimestampNanosecondArray::from(vec![0, 10, 10, 10, 99])),
+                Arc::new(UInt64Array::from(vec![0, 1, 2, 3, 4])),
+                Arc::new(UInt64Array::from(vec![7, 8, 8, 9, 7])),
+                Arc::new(StringArray::from(vec![
+                    Some("a"),
+                    Some("b"),
+                    Some("b"),
+                    None,
+                    Some("a"),
+                ])),
+                Arc::new(Float64Array::from(vec![
+                    Some(5.0),
+                    Some(1.5),
+                    Some(1.5),
+                    None,
+                    None,
+                ])),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_profile_batch() {
+        let options = DataProfileOptions {
+            time_histogram_buckets: 4,
+            rules: vec![],
+        };
+        let profile = profile_batch(&prepared_batch(), &options).unwrap();
+        insta::assert_yaml_snapshot!(profile, @r###"
+        ---
+        num_rows: 5
+        distinct_entities: 3
+        duplicate_rows: 1
+        time_histogram:
+          start: "1970-01-01T00:00:00Z"
+          bucket_width_ns: 25
+          counts:
+            - 4
+            - 0
+            - 0
+            - 1
+          end: "1970-01-01T00:00:00.000000099Z"
+        columns:
+          - name: name
+            null_count: 1
+            distinct_count: 2
+            min: a
+            max: b
+          - name: amount
+            null_count: 2
+            distinct_count: 2
+            min: "1.5"
+            max: "5.0"
+        "###);
+    }
+
+    #[test]
+    fn test_distinct_estimate() {
+        let mut estimator = DistinctEstimator::default();
+        for i in 0..100_000u64 {
+            estimator.add(hash_bytes(&(i % 50_000).to_le_bytes()));
+        }
+        let estimate = estimator.estimate();
+        assert!(
+            (45_000..55_000).contains(&estimate),
+            "estimate {estimate} should be near 50000"
+        );
+    }
+
+    #[test]
+    fn test_key_value_round_trip() {
+        let profile = profile_batch(&prepared_batch(), &DataProfileOptions::default()).unwrap();
+        let key_value = to_key_value(&profile).unwrap();
+        assert_eq!(
+            from_key_value(Some(&vec![key_value])).unwrap(),
+            Some(profile)
+        );
+        assert_eq!(from_key_value(None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_check_data_quality() {
+        let profile = profile_batch(&prepared_batch(), &DataProfileOptions::default()).unwrap();
+        let rules = vec![
+            DataQualityRule {
+                column: "amount".to_owned(),
+                check: Some(Check::MaxNullFraction(0.5)),
+                action: Action::Fail as i32,
+            },
+            DataQualityRule {
+                column: "amount".to_owned(),
+                check: Some(Check::MaxNullFraction(0.2)),
+                action: Action::Warn as i32,
+            },
+            DataQualityRule {
+                column: "".to_owned(),
+                check: Some(Check::MinEventTime(prost_wkt_types::Timestamp {
+                    seconds: 1,
+                    nanos: 0,
+                })),
+                action: Action::Fail as i32,
+            },
+            DataQualityRule {
+                column: "".to_owned(),
+                check: Some(Check::MaxDuplicateRows(0)),
+                action: Action::Unspecified as i32,
+            },
+        ];
+
+        let violations = check_data_quality("file.parquet", &profile, &rules);
+        let messages: Vec<_> = violations
+            .iter()
+            .map(|v| (is_failure(v), v.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    false,
+                    "column 'amount' is 40.0% null, exceeding the limit of 20.0%"
+                ),
+                (
+                    true,
+                    "event time 1970-01-01 00:00:00 is before the earliest allowed time \
+                     1970-01-01 00:00:01"
+                ),
+                (false, "1 duplicate rows, exceeding the limit of 0"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use arrow::array::{ArrayRef, StructArray};
+use arrow::datatypes::{DataType, Field, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use sparrow_api::kaskada::v1alpha::TableConfig;
+use sparrow_core::context_code;
+
+/// The column(s) of a raw batch making up the entity key.
+///
+/// Most tables are keyed by a single column, which is used as-is. Tables with
+/// additional grouping columns have a composite key, which is represented as a
+/// struct containing a field for each component column.
+#[derive(Debug, Clone)]
+pub(super) struct EntityKeyColumns {
+    /// The index of each key column in the raw schema.
+    indices: Vec<usize>,
+    /// The type of the (possibly composite) key.
+    data_type: DataType,
+}
+
+impl EntityKeyColumns {
+    /// Locate the entity key columns of the table in the given raw schema.
+    ///
+    /// # Errors
+    /// Internal error if any of the key columns don't exist.
+    pub fn try_new(source_schema: &SchemaRef, config: &TableConfig) -> anyhow::Result<Self> {
+        let mut indices = Vec::new();
+        let mut fields = Vec::new();
+        for name in config.group_column_names() {
+            let (index, field) = source_schema.column_with_name(name).with_context(|| {
+                context_code!(
+                    tonic::Code::Internal,
+                    "entity key column '{}' not present in schema {:?}",
+                    name,
+                    source_schema
+                )
+            })?;
+            indices.push(index);
+            fields.push(Field::new(name, field.data_type().clone(), true));
+        }
+
+        let data_type = if config.has_composite_key() {
+            DataType::Struct(fields)
+        } else {
+            fields[0].data_type().clone()
+        };
+
+        Ok(Self { indices, data_type })
+    }
+
+    /// Create entity key columns for a single key column.
+    #[cfg(test)]
+    pub fn new_single(index: usize, data_type: DataType) -> Self {
+        Self {
+            indices: vec![index],
+            data_type,
+        }
+    }
+
+    /// The indices of the columns making up the key.
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// The type of the entity key.
+    pub fn data_type(&self) -> &DataType {
+        &self.data_type
+    }
+
+    /// Return the entity key of each row of the raw batch.
+    pub fn key_column(&self, batch: &RecordBatch) -> ArrayRef {
+        match &self.data_type {
+            DataType::Struct(fields) if self.indices.len() > 1 => {
+                let columns = fields
+                    .iter()
+                    .zip(self.indices.iter())
+                    .map(|(field, index)| (field.clone(), batch.column(*index).clone()))
+                    .collect::<Vec<_>>();
+                Arc::new(StructArray::from(columns))
+            }
+            _ => batch.column(self.indices[0]).clone(),
+        }
+    }
+}
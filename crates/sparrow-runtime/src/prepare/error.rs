@@ -53,6 +53,10 @@ pub enum Error {
     DownloadingObject(String, String),
     #[display(fmt = "invalid url: {_0}")]
     InvalidUrl(String),
+    #[display(fmt = "data quality checks failed for '{path}'")]
+    DataQuality { path: String },
+    #[display(fmt = "invalid change-data-capture operation in column '{column}': {detail}")]
+    InvalidChangeDataCapture { column: String, detail: String },
 }
 
 impl error_stack::Context for Error {}
@@ -64,6 +68,9 @@ impl sparrow_core::ErrorCode for Error {
                 tonic::Code::InvalidArgument
             }
             Self::UnsupportedOutputPath(_) => tonic::Code::Unimplemented,
+            Self::DataQuality { .. } | Self::InvalidChangeDataCapture { .. } => {
+                tonic::Code::FailedPrecondition
+            }
             _ => tonic::Code::Internal,
         }
     }
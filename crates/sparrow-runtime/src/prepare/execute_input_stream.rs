@@ -1,6 +1,5 @@
 use std::sync::Arc;
 
-use anyhow::Context;
 use arrow::array::{ArrayRef, PrimitiveArray, TimestampNanosecondArray, UInt64Array};
 use arrow::compute::SortColumn;
 use arrow::datatypes::{ArrowPrimitiveType, SchemaRef, TimestampNanosecondType, UInt64Type};
@@ -14,6 +13,8 @@ use sparrow_api::kaskada::v1alpha::{slice_plan, TableConfig};
 use sparrow_core::{downcast_primitive_array, TableSchema};
 
 use crate::execute::key_hash_inverse::ThreadSafeKeyHashInverse;
+use crate::prepare::change_data_capture::OperationColumn;
+use crate::prepare::entity_key::EntityKeyColumns;
 use crate::prepare::slice_preparer::SlicePreparer;
 use crate::prepare::Error;
 
@@ -86,11 +87,9 @@ pub async fn prepare_input<'a>(
         columns.push(ColumnBehavior::try_default_subsort(prepare_hash)?);
     }
 
-    columns.push(ColumnBehavior::try_new_entity_key(
-        &raw_schema,
-        &config.group_column_name,
-        false,
-    )?);
+    let entity_key = EntityKeyColumns::try_new(&raw_schema, config)?;
+    let operation_column = OperationColumn::try_new(&raw_schema, config)?;
+    columns.push(ColumnBehavior::new_entity_key(entity_key.clone(), false));
 
     // Add column behaviors for each column.  This means we include the key columns
     // redundantly, but cleaning that up is a big refactor.
@@ -102,15 +101,7 @@ pub async fn prepare_input<'a>(
         )?);
     }
 
-    // we've already checked that the group column exists so we can just unwrap it here
-    let (entity_column_index, entity_key_column) = raw_schema
-        .column_with_name(&config.group_column_name)
-        .with_context(|| "")?;
-    let slice_preparer = SlicePreparer::try_new(
-        entity_column_index,
-        entity_key_column.data_type().clone(),
-        slice,
-    )?;
+    let slice_preparer = SlicePreparer::try_new(entity_key.clone(), slice)?;
 
     Ok(async_stream::try_stream! {
         let mut input_buffer = InputBuffer::new();
@@ -183,6 +174,9 @@ pub async fn prepare_input<'a>(
 
             // 2. Slicing may reduce the number of entities to operate and sort on.
             let record_batch = slice_preparer.slice_batch(record_batch)?;
+            if let Some(operation_column) = &operation_column {
+                operation_column.validate(&record_batch)?;
+            }
 
             // 3. Prepare each of the columns by getting the column behavior result
             let mut prepared_columns: Vec<ArrayRef> = Vec::new();
@@ -192,9 +186,9 @@ pub async fn prepare_input<'a>(
             }
 
             // 4. Update the key hash mappings
-            let key_column = record_batch.column(entity_column_index);
+            let key_column = entity_key.key_column(&record_batch);
             let key_hashes = prepared_columns.get(2).expect("key column");
-            update_key_inverse(key_column, key_hashes, key_hash_inverse.clone()).await?;
+            update_key_inverse(&key_column, key_hashes, key_hash_inverse.clone()).await?;
 
             let record_batch = RecordBatch::try_new(prepared_schema.clone(), prepared_columns)
                 .into_report()
@@ -1,6 +1,5 @@
 use std::borrow::BorrowMut;
 
-use anyhow::Context;
 use arrow::array::{ArrayRef, UInt64Array};
 use arrow::compute::SortColumn;
 use arrow::datatypes::{ArrowPrimitiveType, TimestampNanosecondType};
@@ -13,6 +12,8 @@ use itertools::Itertools;
 use sparrow_api::kaskada::v1alpha::{slice_plan, TableConfig};
 use sparrow_core::{downcast_primitive_array, TableSchema};
 
+use crate::prepare::change_data_capture::OperationColumn;
+use crate::prepare::entity_key::EntityKeyColumns;
 use crate::prepare::slice_preparer::SlicePreparer;
 use crate::prepare::Error;
 use crate::RawMetadata;
@@ -57,11 +58,9 @@ pub async fn prepare_input<'a>(
         columns.push(ColumnBehavior::try_default_subsort(prepare_hash)?);
     }
 
-    columns.push(ColumnBehavior::try_new_entity_key(
-        &raw_metadata.raw_schema,
-        &config.group_column_name,
-        false,
-    )?);
+    let entity_key = EntityKeyColumns::try_new(&raw_metadata.raw_schema, config)?;
+    let operation_column = OperationColumn::try_new(&raw_metadata.raw_schema, config)?;
+    columns.push(ColumnBehavior::new_entity_key(entity_key.clone(), false));
 
     // Add column behaviors for each column.  This means we include the key columns
     // redundantly, but cleaning that up is a big refactor.
@@ -73,23 +72,16 @@ pub async fn prepare_input<'a>(
         )?);
     }
 
-    // we've already checked that the group column exists so we can just unwrap it here
-    let (entity_column_index, entity_key_column) = raw_metadata
-        .raw_schema
-        .column_with_name(&config.group_column_name)
-        .with_context(|| "")?;
-    let slice_preparer = SlicePreparer::try_new(
-        entity_column_index,
-        entity_key_column.data_type().clone(),
-        slice.as_ref(),
-    )?;
-
-    let mut metadata = PrepareMetadata::new(entity_key_column.data_type().clone());
+    let mut metadata = PrepareMetadata::new(entity_key.data_type().clone());
+    let slice_preparer = SlicePreparer::try_new(entity_key.clone(), slice.as_ref())?;
 
     Ok(async_stream::try_stream! {
         while let Some(Ok(batch)) = reader.next().await {
             // 1. Slicing may reduce the number of entities to operate and sort on.
             let read_batch = slice_preparer.slice_batch(batch)?;
+            if let Some(operation_column) = &operation_column {
+                operation_column.validate(&read_batch)?;
+            }
             // 2. Prepare each of the columns by getting the column behavior result
             let mut prepared_columns = Vec::new();
             for c in columns.iter_mut() {
@@ -102,9 +94,9 @@ pub async fn prepare_input<'a>(
             }
 
             // 3. Update the key hash mappings
-            let key_column = read_batch.column(entity_column_index);
+            let key_column = entity_key.key_column(&read_batch);
             let key_hashes = prepared_columns.get(2).expect("key column");
-            update_key_metadata(key_column, key_hashes, &mut metadata)?;
+            update_key_metadata(&key_column, key_hashes, &mut metadata)?;
 
             // 4. Pull out the time, subsort and key hash columns to sort the record batch
             let time_column = &prepared_columns[0];
@@ -273,4 +265,59 @@ mod tests {
         assert_eq!(vec![1, 3, 3, 5, 6, 7, 7], time.values());
         assert_eq!(metadata.num_rows(), 4);
     }
+
+    #[tokio::test]
+    async fn test_entity_key_mapping_composite() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                "time",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+            Field::new("merchant", DataType::Utf8, true),
+            Field::new("country", DataType::Utf8, true),
+        ]));
+        let raw_metadata = RawMetadata::from_raw_schema(schema.clone()).unwrap();
+        let time = TimestampNanosecondArray::from(vec![1, 2, 3, 4]);
+        let merchants = StringArray::from(vec!["a", "a", "b", "a"]);
+        let countries = StringArray::from(vec!["us", "uk", "us", "us"]);
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![Arc::new(time), Arc::new(merchants), Arc::new(countries)],
+        )
+        .unwrap();
+        let reader = futures::stream::iter(vec![Ok(batch)]);
+        let config = TableConfig::new_with_table_source(
+            "Table1",
+            &Uuid::parse_str("936DA01F9ABD4d9d80C702AF85C822A8").unwrap(),
+            "time",
+            None,
+            "merchant",
+            "grouping",
+        )
+        .with_additional_group_columns(&["country"]);
+        let stream =
+            prepare_input_stream::prepare_input(reader.boxed(), &config, raw_metadata, 0, &None)
+                .await
+                .unwrap();
+        let batches = stream.collect::<Vec<_>>().await;
+        assert_eq!(batches.len(), 1);
+        let (batch, metadata) = batches[0].as_ref().unwrap();
+
+        // Rows with the same merchant and country have the same key hash.
+        let key_hashes: &UInt64Array = downcast_primitive_array(batch.column(2).as_ref()).unwrap();
+        assert_eq!(key_hashes.value(0), key_hashes.value(3));
+        assert_ne!(key_hashes.value(0), key_hashes.value(1));
+        assert_ne!(key_hashes.value(0), key_hashes.value(2));
+
+        // The metadata contains the composite key for each distinct entity.
+        assert_eq!(metadata.num_rows(), 3);
+        assert_eq!(
+            metadata.schema().field(1).data_type(),
+            &DataType::Struct(vec![
+                Field::new("merchant", DataType::Utf8, true),
+                Field::new("country", DataType::Utf8, true),
+            ])
+        );
+    }
 }
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use arrow::array::{Array, ArrayRef, StringArray, UInt64Array};
+use arrow::array::{Array, ArrayRef, BooleanArray, StringArray, UInt64Array};
 use arrow::compute::eq_scalar;
 use arrow::datatypes::DataType;
 use arrow::record_batch::RecordBatch;
@@ -10,10 +10,11 @@ use sparrow_api::kaskada::v1alpha::slice_plan;
 use sparrow_core::{context_code, downcast_primitive_array};
 use sparrow_kernels::hash::hash;
 
+use crate::prepare::entity_key::EntityKeyColumns;
 use crate::prepare::Error;
 
 pub(super) struct SlicePreparer {
-    entity_column_index: usize,
+    entity_key: EntityKeyColumns,
     prepare_filter: PrepareFilter,
 }
 
@@ -21,44 +22,49 @@ enum PrepareFilter {
     NoFilter,
     EntityKeys { entity_keys: HashSet<u64> },
     PercentFilter { percent: f64 },
+    Sample { upper_bound: u64, seed: u64 },
 }
 
 impl SlicePreparer {
     pub(super) fn try_new(
-        entity_column_index: usize,
-        entity_type: DataType,
+        entity_key: EntityKeyColumns,
         slice: Option<&slice_plan::Slice>,
     ) -> anyhow::Result<Self> {
+        let entity_type = entity_key.data_type().clone();
         let prepare_filter = match slice {
             None => PrepareFilter::NoFilter,
             Some(slice_plan::Slice::Percent(percent)) => PrepareFilter::PercentFilter {
                 percent: percent.percent,
             },
             Some(slice_plan::Slice::EntityKeys(entity_keys)) => {
-                let entity_keys: ArrayRef =
-                    Arc::new(StringArray::from(entity_keys.entity_keys.clone()));
-                let entity_keys = arrow::compute::cast(&entity_keys, &entity_type)?;
                 anyhow::ensure!(
-                    entity_keys.null_count() == 0,
+                    !matches!(entity_type, DataType::Struct(_)),
                     context_code!(
                         tonic::Code::InvalidArgument,
-                        "Casting provided entity keys to type {} resulted in {} null keys.",
-                        entity_type,
-                        entity_keys.null_count()
+                        "Slicing by entity keys is not supported for composite keys"
                     )
                 );
-                let entity_key_hashes = hash(&entity_keys)?;
-                let entity_key_hashes: &UInt64Array = downcast_primitive_array(&entity_key_hashes)?;
-                let desired_keys: HashSet<u64> =
-                    entity_key_hashes.values().iter().copied().collect();
+                let desired_keys = entity_key_hashes(&entity_keys.entity_keys, &entity_type)?;
                 PrepareFilter::EntityKeys {
                     entity_keys: desired_keys,
                 }
             }
+            Some(slice_plan::Slice::Sample(sample)) => PrepareFilter::Sample {
+                upper_bound: percent_upper_bound(sample.percent),
+                seed: sample.seed,
+            },
+            // These slices depend on the rows of another table, so tables
+            // are prepared in full and filtered when they are read.
+            Some(slice_plan::Slice::Stratified(_) | slice_plan::Slice::Dimension(_)) => {
+                anyhow::bail!(context_code!(
+                    tonic::Code::InvalidArgument,
+                    "Stratified and dimension slices are applied when reading, not preparing"
+                ))
+            }
         };
 
         Ok(Self {
-            entity_column_index,
+            entity_key,
             prepare_filter,
         })
     }
@@ -87,7 +93,7 @@ impl SlicePreparer {
                 // solution since this can result in unexpected results such as
                 // more than 10% (all the entity keys hash to below the upper
                 // bound) or less than 10%.
-                let upper_bound = (percent / 100.0 * (u64::MAX as f64)).round() as u64;
+                let upper_bound = percent_upper_bound(*percent);
                 let entity_column = self.hash_entity_column(&record_batch)?;
 
                 arrow::compute::lt_eq_scalar(&entity_column, upper_bound)
@@ -112,6 +118,14 @@ impl SlicePreparer {
                     .into_report()
                     .change_context(Error::SlicingBatch)?
             }
+            PrepareFilter::Sample { upper_bound, seed } => {
+                let entity_column = self.hash_entity_column(&record_batch)?;
+                entity_column
+                    .values()
+                    .iter()
+                    .map(|key_hash| Some(sample_hash(*key_hash, *seed) <= *upper_bound))
+                    .collect::<BooleanArray>()
+            }
         };
 
         let result = arrow::compute::filter_record_batch(&record_batch, &include_filter)
@@ -124,13 +138,54 @@ impl SlicePreparer {
         &self,
         record_batch: &RecordBatch,
     ) -> error_stack::Result<UInt64Array, Error> {
-        let entity_column = record_batch.column(self.entity_column_index);
-        hash(entity_column)
+        let entity_column = self.entity_key.key_column(record_batch);
+        hash(entity_column.as_ref())
             .into_report()
             .change_context(Error::SlicingBatch)
     }
 }
 
+/// Return the largest key hash included in a slice of the given percent.
+pub(crate) fn percent_upper_bound(percent: f64) -> u64 {
+    (percent / 100.0 * (u64::MAX as f64)).round() as u64
+}
+
+/// Return the position of an entity within the sample with the given seed.
+///
+/// An entity is in a sample of a given percent if this is at most the
+/// [percent_upper_bound]. The seed is mixed into the key hash, so the same
+/// seed always selects the same entities while different seeds select
+/// independent samples.
+pub(crate) fn sample_hash(key_hash: u64, seed: u64) -> u64 {
+    // The finalizer from SplitMix64.
+    let mut z = key_hash ^ seed.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Return the hashes of the given entity keys, after casting them to the
+/// type of the entity key column.
+pub(crate) fn entity_key_hashes(
+    entity_keys: &[String],
+    entity_type: &DataType,
+) -> anyhow::Result<HashSet<u64>> {
+    let entity_keys: ArrayRef = Arc::new(StringArray::from(entity_keys.to_vec()));
+    let entity_keys = arrow::compute::cast(&entity_keys, entity_type)?;
+    anyhow::ensure!(
+        entity_keys.null_count() == 0,
+        context_code!(
+            tonic::Code::InvalidArgument,
+            "Casting provided entity keys to type {} resulted in {} null keys.",
+            entity_type,
+            entity_keys.null_count()
+        )
+    );
+    let entity_key_hashes = hash(&entity_keys)?;
+    let entity_key_hashes: &UInt64Array = downcast_primitive_array(&entity_key_hashes)?;
+    Ok(entity_key_hashes.values().iter().copied().collect())
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -146,12 +201,11 @@ mod tests {
 
     #[test]
     fn test_preparer_slice_batch_100_percent() {
-        let entity_column_index = 0;
         let percent = 100.0;
         let expected_batch_size = 6;
 
         let preparer = SlicePreparer {
-            entity_column_index,
+            entity_key: EntityKeyColumns::new_single(0, DataType::Utf8),
             prepare_filter: PrepareFilter::PercentFilter { percent },
         };
 
@@ -173,11 +227,10 @@ mod tests {
 
     #[test]
     fn test_preparer_slice_batch_50_percent() {
-        let entity_column_index = 0;
         let percent = 50.0;
 
         let preparer = SlicePreparer {
-            entity_column_index,
+            entity_key: EntityKeyColumns::new_single(0, DataType::Utf8),
             prepare_filter: PrepareFilter::PercentFilter { percent },
         };
 
@@ -200,11 +253,10 @@ mod tests {
 
     #[test]
     fn test_preparer_slice_batch_20_percent() {
-        let entity_column_index = 0;
         let percent = 20.0;
 
         let preparer = SlicePreparer {
-            entity_column_index,
+            entity_key: EntityKeyColumns::new_single(0, DataType::Utf8),
             prepare_filter: PrepareFilter::PercentFilter { percent },
         };
 
@@ -228,13 +280,12 @@ mod tests {
 
     #[test]
     fn test_preparer_slice_specific_entity_key() {
-        let entity_column_index = 0;
         let mut hash_set = HashSet::new();
         hash_set.insert(HASH_A);
         hash_set.insert(HASH_B);
 
         let preparer = SlicePreparer {
-            entity_column_index,
+            entity_key: EntityKeyColumns::new_single(0, DataType::Utf8),
             prepare_filter: PrepareFilter::EntityKeys {
                 entity_keys: hash_set,
             },
@@ -267,4 +318,60 @@ mod tests {
         .unwrap();
         assert_eq!(sliced_batch, expected_batch);
     }
+
+    fn labeled_batch() -> RecordBatch {
+        RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Utf8, false),
+                Field::new("label", DataType::Utf8, true),
+            ])),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b", "c", "d", "e", "f"])),
+                Arc::new(StringArray::from(vec![
+                    Some("fraud"),
+                    Some("ok"),
+                    None,
+                    Some("ok"),
+                    Some("fraud"),
+                    Some("ok"),
+                ])),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn slice_preparer(slice: slice_plan::Slice) -> anyhow::Result<SlicePreparer> {
+        SlicePreparer::try_new(
+            EntityKeyColumns::new_single(0, DataType::Utf8),
+            Some(&slice),
+        )
+    }
+
+    #[test]
+    fn test_preparer_sample_is_reproducible() {
+        let sample = |percent, seed| {
+            slice_preparer(slice_plan::Slice::Sample(slice_plan::SampleSlice {
+                percent,
+                seed,
+            }))
+            .unwrap()
+            .slice_batch(labeled_batch())
+            .unwrap()
+        };
+
+        assert_eq!(sample(50.0, 7), sample(50.0, 7));
+        assert_eq!(sample(100.0, 7).num_rows(), 6);
+        assert_eq!(sample(0.0, 7).num_rows(), 0);
+    }
+
+    #[test]
+    fn test_preparer_rejects_dimension() {
+        // Dimension slices are applied when tables are read.
+        let slice = slice_plan::Slice::Dimension(slice_plan::DimensionSlice {
+            table_name: "Customers".to_owned(),
+            column: "label".to_owned(),
+            values: vec!["ok".to_owned()],
+        });
+        assert!(slice_preparer(slice).is_err());
+    }
 }
@@ -1,5 +1,6 @@
 mod error;
 mod parquet_stream;
+mod scan_filter;
 pub(super) mod sort_in_time;
 pub(crate) mod stream_reader;
 pub(crate) mod table_reader;
@@ -32,6 +32,8 @@ pub enum Error {
     },
     #[display(fmt = "failed to select necessary prepared files")]
     SelectPreparedFiles,
+    #[display(fmt = "failed to determine filter for skipping row groups")]
+    DetermineScanFilter,
     #[display(fmt = "failed to queue download of necessary prepared files")]
     QueueFileDownloads,
     #[display(
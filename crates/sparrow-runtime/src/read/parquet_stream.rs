@@ -4,11 +4,13 @@ use error_stack::{IntoReport, IntoReportCompat, ResultExt};
 use futures::stream::BoxStream;
 use futures::StreamExt;
 use hashbrown::HashSet;
+use parquet::arrow::arrow_reader::ArrowReaderOptions;
 use parquet::arrow::{ParquetRecordBatchStreamBuilder, ProjectionMask};
 use sparrow_arrow::attachments::{RecordBatchAttachment, SchemaAttachment};
 use sparrow_core::{KeyTriple, TableSchema};
 
 use crate::data_manager::DataHandle;
+use crate::read::scan_filter::{PrunedRowGroups, ScanFilter};
 use crate::{validate_batch_schema, Batch};
 
 #[derive(derive_more::Display, Debug)]
@@ -19,6 +21,8 @@ pub enum Error {
     OpenParquetFile,
     #[display(fmt = "failed to determine columns to read")]
     DetermineColumns,
+    #[display(fmt = "failed to determine row groups to read")]
+    PruneRowGroups,
     #[display(
         fmt = "data appeared out of order (prev last row = {prev_last}, curr first row = {curr_first})"
     )]
@@ -32,10 +36,21 @@ pub enum Error {
 
 impl error_stack::Context for Error {}
 
+/// Create a stream reading the projected columns of a prepared file.
+///
+/// Row groups and pages which can't contain rows matching the `scan_filter`
+/// are skipped. Returns the stream and the row groups which will be read.
 pub(super) async fn new_parquet_stream(
     data_handle: &DataHandle,
     projected_schema: &TableSchema,
-) -> error_stack::Result<BoxStream<'static, error_stack::Result<Batch, Error>>, Error> {
+    scan_filter: &ScanFilter,
+) -> error_stack::Result<
+    (
+        BoxStream<'static, error_stack::Result<Batch, Error>>,
+        PrunedRowGroups,
+    ),
+    Error,
+> {
     let path = data_handle
         .get_path()
         .await
@@ -46,7 +61,8 @@ pub(super) async fn new_parquet_stream(
         .await
         .into_report()
         .change_context(Error::OpenParquetFile)?;
-    let builder = ParquetRecordBatchStreamBuilder::new(file)
+    let options = ArrowReaderOptions::new().with_page_index(true);
+    let builder = ParquetRecordBatchStreamBuilder::new_with_options(file, options)
         .await
         .into_report()
         .change_context(Error::OpenParquetFile)?;
@@ -56,10 +72,29 @@ pub(super) async fn new_parquet_stream(
         .into_report()
         .change_context(Error::DetermineColumns)?;
 
+    let mut pruned = scan_filter.prune_row_groups(builder.metadata());
+    let row_groups = std::mem::take(&mut pruned.row_groups);
+    let bloom_filter = scan_filter.clone();
+    let bloom_path = path.to_owned();
+    pruned.row_groups = tokio::task::spawn_blocking(move || {
+        bloom_filter.check_bloom_filters(&bloom_path, row_groups)
+    })
+    .await
+    .into_report()
+    .change_context(Error::PruneRowGroups)?
+    .into_report()
+    .change_context(Error::PruneRowGroups)?;
+    let row_selection = scan_filter.select_pages(builder.metadata(), &pruned.row_groups);
+
     let mask = ProjectionMask::leaves(builder.parquet_schema(), reader_columns);
-    let projected_reader = builder
+    let mut builder = builder
         .with_batch_size(BATCH_SIZE)
         .with_projection(mask)
+        .with_row_groups(pruned.row_groups.clone());
+    if let Some(row_selection) = row_selection {
+        builder = builder.with_row_selection(row_selection);
+    }
+    let projected_reader = builder
         .build()
         .into_report()
         .change_context(Error::OpenParquetFile)?;
@@ -105,7 +140,7 @@ pub(super) async fn new_parquet_stream(
         Ok(batch)
     });
 
-    Ok(stream.boxed())
+    Ok((stream.boxed(), pruned))
 }
 
 /// Determine needed indices given a file schema and projected schema.
@@ -150,6 +185,7 @@ mod tests {
 
     use super::*;
     use crate::data_manager::DataHandle;
+    use crate::read::scan_filter::{PrunedRowGroups, ScanFilter};
     use crate::read::testing::write_parquet_file;
     use crate::s3::S3Helper;
 
@@ -183,9 +219,10 @@ mod tests {
 
     async fn check_complete(data_handle: &DataHandle, expected: &RecordBatch) {
         let table_schema = TableSchema::from_sparrow_schema(expected.schema()).unwrap();
-        let mut reader = new_parquet_stream(data_handle, &table_schema)
-            .await
-            .unwrap();
+        let (mut reader, _) =
+            new_parquet_stream(data_handle, &table_schema, &ScanFilter::default())
+                .await
+                .unwrap();
 
         assert_eq!(reader.try_next().await.unwrap().unwrap().data(), expected);
         assert!(reader.try_next().await.unwrap().is_none());
@@ -193,9 +230,10 @@ mod tests {
 
     async fn check_projected(data_handle: &DataHandle, expected: &RecordBatch) {
         let table_schema = TableSchema::from_sparrow_schema(expected.schema()).unwrap();
-        let mut reader = new_parquet_stream(data_handle, &table_schema)
-            .await
-            .unwrap();
+        let (mut reader, _) =
+            new_parquet_stream(data_handle, &table_schema, &ScanFilter::default())
+                .await
+                .unwrap();
 
         assert_eq!(reader.try_next().await.unwrap().unwrap().data(), expected);
         assert!(reader.try_next().await.unwrap().is_none());
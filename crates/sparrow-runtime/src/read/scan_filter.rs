@@ -0,0 +1,423 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use chrono::NaiveDateTime;
+use hashbrown::HashSet;
+use parquet::arrow::arrow_reader::{RowSelection, RowSelector};
+use parquet::file::metadata::{ParquetMetaData, RowGroupMetaData};
+use parquet::file::page_index::index::Index;
+use parquet::file::properties::ReaderProperties;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::file::serialized_reader::ReadOptionsBuilder;
+use parquet::file::statistics::Statistics;
+use sparrow_api::kaskada::v1alpha::slice_plan::Slice;
+use sparrow_compiler::TableInfo;
+
+use crate::prepare::slice_preparer::{entity_key_hashes, percent_upper_bound};
+
+/// Index of the `_time` column within prepared files.
+const TIME_COLUMN: usize = 0;
+/// Index of the `_key_hash` column within prepared files.
+const KEY_HASH_COLUMN: usize = 2;
+
+/// Filters on the key columns of the prepared files being scanned.
+///
+/// These are derived from the query (the snapshot being resumed, the earliest
+/// input time it needs, the final result time and the requested slice) and
+/// used to skip row groups and pages which can't contain rows needed by the
+/// query. Skipping is conservative -- rows outside the filter may still be
+/// read, and must be handled as before.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ScanFilter {
+    /// Rows at or before this time are not needed, because they have
+    /// already been processed or are older than the query's look-back.
+    min_time_exclusive: Option<i64>,
+    /// Rows after this time are not needed.
+    max_time_inclusive: Option<i64>,
+    /// The key hashes which are needed.
+    key_hashes: KeyHashFilter,
+}
+
+#[derive(Clone, Debug, Default)]
+enum KeyHashFilter {
+    /// All key hashes are needed.
+    #[default]
+    All,
+    /// Key hashes less than or equal to the given hash are needed.
+    AtMost(u64),
+    /// Only the given key hashes are needed.
+    OneOf(Arc<HashSet<u64>>),
+}
+
+/// The result of pruning the row groups of a single file.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct PrunedRowGroups {
+    /// The row groups which need to be read.
+    pub row_groups: Vec<usize>,
+    /// The total number of row groups in the file.
+    pub num_row_groups: usize,
+}
+
+impl PrunedRowGroups {
+    pub fn num_skipped(&self) -> usize {
+        self.num_row_groups - self.row_groups.len()
+    }
+}
+
+impl ScanFilter {
+    pub(crate) fn try_new(
+        table_info: &TableInfo,
+        requested_slice: &Option<Slice>,
+        max_event_in_snapshot: Option<NaiveDateTime>,
+        lower_bound_opt: Option<NaiveDateTime>,
+        upper_bound_opt: Option<NaiveDateTime>,
+    ) -> anyhow::Result<Self> {
+        let key_hashes = match requested_slice {
+            None => KeyHashFilter::All,
+            Some(Slice::Percent(percent)) => {
+                KeyHashFilter::AtMost(percent_upper_bound(percent.percent))
+            }
+            // Sampled entities are spread across all key hashes. Stratified
+            // and dimension slices are applied after the table is read.
+            Some(Slice::Sample(_) | Slice::Stratified(_) | Slice::Dimension(_)) => {
+                KeyHashFilter::All
+            }
+            // Slicing by entity keys isn't supported for composite keys.
+            Some(Slice::EntityKeys(_))
+                if !table_info.config().additional_group_column_names.is_empty() =>
+            {
+                KeyHashFilter::All
+            }
+            Some(Slice::EntityKeys(entity_keys)) => {
+                let key_column = &table_info.config().group_column_name;
+                let key_field = table_info
+                    .schema()
+                    .field_with_name(key_column)
+                    .with_context(|| format!("missing entity key column '{key_column}'"))?;
+                let key_hashes =
+                    entity_key_hashes(&entity_keys.entity_keys, key_field.data_type())?;
+                KeyHashFilter::OneOf(Arc::new(key_hashes))
+            }
+        };
+
+        // Rows before the (inclusive) lower bound are not needed, which is the
+        // same as rows at or before the nanosecond preceding it.
+        let min_time_exclusive = max_event_in_snapshot
+            .map(|time| time.timestamp_nanos())
+            .into_iter()
+            .chain(lower_bound_opt.map(|time| time.timestamp_nanos() - 1))
+            .max();
+
+        Ok(Self {
+            min_time_exclusive,
+            max_time_inclusive: upper_bound_opt.map(|time| time.timestamp_nanos()),
+            key_hashes,
+        })
+    }
+
+    /// Return true if no rows may be skipped by this filter.
+    fn is_unfiltered(&self) -> bool {
+        self.min_time_exclusive.is_none()
+            && self.max_time_inclusive.is_none()
+            && matches!(self.key_hashes, KeyHashFilter::All)
+    }
+
+    fn may_contain_times(&self, min_time: i64, max_time: i64) -> bool {
+        self.min_time_exclusive.map_or(true, |t| max_time > t)
+            && self.max_time_inclusive.map_or(true, |t| min_time <= t)
+    }
+
+    fn may_contain_key_hashes(&self, min_hash: u64, max_hash: u64) -> bool {
+        match &self.key_hashes {
+            KeyHashFilter::All => true,
+            KeyHashFilter::AtMost(upper_bound) => min_hash <= *upper_bound,
+            KeyHashFilter::OneOf(key_hashes) => key_hashes
+                .iter()
+                .any(|key_hash| (min_hash..=max_hash).contains(key_hash)),
+        }
+    }
+
+    /// Return the row groups which may contain needed rows, based on the
+    /// statistics of the `_time` and `_key_hash` columns.
+    pub(crate) fn prune_row_groups(&self, metadata: &ParquetMetaData) -> PrunedRowGroups {
+        let num_row_groups = metadata.num_row_groups();
+        let row_groups = (0..num_row_groups)
+            .filter(|index| self.may_contain_row_group(metadata.row_group(*index)))
+            .collect();
+        PrunedRowGroups {
+            row_groups,
+            num_row_groups,
+        }
+    }
+
+    fn may_contain_row_group(&self, row_group: &RowGroupMetaData) -> bool {
+        if self.is_unfiltered() {
+            return true;
+        }
+
+        let times_match = match int64_min_max(row_group, TIME_COLUMN) {
+            Some((min_time, max_time)) => self.may_contain_times(min_time, max_time),
+            None => true,
+        };
+
+        // The `_key_hash` is a `u64` stored as a parquet `INT64`.
+        let key_hashes_match = match int64_min_max(row_group, KEY_HASH_COLUMN) {
+            Some((min_hash, max_hash)) if (min_hash as u64) <= (max_hash as u64) => {
+                self.may_contain_key_hashes(min_hash as u64, max_hash as u64)
+            }
+            _ => true,
+        };
+
+        times_match && key_hashes_match
+    }
+
+    /// Return the subset of `row_groups` whose `_key_hash` bloom filter may
+    /// contain one of the needed key hashes.
+    ///
+    /// Row groups without a bloom filter are retained. This reads the file
+    /// synchronously, so should be run on a blocking thread.
+    pub(crate) fn check_bloom_filters(
+        &self,
+        path: &Path,
+        row_groups: Vec<usize>,
+    ) -> anyhow::Result<Vec<usize>> {
+        let KeyHashFilter::OneOf(key_hashes) = &self.key_hashes else {
+            return Ok(row_groups);
+        };
+        if row_groups.is_empty() {
+            return Ok(row_groups);
+        }
+
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("opening {path:?} to read bloom filters"))?;
+        let options = ReadOptionsBuilder::new()
+            .with_reader_properties(
+                ReaderProperties::builder()
+                    .set_read_bloom_filter(true)
+                    .build(),
+            )
+            .build();
+        let reader = SerializedFileReader::new_with_options(file, options)?;
+
+        let mut selected = Vec::with_capacity(row_groups.len());
+        for index in row_groups {
+            let row_group = reader.get_row_group(index)?;
+            let may_contain = match row_group.get_column_bloom_filter(KEY_HASH_COLUMN) {
+                Some(bloom_filter) => key_hashes
+                    .iter()
+                    .any(|key_hash| bloom_filter.check(&(*key_hash as i64))),
+                None => true,
+            };
+            if may_contain {
+                selected.push(index);
+            }
+        }
+        Ok(selected)
+    }
+
+    /// Return the rows to read from the given row groups, based on the page
+    /// index of the `_time` column.
+    ///
+    /// Returns `None` if all rows should be read, either because no pages
+    /// could be skipped or the file has no page index.
+    pub(crate) fn select_pages(
+        &self,
+        metadata: &ParquetMetaData,
+        row_groups: &[usize],
+    ) -> Option<RowSelection> {
+        if self.min_time_exclusive.is_none() && self.max_time_inclusive.is_none() {
+            return None;
+        }
+
+        let column_indices = metadata.page_indexes()?;
+        let offset_indices = metadata.offset_indexes()?;
+
+        let mut selectors = Vec::new();
+        let mut skipped_any = false;
+        for row_group in row_groups {
+            let num_rows = metadata.row_group(*row_group).num_rows() as usize;
+            let pages = match (
+                column_indices
+                    .get(*row_group)
+                    .and_then(|c| c.get(TIME_COLUMN)),
+                offset_indices
+                    .get(*row_group)
+                    .and_then(|o| o.get(TIME_COLUMN)),
+            ) {
+                (Some(Index::INT64(index)), Some(locations))
+                    if index.indexes.len() == locations.len() =>
+                {
+                    index.indexes.iter().zip(locations.iter().enumerate())
+                }
+                _ => {
+                    selectors.push(RowSelector::select(num_rows));
+                    continue;
+                }
+            };
+
+            for (page, (page_index, location)) in pages {
+                let first_row = location.first_row_index as usize;
+                let end_row = offset_indices[*row_group][TIME_COLUMN]
+                    .get(page_index + 1)
+                    .map_or(num_rows, |next| next.first_row_index as usize);
+                let page_rows = end_row - first_row;
+
+                let may_contain = match (page.min, page.max) {
+                    (Some(min_time), Some(max_time)) => self.may_contain_times(min_time, max_time),
+                    _ => true,
+                };
+                if may_contain {
+                    selectors.push(RowSelector::select(page_rows));
+                } else {
+                    skipped_any = true;
+                    selectors.push(RowSelector::skip(page_rows));
+                }
+            }
+        }
+
+        skipped_any.then(|| RowSelection::from(selectors))
+    }
+}
+
+/// Return the minimum and maximum of an `INT64` column in the row group, if
+/// the statistics are available.
+fn int64_min_max(row_group: &RowGroupMetaData, column: usize) -> Option<(i64, i64)> {
+    if column >= row_group.num_columns() {
+        return None;
+    }
+    match row_group.column(column).statistics() {
+        Some(Statistics::Int64(stats)) if stats.has_min_max_set() => {
+            Some((*stats.min(), *stats.max()))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{Int64Array, TimestampNanosecondArray, UInt64Array};
+    use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+    use arrow::record_batch::RecordBatch;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use parquet::arrow::ArrowWriter;
+    use parquet::file::properties::WriterProperties;
+    use parquet::schema::types::ColumnPath;
+
+    use super::*;
+
+    fn write_test_file(times: Vec<i64>, key_hashes: Vec<u64>) -> tempfile::NamedTempFile {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                "_time",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+            Field::new("_subsort", DataType::UInt64, false),
+            Field::new("_key_hash", DataType::UInt64, false),
+            Field::new("n", DataType::Int64, true),
+        ]));
+        let num_rows = times.len();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(TimestampNanosecondArray::from(times)),
+                Arc::new(UInt64Array::from_iter_values(0..num_rows as u64)),
+                Arc::new(UInt64Array::from(key_hashes)),
+                Arc::new(Int64Array::from_iter_values(0..num_rows as i64)),
+            ],
+        )
+        .unwrap();
+
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(2)
+            .set_column_bloom_filter_enabled(ColumnPath::from("_key_hash"), true)
+            .build();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = ArrowWriter::try_new(file.reopen().unwrap(), schema, Some(props)).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        file
+    }
+
+    fn metadata(file: &tempfile::NamedTempFile) -> Arc<ParquetMetaData> {
+        ParquetRecordBatchReaderBuilder::try_new(file.reopen().unwrap())
+            .unwrap()
+            .metadata()
+            .clone()
+    }
+
+    #[test]
+    fn test_prune_row_groups_by_time() {
+        let file = write_test_file(vec![1, 2, 3, 4, 5, 6], vec![0, 1, 0, 1, 0, 1]);
+        let filter = ScanFilter {
+            min_time_exclusive: Some(2),
+            max_time_inclusive: Some(4),
+            ..ScanFilter::default()
+        };
+
+        let pruned = filter.prune_row_groups(&metadata(&file));
+        assert_eq!(
+            pruned,
+            PrunedRowGroups {
+                row_groups: vec![1],
+                num_row_groups: 3
+            }
+        );
+        assert_eq!(pruned.num_skipped(), 2);
+    }
+
+    #[test]
+    fn test_prune_row_groups_by_key_hash() {
+        let file = write_test_file(
+            vec![1, 2, 3, 4, 5, 6],
+            vec![1, 2, 5, 6, u64::MAX - 1, u64::MAX],
+        );
+
+        let filter = ScanFilter {
+            key_hashes: KeyHashFilter::AtMost(4),
+            ..ScanFilter::default()
+        };
+        assert_eq!(
+            filter.prune_row_groups(&metadata(&file)).row_groups,
+            vec![0]
+        );
+
+        let filter = ScanFilter {
+            key_hashes: KeyHashFilter::OneOf(Arc::new([6, u64::MAX].into_iter().collect())),
+            ..ScanFilter::default()
+        };
+        assert_eq!(
+            filter.prune_row_groups(&metadata(&file)).row_groups,
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    fn test_check_bloom_filters() {
+        let file = write_test_file(vec![1, 2, 3, 4], vec![1, 3, 2, 4]);
+        let filter = ScanFilter {
+            key_hashes: KeyHashFilter::OneOf(Arc::new([2].into_iter().collect())),
+            ..ScanFilter::default()
+        };
+
+        // Both row groups contain 2 within their min and max, but only the
+        // second contains it.
+        let row_groups = filter.prune_row_groups(&metadata(&file)).row_groups;
+        assert_eq!(row_groups, vec![0, 1]);
+        let row_groups = filter.check_bloom_filters(file.path(), row_groups).unwrap();
+        assert_eq!(row_groups, vec![1]);
+    }
+
+    #[test]
+    fn test_unfiltered_reads_everything() {
+        let file = write_test_file(vec![1, 2, 3], vec![0, 1, 2]);
+        let filter = ScanFilter::default();
+        let metadata = metadata(&file);
+        let pruned = filter.prune_row_groups(&metadata);
+        assert_eq!(pruned.num_skipped(), 0);
+        assert!(filter.select_pages(&metadata, &pruned.row_groups).is_none());
+    }
+}
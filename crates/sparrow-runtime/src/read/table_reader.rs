@@ -23,11 +23,13 @@ use crate::merge::{homogeneous_merge, GatheredBatches, Gatherer};
 use crate::min_heap::{HasPriority, MinHeap};
 use crate::read::error::Error;
 use crate::read::parquet_stream::{self, new_parquet_stream};
+use crate::read::scan_filter::ScanFilter;
 use crate::Batch;
 
 const READ_TABLE: Activity = activity!("scan.read_file");
 const GATHER_TABLE_BATCHES: Activity = activity!("scan.gather");
 const MERGE_TABLE_BATCHES: Activity = activity!("scan.merge");
+const PRUNE_ROW_GROUPS: Activity = activity!("scan.prune_row_groups");
 
 const MIN_BATCH_TIME: Gauge<i64> = gauge!("min_time_in_batch");
 const MAX_BATCH_TIME: Gauge<i64> = gauge!("max_time_in_batch");
@@ -35,12 +37,15 @@ const NUM_INPUT_ROWS: Gauge<usize> = gauge!("num_input_rows");
 const NUM_OUTPUT_ROWS: Gauge<usize> = gauge!("num_output_rows");
 const ACTIVE_SOURCES: Gauge<usize> = gauge!("active_files");
 const REMAINING_FILES: Gauge<usize> = gauge!("remaining_files");
+const NUM_ROW_GROUPS: Gauge<usize> = gauge!("num_row_groups");
+const NUM_SKIPPED_ROW_GROUPS: Gauge<usize> = gauge!("num_skipped_row_groups");
 
 static REGISTRATION: Registration = Registration::new(|| {
     let mut r = Registrations::default();
     r.add(READ_TABLE);
     r.add(GATHER_TABLE_BATCHES);
     r.add(MERGE_TABLE_BATCHES);
+    r.add(PRUNE_ROW_GROUPS);
 
     r.add(MIN_BATCH_TIME);
     r.add(MAX_BATCH_TIME);
@@ -48,6 +53,8 @@ static REGISTRATION: Registration = Registration::new(|| {
     r.add(NUM_OUTPUT_ROWS);
     r.add(ACTIVE_SOURCES);
     r.add(REMAINING_FILES);
+    r.add(NUM_ROW_GROUPS);
+    r.add(NUM_SKIPPED_ROW_GROUPS);
     r
 });
 
@@ -61,6 +68,7 @@ pub fn table_reader(
     projected_columns: Option<Vec<String>>,
     flight_recorder: FlightRecorder,
     max_event_in_snapshot: Option<NaiveDateTime>,
+    lower_bound_opt: Option<NaiveDateTime>,
     upper_bound_opt: Option<NaiveDateTime>,
 ) -> error_stack::Result<impl Stream<Item = error_stack::Result<Batch, Error>> + 'static, Error> {
     let data_handles = select_prepared_files(
@@ -68,8 +76,20 @@ pub fn table_reader(
         table_info,
         requested_slice,
         max_event_in_snapshot,
+        lower_bound_opt,
     )?;
 
+    // Used to skip row groups and pages which aren't needed by the query.
+    let scan_filter = ScanFilter::try_new(
+        table_info,
+        requested_slice,
+        max_event_in_snapshot,
+        lower_bound_opt,
+        upper_bound_opt,
+    )
+    .into_report()
+    .change_context(Error::DetermineScanFilter)?;
+
     let mut gatherer = Gatherer::new(data_handles.len(), None);
     let mut active = Vec::with_capacity(data_handles.len());
 
@@ -128,7 +148,7 @@ pub fn table_reader(
             let next_batch = READ_TABLE.instrument::<error_stack::Result<_, Error>, _>(&flight_recorder, |metrics| {
                 // Weird syntax because we can't easily say "move metrics but not projected schema".
                 // This may get easier with async closures https://github.com/rust-lang/rust/issues/62290.
-                let input = next_input.next_batch(&projected_schema, upper_bound_opt);
+                let input = next_input.next_batch(&projected_schema, &scan_filter, &flight_recorder, lower_bound_opt, upper_bound_opt);
                 async move {
                     let input = input.await?;
 
@@ -195,6 +215,7 @@ fn select_prepared_files(
     table_info: &TableInfo,
     requested_slice: &Option<Slice>,
     max_event_in_snapshot: Option<NaiveDateTime>,
+    lower_bound_opt: Option<NaiveDateTime>,
 ) -> error_stack::Result<Vec<Arc<DataHandle>>, Error> {
     let prepared_files = table_info
         .prepared_files_for_slice(requested_slice)
@@ -230,6 +251,16 @@ fn select_prepared_files(
 
             // All data has already been persisted, nothing more to process.
             continue;
+        } else if matches!(lower_bound_opt, Some(t) if max_event_time < t) {
+            info!(
+                "Skipping '{:?}' for table '{}' -- fully before the earliest needed input {:?}",
+                prepared_file,
+                table_info.name(),
+                lower_bound_opt
+            );
+
+            // The rows are older than the look-back of the results needed.
+            continue;
         }
 
         // Don't queue the download until we know we want the file.
@@ -349,19 +380,37 @@ impl ActiveInput {
     async fn next_batch(
         &mut self,
         projected_schema: &TableSchema,
+        scan_filter: &ScanFilter,
+        flight_recorder: &FlightRecorder,
+        lower_bound_opt: Option<NaiveDateTime>,
         upper_bound_opt: Option<NaiveDateTime>,
     ) -> error_stack::Result<Option<Batch>, Error> {
         if self.stream.is_none() {
-            let stream = new_parquet_stream(self.data_handle.as_ref(), projected_schema)
-                .await
-                .change_context(Error::CreateStream)?;
+            let (stream, pruned) =
+                new_parquet_stream(self.data_handle.as_ref(), projected_schema, scan_filter)
+                    .await
+                    .change_context(Error::CreateStream)?;
+            if pruned.num_skipped() > 0 {
+                info!(
+                    "Skipping {} of {} row groups for data file {:?}",
+                    pruned.num_skipped(),
+                    pruned.num_row_groups,
+                    self.data_handle
+                );
+            }
+            {
+                let mut activation = PRUNE_ROW_GROUPS.start(flight_recorder);
+                activation.report_metric(NUM_ROW_GROUPS, pruned.num_row_groups);
+                activation.report_metric(NUM_SKIPPED_ROW_GROUPS, pruned.num_skipped());
+            }
+
             self.stream = Some(stream);
         }
 
         // SAFETY: If `reader.is_none()` we created it above.
         let stream = unsafe { self.stream.as_mut().unwrap_unchecked() };
 
-        if let Some(next) = stream
+        while let Some(next) = stream
             .try_next()
             .await
             .change_context(Error::ReadNextBatch)?
@@ -384,12 +433,34 @@ impl ActiveInput {
 
             self.min_next_time = next.upper_bound.time;
 
+            // Filter out all events before a timestamp, if provided.
+            //
+            // Results are only needed after a given start time, and rows older
+            // than the start minus the look-back of the query can't affect them.
+            // Batches entirely before the lower bound are skipped.
+            let next = match lower_bound_opt {
+                Some(lower_bound) if next.lower_bound.time < lower_bound.timestamp_nanos() => {
+                    let times = next.times().into_report().change_context(Error::Internal)?;
+                    let ts_nanos = lower_bound.timestamp_nanos();
+                    let offset = times.partition_point(|time| *time < ts_nanos);
+                    if offset == times.len() {
+                        continue;
+                    }
+
+                    let slice = next.data().slice(offset, times.len() - offset);
+                    Batch::try_new_from_batch(slice)
+                        .into_report()
+                        .change_context(Error::Internal)?
+                }
+                _ => next,
+            };
+
             // Filter out all events after a timestamp, if provided.
             //
             // This allows users to supply a specific timestamp to produce outputs at.
             // While the query should filter out rows past this timestamp, sparrow can
             // pre-emptively stop reading at this time to avoid doing unnecessary work.
-            match upper_bound_opt {
+            return match upper_bound_opt {
                 Some(upper_bound) => {
                     let times = next.times().into_report().change_context(Error::Internal)?;
                     let ts_nanos = upper_bound.timestamp_nanos();
@@ -428,10 +499,10 @@ impl ActiveInput {
                     self.min_next_time = next.upper_bound.time;
                     Ok(Some(next))
                 }
-            }
-        } else {
-            Ok(None)
+            };
         }
+
+        Ok(None)
     }
 }
 
@@ -703,6 +774,7 @@ mod tests {
             table_info,
             &None,
             Some(max_event_in_snapshot),
+            None,
         )
         .unwrap();
         assert_eq!(data_handles.len(), 2);
@@ -711,6 +783,29 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_lower_bound_skips_files_and_rows() {
+        // File 1 is entirely before the lower bound.
+        let (_file1, prepared1) = mk_file(&[(0, 2, 0, "c", "d"), (1, 0, 1, "e", "f")]);
+
+        // File 2 contains times from [2, 5], some of which are before it.
+        let (_file2, prepared2) = mk_file(&[
+            (2, 1, 1, "a", "b"),
+            (3, 0, 0, "x", "y"),
+            (5, 0, 0, "g", "h"),
+        ]);
+
+        check_read_table_with_lower_bound(
+            vec![prepared1, prepared2],
+            mk_batch(&[(3, 0, 0, "x", "y"), (5, 0, 0, "g", "h")]),
+            None,
+            Some(NaiveDateTime::from_timestamp_opt(0, 3).unwrap()),
+            None,
+        )
+        .await
+        .unwrap();
+    }
+
     #[test]
     #[ignore = "Multiple files with different schemas unsupported - see https://github.com/apache/arrow-rs/issues/782"]
     fn test_multi_parquet_file_diff_field_order() {
@@ -740,6 +835,23 @@ mod tests {
         expected: RecordBatch,
         max_time_processed: Option<NaiveDateTime>,
         max_event_time: Option<Timestamp>,
+    ) -> error_stack::Result<(), Error> {
+        check_read_table_with_lower_bound(
+            prepared_files,
+            expected,
+            max_time_processed,
+            None,
+            max_event_time,
+        )
+        .await
+    }
+
+    async fn check_read_table_with_lower_bound(
+        prepared_files: Vec<PreparedFile>,
+        expected: RecordBatch,
+        max_time_processed: Option<NaiveDateTime>,
+        lower_bound_opt: Option<NaiveDateTime>,
+        max_event_time: Option<Timestamp>,
     ) -> error_stack::Result<(), Error> {
         sparrow_testing::init_test_logging();
 
@@ -775,6 +887,7 @@ mod tests {
             None,
             FlightRecorder::disabled(),
             max_time_processed,
+            lower_bound_opt,
             upper_bound_opt,
         )?
         .try_collect()
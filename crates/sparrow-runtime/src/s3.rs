@@ -217,6 +217,19 @@ impl S3Helper {
         Ok(())
     }
 
+    pub async fn delete_s3(&self, s3_object: S3Object) -> anyhow::Result<()> {
+        let s3_object_clone = s3_object.clone();
+        self.client
+            .delete_object()
+            .set_key(Some(s3_object.key))
+            .set_bucket(Some(s3_object.bucket))
+            .send()
+            .await
+            .with_context(|| format!("unable to delete object from S3 at {s3_object_clone:?}"))?;
+
+        Ok(())
+    }
+
     /// Lists s3 objects with the given prefix in s3.
     ///
     /// Appends a trailing '/' to the `prefix` if it does not exist.
@@ -225,6 +238,21 @@ impl S3Helper {
         bucket: &str,
         prefix: &str,
     ) -> anyhow::Result<Vec<S3Object>> {
+        let objects = self
+            .list_prefix_delimited_with_modified(bucket, prefix)
+            .await?;
+        Ok(objects.into_iter().map(|(object, _)| object).collect())
+    }
+
+    /// Lists s3 objects with the given prefix in s3, along with the time each
+    /// was last modified (in seconds since the epoch).
+    ///
+    /// Appends a trailing '/' to the `prefix` if it does not exist.
+    pub async fn list_prefix_delimited_with_modified(
+        &self,
+        bucket: &str,
+        prefix: &str,
+    ) -> anyhow::Result<Vec<(S3Object, Option<i64>)>> {
         let prefix = if !prefix.is_empty() {
             let c = &prefix[prefix.len() - 1..prefix.len()];
             if c != "/" {
@@ -256,10 +284,11 @@ impl S3Helper {
             .unwrap_or_default()
             .iter()
             .map(|i| -> anyhow::Result<_> {
-                Ok(S3Object {
+                let object = S3Object {
                     bucket: bucket.to_owned(),
                     key: i.key().context("Could not parse s3 key")?.to_owned(),
-                })
+                };
+                Ok((object, i.last_modified().map(|t| t.secs())))
             })
             .try_collect()?;
 
@@ -0,0 +1,693 @@
+//! Tools for listing, inspecting and pruning compute snapshots.
+//!
+//! Each query execution with a `ComputeSnapshotConfig` writes a new snapshot
+//! (a RocksDB directory) named by a UUID under the configured
+//! `output_prefix`. The functions in this module allow enumerating those
+//! snapshots, describing and verifying their contents and deleting those
+//! which are no longer needed.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use error_stack::{IntoReport, IntoReportCompat, ResultExt};
+use hashbrown::HashMap;
+use itertools::Itertools;
+use prost_wkt_types::Timestamp;
+use serde::de::DeserializeOwned;
+use sparrow_api::kaskada::v1alpha::{ComputeSnapshotConfig, PlanHash};
+use sparrow_instructions::{ComputeStore, StoreKeyKind};
+use tempfile::TempDir;
+
+use crate::execute::key_hash_inverse::KeyHashInverse;
+use crate::s3::{S3Helper, S3Object};
+
+#[derive(derive_more::Display, Debug)]
+pub enum Error {
+    #[display(fmt = "i/o error accessing snapshots")]
+    Io,
+    #[display(fmt = "failed to list snapshots in '{_0}'")]
+    ListSnapshots(String),
+    #[display(fmt = "failed to download snapshot '{_0}'")]
+    DownloadSnapshot(String),
+    #[display(fmt = "failed to delete snapshot '{_0}'")]
+    DeleteSnapshot(String),
+    #[display(fmt = "failed to open snapshot at '{_0:?}'")]
+    OpenSnapshot(PathBuf),
+    #[display(fmt = "failed to read snapshot contents")]
+    ReadSnapshot,
+    #[display(fmt = "no entity with key '{_0}' in snapshot")]
+    MissingEntity(String),
+}
+
+impl error_stack::Context for Error {}
+
+/// Summary information about a single snapshot.
+#[derive(Debug)]
+pub struct SnapshotInfo {
+    /// The version of the compute store format.
+    pub snapshot_version: i32,
+    /// The hash of the plan the snapshot was written for.
+    pub plan_hash: Option<PlanHash>,
+    /// The maximum event time included in the snapshot.
+    pub max_event_time: Option<Timestamp>,
+    /// The number of entities in the key hash inverse.
+    pub num_entities: Option<usize>,
+    /// The number of keys stored in the snapshot.
+    pub num_keys: usize,
+    /// The total size (in bytes) of the values stored in the snapshot.
+    pub value_bytes: usize,
+}
+
+/// A snapshot written to an output prefix.
+#[derive(Debug, Clone)]
+pub struct ListedSnapshot {
+    /// The path (or S3 URI) of the snapshot.
+    pub path: String,
+    /// When the snapshot was last modified, if known.
+    pub last_modified: Option<SystemTime>,
+}
+
+impl ListedSnapshot {
+    /// Returns true if the snapshot was last modified more than `age` before
+    /// `now`.
+    ///
+    /// Snapshots with an unknown modification time are never considered old.
+    pub fn is_older_than(&self, now: SystemTime, age: Duration) -> bool {
+        self.last_modified
+            .and_then(|last_modified| now.duration_since(last_modified).ok())
+            .map_or(false, |elapsed| elapsed > age)
+    }
+}
+
+/// A snapshot that is available on the local file system.
+///
+/// Snapshots stored in S3 are downloaded to a temporary directory which is
+/// deleted when this is dropped.
+#[derive(Debug)]
+pub struct LocalSnapshot {
+    path: PathBuf,
+    _temp_dir: Option<TempDir>,
+}
+
+impl LocalSnapshot {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// The state of a single entity within a snapshot.
+#[derive(Debug)]
+pub struct EntityState {
+    pub key_hash: u64,
+    pub operations: Vec<OperationEntityState>,
+}
+
+/// The state of an entity within a specific operation.
+#[derive(Debug)]
+pub struct OperationEntityState {
+    pub operation_index: u8,
+    /// The index of the entity in the operation's accumulators.
+    pub entity_index: u32,
+    pub accumulators: Vec<AccumulatorEntry>,
+}
+
+/// The value of an accumulator for a specific entity.
+#[derive(Debug)]
+pub struct AccumulatorEntry {
+    pub inst_index: u32,
+    pub value: AccumulatorValue,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AccumulatorValue {
+    /// The value for the entity, decoded as the requested type.
+    Value(String),
+    /// The accumulator does not have an entry for the entity.
+    Missing,
+    /// The accumulator could not be decoded as the requested type.
+    Invalid(String),
+    /// No type was requested, so the value of the specific entity can't be
+    /// extracted.
+    Undecoded { total_bytes: usize },
+}
+
+/// The type of the per-entity values in an accumulator.
+///
+/// Accumulators are `bincode` serialized vectors with one element per entity.
+/// The element type depends on the instruction and isn't recorded in the
+/// snapshot, so it must be provided to decode the value of an entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum AccumulatorType {
+    /// Counts, stored as `u32`.
+    Count,
+    /// Optional `i64` values (also used for timestamps).
+    Int64,
+    /// Optional `u64` values.
+    Uint64,
+    /// Optional `i32` values.
+    Int32,
+    /// Optional `u32` values.
+    Uint32,
+    /// Optional `f32` values.
+    Float32,
+    /// Optional `f64` values.
+    Float64,
+    /// Optional strings.
+    String,
+}
+
+impl AccumulatorType {
+    /// Decodes the value at `index` of a `bincode` serialized accumulator.
+    fn decode(&self, bytes: &[u8], index: u32) -> AccumulatorValue {
+        fn decode_as<T: DeserializeOwned + std::fmt::Debug>(
+            bytes: &[u8],
+            index: u32,
+        ) -> AccumulatorValue {
+            match bincode::deserialize::<Vec<T>>(bytes) {
+                Ok(values) => values
+                    .get(index as usize)
+                    .map_or(AccumulatorValue::Missing, |value| {
+                        AccumulatorValue::Value(format!("{value:?}"))
+                    }),
+                Err(e) => AccumulatorValue::Invalid(e.to_string()),
+            }
+        }
+
+        match self {
+            Self::Count => decode_as::<u32>(bytes, index),
+            Self::Int64 => decode_as::<Option<i64>>(bytes, index),
+            Self::Uint64 => decode_as::<Option<u64>>(bytes, index),
+            Self::Int32 => decode_as::<Option<i32>>(bytes, index),
+            Self::Uint32 => decode_as::<Option<u32>>(bytes, index),
+            Self::Float32 => decode_as::<Option<f32>>(bytes, index),
+            Self::Float64 => decode_as::<Option<f64>>(bytes, index),
+            Self::String => decode_as::<Option<String>>(bytes, index),
+        }
+    }
+}
+
+/// Lists the snapshots written to the given `output_prefix`.
+///
+/// Returns the path (or S3 URI) of each snapshot, sorted by name. This only
+/// lists the snapshots -- nothing is downloaded.
+pub async fn list_snapshots(
+    s3_helper: &S3Helper,
+    output_prefix: &str,
+) -> error_stack::Result<Vec<ListedSnapshot>, Error> {
+    if crate::s3::is_s3_path(output_prefix) {
+        let prefix = S3Object::try_from_uri(output_prefix)
+            .into_report()
+            .change_context_lazy(|| Error::ListSnapshots(output_prefix.to_owned()))?;
+        let objects = s3_helper
+            .list_prefix_delimited_with_modified(&prefix.bucket, &prefix.key)
+            .await
+            .into_report()
+            .change_context_lazy(|| Error::ListSnapshots(output_prefix.to_owned()))?;
+
+        // The snapshot was last modified when its most recent object was.
+        let mut snapshots: HashMap<String, Option<i64>> = HashMap::new();
+        for (object, last_modified) in objects.iter() {
+            let relative = object
+                .get_relative_key_path(&prefix.key)
+                .into_report()
+                .change_context_lazy(|| Error::ListSnapshots(output_prefix.to_owned()))?;
+            if let Some((snapshot, _)) = relative.split_once('/') {
+                let snapshot = prefix.join_delimited(snapshot).get_formatted_key();
+                let entry = snapshots.entry(snapshot).or_insert(*last_modified);
+                *entry = (*entry).max(*last_modified);
+            }
+        }
+        Ok(snapshots
+            .into_iter()
+            .sorted()
+            .map(|(path, last_modified)| ListedSnapshot {
+                path,
+                last_modified: last_modified.and_then(|seconds| {
+                    SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(seconds.max(0) as u64))
+                }),
+            })
+            .collect())
+    } else {
+        let entries = std::fs::read_dir(output_prefix)
+            .into_report()
+            .change_context_lazy(|| Error::ListSnapshots(output_prefix.to_owned()))?;
+
+        let mut snapshots = Vec::new();
+        for entry in entries {
+            let path = entry.into_report().change_context(Error::Io)?.path();
+            // Every RocksDB directory contains a `CURRENT` file.
+            let current = path.join("CURRENT");
+            if path.is_dir() && current.is_file() {
+                let last_modified = std::fs::metadata(&current)
+                    .and_then(|metadata| metadata.modified())
+                    .ok();
+                snapshots.push(ListedSnapshot {
+                    path: path.to_string_lossy().into_owned(),
+                    last_modified,
+                });
+            }
+        }
+        snapshots.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(snapshots)
+    }
+}
+
+/// Makes the given snapshot available locally, downloading it if needed.
+pub async fn fetch_snapshot(
+    s3_helper: &S3Helper,
+    snapshot: &str,
+) -> error_stack::Result<LocalSnapshot, Error> {
+    if !crate::s3::is_s3_path(snapshot) {
+        return Ok(LocalSnapshot {
+            path: PathBuf::from(snapshot),
+            _temp_dir: None,
+        });
+    }
+
+    let temp_dir = tempfile::Builder::new()
+        .prefix("snapshot_")
+        .tempdir()
+        .into_report()
+        .change_context(Error::Io)?;
+    let output_prefix = snapshot
+        .trim_end_matches('/')
+        .rsplit_once('/')
+        .map(|(prefix, _)| prefix)
+        .unwrap_or(snapshot);
+    let config = ComputeSnapshotConfig {
+        output_prefix: output_prefix.to_owned(),
+        resume_from: Some(snapshot.to_owned()),
+    };
+    crate::s3::download_snapshot(s3_helper, temp_dir.path(), &config)
+        .await
+        .into_report()
+        .change_context_lazy(|| Error::DownloadSnapshot(snapshot.to_owned()))?;
+
+    Ok(LocalSnapshot {
+        path: temp_dir.path().to_owned(),
+        _temp_dir: Some(temp_dir),
+    })
+}
+
+/// Deletes the given snapshot.
+pub async fn delete_snapshot(
+    s3_helper: &S3Helper,
+    snapshot: &str,
+) -> error_stack::Result<(), Error> {
+    if crate::s3::is_s3_path(snapshot) {
+        let prefix = S3Object::try_from_uri(snapshot)
+            .into_report()
+            .change_context_lazy(|| Error::DeleteSnapshot(snapshot.to_owned()))?;
+        let objects = s3_helper
+            .list_prefix_delimited(&prefix.bucket, &prefix.key)
+            .await
+            .into_report()
+            .change_context_lazy(|| Error::DeleteSnapshot(snapshot.to_owned()))?;
+        for object in objects {
+            s3_helper
+                .delete_s3(object)
+                .await
+                .into_report()
+                .change_context_lazy(|| Error::DeleteSnapshot(snapshot.to_owned()))?;
+        }
+    } else {
+        tokio::fs::remove_dir_all(snapshot)
+            .await
+            .into_report()
+            .change_context_lazy(|| Error::DeleteSnapshot(snapshot.to_owned()))?;
+    }
+    Ok(())
+}
+
+fn open_store(path: &Path) -> error_stack::Result<ComputeStore, Error> {
+    ComputeStore::try_open_read_only(path)
+        .into_report()
+        .change_context_lazy(|| Error::OpenSnapshot(path.to_owned()))
+}
+
+/// Describes the snapshot at the given local path.
+pub fn describe_snapshot(path: &Path) -> error_stack::Result<SnapshotInfo, Error> {
+    let store = open_store(path)?;
+
+    let snapshot_version = store
+        .stored_version()
+        .into_report()
+        .change_context(Error::ReadSnapshot)?;
+    let plan_hash = store
+        .get_plan_hash()
+        .into_report()
+        .change_context(Error::ReadSnapshot)?;
+    let max_event_time = store
+        .get_max_event_time()
+        .into_report()
+        .change_context(Error::ReadSnapshot)?;
+    let num_entities = KeyHashInverse::restore_from(&store)
+        .ok()
+        .map(|inverse| inverse.len());
+
+    let mut num_keys = 0;
+    let mut value_bytes = 0;
+    for entry in store.raw_entries() {
+        let (_, value) = entry.into_report().change_context(Error::ReadSnapshot)?;
+        num_keys += 1;
+        value_bytes += value.len();
+    }
+
+    Ok(SnapshotInfo {
+        snapshot_version,
+        plan_hash,
+        max_event_time,
+        num_entities,
+        num_keys,
+        value_bytes,
+    })
+}
+
+/// Verifies the snapshot at the given local path can be resumed from.
+///
+/// If `expected_plan_hash` is set, the snapshot must have been written for
+/// that plan. Returns a list of problems, which is empty if the snapshot is
+/// valid.
+pub fn verify_snapshot(
+    path: &Path,
+    expected_plan_hash: Option<&PlanHash>,
+) -> error_stack::Result<Vec<String>, Error> {
+    let store = open_store(path)?;
+    let mut problems = Vec::new();
+
+    match store.stored_version() {
+        Ok(version) if version == ComputeStore::current_version() => {}
+        Ok(version) => problems.push(format!(
+            "incompatible snapshot version {version}, expected {}",
+            ComputeStore::current_version()
+        )),
+        Err(e) => problems.push(format!("unable to read snapshot version: {e}")),
+    }
+
+    match (store.get_plan_hash(), expected_plan_hash) {
+        (Ok(Some(actual)), Some(expected)) if &actual != expected => problems.push(format!(
+            "snapshot plan hash {actual} does not match expected plan hash {expected}"
+        )),
+        (Ok(Some(_)), _) => {}
+        (Ok(None), _) => problems.push("missing plan hash".to_owned()),
+        (Err(e), _) => problems.push(format!("unable to read plan hash: {e}")),
+    }
+
+    match store.get_max_event_time() {
+        Ok(Some(_)) => {}
+        Ok(None) => problems.push("missing max event time".to_owned()),
+        Err(e) => problems.push(format!("unable to read max event time: {e}")),
+    }
+
+    if let Err(e) = KeyHashInverse::restore_from(&store) {
+        problems.push(format!("unable to read key hash inverse: {e}"));
+    }
+
+    // Make sure each accumulator has at most as many entries as there are
+    // entities in the corresponding operation.
+    let mut entities_per_operation = HashMap::new();
+    let mut accumulator_lengths = Vec::new();
+    for entry in store.raw_entries() {
+        let (key, value) = entry.into_report().change_context(Error::ReadSnapshot)?;
+        match StoreKeyKind::from_bytes(&key) {
+            StoreKeyKind::KeyHashToIndex { operation_index } => {
+                match bincode::deserialize::<HashMap<u64, u32>>(&value) {
+                    Ok(index) => {
+                        entities_per_operation.insert(operation_index, index.len());
+                    }
+                    Err(e) => problems.push(format!(
+                        "unable to read key hash index for operation {operation_index}: {e}"
+                    )),
+                }
+            }
+            StoreKeyKind::Accumulator {
+                operation_index,
+                inst_index,
+            } => {
+                if let Some(len) = vec_len(&value) {
+                    accumulator_lengths.push((operation_index, inst_index, len));
+                } else {
+                    problems.push(format!(
+                        "accumulator for operation {operation_index}, instruction {inst_index} is \
+                         truncated"
+                    ));
+                }
+            }
+            StoreKeyKind::Unknown => {
+                problems.push(format!("unrecognized key {}", hex_string(&key)));
+            }
+            _ => {}
+        }
+    }
+
+    for (operation_index, inst_index, len) in accumulator_lengths {
+        match entities_per_operation.get(&operation_index) {
+            Some(num_entities) if len as usize > *num_entities => problems.push(format!(
+                "accumulator for operation {operation_index}, instruction {inst_index} has {len} \
+                 entries but the operation only has {num_entities} entities"
+            )),
+            Some(_) => {}
+            None => problems.push(format!(
+                "accumulator for operation {operation_index}, instruction {inst_index} has no key \
+                 hash index"
+            )),
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Returns the state stored for the entity with the given key.
+///
+/// The `entity_key` is compared to the display form of each key in the
+/// snapshot's key hash inverse. If `accumulator_type` is set, each
+/// accumulator is decoded as that type.
+pub fn entity_state(
+    path: &Path,
+    entity_key: &str,
+    accumulator_type: Option<AccumulatorType>,
+) -> error_stack::Result<EntityState, Error> {
+    let store = open_store(path)?;
+
+    let key_hash = KeyHashInverse::restore_from(&store)
+        .and_then(|inverse| inverse.find_key_hash(entity_key))
+        .into_report()
+        .change_context(Error::ReadSnapshot)?
+        .ok_or_else(|| Error::MissingEntity(entity_key.to_owned()))?;
+
+    let mut entity_indices = HashMap::new();
+    let mut accumulators = Vec::new();
+    for entry in store.raw_entries() {
+        let (key, value) = entry.into_report().change_context(Error::ReadSnapshot)?;
+        match StoreKeyKind::from_bytes(&key) {
+            StoreKeyKind::KeyHashToIndex { operation_index } => {
+                let index: HashMap<u64, u32> = bincode::deserialize(&value)
+                    .into_report()
+                    .change_context(Error::ReadSnapshot)?;
+                if let Some(entity_index) = index.get(&key_hash) {
+                    entity_indices.insert(operation_index, *entity_index);
+                }
+            }
+            StoreKeyKind::Accumulator {
+                operation_index,
+                inst_index,
+            } => accumulators.push((operation_index, inst_index, value)),
+            _ => {}
+        }
+    }
+
+    let operations = entity_indices
+        .into_iter()
+        .sorted()
+        .map(|(operation_index, entity_index)| {
+            let accumulators = accumulators
+                .iter()
+                .filter(|(accum_operation, _, _)| *accum_operation == operation_index)
+                .map(|(_, inst_index, value)| AccumulatorEntry {
+                    inst_index: *inst_index,
+                    value: match accumulator_type {
+                        Some(accumulator_type) => accumulator_type.decode(value, entity_index),
+                        None => AccumulatorValue::Undecoded {
+                            total_bytes: value.len(),
+                        },
+                    },
+                })
+                .collect();
+            OperationEntityState {
+                operation_index,
+                entity_index,
+                accumulators,
+            }
+        })
+        .collect();
+
+    Ok(EntityState {
+        key_hash,
+        operations,
+    })
+}
+
+/// Returns the length of a `bincode` serialized vector.
+fn vec_len(bytes: &[u8]) -> Option<u64> {
+    let len: [u8; 8] = bytes.get(0..8)?.try_into().ok()?;
+    Some(u64::from_le_bytes(len))
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    data_encoding::HEXLOWER.encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{StringArray, UInt64Array};
+    use arrow::datatypes::DataType;
+    use sparrow_instructions::StoreKey;
+
+    use super::*;
+
+    fn write_snapshot(path: &Path) -> PlanHash {
+        let plan_hash = PlanHash {
+            hash: vec![1, 2, 3],
+        };
+        let max_event_time = Timestamp {
+            seconds: i64::MAX,
+            nanos: i32::MAX,
+        };
+        let store = ComputeStore::try_new(path, &max_event_time, &plan_hash).unwrap();
+        store
+            .put_max_event_time(&Timestamp {
+                seconds: 100,
+                nanos: 0,
+            })
+            .unwrap();
+
+        let mut inverse = KeyHashInverse::from_data_type(DataType::Utf8);
+        inverse
+            .add(
+                Arc::new(StringArray::from(vec!["a", "b"])),
+                &UInt64Array::from(vec![10, 20]),
+            )
+            .unwrap();
+        inverse.store_to(&store).unwrap();
+
+        let index: HashMap<u64, u32> = [(10, 0), (20, 1)].into_iter().collect();
+        store
+            .put(&StoreKey::new_key_hash_to_index(1), &index)
+            .unwrap();
+        store
+            .put(&StoreKey::new_accumulator(1, 4), &vec![None, Some(7i64)])
+            .unwrap();
+        plan_hash
+    }
+
+    #[test]
+    fn test_describe_and_verify_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan_hash = write_snapshot(dir.path());
+
+        let info = describe_snapshot(dir.path()).unwrap();
+        assert_eq!(info.snapshot_version, ComputeStore::current_version());
+        assert_eq!(info.plan_hash.as_ref(), Some(&plan_hash));
+        assert_eq!(info.max_event_time.map(|t| t.seconds), Some(100));
+        assert_eq!(info.num_entities, Some(2));
+
+        assert_eq!(
+            verify_snapshot(dir.path(), Some(&plan_hash)).unwrap(),
+            Vec::<String>::new()
+        );
+        let other_hash = PlanHash { hash: vec![4] };
+        assert_eq!(
+            verify_snapshot(dir.path(), Some(&other_hash)).unwrap(),
+            vec!["snapshot plan hash 123 does not match expected plan hash 4".to_owned()]
+        );
+    }
+
+    #[test]
+    fn test_entity_state() {
+        let dir = tempfile::tempdir().unwrap();
+        write_snapshot(dir.path());
+
+        let state = entity_state(dir.path(), "b", Some(AccumulatorType::Int64)).unwrap();
+        assert_eq!(state.key_hash, 20);
+        assert_eq!(state.operations.len(), 1);
+        let operation = &state.operations[0];
+        assert_eq!(operation.operation_index, 1);
+        assert_eq!(operation.entity_index, 1);
+        assert_eq!(operation.accumulators.len(), 1);
+        assert_eq!(operation.accumulators[0].inst_index, 4);
+        assert_eq!(
+            operation.accumulators[0].value,
+            AccumulatorValue::Value("Some(7)".to_owned())
+        );
+
+        let state = entity_state(dir.path(), "b", None).unwrap();
+        assert_eq!(
+            state.operations[0].accumulators[0].value,
+            AccumulatorValue::Undecoded { total_bytes: 18 }
+        );
+
+        assert!(entity_state(dir.path(), "c", None).is_err());
+    }
+
+    #[test]
+    fn test_entity_state_with_none_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        write_snapshot(dir.path());
+
+        // The `None` for the first entity is serialized as a single byte, so
+        // the entries are not fixed-width.
+        let state = entity_state(dir.path(), "a", Some(AccumulatorType::Int64)).unwrap();
+        assert_eq!(state.operations[0].entity_index, 0);
+        assert_eq!(
+            state.operations[0].accumulators[0].value,
+            AccumulatorValue::Value("None".to_owned())
+        );
+
+        let state = entity_state(dir.path(), "a", Some(AccumulatorType::String)).unwrap();
+        assert!(matches!(
+            state.operations[0].accumulators[0].value,
+            AccumulatorValue::Invalid(_)
+        ));
+    }
+
+    #[test]
+    fn test_is_older_than() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        let snapshot = |last_modified: Option<u64>| ListedSnapshot {
+            path: "snapshot".to_owned(),
+            last_modified: last_modified
+                .map(|seconds| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)),
+        };
+
+        let age = Duration::from_secs(100);
+        assert!(snapshot(Some(800)).is_older_than(now, age));
+        assert!(!snapshot(Some(950)).is_older_than(now, age));
+        assert!(!snapshot(Some(2000)).is_older_than(now, age));
+        assert!(!snapshot(None).is_older_than(now, age));
+    }
+
+    #[tokio::test]
+    async fn test_list_and_delete_local_snapshots() {
+        let prefix = tempfile::tempdir().unwrap();
+        let snapshot = prefix.path().join("snapshot");
+        write_snapshot(&snapshot);
+        std::fs::create_dir(prefix.path().join("not_a_snapshot")).unwrap();
+
+        let s3_helper = S3Helper::new().await;
+        let output_prefix = prefix.path().to_string_lossy();
+        let snapshots = list_snapshots(&s3_helper, &output_prefix).await.unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].path, snapshot.to_string_lossy());
+        assert!(snapshots[0].last_modified.is_some());
+
+        delete_snapshot(&s3_helper, &snapshots[0].path)
+            .await
+            .unwrap();
+        assert!(list_snapshots(&s3_helper, &output_prefix)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
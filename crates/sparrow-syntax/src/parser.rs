@@ -15,9 +15,11 @@ lalrpop_mod!(
     "/parser/grammar.rs" // generated by LALRPOP
 );
 
+mod format;
 mod lexer;
 mod token;
 
+pub use format::format_expr;
 pub use token::Token;
 
 #[cfg(test)]
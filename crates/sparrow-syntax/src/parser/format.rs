@@ -0,0 +1,557 @@
+//! Pretty-printing of Fenl expressions.
+//!
+//! The formatter works from the parsed expression, re-using the original
+//! source for literals, operators and types so they are written as the user
+//! wrote them. Comments aren't part of the AST, so they are collected from the
+//! gaps between tokens and written at the nearest line break.
+
+use std::ops::Range;
+
+use crate::parser::lexer::Lexer;
+use crate::parser::try_parse_expr;
+use crate::{Argument, Expr, ExprOp, ExprRef, FeatureSetPart, Located, ParseErrors};
+
+/// The width expressions are formatted to fit within.
+const MAX_WIDTH: usize = 100;
+
+/// The string used for each level of indentation.
+const INDENT: &str = "  ";
+
+/// Format the Fenl expression in `input`.
+///
+/// Expressions which fit on the line and contain no comments are written on
+/// a single line. Otherwise records and argument lists are written with one
+/// entry per line, pipes with one stage per line, and binary operators with
+/// the operator starting a new line. Each `let` binding is always written on
+/// its own line, preserving blank lines between bindings and record fields.
+///
+/// Comments are preserved. Comments following code on a line remain at the
+/// end of that line, while other comments are written on their own line.
+pub fn format_expr(input: &str) -> Result<String, ParseErrors<'_>> {
+    let expr = try_parse_expr(FeatureSetPart::Query, input)?;
+    let (comments, span) = scan_comments(input);
+
+    let mut formatter = Formatter {
+        source: input,
+        comments,
+        next_comment: 0,
+        output: String::new(),
+        indent: 0,
+    };
+    while let Some(comment) = formatter.take_comment(span.start) {
+        formatter.output.push_str(comment);
+        formatter.newline();
+    }
+    formatter.expr(&expr, span, 0);
+    formatter.finish();
+    Ok(formatter.output)
+}
+
+/// A comment in the source.
+#[derive(Debug)]
+struct Comment<'a> {
+    /// The byte offset of the `#` starting the comment.
+    offset: usize,
+    /// The text of the comment, including the `#`.
+    text: &'a str,
+    /// Whether the comment follows code on the same line.
+    trailing: bool,
+}
+
+/// Return the comments within `input`, and the range of bytes between the
+/// start of the first token and the end of the last token.
+fn scan_comments(input: &str) -> (Vec<Comment<'_>>, Range<usize>) {
+    let mut comments = Vec::new();
+    let mut first_token = None;
+    let mut gap_start = 0;
+    for (start, _, end) in Lexer::new(input).flatten() {
+        collect_comments(
+            input,
+            gap_start..start,
+            first_token.is_some(),
+            &mut comments,
+        );
+        first_token.get_or_insert(start);
+        gap_start = end;
+    }
+    collect_comments(
+        input,
+        gap_start..input.len(),
+        first_token.is_some(),
+        &mut comments,
+    );
+    (comments, first_token.unwrap_or(0)..gap_start)
+}
+
+/// Collect the comments in the gap between two tokens.
+///
+/// The lexer skips whitespace and comments, so the gap contains only those.
+/// Since comments extend to the end of the line, the first `#` on each line
+/// of the gap starts a comment.
+fn collect_comments<'a>(
+    input: &'a str,
+    gap: Range<usize>,
+    after_token: bool,
+    comments: &mut Vec<Comment<'a>>,
+) {
+    let mut offset = gap.start;
+    for (index, line) in input[gap].split('\n').enumerate() {
+        if let Some(hash) = line.find('#') {
+            comments.push(Comment {
+                offset: offset + hash,
+                text: line[hash..].trim_end(),
+                trailing: after_token && index == 0,
+            });
+        }
+        offset += line.len() + 1;
+    }
+}
+
+/// How a call expression is written in the source.
+enum CallSyntax<'a> {
+    /// A function call, such as `sum(x)`.
+    Function,
+    /// An index, such as `x[0]`.
+    Index,
+    /// A prefix operator, such as `-x`.
+    Prefix(&'a str),
+    /// A binary operator with the given precedence, such as `x + y`.
+    Binary(&'a str, u8),
+}
+
+/// Precedence of expressions which may be cast, such as `x as i64`.
+const CAST_PRECEDENCE: u8 = 0;
+/// Precedence of pipe and `let` expressions.
+const PIPE_PRECEDENCE: u8 = 1;
+/// Precedence of prefix operators.
+const PREFIX_PRECEDENCE: u8 = 8;
+/// Precedence of postfix expressions -- field refs, indexing and calls.
+const POSTFIX_PRECEDENCE: u8 = 9;
+/// Precedence of primary expressions, which never need parentheses.
+const PRIMARY_PRECEDENCE: u8 = 10;
+
+struct Formatter<'a> {
+    source: &'a str,
+    comments: Vec<Comment<'a>>,
+    /// The index of the first comment which hasn't been written.
+    next_comment: usize,
+    output: String,
+    indent: usize,
+}
+
+impl<'a> Formatter<'a> {
+    fn slice(&self, location: &crate::Location) -> &'a str {
+        &self.source[location.start()..location.end()]
+    }
+
+    fn call_syntax(&self, name: &Located<String>) -> CallSyntax<'a> {
+        let text = self.slice(name.location());
+        if text == name.inner() {
+            return CallSyntax::Function;
+        }
+
+        match name.inner().as_str() {
+            "index" => CallSyntax::Index,
+            "neg" | "not" => CallSyntax::Prefix(text),
+            "logical_or" => CallSyntax::Binary(text, 2),
+            "logical_and" => CallSyntax::Binary(text, 3),
+            "eq" | "neq" => CallSyntax::Binary(text, 4),
+            "lt" | "gt" | "lte" | "gte" => CallSyntax::Binary(text, 5),
+            "add" | "sub" => CallSyntax::Binary(text, 6),
+            "mul" | "div" => CallSyntax::Binary(text, 7),
+            _ => CallSyntax::Function,
+        }
+    }
+
+    fn precedence(&self, expr: &Expr) -> u8 {
+        match expr.op() {
+            ExprOp::Cast(_, _) => CAST_PRECEDENCE,
+            ExprOp::Pipe(_) | ExprOp::Let(_, _) => PIPE_PRECEDENCE,
+            ExprOp::Call(name) => match self.call_syntax(name) {
+                CallSyntax::Binary(_, precedence) => precedence,
+                CallSyntax::Prefix(_) => PREFIX_PRECEDENCE,
+                CallSyntax::Function | CallSyntax::Index => POSTFIX_PRECEDENCE,
+            },
+            ExprOp::FieldRef(_, _)
+            | ExprOp::ExtendRecord(_)
+            | ExprOp::RemoveFields(_)
+            | ExprOp::SelectFields(_) => POSTFIX_PRECEDENCE,
+            ExprOp::Literal(_) | ExprOp::Reference(_) | ExprOp::Record(_, _) | ExprOp::Error => {
+                PRIMARY_PRECEDENCE
+            }
+        }
+    }
+
+    /// Return the expression formatted on a single line.
+    ///
+    /// Returns `None` if the expression can't be written on a single line.
+    /// This doesn't check for comments within the expression.
+    fn flat(&self, expr: &Expr, min_precedence: u8) -> Option<String> {
+        let arg = |index: usize, min_precedence: u8| {
+            let value = expr.arg(index)?;
+            self.flat(value.inner(), min_precedence)
+        };
+
+        let text = match expr.op() {
+            ExprOp::Literal(literal) => self.slice(literal.location()).to_owned(),
+            ExprOp::Reference(name) => name.inner().clone(),
+            ExprOp::FieldRef(field, _) => format!("{}.{field}", arg(0, POSTFIX_PRECEDENCE)?),
+            ExprOp::Call(name) => match self.call_syntax(name) {
+                CallSyntax::Function => format!("{name}({})", self.flat_args(expr)?),
+                CallSyntax::Index => format!("{}[{}]", arg(0, POSTFIX_PRECEDENCE)?, arg(1, 0)?),
+                CallSyntax::Prefix(op) => format!("{op}{}", arg(0, PREFIX_PRECEDENCE)?),
+                CallSyntax::Binary(op, precedence) => {
+                    format!("{} {op} {}", arg(0, precedence)?, arg(1, precedence + 1)?)
+                }
+            },
+            ExprOp::ExtendRecord(_) => format!("extend({})", self.flat_args(expr)?),
+            ExprOp::RemoveFields(_) => format!("remove_fields({})", self.flat_args(expr)?),
+            ExprOp::SelectFields(_) => format!("select_fields({})", self.flat_args(expr)?),
+            ExprOp::Pipe(_) => format!(
+                "{} | {}",
+                arg(0, PIPE_PRECEDENCE + 1)?,
+                arg(1, PIPE_PRECEDENCE)?
+            ),
+            ExprOp::Record(names, _) if names.is_empty() => "{}".to_owned(),
+            ExprOp::Record(names, _) => {
+                let mut fields = Vec::with_capacity(names.len());
+                for (name, arg) in names.iter().zip(expr.args().iter()) {
+                    let value = arg.value();
+                    if is_shorthand(name, value) {
+                        fields.push(name.inner().clone());
+                    } else {
+                        fields.push(format!("{name}: {}", self.flat(value.inner(), 0)?));
+                    }
+                }
+                format!("{{ {} }}", fields.join(", "))
+            }
+            ExprOp::Cast(fenl_type, _) => format!(
+                "{} as {}",
+                arg(0, PIPE_PRECEDENCE)?,
+                self.slice(fenl_type.location())
+            ),
+            // `let` bindings are always written one per line.
+            ExprOp::Let(_, _) => return None,
+            ExprOp::Error => return None,
+        };
+
+        if self.precedence(expr) < min_precedence {
+            Some(format!("({text})"))
+        } else {
+            Some(text)
+        }
+    }
+
+    fn flat_args(&self, expr: &Expr) -> Option<String> {
+        let mut args = Vec::with_capacity(expr.args().len());
+        for arg in expr.args().iter() {
+            let value = self.flat(arg.value().inner(), 0)?;
+            match arg {
+                Argument::Positional(_) => args.push(value),
+                Argument::Keyword(name, _) => args.push(format!("{name} = {value}")),
+            }
+        }
+        Some(args.join(", "))
+    }
+
+    /// Write the expression spanning the given range of the source.
+    ///
+    /// The expression is written on a single line if possible, and broken
+    /// across lines otherwise.
+    fn expr(&mut self, expr: &Expr, span: Range<usize>, min_precedence: u8) {
+        if !self.has_comments(&span) {
+            if let Some(flat) = self.flat(expr, min_precedence) {
+                if self.column() + flat.chars().count() <= MAX_WIDTH {
+                    self.output.push_str(&flat);
+                    return;
+                }
+            }
+        }
+
+        let parenthesize = self.precedence(expr) < min_precedence;
+        if parenthesize {
+            self.output.push('(');
+        }
+        self.broken(expr, span);
+        if parenthesize {
+            self.output.push(')');
+        }
+    }
+
+    fn located(&mut self, value: &Located<ExprRef>, min_precedence: u8) {
+        let location = value.location();
+        self.expr(
+            value.inner(),
+            location.start()..location.end(),
+            min_precedence,
+        )
+    }
+
+    fn arg(&mut self, expr: &Expr, index: usize, min_precedence: u8) {
+        let value = expr.arg(index).expect("missing argument");
+        self.located(value, min_precedence)
+    }
+
+    /// Write the expression broken across lines.
+    fn broken(&mut self, expr: &Expr, span: Range<usize>) {
+        match expr.op() {
+            ExprOp::Literal(literal) => self.output.push_str(self.slice(literal.location())),
+            ExprOp::Reference(name) => self.output.push_str(name.inner()),
+            ExprOp::FieldRef(field, _) => {
+                self.arg(expr, 0, POSTFIX_PRECEDENCE);
+                self.output.push('.');
+                self.output.push_str(field.inner());
+            }
+            ExprOp::Call(name) => match self.call_syntax(name) {
+                CallSyntax::Function => self.call(name.inner(), expr, span.end),
+                CallSyntax::Index => {
+                    self.arg(expr, 0, POSTFIX_PRECEDENCE);
+                    self.output.push('[');
+                    self.arg(expr, 1, 0);
+                    self.output.push(']');
+                }
+                CallSyntax::Prefix(op) => {
+                    self.output.push_str(op);
+                    self.arg(expr, 0, PREFIX_PRECEDENCE);
+                }
+                CallSyntax::Binary(op, precedence) => {
+                    self.arg(expr, 0, precedence);
+                    self.indent += 1;
+                    self.line_break(name.location().start(), false);
+                    self.output.push_str(op);
+                    self.output.push(' ');
+                    self.arg(expr, 1, precedence + 1);
+                    self.indent -= 1;
+                }
+            },
+            ExprOp::ExtendRecord(_) => self.call("extend", expr, span.end),
+            ExprOp::RemoveFields(_) => self.call("remove_fields", expr, span.end),
+            ExprOp::SelectFields(_) => self.call("select_fields", expr, span.end),
+            ExprOp::Pipe(_) => {
+                // Pipes are right-associative, so `a | b | c` is `a | (b | c)`.
+                // Write each stage of the chain on its own line.
+                self.arg(expr, 0, PIPE_PRECEDENCE + 1);
+                let mut stage = expr;
+                while let ExprOp::Pipe(op) = stage.op() {
+                    let rhs = stage.arg(1).expect("pipe rhs");
+                    self.line_break(op.start(), false);
+                    self.output.push_str("| ");
+                    match rhs.op() {
+                        ExprOp::Pipe(_) => self.arg(rhs, 0, PIPE_PRECEDENCE + 1),
+                        _ => self.located(rhs, PIPE_PRECEDENCE),
+                    }
+                    stage = &**rhs.inner();
+                }
+            }
+            ExprOp::Let(names, _) => {
+                // The last name and argument correspond to the body.
+                let bindings = names.len() - 1;
+                let mut previous_end = None;
+                for (index, name) in names.iter().take(bindings).enumerate() {
+                    let start = name.location().start();
+                    if let Some(previous_end) = previous_end {
+                        let blank_line = self.has_blank_line(previous_end..start);
+                        self.line_break(start, blank_line);
+                    }
+                    self.output.push_str("let ");
+                    self.output.push_str(name.inner());
+                    self.output.push_str(" =");
+
+                    let value = expr.arg(index).expect("let binding");
+                    self.binding_value(value);
+                    previous_end = Some(value.location().end());
+                }
+
+                let body = expr.arg(bindings).expect("let body");
+                let start = body.location().start();
+                let blank_line = previous_end.map_or(false, |end| self.has_blank_line(end..start));
+                self.line_break(start, blank_line);
+                self.output.push_str("in ");
+                self.located(body, PIPE_PRECEDENCE);
+            }
+            ExprOp::Record(names, _) => {
+                self.output.push('{');
+                self.indent += 1;
+                let mut previous_end = None;
+                for (name, arg) in names.iter().zip(expr.args().iter()) {
+                    let start = name.location().start();
+                    let blank_line =
+                        previous_end.map_or(false, |end| self.has_blank_line(end..start));
+                    self.line_break(start, blank_line);
+
+                    let value = arg.value();
+                    self.output.push_str(name.inner());
+                    if !is_shorthand(name, value) {
+                        self.output.push_str(": ");
+                        self.located(value, 0);
+                    }
+                    self.output.push(',');
+                    previous_end = Some(value.location().end());
+                }
+                self.close(span.end - 1);
+                self.output.push('}');
+            }
+            ExprOp::Cast(fenl_type, _) => {
+                self.arg(expr, 0, PIPE_PRECEDENCE);
+                self.output.push_str(" as ");
+                self.output.push_str(self.slice(fenl_type.location()));
+            }
+            ExprOp::Error => unreachable!("formatting expression with errors"),
+        }
+    }
+
+    /// Write a call with one argument per line.
+    fn call(&mut self, name: &str, expr: &Expr, end: usize) {
+        self.output.push_str(name);
+        self.output.push('(');
+        self.indent += 1;
+        for arg in expr.args().iter() {
+            match arg {
+                Argument::Positional(value) => {
+                    self.line_break(value.location().start(), false);
+                }
+                Argument::Keyword(name, _) => {
+                    self.line_break(name.location().start(), false);
+                    self.output.push_str(name.inner());
+                    self.output.push_str(" = ");
+                }
+            }
+            self.located(arg.value(), 0);
+            self.output.push(',');
+        }
+        self.close(end - 1);
+        self.output.push(')');
+    }
+
+    /// Write the value of a `let` binding, following the `=`.
+    ///
+    /// If the value doesn't fit on the line but would fit on its own line, it
+    /// is written on the next line.
+    fn binding_value(&mut self, value: &Located<ExprRef>) {
+        self.indent += 1;
+        let location = value.location();
+        let span = location.start()..location.end();
+        if !self.has_comments(&span) {
+            if let Some(flat) = self.flat(value.inner(), 0) {
+                let width = flat.chars().count();
+                if self.column() + 1 + width > MAX_WIDTH
+                    && self.indent * INDENT.len() + width <= MAX_WIDTH
+                {
+                    self.newline();
+                    self.output.push_str(&flat);
+                    self.indent -= 1;
+                    return;
+                }
+            }
+        }
+        self.output.push(' ');
+        self.located(value, 0);
+        self.indent -= 1;
+    }
+
+    fn column(&self) -> usize {
+        let line_start = self.output.rfind('\n').map_or(0, |index| index + 1);
+        self.output[line_start..].chars().count()
+    }
+
+    fn line_is_blank(&self) -> bool {
+        let line_start = self.output.rfind('\n').map_or(0, |index| index + 1);
+        self.output[line_start..].trim().is_empty()
+    }
+
+    fn has_blank_line(&self, range: Range<usize>) -> bool {
+        let lines: Vec<_> = self.source[range].split('\n').collect();
+        lines.len() > 2
+            && lines[1..lines.len() - 1]
+                .iter()
+                .any(|line| line.trim().is_empty())
+    }
+
+    /// Return true if there are unwritten comments within the range.
+    fn has_comments(&self, range: &Range<usize>) -> bool {
+        self.comments[self.next_comment..]
+            .iter()
+            .any(|comment| range.contains(&comment.offset))
+    }
+
+    /// Take the next unwritten comment if it precedes `offset`.
+    fn take_comment(&mut self, offset: usize) -> Option<&'a str> {
+        let comment = self.comments.get(self.next_comment)?;
+        if comment.offset < offset {
+            self.next_comment += 1;
+            Some(comment.text)
+        } else {
+            None
+        }
+    }
+
+    /// Write the next comment at the end of the current line, if it preceded
+    /// `offset` and followed code on the same line in the source.
+    fn trailing_comment(&mut self, offset: usize) {
+        let trailing = self
+            .comments
+            .get(self.next_comment)
+            .map_or(false, |comment| comment.trailing && comment.offset < offset);
+        if trailing && !self.line_is_blank() {
+            let comment = self.take_comment(offset).expect("trailing comment");
+            self.output.push(' ');
+            self.output.push_str(comment);
+        }
+    }
+
+    fn newline(&mut self) {
+        let len = self.output.trim_end_matches(' ').len();
+        self.output.truncate(len);
+        self.output.push('\n');
+        for _ in 0..self.indent {
+            self.output.push_str(INDENT);
+        }
+    }
+
+    /// Start a new line before the element starting at `next`.
+    ///
+    /// Comments preceding the element are written first.
+    fn line_break(&mut self, next: usize, blank_line: bool) {
+        self.trailing_comment(next);
+        if blank_line {
+            self.newline();
+        }
+        self.newline();
+        while let Some(comment) = self.take_comment(next) {
+            self.output.push_str(comment);
+            self.newline();
+        }
+    }
+
+    /// Close a level of indentation before the delimiter at `end`.
+    ///
+    /// Comments preceding the delimiter are written at the inner indentation.
+    fn close(&mut self, end: usize) {
+        self.trailing_comment(end);
+        while let Some(comment) = self.take_comment(end) {
+            self.newline();
+            self.output.push_str(comment);
+        }
+        self.indent -= 1;
+        self.newline();
+    }
+
+    /// Write any remaining comments and the final newline.
+    fn finish(&mut self) {
+        self.trailing_comment(usize::MAX);
+        while let Some(comment) = self.take_comment(usize::MAX) {
+            self.newline();
+            self.output.push_str(comment);
+        }
+        self.newline();
+    }
+}
+
+/// Return true if the record field was written as `{ name }`.
+fn is_shorthand(name: &Located<String>, value: &Located<ExprRef>) -> bool {
+    matches!(value.op(), ExprOp::Reference(reference) if reference == name)
+        && value.location() == name.location()
+}
+
+#[cfg(test)]
+mod tests;
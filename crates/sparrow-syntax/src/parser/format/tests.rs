@@ -0,0 +1,173 @@
+use itertools::Itertools;
+
+use super::*;
+
+/// Return a representation of the expression which ignores locations.
+fn sexpr(expr: &Expr) -> String {
+    let op = match expr.op() {
+        ExprOp::Literal(literal) => format!("{:?}", literal.inner()),
+        ExprOp::Reference(name) => name.inner().clone(),
+        ExprOp::FieldRef(field, _) => format!(".{field}"),
+        ExprOp::Call(name) => name.inner().clone(),
+        ExprOp::Pipe(_) => "pipe".to_owned(),
+        ExprOp::Let(names, _) => format!("let {}", names.iter().format(" ")),
+        ExprOp::Record(names, _) => format!("record {}", names.iter().format(" ")),
+        ExprOp::ExtendRecord(_) => "extend".to_owned(),
+        ExprOp::RemoveFields(_) => "remove_fields".to_owned(),
+        ExprOp::SelectFields(_) => "select_fields".to_owned(),
+        ExprOp::Cast(fenl_type, _) => format!("as {:?}", fenl_type.inner()),
+        ExprOp::Error => "error".to_owned(),
+    };
+    let args = expr.args().iter().map(|arg| match arg {
+        Argument::Positional(value) => sexpr(value.inner()),
+        Argument::Keyword(name, value) => format!("{name}={}", sexpr(value.inner())),
+    });
+    format!("({op} {})", args.format(" "))
+}
+
+/// Format the input, and check that the result parses to the same
+/// expression and is unchanged by formatting again.
+fn format_round_trip(input: &'static str) -> String {
+    let formatted = format_expr(input).unwrap();
+
+    let original = Expr::try_from_str(FeatureSetPart::Query, input).unwrap();
+    let reparsed = Expr::try_from_str(FeatureSetPart::Query, &formatted).unwrap();
+    assert_eq!(
+        sexpr(&original),
+        sexpr(&reparsed),
+        "formatting changed the expression:\n{formatted}"
+    );
+    assert_eq!(
+        format_expr(&formatted).unwrap(),
+        formatted,
+        "formatting is not idempotent"
+    );
+    formatted
+}
+
+#[test]
+fn test_round_trip_parser_tests() {
+    // The expressions used in the parser tests.
+    let inputs = [
+        "1",
+        "1.0",
+        "-0.1",
+        "-5",
+        "-a",
+        "!a",
+        "!a or b",
+        "(!a) or b",
+        "-5 * 3",
+        "(-5) * 3",
+        "5 * -3",
+        "5 * (-3)",
+        "-5 + 3",
+        "5 + -3",
+        "1 + 2 * 3",
+        "1 + 2 | 3 * 4",
+        "1 | 2 | 3",
+        "a and b",
+        "a or b",
+        "a as i32",
+        "a or b as i32",
+        "a | b as i32",
+        "a.foo",
+        "a[0]",
+        "a[0 + 1]",
+        "a[0].foo[1]",
+        "a[0][1 + 1]",
+        "foo.x + bar.y",
+        "let a = 5 in a + 1",
+        "{ a: 5, b: 6, c: d, e, f: 8}",
+    ];
+
+    for input in inputs {
+        format_round_trip(input);
+    }
+}
+
+#[test]
+fn test_format_parentheses() {
+    assert_eq!(format_round_trip("(a + b) * c"), "(a + b) * c\n");
+    assert_eq!(format_round_trip("a-b-c"), "a - b - c\n");
+    assert_eq!(format_round_trip("a - (b - c)"), "a - (b - c)\n");
+    assert_eq!(format_round_trip("(a | b) | c"), "(a | b) | c\n");
+    assert_eq!(format_round_trip("a | (b | c)"), "a | b | c\n");
+    assert_eq!(format_round_trip("-(a + b)"), "-(a + b)\n");
+    assert_eq!(format_round_trip("(a as i32).x"), "(a as i32).x\n");
+    assert_eq!(format_round_trip("((a <> b))"), "a <> b\n");
+}
+
+#[test]
+fn test_format_records_and_calls() {
+    assert_eq!(format_round_trip("{x:x, y,}"), "{ x: x, y }\n");
+    assert_eq!(format_round_trip("{ }"), "{}\n");
+    assert_eq!(format_round_trip("foo( a,b=1, )"), "foo(a, b = 1)\n");
+    assert_eq!(
+        format_round_trip("Foo | extend({ n: 1 }) | remove_fields($input, 'x')"),
+        "Foo | extend({ n: 1 }) | remove_fields($input, 'x')\n"
+    );
+
+    insta::assert_snapshot!(format_round_trip(
+        "lookup(Purchases.customer_id_with_a_very_long_name, Customers.average_review_by_product_over_last_year | last())"
+    ), @r###"
+    lookup(
+      Purchases.customer_id_with_a_very_long_name,
+      Customers.average_review_by_product_over_last_year | last(),
+    )
+    "###);
+}
+
+#[test]
+fn test_format_let() {
+    insta::assert_snapshot!(format_round_trip(
+        "# Will always be non-`null` after the first non-`null` `Input.n`.
+let prev_value = Input.n | lag(1)
+
+# Will be `null` if current `Input.n` is `null`.
+let difference = Input.n - prev_value
+in
+{
+  difference,
+  mean_difference: mean(difference),
+} | extend({ time: time_of($input), key: first(Input.key) })"
+    ), @r###"
+    # Will always be non-`null` after the first non-`null` `Input.n`.
+    let prev_value = Input.n | lag(1)
+
+    # Will be `null` if current `Input.n` is `null`.
+    let difference = Input.n - prev_value
+    in { difference, mean_difference: mean(difference) }
+    | extend({ time: time_of($input), key: first(Input.key) })
+    "###);
+}
+
+#[test]
+fn test_format_preserves_comments() {
+    insta::assert_snapshot!(format_round_trip(
+        "{ key: Purchases.customer_id, # the customer
+  total: sum(Purchases.amount, window = since(daily())) }"
+    ), @r###"
+    {
+      key: Purchases.customer_id, # the customer
+      total: sum(Purchases.amount, window = since(daily())),
+    }
+    "###);
+
+    insta::assert_snapshot!(format_round_trip(
+        "Purchases
+    # only large purchases
+      | when(Purchases.amount > 10)   # filter
+  | count() # total"
+    ), @r###"
+    Purchases
+    # only large purchases
+    | when(Purchases.amount > 10) # filter
+    | count() # total
+    "###);
+}
+
+#[test]
+fn test_format_invalid() {
+    assert!(format_expr("a +").is_err());
+}
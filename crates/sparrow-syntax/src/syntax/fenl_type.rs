@@ -129,6 +129,7 @@ impl Display for TypeConstraint {
 pub enum WindowBehavior {
     Since,
     Sliding,
+    Session,
 }
 
 impl WindowBehavior {
@@ -136,6 +137,7 @@ impl WindowBehavior {
         match self {
             Self::Since => "since",
             Self::Sliding => "sliding",
+            Self::Session => "session",
         }
     }
 }